@@ -1,6 +1,7 @@
 use cosmwasm_std::{
-    attr, entry_point, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env,
-    MessageInfo, Order, Reply, ReplyOn, Response, StdError, StdResult, SubMsg, WasmMsg,
+    attr, entry_point, from_binary, to_binary, Addr, Api, Binary, CosmosMsg, Decimal, Deps,
+    DepsMut, Env, MessageInfo, Order, Reply, ReplyOn, Response, StdError, StdResult, SubMsg,
+    SubMsgResult, WasmMsg,
 };
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::stake::UnbondingPeriod;
@@ -9,25 +10,31 @@ use crate::error::ContractError;
 use crate::querier::query_pair_info;
 
 use crate::state::{
-    check_asset_infos, pair_key, read_pairs, Config, TmpPairInfo, CONFIG, OWNERSHIP_PROPOSAL,
-    PAIRS, PAIRS_TO_MIGRATE, PAIR_CONFIGS, STAKING_ADDRESSES, TMP_PAIR_INFO,
+    check_asset_infos, pair_key, read_pairs, read_pairs_by_asset, Config, TmpPairInfo,
+    BLOCKED_ASSETS, CONFIG, MIGRATION_CODE_IDS, OWNERSHIP_PROPOSAL, PAIRS, PAIRS_BY_ASSET,
+    PAIRS_TO_MIGRATE, PAIR_CONFIGS, STAKING_ADDRESSES, TMP_PAIR_INFOS,
 };
 
 use cw2::set_contract_version;
+use cw_utils::ensure_from_older_version;
 use itertools::Itertools;
 use sg_swap::asset::{addr_opt_validate, AssetInfo};
 use sg_swap::common::{
     claim_ownership, drop_ownership_proposal, propose_new_owner, validate_addresses,
 };
 use sg_swap::factory::{
-    ConfigResponse, DistributionFlow, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg,
-    PairConfig, PairType, PairsResponse, PartialStakeConfig, QueryMsg, ROUTE,
+    ConfigResponse, CreatePairParams, DistributionFlow, ExecuteMsg, FeeInfoResponse,
+    InstantiateMsg, MigrateMsg, PairConfig, PairType, PairsResponse, QueryMsg, MAX_PAIRS_PER_BATCH,
+    MAX_PAIR_MIGRATIONS, MAX_REWARD_WITHDRAWALS, ROUTE,
 };
 use sg_swap_stake::msg::ExecuteMsg as StakeExecuteMsg;
 use std::collections::HashSet;
 
 use cw_placeholder::contract::CONTRACT_NAME as PLACEHOLDER_CONTRACT_NAME;
-use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg, PairInfo};
+use sg_swap::pair::{
+    ExecuteMsg as PairExecuteMsg, InstantiateMsg as PairInstantiateMsg, PairInfo, StablePoolParams,
+    WeightedPoolParams, MAX_AMP,
+};
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "sg-swap-factory";
 /// Contract version that is used for migration.
@@ -36,9 +43,19 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 const INSTANTIATE_PAIR_REPLY_ID: u64 = 1;
 
 const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
-/// The maximum amount of seconds that the trading can be delayed when the contract is instantiated.
+/// The maximum amount of seconds that the trading can be delayed when the contract is instantiated
+/// or a pair is created.
 const MAX_TRADING_STARTS_DELAY: u64 = 60 * SECONDS_PER_DAY;
 
+/// Checks that trading starts neither in the past nor more than [`MAX_TRADING_STARTS_DELAY`] from now.
+fn validate_trading_starts(env: &Env, trading_starts: u64) -> Result<(), ContractError> {
+    let block_time = env.block.time.seconds();
+    if trading_starts < block_time || trading_starts > block_time + MAX_TRADING_STARTS_DELAY {
+        return Err(ContractError::InvalidTradingStart {});
+    }
+    Ok(())
+}
+
 /// Creates a new contract with the specified parameters packed in the `msg` variable.
 ///
 /// * **msg**  is message which contains the parameters used for creating the contract.
@@ -58,10 +75,7 @@ pub fn instantiate(
     }
 
     if let Some(trading_starts) = msg.trading_starts {
-        let block_time = env.block.time.seconds();
-        if trading_starts < block_time || trading_starts > block_time + MAX_TRADING_STARTS_DELAY {
-            return Err(ContractError::InvalidTradingStart {});
-        }
+        validate_trading_starts(&env, trading_starts)?;
     }
 
     let config = Config {
@@ -72,6 +86,7 @@ pub fn instantiate(
         default_stake_config: msg.default_stake_config,
         only_owner_can_create_pairs: true,
         trading_starts: msg.trading_starts,
+        fee_recipients: validate_fee_recipients(deps.api, msg.fee_recipients.unwrap_or_default())?,
     };
 
     let config_set: HashSet<String> = msg
@@ -89,6 +104,9 @@ pub fn instantiate(
         if !pc.fee_config.valid_fee_bps() {
             return Err(ContractError::PairConfigInvalidFeeBps {});
         }
+        if !pc.pair_type.valid_weights() {
+            return Err(ContractError::InvalidWeights {});
+        }
         PAIR_CONFIGS.save(deps.storage, pc.pair_type.to_string(), pc)?;
     }
     CONFIG.save(deps.storage, &config)?;
@@ -104,6 +122,37 @@ pub struct UpdateConfig {
     fee_address: Option<String>,
     /// Whether only the owner or anyone can create new pairs
     only_owner_can_create_pairs: Option<bool>,
+    /// Addresses to split the protocol fees between
+    fee_recipients: Option<Vec<(String, Decimal)>>,
+}
+
+/// Validates the addresses of the protocol fee recipients
+/// and checks that their weights are non-zero and sum up to one.
+/// An empty list is valid and means that all fees go to the fee address.
+fn validate_fee_recipients(
+    api: &dyn Api,
+    recipients: Vec<(String, Decimal)>,
+) -> Result<Vec<(Addr, Decimal)>, ContractError> {
+    if recipients.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut total = Decimal::zero();
+    let recipients = recipients
+        .into_iter()
+        .map(|(addr, weight)| {
+            if weight.is_zero() {
+                return Err(ContractError::InvalidFeeRecipients {});
+            }
+            total += weight;
+            Ok((api.addr_validate(&addr)?, weight))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if total != Decimal::one() {
+        return Err(ContractError::InvalidFeeRecipients {});
+    }
+
+    Ok(recipients)
 }
 
 /// Exposes all the execute functions available in the contract.
@@ -113,6 +162,8 @@ pub struct UpdateConfig {
 /// * **ExecuteMsg::UpdateConfig {
 ///             token_code_id,
 ///             fee_address,
+///             only_owner_can_create_pairs,
+///             fee_recipients,
 ///         }** Updates general contract parameters.
 ///
 /// * **ExecuteMsg::UpdatePairConfig { config }** Updates a pair type
@@ -124,9 +175,19 @@ pub struct UpdateConfig {
 ///             init_params,
 ///         }** Creates a new pair with the specified input parameters.
 ///
+/// * **ExecuteMsg::CreatePairs { pairs }** Creates multiple new pairs at once.
+///
+/// * **ExecuteMsg::PausePair { asset_infos, paused }** Pauses or unpauses an existing pair.
+///
 /// * **ExecuteMsg::Deregister { asset_infos }** Removes an existing pair from the factory.
 /// * The asset information is for the assets that are traded in the pair.
 ///
+/// * **ExecuteMsg::BlockAsset { asset, until }** Prevents new pairs from being created for an asset.
+///
+/// * **ExecuteMsg::UnblockAsset { asset }** Allows creating pairs for a blocked asset again.
+///
+/// * **ExecuteMsg::WithdrawAllRewards { pairs, receiver }** Withdraws the sender's rewards from multiple pairs at once.
+///
 /// * **ExecuteMsg::ProposeNewOwner { owner, expires_in }** Creates a request to change contract ownership.
 ///
 /// * **ExecuteMsg::DropOwnershipProposal {}** Removes a request to change contract ownership.
@@ -134,6 +195,10 @@ pub struct UpdateConfig {
 /// * **ExecuteMsg::ClaimOwnership {}** Claims contract ownership.
 ///
 /// * **ExecuteMsg::MarkAsMigrated {}** Mark pairs as migrated.
+///
+/// * **ExecuteMsg::AllowMigrationCodeId { pair_type, code_id, allowed }** Allows migrating pairs of a type to a code id.
+///
+/// * **ExecuteMsg::MigratePairs { pairs, new_code_id, msg }** Migrates multiple pairs to a new code id.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -146,6 +211,7 @@ pub fn execute(
             token_code_id,
             fee_address,
             only_owner_can_create_pairs,
+            fee_recipients,
         } => execute_update_config(
             deps,
             info,
@@ -153,12 +219,17 @@ pub fn execute(
                 token_code_id,
                 fee_address,
                 only_owner_can_create_pairs,
+                fee_recipients,
             },
         ),
         ExecuteMsg::UpdatePairFees {
             asset_infos,
             fee_config,
         } => execute_update_pair_fees(deps, info, asset_infos, fee_config),
+        ExecuteMsg::PausePair {
+            asset_infos,
+            paused,
+        } => execute_pause_pair(deps, info, asset_infos, paused),
         ExecuteMsg::UpdatePairConfig { config } => execute_update_pair_config(deps, info, config),
         ExecuteMsg::CreatePair {
             pair_type,
@@ -166,18 +237,36 @@ pub fn execute(
             init_params,
             total_fee_bps,
             staking_config,
+            lp_token_name,
+            lp_token_symbol,
+            lp_token_type,
+            trading_starts,
+            trading_starts_delay,
         } => execute_create_pair(
             deps,
             info,
             env,
-            pair_type,
-            asset_infos,
-            init_params,
-            total_fee_bps,
-            staking_config,
+            CreatePairParams {
+                pair_type,
+                asset_infos,
+                init_params,
+                total_fee_bps,
+                staking_config,
+                lp_token_name,
+                lp_token_symbol,
+                lp_token_type,
+                trading_starts,
+                trading_starts_delay,
+            },
             Vec::new(),
         ),
+        ExecuteMsg::CreatePairs { pairs } => execute_create_pairs(deps, info, env, pairs),
         ExecuteMsg::Deregister { asset_infos } => deregister(deps, info, asset_infos),
+        ExecuteMsg::BlockAsset { asset, until } => execute_block_asset(deps, info, asset, until),
+        ExecuteMsg::UnblockAsset { asset } => execute_unblock_asset(deps, info, asset),
+        ExecuteMsg::WithdrawAllRewards { pairs, receiver } => {
+            execute_withdraw_all_rewards(deps, info, pairs, receiver)
+        }
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
             let config = CONFIG.load(deps.storage)?;
 
@@ -217,22 +306,44 @@ pub fn execute(
             .map_err(Into::into)
         }
         ExecuteMsg::MarkAsMigrated { pairs } => execute_mark_pairs_as_migrated(deps, info, pairs),
+        ExecuteMsg::AllowMigrationCodeId {
+            pair_type,
+            code_id,
+            allowed,
+        } => execute_allow_migration_code_id(deps, info, pair_type, code_id, allowed),
+        ExecuteMsg::MigratePairs {
+            pairs,
+            new_code_id,
+            msg,
+        } => execute_migrate_pairs(deps, info, pairs, new_code_id, msg),
         ExecuteMsg::CreatePairAndDistributionFlows {
             pair_type,
             asset_infos,
             init_params,
             total_fee_bps,
             staking_config,
+            lp_token_name,
+            lp_token_symbol,
+            lp_token_type,
+            trading_starts,
+            trading_starts_delay,
             distribution_flows,
         } => execute_create_pair(
             deps,
             info,
             env,
-            pair_type,
-            asset_infos,
-            init_params,
-            total_fee_bps,
-            staking_config,
+            CreatePairParams {
+                pair_type,
+                asset_infos,
+                init_params,
+                total_fee_bps,
+                staking_config,
+                lp_token_name,
+                lp_token_symbol,
+                lp_token_type,
+                trading_starts,
+                trading_starts_delay,
+            },
             distribution_flows,
         ),
         ExecuteMsg::CreateDistributionFlow {
@@ -243,6 +354,10 @@ pub fn execute(
     }
 }
 
+/// Forwards a new fee configuration to the pair of the given assets.
+///
+/// ## Executor
+/// Only the owner can execute this.
 fn execute_update_pair_fees(
     deps: DepsMut,
     info: MessageInfo,
@@ -257,6 +372,9 @@ fn execute_update_pair_fees(
 
     // validate
     let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+    if !fee_config.valid_fee_bps() {
+        return Err(ContractError::PairConfigInvalidFeeBps {});
+    }
 
     // get pair address
     let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
@@ -269,6 +387,131 @@ fn execute_update_pair_fees(
     }))
 }
 
+/// Forwards pausing or unpausing to the pair of the given assets.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_pause_pair(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset_infos: Vec<AssetInfo>,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    // check permissions
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    // get pair address
+    let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+    let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+
+    // send pause message to pair
+    Ok(Response::default().add_message(WasmMsg::Execute {
+        contract_addr: pair.to_string(),
+        msg: to_binary(&PairExecuteMsg::SetPaused { paused })?,
+        funds: Vec::new(),
+    }))
+}
+
+/// Blocks pair creation for the given asset until the block time `until`,
+/// or indefinitely if `until` is not provided.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_block_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: AssetInfo,
+    until: Option<u64>,
+) -> Result<Response, ContractError> {
+    // check permissions
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset = asset.validate(deps.api)?;
+    BLOCKED_ASSETS.save(deps.storage, asset.to_string(), &until)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "block_asset"),
+        attr("asset", asset.to_string()),
+        attr(
+            "until",
+            until.map_or_else(|| "indefinite".to_string(), |until| until.to_string()),
+        ),
+    ]))
+}
+
+/// Removes the block on pair creation for the given asset.
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_unblock_asset(
+    deps: DepsMut,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    // check permissions
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let asset = asset.validate(deps.api)?;
+    BLOCKED_ASSETS.remove(deps.storage, asset.to_string());
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "unblock_asset"),
+        attr("asset", asset.to_string()),
+    ]))
+}
+
+/// Withdraws the sender's rewards from the LP token staking contracts of all given pairs.
+/// This just sends a `WithdrawRewards` message on behalf of the sender to each staking contract,
+/// so the sender needs to have delegated withdrawal to the factory there.
+///
+/// * **pairs** contains the assets of each pair to withdraw from.
+///
+/// * **receiver** is the address that gets the rewards, the sender by default.
+fn execute_withdraw_all_rewards(
+    deps: DepsMut,
+    info: MessageInfo,
+    pairs: Vec<Vec<AssetInfo>>,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    if pairs.len() > MAX_REWARD_WITHDRAWALS {
+        return Err(ContractError::TooManyWithdrawals(MAX_REWARD_WITHDRAWALS));
+    }
+
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
+
+    let mut response = Response::new()
+        .add_attribute("action", "withdraw_all_rewards")
+        .add_attribute("owner", info.sender.as_str())
+        .add_attribute("receiver", receiver.as_str());
+    for asset_infos in pairs {
+        let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+        let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+        let staking_addr = query_pair_info(&deps.querier, &pair)?.staking_addr;
+
+        response = response
+            .add_attribute("staking", staking_addr.as_str())
+            .add_message(WasmMsg::Execute {
+                contract_addr: staking_addr.to_string(),
+                msg: to_binary(&StakeExecuteMsg::WithdrawRewards {
+                    owner: Some(info.sender.to_string()),
+                    receiver: Some(receiver.to_string()),
+                })?,
+                funds: vec![],
+            });
+    }
+
+    Ok(response)
+}
+
 /// Forwards distribution flow creation to the correct LP token staking contract.
 ///
 /// * **asset_infos** is the pair of assets whose LP token staking contract should get the new distribution flow.
@@ -341,6 +584,10 @@ pub fn execute_update_config(
         config.only_owner_can_create_pairs = only_owner;
     }
 
+    if let Some(fee_recipients) = param.fee_recipients {
+        config.fee_recipients = validate_fee_recipients(deps.api, fee_recipients)?;
+    }
+
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new().add_attribute("action", "update_config"))
@@ -368,6 +615,9 @@ pub fn execute_update_pair_config(
     if !pair_config.fee_config.valid_fee_bps() {
         return Err(ContractError::PairConfigInvalidFeeBps {});
     }
+    if !pair_config.pair_type.valid_weights() {
+        return Err(ContractError::InvalidWeights {});
+    }
 
     PAIR_CONFIGS.save(
         deps.storage,
@@ -378,29 +628,72 @@ pub fn execute_update_pair_config(
     Ok(Response::new().add_attribute("action", "update_pair_config"))
 }
 
-/// Creates a new pair of `pair_type` with the assets specified in `asset_infos`.
-///
-/// * **pair_type** is the pair type of the newly created pair.
-///
-/// * **asset_infos** is a vector with assets for which we create a pair.
-///
-/// * **init_params** These are packed params used for custom pair types that need extra data to be instantiated.
+/// Creates a new pair with the given parameters.
 ///
-/// * **staking_config** is the configuration for the staking contract. Overrides the default staking config.
+/// * **params** describe the pair, see [`CreatePairParams`].
+/// If no init params are given, the default init params of the pair type's [`PairConfig`] are used.
+/// Trading in the pair starts at `trading_starts`, or `trading_starts_delay` seconds from now,
+/// but never before the factory's `trading_starts`.
 ///
 /// * **distribution_flows** is a vector of distribution flows to be created for the pair's staking contract.
-#[allow(clippy::too_many_arguments)]
 pub fn execute_create_pair(
     deps: DepsMut,
     info: MessageInfo,
     env: Env,
-    pair_type: PairType,
-    asset_infos: Vec<AssetInfo>,
-    init_params: Option<Binary>,
-    total_fee_bps: Option<u16>,
-    staking_config: PartialStakeConfig,
+    params: CreatePairParams,
     distribution_flows: Vec<DistributionFlow>,
 ) -> Result<Response, ContractError> {
+    let (sub_msg, pair) = create_pair_msg(deps, &info, &env, params, distribution_flows)?;
+
+    Ok(Response::new()
+        .add_submessage(sub_msg)
+        .add_attributes(vec![attr("action", "create_pair"), attr("pair", pair)]))
+}
+
+/// Creates all the given pairs in one go.
+/// If any of them fails, the whole message fails.
+///
+/// * **pairs** contains the parameters of each pair, see [`execute_create_pair`].
+pub fn execute_create_pairs(
+    mut deps: DepsMut,
+    info: MessageInfo,
+    env: Env,
+    pairs: Vec<CreatePairParams>,
+) -> Result<Response, ContractError> {
+    if pairs.len() > MAX_PAIRS_PER_BATCH {
+        return Err(ContractError::TooManyPairs(MAX_PAIRS_PER_BATCH));
+    }
+
+    let mut response = Response::new().add_attribute("action", "create_pairs");
+    for params in pairs {
+        let (sub_msg, pair) = create_pair_msg(deps.branch(), &info, &env, params, vec![])?;
+        response = response.add_submessage(sub_msg).add_attribute("pair", pair);
+    }
+
+    Ok(response)
+}
+
+/// Validates the parameters of a new pair and returns the submessage instantiating it,
+/// together with the name of the pair.
+fn create_pair_msg(
+    deps: DepsMut,
+    info: &MessageInfo,
+    env: &Env,
+    params: CreatePairParams,
+    distribution_flows: Vec<DistributionFlow>,
+) -> Result<(SubMsg, String), ContractError> {
+    let CreatePairParams {
+        pair_type,
+        asset_infos,
+        init_params,
+        total_fee_bps,
+        staking_config,
+        lp_token_name,
+        lp_token_symbol,
+        lp_token_type,
+        trading_starts,
+        trading_starts_delay,
+    } = params;
     let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
 
     let config = CONFIG.load(deps.storage)?;
@@ -409,10 +702,39 @@ pub fn execute_create_pair(
         return Err(ContractError::Unauthorized {});
     }
 
-    if PAIRS.has(deps.storage, &pair_key(&asset_infos)) {
+    let trading_starts = match (trading_starts, trading_starts_delay) {
+        (Some(_), Some(_)) => return Err(ContractError::ConflictingTradingStarts {}),
+        (trading_starts, None) => trading_starts,
+        (None, Some(delay)) => Some(env.block.time.seconds().saturating_add(delay)),
+    };
+    if let Some(trading_starts) = trading_starts {
+        validate_trading_starts(env, trading_starts)?;
+    }
+    // the pair cannot start trading before the factory does
+    let trading_starts = trading_starts
+        .max(config.trading_starts)
+        .unwrap_or_else(|| env.block.time.seconds());
+
+    // blocked assets cannot get new pairs until their block expires
+    for asset_info in &asset_infos {
+        let asset = asset_info.to_string();
+        if let Some(until) = BLOCKED_ASSETS.may_load(deps.storage, asset.clone())? {
+            if until.map_or(true, |until| env.block.time.seconds() < until) {
+                return Err(ContractError::AssetBlocked { asset });
+            }
+        }
+    }
+
+    // also check the pairs that are created in the same message
+    let pair_key = pair_key(&asset_infos);
+    if PAIRS.has(deps.storage, &pair_key) || TMP_PAIR_INFOS.has(deps.storage, &pair_key) {
         return Err(ContractError::PairWasCreated {});
     }
 
+    if !pair_type.valid_weights() {
+        return Err(ContractError::InvalidWeights {});
+    }
+
     // Get pair type from config
     let pair_config = PAIR_CONFIGS
         .load(deps.storage, pair_type.to_string())
@@ -423,20 +745,31 @@ pub fn execute_create_pair(
         return Err(ContractError::PairConfigDisabled {});
     }
 
-    let pair_key = pair_key(&asset_infos);
-    TMP_PAIR_INFO.save(
+    // Weighted pairs get their weights from the pair type, all others fall back to the pair config.
+    // Xyk pairs take no init params, since the pair contract reads any init params as weights.
+    let init_params = match (&pair_type, pair_type.weights()) {
+        (PairType::Xyk {}, _) => None,
+        (_, Some(weights)) => Some(to_binary(&WeightedPoolParams { weights })?),
+        (_, None) => init_params.or_else(|| pair_config.init_params.clone()),
+    };
+
+    validate_init_params(&pair_type, init_params.as_ref())?;
+
+    TMP_PAIR_INFOS.save(
         deps.storage,
+        &pair_key,
         &TmpPairInfo {
-            pair_key,
+            pair_key: pair_key.clone(),
             asset_infos: asset_infos.clone(),
             distribution_flows,
         },
     )?;
 
-    let sub_msg: Vec<SubMsg> = vec![SubMsg {
+    let sub_msg = SubMsg {
         id: INSTANTIATE_PAIR_REPLY_ID,
         msg: WasmMsg::Instantiate {
-            admin: Some(config.owner.to_string()),
+            // the factory is the admin, so it can migrate the pair
+            admin: Some(env.contract.address.to_string()),
             code_id: pair_config.code_id,
             msg: to_binary(&PairInstantiateMsg {
                 asset_infos: asset_infos.iter().cloned().map(Into::into).collect(),
@@ -447,28 +780,47 @@ pub fn execute_create_pair(
                     .default_stake_config
                     .combine_with(staking_config)
                     .to_stake_config(),
-                trading_starts: config
-                    .trading_starts
-                    .unwrap_or_else(|| env.block.time.seconds()),
+                trading_starts,
                 fee_config: FeeConfig {
                     total_fee_bps: total_fee_bps.unwrap_or(pair_config.fee_config.total_fee_bps),
                     protocol_fee_bps: pair_config.fee_config.protocol_fee_bps,
                 },
+                default_max_spread: pair_config.default_max_spread,
+                lp_token_type: lp_token_type.unwrap_or_default(),
+                lp_token_name,
+                lp_token_symbol,
             })?,
             funds: vec![],
             label: "Stargaze swap pair".to_string(),
         }
         .into(),
         gas_limit: None,
-        reply_on: ReplyOn::Success,
-    }];
+        // failures are reported by the reply with the reason, which reverts the tentative registration
+        reply_on: ReplyOn::Always,
+    };
 
-    Ok(Response::new()
-        .add_submessages(sub_msg)
-        .add_attributes(vec![
-            attr("action", "create_pair"),
-            attr("pair", asset_infos.iter().join("-")),
-        ]))
+    Ok((sub_msg, asset_infos.iter().join("-")))
+}
+
+/// Checks the pair type specific invariants of the init params that are forwarded to the pair,
+/// so that creating the pair fails early instead of in its instantiation.
+/// The weights of weighted pairs are already checked by [`PairType::valid_weights`].
+fn validate_init_params(
+    pair_type: &PairType,
+    init_params: Option<&Binary>,
+) -> Result<(), ContractError> {
+    if let PairType::Stable {} = pair_type {
+        // Stable pairs cannot be instantiated without an amp
+        let params: StablePoolParams = init_params
+            .and_then(|params| from_binary(params).ok())
+            .ok_or(ContractError::StableAmpNotFound {})?;
+        if params.amp == 0 || params.amp > MAX_AMP {
+            return Err(ContractError::InvalidInitParams {
+                reason: format!("amp must be between 1 and {MAX_AMP}"),
+            });
+        }
+    }
+    Ok(())
 }
 
 /// Marks specified pairs as migrated to the new admin.
@@ -497,9 +849,110 @@ fn execute_mark_pairs_as_migrated(
     Ok(Response::new().add_attribute("action", "execute_mark_pairs_as_migrated"))
 }
 
+/// Allows or disallows migrating pairs of `pair_type` to `code_id` with [`execute_migrate_pairs`].
+///
+/// ## Executor
+/// Only the owner can execute this.
+fn execute_allow_migration_code_id(
+    deps: DepsMut,
+    info: MessageInfo,
+    pair_type: PairType,
+    code_id: u64,
+    allowed: bool,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    let pair_type_key = pair_type.to_string();
+    if !PAIR_CONFIGS.has(deps.storage, pair_type_key.clone()) {
+        return Err(ContractError::PairConfigNotFound {});
+    }
+
+    if allowed {
+        MIGRATION_CODE_IDS.save(deps.storage, (pair_type_key.clone(), code_id), &())?;
+    } else {
+        MIGRATION_CODE_IDS.remove(deps.storage, (pair_type_key.clone(), code_id));
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "allow_migration_code_id")
+        .add_attribute("pair_type", pair_type_key)
+        .add_attribute("code_id", code_id.to_string())
+        .add_attribute("allowed", allowed.to_string()))
+}
+
+/// Migrates the given pairs to `new_code_id` and makes it the code id of their pair type.
+/// The code id has to be allowed for the pair type with [`execute_allow_migration_code_id`] first.
+///
+/// * **pairs** contains the assets of each pair to migrate. All of them need to have the same pair type.
+///
+/// ## Executor
+/// Only the owner can execute this. The factory has to be the admin of the pairs.
+fn execute_migrate_pairs(
+    deps: DepsMut,
+    info: MessageInfo,
+    pairs: Vec<Vec<AssetInfo>>,
+    new_code_id: u64,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pairs.len() > MAX_PAIR_MIGRATIONS {
+        return Err(ContractError::TooManyMigrations(MAX_PAIR_MIGRATIONS));
+    }
+
+    let mut pair_type = None;
+    let mut response = Response::new()
+        .add_attribute("action", "migrate_pairs")
+        .add_attribute("new_code_id", new_code_id.to_string());
+    for asset_infos in pairs {
+        let asset_infos = check_asset_infos(deps.api, &asset_infos)?;
+        let pair = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
+        let pair_info = query_pair_info(&deps.querier, &pair)?;
+        // compare the keys of the pair configs, since weighted pairs with different weights share one
+        let pair_type_key = pair_info.pair_type.to_string();
+        if pair_type.get_or_insert_with(|| pair_type_key.clone()) != &pair_type_key {
+            return Err(ContractError::MixedPairTypes {});
+        }
+
+        response = response
+            .add_attribute("pair", pair.as_str())
+            .add_message(WasmMsg::Migrate {
+                contract_addr: pair.to_string(),
+                new_code_id,
+                msg: msg.clone(),
+            });
+    }
+
+    if let Some(pair_type) = pair_type {
+        // only code that the owner allowed for this pair type can be migrated to
+        let mut pair_config = PAIR_CONFIGS
+            .load(deps.storage, pair_type.clone())
+            .map_err(|_| ContractError::PairConfigNotFound {})?;
+        if pair_config.code_id != new_code_id
+            && !MIGRATION_CODE_IDS.has(deps.storage, (pair_type.clone(), new_code_id))
+        {
+            return Err(ContractError::UnregisteredCodeId(new_code_id));
+        }
+
+        // new pairs are created with the new code as well
+        pair_config.code_id = new_code_id;
+        PAIR_CONFIGS.save(deps.storage, pair_type, &pair_config)?;
+    }
+
+    Ok(response)
+}
+
 /// The entry point to the contract for processing replies from submessages.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if let SubMsgResult::Err(reason) = msg.result {
+        return reply::instantiate_pair_failed(reason);
+    }
+
     // parse the reply
     let res = cw_utils::parse_reply_instantiate_data(msg).map_err(|_| {
         StdError::parse_err("MsgInstantiateContractResponse", "failed to parse data")
@@ -516,19 +969,34 @@ pub mod reply {
 
     use super::*;
 
+    /// Returns the reason why the instantiation of a pair failed.
+    ///
+    /// Returning an error reverts the whole message, including the tentative registrations in
+    /// [`TMP_PAIR_INFOS`] of this and any other pair created with it, so there is nothing to clean up.
+    pub fn instantiate_pair_failed(reason: String) -> Result<Response, ContractError> {
+        Err(ContractError::PairInstantiationFailed { reason })
+    }
+
     pub fn instantiate_pair(
         deps: DepsMut,
         env: Env,
         res: MsgInstantiateContractResponse,
     ) -> Result<Response, ContractError> {
-        let tmp = TMP_PAIR_INFO.load(deps.storage)?;
-        if PAIRS.has(deps.storage, &tmp.pair_key) {
+        let pair_contract = deps.api.addr_validate(&res.contract_address)?;
+        let pair_info = query_pair_info(&deps.querier, &pair_contract)?;
+
+        // several pairs can be created in one message, so we find this one by its assets
+        let pair_key = pair_key(&pair_info.asset_infos);
+        if PAIRS.has(deps.storage, &pair_key) {
             return Err(ContractError::PairWasRegistered {});
         }
-
-        let pair_contract = deps.api.addr_validate(&res.contract_address)?;
+        let tmp = TMP_PAIR_INFOS.load(deps.storage, &pair_key)?;
+        TMP_PAIR_INFOS.remove(deps.storage, &pair_key);
 
         PAIRS.save(deps.storage, &tmp.pair_key, &pair_contract)?;
+        for asset_info in &tmp.asset_infos {
+            PAIRS_BY_ASSET.save(deps.storage, (asset_info.to_string(), &pair_contract), &())?;
+        }
 
         for asset_info in &tmp.asset_infos {
             for asset_info_2 in &tmp.asset_infos {
@@ -550,7 +1018,6 @@ pub mod reply {
         }
 
         // keep track of staking address
-        let pair_info = query_pair_info(&deps.querier, &pair_contract)?;
         STAKING_ADDRESSES.save(deps.storage, &pair_info.staking_addr, &())?;
 
         Ok(Response::new()
@@ -601,6 +1068,9 @@ pub fn deregister(
 
     let pair_addr = PAIRS.load(deps.storage, &pair_key(&asset_infos))?;
     PAIRS.remove(deps.storage, &pair_key(&asset_infos));
+    for asset_info in &asset_infos {
+        PAIRS_BY_ASSET.remove(deps.storage, (asset_info.to_string(), &pair_addr));
+    }
 
     for asset_info1 in &asset_infos {
         for asset_info2 in &asset_infos {
@@ -637,11 +1107,16 @@ pub fn deregister(
 /// * **QueryMsg::Pairs { start_after, limit }** Returns an array that contains items of type [`PairInfo`].
 /// This returns information about multiple Stargaze swap pairs
 ///
+/// * **QueryMsg::PairsByAsset { asset, start_after, limit }** Returns the pairs that trade the given asset
+/// in an array that contains items of type [`PairInfo`].
+///
 /// * **QueryMsg::FeeInfo { pair_type }** Returns the fee structure (total and protocol fees) for a specific pair type.
 ///
 /// * **QueryMsg::BlacklistedPairTypes {}** Returns a vector that contains blacklisted pair types (pair types that cannot get ASTRO emissions).
 ///
 /// * **QueryMsg::PairsToMigrate {}** Returns a vector that contains pair addresses that are not migrated.
+///
+/// * **QueryMsg::OwnershipProposal {}** Returns the pending ownership transfer proposal, if any.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -650,6 +1125,11 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Pairs { start_after, limit } => {
             to_binary(&query_pairs(deps, start_after, limit)?)
         }
+        QueryMsg::PairsByAsset {
+            asset,
+            start_after,
+            limit,
+        } => to_binary(&query_pairs_by_asset(deps, asset, start_after, limit)?),
         QueryMsg::FeeInfo { pair_type } => to_binary(&query_fee_info(deps, pair_type)?),
         QueryMsg::BlacklistedPairTypes {} => to_binary(&query_blacklisted_pair_types(deps)?),
         QueryMsg::PairsToMigrate {} => {
@@ -658,6 +1138,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::ValidateStakingAddress { address } => {
             to_binary(&STAKING_ADDRESSES.has(deps.storage, &deps.api.addr_validate(&address)?))
         }
+        QueryMsg::OwnershipProposal {} => to_binary(&OWNERSHIP_PROPOSAL.may_load(deps.storage)?),
     }
 }
 
@@ -692,6 +1173,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         max_referral_commission: config.max_referral_commission,
         only_owner_can_create_pairs: config.only_owner_can_create_pairs,
         trading_starts: config.trading_starts,
+        fee_recipients: config.fee_recipients,
     };
 
     Ok(resp)
@@ -726,6 +1208,25 @@ pub fn query_pairs(
     Ok(PairsResponse { pairs })
 }
 
+/// Returns the pairs that trade `asset`, ordered by pair address.
+/// * **start_after** is the address of the last pair of the previous page.
+///
+/// * **limit** sets the number of pairs to be retrieved.
+pub fn query_pairs_by_asset(
+    deps: Deps,
+    asset: AssetInfo,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<PairsResponse> {
+    let asset = asset.validate(deps.api)?;
+    let pairs = read_pairs_by_asset(deps, &asset, start_after, limit)?
+        .iter()
+        .map(|pair_addr| query_pair_info(&deps.querier, pair_addr))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(PairsResponse { pairs })
+}
+
 /// Returns the fee setup for a specific pair type using a [`FeeInfoResponse`] struct.
 /// * **pair_type** is a struct that represents the fee information (total and protocol fees) for a specific pair type.
 pub fn query_fee_info(deps: Deps, pair_type: PairType) -> StdResult<FeeInfoResponse> {
@@ -768,6 +1269,29 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
             )
             .unwrap();
         }
+        MigrateMsg::Update {
+            default_stake_config,
+        } => {
+            // Rejects downgrades and migrations from other contracts
+            ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+            if let Some(default_stake_config) = default_stake_config {
+                CONFIG.update::<_, StdError>(deps.storage, |mut config| {
+                    config.default_stake_config = default_stake_config;
+                    Ok(config)
+                })?;
+            }
+
+            // build the index of pairs by asset from the routes, which contain every pair
+            let routes = ROUTE
+                .range(deps.storage, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()?;
+            for ((asset, _), pairs) in routes {
+                for pair in pairs {
+                    PAIRS_BY_ASSET.save(deps.storage, (asset.clone(), &pair), &())?;
+                }
+            }
+        }
     };
 
     Ok(Response::new())
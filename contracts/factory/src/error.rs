@@ -10,6 +10,9 @@ pub enum ContractError {
     #[error("Invalid value for trading start")]
     InvalidTradingStart {},
 
+    #[error("Only one of trading_starts and trading_starts_delay can be set")]
+    ConflictingTradingStarts {},
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -19,21 +22,56 @@ pub enum ContractError {
     #[error("Pair was already registered")]
     PairWasRegistered {},
 
+    #[error("Instantiating the pair failed: {reason}")]
+    PairInstantiationFailed { reason: String },
+
     #[error("Duplicate of pair configs")]
     PairConfigDuplicate {},
 
     #[error("Fee bps in pair config must be smaller than or equal to 10,000")]
     PairConfigInvalidFeeBps {},
 
+    #[error("Weights in pair type must be non-zero and sum up to 10,000")]
+    InvalidWeights {},
+
     #[error("Pair config not found")]
     PairConfigNotFound {},
 
     #[error("Pair config disabled")]
     PairConfigDisabled {},
 
+    #[error("Stable pairs need an amp, either in the init params or in the pair config")]
+    StableAmpNotFound {},
+
+    #[error("Invalid init params: {reason}")]
+    InvalidInitParams { reason: String },
+
+    #[error("Asset {asset} is blocked from pair creation")]
+    AssetBlocked { asset: String },
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
+    #[error("Cannot create more than {0} pairs at once")]
+    TooManyPairs(usize),
+
+    #[error("Cannot withdraw rewards from more than {0} pairs at once")]
+    TooManyWithdrawals(usize),
+
+    #[error("Cannot migrate more than {0} pairs at once")]
+    TooManyMigrations(usize),
+
+    #[error("All migrated pairs must have the same pair type")]
+    MixedPairTypes {},
+
+    #[error(
+        "Code id {0} is not allowed for the pair type, allow it with allow_migration_code_id first"
+    )]
+    UnregisteredCodeId(u64),
+
+    #[error("Fee recipient weights must be non-zero and sum up to one")]
+    InvalidFeeRecipients {},
+
     #[error("Invalid referral commision: {0}")]
     InvalidReferralCommission(Decimal),
 
@@ -26,6 +26,10 @@ pub struct Config {
     pub only_owner_can_create_pairs: bool,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// Addresses the protocol fees are split between.
+    /// If this is empty, all protocol fees go to `fee_address`
+    #[serde(default)]
+    pub fee_recipients: Vec<(Addr, Decimal)>,
 }
 
 /// This is an intermediate structure for storing a pair's key. It is used in a submessage response.
@@ -36,8 +40,8 @@ pub struct TmpPairInfo {
     pub distribution_flows: Vec<DistributionFlow>,
 }
 
-/// Saves a pair's key
-pub const TMP_PAIR_INFO: Item<TmpPairInfo> = Item::new("tmp_pair_info");
+/// Saves the pairs that are being instantiated by their pair key
+pub const TMP_PAIR_INFOS: Map<&[u8], TmpPairInfo> = Map::new("tmp_pair_infos");
 
 /// Saves factory settings
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -45,12 +49,20 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Saves created pairs (from olders to latest)
 pub const PAIRS: Map<&[u8], Addr> = Map::new("pair_info");
 
+/// Index of the pairs each asset is traded in, keyed by asset and pair address
+pub const PAIRS_BY_ASSET: Map<(String, &Addr), ()> = Map::new("pairs_by_asset");
+
+/// Assets for which no new pairs can be created,
+/// together with the block time until which they are blocked (`None` blocks them indefinitely)
+pub const BLOCKED_ASSETS: Map<String, Option<u64>> = Map::new("blocked_assets");
+
 /// Set of all staking addresses
 pub const STAKING_ADDRESSES: Map<&Addr, ()> = Map::new("staking_addresses");
 
 /// Calculates a pair key from the specified parameters in the `asset_infos` variable.
 ///
 /// `asset_infos` is an array with multiple items of type [`AssetInfo`].
+/// The assets are sorted first, so the key does not depend on their order.
 pub fn pair_key(asset_infos: &[AssetInfoValidated]) -> Vec<u8> {
     asset_infos
         .iter()
@@ -64,9 +76,14 @@ pub fn pair_key(asset_infos: &[AssetInfoValidated]) -> Vec<u8> {
 /// Saves pair type configurations
 pub const PAIR_CONFIGS: Map<String, PairConfig> = Map::new("pair_configs");
 
+/// The code ids that pairs can be migrated to, by the key of their pair config in [`PAIR_CONFIGS`]
+pub const MIGRATION_CODE_IDS: Map<(String, u64), ()> = Map::new("migration_code_ids");
+
 /// ## Pagination settings
 /// The default limit for reading pairs from [`PAIRS`]
 const DEFAULT_LIMIT: u32 = 10;
+/// The maximum limit for reading pairs from [`PAIRS`]
+pub const MAX_LIMIT: u32 = 30;
 
 /// Reads pairs from the [`PAIRS`] vector according to the `start_after` and `limit` variables.
 /// Otherwise, it returns the default number of pairs, starting from the oldest one.
@@ -86,7 +103,7 @@ pub fn read_pairs(
                 .collect::<Result<_, _>>()
         })
         .transpose()?;
-    let limit = limit.unwrap_or(DEFAULT_LIMIT) as usize;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     if let Some(start) = calc_range_start(start_after) {
         PAIRS
@@ -114,6 +131,34 @@ pub fn read_pairs(
     }
 }
 
+/// Reads the pairs that trade `asset` from [`PAIRS_BY_ASSET`], ordered by pair address.
+///
+/// `start_after` is the address of the pair after which the function starts to fetch results.
+///
+/// `limit` is the number of items to retrieve.
+pub fn read_pairs_by_asset(
+    deps: Deps,
+    asset: &AssetInfoValidated,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Addr>> {
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    PAIRS_BY_ASSET
+        .prefix(asset.to_string())
+        .keys(
+            deps.storage,
+            start_after.as_ref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
+
 /// Calculates the key of a pair from which to start reading data.
 ///
 /// `start_after` is an [`Option`] type that accepts [`AssetInfo`] elements.
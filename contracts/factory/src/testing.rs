@@ -1,21 +1,23 @@
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Decimal, ReplyOn, SubMsg, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, Reply, ReplyOn, Response,
+    SubMsg, SubMsgResult, Uint128, WasmMsg,
 };
 use cw_utils::MsgInstantiateContractResponse;
 use sg_swap::fee_config::FeeConfig;
 
 use crate::mock_querier::mock_dependencies;
-use crate::state::CONFIG;
+use crate::state::{pair_key, CONFIG, TMP_PAIR_INFOS};
 use crate::{
-    contract::{execute, instantiate, query},
+    contract::{execute, instantiate, migrate, query},
     error::ContractError,
 };
 use sg_swap::asset::AssetInfo;
+use sg_swap::common::OwnershipProposal;
 use sg_swap::factory::{
-    ConfigResponse, DefaultStakeConfig, ExecuteMsg, InstantiateMsg, PairConfig, PairType,
-    PairsResponse, PartialStakeConfig, QueryMsg,
+    ConfigResponse, DefaultStakeConfig, ExecuteMsg, InstantiateMsg, MigrateMsg, PairConfig,
+    PairType, PairsResponse, PartialStakeConfig, QueryMsg,
 };
-use sg_swap::pair::PairInfo;
+use sg_swap::pair::{LpTokenType, PairInfo, StablePoolParams, MAX_AMP};
 
 use crate::contract::reply;
 use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
@@ -53,6 +55,8 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             },
             PairConfig {
                 code_id: 325u64,
@@ -62,6 +66,8 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             },
         ],
         token_code_id: 123u64,
@@ -70,6 +76,7 @@ fn proper_initialization() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -87,6 +94,8 @@ fn proper_initialization() {
                 protocol_fee_bps: 10,
             },
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id: 123u64,
         fee_address: None,
@@ -94,6 +103,7 @@ fn proper_initialization() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -114,6 +124,8 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             },
             PairConfig {
                 code_id: 123u64,
@@ -123,6 +135,8 @@ fn proper_initialization() {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             },
         ],
         token_code_id: 123u64,
@@ -131,6 +145,7 @@ fn proper_initialization() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -161,6 +176,7 @@ fn trading_starts_validation() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     // in the past
@@ -196,6 +212,8 @@ fn update_config() {
             protocol_fee_bps: 166,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     }];
 
     let msg = InstantiateMsg {
@@ -206,6 +224,7 @@ fn update_config() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -221,6 +240,7 @@ fn update_config() {
         token_code_id: Some(200u64),
         fee_address: Some(String::from("new_fee_addr")),
         only_owner_can_create_pairs: Some(true),
+        fee_recipients: None,
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
@@ -243,12 +263,103 @@ fn update_config() {
         token_code_id: None,
         fee_address: None,
         only_owner_can_create_pairs: None,
+        fee_recipients: None,
     };
 
     let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
     assert_eq!(res, ContractError::Unauthorized {});
 }
 
+#[test]
+fn migrate_from_older_version() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![PairConfig {
+            code_id: 123u64,
+            pair_type: PairType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 3,
+                protocol_fee_bps: 166,
+            },
+            is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
+        }],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+    let old_config = CONFIG.load(deps.as_ref().storage).unwrap();
+
+    // pretend an older version is deployed
+    cw2::set_contract_version(deps.as_mut().storage, "sg-swap-factory", "0.0.1").unwrap();
+
+    let new_stake_config = DefaultStakeConfig {
+        staking_code_id: 4321u64,
+        ..default_stake_config()
+    };
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Update {
+            default_stake_config: Some(new_stake_config.clone()),
+        },
+    )
+    .unwrap();
+
+    let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.contract, "sg-swap-factory");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // only the default stake config changed
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(config.default_stake_config, new_stake_config);
+    assert_eq!(config.owner, old_config.owner);
+    assert_eq!(config.token_code_id, old_config.token_code_id);
+    let pair_config = crate::state::PAIR_CONFIGS
+        .load(deps.as_ref().storage, PairType::Xyk {}.to_string())
+        .unwrap();
+    assert_eq!(pair_config.code_id, 123u64);
+
+    // migrating again without changes keeps the config
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Update {
+            default_stake_config: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        CONFIG
+            .load(deps.as_ref().storage)
+            .unwrap()
+            .default_stake_config,
+        new_stake_config
+    );
+
+    // downgrades are rejected
+    cw2::set_contract_version(deps.as_mut().storage, "sg-swap-factory", "999.0.0").unwrap();
+    let err = migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::Update {
+            default_stake_config: None,
+        },
+    )
+    .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Cannot migrate from newer version"));
+}
+
 #[test]
 fn update_owner() {
     let mut deps = mock_dependencies(&[]);
@@ -262,6 +373,7 @@ fn update_owner() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -328,6 +440,100 @@ fn update_owner() {
     assert_eq!(new_owner, config.owner);
 }
 
+#[test]
+fn ownership_proposal_expiry_and_drop() {
+    let mut deps = mock_dependencies(&[]);
+    let owner = "owner0000";
+    let new_owner = "new_owner";
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: owner.to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info(owner, &[]), msg).unwrap();
+
+    let query_proposal = |deps: Deps| -> Option<OwnershipProposal> {
+        from_binary(&query(deps, mock_env(), QueryMsg::OwnershipProposal {}).unwrap()).unwrap()
+    };
+    assert_eq!(query_proposal(deps.as_ref()), None);
+
+    // Propose new owner
+    let env = mock_env();
+    let msg = ExecuteMsg::ProposeNewOwner {
+        owner: new_owner.to_string(),
+        expires_in: 100, // seconds
+    };
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        msg.clone(),
+    )
+    .unwrap();
+    assert_eq!(
+        query_proposal(deps.as_ref()),
+        Some(OwnershipProposal {
+            owner: Addr::unchecked(new_owner),
+            ttl: env.block.time.seconds() + 100,
+        })
+    );
+
+    // Only the owner can drop the proposal
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(new_owner, &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Unauthorized");
+
+    // Drop the proposal, after which it cannot be claimed anymore
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(owner, &[]),
+        ExecuteMsg::DropOwnershipProposal {},
+    )
+    .unwrap();
+    assert_eq!(query_proposal(deps.as_ref()), None);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info(new_owner, &[]),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Ownership proposal not found"
+    );
+
+    // Propose again, but claim after expiry
+    execute(deps.as_mut(), env.clone(), mock_info(owner, &[]), msg).unwrap();
+    let mut expired_env = env;
+    expired_env.block.time = expired_env.block.time.plus_seconds(101);
+    let err = execute(
+        deps.as_mut(),
+        expired_env.clone(),
+        mock_info(new_owner, &[]),
+        ExecuteMsg::ClaimOwnership {},
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Generic error: Ownership proposal expired");
+
+    // Ownership did not move
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), expired_env, QueryMsg::Config {}).unwrap()).unwrap();
+    assert_eq!(owner, config.owner);
+}
+
 #[test]
 fn update_pair_config() {
     let mut deps = mock_dependencies(&[]);
@@ -340,6 +546,8 @@ fn update_pair_config() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     }];
 
     let msg = InstantiateMsg {
@@ -350,6 +558,7 @@ fn update_pair_config() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -372,6 +581,8 @@ fn update_pair_config() {
             protocol_fee_bps: 2,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     };
 
     // Unauthorized err
@@ -396,12 +607,28 @@ fn update_pair_config() {
                 protocol_fee_bps: 10_001,
             },
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         },
     };
 
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
     assert_eq!(res, ContractError::PairConfigInvalidFeeBps {});
 
+    // Check validation of weights
+    let info = mock_info(owner, &[]);
+    let msg = ExecuteMsg::UpdatePairConfig {
+        config: PairConfig {
+            pair_type: PairType::Weighted {
+                weights: [8_000, 1_000],
+            },
+            ..pair_config.clone()
+        },
+    };
+
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(res, ContractError::InvalidWeights {});
+
     let info = mock_info(owner, &[]);
     let msg = ExecuteMsg::UpdatePairConfig {
         config: pair_config.clone(),
@@ -424,6 +651,8 @@ fn update_pair_config() {
             protocol_fee_bps: 20,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     };
 
     let info = mock_info(owner, &[]);
@@ -454,6 +683,8 @@ fn create_pair() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     };
 
     let msg = InstantiateMsg {
@@ -464,6 +695,7 @@ fn create_pair() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -477,7 +709,6 @@ fn create_pair() {
         AssetInfo::Token("asset0001".to_string()),
     ];
 
-    let config = CONFIG.load(&deps.storage);
     let env = mock_env();
     let info = mock_info("owner0000", &[]);
 
@@ -492,6 +723,11 @@ fn create_pair() {
             init_params: None,
             total_fee_bps: None,
             staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
         },
     )
     .unwrap_err();
@@ -507,6 +743,11 @@ fn create_pair() {
             init_params: None,
             total_fee_bps: None,
             staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
         },
     )
     .unwrap();
@@ -530,21 +771,382 @@ fn create_pair() {
                     staking_config: default_stake_config().to_stake_config(),
                     trading_starts: mock_env().block.time.seconds(),
                     fee_config: pair_config.fee_config,
+                    default_max_spread: pair_config.default_max_spread,
+                    lp_token_type: LpTokenType::Cw20,
+                    lp_token_name: None,
+                    lp_token_symbol: None,
                 })
                 .unwrap(),
                 code_id: pair_config.code_id,
                 funds: vec![],
-                admin: Some(config.unwrap().owner.to_string()),
+                admin: Some(String::from(MOCK_CONTRACT_ADDR)),
                 label: String::from("Stargaze swap pair"),
             }
             .into(),
             id: 1,
             gas_limit: None,
-            reply_on: ReplyOn::Success
+            reply_on: ReplyOn::Always
         }]
     );
 }
 
+#[test]
+fn create_pair_with_trading_starts() {
+    let mut deps = mock_dependencies(&[]);
+    let now = mock_env().block.time.seconds();
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![PairConfig {
+            code_id: 321u64,
+            pair_type: PairType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 10,
+            },
+            is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
+        }],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: Some(now + 100),
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let create_pair = |denom: &str, trading_starts, trading_starts_delay| ExecuteMsg::CreatePair {
+        pair_type: PairType::Xyk {},
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native(denom.to_string()),
+        ],
+        init_params: None,
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts,
+        trading_starts_delay,
+        lp_token_type: None,
+    };
+    let forwarded_trading_starts = |res: Response| match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            from_binary::<PairInstantiateMsg>(msg)
+                .unwrap()
+                .trading_starts
+        }
+        msg => panic!("unexpected message {msg:?}"),
+    };
+    let info = mock_info("owner0000", &[]);
+
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        create_pair("uluna", Some(now + 1000), Some(1000)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::ConflictingTradingStarts {});
+
+    // the same bounds apply as for the factory's trading start
+    const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        create_pair("uluna", None, Some(60 * SECONDS_PER_DAY + 1)),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTradingStart {});
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        create_pair("uluna", Some(now - 1), None),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidTradingStart {});
+
+    // the delay is relative to the block time
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        create_pair("uluna", None, Some(1000)),
+    )
+    .unwrap();
+    assert_eq!(forwarded_trading_starts(res), now + 1000);
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        create_pair("uatom", Some(now + 2000), None),
+    )
+    .unwrap();
+    assert_eq!(forwarded_trading_starts(res), now + 2000);
+
+    // trading never starts before the factory's trading start
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        create_pair("ujuno", None, Some(10)),
+    )
+    .unwrap();
+    assert_eq!(forwarded_trading_starts(res), now + 100);
+}
+
+#[test]
+fn failed_pair_instantiation_is_reported() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![PairConfig {
+            code_id: 321u64,
+            pair_type: PairType::Xyk {},
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 10,
+            },
+            is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
+        }],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::Token("asset0000".to_string()),
+        AssetInfo::Token("asset0001".to_string()),
+    ];
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner0000", &[]),
+        ExecuteMsg::CreatePair {
+            pair_type: PairType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: None,
+            staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
+        },
+    )
+    .unwrap();
+    let reason = "Error executing WasmMsg".to_string();
+    let err = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: 1,
+            result: SubMsgResult::Err(reason.clone()),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::PairInstantiationFailed { reason });
+
+    let res: PairsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(res.pairs.is_empty());
+
+    // the reply leaves the tentative registration to the revert of the whole message,
+    // which the mock storage does not do
+    let asset_infos: Vec<_> = asset_infos
+        .into_iter()
+        .map(|info| info.validate(&deps.api).unwrap())
+        .collect();
+    assert!(TMP_PAIR_INFOS.has(&deps.storage, &pair_key(&asset_infos)));
+}
+
+#[test]
+fn create_stable_pair_with_template_amp() {
+    let mut deps = mock_dependencies(&[]);
+
+    let template = to_binary(&StablePoolParams {
+        amp: 100,
+        owner: None,
+    })
+    .unwrap();
+    let pair_config = PairConfig {
+        code_id: 321u64,
+        pair_type: PairType::Stable {},
+        fee_config: FeeConfig {
+            total_fee_bps: 5,
+            protocol_fee_bps: 0,
+        },
+        is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
+    };
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![pair_config.clone()],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let create_pair = |init_params: Option<Binary>| ExecuteMsg::CreatePair {
+        pair_type: PairType::Stable {},
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native("uluna".to_string()),
+        ],
+        init_params,
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
+    };
+    let forwarded_init_params = |res: Response| match &res.messages[0].msg {
+        CosmosMsg::Wasm(WasmMsg::Instantiate { msg, .. }) => {
+            from_binary::<PairInstantiateMsg>(msg).unwrap().init_params
+        }
+        msg => panic!("unexpected message {msg:?}"),
+    };
+    let info = mock_info("owner0000", &[]);
+
+    // neither template nor override
+    let err = execute(deps.as_mut(), mock_env(), info.clone(), create_pair(None)).unwrap_err();
+    assert_eq!(err, ContractError::StableAmpNotFound {});
+
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        info.clone(),
+        ExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                init_params: Some(template.clone()),
+                ..pair_config
+            },
+        },
+    )
+    .unwrap();
+
+    // the template is used when the creator does not provide init params
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), create_pair(None)).unwrap();
+    assert_eq!(forwarded_init_params(res), Some(template));
+
+    // but it can still be overridden (using a different pair, since this one was created already)
+    let override_params = to_binary(&StablePoolParams {
+        amp: 50,
+        owner: None,
+    })
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        info,
+        ExecuteMsg::CreatePair {
+            pair_type: PairType::Stable {},
+            asset_infos: vec![
+                AssetInfo::Native("uusd".to_string()),
+                AssetInfo::Native("ujuno".to_string()),
+            ],
+            init_params: Some(override_params.clone()),
+            total_fee_bps: None,
+            staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(forwarded_init_params(res), Some(override_params));
+}
+
+#[test]
+fn create_stable_pair_validates_amp() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        pair_configs: vec![PairConfig {
+            code_id: 321u64,
+            pair_type: PairType::Stable {},
+            fee_config: FeeConfig {
+                total_fee_bps: 5,
+                protocol_fee_bps: 0,
+            },
+            is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
+        }],
+        token_code_id: 123u64,
+        fee_address: None,
+        owner: "owner0000".to_string(),
+        max_referral_commission: Decimal::one(),
+        default_stake_config: default_stake_config(),
+        trading_starts: None,
+        fee_recipients: None,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+
+    let create_pair = |amp: u64| ExecuteMsg::CreatePair {
+        pair_type: PairType::Stable {},
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native("uluna".to_string()),
+        ],
+        init_params: Some(to_binary(&StablePoolParams { amp, owner: None }).unwrap()),
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
+    };
+    let info = mock_info("owner0000", &[]);
+
+    for amp in [0, MAX_AMP + 1] {
+        let err = execute(deps.as_mut(), mock_env(), info.clone(), create_pair(amp)).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InvalidInitParams {
+                reason: format!("amp must be between 1 and {MAX_AMP}")
+            }
+        );
+    }
+
+    let res = execute(deps.as_mut(), mock_env(), info, create_pair(MAX_AMP)).unwrap();
+    assert_eq!(res.messages.len(), 1);
+}
+
 #[test]
 fn register() {
     let mut deps = mock_dependencies(&[]);
@@ -559,6 +1161,8 @@ fn register() {
                 protocol_fee_bps: 10,
             },
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id: 123u64,
         fee_address: None,
@@ -566,6 +1170,7 @@ fn register() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let env = mock_env();
@@ -583,6 +1188,11 @@ fn register() {
         init_params: None,
         staking_config: PartialStakeConfig::default(),
         total_fee_bps: None,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     let env = mock_env();
@@ -665,6 +1275,11 @@ fn register() {
         init_params: None,
         staking_config: PartialStakeConfig::default(),
         total_fee_bps: None,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     let env = mock_env();
@@ -86,6 +86,8 @@ impl FactoryHelper {
                     protocol_fee_bps: 10,
                 },
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             }],
             token_code_id: cw20_token_code_id,
             fee_address: None,
@@ -99,6 +101,7 @@ impl FactoryHelper {
                 max_distributions: 6,
             },
             trading_starts: None,
+            fee_recipients: None,
         };
 
         let factory = router
@@ -132,6 +135,7 @@ impl FactoryHelper {
             token_code_id,
             fee_address,
             only_owner_can_create_pairs,
+            fee_recipients: None,
         };
 
         router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
@@ -157,6 +161,11 @@ impl FactoryHelper {
             init_params,
             staking_config: staking_config.unwrap_or_default(),
             total_fee_bps: None,
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
         };
 
         router.execute_contract(sender.clone(), self.factory.clone(), &msg, &[])
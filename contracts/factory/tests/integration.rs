@@ -1,15 +1,18 @@
 mod factory_helper;
 
-use cosmwasm_std::{attr, Addr, Decimal, Uint128};
+use cosmwasm_std::{
+    attr, to_binary, Addr, Decimal, DepsMut, Empty, Env, Response, StdResult, Uint128,
+};
 use sg_swap::asset::AssetInfo;
 use sg_swap::factory::{
-    ConfigResponse, DefaultStakeConfig, ExecuteMsg, FeeInfoResponse, InstantiateMsg, MigrateMsg,
-    PairConfig, PairType, QueryMsg,
+    ConfigResponse, CreatePairParams, DefaultStakeConfig, ExecuteMsg, FeeInfoResponse,
+    InstantiateMsg, MigrateMsg, PairConfig, PairType, PairsResponse, QueryMsg, MAX_PAIR_MIGRATIONS,
 };
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::PairInfo;
 
 use crate::factory_helper::{instantiate_token, FactoryHelper};
+use cw20::{Cw20QueryMsg, TokenInfoResponse};
 use cw_multi_test::{App, ContractWrapper, Executor};
 use cw_placeholder::msg::InstantiateMsg as PlaceholderContractInstantiateMsg;
 use sg_swap::pair::ExecuteMsg as PairExecuteMsg;
@@ -67,6 +70,8 @@ fn proper_initialization() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     }];
 
     let msg = InstantiateMsg {
@@ -77,6 +82,7 @@ fn proper_initialization() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let factory_instance = app
@@ -229,6 +235,8 @@ fn test_create_pair() {
                     protocol_fee_bps: 40,
                 },
                 is_disabled: true,
+                default_max_spread: None,
+                init_params: None,
             },
         },
         &[],
@@ -276,6 +284,154 @@ fn test_create_pair() {
     assert_eq!(pair_types, vec![PairType::Custom("Custom".to_string())]);
 }
 
+#[test]
+fn create_pair_with_lp_token_override() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+    let asset_infos = vec![
+        AssetInfo::Token(token1.to_string()),
+        AssetInfo::Token(token2.to_string()),
+    ];
+    let create_pair = |symbol: &str| ExecuteMsg::CreatePair {
+        pair_type: PairType::Xyk {},
+        asset_infos: asset_infos.clone(),
+        init_params: None,
+        total_fee_bps: None,
+        staking_config: Default::default(),
+        lp_token_name: Some("MYTOKEN-USDC LP".to_string()),
+        lp_token_symbol: Some(symbol.to_string()),
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
+    };
+
+    // cw20 symbols only contain letters and dashes
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &create_pair("MY_LP"),
+            &[],
+        )
+        .unwrap_err();
+    assert!(err.chain().any(|err| err
+        .to_string()
+        .contains("LP token symbol must be between 3 and 12 characters long")));
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &create_pair("MYLP"),
+        &[],
+    )
+    .unwrap();
+
+    let pair: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Pair { asset_infos })
+        .unwrap();
+    let token_info: TokenInfoResponse = app
+        .wrap()
+        .query_wasm_smart(&pair.liquidity_token, &Cw20QueryMsg::TokenInfo {})
+        .unwrap();
+    assert_eq!(token_info.name, "MYTOKEN-USDC LP");
+    assert_eq!(token_info.symbol, "MYLP");
+}
+
+#[test]
+fn failed_pair_instantiation_is_reported() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenX",
+        Some(18),
+    );
+    let token2 = instantiate_token(
+        &mut app,
+        helper.cw20_token_code_id,
+        &owner,
+        "tokenY",
+        Some(18),
+    );
+
+    // a pair type whose code is not a pair contract, so its instantiation fails
+    let broken = PairType::Custom("broken".to_string());
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                code_id: helper.cw20_token_code_id,
+                pair_type: broken.clone(),
+                fee_config: FeeConfig {
+                    total_fee_bps: 100,
+                    protocol_fee_bps: 10,
+                },
+                is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            broken,
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .chain()
+        .any(|err| err.to_string().starts_with("Instantiating the pair failed")));
+
+    let asset_infos = vec![
+        AssetInfo::Token(token1.to_string()),
+        AssetInfo::Token(token2.to_string()),
+    ];
+    app.wrap()
+        .query_wasm_smart::<PairInfo>(&helper.factory, &QueryMsg::Pair { asset_infos })
+        .unwrap_err();
+
+    // the failure reverted the tentative registration, so the pair can be created afterwards
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+}
+
 #[test]
 fn test_create_pair_permissions() {
     let mut app = mock_app();
@@ -328,6 +484,449 @@ fn test_create_pair_permissions() {
         .unwrap();
 }
 
+#[test]
+fn create_multiple_pairs() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens: Vec<_> = ["tokenX", "tokenY", "tokenZ"]
+        .into_iter()
+        .map(|name| instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, Some(18)))
+        .collect();
+    let token_pairs = [
+        [&tokens[0], &tokens[1]],
+        [&tokens[0], &tokens[2]],
+        [&tokens[1], &tokens[2]],
+    ];
+    let params = |assets: &[[&Addr; 2]]| -> Vec<CreatePairParams> {
+        assets
+            .iter()
+            .map(|[a, b]| CreatePairParams {
+                pair_type: PairType::Xyk {},
+                asset_infos: vec![
+                    AssetInfo::Token(a.to_string()),
+                    AssetInfo::Token(b.to_string()),
+                ],
+                init_params: None,
+                total_fee_bps: None,
+                staking_config: Default::default(),
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
+            })
+            .collect()
+    };
+
+    // a duplicate pair fails the whole batch
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::CreatePairs {
+                pairs: params(&[token_pairs[0], token_pairs[1], token_pairs[0]]),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Pair was already created");
+
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::CreatePairs {
+                pairs: params(&[token_pairs[0]; 11]),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Cannot create more than 10 pairs at once"
+    );
+
+    let res: PairsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert!(res.pairs.is_empty());
+
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::CreatePairs {
+            pairs: params(&token_pairs),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let mut pair_addrs = vec![];
+    for [a, b] in token_pairs {
+        let asset_infos = vec![
+            AssetInfo::Token(a.to_string()),
+            AssetInfo::Token(b.to_string()),
+        ];
+        let res: PairInfo = app
+            .wrap()
+            .query_wasm_smart(
+                &helper.factory,
+                &QueryMsg::Pair {
+                    asset_infos: asset_infos.clone(),
+                },
+            )
+            .unwrap();
+        assert_eq!(
+            res.asset_infos,
+            asset_infos
+                .iter()
+                .map(|a| a.validate(app.api()).unwrap())
+                .collect::<Vec<_>>()
+        );
+
+        // the pair knows about itself as well
+        let pair: PairInfo = app
+            .wrap()
+            .query_wasm_smart(&res.contract_addr, &sg_swap::pair::QueryMsg::Pair {})
+            .unwrap();
+        assert_eq!(pair, res);
+        pair_addrs.push(res.contract_addr);
+    }
+    pair_addrs.sort();
+    pair_addrs.dedup();
+    assert_eq!(pair_addrs.len(), 3);
+}
+
+#[test]
+fn deregister_and_recreate_pair() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token2 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+    let asset_infos = vec![
+        AssetInfo::Token(token1.to_string()),
+        AssetInfo::Token(token2.to_string()),
+    ];
+
+    let old_pair = helper
+        .create_pair_with_addr(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+        )
+        .unwrap();
+
+    // only the owner can deregister
+    let err = app
+        .execute_contract(
+            Addr::unchecked("someone"),
+            helper.factory.clone(),
+            &ExecuteMsg::Deregister {
+                asset_infos: asset_infos.clone(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    let res = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::Deregister {
+                asset_infos: asset_infos.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        res.events[1].attributes[1..],
+        [
+            attr("action", "deregister"),
+            attr("pair_contract_addr", old_pair.as_str()),
+        ]
+    );
+
+    // the factory does not know the pair anymore
+    app.wrap()
+        .query_wasm_smart::<PairInfo>(
+            &helper.factory,
+            &QueryMsg::Pair {
+                asset_infos: asset_infos.clone(),
+            },
+        )
+        .unwrap_err();
+    let pairs: PairsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pairs {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(pairs.pairs, vec![]);
+
+    // but the pair contract itself is untouched
+    let old_pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&old_pair, &sg_swap::pair::QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!(old_pair_info.contract_addr, old_pair);
+
+    // a new pair can be created for the same assets
+    let new_pair = helper
+        .create_pair_with_addr(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+        )
+        .unwrap();
+    assert_ne!(new_pair, old_pair);
+}
+
+#[test]
+fn query_pairs_by_asset() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token_names = ["tokenA", "tokenB", "tokenC", "tokenD", "tokenE"];
+    let tokens: Vec<String> = token_names
+        .iter()
+        .map(|name| {
+            instantiate_token(&mut app, helper.cw20_token_code_id, &owner, name, None).to_string()
+        })
+        .collect();
+    let (a, b, c, d, e) = (
+        tokens[0].as_str(),
+        tokens[1].as_str(),
+        tokens[2].as_str(),
+        tokens[3].as_str(),
+        tokens[4].as_str(),
+    );
+
+    let pair_ab = helper
+        .create_pair_with_addr(&mut app, &owner, PairType::Xyk {}, [a, b], None)
+        .unwrap();
+    let pair_ac = helper
+        .create_pair_with_addr(&mut app, &owner, PairType::Xyk {}, [a, c], None)
+        .unwrap();
+    helper
+        .create_pair_with_addr(&mut app, &owner, PairType::Xyk {}, [d, e], None)
+        .unwrap();
+
+    let pairs_by_asset = |app: &App, asset: &str, start_after: Option<String>, limit| {
+        app.wrap()
+            .query_wasm_smart::<PairsResponse>(
+                &helper.factory,
+                &QueryMsg::PairsByAsset {
+                    asset: AssetInfo::Token(asset.to_string()),
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap()
+            .pairs
+            .into_iter()
+            .map(|pair| pair.contract_addr)
+            .collect::<Vec<_>>()
+    };
+
+    // only the two pairs with token A are returned, ordered by address
+    let mut expected = vec![pair_ab.clone(), pair_ac.clone()];
+    expected.sort();
+    assert_eq!(pairs_by_asset(&app, a, None, None), expected);
+    assert_eq!(pairs_by_asset(&app, b, None, None), vec![pair_ab.clone()]);
+
+    // paginated
+    assert_eq!(
+        pairs_by_asset(&app, a, None, Some(1)),
+        vec![expected[0].clone()]
+    );
+    assert_eq!(
+        pairs_by_asset(&app, a, Some(expected[0].to_string()), None),
+        vec![expected[1].clone()]
+    );
+
+    // deregistering removes the pair from the index
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::Deregister {
+            asset_infos: vec![
+                AssetInfo::Token(a.to_string()),
+                AssetInfo::Token(b.to_string()),
+            ],
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(pairs_by_asset(&app, a, None, None), vec![pair_ac]);
+    assert_eq!(pairs_by_asset(&app, b, None, None), Vec::<Addr>::new());
+}
+
+#[test]
+fn blocked_assets_cannot_get_new_pairs() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token2 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+    let token3 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenZ", None);
+
+    let factory = helper.factory.clone();
+    let block_asset = |app: &mut App, sender: &str, token: &Addr, until: Option<u64>| {
+        app.execute_contract(
+            Addr::unchecked(sender),
+            factory.clone(),
+            &ExecuteMsg::BlockAsset {
+                asset: AssetInfo::Token(token.to_string()),
+                until,
+            },
+            &[],
+        )
+    };
+
+    // only the owner can block assets
+    let err = block_asset(&mut app, "someone", &token1, None).unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // blocked indefinitely
+    block_asset(&mut app, owner.as_str(), &token1, None).unwrap();
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!("Asset {token1} is blocked from pair creation")
+    );
+
+    // unblocking allows creating the pair again
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UnblockAsset {
+            asset: AssetInfo::Token(token1.to_string()),
+        },
+        &[],
+    )
+    .unwrap();
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+
+    // blocked until a given time
+    let until = app.block_info().time.plus_seconds(100).seconds();
+    block_asset(&mut app, owner.as_str(), &token3, Some(until)).unwrap();
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token2.as_str(), token3.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!("Asset {token3} is blocked from pair creation")
+    );
+
+    // after the block expired, the pair can be created
+    app.update_block(|block| block.time = block.time.plus_seconds(100));
+    helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token2.as_str(), token3.as_str()],
+            None,
+            None,
+        )
+        .unwrap();
+}
+
+#[test]
+fn pair_key_ignores_asset_order() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token1 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token2 = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+
+    let pair = helper
+        .create_pair_with_addr(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token1.as_str(), token2.as_str()],
+            None,
+        )
+        .unwrap();
+
+    // querying with the assets in reversed order finds the same pair
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(
+            &helper.factory,
+            &QueryMsg::Pair {
+                asset_infos: vec![
+                    AssetInfo::Token(token2.to_string()),
+                    AssetInfo::Token(token1.to_string()),
+                ],
+            },
+        )
+        .unwrap();
+    assert_eq!(pair_info.contract_addr, pair);
+
+    // and creating it again in reversed order is rejected as a duplicate
+    let err = helper
+        .create_pair(
+            &mut app,
+            &owner,
+            PairType::Xyk {},
+            [token2.as_str(), token1.as_str()],
+            None,
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Pair was already created");
+}
+
 #[test]
 fn test_update_pair_fee() {
     let mut app = mock_app();
@@ -408,6 +1007,74 @@ fn test_update_pair_fee() {
     );
 }
 
+#[test]
+fn pairs_are_paginated() {
+    let mut app = mock_app();
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let tokens: Vec<_> = (0..32u8)
+        .map(|i| {
+            let name = format!(
+                "token{}{}",
+                (b'a' + i / 26) as char,
+                (b'a' + i % 26) as char
+            );
+            instantiate_token(&mut app, helper.cw20_token_code_id, &owner, &name, None)
+        })
+        .collect();
+    for token in &tokens[1..] {
+        helper
+            .create_pair(
+                &mut app,
+                &owner,
+                PairType::Xyk {},
+                [tokens[0].as_str(), token.as_str()],
+                None,
+                None,
+            )
+            .unwrap();
+    }
+
+    let query_pairs = |start_after: Option<Vec<AssetInfo>>, limit: Option<u32>| {
+        app.wrap()
+            .query_wasm_smart::<PairsResponse>(
+                &helper.factory,
+                &QueryMsg::Pairs { start_after, limit },
+            )
+            .unwrap()
+            .pairs
+    };
+
+    // default and maximum limit
+    assert_eq!(query_pairs(None, None).len(), 10);
+    assert_eq!(query_pairs(None, Some(100)).len(), 30);
+
+    // walk all pairs in two pages
+    let first_page = query_pairs(None, Some(20));
+    assert_eq!(first_page.len(), 20);
+    let start_after = first_page
+        .last()
+        .unwrap()
+        .asset_infos
+        .iter()
+        .cloned()
+        .map(Into::into)
+        .collect();
+    let second_page = query_pairs(Some(start_after), Some(20));
+    assert_eq!(second_page.len(), 11);
+
+    let mut pairs: Vec<_> = first_page
+        .into_iter()
+        .chain(second_page)
+        .map(|pair| pair.contract_addr)
+        .collect();
+    assert_eq!(pairs.len(), 31);
+    pairs.sort();
+    pairs.dedup();
+    assert_eq!(pairs.len(), 31, "pages should not overlap");
+}
+
 #[test]
 fn test_pair_migration() {
     let mut app = mock_app();
@@ -553,6 +1220,221 @@ fn test_pair_migration() {
     }
 }
 
+fn mock_pair_migrate(deps: DepsMut, _env: Env, _msg: Empty) -> StdResult<Response> {
+    cw2::set_contract_version(deps.storage, "mock-pair", "2.0.0")?;
+    Ok(Response::new())
+}
+
+#[test]
+fn migrate_pairs_to_new_code_id() {
+    let mut app = mock_app();
+
+    let owner = Addr::unchecked("owner");
+    let mut helper = FactoryHelper::init(&mut app, &owner);
+
+    let token_x = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenX", None);
+    let token_y = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenY", None);
+    let token_z = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenZ", None);
+
+    let tokens = [[&token_x, &token_y], [&token_x, &token_z]];
+    let mut pairs = vec![];
+    for [token_a, token_b] in tokens {
+        let pair = helper
+            .create_pair_with_addr(
+                &mut app,
+                &owner,
+                PairType::Xyk {},
+                [token_a.as_str(), token_b.as_str()],
+                None,
+            )
+            .unwrap();
+        // the factory is the admin of the pairs, so it can migrate them
+        let contract_info = app.wrap().query_wasm_contract_info(&pair).unwrap();
+        assert_eq!(contract_info.admin, Some(helper.factory.to_string()));
+        pairs.push(pair);
+    }
+    let asset_infos: Vec<_> = tokens
+        .iter()
+        .map(|[token_a, token_b]| {
+            vec![
+                AssetInfo::Token(token_a.to_string()),
+                AssetInfo::Token(token_b.to_string()),
+            ]
+        })
+        .collect();
+
+    let new_code_id = app.store_code(Box::new(
+        ContractWrapper::new_with_empty(
+            sg_swap_pair::contract::execute,
+            sg_swap_pair::contract::instantiate,
+            sg_swap_pair::contract::query,
+        )
+        .with_reply_empty(sg_swap_pair::contract::reply)
+        .with_migrate_empty(mock_pair_migrate),
+    ));
+    let msg = ExecuteMsg::MigratePairs {
+        pairs: asset_infos.clone(),
+        new_code_id,
+        msg: to_binary(&Empty {}).unwrap(),
+    };
+
+    // only the owner can migrate pairs
+    let err = app
+        .execute_contract(Addr::unchecked("user"), helper.factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    // the number of pairs per message is limited
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            helper.factory.clone(),
+            &ExecuteMsg::MigratePairs {
+                pairs: vec![asset_infos[0].clone(); MAX_PAIR_MIGRATIONS + 1],
+                new_code_id,
+                msg: to_binary(&Empty {}).unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!("Cannot migrate more than {MAX_PAIR_MIGRATIONS} pairs at once")
+    );
+
+    // the code id has to be allowed for the pair type first
+    let err = app
+        .execute_contract(owner.clone(), helper.factory.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        format!(
+            "Code id {new_code_id} is not allowed for the pair type, allow it with allow_migration_code_id first"
+        )
+    );
+    let allow = |pair_type| ExecuteMsg::AllowMigrationCodeId {
+        pair_type,
+        code_id: new_code_id,
+        allowed: true,
+    };
+    let err = app
+        .execute_contract(
+            Addr::unchecked("user"),
+            helper.factory.clone(),
+            &allow(PairType::Xyk {}),
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &allow(PairType::Xyk {}),
+        &[],
+    )
+    .unwrap();
+
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    let old_code_id = config.pair_configs[0].code_id;
+
+    app.execute_contract(owner.clone(), helper.factory.clone(), &msg, &[])
+        .unwrap();
+
+    for pair in &pairs {
+        let contract_info = app.wrap().query_wasm_contract_info(pair).unwrap();
+        assert_eq!(contract_info.code_id, new_code_id);
+        let version = cw2::query_contract_info(&app.wrap(), pair).unwrap();
+        assert_eq!(version.contract, "mock-pair");
+        assert_eq!(version.version, "2.0.0");
+    }
+
+    // new pairs are created with the new code id
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    let xyk_config = config
+        .pair_configs
+        .iter()
+        .find(|pair_config| pair_config.pair_type == PairType::Xyk {})
+        .unwrap();
+    assert_eq!(xyk_config.code_id, new_code_id);
+
+    // weighted pairs with different weights have the same pair type
+    let weighted_type = PairType::Weighted {
+        weights: [5_000, 5_000],
+    };
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &ExecuteMsg::UpdatePairConfig {
+            config: PairConfig {
+                code_id: old_code_id,
+                pair_type: weighted_type.clone(),
+                ..xyk_config.clone()
+            },
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        owner.clone(),
+        helper.factory.clone(),
+        &allow(weighted_type),
+        &[],
+    )
+    .unwrap();
+    let token_w = instantiate_token(&mut app, helper.cw20_token_code_id, &owner, "tokenW", None);
+    let weighted = [
+        ([&token_y, &token_z], [5_000, 5_000]),
+        ([&token_y, &token_w], [8_000, 2_000]),
+    ];
+    let mut asset_infos = vec![];
+    for ([token_a, token_b], weights) in weighted {
+        let pair = helper
+            .create_pair_with_addr(
+                &mut app,
+                &owner,
+                PairType::Weighted { weights },
+                [token_a.as_str(), token_b.as_str()],
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            app.wrap().query_wasm_contract_info(&pair).unwrap().code_id,
+            old_code_id
+        );
+        asset_infos.push(vec![
+            AssetInfo::Token(token_a.to_string()),
+            AssetInfo::Token(token_b.to_string()),
+        ]);
+    }
+    app.execute_contract(
+        owner,
+        helper.factory.clone(),
+        &ExecuteMsg::MigratePairs {
+            pairs: asset_infos,
+            new_code_id,
+            msg: to_binary(&Empty {}).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    let config: ConfigResponse = app
+        .wrap()
+        .query_wasm_smart(&helper.factory, &QueryMsg::Config {})
+        .unwrap();
+    let weighted_config = config
+        .pair_configs
+        .iter()
+        .find(|pair_config| matches!(pair_config.pair_type, PairType::Weighted { .. }))
+        .unwrap();
+    assert_eq!(weighted_config.code_id, new_code_id);
+}
+
 #[test]
 fn check_update_owner() {
     let mut app = mock_app();
@@ -677,6 +1559,8 @@ fn can_migrate_the_placeholder_to_a_factory_properly() {
             protocol_fee_bps: 10,
         },
         is_disabled: false,
+        default_max_spread: None,
+        init_params: None,
     }];
     // Instantiate an instance of the placeholder contract which we will migrate
     let placeholder = app
@@ -698,6 +1582,7 @@ fn can_migrate_the_placeholder_to_a_factory_properly() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(),
         trading_starts: None,
+        fee_recipients: None,
     };
     // Migrate the contract
     app.migrate_contract(
@@ -1,14 +1,14 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
-    StdResult, Uint128, WasmMsg,
+    attr, coins, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128, WasmMsg,
 };
 use cw2::set_contract_version;
 use cw20::Cw20ExecuteMsg;
 
 use cw_placeholder::contract::CONTRACT_NAME as PLACEHOLDER_CONTRACT_NAME;
-use sg_swap::asset::{AssetInfoValidated, AssetValidated};
+use sg_swap::asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated};
 use sg_swap_stake::msg::{
     ExecuteMsg as StakeExecuteMsg, ReceiveDelegationMsg as StakeReceiveDelegationMsg,
 };
@@ -16,7 +16,7 @@ use wynd_curve_utils::ScalableCurve;
 
 use crate::error::ContractError;
 use crate::msg::{AdapterQueryMsg, ExecuteMsg, InstantiateMsg, MigrateMsg};
-use crate::state::{Config, CONFIG};
+use crate::state::{Config, RewardAsset, CONFIG};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:gauge-adapter";
@@ -31,17 +31,41 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let mut rewards_assets = vec![];
+    for asset in msg.rewards_assets {
+        add_reward_asset(
+            &mut rewards_assets,
+            asset.validate(deps.api)?,
+            msg.epoch_length,
+        )?;
+    }
+
     let config = Config {
         factory: deps.api.addr_validate(&msg.factory)?,
         owner: deps.api.addr_validate(&msg.owner)?,
-        rewards_asset: msg.rewards_asset.validate(deps.api)?,
-        distribution_curve: ScalableCurve::linear((0, 100), (msg.epoch_length, 0)),
+        rewards_assets,
     };
     CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new())
 }
 
+/// Adds `asset` to the reward assets, released linearly over `epoch_length` seconds.
+fn add_reward_asset(
+    rewards_assets: &mut Vec<RewardAsset>,
+    asset: AssetValidated,
+    epoch_length: u64,
+) -> Result<(), ContractError> {
+    if rewards_assets.iter().any(|r| r.asset.info == asset.info) {
+        return Err(ContractError::DuplicateRewardAsset(asset.info.to_string()));
+    }
+    rewards_assets.push(RewardAsset {
+        asset,
+        distribution_curve: ScalableCurve::linear((0, 100), (epoch_length, 0)),
+    });
+    Ok(())
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -50,7 +74,19 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
-        ExecuteMsg::UpdateRewards { amount } => execute::update_rewards(deps, info.sender, amount),
+        ExecuteMsg::UpdateRewards { asset, amount } => {
+            execute::update_rewards(deps, info.sender, asset, amount)
+        }
+        ExecuteMsg::AddRewardAsset {
+            asset,
+            epoch_length,
+        } => execute::add_reward_asset(deps, info.sender, asset, epoch_length),
+        ExecuteMsg::RemoveRewardAsset { asset } => {
+            execute::remove_reward_asset(deps, info.sender, asset)
+        }
+        ExecuteMsg::UpdateDistributionCurve { asset, curve } => {
+            execute::update_distribution_curve(deps, info.sender, asset, curve)
+        }
     }
 }
 
@@ -60,6 +96,7 @@ mod execute {
     pub fn update_rewards(
         deps: DepsMut,
         sender: Addr,
+        asset: AssetInfo,
         new_amount: Uint128,
     ) -> Result<Response, ContractError> {
         let mut config = CONFIG.load(deps.storage)?;
@@ -67,14 +104,117 @@ mod execute {
             return Err(ContractError::Unauthorized {});
         }
 
-        config.rewards_asset.amount = new_amount;
+        let asset = asset.validate(deps.api)?;
+        let reward = config
+            .rewards_assets
+            .iter_mut()
+            .find(|r| r.asset.info == asset)
+            .ok_or_else(|| ContractError::UnknownRewardAsset(asset.to_string()))?;
+        reward.asset.amount = new_amount;
         CONFIG.save(deps.storage, &config)?;
 
         Ok(Response::new()
             .add_attribute("update", "rewards")
-            .add_attribute("asset", config.rewards_asset.info.to_string())
+            .add_attribute("asset", asset.to_string())
             .add_attribute("amount", new_amount.to_string()))
     }
+
+    pub fn add_reward_asset(
+        deps: DepsMut,
+        sender: Addr,
+        asset: Asset,
+        epoch_length: u64,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let asset = asset.validate(deps.api)?;
+        let attributes = vec![
+            attr("update", "add_reward_asset"),
+            attr("asset", asset.info.to_string()),
+            attr("amount", asset.amount.to_string()),
+        ];
+        super::add_reward_asset(&mut config.rewards_assets, asset, epoch_length)?;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new().add_attributes(attributes))
+    }
+
+    pub fn remove_reward_asset(
+        deps: DepsMut,
+        sender: Addr,
+        asset: AssetInfo,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let asset = asset.validate(deps.api)?;
+        let count = config.rewards_assets.len();
+        config.rewards_assets.retain(|r| r.asset.info != asset);
+        if config.rewards_assets.len() == count {
+            return Err(ContractError::UnknownRewardAsset(asset.to_string()));
+        }
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("update", "remove_reward_asset")
+            .add_attribute("asset", asset.to_string()))
+    }
+
+    pub fn update_distribution_curve(
+        deps: DepsMut,
+        sender: Addr,
+        asset: AssetInfo,
+        curve: ScalableCurve,
+    ) -> Result<Response, ContractError> {
+        let mut config = CONFIG.load(deps.storage)?;
+        if sender != config.owner {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        validate_distribution_curve(&curve)?;
+
+        let asset = asset.validate(deps.api)?;
+        let reward = config
+            .rewards_assets
+            .iter_mut()
+            .find(|r| r.asset.info == asset)
+            .ok_or_else(|| ContractError::UnknownRewardAsset(asset.to_string()))?;
+        let (old_min, old_max) = reward
+            .distribution_curve
+            .clone()
+            .scale(reward.asset.amount)
+            .range();
+        let (new_min, new_max) = curve.clone().scale(reward.asset.amount).range();
+        reward.distribution_curve = curve;
+        CONFIG.save(deps.storage, &config)?;
+
+        Ok(Response::new()
+            .add_attribute("action", "update_distribution_curve")
+            .add_attribute("asset", asset.to_string())
+            .add_attribute("old_curve_min", old_min.to_string())
+            .add_attribute("old_curve_max", old_max.to_string())
+            .add_attribute("new_curve_min", new_min.to_string())
+            .add_attribute("new_curve_max", new_max.to_string()))
+    }
+
+    /// The amount a curve is scaled to for validation, so rounding does not hide any issues.
+    const VALIDATION_AMOUNT: u128 = 1_000_000_000;
+
+    /// Checks that the curve releases everything by the end and never locks more than the full amount.
+    fn validate_distribution_curve(curve: &ScalableCurve) -> Result<(), ContractError> {
+        let scaled = curve.clone().scale(VALIDATION_AMOUNT.into());
+        scaled.validate_monotonic_decreasing()?;
+        let (min, max) = scaled.range();
+        if min != 0 || max > VALIDATION_AMOUNT {
+            return Err(ContractError::InvalidDistributionCurve {});
+        }
+        Ok(())
+    }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -86,6 +226,9 @@ pub fn query(deps: Deps, env: Env, msg: AdapterQueryMsg) -> StdResult<Binary> {
         AdapterQueryMsg::SampleGaugeMsgs { selected } => {
             to_binary(&query::sample_gauge_msgs(deps, env, selected)?)
         }
+        AdapterQueryMsg::CurrentRewards { at_time } => {
+            to_binary(&query::current_rewards(deps, at_time)?)
+        }
     }
 }
 
@@ -93,7 +236,10 @@ mod query {
     use cosmwasm_std::Decimal;
 
     use crate::{
-        msg::{AllOptionsResponse, CheckOptionResponse, SampleGaugeMsgsResponse},
+        msg::{
+            AllOptionsResponse, CheckOptionResponse, CurrentReward, CurrentRewardsResponse,
+            SampleGaugeMsgsResponse,
+        },
         querier::{query_pairs, query_validate_staking_address},
         state::CONFIG,
     };
@@ -126,19 +272,47 @@ mod query {
         let Config {
             factory: _,
             owner: _,
-            rewards_asset,
-            distribution_curve,
+            rewards_assets,
         } = CONFIG.load(deps.storage)?;
-        Ok(SampleGaugeMsgsResponse {
-            execute: selected
+
+        // every voted-for staking contract gets its share of each reward asset
+        let mut execute = vec![];
+        for (option, weight) in selected {
+            for reward in &rewards_assets {
+                let asset = AssetValidated {
+                    info: reward.asset.info.clone(),
+                    amount: reward.asset.amount * weight,
+                };
+                execute.extend(
+                    create_distribute_msgs(
+                        &env,
+                        asset,
+                        option.clone(),
+                        reward.distribution_curve.clone(),
+                    )
+                    .map_err(|err| StdError::generic_err(err.to_string()))?,
+                );
+            }
+        }
+        Ok(SampleGaugeMsgsResponse { execute })
+    }
+
+    /// The distribution curves start at the beginning of each epoch, when the stake contracts are funded,
+    /// so `at_time` is in seconds since the start of the epoch.
+    pub fn current_rewards(deps: Deps, at_time: Option<u64>) -> StdResult<CurrentRewardsResponse> {
+        let config = CONFIG.load(deps.storage)?;
+        let at_time = at_time.unwrap_or_default();
+        Ok(CurrentRewardsResponse {
+            factory: config.factory,
+            rewards: config
+                .rewards_assets
                 .into_iter()
-                .flat_map(|(option, weight)| {
-                    let rewards_asset = AssetValidated {
-                        info: rewards_asset.info.clone(),
-                        amount: rewards_asset.amount * weight,
-                    };
-                    create_distribute_msgs(&env, rewards_asset, option, distribution_curve.clone())
-                        .unwrap()
+                .map(|reward| CurrentReward {
+                    amount: reward
+                        .distribution_curve
+                        .scale(reward.asset.amount)
+                        .value(at_time),
+                    rewards_asset: reward.asset,
                 })
                 .collect(),
         })
@@ -179,6 +353,10 @@ fn create_distribute_msgs(
             }
             .into()])
         }
+        AssetInfoValidated::Cw1155 { .. } => Err(StdError::generic_err(
+            "Cw1155 tokens cannot be distributed to the staking contract",
+        )
+        .into()),
     }
 }
 
@@ -218,15 +396,16 @@ pub fn migrate(deps: DepsMut, env: Env, msg: MigrateMsg) -> Result<Response, Con
 #[cfg(test)]
 mod tests {
     use cosmwasm_std::{
+        from_binary,
         testing::{mock_dependencies, mock_env, mock_info},
         to_binary, Coin, CosmosMsg, Decimal, WasmMsg,
     };
-    use wynd_curve_utils::Curve;
+    use wynd_curve_utils::{Curve, ScalableCurve};
 
     use super::{execute, instantiate, query};
     use crate::{
         error::ContractError,
-        msg::{ExecuteMsg, InstantiateMsg},
+        msg::{AdapterQueryMsg, CurrentRewardsResponse, ExecuteMsg, InstantiateMsg},
         state::CONFIG,
     };
     use sg_swap::asset::{Asset, AssetInfo};
@@ -240,10 +419,10 @@ mod tests {
         let msg = InstantiateMsg {
             factory: "factory".to_string(),
             owner: "owner".to_string(),
-            rewards_asset: sg_swap::asset::Asset {
+            rewards_assets: vec![sg_swap::asset::Asset {
                 info: sg_swap::asset::AssetInfo::Native("juno".to_string()),
                 amount: amount.into(),
-            },
+            }],
             epoch_length: EPOCH_LENGTH,
         };
         instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
@@ -251,11 +430,29 @@ mod tests {
         // check if the config is stored
         let config = CONFIG.load(deps.as_ref().storage).unwrap();
         assert_eq!(config.factory, "factory");
+        assert_eq!(config.rewards_assets.len(), 1);
         assert_eq!(
-            config.rewards_asset.info,
+            config.rewards_assets[0].asset.info,
             sg_swap::asset::AssetInfoValidated::Native("juno".to_string())
         );
-        assert_eq!(config.rewards_asset.amount.u128(), 1000);
+        assert_eq!(config.rewards_assets[0].asset.amount.u128(), 1000);
+    }
+
+    #[test]
+    fn duplicate_reward_assets_are_rejected() {
+        let mut deps = mock_dependencies();
+        let juno = Asset {
+            info: AssetInfo::Native("juno".to_string()),
+            amount: 1000u128.into(),
+        };
+        let msg = InstantiateMsg {
+            factory: "factory".to_string(),
+            owner: "owner".to_string(),
+            rewards_assets: vec![juno.clone(), juno],
+            epoch_length: EPOCH_LENGTH,
+        };
+        let err = instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap_err();
+        assert_eq!(ContractError::DuplicateRewardAsset("juno".to_string()), err);
     }
 
     #[test]
@@ -270,10 +467,10 @@ mod tests {
             InstantiateMsg {
                 factory: "factory".to_string(),
                 owner: "owner".to_string(),
-                rewards_asset: sg_swap::asset::Asset {
+                rewards_assets: vec![sg_swap::asset::Asset {
                     info: sg_swap::asset::AssetInfo::Native("juno".to_string()),
                     amount: amount.into(),
-                },
+                }],
                 epoch_length: EPOCH_LENGTH,
             },
         )
@@ -330,18 +527,74 @@ mod tests {
         );
     }
 
+    #[test]
+    fn current_rewards() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            factory: "factory".to_string(),
+            owner: "owner".to_string(),
+            rewards_assets: vec![
+                Asset {
+                    info: AssetInfo::Native("juno".to_string()),
+                    amount: 10_000u128.into(),
+                },
+                Asset {
+                    info: AssetInfo::Native("atom".to_string()),
+                    amount: 2_000u128.into(),
+                },
+            ],
+            epoch_length: EPOCH_LENGTH,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
+
+        let amounts = |at_time| {
+            let res = query::current_rewards(deps.as_ref(), at_time).unwrap();
+            assert_eq!(res.factory, "factory");
+            assert_eq!(res.rewards[0].rewards_asset.amount.u128(), 10_000);
+            assert_eq!(res.rewards[1].rewards_asset.amount.u128(), 2_000);
+            res.rewards
+                .into_iter()
+                .map(|r| r.amount.u128())
+                .collect::<Vec<_>>()
+        };
+
+        // the whole amount at the start of the epoch, decreasing linearly to zero at its end
+        assert_eq!(amounts(Some(0)), vec![10_000, 2_000]);
+        assert_eq!(amounts(Some(EPOCH_LENGTH / 4)), vec![7_500, 1_500]);
+        assert_eq!(amounts(Some(EPOCH_LENGTH / 2)), vec![5_000, 1_000]);
+        assert_eq!(amounts(Some(EPOCH_LENGTH)), vec![0, 0]);
+        assert_eq!(amounts(Some(EPOCH_LENGTH * 2)), vec![0, 0]);
+
+        // without a time, the curves are sampled at the start of the epoch, whatever the block time is
+        assert_eq!(amounts(None), vec![10_000, 2_000]);
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(EPOCH_LENGTH / 4);
+        let res: CurrentRewardsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env,
+                AdapterQueryMsg::CurrentRewards { at_time: None },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(res.rewards[0].amount.u128(), 10_000);
+        assert_eq!(res.rewards[1].amount.u128(), 2_000);
+    }
+
     #[test]
     fn update_rewards() {
         let amount = 2000u128;
+        let juno = AssetInfo::Native("juno".to_string());
 
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
             factory: "factory".to_string(),
             owner: "owner".to_string(),
-            rewards_asset: Asset {
-                info: AssetInfo::Native("juno".to_string()),
+            rewards_assets: vec![Asset {
+                info: juno.clone(),
                 amount: 1000u128.into(),
-            },
+            }],
             epoch_length: EPOCH_LENGTH,
         };
         instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
@@ -352,6 +605,7 @@ mod tests {
             mock_env(),
             mock_info("user", &[]),
             ExecuteMsg::UpdateRewards {
+                asset: juno.clone(),
                 amount: amount.into(),
             },
         )
@@ -363,17 +617,32 @@ mod tests {
             mock_env(),
             mock_info("factory", &[]),
             ExecuteMsg::UpdateRewards {
+                asset: juno.clone(),
                 amount: amount.into(),
             },
         )
         .unwrap_err();
         assert_eq!(ContractError::Unauthorized {}, err);
 
+        // only existing reward assets can be updated
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::UpdateRewards {
+                asset: AssetInfo::Native("atom".to_string()),
+                amount: amount.into(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::UnknownRewardAsset("atom".to_string()), err);
+
         execute(
             deps.as_mut(),
             mock_env(),
             mock_info("owner", &[]),
             ExecuteMsg::UpdateRewards {
+                asset: juno,
                 amount: amount.into(),
             },
         )
@@ -382,9 +651,208 @@ mod tests {
         // check if the config is stored
         let config = CONFIG.load(deps.as_ref().storage).unwrap();
         assert_eq!(
-            config.rewards_asset.info,
+            config.rewards_assets[0].asset.info,
             sg_swap::asset::AssetInfoValidated::Native("juno".to_string())
         );
-        assert_eq!(config.rewards_asset.amount.u128(), 2000);
+        assert_eq!(config.rewards_assets[0].asset.amount.u128(), 2000);
+    }
+
+    #[test]
+    fn add_and_remove_reward_assets() {
+        let mut deps = mock_dependencies();
+        let juno = AssetInfo::Native("juno".to_string());
+        let atom = AssetInfo::Native("atom".to_string());
+        let msg = InstantiateMsg {
+            factory: "factory".to_string(),
+            owner: "owner".to_string(),
+            rewards_assets: vec![Asset {
+                info: juno.clone(),
+                amount: 1000u128.into(),
+            }],
+            epoch_length: EPOCH_LENGTH,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
+
+        let add_atom = ExecuteMsg::AddRewardAsset {
+            asset: Asset {
+                info: atom.clone(),
+                amount: 500u128.into(),
+            },
+            epoch_length: EPOCH_LENGTH * 2,
+        };
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            add_atom.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            add_atom.clone(),
+        )
+        .unwrap();
+        let err =
+            execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), add_atom).unwrap_err();
+        assert_eq!(ContractError::DuplicateRewardAsset("atom".to_string()), err);
+
+        // both assets are sent, each with its own curve
+        let res = query::sample_gauge_msgs(
+            deps.as_ref(),
+            mock_env(),
+            vec![("juno1555".to_string(), Decimal::one())],
+        )
+        .unwrap();
+        assert_eq!(
+            res.execute,
+            vec![
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "juno1555".to_string(),
+                    msg: to_binary(&sg_swap_stake::msg::ExecuteMsg::FundDistribution {
+                        curve: Curve::saturating_linear((0, 1000u128), (EPOCH_LENGTH, 0)),
+                    })
+                    .unwrap(),
+                    funds: vec![Coin {
+                        denom: "juno".to_string(),
+                        amount: 1000u128.into(),
+                    }],
+                }),
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "juno1555".to_string(),
+                    msg: to_binary(&sg_swap_stake::msg::ExecuteMsg::FundDistribution {
+                        curve: Curve::saturating_linear((0, 500u128), (EPOCH_LENGTH * 2, 0)),
+                    })
+                    .unwrap(),
+                    funds: vec![Coin {
+                        denom: "atom".to_string(),
+                        amount: 500u128.into(),
+                    }],
+                }),
+            ]
+        );
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::RemoveRewardAsset {
+                asset: juno.clone(),
+            },
+        )
+        .unwrap();
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::RemoveRewardAsset { asset: juno },
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::UnknownRewardAsset("juno".to_string()), err);
+
+        let config = CONFIG.load(deps.as_ref().storage).unwrap();
+        assert_eq!(config.rewards_assets.len(), 1);
+        assert_eq!(
+            config.rewards_assets[0].asset.info,
+            sg_swap::asset::AssetInfoValidated::Native("atom".to_string())
+        );
+    }
+
+    #[test]
+    fn update_distribution_curve() {
+        let mut deps = mock_dependencies();
+        let juno = AssetInfo::Native("juno".to_string());
+        let msg = InstantiateMsg {
+            factory: "factory".to_string(),
+            owner: "owner".to_string(),
+            rewards_assets: vec![Asset {
+                info: juno.clone(),
+                amount: 10_000u128.into(),
+            }],
+            epoch_length: EPOCH_LENGTH,
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("user", &[]), msg).unwrap();
+
+        let update = |curve| ExecuteMsg::UpdateDistributionCurve {
+            asset: juno.clone(),
+            curve,
+        };
+        let slower = ScalableCurve::linear((0, 100), (EPOCH_LENGTH * 2, 0));
+
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("user", &[]),
+            update(slower.clone()),
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::Unauthorized {}, err);
+
+        // the curve has to decrease and release everything
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(ScalableCurve::linear((0, 0), (EPOCH_LENGTH, 100))),
+        )
+        .unwrap_err();
+        assert!(matches!(err, ContractError::Curve(_)), "{err:?}");
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(ScalableCurve::linear((0, 100), (EPOCH_LENGTH, 50))),
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::InvalidDistributionCurve {}, err);
+
+        // only existing reward assets can be updated
+        let err = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::UpdateDistributionCurve {
+                asset: AssetInfo::Native("atom".to_string()),
+                curve: slower.clone(),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(ContractError::UnknownRewardAsset("atom".to_string()), err);
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            update(slower),
+        )
+        .unwrap();
+        let attribute = |key: &str| {
+            res.attributes
+                .iter()
+                .find(|a| a.key == key)
+                .map(|a| a.value.clone())
+                .unwrap()
+        };
+        assert_eq!(attribute("action"), "update_distribution_curve");
+        assert_eq!(attribute("old_curve_min"), "0");
+        assert_eq!(attribute("old_curve_max"), "10000");
+        assert_eq!(attribute("new_curve_min"), "0");
+        assert_eq!(attribute("new_curve_max"), "10000");
+
+        // the rewards are now released over two epochs
+        let amount = |at_time| {
+            query::current_rewards(deps.as_ref(), Some(at_time))
+                .unwrap()
+                .rewards[0]
+                .amount
+                .u128()
+        };
+        assert_eq!(amount(0), 10_000);
+        assert_eq!(amount(EPOCH_LENGTH / 2), 7_500);
+        assert_eq!(amount(EPOCH_LENGTH), 5_000);
+        assert_eq!(amount(EPOCH_LENGTH * 2), 0);
     }
 }
@@ -1,14 +1,27 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
+use wynd_curve_utils::CurveError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Curve(#[from] CurveError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Can only init upgrade from cw-placeholder")]
     NotPlaceholder,
+
+    #[error("{0} is already a reward asset")]
+    DuplicateRewardAsset(String),
+
+    #[error("{0} is not a reward asset")]
+    UnknownRewardAsset(String),
+
+    #[error("The distribution curve has to release the full amount and never exceed it")]
+    InvalidDistributionCurve {},
 }
@@ -1,6 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
-use cosmwasm_std::{CosmosMsg, Decimal, Uint128};
-use sg_swap::asset::Asset;
+use cosmwasm_std::{Addr, CosmosMsg, Decimal, Uint128};
+use sg_swap::asset::{Asset, AssetInfo, AssetValidated};
+use wynd_curve_utils::ScalableCurve;
 
 #[cw_serde]
 pub struct InstantiateMsg {
@@ -8,14 +9,25 @@ pub struct InstantiateMsg {
     pub factory: String,
     /// Owner of the creator (instantiator of the factory)
     pub owner: String,
-    /// The asset to send to the voted-for lp staking contracts every epoch
-    pub rewards_asset: Asset,
+    /// The assets to send to the voted-for lp staking contracts every epoch
+    pub rewards_assets: Vec<Asset>,
     pub epoch_length: u64,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
-    UpdateRewards { amount: Uint128 },
+    /// Changes the amount of a reward asset that is sent every epoch
+    UpdateRewards { asset: AssetInfo, amount: Uint128 },
+    /// Adds a new reward asset that is released over `epoch_length` seconds
+    AddRewardAsset { asset: Asset, epoch_length: u64 },
+    /// Stops sending the given reward asset
+    RemoveRewardAsset { asset: AssetInfo },
+    /// Replaces the curve over which the given reward asset is released every epoch.
+    /// The curve has to decrease monotonically to zero and never exceed the full amount.
+    UpdateDistributionCurve {
+        asset: AssetInfo,
+        curve: ScalableCurve,
+    },
 }
 
 #[cw_serde]
@@ -41,6 +53,13 @@ pub enum AdapterQueryMsg {
         /// sum of all weights should be 1.0 (within rounding error)
         selected: Vec<(String, Decimal)>,
     },
+    /// Returns how much of each reward asset is still to be released at the given time of an epoch
+    #[returns(CurrentRewardsResponse)]
+    CurrentRewards {
+        /// The time in seconds since the start of the epoch to sample the distribution curves at,
+        /// defaults to the start of the epoch
+        at_time: Option<u64>,
+    },
 }
 
 #[cw_serde]
@@ -57,3 +76,18 @@ pub struct CheckOptionResponse {
 pub struct SampleGaugeMsgsResponse {
     pub execute: Vec<CosmosMsg>,
 }
+
+#[cw_serde]
+pub struct CurrentRewardsResponse {
+    /// Address of the factory contract
+    pub factory: Addr,
+    pub rewards: Vec<CurrentReward>,
+}
+
+#[cw_serde]
+pub struct CurrentReward {
+    /// The configured reward asset and the amount of it sent every epoch
+    pub rewards_asset: AssetValidated,
+    /// The amount the distribution curve yields at the requested time
+    pub amount: Uint128,
+}
@@ -1,4 +1,4 @@
-use crate::multitest::suite::Suite;
+use crate::multitest::suite::{StakingContract, Suite};
 use cosmwasm_std::{assert_approx_eq, coin, Addr, Decimal};
 use sg_swap::{asset::AssetInfo, factory::DefaultStakeConfig};
 
@@ -147,6 +147,108 @@ fn native_rewards_work() {
     );
 }
 
+#[test]
+fn multiple_native_rewards_work() {
+    let mut suite = SuiteBuilder::new()
+        .with_funds("owner", &[coin(100_000, "juno"), coin(50_000, "atom")])
+        .with_stake_config(DefaultStakeConfig {
+            staking_code_id: 0,
+            tokens_per_power: 1000u128.into(),
+            min_bond: 1000u128.into(),
+            unbonding_periods: vec![SECONDS_PER_DAY * 7],
+            max_distributions: 5,
+        })
+        .with_native_reward(100_000, "juno")
+        .with_native_reward(50_000, "atom")
+        .build();
+
+    let juno = AssetInfo::Native("juno".to_string());
+    let atom = AssetInfo::Native("atom".to_string());
+    let asdf = AssetInfo::Native("asdf".to_string());
+    let test = AssetInfo::Native("test".to_string());
+    // create pairs to reward
+    let (pair1_staking, pair1_lpt) = suite
+        .create_pair_staking(asdf.clone(), test.clone())
+        .unwrap();
+    let (pair2_staking, pair2_lpt) = suite
+        .create_pair_staking(juno.clone(), asdf.clone())
+        .unwrap();
+    let pairs = [
+        (&pair1_staking, pair1_lpt, vec![asdf.clone(), test]),
+        (&pair2_staking, pair2_lpt, vec![juno.clone(), asdf]),
+    ];
+
+    for (staking, lpt, asset_infos) in pairs {
+        // stake all lp tokens
+        staking
+            .stake(&mut suite.app, "whale", 999_000, SECONDS_PER_DAY * 7, lpt)
+            .unwrap();
+
+        // create distribution flows for both reward assets
+        for reward in [&juno, &atom] {
+            suite
+                .create_distribution_flow(
+                    "owner",
+                    asset_infos.clone(),
+                    reward.clone(),
+                    vec![(SECONDS_PER_DAY * 7, Decimal::one())],
+                )
+                .unwrap();
+        }
+    }
+
+    // sample messages, one per reward asset and staking contract
+    let messages = suite.sample_gauge_msgs(vec![
+        (pair1_staking.0.to_string(), Decimal::percent(60)),
+        (pair2_staking.0.to_string(), Decimal::percent(40)),
+    ]);
+    assert_eq!(messages.len(), 4);
+    suite
+        .app
+        .execute_multi(Addr::unchecked("owner"), messages)
+        .unwrap();
+
+    // move past the whole epoch
+    suite.next_block(suite.epoch_length);
+    pair1_staking
+        .distribute_rewards(&mut suite.app, "owner")
+        .unwrap();
+    pair2_staking
+        .distribute_rewards(&mut suite.app, "owner")
+        .unwrap();
+
+    // both assets are distributed proportionally to the votes
+    let reward_of = |staking: &StakingContract, info: &AssetInfo| {
+        staking
+            .query_withdrawable_rewards(&suite.app, "whale")
+            .unwrap()
+            .into_iter()
+            .find(|r| r.info.to_string() == info.to_string())
+            .unwrap()
+            .amount
+    };
+    assert_approx_eq!(
+        reward_of(&pair1_staking, &juno),
+        60_000u128.into(),
+        "0.0001"
+    );
+    assert_approx_eq!(
+        reward_of(&pair1_staking, &atom),
+        30_000u128.into(),
+        "0.0001"
+    );
+    assert_approx_eq!(
+        reward_of(&pair2_staking, &juno),
+        40_000u128.into(),
+        "0.0001"
+    );
+    assert_approx_eq!(
+        reward_of(&pair2_staking, &atom),
+        20_000u128.into(),
+        "0.0001"
+    );
+}
+
 #[test]
 fn cw20_rewards_work_direct() {
     let suite = SuiteBuilder::new()
@@ -185,7 +287,7 @@ fn cw20_rewards_work_via_migration() {
 
 fn cw20_rewards_work(mut suite: Suite) {
     // FIXME: how does this work? AssetInfo::to_string() ?? not a cleaner way to unwrap the enum?
-    let reward_contract = Addr::unchecked(suite.reward.info.to_string());
+    let reward_contract = Addr::unchecked(suite.rewards[0].info.to_string());
 
     // mint reward token to distribute later for owner
     suite
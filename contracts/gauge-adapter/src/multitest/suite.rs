@@ -96,7 +96,7 @@ fn store_placeholder_code(app: &mut App) -> u64 {
 pub struct SuiteBuilder {
     funds: Vec<(Addr, Vec<Coin>)>,
     stake_config: DefaultStakeConfig,
-    reward: Asset,
+    rewards: Vec<Asset>,
     via_placeholder: bool,
 }
 
@@ -111,10 +111,7 @@ impl SuiteBuilder {
                 unbonding_periods: vec![],
                 max_distributions: 6,
             },
-            reward: Asset {
-                amount: Uint128::zero(),
-                info: AssetInfo::Native("juno".to_string()),
-            },
+            rewards: vec![],
             via_placeholder: false,
         }
     }
@@ -135,18 +132,18 @@ impl SuiteBuilder {
     }
 
     pub fn with_native_reward(mut self, amount: u128, denom: &str) -> Self {
-        self.reward = Asset {
+        self.rewards.push(Asset {
             amount: amount.into(),
             info: AssetInfo::Native(denom.to_string()),
-        };
+        });
         self
     }
 
     pub fn with_cw20_reward(mut self, amount: u128) -> Self {
-        self.reward = Asset {
+        self.rewards.push(Asset {
             amount: amount.into(),
             info: AssetInfo::Token(String::new()), // will be filled in when we [`build`]
-        };
+        });
         self
     }
 
@@ -178,6 +175,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: 0,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                         PairConfig {
                             code_id: pair_code_id,
@@ -187,6 +186,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: 0,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                     ],
                     token_code_id: cw20_code_id,
@@ -198,6 +199,7 @@ impl SuiteBuilder {
                         ..self.stake_config
                     },
                     trading_starts: None,
+                    fee_recipients: None,
                 },
                 &[],
                 "Stargaze Factory",
@@ -205,8 +207,12 @@ impl SuiteBuilder {
             )
             .unwrap();
 
-        // special handling for cw20 reward
-        if let AssetInfo::Token(_) = self.reward.info {
+        // special handling for cw20 rewards
+        for reward in self
+            .rewards
+            .iter_mut()
+            .filter(|r| matches!(r.info, AssetInfo::Token(_)))
+        {
             let cw20 = app
                 .instantiate_contract(
                     cw20_code_id,
@@ -228,13 +234,13 @@ impl SuiteBuilder {
                 )
                 .unwrap();
 
-            self.reward.info = AssetInfo::Token(cw20.to_string());
+            reward.info = AssetInfo::Token(cw20.to_string());
         }
 
         let adapter_init_msg = crate::msg::InstantiateMsg {
             factory: factory.to_string(),
             owner: owner.to_string(),
-            rewards_asset: self.reward.clone(),
+            rewards_assets: self.rewards.clone(),
             epoch_length,
         };
         let adapter_label = "Gauge Adapter";
@@ -286,7 +292,7 @@ impl SuiteBuilder {
             factory,
             gauge_adapter,
             cw20_code_id,
-            reward: self.reward,
+            rewards: self.rewards,
             epoch_length,
         }
     }
@@ -298,7 +304,7 @@ pub struct Suite {
     pub factory: Addr,
     pub gauge_adapter: Addr,
     cw20_code_id: u64,
-    pub reward: Asset,
+    pub rewards: Vec<Asset>,
     pub epoch_length: u64,
 }
 
@@ -355,6 +361,11 @@ impl Suite {
                 staking_config: PartialStakeConfig::default(),
                 init_params: None,
                 total_fee_bps: None,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
             },
             &[],
         )?;
@@ -428,6 +439,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
         match second_asset.0.clone() {
             AssetInfo::Token(addr) => {
@@ -451,6 +465,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
 
         pair.provide_liquidity(
@@ -598,6 +615,7 @@ impl PairContract {
                 assets: assets.to_vec(),
                 slippage_tolerance: None,
                 receiver: None,
+                min_lp_amount: None,
             },
             send_funds,
         )
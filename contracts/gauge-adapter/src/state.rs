@@ -10,8 +10,15 @@ pub struct Config {
     pub factory: Addr,
     /// Owner of the creator (instantiator of the factory)
     pub owner: Addr,
-    /// The asset to send to the voted-for lp staking contracts every epoch
-    pub rewards_asset: AssetValidated,
+    /// The assets to send to the voted-for lp staking contracts every epoch
+    pub rewards_assets: Vec<RewardAsset>,
+}
+
+#[cw_serde]
+pub struct RewardAsset {
+    /// The asset and the amount of it to send every epoch
+    pub asset: AssetValidated,
+    /// How the rewards are released over the epoch
     pub distribution_curve: ScalableCurve,
 }
 
@@ -7,13 +7,15 @@ use cosmwasm_std::{
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
-use sg_swap::asset::{addr_opt_validate, Asset, AssetInfo, AssetInfoExt};
+use sg_swap::asset::{addr_opt_validate, Asset, AssetInfo, AssetInfoExt, AssetInfoValidated};
 use sg_swap::multi_hop::{
-    ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
-    SimulateSwapOperationsResponse, SwapOperation, MAX_SWAP_OPERATIONS,
+    ConfigResponse, Cw20HookMsg, ExecuteMsg, FindBestRouteResponse, InstantiateMsg, QueryMsg,
+    SimulateSwapOperationsResponse, SwapOperation, DEFAULT_MAX_HOPS, MAX_SWAP_OPERATIONS,
 };
-use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse};
-use sg_swap::querier::{query_balance, query_pair_info, query_token_balance};
+use sg_swap::pair::{
+    swap_attributes, ExecuteMsg as PairExecuteMsg, QueryMsg as PairQueryMsg, SimulationResponse,
+};
+use sg_swap::querier::{query_pair_info, query_pairs_info, simulate};
 
 use crate::error::ContractError;
 use crate::state::{Config, CONFIG};
@@ -31,10 +33,16 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
+    let max_hops = msg.max_hops.unwrap_or(DEFAULT_MAX_HOPS);
+    if max_hops == 0 || max_hops as usize > MAX_SWAP_OPERATIONS {
+        return Err(ContractError::InvalidMaxHops {});
+    }
+
     CONFIG.save(
         deps.storage,
         &Config {
             sg_swap_factory: deps.api.addr_validate(&msg.sg_swap_factory)?,
+            max_hops,
         },
     )?;
 
@@ -57,16 +65,21 @@ pub fn execute(
             max_spread,
             referral_address,
             referral_commission,
+            deadline,
+            receive_msg,
         } => execute::swap_operations(
             deps,
             env,
             info.sender,
+            sent_offer_amount(&info.funds, &operations),
             operations,
             minimum_receive,
             receiver,
             max_spread,
             referral_address,
             referral_commission,
+            deadline,
+            receive_msg,
         ),
         ExecuteMsg::ExecuteSwapOperation {
             operation,
@@ -98,6 +111,22 @@ pub fn execute(
             minimum_receive,
             deps.api.addr_validate(&receiver)?,
         ),
+        ExecuteMsg::SendWithReceiveMsg {
+            asset_info,
+            prev_balance,
+            receiver,
+            msg,
+            minimum_receive,
+        } => execute::send_with_receive_msg(
+            deps.as_ref(),
+            env,
+            info,
+            asset_info,
+            prev_balance,
+            receiver,
+            msg,
+            minimum_receive,
+        ),
     }
 }
 
@@ -115,16 +144,21 @@ pub fn receive_cw20(
             max_spread,
             referral_address,
             referral_commission,
+            deadline,
+            receive_msg,
         } => execute::swap_operations(
             deps,
             env,
             sender,
+            cw20_msg.amount,
             operations,
             minimum_receive,
             receiver,
             max_spread,
             referral_address,
             referral_commission,
+            deadline,
+            receive_msg,
         ),
     }
 }
@@ -148,6 +182,7 @@ mod execute {
             return Err(ContractError::Unauthorized {});
         }
 
+        let mut response = Response::new();
         let message = match operation {
             SwapOperation::StargazeSwap {
                 offer_asset_info,
@@ -160,19 +195,42 @@ mod execute {
                     &[offer_asset_info.clone(), ask_asset_info.clone()],
                 )?;
 
-                let amount = match &offer_asset_info {
-                    AssetInfo::Native(denom) => {
-                        query_balance(&deps.querier, env.contract.address, denom)?
-                    }
-                    AssetInfo::Token(contract_addr) => {
-                        query_token_balance(&deps.querier, contract_addr, env.contract.address)?
-                    }
-                };
+                let amount = offer_asset_info.query_pool(&deps.querier, env.contract.address)?;
                 let offer_asset = Asset {
                     info: offer_asset_info,
                     amount,
                 };
 
+                // The pair swaps right after this, so its simulation matches what the hop does.
+                // This includes the referral commission, which the pair deducts from every hop.
+                let simulation: SimulationResponse = deps.querier.query_wasm_smart(
+                    &pair_info.contract_addr,
+                    &PairQueryMsg::Simulation {
+                        offer_asset: offer_asset.clone(),
+                        ask_asset_info: Some(ask_asset_info.clone()),
+                        referral: referral_address.is_some(),
+                        referral_commission,
+                    },
+                )?;
+                let offer_info = offer_asset.info.validate(deps.api)?;
+                if referral_address.is_some() {
+                    response = response.add_attribute(
+                        "referral_commission",
+                        offer_info
+                            .with_balance(simulation.referral_amount)
+                            .to_string(),
+                    );
+                }
+                response = response.add_attributes(swap_attributes(
+                    &offer_info,
+                    &ask_asset_info.validate(deps.api)?,
+                    amount - simulation.referral_amount,
+                    simulation.return_amount,
+                    simulation.spread_amount,
+                    simulation.commission_amount,
+                    simulation.referral_amount,
+                ));
+
                 asset_into_swap_msg(
                     pair_info.contract_addr.to_string(),
                     offer_asset,
@@ -186,7 +244,7 @@ mod execute {
             }
         };
 
-        Ok(Response::new().add_message(message))
+        Ok(response.add_message(message))
     }
 
     #[allow(clippy::too_many_arguments)]
@@ -243,6 +301,9 @@ mod execute {
                     })?,
                 })?,
             })),
+            AssetInfo::Cw1155 { .. } => {
+                Err(StdError::generic_err("Cw1155 tokens cannot be swapped"))
+            }
         }
     }
 
@@ -251,25 +312,41 @@ mod execute {
         deps: DepsMut,
         env: Env,
         sender: Addr,
+        offer_amount: Uint128,
         operations: Vec<SwapOperation>,
         minimum_receive: Option<Uint128>,
         receiver: Option<String>,
         max_spread: Option<Decimal>,
         referral_address: Option<String>,
         referral_commission: Option<Decimal>,
+        deadline: Option<u64>,
+        receive_msg: Option<Binary>,
     ) -> Result<Response, ContractError> {
+        if let Some(deadline) = deadline {
+            if env.block.time.seconds() > deadline {
+                return Err(ContractError::DeadlineExpired {});
+            }
+        }
+
         if operations.is_empty() {
             return Err(ContractError::MustProvideOperations {});
         }
 
         let operations_len = operations.len();
-        if operations_len > MAX_SWAP_OPERATIONS {
-            return Err(ContractError::SwapLimitExceeded {});
+        let config = CONFIG.load(deps.storage)?;
+        if operations_len > config.max_hops as usize {
+            return Err(ContractError::TooManyHops {
+                max: config.max_hops,
+            });
         }
 
         // Assert the operations are properly set
         assert_operations(deps.api, &operations)?;
 
+        if offer_amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+
         let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or(sender);
 
         let target_asset_info = operations
@@ -277,7 +354,13 @@ mod execute {
             .unwrap()
             .get_target_asset_info()
             .validate(deps.api)?;
+        let first_offer_asset_info = operations[0].get_offer_asset_info().validate(deps.api)?;
+        if receive_msg.is_some() && !matches!(target_asset_info, AssetInfoValidated::Token(_)) {
+            return Err(ContractError::ReceiveMsgRequiresCw20 {});
+        }
 
+        // with a receive message, the final asset is sent on by this contract
+        let last_receiver = receive_msg.is_none().then(|| receiver.to_string());
         let mut messages = operations
             .into_iter()
             .enumerate()
@@ -288,29 +371,41 @@ mod execute {
                     msg: to_binary(&ExecuteMsg::ExecuteSwapOperation {
                         operation: op,
                         receiver: if operation_index == operations_len - 1 {
-                            Some(receiver.to_string())
+                            last_receiver.clone()
                         } else {
                             None
                         },
                         max_spread,
                         single: operations_len == 1,
-                        referral_address: if operation_index == 0 {
-                            referral_address.clone()
-                        } else {
-                            None
-                        },
-                        referral_commission: if operation_index == 0 {
-                            referral_commission
-                        } else {
-                            None
-                        },
+                        referral_address: referral_address.clone(),
+                        referral_commission,
                     })?,
                 }))
             })
             .collect::<StdResult<Vec<CosmosMsg>>>()?;
 
-        // Execute minimum amount assertion
-        if let Some(minimum_receive) = minimum_receive {
+        if let Some(msg) = receive_msg {
+            // Only the tokens returned by the swap are sent on, not anything else this contract holds.
+            // Tokens of a cw20 swap are already here if the route returns to the offered token.
+            let mut prev_balance =
+                target_asset_info.query_balance(&deps.querier, &env.contract.address)?;
+            if first_offer_asset_info == target_asset_info {
+                prev_balance = prev_balance.checked_sub(offer_amount)?;
+            }
+            // The minimum amount is checked when sending the final asset
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::SendWithReceiveMsg {
+                    asset_info: target_asset_info.into(),
+                    prev_balance,
+                    receiver: receiver.to_string(),
+                    msg,
+                    minimum_receive,
+                })?,
+            }));
+        } else if let Some(minimum_receive) = minimum_receive {
+            // Execute minimum amount assertion
             let receiver_balance = target_asset_info.query_balance(&deps.querier, &receiver)?;
             messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
                 contract_addr: env.contract.address.to_string(),
@@ -327,6 +422,49 @@ mod execute {
         Ok(Response::new().add_messages(messages))
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn send_with_receive_msg(
+        deps: Deps,
+        env: Env,
+        info: MessageInfo,
+        asset_info: AssetInfo,
+        prev_balance: Uint128,
+        receiver: String,
+        msg: Binary,
+        minimum_receive: Option<Uint128>,
+    ) -> Result<Response, ContractError> {
+        if env.contract.address != info.sender {
+            return Err(ContractError::Unauthorized {});
+        }
+
+        let asset_info = asset_info.validate(deps.api)?;
+        let token = match &asset_info {
+            AssetInfoValidated::Token(token) => token.to_string(),
+            _ => return Err(ContractError::ReceiveMsgRequiresCw20 {}),
+        };
+        let amount = asset_info
+            .query_balance(&deps.querier, &env.contract.address)?
+            .checked_sub(prev_balance)?;
+        if let Some(minimum_receive) = minimum_receive {
+            if amount < minimum_receive {
+                return Err(ContractError::MinimumReceiveNotMet {
+                    received: amount,
+                    minimum: minimum_receive,
+                });
+            }
+        }
+
+        Ok(Response::new().add_message(WasmMsg::Execute {
+            contract_addr: token,
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: receiver,
+                amount,
+                msg,
+            })?,
+        }))
+    }
+
     pub fn assert_minimum_receive(
         deps: Deps,
         asset_info: AssetInfo,
@@ -339,9 +477,9 @@ mod execute {
         let swap_amount = receiver_balance.checked_sub(prev_balance)?;
 
         if swap_amount < minimum_receive {
-            Err(ContractError::AssertionMinimumReceive {
-                receive: minimum_receive,
-                amount: swap_amount,
+            Err(ContractError::MinimumReceiveNotMet {
+                received: swap_amount,
+                minimum: minimum_receive,
             })
         } else {
             Ok(Response::default())
@@ -377,11 +515,33 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> Result<Binary, ContractErr
             referral_commission,
             operations,
         )?)?),
+        QueryMsg::FindBestRoute {
+            offer_asset,
+            ask_asset,
+            offer_amount,
+            max_hops,
+        } => Ok(to_binary(&query::find_best_route(
+            deps,
+            offer_asset,
+            ask_asset,
+            offer_amount,
+            max_hops,
+        )?)?),
+        QueryMsg::SimulateDirectSwap {
+            offer,
+            ask,
+            offer_amount,
+        } => Ok(to_binary(&query::simulate_direct_swap(
+            deps,
+            offer,
+            ask,
+            offer_amount,
+        )?)?),
     }
 }
 
 mod query {
-    use sg_swap::pair::ReverseSimulationResponse;
+    use sg_swap::pair::{PairInfo, ReverseSimulationResponse};
 
     use super::*;
 
@@ -390,6 +550,7 @@ mod query {
         let state = CONFIG.load(deps.storage)?;
         let resp = ConfigResponse {
             sg_swap_factory: state.sg_swap_factory.into_string(),
+            max_hops: state.max_hops,
         };
 
         Ok(resp)
@@ -417,19 +578,25 @@ mod query {
             return Err(ContractError::MustProvideOperations {});
         }
 
-        if operations_len > MAX_SWAP_OPERATIONS {
-            return Err(ContractError::SwapLimitExceeded {});
+        if operations_len > config.max_hops as usize {
+            return Err(ContractError::TooManyHops {
+                max: config.max_hops,
+            });
         }
 
         assert_operations(deps.api, &operations)?;
 
+        if offer_amount.is_zero() {
+            return Err(ContractError::ZeroAmount {});
+        }
+
         let mut offer_amount = offer_amount;
         let mut spread_amounts = Vec::with_capacity(operations_len);
         let mut commission_amounts = Vec::with_capacity(operations_len);
-        let mut referral_amount = None;
+        let mut referral_amounts = Vec::with_capacity(operations_len);
         // the ratio of swap result to ideal swap result (= 1 - spread percentage)
         let mut percent_of_ideal = Decimal::one();
-        for (idx, operation) in operations.into_iter().enumerate() {
+        for operation in operations {
             match operation {
                 SwapOperation::StargazeSwap {
                     offer_asset_info,
@@ -449,8 +616,8 @@ mod query {
                                 amount: offer_amount,
                             },
                             ask_asset_info: Some(ask_asset_info.clone()),
-                            referral: if idx == 0 { referral } else { false },
-                            referral_commission: if idx == 0 { referral_commission } else { None },
+                            referral,
+                            referral_commission,
                         },
                     )?;
                     offer_amount = res.return_amount;
@@ -469,11 +636,8 @@ mod query {
                     let ask_asset_info = ask_asset_info.validate(deps.api)?;
                     spread_amounts.push(ask_asset_info.with_balance(res.spread_amount));
                     commission_amounts.push(ask_asset_info.with_balance(res.commission_amount));
-                    if idx == 0 {
-                        // only first operation can have a referral commission
-                        let offer_asset_info = offer_asset_info.validate(deps.api)?;
-                        referral_amount = Some(offer_asset_info.with_balance(res.referral_amount));
-                    }
+                    let offer_asset_info = offer_asset_info.validate(deps.api)?;
+                    referral_amounts.push(offer_asset_info.with_balance(res.referral_amount));
                 }
             }
         }
@@ -483,8 +647,9 @@ mod query {
             spread: Decimal::one() - percent_of_ideal,
             spread_amounts,
             commission_amounts,
-            referral_amount: referral_amount
-                .expect("referral_amount must be set for first operation"),
+            // the first operation was simulated first
+            referral_amount: referral_amounts[0].clone(),
+            referral_amounts,
         })
     }
 
@@ -510,8 +675,10 @@ mod query {
             return Err(ContractError::MustProvideOperations {});
         }
 
-        if operations_len > MAX_SWAP_OPERATIONS {
-            return Err(ContractError::SwapLimitExceeded {});
+        if operations_len > config.max_hops as usize {
+            return Err(ContractError::TooManyHops {
+                max: config.max_hops,
+            });
         }
 
         assert_operations(deps.api, &operations)?;
@@ -519,10 +686,10 @@ mod query {
         let mut ask_amount = ask_amount;
         let mut spread_amounts = Vec::with_capacity(operations_len);
         let mut commission_amounts = Vec::with_capacity(operations_len);
-        let mut referral_amount = None;
+        let mut referral_amounts = Vec::with_capacity(operations_len);
         // the ratio of swap result to ideal swap result (= 1 - spread percentage)
         let mut percent_of_ideal = Decimal::one();
-        for (idx, operation) in operations.into_iter().enumerate().rev() {
+        for operation in operations.into_iter().rev() {
             match operation {
                 SwapOperation::StargazeSwap {
                     offer_asset_info,
@@ -542,8 +709,8 @@ mod query {
                                 info: ask_asset_info.clone(),
                                 amount: ask_amount,
                             },
-                            referral: if idx == 0 { referral } else { false },
-                            referral_commission: if idx == 0 { referral_commission } else { None },
+                            referral,
+                            referral_commission,
                         },
                     )?;
                     // to calculate the percentage of ideal amount for one operation,
@@ -563,11 +730,8 @@ mod query {
                     let ask_asset_info = ask_asset_info.validate(deps.api)?;
                     spread_amounts.push(ask_asset_info.with_balance(res.spread_amount));
                     commission_amounts.push(ask_asset_info.with_balance(res.commission_amount));
-                    if idx == 0 {
-                        // only first operation can have a referral commission
-                        let offer_asset_info = offer_asset_info.validate(deps.api)?;
-                        referral_amount = Some(offer_asset_info.with_balance(res.referral_amount));
-                    }
+                    let offer_asset_info = offer_asset_info.validate(deps.api)?;
+                    referral_amounts.push(offer_asset_info.with_balance(res.referral_amount));
                 }
             }
         }
@@ -577,10 +741,202 @@ mod query {
             spread: Decimal::one() - percent_of_ideal,
             spread_amounts,
             commission_amounts,
-            referral_amount: referral_amount
-                .expect("referral_amount must be set for first operation"),
+            // the first operation was simulated last
+            referral_amount: referral_amounts[operations_len - 1].clone(),
+            referral_amounts,
+        })
+    }
+
+    /// Returns the result of swapping the native token `offer` for the native token `ask`
+    /// through their pair, using a [`SimulateSwapOperationsResponse`] object.
+    ///
+    /// Returns [`ContractError::NoDirectPair`] if the factory has no pair of the two tokens.
+    pub fn simulate_direct_swap(
+        deps: Deps,
+        offer: String,
+        ask: String,
+        offer_amount: Uint128,
+    ) -> Result<SimulateSwapOperationsResponse, ContractError> {
+        if offer == ask {
+            return Err(ContractError::SameAssetSwap {});
+        }
+
+        let config = CONFIG.load(deps.storage)?;
+        let offer_asset_info = AssetInfo::Native(offer);
+        let ask_asset_info = AssetInfo::Native(ask);
+        query_pair_info(
+            &deps.querier,
+            config.sg_swap_factory,
+            &[offer_asset_info.clone(), ask_asset_info.clone()],
+        )
+        .map_err(|_| ContractError::NoDirectPair {
+            offer: offer_asset_info.to_string(),
+            ask: ask_asset_info.to_string(),
+        })?;
+
+        simulate_swap_operations(
+            deps,
+            offer_amount,
+            false,
+            None,
+            vec![SwapOperation::StargazeSwap {
+                offer_asset_info,
+                ask_asset_info,
+            }],
+        )
+    }
+
+    /// The number of pairs loaded from the factory per query
+    const PAIRS_PAGE_SIZE: u32 = 30;
+
+    /// Returns the route from `offer_asset` to `ask_asset` that returns the most tokens for
+    /// `offer_amount`, using a [`FindBestRouteResponse`] object.
+    ///
+    /// All routes of the factory's two-asset pairs with at most `max_hops` swaps are simulated,
+    /// without visiting an asset twice.
+    ///
+    /// * **max_hops** defaults to the configured maximum number of hops and cannot exceed it.
+    pub fn find_best_route(
+        deps: Deps,
+        offer_asset: AssetInfo,
+        ask_asset: AssetInfo,
+        offer_amount: Uint128,
+        max_hops: Option<u32>,
+    ) -> Result<FindBestRouteResponse, ContractError> {
+        let config = CONFIG.load(deps.storage)?;
+        let max_hops = max_hops.unwrap_or(config.max_hops);
+        if max_hops > config.max_hops {
+            return Err(ContractError::TooManyHops {
+                max: config.max_hops,
+            });
+        }
+
+        let offer_asset = offer_asset.validate(deps.api)?;
+        let ask_asset = ask_asset.validate(deps.api)?;
+        if offer_asset == ask_asset {
+            return Err(ContractError::SameAssetSwap {});
+        }
+
+        // load all pairs that can be used in a swap operation
+        let mut pairs = vec![];
+        let mut start_after = None;
+        loop {
+            let page = query_pairs_info(
+                &deps.querier,
+                &config.sg_swap_factory,
+                start_after,
+                Some(PAIRS_PAGE_SIZE),
+            )?
+            .pairs;
+            start_after = page
+                .last()
+                .map(|pair| pair.asset_infos.iter().cloned().map(Into::into).collect());
+            let page_len = page.len();
+            pairs.extend(page.into_iter().filter(|pair| pair.asset_infos.len() == 2));
+            if page_len < PAIRS_PAGE_SIZE as usize {
+                break;
+            }
+        }
+
+        let mut best = None;
+        search_routes(
+            deps,
+            &pairs,
+            &ask_asset,
+            offer_amount,
+            max_hops,
+            &mut vec![offer_asset],
+            &mut best,
+        )?;
+        let (amount, route) = best.ok_or(ContractError::NoRouteFound {})?;
+
+        Ok(FindBestRouteResponse {
+            operations: route
+                .windows(2)
+                .map(|assets| SwapOperation::StargazeSwap {
+                    offer_asset_info: assets[0].clone().into(),
+                    ask_asset_info: assets[1].clone().into(),
+                })
+                .collect(),
+            amount,
         })
     }
+
+    /// Depth-first search for the route to `ask_asset` with the highest return amount.
+    /// `route` contains the assets visited so far, starting with the offer asset.
+    /// Swaps that cannot be simulated (e.g. because of an empty pool) are skipped.
+    /// Fails if `route` is empty.
+    fn search_routes(
+        deps: Deps,
+        pairs: &[PairInfo],
+        ask_asset: &AssetInfoValidated,
+        amount: Uint128,
+        hops_left: u32,
+        route: &mut Vec<AssetInfoValidated>,
+        best: &mut Option<(Uint128, Vec<AssetInfoValidated>)>,
+    ) -> Result<(), ContractError> {
+        let current = route
+            .last()
+            .cloned()
+            .ok_or(ContractError::NoRouteFound {})?;
+        if &current == ask_asset {
+            if best
+                .as_ref()
+                .map_or(true, |(best_amount, _)| amount > *best_amount)
+            {
+                *best = Some((amount, route.clone()));
+            }
+            return Ok(());
+        }
+        if hops_left == 0 {
+            return Ok(());
+        }
+
+        for pair in pairs {
+            if !pair.asset_infos.contains(&current) {
+                continue;
+            }
+            for next in &pair.asset_infos {
+                if route.contains(next) {
+                    continue;
+                }
+                let return_amount = match simulate(
+                    &deps.querier,
+                    &pair.contract_addr,
+                    &current.with_balance(amount).into(),
+                ) {
+                    Ok(res) if !res.return_amount.is_zero() => res.return_amount,
+                    _ => continue,
+                };
+
+                route.push(next.clone());
+                search_routes(
+                    deps,
+                    pairs,
+                    ask_asset,
+                    return_amount,
+                    hops_left - 1,
+                    route,
+                    best,
+                )?;
+                route.pop();
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns the amount of the first operation's offer asset that was sent along with the message.
+/// Cw20 tokens cannot be sent along, they have to be sent with a [`Cw20HookMsg`] instead.
+fn sent_offer_amount(funds: &[Coin], operations: &[SwapOperation]) -> Uint128 {
+    match operations.first().map(SwapOperation::get_offer_asset_info) {
+        Some(AssetInfo::Native(denom)) => funds
+            .iter()
+            .filter(|coin| coin.denom == denom)
+            .map(|coin| coin.amount)
+            .sum(),
+        _ => Uint128::zero(),
+    }
 }
 
 /// Validates swap operations.
@@ -596,6 +952,9 @@ fn assert_operations(api: &dyn Api, operations: &[SwapOperation]) -> Result<(),
                 ask_asset_info.validate(api)?,
             ),
         };
+        if offer_asset == ask_asset {
+            return Err(ContractError::SameAssetSwap {});
+        }
 
         ask_asset_map.remove(&offer_asset.to_string());
         ask_asset_map.insert(ask_asset.to_string());
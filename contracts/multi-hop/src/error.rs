@@ -1,6 +1,8 @@
 use thiserror::Error;
 
 use cosmwasm_std::{OverflowError, StdError, Uint128};
+use sg_swap::multi_hop::MAX_SWAP_OPERATIONS;
+use sg_swap::pair::ContractError as PairContractError;
 
 /// This enum describes oracle contract errors
 #[derive(Error, Debug, PartialEq)]
@@ -8,17 +10,44 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Pair(#[from] PairContractError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
     #[error("Must specify swap operations!")]
     MustProvideOperations {},
 
-    #[error("Assertion failed; minimum receive amount: {receive}, swap amount: {amount}")]
-    AssertionMinimumReceive { receive: Uint128, amount: Uint128 },
+    #[error("The swap returns {received}, which is less than the minimum of {minimum}")]
+    MinimumReceiveNotMet { received: Uint128, minimum: Uint128 },
+
+    #[error("The swap deadline has expired")]
+    DeadlineExpired {},
+
+    #[error(
+        "The maximum number of hops must be between 1 and {}",
+        MAX_SWAP_OPERATIONS
+    )]
+    InvalidMaxHops {},
+
+    #[error("Too many swap operations; the maximum is {max}")]
+    TooManyHops { max: u32 },
+
+    #[error("Cannot swap an asset for itself")]
+    SameAssetSwap {},
+
+    #[error("Cannot swap a zero amount")]
+    ZeroAmount {},
+
+    #[error("No route found between the assets")]
+    NoRouteFound {},
+
+    #[error("No direct pair between {offer} and {ask}")]
+    NoDirectPair { offer: String, ask: String },
 
-    #[error("The swap operation limit was exceeded!")]
-    SwapLimitExceeded {},
+    #[error("A receive message can only be attached if the final asset is a cw20 token")]
+    ReceiveMsgRequiresCw20 {},
 }
 
 impl From<OverflowError> for ContractError {
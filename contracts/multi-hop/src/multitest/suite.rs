@@ -1,7 +1,11 @@
 use anyhow::Result as AnyResult;
 
-use cosmwasm_std::{coin, to_binary, Addr, Coin, Decimal, Uint128};
-use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    coin, to_binary, Addr, Binary, Coin, Decimal, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdError, StdResult, Uint128,
+};
+use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as Cw20BaseInstantiateMsg;
 use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
 
@@ -12,7 +16,8 @@ use sg_swap::factory::{
 };
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::multi_hop::{
-    ExecuteMsg, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation,
+    ExecuteMsg, FindBestRouteResponse, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse,
+    SwapOperation,
 };
 use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, PairInfo};
 
@@ -74,6 +79,37 @@ fn store_staking(app: &mut App) -> u64 {
     app.store_code(contract)
 }
 
+/// A vault that only accepts cw20 deposits through the receive hook
+#[cw_serde]
+enum VaultExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+}
+
+fn store_vault(app: &mut App) -> u64 {
+    let contract = Box::new(ContractWrapper::new_with_empty(
+        |_deps: DepsMut,
+         _env: Env,
+         info: MessageInfo,
+         msg: VaultExecuteMsg|
+         -> StdResult<Response> {
+            let VaultExecuteMsg::Receive(msg) = msg;
+            Ok(Response::new()
+                .add_attribute("action", "deposit")
+                .add_attribute("token", info.sender)
+                .add_attribute("amount", msg.amount)
+                .add_attribute("msg", msg.msg.to_base64()))
+        },
+        |_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty| -> StdResult<Response> {
+            Ok(Response::new())
+        },
+        |_deps: Deps, _env: Env, _msg: Empty| -> StdResult<Binary> {
+            Err(StdError::generic_err("not supported"))
+        },
+    ));
+
+    app.store_code(contract)
+}
+
 #[derive(Debug)]
 pub struct SuiteBuilder {
     funds: Vec<(Addr, Vec<Coin>)>,
@@ -81,6 +117,7 @@ pub struct SuiteBuilder {
     stake_config: DefaultStakeConfig,
     total_fee_bps: u16,
     protocol_fee_bps: u16,
+    max_hops: Option<u32>,
 }
 
 impl SuiteBuilder {
@@ -90,6 +127,7 @@ impl SuiteBuilder {
             max_referral_commission: Decimal::one(),
             total_fee_bps: 0,
             protocol_fee_bps: 0,
+            max_hops: None,
             stake_config: DefaultStakeConfig {
                 staking_code_id: 0, // will be set in build()
                 tokens_per_power: Uint128::new(1000),
@@ -115,6 +153,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_max_hops(mut self, max_hops: u32) -> Self {
+        self.max_hops = Some(max_hops);
+        self
+    }
+
     pub fn with_max_referral_commission(mut self, max: Decimal) -> Self {
         self.max_referral_commission = max;
         self
@@ -143,6 +186,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: self.protocol_fee_bps,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                         PairConfig {
                             code_id: pair_code_id,
@@ -152,6 +197,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: self.protocol_fee_bps,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                     ],
                     token_code_id: cw20_code_id,
@@ -163,6 +210,7 @@ impl SuiteBuilder {
                         ..self.stake_config
                     },
                     trading_starts: None,
+                    fee_recipients: None,
                 },
                 &[],
                 "Stargaze Factory",
@@ -177,6 +225,7 @@ impl SuiteBuilder {
                 owner.clone(),
                 &InstantiateMsg {
                     sg_swap_factory: factory.to_string(),
+                    max_hops: self.max_hops,
                 },
                 &[],
                 "Stargaze Multi Hop",
@@ -207,7 +256,7 @@ pub struct Suite {
     pub owner: String,
     pub app: App,
     pub factory: Addr,
-    multi_hop: Addr,
+    pub multi_hop: Addr,
     cw20_code_id: u64,
 }
 
@@ -227,6 +276,11 @@ impl Suite {
                 init_params: None,
                 staking_config: Default::default(),
                 total_fee_bps: None,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
             },
             &[],
         )?;
@@ -255,6 +309,7 @@ impl Suite {
                 assets: assets.to_vec(),
                 slippage_tolerance: None,
                 receiver: None,
+                min_lp_amount: None,
             },
             send_funds,
         )
@@ -319,6 +374,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
         match second_asset.0.clone() {
             AssetInfo::Token(addr) => {
@@ -342,6 +400,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
 
         self.provide_liquidity(
@@ -387,6 +448,20 @@ impl Suite {
             .unwrap()
     }
 
+    pub fn instantiate_vault(&mut self) -> Addr {
+        let code_id = store_vault(&mut self.app);
+        self.app
+            .instantiate_contract(
+                code_id,
+                Addr::unchecked(&self.owner),
+                &Empty {},
+                &[],
+                "vault",
+                None,
+            )
+            .unwrap()
+    }
+
     pub fn mint_cw20(
         &mut self,
         owner: &str,
@@ -432,6 +507,81 @@ impl Suite {
                 max_spread: None,
                 referral_address: referral_address.into(),
                 referral_commission: referral_commission.into(),
+                deadline: None,
+                receive_msg: None,
+            },
+            &[amount],
+        )
+    }
+
+    pub fn swap_operations_with_minimum_receive(
+        &mut self,
+        sender: &str,
+        amount: Coin,
+        operations: Vec<SwapOperation>,
+        minimum_receive: Uint128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.multi_hop.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: Some(minimum_receive),
+                receiver: None,
+                max_spread: None,
+                referral_address: None,
+                referral_commission: None,
+                deadline: None,
+                receive_msg: None,
+            },
+            &[amount],
+        )
+    }
+
+    pub fn swap_operations_with_deadline(
+        &mut self,
+        sender: &str,
+        amount: Coin,
+        operations: Vec<SwapOperation>,
+        deadline: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.multi_hop.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: None,
+                receiver: None,
+                max_spread: None,
+                referral_address: None,
+                referral_commission: None,
+                deadline: Some(deadline),
+                receive_msg: None,
+            },
+            &[amount],
+        )
+    }
+
+    pub fn swap_operations_with_receive_msg(
+        &mut self,
+        sender: &str,
+        amount: Coin,
+        operations: Vec<SwapOperation>,
+        receiver: &str,
+        receive_msg: Binary,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.multi_hop.clone(),
+            &ExecuteMsg::ExecuteSwapOperations {
+                operations,
+                minimum_receive: None,
+                receiver: Some(receiver.to_owned()),
+                max_spread: None,
+                referral_address: None,
+                referral_commission: None,
+                deadline: None,
+                receive_msg: Some(receive_msg),
             },
             &[amount],
         )
@@ -469,6 +619,8 @@ impl Suite {
                     max_spread: None,
                     referral_address: referral_address.into(),
                     referral_commission: referral_commission.into(),
+                    deadline: None,
+                    receive_msg: None,
                 })
                 .unwrap(),
             },
@@ -583,4 +735,40 @@ impl Suite {
         )?;
         Ok(res)
     }
+
+    pub fn query_simulate_direct_swap(
+        &self,
+        offer: &str,
+        ask: &str,
+        offer_amount: impl Into<Uint128>,
+    ) -> AnyResult<SimulateSwapOperationsResponse> {
+        let res: SimulateSwapOperationsResponse = self.app.wrap().query_wasm_smart(
+            self.multi_hop.clone(),
+            &QueryMsg::SimulateDirectSwap {
+                offer: offer.to_owned(),
+                ask: ask.to_owned(),
+                offer_amount: offer_amount.into(),
+            },
+        )?;
+        Ok(res)
+    }
+
+    pub fn query_find_best_route(
+        &self,
+        offer_asset: AssetInfo,
+        ask_asset: AssetInfo,
+        offer_amount: impl Into<Uint128>,
+        max_hops: impl Into<Option<u32>>,
+    ) -> AnyResult<FindBestRouteResponse> {
+        let res: FindBestRouteResponse = self.app.wrap().query_wasm_smart(
+            self.multi_hop.clone(),
+            &QueryMsg::FindBestRoute {
+                offer_asset,
+                ask_asset,
+                offer_amount: offer_amount.into(),
+                max_hops: max_hops.into(),
+            },
+        )?;
+        Ok(res)
+    }
 }
@@ -1,14 +1,14 @@
 use super::suite::SuiteBuilder;
 
 use cosmwasm_std::testing::MockApi;
-use cosmwasm_std::{assert_approx_eq, coin, Decimal, Fraction, Uint128};
-use sg_swap::pair::{add_referral, take_referral};
+use cosmwasm_std::{assert_approx_eq, coin, to_binary, Decimal, Fraction, Uint128};
+use sg_swap::pair::{add_referral, take_referral, OFFER_ASSET_KEY, SWAP_ATTRIBUTE_KEYS};
 use sg_swap::querier::query_factory_config;
 
 use crate::error::ContractError;
-use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
+use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated};
 use sg_swap::factory::PairType;
-use sg_swap::multi_hop::{SwapOperation, MAX_SWAP_OPERATIONS};
+use sg_swap::multi_hop::{SwapOperation, DEFAULT_MAX_HOPS, MAX_SWAP_OPERATIONS};
 
 #[test]
 fn must_provide_operations() {
@@ -69,6 +69,97 @@ fn single_swap() {
     assert_eq!(suite.query_balance(user, ujuno).unwrap(), 99_900u128);
 }
 
+#[test]
+fn swap_into_contract_with_receive_msg() {
+    let ujuno = "ujuno";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .build();
+
+    let owner = suite.owner.clone();
+    let token = suite.instantiate_token(&owner, "wynd");
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Token(token.to_string()), 100_000_000u128),
+            (AssetInfo::Native(ujuno.to_owned()), 100_000_000u128),
+            vec![coin(100_000_000, ujuno)],
+        )
+        .unwrap();
+    let vault = suite.instantiate_vault();
+    // tokens the multi-hop contract already holds are not part of the swap
+    let multi_hop = suite.multi_hop.to_string();
+    suite.mint_cw20(&owner, &token, 5_000, &multi_hop).unwrap();
+
+    // the vault only accepts tokens sent with a hook
+    let hook = to_binary("deposit").unwrap();
+    let res = suite
+        .swap_operations_with_receive_msg(
+            user,
+            coin(100_000u128, ujuno),
+            vec![SwapOperation::StargazeSwap {
+                offer_asset_info: AssetInfo::Native(ujuno.to_string()),
+                ask_asset_info: AssetInfo::Token(token.to_string()),
+            }],
+            vault.as_str(),
+            hook.clone(),
+        )
+        .unwrap();
+    assert_eq!(
+        suite.query_cw20_balance(vault.as_str(), &token).unwrap(),
+        99_900u128
+    );
+    let deposit = res
+        .events
+        .iter()
+        .find(|event| {
+            event.ty == "wasm"
+                && event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "action" && attr.value == "deposit")
+        })
+        .unwrap();
+    assert!(deposit
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "amount" && attr.value == "99900"));
+    assert!(deposit
+        .attributes
+        .iter()
+        .any(|attr| attr.key == "msg" && attr.value == hook.to_base64()));
+    assert_eq!(
+        suite.query_cw20_balance(&multi_hop, &token).unwrap(),
+        5_000u128
+    );
+
+    // a receive message needs a cw20 as the final asset
+    let err = suite
+        .swap_operations_with_receive_msg(
+            user,
+            coin(100_000u128, ujuno),
+            vec![
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Native(ujuno.to_string()),
+                    ask_asset_info: AssetInfo::Token(token.to_string()),
+                },
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Token(token.to_string()),
+                    ask_asset_info: AssetInfo::Native(ujuno.to_string()),
+                },
+            ],
+            vault.as_str(),
+            hook,
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ReceiveMsgRequiresCw20 {},
+        err.downcast().unwrap()
+    );
+}
+
 #[test]
 fn multiple_swaps() {
     let ujuno = "ujuno";
@@ -288,6 +379,155 @@ fn query_buy_with_routes() {
     );
 }
 
+#[test]
+fn find_best_route() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+
+    let mut suite = SuiteBuilder::new().build();
+
+    let owner = suite.owner.clone();
+
+    let token = suite.instantiate_token(&owner, "TOKA");
+
+    // two deep pools through the token and one shallow direct pool
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Token(token.to_string()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Token(token.to_string()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 10_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 10_000_000u128),
+            vec![coin(10_000_000, ujuno), coin(10_000_000, uluna)],
+        )
+        .unwrap();
+
+    let indirect_route = vec![
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+            ask_asset_info: AssetInfo::Token(token.to_string()),
+        },
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Token(token.to_string()),
+            ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+        },
+    ];
+    let direct_route = vec![SwapOperation::StargazeSwap {
+        offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+    }];
+
+    // the shallow pool has more spread than the two deep ones combined
+    let response = suite
+        .query_find_best_route(
+            AssetInfo::Native(ujuno.to_owned()),
+            AssetInfo::Native(uluna.to_owned()),
+            1_000_000u128,
+            None,
+        )
+        .unwrap();
+    assert_eq!(response.operations, indirect_route);
+    assert_eq!(response.amount.u128(), 998_002u128);
+    let simulated = suite
+        .query_simulate_swap_operations(1_000_000u128, indirect_route)
+        .unwrap();
+    assert_eq!(response.amount, simulated.amount);
+
+    // with one hop only the direct pool can be used
+    let response = suite
+        .query_find_best_route(
+            AssetInfo::Native(ujuno.to_owned()),
+            AssetInfo::Native(uluna.to_owned()),
+            1_000_000u128,
+            1,
+        )
+        .unwrap();
+    assert_eq!(response.operations, direct_route);
+    // 10_000_000 * 1_000_000 / 11_000_000
+    assert_eq!(response.amount.u128(), 909_090u128);
+
+    let err = suite
+        .query_find_best_route(
+            AssetInfo::Native(ujuno.to_owned()),
+            AssetInfo::Native("uatom".to_owned()),
+            1_000_000u128,
+            None,
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("No route found between the assets"));
+
+    let err = suite
+        .query_find_best_route(
+            AssetInfo::Native(ujuno.to_owned()),
+            AssetInfo::Native(uluna.to_owned()),
+            1_000_000u128,
+            DEFAULT_MAX_HOPS + 1,
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains(&format!(
+        "Too many swap operations; the maximum is {DEFAULT_MAX_HOPS}"
+    )));
+}
+
+#[test]
+fn simulate_direct_swap() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uatom = "uatom";
+
+    let mut suite = SuiteBuilder::new().build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 10_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 10_000_000u128),
+            vec![coin(10_000_000, ujuno), coin(10_000_000, uluna)],
+        )
+        .unwrap();
+
+    // same result as simulating the single swap operation
+    let response = suite
+        .query_simulate_direct_swap(ujuno, uluna, 1_000_000u128)
+        .unwrap();
+    let simulated = suite
+        .query_simulate_swap_operations(
+            1_000_000u128,
+            vec![SwapOperation::StargazeSwap {
+                offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+                ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+            }],
+        )
+        .unwrap();
+    assert_eq!(response, simulated);
+    // 10_000_000 * 1_000_000 / 11_000_000
+    assert_eq!(response.amount.u128(), 909_090u128);
+
+    // there is no ujuno / uatom pair
+    let err = suite
+        .query_simulate_direct_swap(ujuno, uatom, 1_000_000u128)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("No direct pair between ujuno and uatom"));
+}
+
 #[test]
 fn simulation_with_fee() {
     let ujuno = "ujuno";
@@ -370,10 +610,11 @@ fn simulation_with_fee() {
         "999_000 * 1% = 9_990, 988_032 * 1% = 9_880"
     );
     assert_eq!(
-        response.referral_amount,
-        ujuno_val.with_balance(0u128),
+        response.referral_amounts,
+        vec![ujuno_val.with_balance(0u128), token_val.with_balance(0u128)],
         "no referral"
     );
+    assert_eq!(response.referral_amount, ujuno_val.with_balance(0u128));
 
     // now another swap with referral commission
     let response = suite
@@ -395,37 +636,45 @@ fn simulation_with_fee() {
 
     // ideal amount for first swap is `1_000_000 - 10_000 = 990_000`, but because of spread (980) it's `989_020` and
     // the fee is `989_020 * 1% = 9_890`, so it returns `979_130`
-    // the ideal amount for the second swap is `979_130`, but because of spread (958) it's `978_172` and
-    // the fee is `978_172 * 1% = 9_781`, so it returns `968_391`
-    assert_eq!(response.amount.u128(), 968_391u128);
+    // the ideal amount for the second swap is `979_130 - 9_791 = 969_339`, but because of spread (939) it's `968_400` and
+    // the fee is `968_400 * 1% = 9_684`, so it returns `958_716`
+    assert_eq!(response.amount.u128(), 958_716u128);
     assert_eq!(
         response.spread,
         (Decimal::one()
             - (Decimal::from_ratio(989_020u128, 990_000u128)
-                * Decimal::from_ratio(978_172u128, 979_130u128)))
+                * Decimal::from_ratio(968_400u128, 969_339u128)))
     );
     // validate absolute amounts
     assert_eq!(
         response.spread_amounts,
         vec![
             token_val.with_balance(980u128),
-            uluna_val.with_balance(958u128)
+            uluna_val.with_balance(939u128)
         ],
     );
     assert_eq!(
         response.commission_amounts,
         vec![
             token_val.with_balance(9_890u128),
-            uluna_val.with_balance(9_781u128)
+            uluna_val.with_balance(9_684u128)
+        ],
+        "989_020 * 1% = 9_890, 968_400 * 1% = 9_684"
+    );
+    assert_eq!(
+        response.referral_amounts,
+        vec![
+            ujuno_val.with_balance(10_000u128),
+            token_val.with_balance(9_791u128)
         ],
-        "989_020 * 1% = 9_890, 978_172 * 1% = 9_781"
+        "1_000_000 * 1% = 10_000, 979_130 * 1% = 9_791"
     );
     assert_eq!(response.referral_amount, ujuno_val.with_balance(10_000u128));
 
     // now same swap, but in reverse
     let response = suite
         .query_simulate_reverse_swap_operations_ref(
-            968_391u128,
+            958_716u128,
             vec![
                 SwapOperation::StargazeSwap {
                     offer_asset_info: ujuno_info,
@@ -445,12 +694,97 @@ fn simulation_with_fee() {
         response.spread.numerator(),
         (Decimal::one()
             - (Decimal::from_ratio(989_020u128, 990_000u128)
-                * Decimal::from_ratio(978_172u128, 979_130u128)))
+                * Decimal::from_ratio(968_400u128, 969_339u128)))
         .numerator(),
         "0.01"
     );
 }
 
+#[test]
+fn reverse_simulation_round_trip() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+
+    // fee is 1% for both tokens
+    let mut suite = SuiteBuilder::new().with_fees(100, 50).build();
+
+    let owner = suite.owner.clone();
+
+    let token = suite.instantiate_token(&owner, "TOKA");
+
+    let token_info = AssetInfo::Token(token.to_string());
+    let ujuno_info = AssetInfo::Native(ujuno.to_owned());
+    let uluna_info = AssetInfo::Native(uluna.to_owned());
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (ujuno_info.clone(), 1_000_000_000u128),
+            (token_info.clone(), 2_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (uluna_info.clone(), 3_000_000_000u128),
+            (token_info.clone(), 1_000_000_000u128),
+            vec![coin(3_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    let operations = vec![
+        SwapOperation::StargazeSwap {
+            offer_asset_info: ujuno_info,
+            ask_asset_info: token_info.clone(),
+        },
+        SwapOperation::StargazeSwap {
+            offer_asset_info: token_info,
+            ask_asset_info: uluna_info,
+        },
+    ];
+
+    for ask_amount in [10_000u128, 500_000, 10_000_000] {
+        // without referral
+        let reverse = suite
+            .query_simulate_reverse_swap_operations(ask_amount, operations.clone())
+            .unwrap();
+        let forward = suite
+            .query_simulate_swap_operations(reverse.amount, operations.clone())
+            .unwrap();
+        assert_approx_eq!(forward.amount.u128(), ask_amount, "0.001");
+
+        // with referral, the commission is applied to the offer asset in both directions
+        let reverse = suite
+            .query_simulate_reverse_swap_operations_ref(
+                ask_amount,
+                operations.clone(),
+                Decimal::percent(1),
+            )
+            .unwrap();
+        let forward = suite
+            .query_simulate_swap_operations_ref(
+                reverse.amount,
+                operations.clone(),
+                Decimal::percent(1),
+            )
+            .unwrap();
+        assert_approx_eq!(forward.amount.u128(), ask_amount, "0.001");
+        assert_eq!(forward.referral_amount, reverse.referral_amount);
+        // the reverse simulation lists the operations from last to first
+        assert_eq!(forward.referral_amounts[0], reverse.referral_amounts[1]);
+        assert_eq!(
+            forward.referral_amounts[1].info,
+            reverse.referral_amounts[0].info
+        );
+        assert_approx_eq!(
+            forward.referral_amounts[1].amount,
+            reverse.referral_amounts[0].amount,
+            "0.001"
+        );
+    }
+}
+
 #[test]
 fn assert_minimum_receive_native_tokens() {
     let ujuno = "ujuno";
@@ -468,9 +802,9 @@ fn assert_minimum_receive_native_tokens() {
         .assert_minimum_receive("user", AssetInfo::Native(ujuno.to_owned()), 1_000_001u128)
         .unwrap_err();
     assert_eq!(
-        ContractError::AssertionMinimumReceive {
-            receive: Uint128::new(1_000_001),
-            amount: Uint128::new(1_000_000)
+        ContractError::MinimumReceiveNotMet {
+            received: Uint128::new(1_000_000),
+            minimum: Uint128::new(1_000_001)
         },
         err.downcast().unwrap()
     );
@@ -494,14 +828,61 @@ fn assert_minimum_receive_cw20_tokens() {
         .assert_minimum_receive("user", AssetInfo::Token(token.to_string()), 1_000_001u128)
         .unwrap_err();
     assert_eq!(
-        ContractError::AssertionMinimumReceive {
-            receive: Uint128::new(1_000_001),
-            amount: Uint128::new(1_000_000)
+        ContractError::MinimumReceiveNotMet {
+            received: Uint128::new(1_000_000),
+            minimum: Uint128::new(1_000_001)
         },
         err.downcast().unwrap()
     );
 }
 
+#[test]
+fn swap_below_minimum_receive_reports_shortfall() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    let operations = vec![SwapOperation::StargazeSwap {
+        offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+    }];
+    let received = suite
+        .query_simulate_swap_operations(100_000u128, operations.clone())
+        .unwrap()
+        .amount;
+
+    let err = suite
+        .swap_operations_with_minimum_receive(
+            user,
+            coin(100_000u128, ujuno),
+            operations,
+            received + Uint128::new(10),
+        )
+        .unwrap_err();
+    // the assertion runs in a sub-message after the swaps
+    assert_eq!(
+        ContractError::MinimumReceiveNotMet {
+            received,
+            minimum: received + Uint128::new(10)
+        }
+        .to_string(),
+        err.root_cause().to_string()
+    );
+}
+
 #[test]
 fn maximum_receive_swap_operations() {
     let ujuno = "ujuno";
@@ -535,7 +916,214 @@ fn maximum_receive_swap_operations() {
             ],
         )
         .unwrap_err();
-    assert_eq!(ContractError::SwapLimitExceeded {}, err.downcast().unwrap());
+    assert_eq!(
+        ContractError::TooManyHops {
+            max: DEFAULT_MAX_HOPS
+        },
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn routes_cannot_exceed_max_hops() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uusd = "uusd";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .with_max_hops(2)
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uusd.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna), coin(1_000_000_000, uusd)],
+        )
+        .unwrap();
+
+    let hop = |offer: &str, ask: &str| SwapOperation::StargazeSwap {
+        offer_asset_info: AssetInfo::Native(offer.to_owned()),
+        ask_asset_info: AssetInfo::Native(ask.to_owned()),
+    };
+    let two_hops = vec![hop(ujuno, uluna), hop(uluna, uusd)];
+    let three_hops = vec![hop(ujuno, uluna), hop(uluna, ujuno), hop(ujuno, uluna)];
+
+    suite
+        .query_simulate_swap_operations(100_000u128, two_hops.clone())
+        .unwrap();
+
+    let err = suite
+        .query_simulate_swap_operations(100_000u128, three_hops.clone())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::TooManyHops { max: 2 }.to_string()));
+    let err = suite
+        .query_simulate_reverse_swap_operations(100_000u128, three_hops.clone())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::TooManyHops { max: 2 }.to_string()));
+
+    let err = suite
+        .swap_operations(user, coin(100_000u128, ujuno), three_hops)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TooManyHops { max: 2 },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .swap_operations(user, coin(100_000u128, ujuno), two_hops)
+        .unwrap();
+    assert_ne!(suite.query_balance(user, uusd).unwrap(), 0);
+}
+
+#[test]
+fn swapping_an_asset_for_itself_is_rejected() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    let operations = vec![
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+            ask_asset_info: AssetInfo::Native(ujuno.to_owned()),
+        },
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+            ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+        },
+    ];
+
+    let err = suite
+        .query_simulate_swap_operations(100_000u128, operations.clone())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::SameAssetSwap {}.to_string()));
+
+    let err = suite
+        .swap_operations(user, coin(100_000u128, ujuno), operations)
+        .unwrap_err();
+    assert_eq!(ContractError::SameAssetSwap {}, err.downcast().unwrap());
+}
+
+#[test]
+fn zero_and_dust_swaps() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno), coin(100_000, uluna)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    let operations = vec![SwapOperation::StargazeSwap {
+        offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+    }];
+
+    let err = suite
+        .query_simulate_swap_operations(0u128, operations.clone())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::ZeroAmount {}.to_string()));
+
+    // sending a different token than the offered one offers nothing
+    let err = suite
+        .swap_operations(user, coin(100_000u128, uluna), operations.clone())
+        .unwrap_err();
+    assert_eq!(ContractError::ZeroAmount {}, err.downcast().unwrap());
+
+    // 1 * 1_000_000_000 / (1_000_000_000 + 1) rounds down to nothing
+    let err = suite
+        .query_simulate_swap_operations(1u128, operations.clone())
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("The offered amount is too small to return anything"));
+    let err = suite
+        .swap_operations(user, coin(1u128, ujuno), operations)
+        .unwrap_err();
+    assert!(err
+        .root_cause()
+        .to_string()
+        .contains("The offered amount is too small to return anything"));
+    assert_eq!(suite.query_balance(user, ujuno).unwrap(), 100_000);
+}
+
+#[test]
+fn swap_with_expired_deadline() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(200_000, ujuno)])
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+
+    let operations = vec![SwapOperation::StargazeSwap {
+        offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+        ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+    }];
+    let now = suite.app.block_info().time.seconds();
+
+    let err = suite
+        .swap_operations_with_deadline(user, coin(100_000u128, ujuno), operations.clone(), now - 1)
+        .unwrap_err();
+    assert_eq!(ContractError::DeadlineExpired {}, err.downcast().unwrap());
+    assert_eq!(suite.query_balance(user, uluna).unwrap(), 0);
+
+    // the deadline is inclusive
+    suite
+        .swap_operations_with_deadline(user, coin(100_000u128, ujuno), operations, now)
+        .unwrap();
+    assert_ne!(suite.query_balance(user, uluna).unwrap(), 0);
 }
 
 /// Tests the helper functions for calculating referral commission.
@@ -708,13 +1296,267 @@ fn referral_multiple() {
         query_result
     );
 
+    // the commission is deducted on every hop
     assert_eq!(
         suite.query_cw20_balance(user, &token_b).unwrap(),
-        99_970u128
+        97_982u128
     );
 
-    // make sure referral got the commission
+    // make sure referral got the commission of each hop
     assert_eq!(suite.query_balance(referral, ujuno).unwrap(), 1010u128);
+    assert_eq!(
+        suite.query_cw20_balance(referral, &token_a).unwrap(),
+        999u128
+    );
+    assert_eq!(suite.query_balance(referral, uluna).unwrap(), 989u128);
+}
+
+#[test]
+fn referral_commission_per_hop() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uusd = "uusd";
+    let user = "user";
+    let referral = "referral";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .with_max_referral_commission(Decimal::percent(1))
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uusd.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna), coin(1_000_000_000, uusd)],
+        )
+        .unwrap();
+
+    let res = suite
+        .swap_operations_ref(
+            user,
+            coin(100_000u128, ujuno),
+            vec![
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+                    ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+                },
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Native(uluna.to_owned()),
+                    ask_asset_info: AssetInfo::Native(uusd.to_owned()),
+                },
+            ],
+            referral.to_string(),
+            None,
+        )
+        .unwrap();
+
+    // the commission defaults to the maximum of 1% and is reported for each hop:
+    // 100_000 * 1% = 1_000 and (99_000 - 10 spread) * 1% = 989
+    let commissions: Vec<_> = res
+        .events
+        .iter()
+        .flat_map(|event| &event.attributes)
+        .filter(|attr| attr.key == "referral_commission")
+        .map(|attr| attr.value.as_str())
+        .collect();
+    assert_eq!(commissions, ["1000ujuno", "989uluna"]);
+
+    assert_eq!(suite.query_balance(referral, ujuno).unwrap(), 1_000u128);
+    assert_eq!(suite.query_balance(referral, uluna).unwrap(), 989u128);
+}
+
+#[test]
+fn simulation_breakdown_matches_swap() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uusd = "uusd";
+    let user = "user";
+    let referral = "referral";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .with_max_referral_commission(Decimal::percent(1))
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uusd.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna), coin(1_000_000_000, uusd)],
+        )
+        .unwrap();
+
+    let operations = vec![
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+            ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+        },
+        SwapOperation::StargazeSwap {
+            offer_asset_info: AssetInfo::Native(uluna.to_owned()),
+            ask_asset_info: AssetInfo::Native(uusd.to_owned()),
+        },
+    ];
+    let simulation = suite
+        .query_simulate_swap_operations_ref(100_000u128, operations.clone(), None)
+        .unwrap();
+    assert_eq!(simulation.spread_amounts.len(), 2);
+    assert_eq!(simulation.commission_amounts.len(), 2);
+    assert_eq!(simulation.referral_amounts.len(), 2);
+
+    let res = suite
+        .swap_operations_ref(
+            user,
+            coin(100_000u128, ujuno),
+            operations,
+            referral.to_string(),
+            None,
+        )
+        .unwrap();
+
+    // every hop of the simulation matches the corresponding swap of the pairs
+    let hop_amounts = |key: &str| -> Vec<Uint128> {
+        res.events
+            .iter()
+            .filter(|event| {
+                event
+                    .attributes
+                    .iter()
+                    .any(|attr| attr.key == "action" && attr.value == "swap")
+            })
+            .flat_map(|event| &event.attributes)
+            .filter(|attr| attr.key == key)
+            .map(|attr| attr.value.parse().unwrap())
+            .collect()
+    };
+    let amounts = |assets: &[AssetValidated]| -> Vec<Uint128> {
+        assets.iter().map(|asset| asset.amount).collect()
+    };
+    assert_eq!(
+        amounts(&simulation.spread_amounts),
+        hop_amounts("spread_amount")
+    );
+    assert_eq!(
+        amounts(&simulation.commission_amounts),
+        hop_amounts("commission_amount")
+    );
+
+    // the referral commissions of all hops add up to what the referrer received
+    assert_eq!(
+        simulation.referral_amounts,
+        vec![
+            AssetInfoValidated::Native(ujuno.to_owned()).with_balance(1_000u128),
+            AssetInfoValidated::Native(uluna.to_owned()).with_balance(989u128),
+        ]
+    );
+    assert_eq!(suite.query_balance(referral, ujuno).unwrap(), 1_000u128);
+    assert_eq!(suite.query_balance(referral, uluna).unwrap(), 989u128);
+    assert_eq!(
+        suite.query_balance(user, uusd).unwrap(),
+        simulation.amount.u128()
+    );
+}
+
+#[test]
+fn hops_report_standard_swap_attributes() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let uusd = "uusd";
+    let user = "user";
+    let referral = "referral";
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .with_max_referral_commission(Decimal::percent(1))
+        .build();
+
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(ujuno.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, ujuno), coin(1_000_000_000, uluna)],
+        )
+        .unwrap();
+    suite
+        .create_pair_and_provide_liquidity(
+            PairType::Xyk {},
+            (AssetInfo::Native(uluna.to_owned()), 1_000_000_000u128),
+            (AssetInfo::Native(uusd.to_owned()), 1_000_000_000u128),
+            vec![coin(1_000_000_000, uluna), coin(1_000_000_000, uusd)],
+        )
+        .unwrap();
+
+    let res = suite
+        .swap_operations_ref(
+            user,
+            coin(100_000u128, ujuno),
+            vec![
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Native(ujuno.to_owned()),
+                    ask_asset_info: AssetInfo::Native(uluna.to_owned()),
+                },
+                SwapOperation::StargazeSwap {
+                    offer_asset_info: AssetInfo::Native(uluna.to_owned()),
+                    ask_asset_info: AssetInfo::Native(uusd.to_owned()),
+                },
+            ],
+            referral.to_string(),
+            None,
+        )
+        .unwrap();
+
+    // the values of all standard keys of every event reporting a swap
+    let swaps: Vec<Vec<&str>> = res
+        .events
+        .iter()
+        .filter(|event| {
+            event
+                .attributes
+                .iter()
+                .any(|attr| attr.key == OFFER_ASSET_KEY)
+        })
+        .map(|event| {
+            SWAP_ATTRIBUTE_KEYS
+                .iter()
+                .map(|key| {
+                    let values: Vec<_> = event
+                        .attributes
+                        .iter()
+                        .filter(|attr| attr.key == *key)
+                        .collect();
+                    assert_eq!(values.len(), 1, "{key} must be reported exactly once");
+                    values[0].value.as_str()
+                })
+                .collect()
+        })
+        .collect();
+
+    // every hop is reported by the multi-hop contract and then by the pair, with the same values
+    let first_hop = vec!["ujuno", "uluna", "99000", "98990", "10", "0", "1000"];
+    let second_hop = vec!["uluna", "uusd", "98001", "97991", "10", "0", "989"];
+    assert_eq!(
+        swaps,
+        vec![first_hop.clone(), first_hop, second_hop.clone(), second_hop]
+    );
 }
 
 #[test]
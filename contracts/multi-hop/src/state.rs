@@ -8,4 +8,6 @@ pub const CONFIG: Item<Config> = Item::new("config");
 pub struct Config {
     /// The factory contract address
     pub sg_swap_factory: Addr,
+    /// The maximum number of swap operations in a single route
+    pub max_hops: u32,
 }
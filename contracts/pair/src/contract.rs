@@ -1,34 +1,47 @@
-use crate::state::{Config, CONFIG};
+use crate::math::{compute_weighted_offer_amount, compute_weighted_swap};
+use crate::state::{Config, CONFIG, VOLUME_SNAPSHOTS, VOLUME_SNAPSHOT_INTERVAL};
+use crate::token_factory;
 
 use cosmwasm_std::{
-    attr, entry_point, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal, Decimal256, Deps,
-    DepsMut, Env, Isqrt, MessageInfo, QuerierWrapper, Reply, Response, StdError, StdResult,
-    Uint128, Uint256, WasmMsg,
+    attr, coin, entry_point, from_binary, to_binary, wasm_execute, Addr, BankMsg, Binary,
+    CosmosMsg, Decimal, Decimal256, Deps, DepsMut, Env, Isqrt, MessageInfo, Order, QuerierWrapper,
+    Reply, Response, StdError, StdResult, SubMsg, Uint128, Uint256, WasmMsg,
 };
+use cw_storage_plus::Bound;
 
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_utils::{ensure_from_older_version, must_pay};
 use sg_swap::asset::{
-    addr_opt_validate, check_swap_parameters, Asset, AssetInfoValidated, AssetValidated,
+    addr_opt_validate, check_swap_parameters, Asset, AssetInfo, AssetInfoValidated, AssetValidated,
     MINIMUM_LIQUIDITY_AMOUNT,
 };
 use sg_swap::decimal2decimal256;
-use sg_swap::factory::{ConfigResponse as FactoryConfig, PairType};
+use sg_swap::factory::{ConfigResponse as FactoryConfig, PairType, WEIGHTS_BASIS};
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
-    add_referral, assert_max_spread, check_asset_infos, check_assets, check_cw20_in_pool,
-    create_lp_token, get_share_in_assets, handle_referral, handle_reply, migration_check,
-    mint_token_message, save_tmp_staking_config, take_referral, ConfigResponse, ContractError,
-    Cw20HookMsg, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
+    accrue_fee, accumulate_volume, add_referral, assert_max_spread, assert_min_assets,
+    assert_min_lp_amount, calculate_twap, check_asset_infos, check_assets, check_cw20_in_pool,
+    create_lp_token, create_native_lp_staking, cw20_balances_before, cw20_tokens,
+    get_share_in_assets, handle_referral, handle_reply, migration_check, mint_token_message,
+    protocol_fee_msgs, pull_swap_offer, received_amount, received_hook_amount,
+    replace_received_amounts, save_cw20_reserves, save_tmp_staking_config, simulate_withdraw,
+    spread_exceeds, swap_attributes, take_referral, ConfigResponse, ContractError, Cw20HookMsg,
+    MigrateMsg, DEFAULT_SLIPPAGE, MAX_ALLOWED_SLIPPAGE,
 };
 use sg_swap::pair::{
-    CumulativePricesResponse, ExecuteMsg, InstantiateMsg, PairInfo, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse, TWAP_PRECISION,
+    CumulativePricesResponse, CumulativeVolumeResponse, EstimateSwapFeesResponse, ExecuteMsg,
+    InstantiateMsg, LpTokenType, PairInfo, PoolResponse, PoolStateResponse, QueryMsg,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulationResponse, TwapResponse,
+    WeightedPoolParams, TWAP_PRECISION,
 };
 use sg_swap::querier::{query_factory_config, query_supply};
 use std::str::FromStr;
 use std::vec;
 
+/// Seconds per year, used to annualize the fee yield
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+
 /// Contract name that is used for migration.
 const CONTRACT_NAME: &str = "sg-swap-pair";
 /// Contract version that is used for migration.
@@ -51,24 +64,66 @@ pub fn instantiate(
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
     msg.validate_fees()?;
+    msg.validate_lp_token_info()?;
+    let default_max_spread = msg.default_max_spread()?;
+
+    // Weighted pairs receive their weights from the factory in the init params
+    let pair_type = match msg.init_params {
+        Some(params) => {
+            let params: WeightedPoolParams = from_binary(&params)?;
+            let pair_type = PairType::Weighted {
+                weights: params.weights,
+            };
+            if !pair_type.valid_weights() {
+                return Err(ContractError::InvalidWeights {});
+            }
+            pair_type
+        }
+        None => PairType::Xyk {},
+    };
 
     let factory_addr = deps.api.addr_validate(msg.factory_addr.as_str())?;
 
-    let create_lp_token_msg = create_lp_token(
-        &deps.querier,
-        &env,
-        msg.token_code_id,
-        &asset_infos,
-        &factory_addr,
-    )?;
+    // Native LP shares and their staking contract are created right away,
+    // cw20 ones are set in the reply
+    let (liquidity_token, lp_token_msgs) = match msg.lp_token_type {
+        LpTokenType::Cw20 => {
+            save_tmp_staking_config(deps.storage, &msg.staking_config)?;
+            let create_lp_token_msg = create_lp_token(
+                &deps.querier,
+                &env,
+                msg.token_code_id,
+                &asset_infos,
+                &factory_addr,
+                msg.lp_token_name.clone(),
+                msg.lp_token_symbol.clone(),
+            )?;
+            (Addr::unchecked(""), vec![create_lp_token_msg])
+        }
+        LpTokenType::Native => {
+            let lp_denom = token_factory::lp_denom(&env.contract.address);
+            let create_denom_msg =
+                SubMsg::new(token_factory::create_denom_msg(&env.contract.address)?);
+            let create_staking_msg = create_native_lp_staking(
+                &deps.querier,
+                msg.staking_config,
+                lp_denom.clone(),
+                &factory_addr,
+            )?;
+            (
+                Addr::unchecked(lp_denom),
+                vec![create_denom_msg, create_staking_msg],
+            )
+        }
+    };
 
     let config = Config {
         pair_info: PairInfo {
             contract_addr: env.contract.address,
-            liquidity_token: Addr::unchecked(""),
+            liquidity_token,
             staking_addr: Addr::unchecked(""),
             asset_infos,
-            pair_type: PairType::Xyk {},
+            pair_type,
             fee_config: msg.fee_config,
         },
         factory_addr,
@@ -76,13 +131,17 @@ pub fn instantiate(
         price0_cumulative_last: Uint128::zero(),
         price1_cumulative_last: Uint128::zero(),
         trading_starts: msg.trading_starts,
+        paused: false,
+        default_max_spread,
+        lp_token_type: msg.lp_token_type,
+        accrued_protocol_fees: vec![],
+        offered_volume: vec![],
+        returned_volume: vec![],
     };
 
     CONFIG.save(deps.storage, &config)?;
 
-    save_tmp_staking_config(deps.storage, &msg.staking_config)?;
-
-    Ok(Response::new().add_submessage(create_lp_token_msg))
+    Ok(Response::new().add_submessages(lp_token_msgs))
 }
 
 /// The entry point to the contract for processing replies from submessages.
@@ -107,17 +166,29 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             assets,
 ///             slippage_tolerance,
 ///             receiver,
+///             min_lp_amount,
 ///         }** Provides liquidity in the pair with the specified input parameters.
 ///
+/// * **ExecuteMsg::ProvideReceived { .. }** Continues providing cw20 tokens with the amounts the pair received.
+///
+/// * **ExecuteMsg::WithdrawLiquidity { assets, min_assets }** Withdraws liquidity by burning the native LP shares
+/// sent along with the message, failing if less than `min_assets` would be returned.
+///
 /// * **ExecuteMsg::Swap {
 ///             offer_asset,
 ///             belief_price,
 ///             max_spread,
 ///             to,
 ///         }** Performs a swap operation with the specified parameters.
+///
+/// * **ExecuteMsg::SwapReceived { .. }** Continues swapping a cw20 token with the amount the pair received.
+///
+/// * **ExecuteMsg::SetPaused { paused }** Pauses or unpauses swaps and liquidity operations.
+///
+/// * **ExecuteMsg::CollectProtocolFees {}** Sends the accrued protocol fees to the fee address.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
@@ -128,26 +199,120 @@ pub fn execute(
         return Err(ContractError::PairIsNotMigrated {});
     }
 
+    if cfg.paused
+        && matches!(
+            msg,
+            ExecuteMsg::Receive(_)
+                | ExecuteMsg::ProvideLiquidity { .. }
+                | ExecuteMsg::WithdrawLiquidity { .. }
+                | ExecuteMsg::Swap { .. }
+        )
+    {
+        return Err(ContractError::PairPaused {});
+    }
+
+    // the cw20 tokens that arrived before this message, as opposed to the ones the pair sends itself
+    let asset_infos = cfg.pair_info.asset_infos.clone();
+    let received = match &msg {
+        ExecuteMsg::Receive(_) => vec![info.sender.clone()],
+        ExecuteMsg::ProvideReceived { .. } | ExecuteMsg::SwapReceived { .. } => {
+            cw20_tokens(&asset_infos)
+        }
+        _ => vec![],
+    };
+
+    let res = execute_msg(deps.branch(), env.clone(), info, msg, cfg)?;
+    save_cw20_reserves(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &asset_infos,
+        &received,
+        &res,
+    )?;
+    Ok(res)
+}
+
+/// Executes the [`ExecuteMsg`] after [`execute`] checked that the pair can handle it.
+fn execute_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    cfg: Config,
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             slippage_tolerance,
             receiver,
-        } => provide_liquidity(deps, env, info, assets, slippage_tolerance, receiver),
+            min_lp_amount,
+        } => provide_liquidity(
+            deps,
+            env,
+            info,
+            assets,
+            slippage_tolerance,
+            receiver,
+            min_lp_amount,
+        ),
+        ExecuteMsg::ProvideReceived {
+            sender,
+            assets,
+            prev_balances,
+            slippage_tolerance,
+            receiver,
+            min_lp_amount,
+        } => provide_received(
+            deps,
+            env,
+            info,
+            sender,
+            assets,
+            prev_balances,
+            slippage_tolerance,
+            receiver,
+            min_lp_amount,
+        ),
+        ExecuteMsg::WithdrawLiquidity { min_assets, .. } => {
+            let amount = must_pay(&info, cfg.pair_info.liquidity_token.as_str())?;
+            withdraw_liquidity(deps, env, info.clone(), info.sender, amount, min_assets)
+        }
         ExecuteMsg::UpdateFees { fee_config } => update_fees(deps, info, fee_config),
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, info, paused),
+        ExecuteMsg::CollectProtocolFees {} => collect_protocol_fees(deps),
         ExecuteMsg::Swap {
             offer_asset,
+            ask_asset_info,
             belief_price,
             max_spread,
             to,
             referral_address,
             referral_commission,
-            ..
         } => {
             let offer_asset = offer_asset.validate(deps.api)?;
-            if !offer_asset.is_native_token() {
-                return Err(ContractError::Unauthorized {});
+            match &offer_asset.info {
+                AssetInfoValidated::Token(contract_addr) => {
+                    check_cw20_in_pool(
+                        &CONFIG.load(deps.storage)?.pair_info.asset_infos,
+                        contract_addr,
+                    )?;
+                    return pull_swap_offer(
+                        &deps.querier,
+                        &env,
+                        info,
+                        offer_asset,
+                        ask_asset_info,
+                        belief_price,
+                        max_spread,
+                        to,
+                        referral_address,
+                        referral_commission,
+                    );
+                }
+                AssetInfoValidated::Native(_) => {}
+                AssetInfoValidated::Cw1155 { .. } => return Err(ContractError::Unauthorized {}),
             }
 
             let to_addr = addr_opt_validate(deps.api, &to)?;
@@ -166,6 +331,49 @@ pub fn execute(
                 referral_commission,
             )
         }
+        ExecuteMsg::SwapReceived {
+            sender,
+            offer_asset_info,
+            prev_balance,
+            belief_price,
+            max_spread,
+            to,
+            referral_address,
+            referral_commission,
+            ..
+        } => {
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized {});
+            }
+            let sender = deps.api.addr_validate(&sender)?;
+            let offer_asset_info = offer_asset_info.validate(deps.api)?;
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+            let referral_address = addr_opt_validate(deps.api, &referral_address)?;
+
+            // swap what the pair actually received, which can be less than the pulled amount
+            let amount = received_amount(
+                &deps.querier,
+                &env.contract.address,
+                &offer_asset_info,
+                prev_balance,
+            )?;
+
+            swap(
+                deps,
+                env,
+                info,
+                sender,
+                AssetValidated {
+                    info: offer_asset_info,
+                    amount,
+                },
+                belief_price,
+                max_spread,
+                to_addr,
+                referral_address,
+                referral_commission,
+            )
+        }
         _ => Err(ContractError::NonSupported {}),
     }
 }
@@ -198,6 +406,14 @@ pub fn receive_cw20(
             let referral_address = addr_opt_validate(deps.api, &referral_address)?;
             let contract_addr = info.sender.clone();
             let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            // swap what the pair actually received, which can be less than the reported amount
+            let amount = received_hook_amount(
+                deps.storage,
+                &deps.querier,
+                &env.contract.address,
+                &contract_addr,
+                cw20_msg.amount,
+            )?;
             swap(
                 deps,
                 env,
@@ -205,7 +421,7 @@ pub fn receive_cw20(
                 sender,
                 AssetValidated {
                     info: AssetInfoValidated::Token(contract_addr),
-                    amount: cw20_msg.amount,
+                    amount,
                 },
                 belief_price,
                 max_spread,
@@ -214,13 +430,18 @@ pub fn receive_cw20(
                 referral_commission,
             )
         }
-        Cw20HookMsg::WithdrawLiquidity { .. } => {
+        Cw20HookMsg::WithdrawLiquidity { min_assets, .. } => {
             let sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            withdraw_liquidity(deps, env, info, sender, cw20_msg.amount)
+            withdraw_liquidity(deps, env, info, sender, cw20_msg.amount, min_assets)
         }
+        Cw20HookMsg::WithdrawToAsset { .. } => Err(ContractError::NonSupported {}),
     }
 }
 
+/// Updates the fee configuration of the pair. Subsequent swaps use the new fees.
+///
+/// ## Executor
+/// Only the factory can execute this.
 pub fn update_fees(
     deps: DepsMut,
     info: MessageInfo,
@@ -233,6 +454,10 @@ pub fn update_fees(
         return Err(ContractError::Unauthorized {});
     }
 
+    if !fee_config.valid_fee_bps() {
+        return Err(ContractError::InvalidFeeBps {});
+    }
+
     // update config
     config.pair_info.fee_config = fee_config;
     CONFIG.save(deps.storage, &config)?;
@@ -240,6 +465,69 @@ pub fn update_fees(
     Ok(Response::default())
 }
 
+/// Pauses or unpauses swaps, liquidity provision and withdrawal.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_paused"),
+        attr("paused", paused.to_string()),
+    ]))
+}
+
+/// Sends all accrued protocol fees to the fee address of the factory,
+/// or splits them between its fee recipients if it has any.
+///
+/// ## Executor
+/// Anyone can execute this.
+pub fn collect_protocol_fees(deps: DepsMut) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let recipients = factory_config.protocol_fee_recipients();
+    if recipients.is_empty() {
+        return Err(ContractError::NoFeeAddress {});
+    }
+
+    let fees = std::mem::take(&mut config.accrued_protocol_fees);
+    CONFIG.save(deps.storage, &config)?;
+
+    let messages = protocol_fee_msgs(&fees, &recipients)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "collect_protocol_fees"),
+        attr(
+            "fee_address",
+            recipients
+                .iter()
+                .map(|(addr, _)| addr.as_str())
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        attr(
+            "fees",
+            fees.iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    ]))
+}
+
 /// Provides liquidity in the pair with the specified input parameters.
 ///
 /// * **assets** is an array with assets available in the pool.
@@ -251,16 +539,21 @@ pub fn update_fees(
 /// * **receiver** is an optional parameter which defines the receiver of the LP tokens.
 /// If no custom receiver is specified, the pair will mint LP tokens for the function caller.
 ///
+/// * **min_lp_amount** is an optional minimum amount of LP tokens that need to be minted.
+///
 /// NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+/// When cw20 tokens are provided, the pair continues with [`ExecuteMsg::ProvideReceived`] after pulling them,
+/// so that the deposits are the amounts it actually received.
 pub fn provide_liquidity(
-    mut deps: DepsMut,
+    deps: DepsMut,
     env: Env,
     info: MessageInfo,
     assets: Vec<Asset>,
     slippage_tolerance: Option<Decimal>,
     receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let mut assets = check_assets(deps.api, &assets)?;
+    let assets = check_assets(deps.api, &assets)?;
 
     if assets.len() > 2 {
         return Err(ContractError::TooManyAssets {
@@ -273,37 +566,150 @@ pub fn provide_liquidity(
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let mut config = CONFIG.load(deps.storage)?;
-    let mut pools = config
-        .pair_info
-        .query_pools(&deps.querier, &env.contract.address)?;
-
-    // maps an index in `assets` to the index of the same asset in `pools`
-    let mut pool_indices = assets
-        .iter()
-        .map(|a| {
-            pools
-                .iter()
-                .enumerate()
-                .find(|(_, pool)| pool.info.equal(&a.info))
-                .map(|(i, _)| i)
-                .ok_or_else(|| ContractError::InvalidAsset(a.info.to_string()))
-        })
-        .collect::<Result<Vec<_>, _>>()?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut pools = config.query_pools(&deps.querier, &env.contract.address)?;
+    let pool_indices = find_pool_indices(&assets, &pools)?;
 
     let mut messages = vec![];
 
-    for (i, asset) in assets.iter().enumerate() {
+    for asset in &assets {
         // take asset / make sure it actually got sent
         asset.receive(&env, &info, &mut messages)?;
+    }
+
+    // the received cw20 amounts are only known once the transfers went through
+    let prev_balances = cw20_balances_before(&deps.querier, &env.contract.address, &assets)?;
+    if !prev_balances.is_empty() {
+        messages.push(
+            wasm_execute(
+                &env.contract.address,
+                &ExecuteMsg::ProvideReceived {
+                    sender: info.sender.to_string(),
+                    assets: assets.into_iter().map(Into::into).collect(),
+                    prev_balances: prev_balances.into_iter().map(Into::into).collect(),
+                    slippage_tolerance,
+                    receiver,
+                    min_lp_amount,
+                },
+                vec![],
+            )?
+            .into(),
+        );
+        return Ok(Response::new().add_messages(messages));
+    }
+
+    for (asset, &i) in assets.iter().zip(&pool_indices) {
         // If the asset is native token, the pool balance is already increased
         // To calculate the total amount of deposits properly, we should subtract the user deposit from the pool
         if asset.info.is_native_token() {
-            let pool = &mut pools[pool_indices[i]].amount;
+            let pool = &mut pools[i].amount;
             *pool = pool.checked_sub(asset.amount)?;
         }
     }
 
+    provide_deposits(
+        deps,
+        env,
+        config,
+        info.sender,
+        assets,
+        pools,
+        pool_indices,
+        slippage_tolerance,
+        receiver,
+        min_lp_amount,
+        messages,
+    )
+}
+
+/// Continues a [`provide_liquidity`] with cw20 tokens after the pair pulled them,
+/// replacing the amounts of the pulled tokens by what the pair received.
+///
+/// ## Executor
+/// Only the pair itself can execute this.
+#[allow(clippy::too_many_arguments)]
+fn provide_received(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    assets: Vec<Asset>,
+    prev_balances: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let sender = deps.api.addr_validate(&sender)?;
+    let mut assets = check_assets(deps.api, &assets)?;
+    let prev_balances = check_assets(deps.api, &prev_balances)?;
+    replace_received_amounts(
+        &deps.querier,
+        &env.contract.address,
+        &mut assets,
+        &prev_balances,
+    )?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let mut pools = config.query_pools(&deps.querier, &env.contract.address)?;
+    let pool_indices = find_pool_indices(&assets, &pools)?;
+
+    // all deposits were transferred to the pair by now, so none of them are part of the pool
+    for (asset, &i) in assets.iter().zip(&pool_indices) {
+        let pool = &mut pools[i].amount;
+        *pool = pool.checked_sub(asset.amount)?;
+    }
+
+    provide_deposits(
+        deps,
+        env,
+        config,
+        sender,
+        assets,
+        pools,
+        pool_indices,
+        slippage_tolerance,
+        receiver,
+        min_lp_amount,
+        vec![],
+    )
+}
+
+/// Maps each index in `assets` to the index of the same asset in `pools`.
+fn find_pool_indices(
+    assets: &[AssetValidated],
+    pools: &[AssetValidated],
+) -> Result<Vec<usize>, ContractError> {
+    assets
+        .iter()
+        .map(|a| {
+            pools
+                .iter()
+                .position(|pool| pool.info.equal(&a.info))
+                .ok_or_else(|| ContractError::InvalidAsset(a.info.to_string()))
+        })
+        .collect()
+}
+
+/// Mints LP shares for the deposited `assets`.
+///
+/// Important: When providing the pool balances for this method, make sure that those do *not* include the deposits.
+#[allow(clippy::too_many_arguments)]
+fn provide_deposits(
+    mut deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    sender: Addr,
+    mut assets: Vec<AssetValidated>,
+    mut pools: Vec<AssetValidated>,
+    mut pool_indices: Vec<usize>,
+    slippage_tolerance: Option<Decimal>,
+    receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
+    mut messages: Vec<CosmosMsg>,
+) -> Result<Response, ContractError> {
     if assets.len() == 1 {
         let offer_asset = assets.pop().unwrap();
         if pool_indices[0] == 0 {
@@ -316,10 +722,14 @@ pub fn provide_liquidity(
             return Err(ContractError::InvalidProvideLPsWithSingleToken {});
         }
 
-        // use half for swapping
+        // use half for swapping, or the ask asset's share of the pool for weighted pairs
+        let swap_amount = match swap_weights(&config.pair_info, &offer_asset.info) {
+            Some((_, ask_weight)) => offer_asset.amount.multiply_ratio(ask_weight, WEIGHTS_BASIS),
+            None => offer_asset.amount / Uint128::from(2u128),
+        };
         let input_asset = AssetValidated {
             info: offer_asset.info.clone(),
-            amount: offer_asset.amount / Uint128::from(2u128),
+            amount: swap_amount,
         };
 
         // Get config from the factory
@@ -327,7 +737,8 @@ pub fn provide_liquidity(
         // swap half of the asset for the other first
         let SwapResult {
             return_asset,
-            protocol_fee_msg,
+            spread_amount,
+            commission_amount,
             protocol_fee_amount,
             ..
         } = do_swap(
@@ -337,17 +748,20 @@ pub fn provide_liquidity(
             &factory_config,
             &pools,
             &input_asset,
-            None,
-            None,
         )?;
-
-        // pay swap fee
-        if let Some(msg) = protocol_fee_msg {
-            messages.push(msg);
-            // remove from pool, protocol fee is denominated in returned asset, so index 1
-            pools[pool_indices[1]].amount -= protocol_fee_amount;
+        // a swap that is too small to return anything only adds the input to the pool
+        if spread_exceeds(
+            config.default_max_spread,
+            return_asset.amount + commission_amount,
+            spread_amount,
+        ) {
+            return Err(ContractError::MaxSpreadAssertion {});
         }
 
+        // the swap fee was accrued, so remove it from the pool.
+        // The protocol fee is denominated in the returned asset, so index 1
+        pools[pool_indices[1]].amount -= protocol_fee_amount;
+
         // swap input should now be considered part of the pool (since we swapped it for the other asset),
         // but return_asset should not (since it is considered the lp's deposit from now on)
         pools[pool_indices[0]].amount += input_asset.amount;
@@ -365,67 +779,30 @@ pub fn provide_liquidity(
         assets
             .iter()
             .find(|a| a.info.equal(&pools[0].info))
-            .expect("Wrong asset info is given"),
+            .expect("Wrong asset info is given")
+            .amount,
         assets
             .iter()
             .find(|a| a.info.equal(&pools[1].info))
-            .expect("Wrong asset info is given"),
+            .expect("Wrong asset info is given")
+            .amount,
     ];
 
-    if deposits[0].amount.is_zero() || deposits[1].amount.is_zero() {
-        return Err(ContractError::InvalidZeroAmount {});
-    }
-
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
-    let share = if total_share.is_zero() {
-        // Initial share = collateral amount
-        let share: Uint128 = deposits[0]
-            .amount
-            .full_mul(deposits[1].amount)
-            .isqrt()
-            .try_into()?;
-        let share = share
-            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT)
-            .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
-
-        messages.extend(mint_token_message(
-            &config.pair_info.liquidity_token,
+    let total_share = query_lp_supply(&deps.querier, &config)?;
+    let share = compute_provide_share(deposits, &pools, total_share, slippage_tolerance)?;
+    assert_min_lp_amount(share, min_lp_amount)?;
+    if total_share.is_zero() {
+        messages.extend(mint_lp_tokens(
+            &env,
+            &config,
             &env.contract.address,
             MINIMUM_LIQUIDITY_AMOUNT,
         )?);
-
-        // share cannot become zero after minimum liquidity subtraction
-        if share.is_zero() {
-            return Err(ContractError::MinimumLiquidityAmountError {});
-        }
-
-        share
-    } else {
-        // Assert slippage tolerance
-        assert_slippage_tolerance(slippage_tolerance, &deposits.map(|a| a.amount), &pools)?;
-
-        // min(1, 2)
-        // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_0))
-        // == deposit_0 * total_share / pool_0
-        // 2. sqrt(deposit_1 * exchange_rate_1_to_0 * deposit_1) * (total_share / sqrt(pool_1 * pool_1))
-        // == deposit_1 * total_share / pool_1
-        std::cmp::min(
-            deposits[0]
-                .amount
-                .multiply_ratio(total_share, pools[0].amount),
-            deposits[1]
-                .amount
-                .multiply_ratio(total_share, pools[1].amount),
-        )
-    };
+    }
 
     // Mint LP tokens for the sender or for the receiver (if set)
-    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
-    messages.extend(mint_token_message(
-        &config.pair_info.liquidity_token,
-        &receiver,
-        share,
-    )?);
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| sender.clone());
+    messages.extend(mint_lp_tokens(&env, &config, &receiver, share)?);
 
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
@@ -439,32 +816,96 @@ pub fn provide_liquidity(
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
         attr("action", "provide_liquidity"),
-        attr("sender", info.sender),
+        attr("sender", sender),
         attr("receiver", receiver),
         attr("assets", format!("{}, {}", assets[0], assets[1])),
         attr("share", share),
     ]))
 }
 
+/// Calculates the amount of LP tokens minted for the given deposits.
+/// The first deposit mints the geometric mean of the deposits minus the [`MINIMUM_LIQUIDITY_AMOUNT`]
+/// that stays locked in the pair.
+///
+/// * **deposits** are the deposited amounts, in the same order as the pools.
+///
+/// Important: When providing the pool balances for this method, make sure that those do *not* include the deposits.
+fn compute_provide_share(
+    deposits: [Uint128; 2],
+    pools: &[AssetValidated],
+    total_share: Uint128,
+    slippage_tolerance: Option<Decimal>,
+) -> Result<Uint128, ContractError> {
+    if deposits[0].is_zero() || deposits[1].is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+
+    if total_share.is_zero() {
+        // Initial share = collateral amount
+        let share: Uint128 = deposits[0].full_mul(deposits[1]).isqrt().try_into()?;
+        let share = share
+            .checked_sub(MINIMUM_LIQUIDITY_AMOUNT)
+            .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
+
+        // share cannot become zero after minimum liquidity subtraction
+        if share.is_zero() {
+            return Err(ContractError::MinimumLiquidityAmountError {});
+        }
+
+        return Ok(share);
+    }
+
+    // Assert slippage tolerance
+    assert_slippage_tolerance(slippage_tolerance, &deposits, pools)?;
+
+    // min(1, 2)
+    // 1. sqrt(deposit_0 * exchange_rate_0_to_1 * deposit_0) * (total_share / sqrt(pool_0 * pool_0))
+    // == deposit_0 * total_share / pool_0
+    // 2. sqrt(deposit_1 * exchange_rate_1_to_0 * deposit_1) * (total_share / sqrt(pool_1 * pool_1))
+    // == deposit_1 * total_share / pool_1
+    let share = std::cmp::min(
+        deposits[0].multiply_ratio(total_share, pools[0].amount),
+        deposits[1].multiply_ratio(total_share, pools[1].amount),
+    );
+
+    // do not take deposits that are too small to be worth a single LP token
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
+    }
+
+    Ok(share)
+}
+
 /// Withdraw liquidity from the pool.
 /// * **sender** is the address that will receive assets back from the pair contract.
 ///
 /// * **amount** is the amount of LP tokens to burn.
+///
+/// * **min_assets** optional minimum amounts of the assets to receive.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     sender: Addr,
     amount: Uint128,
+    min_assets: Option<Vec<Asset>>,
 ) -> Result<Response, ContractError> {
     let mut config = CONFIG.load(deps.storage).unwrap();
 
-    if info.sender != config.pair_info.liquidity_token {
+    // cw20 LP shares are sent by the LP token contract, native ones along with the message
+    let authorized = match config.lp_token_type {
+        LpTokenType::Cw20 => info.sender == config.pair_info.liquidity_token,
+        LpTokenType::Native => {
+            must_pay(&info, config.pair_info.liquidity_token.as_str())? == amount
+        }
+    };
+    if !authorized {
         return Err(ContractError::Unauthorized {});
     }
 
     let (pools, total_share) = pool_info(deps.querier, &config)?;
     let refund_assets = get_share_in_assets(&pools, amount, total_share);
+    assert_min_assets(deps.api, &refund_assets, min_assets)?;
 
     // Accumulate prices for the pair assets
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
@@ -480,11 +921,7 @@ pub fn withdraw_liquidity(
     let messages: Vec<CosmosMsg> = vec![
         refund_assets[0].clone().into_msg(sender.clone())?,
         refund_assets[1].clone().into_msg(sender.clone())?,
-        CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: config.pair_info.liquidity_token.to_string(),
-            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
-            funds: vec![],
-        }),
+        burn_lp_tokens(&env, &config, amount)?,
     ];
 
     Ok(Response::new().add_messages(messages).add_attributes(vec![
@@ -525,6 +962,9 @@ pub fn swap(
     referral_address: Option<Addr>,
     referral_commission: Option<Decimal>,
 ) -> Result<Response, ContractError> {
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
     offer_asset.assert_sent_native_token_balance(&info)?;
     let original_offer_asset = offer_asset.clone();
 
@@ -544,7 +984,6 @@ pub fn swap(
 
     // If the asset balance is already increased, we should subtract the user deposit from the pool amount
     let pools = config
-        .pair_info
         .query_pools(&deps.querier, &env.contract.address)?
         .into_iter()
         .map(|mut p| {
@@ -561,7 +1000,6 @@ pub fn swap(
         spread_amount,
         commission_amount,
         protocol_fee_amount,
-        protocol_fee_msg,
     } = do_swap(
         deps,
         &env,
@@ -569,35 +1007,44 @@ pub fn swap(
         &factory_config,
         &pools,
         &offer_asset,
+    )?;
+    if return_asset.amount.is_zero() {
+        return Err(ContractError::SwapTooSmall {});
+    }
+
+    // Check the max spread limit, falling back to the default max spread
+    assert_max_spread(
         belief_price,
-        max_spread,
+        max_spread.or(Some(config.default_max_spread)),
+        offer_asset.amount,
+        return_asset.amount + commission_amount,
+        spread_amount,
     )?;
 
     let receiver = to.unwrap_or_else(|| sender.clone());
+    let return_amount = return_asset.amount;
     messages.push(return_asset.into_msg(&receiver)?);
 
-    if let Some(msg) = protocol_fee_msg {
-        messages.push(msg);
-    }
-
     Ok(Response::new()
         .add_messages(
-            // 1. send collateral tokens from the contract to a user
-            // 2. send inactive commission fees to the protocol
+            // send collateral tokens from the contract to a user
             messages,
         )
         .add_attributes(vec![
             attr("action", "swap"),
             attr("sender", sender),
             attr("receiver", receiver),
-            attr("offer_asset", offer_asset.info.to_string()),
-            attr("ask_asset", ask_info.to_string()),
-            attr("offer_amount", offer_asset.amount),
-            attr("return_amount", return_asset.amount),
-            attr("spread_amount", spread_amount),
-            attr("commission_amount", commission_amount),
-            attr("protocol_fee_amount", protocol_fee_amount),
-        ]))
+        ])
+        .add_attributes(swap_attributes(
+            &offer_asset.info,
+            &ask_info,
+            offer_asset.amount,
+            return_amount,
+            spread_amount,
+            commission_amount,
+            original_offer_asset.amount - offer_asset.amount,
+        ))
+        .add_attribute("protocol_fee_amount", protocol_fee_amount))
 }
 
 struct SwapResult {
@@ -606,14 +1053,13 @@ struct SwapResult {
     spread_amount: Uint128,
     commission_amount: Uint128,
     protocol_fee_amount: Uint128,
-    protocol_fee_msg: Option<CosmosMsg>,
 }
 /// Helper method that executes a swap of one asset for another without needing to receive or send out the coins.
-/// Instead it returns the amount of the ask asset, as well as the protocol fee.
+/// Instead it returns the amount of the ask asset, as well as the protocol fee, which is accrued in the config.
 /// This method is useful for swapping in the middle of another message, where the coins are already in the contract.
+/// The caller has to check the spread of the swap.
 ///
 /// Important: When providing the pool balances for this method, make sure that those do *not* include the offer asset.
-#[allow(clippy::too_many_arguments)]
 fn do_swap(
     deps: DepsMut,
     env: &Env,
@@ -621,8 +1067,6 @@ fn do_swap(
     factory_config: &FactoryConfig,
     pools: &[AssetValidated],
     offer_asset: &AssetValidated,
-    belief_price: Option<Decimal>,
-    max_spread: Option<Decimal>,
 ) -> Result<SwapResult, ContractError> {
     if env.block.time.seconds() < config.trading_starts {
         return Err(ContractError::TradingNotStarted {});
@@ -640,24 +1084,14 @@ fn do_swap(
     } else {
         return Err(ContractError::AssetMismatch {});
     }
+    if offer_pool.amount.is_zero() || ask_pool.amount.is_zero() {
+        return Err(ContractError::PoolEmpty {});
+    }
 
     let offer_amount = offer_asset.amount;
 
-    let (return_amount, spread_amount, commission_amount) = compute_swap(
-        offer_pool.amount,
-        ask_pool.amount,
-        offer_amount,
-        config.pair_info.fee_config.total_fee_rate(),
-    )?;
-
-    // Check the max spread limit (if it was specified)
-    assert_max_spread(
-        belief_price,
-        max_spread,
-        offer_amount,
-        return_amount + commission_amount,
-        spread_amount,
-    )?;
+    let (return_amount, spread_amount, commission_amount) =
+        compute_pair_swap(&config.pair_info, &offer_pool, &ask_pool, offer_amount)?;
 
     // Compute the tax for the receiving asset (if it is a native one)
     let return_asset = AssetValidated {
@@ -665,20 +1099,31 @@ fn do_swap(
         amount: return_amount,
     };
 
-    // Compute the protocol fee
-    let mut fee_msg = None;
+    // Compute the protocol fee, which stays in the pair until it is collected
     let mut protocol_fee_amount = Uint128::zero();
-    if let Some(ref fee_address) = factory_config.fee_address {
+    if !factory_config.protocol_fee_recipients().is_empty() {
         if let Some(f) = calculate_protocol_fee(
             &ask_pool.info,
             commission_amount,
             config.pair_info.fee_config.protocol_fee_rate(),
         ) {
             protocol_fee_amount = f.amount;
-            fee_msg = Some(f.into_msg(fee_address)?);
+            accrue_fee(&mut config.accrued_protocol_fees, f)?;
         }
     }
 
+    // take a snapshot of the volume every once in a while to be able to estimate it over a time window
+    let now = env.block.time.seconds();
+    let last_snapshot = VOLUME_SNAPSHOTS
+        .keys(deps.storage, None, None, Order::Descending)
+        .next()
+        .transpose()?;
+    if last_snapshot.map_or(true, |time| time + VOLUME_SNAPSHOT_INTERVAL <= now) {
+        VOLUME_SNAPSHOTS.save(deps.storage, now, &config.offered_volume)?;
+    }
+    accumulate_volume(&mut config.offered_volume, offer_asset);
+    accumulate_volume(&mut config.returned_volume, &return_asset);
+
     // Accumulate prices for the assets in the pool
     if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(env, config, pools[0].amount, pools[1].amount)?
@@ -686,8 +1131,8 @@ fn do_swap(
         config.price0_cumulative_last = price0_cumulative_new;
         config.price1_cumulative_last = price1_cumulative_new;
         config.block_time_last = block_time;
-        CONFIG.save(deps.storage, config)?;
     }
+    CONFIG.save(deps.storage, config)?;
 
     Ok(SwapResult {
         return_asset,
@@ -695,7 +1140,6 @@ fn do_swap(
         spread_amount,
         commission_amount,
         protocol_fee_amount,
-        protocol_fee_msg: fee_msg,
     })
 }
 
@@ -724,6 +1168,14 @@ pub fn accumulate_prices(
     let mut pcl1 = config.price1_cumulative_last;
 
     if !x.is_zero() && !y.is_zero() {
+        // the spot price of a weighted pair is adjusted by the ratio of the weights
+        let (w0, w1) = config
+            .pair_info
+            .pair_type
+            .weights()
+            .map_or((1u128, 1u128), |[w0, w1]| (w0.into(), w1.into()));
+        let (x, y) = (x.checked_mul(w1.into())?, y.checked_mul(w0.into())?);
+
         let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
         pcl0 = config.price0_cumulative_last.wrapping_add(
             time_elapsed
@@ -775,21 +1227,59 @@ pub fn calculate_protocol_fee(
 /// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
 /// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
 ///
+/// * **QueryMsg::SimulateWithdraw { amount }** Returns the amount of assets that withdrawing a specific amount
+/// of LP tokens would return. Fails if there is no liquidity or the amount exceeds the LP token supply.
+///
+/// * **QueryMsg::SharesToAssets { shares }** Returns the assets that the given amount of LP-equivalent shares,
+/// such as the shares of an sg721-pair NFT, are redeemable for. Calculated like `SimulateWithdraw`.
+///
+/// * **QueryMsg::SimulateProvide { assets, slippage_tolerance }** Returns the amount of LP tokens that providing
+/// the given assets would mint using a [`SimulateProvideResponse`] object.
+///
 /// * **QueryMsg::Simulation { offer_asset }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
 ///
+/// * **QueryMsg::EstimateSwapFees { offer_asset }** Returns the LP fee, protocol fee and spread of a swap
+/// using an [`EstimateSwapFeesResponse`] object.
+///
 /// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation  using
 /// a [`ReverseSimulationResponse`] object.
 ///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
+/// * **QueryMsg::Twap { start_cumulative, start_time }** Returns the time-weighted average prices
+/// since the given snapshot using a [`TwapResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer, ask }** Returns the current marginal price of `offer` in terms of `ask`.
+///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::AccruedProtocolFees {}** Returns the protocol fees that were not collected yet.
+///
+/// * **QueryMsg::CumulativeVolume {}** Returns the total amounts offered to and returned by the pair in swaps
+/// using a [`CumulativeVolumeResponse`] object.
+///
+/// * **QueryMsg::PoolState {}** Returns the reserves, LP token supply, fees, pair type and trading start
+/// of the pool using a [`PoolStateResponse`] object.
+///
+/// * **QueryMsg::FeeApr { window_seconds }** Returns the estimated annualized yield of the swap fees.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Pair {} => to_binary(&CONFIG.load(deps.storage)?.pair_info),
         QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
         QueryMsg::Share { amount } => to_binary(&query_share(deps, amount)?),
+        QueryMsg::SimulateWithdraw { amount } => to_binary(&query_simulate_withdraw(deps, amount)?),
+        QueryMsg::SharesToAssets { shares } => to_binary(&query_simulate_withdraw(deps, shares)?),
+        QueryMsg::SimulateProvide {
+            assets,
+            slippage_tolerance,
+        } => to_binary(&query_simulate_provide(
+            deps,
+            env,
+            assets,
+            slippage_tolerance,
+        )?),
         QueryMsg::Simulation {
             offer_asset,
             referral,
@@ -801,6 +1291,9 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             referral,
             referral_commission,
         )?),
+        QueryMsg::EstimateSwapFees { offer_asset, .. } => {
+            to_binary(&query_estimate_swap_fees(deps, offer_asset)?)
+        }
         QueryMsg::ReverseSimulation {
             ask_asset,
             referral,
@@ -813,11 +1306,56 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             referral_commission,
         )?),
         QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::Twap {
+            start_cumulative,
+            start_time,
+        } => to_binary(&query_twap(deps, env, start_cumulative, start_time)?),
+        QueryMsg::SpotPrice { offer, ask } => to_binary(&query_spot_price(deps, offer, ask)?),
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
+        QueryMsg::AccruedProtocolFees {} => {
+            to_binary(&CONFIG.load(deps.storage)?.accrued_protocol_fees)
+        }
+        QueryMsg::PoolState {} => to_binary(&query_pool_state(deps)?),
+        QueryMsg::CumulativeVolume {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&CumulativeVolumeResponse {
+                offered: config.offered_volume,
+                returned: config.returned_volume,
+            })
+        }
+        QueryMsg::FeeApr { window_seconds } => {
+            to_binary(&query_fee_apr(deps, env, window_seconds)?)
+        }
         _ => Err(StdError::generic_err("Query is not supported")),
     }
 }
 
+/// Manages the contract migration.
+/// New fields of the stored config have defaults, so it is read as is.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // Rejects downgrades and migrations from other contracts
+    let previous_version =
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // start measuring the cw20 tokens received with a hook
+    let asset_infos = CONFIG.load(deps.storage)?.pair_info.asset_infos;
+    save_cw20_reserves(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &asset_infos,
+        &cw20_tokens(&asset_infos),
+        &Response::new(),
+    )?;
+
+    Ok(Response::new().add_attributes([
+        attr("action", "migrate"),
+        attr("previous_contract_version", previous_version.to_string()),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
+}
+
 /// Returns the amounts of assets in the pair contract as well as the amount of LP
 /// tokens currently minted in an object of type [`PoolResponse`].
 pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
@@ -832,6 +1370,82 @@ pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     Ok(resp)
 }
 
+/// Returns the combined state of the pool in a [`PoolStateResponse`] object.
+pub fn query_pool_state(deps: Deps) -> StdResult<PoolStateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps.querier, &config)?;
+
+    Ok(PoolStateResponse {
+        assets,
+        total_share,
+        fee_config: config.pair_info.fee_config,
+        pair_type: config.pair_info.pair_type,
+        trading_starts: config.trading_starts,
+    })
+}
+
+/// Estimates the annualized yield of the swap fees for liquidity providers.
+///
+/// The volume is measured from the last snapshot before the window (or the first one, if the pair is younger),
+/// so the actual window is slightly longer than `window_seconds`.
+/// Each asset's volume is valued relative to its current reserve, which is worth its weight in the whole pool,
+/// so no external prices are needed.
+pub fn query_fee_apr(deps: Deps, env: Env, window_seconds: u64) -> StdResult<Decimal> {
+    let config = CONFIG.load(deps.storage)?;
+    let now = env.block.time.seconds();
+
+    let start = now.saturating_sub(window_seconds);
+    let snapshot = match VOLUME_SNAPSHOTS
+        .range(
+            deps.storage,
+            None,
+            Some(Bound::inclusive(start)),
+            Order::Descending,
+        )
+        .next()
+    {
+        Some(snapshot) => Some(snapshot?),
+        None => VOLUME_SNAPSHOTS
+            .range(deps.storage, None, None, Order::Ascending)
+            .next()
+            .transpose()?,
+    };
+    let (start_time, start_volume) = match snapshot {
+        Some(snapshot) if snapshot.0 < now => snapshot,
+        // no volume to measure yet
+        _ => return Ok(Decimal::zero()),
+    };
+
+    let volume_of = |volume: &[AssetValidated], info: &AssetInfoValidated| {
+        volume
+            .iter()
+            .find(|asset| asset.info.equal(info))
+            .map(|asset| asset.amount)
+            .unwrap_or_default()
+    };
+    let weights = config
+        .pair_info
+        .pair_type
+        .weights()
+        .unwrap_or([WEIGHTS_BASIS / 2; 2]);
+    let pools = config.query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+
+    let mut volume_yield = Decimal::zero();
+    for (pool, weight) in pools.iter().zip(weights) {
+        if pool.amount.is_zero() {
+            continue;
+        }
+        let volume = volume_of(&config.offered_volume, &pool.info)
+            .saturating_sub(volume_of(&start_volume, &pool.info));
+        volume_yield +=
+            Decimal::from_ratio(volume, pool.amount) * Decimal::from_ratio(weight, WEIGHTS_BASIS);
+    }
+
+    Ok(volume_yield
+        * config.pair_info.fee_config.total_fee_rate()
+        * Decimal::from_ratio(SECONDS_PER_YEAR, now - start_time))
+}
+
 /// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
 /// The result is returned in a vector that contains objects of type [`Asset`].
 ///
@@ -844,6 +1458,107 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<AssetValidated>
     Ok(refund_assets)
 }
 
+/// Returns the amount of assets that withdrawing a specific amount of LP tokens would return.
+/// This uses the same calculation as a balanced withdrawal of liquidity.
+///
+/// * **amount** is the amount of LP tokens to withdraw.
+pub fn query_simulate_withdraw(deps: Deps, amount: Uint128) -> StdResult<Vec<AssetValidated>> {
+    let config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps.querier, &config)?;
+
+    simulate_withdraw(&pools, amount, total_share)
+}
+
+/// Returns the amount of LP tokens that providing the given assets would mint in a [`SimulateProvideResponse`] object.
+/// Providing a single asset is simulated like in [`provide_liquidity`], by swapping a part of it first.
+///
+/// * **assets** are the assets to provide.
+///
+/// * **slippage_tolerance** is the slippage tolerance to check the deposit against.
+pub fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: Vec<Asset>,
+    slippage_tolerance: Option<Decimal>,
+) -> StdResult<SimulateProvideResponse> {
+    let assets = check_assets(deps.api, &assets)?;
+
+    if assets.len() > 2 {
+        return Err(ContractError::TooManyAssets {
+            max: 2,
+            provided: assets.len(),
+        }
+        .into());
+    }
+
+    if assets.iter().all(|a| a.amount.is_zero()) {
+        return Err(ContractError::InvalidZeroAmount {}.into());
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let (mut pools, total_share) = pool_info(deps.querier, &config)?;
+
+    // the deposits in the same order as the pools
+    let mut deposits = [Uint128::zero(); 2];
+    for asset in &assets {
+        let idx = pools
+            .iter()
+            .position(|pool| pool.info.equal(&asset.info))
+            .ok_or_else(|| ContractError::InvalidAsset(asset.info.to_string()))?;
+        deposits[idx] += asset.amount;
+    }
+
+    if assets.len() == 1 {
+        let (offer_idx, ask_idx) = if deposits[0].is_zero() {
+            (1, 0)
+        } else {
+            (0, 1)
+        };
+        // We cannot swap with an empty pool.
+        if pools[ask_idx].amount.is_zero() {
+            return Err(ContractError::InvalidProvideLPsWithSingleToken {}.into());
+        }
+        if env.block.time.seconds() < config.trading_starts {
+            return Err(ContractError::TradingNotStarted {}.into());
+        }
+
+        // use half for swapping, or the ask asset's share of the pool for weighted pairs
+        let offer_amount = deposits[offer_idx];
+        let swap_amount = match swap_weights(&config.pair_info, &pools[offer_idx].info) {
+            Some((_, ask_weight)) => offer_amount.multiply_ratio(ask_weight, WEIGHTS_BASIS),
+            None => offer_amount / Uint128::from(2u128),
+        };
+        let (return_amount, _, commission_amount) = compute_pair_swap(
+            &config.pair_info,
+            &pools[offer_idx],
+            &pools[ask_idx],
+            swap_amount,
+        )?;
+
+        // the protocol fee is sent out of the pool
+        let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+        if !factory_config.protocol_fee_recipients().is_empty() {
+            if let Some(protocol_fee) = calculate_protocol_fee(
+                &pools[ask_idx].info,
+                commission_amount,
+                config.pair_info.fee_config.protocol_fee_rate(),
+            ) {
+                pools[ask_idx].amount -= protocol_fee.amount;
+            }
+        }
+
+        pools[offer_idx].amount += swap_amount;
+        pools[ask_idx].amount -= return_amount;
+        deposits[offer_idx] = offer_amount - swap_amount;
+        deposits[ask_idx] = return_amount;
+    }
+
+    Ok(SimulateProvideResponse {
+        share: compute_provide_share(deposits, &pools, total_share, slippage_tolerance)?,
+        imbalance_fee: vec![],
+    })
+}
+
 /// Returns information about a swap simulation in a [`SimulationResponse`] object.
 ///
 /// * **offer_asset** is the asset to swap as well as an amount of the said asset.
@@ -854,6 +1569,9 @@ pub fn query_simulation(
     referral_commission: Option<Decimal>,
 ) -> StdResult<SimulationResponse> {
     let mut offer_asset = offer_asset.validate(deps.api)?;
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {}.into());
+    }
     let config = CONFIG.load(deps.storage)?;
 
     let referral_amount = if referral {
@@ -863,9 +1581,7 @@ pub fn query_simulation(
         Uint128::zero()
     };
 
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools = config.query_pools(&deps.querier, &config.pair_info.contract_addr)?;
 
     let offer_pool: AssetValidated;
     let ask_pool: AssetValidated;
@@ -881,18 +1597,53 @@ pub fn query_simulation(
         ));
     }
 
-    let (return_amount, spread_amount, commission_amount) = compute_swap(
-        offer_pool.amount,
-        ask_pool.amount,
+    let (return_amount, spread_amount, commission_amount) = compute_pair_swap(
+        &config.pair_info,
+        &offer_pool,
+        &ask_pool,
         offer_asset.amount,
-        config.pair_info.fee_config.total_fee_rate(),
     )?;
+    // same check as in `swap`
+    if return_amount.is_zero() {
+        return Err(ContractError::SwapTooSmall {}.into());
+    }
 
     Ok(SimulationResponse {
         return_amount,
         spread_amount,
         commission_amount,
         referral_amount,
+        // same values as the max spread check in `swap`
+        spread_exceeds_default: spread_exceeds(
+            config.default_max_spread,
+            return_amount + commission_amount,
+            spread_amount,
+        ),
+    })
+}
+
+/// Returns the fee breakdown of a swap in an [`EstimateSwapFeesResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+pub fn query_estimate_swap_fees(
+    deps: Deps,
+    offer_asset: Asset,
+) -> StdResult<EstimateSwapFeesResponse> {
+    let simulation = query_simulation(deps, offer_asset, false, None)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // same condition as in `swap`, the protocol fee is only taken if there are recipients
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let protocol_fee = if factory_config.protocol_fee_recipients().is_empty() {
+        Uint128::zero()
+    } else {
+        simulation.commission_amount * config.pair_info.fee_config.protocol_fee_rate()
+    };
+    Ok(EstimateSwapFeesResponse {
+        lp_fee: simulation.commission_amount - protocol_fee,
+        protocol_fee,
+        spread: simulation.spread_amount,
+        return_amount: simulation.return_amount,
     })
 }
 
@@ -907,11 +1658,12 @@ pub fn query_reverse_simulation(
     referral_commission: Option<Decimal>,
 ) -> StdResult<ReverseSimulationResponse> {
     let ask_asset = ask_asset.validate(deps.api)?;
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {}.into());
+    }
     let config = CONFIG.load(deps.storage)?;
 
-    let pools = config
-        .pair_info
-        .query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools = config.query_pools(&deps.querier, &config.pair_info.contract_addr)?;
 
     let offer_pool: AssetValidated;
     let ask_pool: AssetValidated;
@@ -927,12 +1679,8 @@ pub fn query_reverse_simulation(
         ));
     }
 
-    let (offer_amount, spread_amount, commission_amount) = compute_offer_amount(
-        offer_pool.amount,
-        ask_pool.amount,
-        ask_asset.amount,
-        config.pair_info.fee_config.total_fee_rate(),
-    )?;
+    let (offer_amount, spread_amount, commission_amount) =
+        compute_pair_offer_amount(&config.pair_info, &offer_pool, &ask_pool, ask_asset.amount)?;
 
     // `offer_pool.info` is already validated
     let offer_asset = AssetValidated {
@@ -962,12 +1710,14 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
 
     let mut price0_cumulative_last = config.price0_cumulative_last;
     let mut price1_cumulative_last = config.price1_cumulative_last;
+    let mut block_time_last = config.block_time_last;
 
-    if let Some((price0_cumulative_new, price1_cumulative_new, _)) =
+    if let Some((price0_cumulative_new, price1_cumulative_new, block_time)) =
         accumulate_prices(&env, &config, assets[0].amount, assets[1].amount)?
     {
         price0_cumulative_last = price0_cumulative_new;
         price1_cumulative_last = price1_cumulative_new;
+        block_time_last = block_time;
     }
 
     let cumulative_prices = vec![
@@ -987,11 +1737,59 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
         assets,
         total_share,
         cumulative_prices,
+        block_time_last,
     };
 
     Ok(resp)
 }
 
+/// Returns the time-weighted average prices since the given snapshot using a [`TwapResponse`] object.
+pub fn query_twap(
+    deps: Deps,
+    env: Env,
+    start_cumulative: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    start_time: u64,
+) -> StdResult<TwapResponse> {
+    let current = query_cumulative_prices(deps, env)?;
+    calculate_twap(deps.api, current, start_cumulative, start_time)
+}
+
+/// Returns the marginal price of one unit of `offer` in terms of `ask` at the current pool balances,
+/// after deducting the swap fee.
+pub fn query_spot_price(deps: Deps, offer: AssetInfo, ask: AssetInfo) -> StdResult<Decimal> {
+    let offer = offer.validate(deps.api)?;
+    let ask = ask.validate(deps.api)?;
+    if offer.equal(&ask) {
+        return Err(StdError::generic_err(
+            "The offer asset and ask asset cannot be the same.",
+        ));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let pools = config.query_pools(&deps.querier, &config.pair_info.contract_addr)?;
+    let find_pool = |info: &AssetInfoValidated| {
+        pools
+            .iter()
+            .find(|pool| pool.info.equal(info))
+            .ok_or_else(|| {
+                StdError::generic_err(format!("Asset {info} does not belong in the pair"))
+            })
+    };
+    let offer_pool = find_pool(&offer)?;
+    let ask_pool = find_pool(&ask)?;
+    if offer_pool.amount.is_zero() || ask_pool.amount.is_zero() {
+        return Err(ContractError::PoolEmpty {}.into());
+    }
+
+    let (offer_weight, ask_weight) = swap_weights(&config.pair_info, &offer).unwrap_or((1, 1));
+    let price = Decimal::checked_from_ratio(
+        ask_pool.amount.checked_mul(offer_weight.into())?,
+        offer_pool.amount.checked_mul(ask_weight.into())?,
+    )
+    .map_err(|err| StdError::generic_err(err.to_string()))?;
+    Ok(price * (Decimal::one() - config.pair_info.fee_config.total_fee_rate()))
+}
+
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
     let config: Config = CONFIG.load(deps.storage)?;
@@ -999,9 +1797,72 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         block_time_last: config.block_time_last,
         params: None,
         owner: None,
+        default_max_spread: config.default_max_spread,
+    })
+}
+
+/// Returns the weights of the offer and the ask asset if the pair is a weighted pair.
+fn swap_weights(pair_info: &PairInfo, offer_asset: &AssetInfoValidated) -> Option<(u16, u16)> {
+    pair_info.pair_type.weights().map(|[w0, w1]| {
+        if offer_asset.equal(&pair_info.asset_infos[0]) {
+            (w0, w1)
+        } else {
+            (w1, w0)
+        }
     })
 }
 
+/// Returns the result of a swap, using the weighted math for weighted pairs.
+fn compute_pair_swap(
+    pair_info: &PairInfo,
+    offer_pool: &AssetValidated,
+    ask_pool: &AssetValidated,
+    offer_amount: Uint128,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let commission_rate = pair_info.fee_config.total_fee_rate();
+    match swap_weights(pair_info, &offer_pool.info) {
+        Some(weights) => compute_weighted_swap(
+            offer_pool.amount,
+            ask_pool.amount,
+            weights,
+            offer_amount,
+            commission_rate,
+        ),
+        None => compute_swap(
+            offer_pool.amount,
+            ask_pool.amount,
+            offer_amount,
+            commission_rate,
+        ),
+    }
+}
+
+/// Returns an amount of offer assets for a specified amount of ask assets,
+/// using the weighted math for weighted pairs.
+fn compute_pair_offer_amount(
+    pair_info: &PairInfo,
+    offer_pool: &AssetValidated,
+    ask_pool: &AssetValidated,
+    ask_amount: Uint128,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    let commission_rate = pair_info.fee_config.total_fee_rate();
+    match swap_weights(pair_info, &offer_pool.info) {
+        Some(weights) => compute_weighted_offer_amount(
+            offer_pool.amount,
+            ask_pool.amount,
+            weights,
+            ask_amount,
+            commission_rate,
+        ),
+        None => compute_offer_amount(
+            offer_pool.amount,
+            ask_pool.amount,
+            ask_amount,
+            commission_rate,
+        ),
+    }
+}
+
 /// Returns the result of a swap.
 ///
 /// * **offer_pool** total amount of offer assets in the pool.
@@ -1068,15 +1929,17 @@ pub fn compute_offer_amount(
     let one_minus_commission = Decimal256::one() - decimal2decimal256(commission_rate)?;
     let inv_one_minus_commission = Decimal256::one() / one_minus_commission;
 
+    let remaining_ask_pool =
+        ask_pool.checked_sub((Uint256::from(ask_amount) * inv_one_minus_commission).try_into()?)?;
+    // asking for the whole pool would need an infinite offer amount
+    if remaining_ask_pool.is_zero() {
+        return Err(StdError::generic_err(
+            "Ask amount must be less than the ask pool",
+        ));
+    }
+
     let offer_amount: Uint128 = cp
-        .multiply_ratio(
-            Uint256::from(1u8),
-            Uint256::from(
-                ask_pool.checked_sub(
-                    (Uint256::from(ask_amount) * inv_one_minus_commission).try_into()?,
-                )?,
-            ),
-        )
+        .multiply_ratio(Uint256::from(1u8), Uint256::from(remaining_ask_pool))
         .checked_sub(offer_pool.into())?
         .try_into()?;
 
@@ -1129,10 +1992,65 @@ pub fn pool_info(
     querier: QuerierWrapper,
     config: &Config,
 ) -> StdResult<(Vec<AssetValidated>, Uint128)> {
-    let pools = config
-        .pair_info
-        .query_pools(&querier, &config.pair_info.contract_addr)?;
-    let total_share = query_supply(&querier, &config.pair_info.liquidity_token)?;
+    let pools = config.query_pools(&querier, &config.pair_info.contract_addr)?;
+    let total_share = query_lp_supply(&querier, config)?;
 
     Ok((pools, total_share))
 }
+
+/// Returns the total amount of LP tokens currently minted.
+fn query_lp_supply(querier: &QuerierWrapper, config: &Config) -> StdResult<Uint128> {
+    match config.lp_token_type {
+        LpTokenType::Cw20 => query_supply(querier, &config.pair_info.liquidity_token),
+        LpTokenType::Native => {
+            token_factory::query_supply(querier, config.pair_info.liquidity_token.as_str())
+        }
+    }
+}
+
+/// Returns the messages to mint `amount` LP tokens for the `recipient`.
+fn mint_lp_tokens(
+    env: &Env,
+    config: &Config,
+    recipient: &Addr,
+    amount: Uint128,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    match config.lp_token_type {
+        LpTokenType::Cw20 => {
+            mint_token_message(&config.pair_info.liquidity_token, recipient, amount)
+        }
+        LpTokenType::Native => {
+            let amount = coin(amount.u128(), config.pair_info.liquidity_token.as_str());
+            let mut messages = vec![token_factory::mint_msg(
+                &env.contract.address,
+                amount.clone(),
+            )?];
+            // the pair keeps the minimum liquidity it mints for itself
+            if recipient != env.contract.address {
+                messages.push(
+                    BankMsg::Send {
+                        to_address: recipient.to_string(),
+                        amount: vec![amount],
+                    }
+                    .into(),
+                );
+            }
+            Ok(messages)
+        }
+    }
+}
+
+/// Returns the message to burn `amount` LP tokens that were sent to the pair.
+fn burn_lp_tokens(env: &Env, config: &Config, amount: Uint128) -> Result<CosmosMsg, ContractError> {
+    match config.lp_token_type {
+        LpTokenType::Cw20 => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: config.pair_info.liquidity_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Burn { amount })?,
+            funds: vec![],
+        })),
+        LpTokenType::Native => token_factory::burn_msg(
+            &env.contract.address,
+            coin(amount.u128(), config.pair_info.liquidity_token.as_str()),
+        ),
+    }
+}
@@ -1,5 +1,7 @@
 pub mod contract;
+pub mod math;
 pub mod state;
+pub mod token_factory;
 
 #[cfg(test)]
 mod mock_querier;
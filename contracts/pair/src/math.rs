@@ -0,0 +1,277 @@
+use cosmwasm_std::{Decimal, Decimal256, StdError, StdResult, Uint128, Uint256};
+use sg_swap::asset::check_swap_parameters;
+use sg_swap::decimal2decimal256;
+
+/// The maximum share of the offer pool that can be swapped in at once in a weighted pool.
+const MAX_IN_RATIO_PERCENT: u64 = 50;
+/// The maximum share of the ask pool that can be swapped out at once in a weighted pool.
+const MAX_OUT_RATIO_PERCENT: u64 = 30;
+/// The precision at which the approximation of fractional powers is stopped.
+const POW_PRECISION: u128 = 10_000_000_000;
+
+/// Calculates `base ^ exp` for a base between 0 and 2.
+///
+/// The integer part of the exponent is calculated exactly, while the fractional part is
+/// approximated with the binomial series.
+pub(crate) fn pow(base: Decimal256, exp: Decimal256) -> StdResult<Decimal256> {
+    if base.is_zero() || base >= Decimal256::from_ratio(2u8, 1u8) {
+        return Err(StdError::generic_err(
+            "Base of the power must be between 0 and 2",
+        ));
+    }
+
+    let whole = exp.atomics() / Decimal256::one().atomics();
+    let remainder = exp - Decimal256::from_ratio(whole, 1u8);
+    let whole = u32::try_from(Uint128::try_from(whole)?.u128())
+        .map_err(|_| StdError::generic_err("Exponent is too large"))?;
+    let whole_pow = base.checked_pow(whole)?;
+
+    if remainder.is_zero() {
+        return Ok(whole_pow);
+    }
+
+    Ok(whole_pow.checked_mul(pow_approx(base, remainder))?)
+}
+
+/// Approximates `base ^ exp` for an exponent between 0 and 1,
+/// using the binomial series `(1 + x) ^ exp = sum(binom(exp, k) * x ^ k)`.
+fn pow_approx(base: Decimal256, exp: Decimal256) -> Decimal256 {
+    let precision = Decimal256::from_ratio(1u8, POW_PRECISION);
+    let (x, x_neg) = abs_diff(base, Decimal256::one());
+
+    let mut term = Decimal256::one();
+    let mut sum = Decimal256::one();
+    let mut negative = false;
+
+    // Each term is `term(k-1) * (exp - (k-1)) * x / k`.
+    // Since 0 < base < 2, |x| < 1 and the terms converge towards zero.
+    let mut k = 1u128;
+    while term >= precision {
+        let big_k = Decimal256::from_ratio(k, 1u8);
+        let (c, c_neg) = abs_diff(exp, big_k - Decimal256::one());
+        term = term * c * x / big_k;
+        if term.is_zero() {
+            break;
+        }
+
+        if x_neg {
+            negative = !negative;
+        }
+        if c_neg {
+            negative = !negative;
+        }
+        sum = if negative { sum - term } else { sum + term };
+
+        k += 1;
+    }
+
+    sum
+}
+
+/// Returns `|a - b|` and whether `a - b` is negative.
+fn abs_diff(a: Decimal256, b: Decimal256) -> (Decimal256, bool) {
+    if a >= b {
+        (a - b, false)
+    } else {
+        (b - a, true)
+    }
+}
+
+/// Returns the result of a swap in a weighted pool.
+///
+/// * **offer_pool** total amount of offer assets in the pool.
+///
+/// * **ask_pool** total amount of ask assets in the pool.
+///
+/// * **weights** the weights of the offer and ask assets.
+///
+/// * **offer_amount** amount of offer assets to swap.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_weighted_swap(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    (offer_weight, ask_weight): (u16, u16),
+    offer_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // offer => ask
+    check_swap_parameters(vec![offer_pool, ask_pool], offer_amount)?;
+
+    if offer_amount > offer_pool * Decimal::percent(MAX_IN_RATIO_PERCENT) {
+        return Err(StdError::generic_err(format!(
+            "Offer amount must not exceed {MAX_IN_RATIO_PERCENT}% of the offer pool"
+        )));
+    }
+
+    let offer_pool: Uint256 = offer_pool.into();
+    let ask_pool: Uint256 = ask_pool.into();
+    let offer_amount: Uint256 = offer_amount.into();
+    let commission_rate = decimal2decimal256(commission_rate)?;
+
+    // ask_amount = ask_pool * (1 - (offer_pool / (offer_pool + offer_amount)) ^ (offer_weight / ask_weight))
+    let factor = pow(
+        Decimal256::from_ratio(offer_pool, offer_pool + offer_amount),
+        Decimal256::from_ratio(offer_weight, ask_weight),
+    )?;
+    let return_amount = if factor < Decimal256::one() {
+        ask_pool * (Decimal256::one() - factor)
+    } else {
+        Uint256::zero()
+    };
+
+    // Calculate spread & commission
+    let spot_price = Decimal256::from_ratio(
+        ask_pool * Uint256::from(offer_weight),
+        offer_pool * Uint256::from(ask_weight),
+    );
+    let spread_amount = (offer_amount * spot_price).saturating_sub(return_amount);
+    let commission_amount: Uint256 = return_amount * commission_rate;
+
+    // The commision (minus the part that goes to the protocol) will be absorbed by the pool
+    let return_amount: Uint256 = return_amount - commission_amount;
+    Ok((
+        return_amount.try_into()?,
+        spread_amount.try_into()?,
+        commission_amount.try_into()?,
+    ))
+}
+
+/// Returns an amount of offer assets for a specified amount of ask assets in a weighted pool.
+///
+/// * **offer_pool** total amount of offer assets in the pool.
+///
+/// * **ask_pool** total amount of ask assets in the pool.
+///
+/// * **weights** the weights of the offer and ask assets.
+///
+/// * **ask_amount** amount of ask assets to swap to.
+///
+/// * **commission_rate** total amount of fees charged for the swap.
+pub fn compute_weighted_offer_amount(
+    offer_pool: Uint128,
+    ask_pool: Uint128,
+    (offer_weight, ask_weight): (u16, u16),
+    ask_amount: Uint128,
+    commission_rate: Decimal,
+) -> StdResult<(Uint128, Uint128, Uint128)> {
+    // ask => offer
+    check_swap_parameters(vec![offer_pool, ask_pool], ask_amount)?;
+
+    let commission_rate = decimal2decimal256(commission_rate)?;
+    let inv_one_minus_commission = Decimal256::one() / (Decimal256::one() - commission_rate);
+    let before_commission_deduction = Uint256::from(ask_amount) * inv_one_minus_commission;
+
+    let ask_pool: Uint256 = ask_pool.into();
+    let offer_pool: Uint256 = offer_pool.into();
+    if before_commission_deduction > ask_pool * Decimal256::percent(MAX_OUT_RATIO_PERCENT) {
+        return Err(StdError::generic_err(format!(
+            "Ask amount must not exceed {MAX_OUT_RATIO_PERCENT}% of the ask pool"
+        )));
+    }
+
+    // offer_amount = offer_pool * ((ask_pool / (ask_pool - ask_amount / (1 - commission_rate))) ^ (ask_weight / offer_weight) - 1)
+    let factor = pow(
+        Decimal256::from_ratio(ask_pool, ask_pool - before_commission_deduction),
+        Decimal256::from_ratio(ask_weight, offer_weight),
+    )?;
+    let offer_amount = if factor > Decimal256::one() {
+        offer_pool * (factor - Decimal256::one())
+    } else {
+        Uint256::zero()
+    };
+
+    let spot_price = Decimal256::from_ratio(
+        ask_pool * Uint256::from(offer_weight),
+        offer_pool * Uint256::from(ask_weight),
+    );
+    let spread_amount = (offer_amount * spot_price).saturating_sub(before_commission_deduction);
+    let commission_amount = before_commission_deduction * commission_rate;
+    Ok((
+        offer_amount.try_into()?,
+        spread_amount.try_into()?,
+        commission_amount.try_into()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{compute_offer_amount, compute_swap};
+    use std::str::FromStr;
+
+    fn assert_close(actual: Decimal256, expected: &str) {
+        let expected = Decimal256::from_str(expected).unwrap();
+        let diff = abs_diff(actual, expected).0;
+        assert!(
+            diff < Decimal256::from_ratio(1u8, 1_000_000_000u128),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    fn assert_within_one(actual: Uint128, expected: Uint128) {
+        assert!(
+            actual.max(expected) - actual.min(expected) <= Uint128::one(),
+            "expected {expected}, got {actual}"
+        );
+    }
+
+    #[test]
+    fn pow_integer_and_fractional_exponents() {
+        let dec = |s: &str| Decimal256::from_str(s).unwrap();
+
+        assert_eq!(pow(dec("1.5"), dec("2")).unwrap(), dec("2.25"));
+        assert_eq!(pow(dec("0.5"), dec("0")).unwrap(), Decimal256::one());
+        assert_close(pow(dec("0.25"), dec("0.5")).unwrap(), "0.5");
+        assert_close(pow(dec("1.44"), dec("0.5")).unwrap(), "1.2");
+        assert_close(pow(dec("0.8"), dec("4")).unwrap(), "0.4096");
+        // 0.9 ^ 2.5 = 0.81 * sqrt(0.9)
+        assert_close(pow(dec("0.9"), dec("2.5")).unwrap(), "0.768433471420916");
+
+        pow(Decimal256::zero(), dec("0.5")).unwrap_err();
+        pow(dec("2"), dec("0.5")).unwrap_err();
+    }
+
+    #[test]
+    fn equal_weights_match_xyk() {
+        let offer_pool = Uint128::new(1_000_000_000);
+        let ask_pool = Uint128::new(3_000_000_000);
+        let amount = Uint128::new(20_000_000);
+        let fee = Decimal::permille(3);
+
+        let (weighted_return, _, weighted_commission) =
+            compute_weighted_swap(offer_pool, ask_pool, (5_000, 5_000), amount, fee).unwrap();
+        let (xyk_return, _, xyk_commission) =
+            compute_swap(offer_pool, ask_pool, amount, fee).unwrap();
+        assert_within_one(weighted_return, xyk_return);
+        assert_within_one(weighted_commission, xyk_commission);
+
+        let (weighted_offer, _, _) =
+            compute_weighted_offer_amount(offer_pool, ask_pool, (5_000, 5_000), amount, fee)
+                .unwrap();
+        let (xyk_offer, _, _) = compute_offer_amount(offer_pool, ask_pool, amount, fee).unwrap();
+        assert_within_one(weighted_offer, xyk_offer);
+    }
+
+    #[test]
+    fn weighted_swap_limits() {
+        let pool = Uint128::new(1_000_000);
+
+        compute_weighted_swap(
+            pool,
+            pool,
+            (8_000, 2_000),
+            Uint128::new(500_001),
+            Decimal::zero(),
+        )
+        .unwrap_err();
+        compute_weighted_offer_amount(
+            pool,
+            pool,
+            (8_000, 2_000),
+            Uint128::new(300_001),
+            Decimal::zero(),
+        )
+        .unwrap_err();
+    }
+}
@@ -100,6 +100,7 @@ impl WasmMockQuerier {
                                 max_referral_commission: Decimal::one(),
                                 only_owner_can_create_pairs: true,
                                 trading_starts: None,
+                                fee_recipients: vec![],
                             })
                             .into(),
                         ),
@@ -181,6 +182,23 @@ impl WasmMockQuerier {
         self.token_querier = TokenQuerier::new(balances);
     }
 
+    pub fn token_balance(&self, token: &str, addr: &str) -> Uint128 {
+        self.token_querier
+            .balances
+            .get(token)
+            .and_then(|balances| balances.get(addr))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    pub fn set_token_balance(&mut self, token: &str, addr: &str, amount: Uint128) {
+        self.token_querier
+            .balances
+            .entry(token.to_string())
+            .or_default()
+            .insert(addr.to_string(), amount);
+    }
+
     pub fn with_balance(&mut self, balances: &[(&String, &[Coin])]) {
         for (addr, balance) in balances {
             self.base.update_balance(addr.to_string(), balance.to_vec());
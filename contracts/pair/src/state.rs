@@ -1,7 +1,8 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
-use sg_swap::pair::PairInfo;
+use cosmwasm_std::{Addr, Decimal, QuerierWrapper, StdResult, Uint128};
+use cw_storage_plus::{Item, Map};
+use sg_swap::asset::AssetValidated;
+use sg_swap::pair::{default_slippage, subtract_accrued_fees, LpTokenType, PairInfo};
 
 /// This structure stores the main config parameters for a constant product pair contract.
 #[cw_serde]
@@ -18,7 +19,47 @@ pub struct Config {
     pub price1_cumulative_last: Uint128,
     /// The block time until which trading is disabled
     pub trading_starts: u64,
+    /// Whether swaps and liquidity operations are currently disabled
+    #[serde(default)]
+    pub paused: bool,
+    /// The max spread applied to swaps that do not specify one
+    #[serde(default = "default_slippage")]
+    pub default_max_spread: Decimal,
+    /// The kind of token used for the LP shares
+    #[serde(default)]
+    pub lp_token_type: LpTokenType,
+    /// The protocol fees held by the pair until they are collected
+    #[serde(default)]
+    pub accrued_protocol_fees: Vec<AssetValidated>,
+    /// The total amount of each asset offered to the pair in swaps
+    #[serde(default)]
+    pub offered_volume: Vec<AssetValidated>,
+    /// The total amount of each asset returned by the pair in swaps
+    #[serde(default)]
+    pub returned_volume: Vec<AssetValidated>,
+}
+
+impl Config {
+    /// Returns the balance for each asset in the pool, excluding the accrued protocol fees.
+    ///
+    /// * **contract_addr** is pair's pool address.
+    pub fn query_pools(
+        &self,
+        querier: &QuerierWrapper,
+        contract_addr: impl Into<String>,
+    ) -> StdResult<Vec<AssetValidated>> {
+        let mut pools = self.pair_info.query_pools(querier, contract_addr)?;
+        subtract_accrued_fees(&mut pools, &self.accrued_protocol_fees)?;
+        Ok(pools)
+    }
 }
 
 /// Stores the config struct at the given key
 pub const CONFIG: Item<Config> = Item::new("config");
+
+/// The minimum number of seconds between two entries of [`VOLUME_SNAPSHOTS`]
+pub const VOLUME_SNAPSHOT_INTERVAL: u64 = 60 * 60;
+
+/// Stores the cumulative offered volume right before the first swap after each [`VOLUME_SNAPSHOT_INTERVAL`],
+/// keyed by the block time of that swap. This is used to estimate the swap volume over a time window.
+pub const VOLUME_SNAPSHOTS: Map<u64, Vec<AssetValidated>> = Map::new("volume_snapshots");
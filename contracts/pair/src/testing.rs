@@ -1,7 +1,7 @@
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env, ReplyOn,
-    Response, StdError, SubMsg, Timestamp, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env,
+    MessageInfo, OwnedDeps, ReplyOn, Response, StdError, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use cw_utils::MsgInstantiateContractResponse;
@@ -12,18 +12,20 @@ use sg_swap::asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated};
 use sg_swap::factory::PairType;
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
-    assert_max_spread, ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PairInfo,
-    PoolResponse, ReverseSimulationResponse, SimulationResponse, StakeConfig, TWAP_PRECISION,
+    assert_max_spread, default_slippage, ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
+    LpTokenType, MigrateMsg, PairInfo, PoolResponse, ReverseSimulationResponse, SimulationResponse,
+    StakeConfig, TWAP_PRECISION,
 };
 
 use crate::contract::compute_offer_amount;
 use crate::contract::{
-    accumulate_prices, compute_swap, execute, instantiate, query_pool, query_reverse_simulation,
-    query_share, query_simulation,
+    accumulate_prices, compute_swap, execute, instantiate, migrate, query_pool,
+    query_reverse_simulation, query_share, query_simulate_withdraw, query_simulation,
+    query_spot_price,
 };
 use crate::state::{Config, CONFIG};
 // TODO: Copied here just as a temporary measure
-use crate::mock_querier::mock_dependencies;
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
 
 fn store_liquidity_token(deps: DepsMut, contract_addr: String) {
     let res = MsgInstantiateContractResponse {
@@ -42,6 +44,59 @@ fn store_liquidity_token(deps: DepsMut, contract_addr: String) {
     CONFIG.save(deps.storage, &config).unwrap();
 }
 
+/// Executes `msg` and, if the pair continues with a message to itself, executes that one as well
+/// after crediting the pulled cw20 tokens to the pair.
+/// The token balances are restored afterwards, so the tests keep setting up the pools themselves.
+/// Returns the messages of both steps, without the one the pair sent to itself.
+fn execute_with_callback(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let mut res = execute(deps.as_mut(), env.clone(), info, msg)?;
+    let callback = match res.messages.last().map(|sub_msg| &sub_msg.msg) {
+        Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        })) if contract_addr == MOCK_CONTRACT_ADDR => from_binary(msg).unwrap(),
+        _ => return Ok(res),
+    };
+    res.messages.pop();
+
+    let mut prev_balances = vec![];
+    for sub_msg in &res.messages {
+        if let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &sub_msg.msg
+        {
+            if let Ok(Cw20ExecuteMsg::TransferFrom { amount, .. }) = from_binary(msg) {
+                let balance = deps
+                    .querier
+                    .token_balance(contract_addr, MOCK_CONTRACT_ADDR);
+                deps.querier
+                    .set_token_balance(contract_addr, MOCK_CONTRACT_ADDR, balance + amount);
+                prev_balances.push((contract_addr.clone(), balance));
+            }
+        }
+    }
+
+    let callback_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        callback,
+    );
+    for (token, balance) in prev_balances {
+        deps.querier
+            .set_token_balance(&token, MOCK_CONTRACT_ADDR, balance);
+    }
+
+    let mut callback_res = callback_res?;
+    res.messages.append(&mut callback_res.messages);
+    callback_res.messages = res.messages;
+    Ok(callback_res)
+}
+
 fn default_stake_config() -> StakeConfig {
     StakeConfig {
         staking_code_id: 11,
@@ -71,6 +126,10 @@ fn proper_initialization() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -125,6 +184,66 @@ fn proper_initialization() {
     );
 }
 
+#[test]
+fn migrate_from_older_version() {
+    let mut deps = mock_dependencies(&[]);
+
+    deps.querier.with_token_balances(&[(
+        &String::from("asset0000"),
+        &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(123u128))],
+    )]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
+    let old_config = CONFIG.load(deps.as_ref().storage).unwrap();
+
+    // pretend an older version is deployed
+    cw2::set_contract_version(deps.as_mut().storage, "sg-swap-pair", "0.0.1").unwrap();
+
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "migrate"),
+            attr("previous_contract_version", "0.0.1"),
+            attr("new_contract_version", env!("CARGO_PKG_VERSION")),
+        ]
+    );
+    let version = cw2::get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+    assert_eq!(CONFIG.load(deps.as_ref().storage).unwrap(), old_config);
+
+    // downgrades are rejected
+    cw2::set_contract_version(deps.as_mut().storage, "sg-swap-pair", "999.0.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Cannot migrate from newer version"));
+
+    // so are migrations from other contracts
+    cw2::set_contract_version(deps.as_mut().storage, "sg-swap-factory", "0.0.1").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+}
+
 #[test]
 fn provide_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -153,6 +272,10 @@ fn provide_liquidity() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -181,6 +304,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env();
@@ -191,7 +315,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
     let mint_min_liquidity_msg = res.messages.get(1).expect("no message");
     let mint_receiver_msg = res.messages.get(2).expect("no message");
@@ -291,6 +415,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(50)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -303,7 +428,7 @@ fn provide_liquidity() {
     );
 
     // Only accept 100, then 50 share will be generated with 100 * (100 / 200)
-    let res: Response = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res: Response = execute_with_callback(&mut deps, env, info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
     assert_eq!(
@@ -358,6 +483,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env();
@@ -368,7 +494,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap_err();
     match res {
         ContractError::Std(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
@@ -417,6 +543,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -427,7 +554,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap_err();
     assert_eq!(res, ContractError::MaxSlippageAssertion {});
 
     // Initialize token balance to 1:1
@@ -453,6 +580,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -463,7 +591,7 @@ fn provide_liquidity() {
             amount: Uint128::from(98_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap_err();
     assert_eq!(res, ContractError::MaxSlippageAssertion {});
 
     // Initialize token amount with a 1:1 ratio
@@ -489,6 +617,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -499,7 +628,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let _res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap();
 
     // Initialize token balance to 1:1
     deps.querier.with_balance(&[(
@@ -524,6 +653,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -534,7 +664,7 @@ fn provide_liquidity() {
             amount: Uint128::from(99_000000000000000000u128),
         }],
     );
-    execute(deps.as_mut(), env, info, msg).unwrap();
+    execute_with_callback(&mut deps, env, info, msg).unwrap();
 
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: vec![
@@ -549,6 +679,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
     let info = mock_info(
         "addr0001",
@@ -557,7 +688,7 @@ fn provide_liquidity() {
             amount: Uint128::from(99_000000000000000000u128),
         }],
     );
-    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    let err = execute_with_callback(&mut deps, mock_env(), info, msg).unwrap_err();
     assert_eq!(err, ContractError::InvalidZeroAmount {});
 
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -573,6 +704,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(51)),
         receiver: None,
+        min_lp_amount: None,
     };
     let info = mock_info(
         "addr0001",
@@ -581,7 +713,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    let err = execute_with_callback(&mut deps, mock_env(), info, msg).unwrap_err();
     assert_eq!(err, ContractError::AllowedSpreadAssertion {});
 }
 
@@ -614,6 +746,10 @@ fn withdraw_liquidity() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -631,7 +767,11 @@ fn withdraw_liquidity() {
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity { assets: vec![] }).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -704,6 +844,288 @@ fn withdraw_liquidity() {
     );
 }
 
+#[test]
+fn withdraw_liquidity_with_min_assets() {
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(100u128),
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from("addr0000"), &Uint128::new(100u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(100u128))],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
+
+    let withdraw_msg = |min_assets| {
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("addr0000"),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                assets: vec![],
+                min_assets: Some(min_assets),
+            })
+            .unwrap(),
+            amount: Uint128::new(50u128),
+        })
+    };
+
+    // half of the shares return 50 of each asset, so asking for more fails
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(vec![
+            Asset {
+                info: AssetInfo::Native("uusd".to_string()),
+                amount: Uint128::new(50u128),
+            },
+            Asset {
+                info: AssetInfo::Token("asset0000".to_string()),
+                amount: Uint128::new(51u128),
+            },
+        ]),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::WithdrawSlippage {
+            returned: "50asset0000".to_string(),
+            min: "51asset0000".to_string(),
+        }
+    );
+
+    // minimums have to refer to the pool assets
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(vec![Asset {
+            info: AssetInfo::Native("uluna".to_string()),
+            amount: Uint128::new(1u128),
+        }]),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidAsset("uluna".to_string()));
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("liquidity0000", &[]),
+        withdraw_msg(vec![Asset {
+            info: AssetInfo::Native("uusd".to_string()),
+            amount: Uint128::new(45u128),
+        }]),
+    )
+    .unwrap();
+    assert_eq!(
+        res.attributes[3],
+        attr("refund_assets", "50uusd, 50asset0000")
+    );
+}
+
+#[cfg(feature = "token-factory")]
+#[test]
+fn native_lp_tokens() {
+    use crate::token_factory::{burn_msg, create_denom_msg, lp_denom, mint_msg};
+    use cosmwasm_std::coin;
+    use cw_utils::PaymentError;
+    use sg_swap::stake::InstantiateMsg as StakeInstantiateMsg;
+
+    let contract = Addr::unchecked(MOCK_CONTRACT_ADDR);
+    let lp_denom = lp_denom(&contract);
+
+    let mut deps = mock_dependencies(&[coin(2_000_000, "uusd"), coin(2_000_000, "uluna")]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native("uluna".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Native,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+    };
+
+    // the LP denom and its staking contract are created right away instead of instantiating a cw20 contract
+    let res = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(create_denom_msg(&contract).unwrap()),
+            SubMsg {
+                msg: WasmMsg::Instantiate {
+                    code_id: 11,
+                    msg: to_binary(&StakeInstantiateMsg {
+                        cw20_contract: String::new(),
+                        native_denom: Some(lp_denom.clone()),
+                        tokens_per_power: Uint128::new(1000),
+                        min_bond: Uint128::new(1000),
+                        unbonding_periods: vec![60 * 60 * 24 * 7],
+                        max_distributions: 6,
+                        instant_unbond_penalty: None,
+                        admin: Some("factory".to_owned()),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                    admin: Some("owner".to_owned()),
+                    label: String::from("sg-swap-stake"),
+                }
+                .into(),
+                id: 2,
+                gas_limit: None,
+                reply_on: ReplyOn::Success
+            },
+        ]
+    );
+    let config = CONFIG.load(&deps.storage).unwrap();
+    assert_eq!(config.pair_info.liquidity_token.as_str(), lp_denom);
+
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            Asset {
+                info: AssetInfo::Native("uusd".to_string()),
+                amount: Uint128::new(2_000_000),
+            },
+            Asset {
+                info: AssetInfo::Native("uluna".to_string()),
+                amount: Uint128::new(2_000_000),
+            },
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+        min_lp_amount: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[coin(2_000_000, "uusd"), coin(2_000_000, "uluna")],
+    );
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(mint_msg(&contract, coin(1_000, &lp_denom)).unwrap()),
+            SubMsg::new(mint_msg(&contract, coin(1_999_000, &lp_denom)).unwrap()),
+            SubMsg::new(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![coin(1_999_000, &lp_denom)],
+            }),
+        ]
+    );
+
+    // the mock bank module now knows about the minted LP shares
+    deps.querier.with_balance(&[
+        (
+            &String::from(MOCK_CONTRACT_ADDR),
+            &[
+                coin(2_000_000, "uusd"),
+                coin(2_000_000, "uluna"),
+                coin(1_000, &lp_denom),
+            ],
+        ),
+        (&String::from("addr0000"), &[coin(1_999_000, &lp_denom)]),
+    ]);
+
+    // native LP shares cannot be withdrawn through the cw20 hook
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
+        amount: Uint128::new(999_500),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::Payment(PaymentError::NoFunds {}));
+
+    let msg = ExecuteMsg::WithdrawLiquidity {
+        assets: vec![],
+        min_assets: None,
+    };
+    let info = mock_info("addr0000", &[coin(999_500, &lp_denom)]);
+    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![coin(999_500, "uusd")],
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![coin(999_500, "uluna")],
+            }),
+            SubMsg::new(burn_msg(&contract, coin(999_500, &lp_denom)).unwrap()),
+        ]
+    );
+}
+
+#[cfg(not(feature = "token-factory"))]
+#[test]
+fn native_lp_tokens_require_feature() {
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native("uluna".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Native,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+    };
+
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::NonSupported {});
+}
+
 #[test]
 fn try_native_to_token() {
     let total_share = Uint128::new(30000000000u128);
@@ -737,6 +1159,10 @@ fn try_native_to_token() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 30,
             protocol_fee_bps: 1660,
@@ -790,6 +1216,16 @@ fn try_native_to_token() {
         .checked_sub(expected_commission_amount)
         .unwrap();
 
+    // The protocol fee stays in the pair until it is collected
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config.accrued_protocol_fees,
+        vec![AssetValidated {
+            info: AssetInfoValidated::Token(Addr::unchecked("asset0000")),
+            amount: expected_protocol_fee_amount,
+        }]
+    );
+
     // Check simulation result
     deps.querier.with_balance(&[(
         &String::from(MOCK_CONTRACT_ADDR),
@@ -798,6 +1234,19 @@ fn try_native_to_token() {
             amount: collateral_pool_amount, /* user deposit must be pre-applied */
         }],
     )]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share)],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &(asset_pool_amount + expected_protocol_fee_amount),
+            )],
+        ),
+    ]);
 
     let err = query_simulation(
         deps.as_ref(),
@@ -883,6 +1332,7 @@ fn try_native_to_token() {
             attr("return_amount", expected_return_amount.to_string()),
             attr("spread_amount", expected_spread_amount.to_string()),
             attr("commission_amount", expected_commission_amount.to_string()),
+            attr("referral_amount", "0"),
             attr(
                 "protocol_fee_amount",
                 expected_protocol_fee_amount.to_string()
@@ -946,6 +1396,10 @@ fn try_token_to_native() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 30,
             protocol_fee_bps: 1660,
@@ -960,7 +1414,7 @@ fn try_token_to_native() {
     // Store liquidity token
     store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
 
-    // Unauthorized access; can not execute swap directy for token swap
+    // Swapping a token directly pulls it and continues with the amount the pair received
     let msg = ExecuteMsg::Swap {
         offer_asset: Asset {
             info: AssetInfo::Token("asset0000".to_string()),
@@ -968,14 +1422,59 @@ fn try_token_to_native() {
         },
         ask_asset_info: None,
         belief_price: None,
-        max_spread: None,
+        max_spread: Some(Decimal::percent(50)),
         to: None,
         referral_address: None,
         referral_commission: None,
     };
     let env = mock_env_with_block_time(1000);
     let info = mock_info("addr0000", &[]);
-    let res = execute(deps.as_mut(), env, info, msg).unwrap_err();
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from("asset0000"),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: String::from("addr0000"),
+                    recipient: String::from(MOCK_CONTRACT_ADDR),
+                    amount: offer_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            SubMsg::new(WasmMsg::Execute {
+                contract_addr: String::from(MOCK_CONTRACT_ADDR),
+                msg: to_binary(&ExecuteMsg::SwapReceived {
+                    sender: String::from("addr0000"),
+                    offer_asset_info: AssetInfo::Token("asset0000".to_string()),
+                    prev_balance: asset_pool_amount + offer_amount,
+                    ask_asset_info: None,
+                    belief_price: None,
+                    max_spread: Some(Decimal::percent(50)),
+                    to: None,
+                    referral_address: None,
+                    referral_commission: None,
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+        ]
+    );
+
+    // only the pair can continue the swap
+    let msg = ExecuteMsg::SwapReceived {
+        sender: String::from("addr0000"),
+        offer_asset_info: AssetInfo::Token("asset0000".to_string()),
+        prev_balance: asset_pool_amount,
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+    let res = execute(deps.as_mut(), env, mock_info("addr0000", &[]), msg).unwrap_err();
     assert_eq!(res, ContractError::Unauthorized {});
 
     // Normal sell
@@ -1011,6 +1510,16 @@ fn try_token_to_native() {
         .checked_sub(expected_commission_amount)
         .unwrap();
 
+    // The protocol fee stays in the pair until it is collected
+    let config = CONFIG.load(deps.as_ref().storage).unwrap();
+    assert_eq!(
+        config.accrued_protocol_fees,
+        vec![AssetValidated {
+            info: AssetInfoValidated::Native("uusd".to_string()),
+            amount: expected_protocol_fee_amount,
+        }]
+    );
+
     // Check simulation res
     // Return asset token balance as normal
     deps.querier.with_token_balances(&[
@@ -1023,6 +1532,13 @@ fn try_token_to_native() {
             &[(&String::from(MOCK_CONTRACT_ADDR), &(asset_pool_amount))],
         ),
     ]);
+    deps.querier.with_balance(&[(
+        &String::from(MOCK_CONTRACT_ADDR),
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: collateral_pool_amount + expected_protocol_fee_amount,
+        }],
+    )]);
 
     let simulation_res: SimulationResponse = query_simulation(
         deps.as_ref(),
@@ -1078,6 +1594,7 @@ fn try_token_to_native() {
             attr("return_amount", expected_return_amount.to_string()),
             attr("spread_amount", expected_spread_amount.to_string()),
             attr("commission_amount", expected_commission_amount.to_string()),
+            attr("referral_amount", "0"),
             attr(
                 "protocol_fee_amount",
                 expected_protocol_fee_amount.to_string()
@@ -1121,6 +1638,254 @@ fn try_token_to_native() {
     assert_eq!(res, ContractError::Unauthorized {});
 }
 
+#[test]
+fn swap_on_drained_pool() {
+    let offer_amount = Uint128::new(1_000_000u128);
+
+    // the token side of the pool was drained completely
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30_000_000_000u128) + offer_amount,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
+
+    let msg = ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Native("uusd".to_string()),
+            amount: offer_amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: offer_amount,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::PoolEmpty {});
+
+    let pool_empty: StdError = ContractError::PoolEmpty {}.into();
+    let err = query_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Native("uusd".to_string()),
+            amount: offer_amount,
+        },
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, pool_empty);
+
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Token("asset0000".to_string()),
+            amount: offer_amount,
+        },
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, pool_empty);
+
+    // the spot price is rejected in both directions
+    for (offer, ask) in [
+        (
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ),
+        (
+            AssetInfo::Token("asset0000".to_string()),
+            AssetInfo::Native("uusd".to_string()),
+        ),
+    ] {
+        let err = query_spot_price(deps.as_ref(), offer, ask).unwrap_err();
+        assert_eq!(err, pool_empty);
+    }
+}
+
+#[test]
+fn swap_zero_and_dust_amounts() {
+    let dust = Uint128::new(1u128);
+
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: Uint128::new(30_000_000_000u128) + dust,
+    }]);
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::new(1000u128))],
+        ),
+        (
+            &String::from("asset0000"),
+            &[(
+                &String::from(MOCK_CONTRACT_ADDR),
+                &Uint128::new(20_000_000_000u128),
+            )],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 30,
+            protocol_fee_bps: 1660,
+        },
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap();
+    store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
+
+    let swap_msg = |amount: Uint128| ExecuteMsg::Swap {
+        offer_asset: Asset {
+            info: AssetInfo::Native("uusd".to_string()),
+            amount,
+        },
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+
+    // zero offers are rejected, no matter how they are sent
+    let err = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        swap_msg(Uint128::zero()),
+    )
+    .unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: String::from("addr0000"),
+        amount: Uint128::zero(),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        })
+        .unwrap(),
+    });
+    let err = execute(deps.as_mut(), mock_env(), mock_info("asset0000", &[]), msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+
+    // 1 * 20_000_000_000 / (30_000_000_000 + 1) rounds down to nothing
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: dust,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, swap_msg(dust)).unwrap_err();
+    assert_eq!(err, ContractError::SwapTooSmall {});
+
+    // simulations fail the same way instead of returning zero
+    let zero_amount: StdError = ContractError::InvalidZeroAmount {}.into();
+    let swap_too_small: StdError = ContractError::SwapTooSmall {}.into();
+    let simulate = |amount: Uint128| {
+        query_simulation(
+            deps.as_ref(),
+            Asset {
+                info: AssetInfo::Native("uusd".to_string()),
+                amount,
+            },
+            false,
+            None,
+        )
+    };
+    assert_eq!(simulate(Uint128::zero()).unwrap_err(), zero_amount);
+    assert_eq!(simulate(dust).unwrap_err(), swap_too_small);
+    let err = query_reverse_simulation(
+        deps.as_ref(),
+        Asset {
+            info: AssetInfo::Token("asset0000".to_string()),
+            amount: Uint128::zero(),
+        },
+        false,
+        None,
+    )
+    .unwrap_err();
+    assert_eq!(err, zero_amount);
+
+    // providing a single asset does not fail as a swap, even if its swap part returns nothing
+    let deposit = dust + dust;
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![Asset {
+            info: AssetInfo::Native("uusd".to_string()),
+            amount: deposit,
+        }],
+        slippage_tolerance: None,
+        receiver: None,
+        min_lp_amount: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: deposit,
+        }],
+    );
+    let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
+    assert_eq!(err, ContractError::InvalidZeroAmount {});
+}
+
 #[test]
 fn test_max_spread() {
     assert_max_spread(
@@ -1200,6 +1965,10 @@ fn test_query_pool() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -1263,6 +2032,10 @@ fn test_query_share() {
         init_params: None,
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -1283,6 +2056,96 @@ fn test_query_share() {
     assert_eq!(res[1].amount, Uint128::new(500));
 }
 
+#[test]
+fn test_query_simulate_withdraw() {
+    let total_share_amount = Uint128::from(500u128);
+    let asset_0_amount = Uint128::from(250u128);
+    let asset_1_amount = Uint128::from(1000u128);
+    let mut deps = mock_dependencies(&[Coin {
+        denom: "uusd".to_string(),
+        amount: asset_0_amount,
+    }]);
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &Uint128::zero())],
+        ),
+    ]);
+
+    let msg = InstantiateMsg {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        factory_addr: String::from("factory"),
+        init_params: None,
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+    };
+
+    let env = mock_env();
+    let info = mock_info("addr0000", &[]);
+    instantiate(deps.as_mut(), env, info, msg).unwrap();
+    store_liquidity_token(deps.as_mut(), "liquidity0000".to_string());
+
+    // no LP tokens were issued yet
+    let err = query_simulate_withdraw(deps.as_ref(), Uint128::new(250)).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("The pool does not have any liquidity")
+    );
+
+    deps.querier.with_token_balances(&[
+        (
+            &String::from("asset0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &asset_1_amount)],
+        ),
+        (
+            &String::from("liquidity0000"),
+            &[(&String::from(MOCK_CONTRACT_ADDR), &total_share_amount)],
+        ),
+    ]);
+
+    // the same proportional amounts as a withdrawal
+    let res = query_simulate_withdraw(deps.as_ref(), Uint128::new(250)).unwrap();
+    assert_eq!(
+        res,
+        vec![
+            AssetValidated {
+                info: AssetInfoValidated::Native("uusd".to_string()),
+                amount: Uint128::new(125),
+            },
+            AssetValidated {
+                info: AssetInfoValidated::Token(Addr::unchecked("asset0000")),
+                amount: Uint128::new(500),
+            },
+        ]
+    );
+    let res = query_simulate_withdraw(deps.as_ref(), total_share_amount).unwrap();
+    assert_eq!(res[0].amount, asset_0_amount);
+    assert_eq!(res[1].amount, asset_1_amount);
+
+    let err = query_simulate_withdraw(deps.as_ref(), Uint128::new(501)).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Cannot withdraw more than the total supply of 500 LP tokens")
+    );
+}
+
 #[test]
 fn test_accumulate_prices() {
     struct Case {
@@ -1381,6 +2244,12 @@ fn test_accumulate_prices() {
                 price0_cumulative_last: Uint128::new(case.last0),
                 price1_cumulative_last: Uint128::new(case.last1),
                 trading_starts: 0,
+                paused: false,
+                default_max_spread: default_slippage(),
+                lp_token_type: LpTokenType::Cw20,
+                accrued_protocol_fees: vec![],
+                offered_volume: vec![],
+                returned_volume: vec![],
             },
             Uint128::new(case.x_amount),
             Uint128::new(case.y_amount),
@@ -1519,5 +2388,9 @@ fn ensure_useful_error_messages_are_given_on_swaps() {
         compute_offer_amount(OFFER, ASK, ZERO, DZERO).unwrap_err(),
         StdError::generic_err("Swap amount must not be zero")
     );
+    assert_eq!(
+        compute_offer_amount(OFFER, ASK, ASK, DZERO).unwrap_err(),
+        StdError::generic_err("Ask amount must be less than the ask pool")
+    );
     compute_offer_amount(OFFER, ASK, AMOUNT, DZERO).unwrap();
 }
@@ -0,0 +1,144 @@
+use cosmwasm_std::{Addr, Coin, CosmosMsg, QuerierWrapper, StdResult, Uint128};
+use sg_swap::pair::ContractError;
+
+/// The subdenom of the LP shares created by the pair
+pub const LP_SUBDENOM: &str = "ulp";
+
+const MSG_CREATE_DENOM: &str = "/osmosis.tokenfactory.v1beta1.MsgCreateDenom";
+const MSG_MINT: &str = "/osmosis.tokenfactory.v1beta1.MsgMint";
+const MSG_BURN: &str = "/osmosis.tokenfactory.v1beta1.MsgBurn";
+
+/// Returns the token factory denom of the LP shares created by `pair`.
+pub fn lp_denom(pair: &Addr) -> String {
+    format!("factory/{pair}/{LP_SUBDENOM}")
+}
+
+/// Returns a message that creates the LP denom with `sender` as its admin.
+pub fn create_denom_msg(sender: &Addr) -> Result<CosmosMsg, ContractError> {
+    let mut value = vec![];
+    encode_bytes(&mut value, 1, sender.as_bytes());
+    encode_bytes(&mut value, 2, LP_SUBDENOM.as_bytes());
+    stargate_msg(MSG_CREATE_DENOM, value)
+}
+
+/// Returns a message that mints `amount` to the denom admin `sender`.
+pub fn mint_msg(sender: &Addr, amount: Coin) -> Result<CosmosMsg, ContractError> {
+    let mut value = vec![];
+    encode_bytes(&mut value, 1, sender.as_bytes());
+    encode_bytes(&mut value, 2, &encode_coin(&amount));
+    stargate_msg(MSG_MINT, value)
+}
+
+/// Returns a message that burns `amount` from the balance of the denom admin `sender`.
+pub fn burn_msg(sender: &Addr, amount: Coin) -> Result<CosmosMsg, ContractError> {
+    let mut value = vec![];
+    encode_bytes(&mut value, 1, sender.as_bytes());
+    encode_bytes(&mut value, 2, &encode_coin(&amount));
+    stargate_msg(MSG_BURN, value)
+}
+
+/// Returns the total supply of the given denom.
+#[cfg(feature = "token-factory")]
+pub fn query_supply(querier: &QuerierWrapper, denom: &str) -> StdResult<Uint128> {
+    Ok(querier.query_supply(denom)?.amount)
+}
+
+#[cfg(not(feature = "token-factory"))]
+pub fn query_supply(_querier: &QuerierWrapper, _denom: &str) -> StdResult<Uint128> {
+    Err(ContractError::NonSupported {}.into())
+}
+
+#[cfg(feature = "token-factory")]
+fn stargate_msg(type_url: &str, value: Vec<u8>) -> Result<CosmosMsg, ContractError> {
+    Ok(CosmosMsg::Stargate {
+        type_url: type_url.to_string(),
+        value: value.into(),
+    })
+}
+
+#[cfg(not(feature = "token-factory"))]
+fn stargate_msg(_type_url: &str, _value: Vec<u8>) -> Result<CosmosMsg, ContractError> {
+    Err(ContractError::NonSupported {})
+}
+
+/// Encodes a `cosmos.base.v1beta1.Coin` protobuf message.
+fn encode_coin(coin: &Coin) -> Vec<u8> {
+    let mut buf = vec![];
+    encode_bytes(&mut buf, 1, coin.denom.as_bytes());
+    encode_bytes(&mut buf, 2, coin.amount.to_string().as_bytes());
+    buf
+}
+
+/// Appends a length-delimited protobuf field (strings and embedded messages) to `buf`.
+fn encode_bytes(buf: &mut Vec<u8>, field: u8, bytes: &[u8]) {
+    buf.push((field << 3) | 2);
+    let mut len = bytes.len();
+    while len >= 0x80 {
+        buf.push(len as u8 | 0x80);
+        len >>= 7;
+    }
+    buf.push(len as u8);
+    buf.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::coin;
+
+    #[test]
+    fn encode_fields() {
+        let mut buf = vec![];
+        encode_bytes(&mut buf, 1, b"pair");
+        assert_eq!(buf, [0x0a, 4, b'p', b'a', b'i', b'r']);
+
+        // lengths above 127 take more than one byte
+        let mut buf = vec![];
+        encode_bytes(&mut buf, 2, &[0; 300]);
+        assert_eq!(buf[..3], [0x12, 0xac, 0x02]);
+        assert_eq!(buf.len(), 303);
+
+        assert_eq!(
+            encode_coin(&coin(25, "ulp")),
+            [0x0a, 3, b'u', b'l', b'p', 0x12, 2, b'2', b'5']
+        );
+    }
+
+    #[cfg(feature = "token-factory")]
+    #[test]
+    fn token_factory_messages() {
+        let pair = Addr::unchecked("pair");
+        assert_eq!(lp_denom(&pair), "factory/pair/ulp");
+
+        match create_denom_msg(&pair).unwrap() {
+            CosmosMsg::Stargate { type_url, value } => {
+                assert_eq!(type_url, MSG_CREATE_DENOM);
+                assert_eq!(
+                    value.as_slice(),
+                    [0x0a, 4, b'p', b'a', b'i', b'r', 0x12, 3, b'u', b'l', b'p']
+                );
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+
+        match burn_msg(&pair, coin(25, "ulp")).unwrap() {
+            CosmosMsg::Stargate { type_url, value } => {
+                assert_eq!(type_url, MSG_BURN);
+                let mut expected = vec![0x0a, 4, b'p', b'a', b'i', b'r', 0x12, 9];
+                expected.extend(encode_coin(&coin(25, "ulp")));
+                assert_eq!(value.as_slice(), expected);
+            }
+            msg => panic!("unexpected message {msg:?}"),
+        }
+    }
+
+    #[cfg(not(feature = "token-factory"))]
+    #[test]
+    fn token_factory_requires_feature() {
+        let pair = Addr::unchecked("pair");
+        assert_eq!(
+            create_denom_msg(&pair).unwrap_err(),
+            ContractError::NonSupported {}
+        );
+    }
+}
@@ -1,16 +1,26 @@
-use cosmwasm_std::{attr, to_binary, Addr, Coin, Decimal, Uint128};
-use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
-use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
+use cosmwasm_std::{
+    assert_approx_eq, attr, coin, to_binary, Addr, Coin, Decimal, DepsMut, Env, Event, MessageInfo,
+    Response, Uint128,
+};
+use cw20::{
+    BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, Cw20ReceiveMsg, MinterResponse,
+};
+use cw20_base::msg::{ExecuteMsg as TokenExecuteMsg, InstantiateMsg as TokenInstantiateMsg};
 use cw_multi_test::{App, ContractWrapper, Executor};
-use sg_swap::asset::{native_asset_info, Asset, AssetInfo, AssetInfoExt, AssetInfoValidated};
+use sg_swap::asset::{
+    native_asset_info, Asset, AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated,
+    MINIMUM_LIQUIDITY_AMOUNT,
+};
 use sg_swap::factory::{
     DefaultStakeConfig, ExecuteMsg as FactoryExecuteMsg, InstantiateMsg as FactoryInstantiateMsg,
     PairConfig, PairType, PartialStakeConfig, QueryMsg as FactoryQueryMsg,
 };
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
-    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PairInfo,
-    PoolResponse, QueryMsg, SimulationResponse, TWAP_PRECISION,
+    ConfigResponse, ContractError, CumulativePricesResponse, CumulativeVolumeResponse, Cw20HookMsg,
+    EstimateSwapFeesResponse, ExecuteMsg, InstantiateMsg, LpTokenType, PairInfo, PoolResponse,
+    PoolStateResponse, QueryMsg, ReverseSimulationResponse, SimulateProvideResponse,
+    SimulationResponse, TwapResponse, SWAP_ATTRIBUTE_KEYS, TWAP_PRECISION,
 };
 use sg_swap::querier::query_token_balance;
 
@@ -70,6 +80,14 @@ fn store_staking_code(app: &mut App) -> u64 {
 }
 
 fn instantiate_factory(router: &mut App, owner: &Addr) -> Addr {
+    instantiate_factory_with_fee_address(router, owner, Some(owner))
+}
+
+fn instantiate_factory_with_fee_address(
+    router: &mut App,
+    owner: &Addr,
+    fee_address: Option<&Addr>,
+) -> Addr {
     let token_contract_code_id = store_token_code(router);
     let pair_contract_code_id = store_pair_code(router);
     let staking_contract_code_id = store_staking_code(router);
@@ -84,13 +102,16 @@ fn instantiate_factory(router: &mut App, owner: &Addr) -> Addr {
                 protocol_fee_bps: 0,
             },
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id: token_contract_code_id,
-        fee_address: Some(owner.to_string()),
+        fee_address: fee_address.map(Addr::to_string),
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(staking_contract_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     router
@@ -107,8 +128,20 @@ fn instantiate_factory(router: &mut App, owner: &Addr) -> Addr {
 
 fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
     let factory = instantiate_factory(router, owner);
+    let pair = create_pair(router, owner, &factory);
+
+    let res: PairInfo = router
+        .wrap()
+        .query_wasm_smart(pair.clone(), &QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!("contract1", res.contract_addr);
+    assert_eq!("contract2", res.liquidity_token);
 
-    // instantiate pair
+    pair
+}
+
+/// Creates a uusd-uluna xyk pair in `factory`.
+fn create_pair(router: &mut App, owner: &Addr, factory: &Addr) -> Addr {
     let asset_infos = vec![
         AssetInfo::Native("uusd".to_string()),
         AssetInfo::Native("uluna".to_string()),
@@ -119,6 +152,11 @@ fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
         init_params: None,
         total_fee_bps: None,
         staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     router
@@ -126,6 +164,62 @@ fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
         .unwrap();
 
     // get pair address
+    router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr
+}
+
+fn instantiate_weighted_pair(router: &mut App, owner: &Addr, weights: [u16; 2]) -> Addr {
+    let factory = instantiate_factory(router, owner);
+    let pair_type = PairType::Weighted { weights };
+
+    // add a pair config for weighted pairs, reusing the xyk code
+    let xyk_config = router
+        .wrap()
+        .query_wasm_smart::<sg_swap::factory::ConfigResponse>(&factory, &FactoryQueryMsg::Config {})
+        .unwrap()
+        .pair_configs
+        .remove(0);
+    router
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::UpdatePairConfig {
+                config: PairConfig {
+                    pair_type: pair_type.clone(),
+                    ..xyk_config
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Native("uluna".to_string()),
+    ];
+    router
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::CreatePair {
+                pair_type: pair_type.clone(),
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                total_fee_bps: None,
+                staking_config: PartialStakeConfig::default(),
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
+            },
+            &[],
+        )
+        .unwrap();
+
     let pair = router
         .wrap()
         .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
@@ -136,8 +230,7 @@ fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
         .wrap()
         .query_wasm_smart(pair.clone(), &QueryMsg::Pair {})
         .unwrap();
-    assert_eq!("contract1", res.contract_addr);
-    assert_eq!("contract2", res.liquidity_token);
+    assert_eq!(res.pair_type, pair_type);
 
     pair
 }
@@ -350,7 +443,11 @@ fn test_provide_and_withdraw_liquidity() {
     let msg = Cw20ExecuteMsg::Send {
         contract: pair_instance.to_string(),
         amount: Uint128::from(50u8),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity { assets: vec![] }).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
     };
     // Try to send withdraw liquidity with FOO token
     let err = router
@@ -399,11 +496,296 @@ fn test_provide_and_withdraw_liquidity() {
         ConfigResponse {
             block_time_last: router.block_info().time.seconds(),
             params: None,
-            owner: None
+            owner: None,
+            default_max_spread: Decimal::permille(5),
         }
     )
 }
 
+#[test]
+fn create_xyk_pair_with_init_params() {
+    let owner = Addr::unchecked(OWNER);
+    let mut router = mock_app(owner.clone(), vec![]);
+    let factory = instantiate_factory(&mut router, &owner);
+
+    let asset_infos = vec![
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Native("uluna".to_string()),
+    ];
+    // xyk pairs have no init params, so these are not forwarded to the pair
+    let init_params = to_binary(&sg_swap::pair::StablePoolParams {
+        amp: 100,
+        owner: None,
+    })
+    .unwrap();
+    router
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::CreatePair {
+                pair_type: PairType::Xyk {},
+                asset_infos: asset_infos.clone(),
+                init_params: Some(init_params),
+                total_fee_bps: None,
+                staking_config: PartialStakeConfig::default(),
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
+            },
+            &[],
+        )
+        .unwrap();
+
+    let pair = router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr;
+    let res: PairInfo = router
+        .wrap()
+        .query_wasm_smart(pair, &QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!(res.pair_type, PairType::Xyk {});
+}
+
+#[test]
+fn minimum_liquidity_is_locked_on_first_deposit() {
+    let owner = Addr::unchecked("owner");
+    let attacker = Addr::unchecked("attacker");
+    let victim = Addr::unchecked("victim");
+
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+    for user in [&attacker, &victim] {
+        router
+            .send_tokens(
+                owner.clone(),
+                user.clone(),
+                &[
+                    Coin {
+                        denom: "uusd".to_string(),
+                        amount: Uint128::new(10_000_000_000u128),
+                    },
+                    Coin {
+                        denom: "uluna".to_string(),
+                        amount: Uint128::new(10_000_000_000u128),
+                    },
+                ],
+            )
+            .unwrap();
+    }
+
+    let pair_instance = instantiate_pair(&mut router, &owner);
+    let lp_token = router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(&pair_instance, &QueryMsg::Pair {})
+        .unwrap()
+        .liquidity_token;
+
+    // the first deposit has to be worth more than the locked amount
+    let (msg, coins) = provide_liquidity_msg(Uint128::new(1_000), Uint128::new(1_000), None, None);
+    let err = router
+        .execute_contract(attacker.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MinimumLiquidityAmountError {},
+        err.downcast().unwrap()
+    );
+
+    // the first provider receives sqrt(a * b) - MINIMUM_LIQUIDITY_AMOUNT
+    let (msg, coins) = provide_liquidity_msg(Uint128::new(1_001), Uint128::new(4_004), None, None);
+    router
+        .execute_contract(attacker.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    let attacker_share = query_token_balance(&router.wrap(), &lp_token, &attacker).unwrap();
+    assert_eq!(
+        attacker_share.u128(),
+        2_002 - MINIMUM_LIQUIDITY_AMOUNT.u128()
+    );
+    let locked = query_token_balance(&router.wrap(), &lp_token, &pair_instance).unwrap();
+    assert_eq!(locked, MINIMUM_LIQUIDITY_AMOUNT);
+
+    // the attacker tries to inflate the value of an LP token by donating to the pair
+    router
+        .send_tokens(
+            attacker.clone(),
+            pair_instance.clone(),
+            &[
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(1_000_000_000u128),
+                },
+                Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::new(4_000_000_000u128),
+                },
+            ],
+        )
+        .unwrap();
+
+    // a deposit that is not worth a single LP token is rejected instead of being swallowed
+    let (msg, coins) = provide_liquidity_msg(Uint128::new(100), Uint128::new(400), None, None);
+    let err = router
+        .execute_contract(victim.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::LiquidityAmountTooSmall {},
+        err.downcast().unwrap()
+    );
+
+    // the locked shares own almost half of the donation, so the attacker cannot get it back
+    let msg = Cw20ExecuteMsg::Send {
+        contract: pair_instance.to_string(),
+        amount: attacker_share,
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(attacker.clone(), lp_token, &msg, &[])
+        .unwrap();
+    let uusd_balance = router
+        .wrap()
+        .query_balance(&attacker, "uusd")
+        .unwrap()
+        .amount
+        .u128();
+    // 1002 of the 2002 LP tokens withdraw 500_500_001uusd of the 1_000_001_001uusd in the pool
+    assert_eq!(
+        uusd_balance,
+        10_000_000_000 - 1_001 - 1_000_000_000 + 500_500_001
+    );
+}
+
+#[test]
+fn shares_to_assets_matches_withdraw() {
+    let owner = Addr::unchecked("owner");
+    let user = Addr::unchecked("user");
+
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+    router
+        .send_tokens(
+            owner.clone(),
+            user.clone(),
+            &[
+                Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::new(10_000_000u128),
+                },
+                Coin {
+                    denom: "uluna".to_string(),
+                    amount: Uint128::new(10_000_000u128),
+                },
+            ],
+        )
+        .unwrap();
+
+    let pair_instance = instantiate_pair(&mut router, &owner);
+    let lp_token = router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(&pair_instance, &QueryMsg::Pair {})
+        .unwrap()
+        .liquidity_token;
+
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(3_000_000), Uint128::new(7_000_000), None, None);
+    router
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(300_000), Uint128::new(700_000), None, None);
+    router
+        .execute_contract(user.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // the user's LP tokens stand in for the shares stored in an sg721-pair NFT
+    let shares = query_token_balance(&router.wrap(), &lp_token, &user).unwrap();
+    let redeemable: Vec<AssetValidated> = router
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::SharesToAssets { shares })
+        .unwrap();
+    let simulated: Vec<AssetValidated> = router
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::SimulateWithdraw { amount: shares },
+        )
+        .unwrap();
+    assert_eq!(redeemable, simulated);
+
+    let balances_before: Vec<_> = redeemable
+        .iter()
+        .map(|asset| {
+            asset
+                .info
+                .query_balance(&router.wrap(), user.clone())
+                .unwrap()
+        })
+        .collect();
+
+    let msg = Cw20ExecuteMsg::Send {
+        contract: pair_instance.to_string(),
+        amount: shares,
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(user.clone(), lp_token, &msg, &[])
+        .unwrap();
+
+    for (asset, before) in redeemable.iter().zip(balances_before) {
+        let after = asset
+            .info
+            .query_balance(&router.wrap(), user.clone())
+            .unwrap();
+        assert_eq!(after - before, asset.amount);
+    }
+
+    // more shares than exist are not redeemable
+    let err = router
+        .wrap()
+        .query_wasm_smart::<Vec<AssetValidated>>(
+            &pair_instance,
+            &QueryMsg::SharesToAssets {
+                shares: Uint128::new(100_000_000),
+            },
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Cannot withdraw more than the total supply"));
+}
+
 fn provide_liquidity_msg(
     uusd_amount: Uint128,
     uluna_amount: Uint128,
@@ -423,6 +805,7 @@ fn provide_liquidity_msg(
         ],
         slippage_tolerance,
         receiver,
+        min_lp_amount: None,
     };
 
     let coins = [
@@ -534,12 +917,15 @@ fn test_compatibility_of_tokens_with_different_precision() {
                 protocol_fee_bps: 0,
             },
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(staking_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let factory_instance = app
@@ -562,6 +948,11 @@ fn test_compatibility_of_tokens_with_different_precision() {
         init_params: None,
         staking_config: PartialStakeConfig::default(),
         total_fee_bps: None,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     app.execute_contract(owner.clone(), factory_instance.clone(), &msg, &[])
@@ -618,10 +1009,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
     let err = app
         .execute_contract(owner.clone(), token_x_instance.clone(), &swap_msg, &[])
         .unwrap_err();
-    assert_eq!(
-        "Generic error: One of the pools is empty",
-        err.root_cause().to_string()
-    );
+    assert_eq!("One of the pools is empty", err.root_cause().to_string());
 
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: vec![
@@ -636,6 +1024,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance, &msg, &[])
@@ -660,97 +1049,1436 @@ fn test_compatibility_of_tokens_with_different_precision() {
 }
 
 #[test]
-fn test_if_twap_is_calculated_correctly_when_pool_idles() {
+fn default_max_spread_is_applied() {
     let owner = Addr::unchecked("owner");
-    let user1 = Addr::unchecked("user1");
-
-    let mut app = mock_app(
+    let mut router = mock_app(
         owner.clone(),
         vec![
             Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::new(100_000_000_000_000_u128),
+                amount: Uint128::new(1_100_000u128),
             },
             Coin {
                 denom: "uluna".to_string(),
-                amount: Uint128::new(100_000_000_000_000_u128),
+                amount: Uint128::new(1_000_000u128),
             },
         ],
     );
 
-    // Set Alice's balances
-    app.send_tokens(
-        owner,
-        user1.clone(),
-        &[
-            Coin {
-                denom: "uusd".to_string(),
-                amount: Uint128::new(4_000_000_000_000),
-            },
-            Coin {
-                denom: "uluna".to_string(),
-                amount: Uint128::new(2_000_000_000_000),
-            },
-        ],
-    )
-    .unwrap();
-
-    // Instantiate pair
-    let pair_instance = instantiate_pair(&mut app, &user1);
+    let factory = instantiate_factory(&mut router, &owner);
 
-    // Provide liquidity, accumulators are empty
-    let (msg, coins) = provide_liquidity_msg(
-        Uint128::new(1_000_000_000_000),
-        Uint128::new(1_000_000_000_000),
-        None,
-        Option::from(Decimal::one()),
-    );
-    app.execute_contract(user1.clone(), pair_instance.clone(), &msg, &coins)
+    // configure a 1% default max spread for xyk pairs
+    let xyk_config = router
+        .wrap()
+        .query_wasm_smart::<sg_swap::factory::ConfigResponse>(&factory, &FactoryQueryMsg::Config {})
+        .unwrap()
+        .pair_configs
+        .remove(0);
+    router
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::UpdatePairConfig {
+                config: PairConfig {
+                    default_max_spread: Some(Decimal::percent(1)),
+                    ..xyk_config
+                },
+            },
+            &[],
+        )
+        .unwrap();
+
+    let asset_infos = vec![
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Native("uluna".to_string()),
+    ];
+    router
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::CreatePair {
+                pair_type: PairType::Xyk {},
+                asset_infos: asset_infos.clone(),
+                init_params: None,
+                total_fee_bps: None,
+                staking_config: PartialStakeConfig::default(),
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
+            },
+            &[],
+        )
+        .unwrap();
+    let pair = router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr;
+
+    let config: ConfigResponse = router
+        .wrap()
+        .query_wasm_smart(&pair, &QueryMsg::Config {})
+        .unwrap();
+    assert_eq!(config.default_max_spread, Decimal::percent(1));
+
+    let (msg, coins) =
+        provide_liquidity_msg(Uint128::new(1_000_000), Uint128::new(1_000_000), None, None);
+    router
+        .execute_contract(owner.clone(), pair.clone(), &msg, &coins)
+        .unwrap();
+
+    let swap_msg = |amount: u128| ExecuteMsg::Swap {
+        offer_asset: AssetInfo::Native("uusd".to_string()).with_balance(amount),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+
+    // swapping 2% of the pool has a spread of almost 2%
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            pair.clone(),
+            &swap_msg(20_000),
+            &[Coin::new(20_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Operation exceeds max spread limit"
+    );
+
+    // swapping 0.5% of the pool stays within the default
+    router
+        .execute_contract(
+            owner.clone(),
+            pair.clone(),
+            &swap_msg(5_000),
+            &[Coin::new(5_000, "uusd")],
+        )
+        .unwrap();
+}
+
+#[test]
+fn test_if_twap_is_calculated_correctly_when_pool_idles() {
+    let owner = Addr::unchecked("owner");
+    let user1 = Addr::unchecked("user1");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    // Set Alice's balances
+    app.send_tokens(
+        owner,
+        user1.clone(),
+        &[
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(4_000_000_000_000),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(2_000_000_000_000),
+            },
+        ],
+    )
+    .unwrap();
+
+    // Instantiate pair
+    let pair_instance = instantiate_pair(&mut app, &user1);
+
+    // Provide liquidity, accumulators are empty
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        Option::from(Decimal::one()),
+    );
+    app.execute_contract(user1.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
 
     const BLOCKS_PER_DAY: u64 = 17280;
     const ELAPSED_SECONDS: u64 = BLOCKS_PER_DAY * 5;
 
-    // A day later
-    app.update_block(|b| {
-        b.height += BLOCKS_PER_DAY;
-        b.time = b.time.plus_seconds(ELAPSED_SECONDS);
-    });
+    // A day later
+    app.update_block(|b| {
+        b.height += BLOCKS_PER_DAY;
+        b.time = b.time.plus_seconds(ELAPSED_SECONDS);
+    });
+
+    // Provide liquidity, accumulators firstly filled with the same prices
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(2_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        Some(Decimal::percent(50)),
+    );
+    app.execute_contract(user1.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // Get current twap accumulator values
+    let msg = QueryMsg::CumulativePrices {};
+    let cpr_old: CumulativePricesResponse =
+        app.wrap().query_wasm_smart(&pair_instance, &msg).unwrap();
+
+    // A day later
+    app.update_block(|b| {
+        b.height += BLOCKS_PER_DAY;
+        b.time = b.time.plus_seconds(ELAPSED_SECONDS);
+    });
+
+    // Get current cumulative price values; they should have been updated by the query method with new 2/1 ratio
+    let msg = QueryMsg::CumulativePrices {};
+    let cpr_new: CumulativePricesResponse =
+        app.wrap().query_wasm_smart(&pair_instance, &msg).unwrap();
+
+    let twap0 = cpr_new.cumulative_prices[0].2 - cpr_old.cumulative_prices[0].2;
+    let twap1 = cpr_new.cumulative_prices[1].2 - cpr_old.cumulative_prices[1].2;
+
+    // Prices weren't changed for the last day, uusd amount in pool = 3000000_000000, uluna = 2000000_000000
+    // In accumulators we don't have any precision so we rely on elapsed time so we don't need to consider it
+    let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
+    assert_eq!(twap0 / price_precision, Uint128::new(57600)); // 0.666666 * ELAPSED_SECONDS (86400)
+    assert_eq!(twap1 / price_precision, Uint128::new(129600)); //   1.5 * ELAPSED_SECONDS
+}
+
+#[test]
+fn twap_from_cumulative_price_snapshots() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let query_prices = |app: &App| -> CumulativePricesResponse {
+        app.wrap()
+            .query_wasm_smart(&pair_instance, &QueryMsg::CumulativePrices {})
+            .unwrap()
+    };
+    let query_twap = |app: &App, start: &CumulativePricesResponse| {
+        app.wrap().query_wasm_smart::<TwapResponse>(
+            &pair_instance,
+            &QueryMsg::Twap {
+                start_cumulative: start
+                    .cumulative_prices
+                    .iter()
+                    .map(|(from, to, value)| (from.clone().into(), to.clone().into(), *value))
+                    .collect(),
+                start_time: start.block_time_last,
+            },
+        )
+    };
+
+    let snapshot0 = query_prices(&app);
+    assert_eq!(snapshot0.block_time_last, app.block_info().time.seconds());
+
+    // the query accumulates prices up to the current block
+    app.update_block(|b| {
+        b.height += 200;
+        b.time = b.time.plus_seconds(1000);
+    });
+    let snapshot1 = query_prices(&app);
+    assert_eq!(snapshot1.block_time_last, snapshot0.block_time_last + 1000);
+    for (old, new) in snapshot0
+        .cumulative_prices
+        .iter()
+        .zip(&snapshot1.cumulative_prices)
+    {
+        assert!(new.2 > old.2);
+    }
+
+    // pool is balanced, so both prices are exactly 1
+    let twap = query_twap(&app, &snapshot0).unwrap();
+    assert_eq!(twap.start_time, snapshot0.block_time_last);
+    assert_eq!(twap.end_time, snapshot1.block_time_last);
+    assert_eq!(twap.twap[0].2, Decimal::one());
+    assert_eq!(twap.twap[1].2, Decimal::one());
+
+    // swap uusd for uluna, making uusd cheaper
+    let msg = ExecuteMsg::Swap {
+        offer_asset: AssetInfo::Native("uusd".to_string()).with_balance(100_000_000_000u128),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        referral_address: None,
+        referral_commission: None,
+    };
+    app.execute_contract(
+        owner,
+        pair_instance.clone(),
+        &msg,
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000_000_000),
+        }],
+    )
+    .unwrap();
+
+    app.update_block(|b| {
+        b.height += 200;
+        b.time = b.time.plus_seconds(1000);
+    });
+    let snapshot2 = query_prices(&app);
+    for (old, new) in snapshot1
+        .cumulative_prices
+        .iter()
+        .zip(&snapshot2.cumulative_prices)
+    {
+        assert!(new.2 > old.2);
+    }
+
+    // since the swap, uusd is worth less than one uluna and vice versa
+    let twap = query_twap(&app, &snapshot1).unwrap();
+    assert!(twap.twap[0].2 < Decimal::one());
+    assert!(twap.twap[1].2 > Decimal::one());
+
+    // the average over the whole time is in between
+    let full_twap = query_twap(&app, &snapshot0).unwrap();
+    assert!(full_twap.twap[0].2 > twap.twap[0].2 && full_twap.twap[0].2 < Decimal::one());
+    assert!(full_twap.twap[1].2 < twap.twap[1].2 && full_twap.twap[1].2 > Decimal::one());
+
+    // an empty period is rejected
+    query_twap(&app, &snapshot2).unwrap_err();
+}
+
+#[test]
+fn spot_price_matches_small_swap() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(2_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner, pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let uusd = AssetInfo::Native("uusd".to_string());
+    let uluna = AssetInfo::Native("uluna".to_string());
+
+    let spot_price: Decimal = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::SpotPrice {
+                offer: uusd.clone(),
+                ask: uluna.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(spot_price, Decimal::from_ratio(2u128, 1u128));
+
+    // a tiny swap returns approximately the spot price
+    let offer_amount = Uint128::new(1_000_000);
+    let simulation: SimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::Simulation {
+                offer_asset: uusd.with_balance(offer_amount),
+                ask_asset_info: Some(uluna.clone()),
+                referral: false,
+                referral_commission: None,
+            },
+        )
+        .unwrap();
+    assert_approx_eq!(
+        simulation.return_amount,
+        offer_amount * spot_price,
+        "0.00001"
+    );
+
+    let spot_price: Decimal = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::SpotPrice {
+                offer: uluna.clone(),
+                ask: uusd,
+            },
+        )
+        .unwrap();
+    assert_eq!(spot_price, Decimal::percent(50));
+
+    // assets have to be part of the pair
+    app.wrap()
+        .query_wasm_smart::<Decimal>(
+            &pair_instance,
+            &QueryMsg::SpotPrice {
+                offer: AssetInfo::Native("uatom".to_string()),
+                ask: uluna.clone(),
+            },
+        )
+        .unwrap_err();
+    app.wrap()
+        .query_wasm_smart::<Decimal>(
+            &pair_instance,
+            &QueryMsg::SpotPrice {
+                offer: uluna.clone(),
+                ask: uluna,
+            },
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn pool_state_matches_individual_queries() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    // the factory lets trading start right away
+    let created_at = app.block_info().time.seconds();
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(2_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner, pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let state: PoolStateResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::PoolState {})
+        .unwrap();
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+
+    assert_eq!(
+        state,
+        PoolStateResponse {
+            assets: pool.assets,
+            total_share: pool.total_share,
+            fee_config: pair_info.fee_config,
+            pair_type: pair_info.pair_type,
+            trading_starts: created_at,
+        }
+    );
+    assert_eq!(state.total_share, Uint128::new(1_414_213_562_373));
+}
+
+#[test]
+fn cumulative_volume_tracks_swaps_in_both_directions() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let uusd = AssetInfoValidated::Native("uusd".to_string());
+    let uluna = AssetInfoValidated::Native("uluna".to_string());
+
+    // no swaps yet
+    let volume: CumulativeVolumeResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::CumulativeVolume {})
+        .unwrap();
+    assert_eq!(
+        volume,
+        CumulativeVolumeResponse {
+            offered: vec![],
+            returned: vec![],
+        }
+    );
+
+    // swaps the given asset and returns the amount received
+    let mut swap = |offer_asset: AssetValidated| -> Uint128 {
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                pair_instance.clone(),
+                &ExecuteMsg::Swap {
+                    offer_asset: offer_asset.clone().into(),
+                    ask_asset_info: None,
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    referral_address: None,
+                    referral_commission: None,
+                },
+                &[Coin {
+                    denom: offer_asset.info.to_string(),
+                    amount: offer_asset.amount,
+                }],
+            )
+            .unwrap();
+        let return_amount = res
+            .events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "return_amount")
+            .unwrap();
+        return_amount.value.parse().unwrap()
+    };
+
+    let usd_return1 = swap(uusd.with_balance(1_000_000u128));
+    let usd_return2 = swap(uusd.with_balance(3_000_000u128));
+    let luna_return = swap(uluna.with_balance(2_000_000u128));
+
+    let volume: CumulativeVolumeResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::CumulativeVolume {})
+        .unwrap();
+    assert_eq!(
+        volume,
+        CumulativeVolumeResponse {
+            offered: vec![
+                uusd.with_balance(4_000_000u128),
+                uluna.with_balance(2_000_000u128)
+            ],
+            returned: vec![
+                uluna.with_balance(usd_return1 + usd_return2),
+                uusd.with_balance(luna_return)
+            ],
+        }
+    );
+    // the returned amounts are after fees
+    assert!(usd_return1 + usd_return2 < Uint128::new(4_000_000));
+    assert!(luna_return < Uint128::new(2_000_000));
+}
+
+#[test]
+fn fee_apr_from_volume_over_window() {
+    let owner = Addr::unchecked("owner");
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    // create a pair with 0.3% fees
+    let factory = instantiate_factory(&mut app, &owner);
+    let asset_infos = vec![
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Native("uluna".to_string()),
+    ];
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::CreatePair {
+            pair_type: PairType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: Some(30),
+            staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let pair_instance = app
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr;
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let query_fee_apr = |app: &App, window_seconds: u64| -> Decimal {
+        app.wrap()
+            .query_wasm_smart(&pair_instance, &QueryMsg::FeeApr { window_seconds })
+            .unwrap()
+    };
+    let swap = |app: &mut App, denom: &str| {
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info(denom).with_balance(1_000_000_000u128),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+            &[Coin {
+                denom: denom.to_string(),
+                amount: Uint128::new(1_000_000_000),
+            }],
+        )
+        .unwrap();
+    };
+    let next_day = |app: &mut App| {
+        app.update_block(|b| b.time = b.time.plus_seconds(86_400));
+    };
+
+    // no volume yet
+    assert_eq!(query_fee_apr(&app, 86_400), Decimal::zero());
+
+    // 0.1% of each reserve is swapped on two consecutive days
+    swap(&mut app, "uusd");
+    next_day(&mut app);
+    swap(&mut app, "uluna");
+    next_day(&mut app);
+
+    // the volume of each asset is worth half of its share of the reserve, so the daily fee yield is
+    // 0.1% * 50% * 0.3% = 0.00015%, which is about 0.0548% per year
+    let lower = Decimal::from_ratio(54u128, 100_000u128);
+    let upper = Decimal::from_ratio(55u128, 100_000u128);
+    for window in [86_400, 2 * 86_400] {
+        let apr = query_fee_apr(&app, window);
+        assert!(lower < apr && apr < upper, "unexpected apr {apr}");
+    }
+    // longer windows than the lifetime of the pair fall back to its first swap
+    assert_eq!(
+        query_fee_apr(&app, 30 * 86_400),
+        query_fee_apr(&app, 2 * 86_400)
+    );
+
+    // without swaps in the window, the yield goes down
+    next_day(&mut app);
+    next_day(&mut app);
+    assert!(query_fee_apr(&app, 86_400) < lower);
+}
+
+#[test]
+fn swap_with_and_without_referral() {
+    let owner = Addr::unchecked("owner");
+    let referral = "referral";
+
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let uusd = AssetInfo::Native("uusd".to_string());
+    let uluna = AssetInfo::Native("uluna".to_string());
+    let swap_msg = |referral_address: Option<&str>, referral_commission| ExecuteMsg::Swap {
+        offer_asset: uusd.with_balance(1_000_000u128),
+        ask_asset_info: Some(uluna.clone()),
+        belief_price: None,
+        max_spread: None,
+        to: None,
+        referral_address: referral_address.map(str::to_string),
+        referral_commission,
+    };
+    let simulate = |app: &App, referral, referral_commission| {
+        app.wrap()
+            .query_wasm_smart::<SimulationResponse>(
+                &pair_instance,
+                &QueryMsg::Simulation {
+                    offer_asset: uusd.with_balance(1_000_000u128),
+                    ask_asset_info: Some(uluna.clone()),
+                    referral,
+                    referral_commission,
+                },
+            )
+            .unwrap()
+            .return_amount
+    };
+
+    // without a referral address, the whole offer is swapped
+    let expected = simulate(&app, false, None);
+    let res = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &swap_msg(None, Some(Decimal::percent(1))),
+            &[Coin::new(1_000_000, "uusd")],
+        )
+        .unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("offer_amount", "1000000")
+            .add_attribute("return_amount", expected),
+    );
+    assert_eq!(
+        app.wrap().query_balance(referral, "uusd").unwrap().amount,
+        Uint128::zero()
+    );
+
+    // the referral commission is taken from the offer asset and sent to the referral address
+    let expected = simulate(&app, true, Some(Decimal::percent(1)));
+    let res = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &swap_msg(Some(referral), Some(Decimal::percent(1))),
+            &[Coin::new(1_000_000, "uusd")],
+        )
+        .unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("offer_amount", "990000")
+            .add_attribute("return_amount", expected)
+            .add_attribute("referral_amount", "10000"),
+    );
+    // the swap is reported with exactly the standard set of keys
+    let swap_keys: Vec<_> = res
+        .events
+        .iter()
+        .filter(|event| event.ty == "wasm")
+        .flat_map(|event| &event.attributes)
+        .map(|attr| attr.key.as_str())
+        .filter(|key| SWAP_ATTRIBUTE_KEYS.contains(key))
+        .collect();
+    assert_eq!(swap_keys, SWAP_ATTRIBUTE_KEYS);
+    assert_eq!(
+        app.wrap().query_balance(referral, "uusd").unwrap().amount,
+        Uint128::new(10_000)
+    );
+
+    // the commission is capped by the factory
+    let err = app
+        .execute_contract(
+            owner,
+            pair_instance,
+            &swap_msg(Some(referral), Some(Decimal::percent(101))),
+            &[Coin::new(1_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::ReferralCommissionTooHigh {},
+        err.downcast().unwrap()
+    );
+}
+
+#[test]
+fn weighted_pool_has_less_price_impact_on_heavy_asset() {
+    let owner = Addr::unchecked("owner");
+    let coins = vec![
+        Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::new(100_000_000_000_000_u128),
+        },
+        Coin {
+            denom: "uluna".to_string(),
+            amount: Uint128::new(100_000_000_000_000_u128),
+        },
+    ];
+    let uusd = AssetInfo::Native("uusd".to_string());
+    let uluna = AssetInfo::Native("uluna".to_string());
+    let offer_amount = Uint128::new(100_000_000);
+
+    // returns the spread of swapping `offer_amount` in both directions, after checking that
+    // a reverse simulation of the received amount requires the same offer amount
+    let spreads = |weights: [u16; 2], pools: [u128; 2]| {
+        let mut app = mock_app(owner.clone(), coins.clone());
+        let pair_instance = instantiate_weighted_pair(&mut app, &owner, weights);
+
+        let (msg, coins) = provide_liquidity_msg(pools[0].into(), pools[1].into(), None, None);
+        app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+            .unwrap();
+
+        [(&uusd, &uluna), (&uluna, &uusd)].map(|(offer, ask)| {
+            // both pools are balanced in value, so the spot price is 1
+            let spot_price: Decimal = app
+                .wrap()
+                .query_wasm_smart(
+                    &pair_instance,
+                    &QueryMsg::SpotPrice {
+                        offer: offer.clone(),
+                        ask: ask.clone(),
+                    },
+                )
+                .unwrap();
+            assert_eq!(spot_price, Decimal::one());
+
+            let simulation: SimulationResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &pair_instance,
+                    &QueryMsg::Simulation {
+                        offer_asset: offer.with_balance(offer_amount),
+                        ask_asset_info: Some(ask.clone()),
+                        referral: false,
+                        referral_commission: None,
+                    },
+                )
+                .unwrap();
+            assert_eq!(
+                simulation.return_amount + simulation.spread_amount,
+                offer_amount
+            );
+
+            let reverse: ReverseSimulationResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &pair_instance,
+                    &QueryMsg::ReverseSimulation {
+                        offer_asset_info: Some(offer.clone()),
+                        ask_asset: ask.with_balance(simulation.return_amount),
+                        referral: false,
+                        referral_commission: None,
+                    },
+                )
+                .unwrap();
+            assert_approx_eq!(reverse.offer_amount, offer_amount, "0.000001");
+
+            simulation.spread_amount
+        })
+    };
+
+    // uusd is weighted 80%, so buying uusd has less price impact than selling it
+    let [sell_heavy, buy_heavy] = spreads([8_000, 2_000], [8_000_000_000, 2_000_000_000]);
+    assert!(
+        buy_heavy < sell_heavy,
+        "buying the heavy asset should have less price impact: {buy_heavy} >= {sell_heavy}"
+    );
+
+    // while both directions are the same in a 50/50 pool
+    let [sell, buy] = spreads([5_000, 5_000], [5_000_000_000, 5_000_000_000]);
+    assert_eq!(sell, buy);
+}
+
+#[test]
+fn weighted_pair_requires_valid_weights() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(owner.clone(), vec![]);
+    let factory = instantiate_factory(&mut app, &owner);
+
+    for weights in [[8_000, 1_000], [10_000, 0], [0, 10_000], [u16::MAX, 10_001]] {
+        let err = app
+            .execute_contract(
+                owner.clone(),
+                factory.clone(),
+                &FactoryExecuteMsg::CreatePair {
+                    pair_type: PairType::Weighted { weights },
+                    asset_infos: vec![
+                        AssetInfo::Native("uusd".to_string()),
+                        AssetInfo::Native("uluna".to_string()),
+                    ],
+                    init_params: None,
+                    total_fee_bps: None,
+                    staking_config: PartialStakeConfig::default(),
+                    lp_token_name: None,
+                    lp_token_symbol: None,
+                    trading_starts: None,
+                    trading_starts_delay: None,
+                    lp_token_type: None,
+                },
+                &[],
+            )
+            .unwrap_err();
+        assert_eq!(
+            sg_swap_factory::error::ContractError::InvalidWeights {},
+            err.downcast::<sg_swap_factory::error::ContractError>()
+                .unwrap()
+        );
+    }
+}
+
+#[test]
+fn swap_uses_updated_pair_fees() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    // the pair is instantiated by the factory
+    let factory = Addr::unchecked(
+        app.wrap()
+            .query_wasm_contract_info(&pair_instance)
+            .unwrap()
+            .creator,
+    );
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let swap = |app: &mut App| {
+        let offer_amount = Uint128::new(1_000_000);
+        app.execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: AssetInfo::Native("uusd".to_string()).with_balance(offer_amount),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+            &[Coin::new(offer_amount.u128(), "uusd")],
+        )
+        .unwrap();
+        app.wrap().query_balance(&owner, "uluna").unwrap().amount
+    };
+
+    let balance = app.wrap().query_balance(&owner, "uluna").unwrap().amount;
+    let return_without_fee = swap(&mut app) - balance;
+
+    // only the factory can update the fees
+    let fee_config = FeeConfig {
+        total_fee_bps: 100,
+        protocol_fee_bps: 0,
+    };
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::UpdateFees {
+            fee_config: fee_config.clone(),
+        },
+        &[],
+    )
+    .unwrap_err();
+
+    // invalid fees are rejected
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::UpdatePairFees {
+                asset_infos: vec![
+                    AssetInfo::Native("uusd".to_string()),
+                    AssetInfo::Native("uluna".to_string()),
+                ],
+                fee_config: FeeConfig {
+                    total_fee_bps: 10_001,
+                    protocol_fee_bps: 0,
+                },
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        sg_swap_factory::error::ContractError::PairConfigInvalidFeeBps {},
+        err.downcast::<sg_swap_factory::error::ContractError>()
+            .unwrap()
+    );
+
+    app.execute_contract(
+        owner.clone(),
+        factory,
+        &FactoryExecuteMsg::UpdatePairFees {
+            asset_infos: vec![
+                AssetInfo::Native("uusd".to_string()),
+                AssetInfo::Native("uluna".to_string()),
+            ],
+            fee_config: fee_config.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    let pair_info: PairInfo = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!(pair_info.fee_config, fee_config);
+
+    // the same swap now returns about 1% less
+    let balance = app.wrap().query_balance(&owner, "uluna").unwrap().amount;
+    let return_with_fee = swap(&mut app) - balance;
+    assert!(return_with_fee < return_without_fee);
+    assert_approx_eq!(
+        return_with_fee,
+        return_without_fee * Decimal::percent(99),
+        "0.00001"
+    );
+}
+
+#[test]
+fn protocol_fees_accrue_until_collected() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let factory = Addr::unchecked(
+        app.wrap()
+            .query_wasm_contract_info(&pair_instance)
+            .unwrap()
+            .creator,
+    );
+    app.execute_contract(
+        owner.clone(),
+        factory,
+        &FactoryExecuteMsg::UpdatePairFees {
+            asset_infos: vec![
+                AssetInfo::Native("uusd".to_string()),
+                AssetInfo::Native("uluna".to_string()),
+            ],
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 5000,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // swaps return the protocol fee they accrued
+    let swap = |app: &mut App, offer: &str| {
+        let offer_amount = Uint128::new(1_000_000);
+        let res = app
+            .execute_contract(
+                owner.clone(),
+                pair_instance.clone(),
+                &ExecuteMsg::Swap {
+                    offer_asset: AssetInfo::Native(offer.to_string()).with_balance(offer_amount),
+                    ask_asset_info: None,
+                    belief_price: None,
+                    max_spread: None,
+                    to: None,
+                    referral_address: None,
+                    referral_commission: None,
+                },
+                &[Coin::new(offer_amount.u128(), offer)],
+            )
+            .unwrap();
+        res.events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "protocol_fee_amount")
+            .unwrap()
+            .value
+            .parse::<u128>()
+            .unwrap()
+    };
+
+    let luna_fees = swap(&mut app, "uusd") + swap(&mut app, "uusd");
+    let usd_fees = swap(&mut app, "uluna");
+    assert!(luna_fees > 0 && usd_fees > 0);
+
+    let accrued: Vec<AssetValidated> = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::AccruedProtocolFees {})
+        .unwrap();
+    assert_eq!(
+        accrued,
+        vec![
+            AssetInfoValidated::Native("uluna".to_string()).with_balance(luna_fees),
+            AssetInfoValidated::Native("uusd".to_string()).with_balance(usd_fees),
+        ]
+    );
+
+    // the accrued fees are not part of the pool
+    let pool: PoolResponse = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pool {})
+        .unwrap();
+    for asset in pool.assets {
+        let balance = app
+            .wrap()
+            .query_balance(&pair_instance, asset.info.to_string())
+            .unwrap()
+            .amount;
+        let fee = accrued.iter().find(|fee| fee.info == asset.info).unwrap();
+        assert_eq!(asset.amount + fee.amount, balance);
+    }
+
+    // a single collect sends everything to the fee address
+    let usd_balance = app.wrap().query_balance(&owner, "uusd").unwrap().amount;
+    let luna_balance = app.wrap().query_balance(&owner, "uluna").unwrap().amount;
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        pair_instance.clone(),
+        &ExecuteMsg::CollectProtocolFees {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        app.wrap().query_balance(&owner, "uusd").unwrap().amount,
+        usd_balance + Uint128::new(usd_fees)
+    );
+    assert_eq!(
+        app.wrap().query_balance(&owner, "uluna").unwrap().amount,
+        luna_balance + Uint128::new(luna_fees)
+    );
+
+    let accrued: Vec<AssetValidated> = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::AccruedProtocolFees {})
+        .unwrap();
+    assert_eq!(accrued, vec![]);
+}
+
+#[test]
+fn protocol_fees_are_split_between_recipients() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let factory = Addr::unchecked(
+        app.wrap()
+            .query_wasm_contract_info(&pair_instance)
+            .unwrap()
+            .creator,
+    );
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::UpdatePairFees {
+            asset_infos: vec![
+                AssetInfo::Native("uusd".to_string()),
+                AssetInfo::Native("uluna".to_string()),
+            ],
+            fee_config: FeeConfig {
+                total_fee_bps: 100,
+                protocol_fee_bps: 5000,
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    let update_recipients = |app: &mut App, recipients: Vec<(&str, u64)>| {
+        app.execute_contract(
+            owner.clone(),
+            factory.clone(),
+            &FactoryExecuteMsg::UpdateConfig {
+                token_code_id: None,
+                fee_address: None,
+                only_owner_can_create_pairs: None,
+                fee_recipients: Some(
+                    recipients
+                        .into_iter()
+                        .map(|(addr, weight)| (addr.to_string(), Decimal::percent(weight)))
+                        .collect(),
+                ),
+            },
+            &[],
+        )
+    };
+
+    // weights have to sum up to one
+    let err = update_recipients(&mut app, vec![("treasury", 70), ("buyback", 20)]).unwrap_err();
+    assert_eq!(
+        sg_swap_factory::error::ContractError::InvalidFeeRecipients {},
+        err.downcast().unwrap()
+    );
+    update_recipients(&mut app, vec![("treasury", 70), ("buyback", 30)]).unwrap();
 
-    // Provide liquidity, accumulators firstly filled with the same prices
     let (msg, coins) = provide_liquidity_msg(
-        Uint128::new(2_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
         Uint128::new(1_000_000_000_000),
         None,
-        Some(Decimal::percent(50)),
+        None,
     );
-    app.execute_contract(user1.clone(), pair_instance.clone(), &msg, &coins)
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
         .unwrap();
 
-    // Get current twap accumulator values
-    let msg = QueryMsg::CumulativePrices {};
-    let cpr_old: CumulativePricesResponse =
-        app.wrap().query_wasm_smart(&pair_instance, &msg).unwrap();
+    app.execute_contract(
+        owner.clone(),
+        pair_instance.clone(),
+        &ExecuteMsg::Swap {
+            offer_asset: native_asset_info("uusd").with_balance(1_000_000_000u128),
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: None,
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        },
+        &[Coin::new(1_000_000_000, "uusd")],
+    )
+    .unwrap();
+    let accrued: Vec<AssetValidated> = app
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::AccruedProtocolFees {})
+        .unwrap();
+    // half of the 1% fee on the 999_000_999 returned
+    let luna_fees = accrued[0].amount;
+    assert_eq!(luna_fees, Uint128::new(4_995_004));
+
+    app.execute_contract(
+        Addr::unchecked("anyone"),
+        pair_instance.clone(),
+        &ExecuteMsg::CollectProtocolFees {},
+        &[],
+    )
+    .unwrap();
 
-    // A day later
-    app.update_block(|b| {
-        b.height += BLOCKS_PER_DAY;
-        b.time = b.time.plus_seconds(ELAPSED_SECONDS);
-    });
+    // the fees are split 70/30 and the rounding remainder goes to the last recipient
+    let treasury = app
+        .wrap()
+        .query_balance("treasury", "uluna")
+        .unwrap()
+        .amount;
+    let buyback = app.wrap().query_balance("buyback", "uluna").unwrap().amount;
+    assert_eq!(treasury, Uint128::new(3_496_502));
+    assert_eq!(buyback, Uint128::new(1_498_502));
+    assert_eq!(treasury + buyback, luna_fees);
+}
 
-    // Get current cumulative price values; they should have been updated by the query method with new 2/1 ratio
-    let msg = QueryMsg::CumulativePrices {};
-    let cpr_new: CumulativePricesResponse =
-        app.wrap().query_wasm_smart(&pair_instance, &msg).unwrap();
+#[test]
+fn reverse_simulation_round_trips_forward_simulation() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
 
-    let twap0 = cpr_new.cumulative_prices[0].2 - cpr_old.cumulative_prices[0].2;
-    let twap1 = cpr_new.cumulative_prices[1].2 - cpr_old.cumulative_prices[1].2;
+    let factory = instantiate_factory(&mut app, &owner);
+    let asset_infos = vec![
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Native("uluna".to_string()),
+    ];
+    app.execute_contract(
+        owner.clone(),
+        factory.clone(),
+        &FactoryExecuteMsg::CreatePair {
+            pair_type: PairType::Xyk {},
+            asset_infos: asset_infos.clone(),
+            init_params: None,
+            total_fee_bps: Some(30),
+            staking_config: PartialStakeConfig::default(),
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let pair_instance = app
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr;
 
-    // Prices weren't changed for the last day, uusd amount in pool = 3000000_000000, uluna = 2000000_000000
-    // In accumulators we don't have any precision so we rely on elapsed time so we don't need to consider it
-    let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
-    assert_eq!(twap0 / price_precision, Uint128::new(57600)); // 0.666666 * ELAPSED_SECONDS (86400)
-    assert_eq!(twap1 / price_precision, Uint128::new(129600)); //   1.5 * ELAPSED_SECONDS
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(2_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner, pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let uusd = AssetInfo::Native("uusd".to_string());
+    let uluna = AssetInfo::Native("uluna".to_string());
+    for (offer, ask) in [(&uusd, &uluna), (&uluna, &uusd)] {
+        for ask_amount in [1_000_000u128, 100_000_000, 10_000_000_000] {
+            let ask_amount = Uint128::new(ask_amount);
+            let reverse: ReverseSimulationResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &pair_instance,
+                    &QueryMsg::ReverseSimulation {
+                        offer_asset_info: Some(offer.clone()),
+                        ask_asset: ask.with_balance(ask_amount),
+                        referral: false,
+                        referral_commission: None,
+                    },
+                )
+                .unwrap();
+
+            let simulation: SimulationResponse = app
+                .wrap()
+                .query_wasm_smart(
+                    &pair_instance,
+                    &QueryMsg::Simulation {
+                        offer_asset: offer.with_balance(reverse.offer_amount),
+                        ask_asset_info: Some(ask.clone()),
+                        referral: false,
+                        referral_commission: None,
+                    },
+                )
+                .unwrap();
+
+            // offering the reverse simulated amount returns the requested amount
+            assert_approx_eq!(simulation.return_amount, ask_amount, "0.002");
+            assert_approx_eq!(
+                simulation.commission_amount,
+                reverse.commission_amount,
+                "0.002"
+            );
+            assert_approx_eq!(
+                simulation.commission_amount,
+                (simulation.return_amount + simulation.commission_amount) * Decimal::permille(3),
+                "0.002"
+            );
+        }
+    }
 }
 
 #[test]
@@ -784,6 +2512,10 @@ fn create_pair_with_same_assets() {
         init_params: None,
         staking_config: default_stake_config(staking_contract_code_id).to_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -842,6 +2574,7 @@ fn provide_liquidity_with_one_asset() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
         .execute_contract(
@@ -863,6 +2596,93 @@ fn provide_liquidity_with_one_asset() {
     assert_eq!(100_000_000_000 + 49_999, res.total_share.u128());
 }
 
+#[test]
+fn simulate_provide_matches_provide() {
+    let owner = Addr::unchecked("owner");
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_100_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(400_000_000_000u128),
+            },
+        ],
+    );
+
+    let pair = instantiate_pair(&mut router, &owner);
+
+    // the first deposit mints the geometric mean minus the locked minimum liquidity
+    let res: SimulateProvideResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &pair,
+            &QueryMsg::SimulateProvide {
+                assets: vec![
+                    AssetInfo::Native("uusd".to_string()).with_balance(100_000_000_000u128),
+                    AssetInfo::Native("uluna".to_string()).with_balance(400_000_000_000u128),
+                ],
+                slippage_tolerance: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.share.u128(), 200_000_000_000 - 1_000);
+    assert!(res.imbalance_fee.is_empty());
+
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::from(100_000_000_000u128),
+        Uint128::from(400_000_000_000u128),
+        None,
+        None,
+    );
+    router
+        .execute_contract(owner.clone(), pair.clone(), &msg, &coins)
+        .unwrap();
+
+    // providing a single asset swaps part of it first
+    let assets = vec![AssetInfo::Native("uusd".to_string()).with_balance(100_000u128)];
+    let res: SimulateProvideResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &pair,
+            &QueryMsg::SimulateProvide {
+                assets: assets.clone(),
+                slippage_tolerance: None,
+            },
+        )
+        .unwrap();
+
+    let before: PoolResponse = router
+        .wrap()
+        .query_wasm_smart(&pair, &QueryMsg::Pool {})
+        .unwrap();
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets,
+        slippage_tolerance: None,
+        receiver: None,
+        min_lp_amount: None,
+    };
+    router
+        .execute_contract(
+            owner.clone(),
+            pair.clone(),
+            &msg,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000u128),
+            }],
+        )
+        .unwrap();
+    let after: PoolResponse = router
+        .wrap()
+        .query_wasm_smart(&pair, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(res.share, after.total_share - before.total_share);
+}
+
 #[test]
 fn provide_liquidity_with_swap() {
     // This is more of a reference implementation to compare `provide_liquidity_with_one_asset` to.
@@ -989,6 +2809,7 @@ fn provide_liquidity_with_unequal_pool() {
         assets: vec![AssetInfo::Native("uluna".to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
         .execute_contract(
@@ -1035,6 +2856,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     let err = router
         .execute_contract(
@@ -1068,6 +2890,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(0u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     let err = router
         .execute_contract(owner.clone(), pair.clone(), &msg, &[])
@@ -1080,6 +2903,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let err = router
@@ -1106,10 +2930,192 @@ fn provide_liquidity_with_one_cw20_asset() {
     );
 
     let factory = instantiate_factory(&mut router, &owner);
-    // create pair
+    // create pair
+    let asset_infos = vec![
+        AssetInfo::Token(token1.to_string()),
+        AssetInfo::Token(token2.to_string()),
+    ];
+    let msg = FactoryExecuteMsg::CreatePair {
+        pair_type: PairType::Xyk {},
+        asset_infos: asset_infos.clone(),
+        init_params: None,
+        total_fee_bps: None,
+        staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
+    };
+    router
+        .execute_contract(owner.clone(), factory.clone(), &msg, &[])
+        .unwrap();
+    // get pair address
+    let pair = router
+        .wrap()
+        .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
+        .unwrap()
+        .contract_addr;
+
+    // increase allowances
+    router
+        .execute_contract(
+            owner.clone(),
+            token1.clone(),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: pair.to_string(),
+                expires: None,
+                amount: 100_000_000_000u128.into(),
+            },
+            &[],
+        )
+        .unwrap();
+    router
+        .execute_contract(
+            owner.clone(),
+            token2.clone(),
+            &Cw20ExecuteMsg::IncreaseAllowance {
+                spender: pair.to_string(),
+                expires: None,
+                amount: 100_000_100_000u128.into(),
+            },
+            &[],
+        )
+        .unwrap();
+
+    // first provide liquidity with two assets
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![
+            AssetInfo::Token(token1.to_string()).with_balance(100_000_000_000u128),
+            AssetInfo::Token(token2.to_string()).with_balance(100_000_000_000u128),
+        ],
+        slippage_tolerance: None,
+        receiver: None,
+        min_lp_amount: None,
+    };
+    router
+        .execute_contract(owner.clone(), pair.clone(), &msg, &[])
+        .unwrap();
+
+    // then with only one asset
+    let msg = ExecuteMsg::ProvideLiquidity {
+        assets: vec![AssetInfo::Token(token2.to_string()).with_balance(100_000u128)],
+        slippage_tolerance: None,
+        receiver: None,
+        min_lp_amount: None,
+    };
+    router
+        .execute_contract(owner.clone(), pair.clone(), &msg, &[])
+        .unwrap();
+
+    // should have no more balance of token2
+    assert_eq!(
+        query_token_balance(&router.wrap(), token2, owner)
+            .unwrap()
+            .u128(),
+        0
+    );
+    let res: PoolResponse = router
+        .wrap()
+        .query_wasm_smart(pair, &QueryMsg::Pool {})
+        .unwrap();
+    // should have 100_000_000_000 + 49999 shares (losing some because of spread)
+    assert_eq!(100_000_000_000 + 49_999, res.total_share.u128());
+}
+
+/// A cw20 token that burns 10% of every `TransferFrom` and `Send`, so the recipient receives less than was sent.
+/// `Send` still reports the full amount to the receiving contract.
+fn deflationary_execute(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: TokenExecuteMsg,
+) -> Result<Response, cw20_base::ContractError> {
+    match msg {
+        TokenExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+        } => {
+            let fee = amount.multiply_ratio(1u128, 10u128);
+            cw20_base::allowances::execute_burn_from(
+                deps.branch(),
+                env.clone(),
+                info.clone(),
+                owner.clone(),
+                fee,
+            )?;
+            cw20_base::allowances::execute_transfer_from(
+                deps,
+                env,
+                info,
+                owner,
+                recipient,
+                amount - fee,
+            )
+        }
+        TokenExecuteMsg::Send {
+            contract,
+            amount,
+            msg,
+        } => {
+            let fee = amount.multiply_ratio(1u128, 10u128);
+            cw20_base::contract::execute_burn(deps.branch(), env.clone(), info.clone(), fee)?;
+            let res = cw20_base::contract::execute_transfer(
+                deps,
+                env,
+                info.clone(),
+                contract.clone(),
+                amount - fee,
+            )?;
+            Ok(res.add_message(
+                Cw20ReceiveMsg {
+                    sender: info.sender.to_string(),
+                    amount,
+                    msg,
+                }
+                .into_cosmos_msg(contract)?,
+            ))
+        }
+        msg => cw20_base::contract::execute(deps, env, info, msg),
+    }
+}
+
+#[test]
+fn deflationary_token_deposits_use_received_amount() {
+    let owner = Addr::unchecked("owner");
+    let mut router = mock_app(owner.clone(), vec![coin(1_000_000, "uusd")]);
+
+    let token_code_id = router.store_code(Box::new(ContractWrapper::new_with_empty(
+        deflationary_execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    let token = router
+        .instantiate_contract(
+            token_code_id,
+            owner.clone(),
+            &TokenInstantiateMsg {
+                name: "Deflationary token".to_string(),
+                symbol: "DEFL".to_string(),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin {
+                    address: owner.to_string(),
+                    amount: Uint128::new(1_000_000),
+                }],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            String::from("DEFL"),
+            None,
+        )
+        .unwrap();
+
+    let factory = instantiate_factory(&mut router, &owner);
     let asset_infos = vec![
-        AssetInfo::Token(token1.to_string()),
-        AssetInfo::Token(token2.to_string()),
+        AssetInfo::Native("uusd".to_string()),
+        AssetInfo::Token(token.to_string()),
     ];
     let msg = FactoryExecuteMsg::CreatePair {
         pair_type: PairType::Xyk {},
@@ -1117,79 +3123,148 @@ fn provide_liquidity_with_one_cw20_asset() {
         init_params: None,
         total_fee_bps: None,
         staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
     router
         .execute_contract(owner.clone(), factory.clone(), &msg, &[])
         .unwrap();
-    // get pair address
     let pair = router
         .wrap()
         .query_wasm_smart::<PairInfo>(factory, &FactoryQueryMsg::Pair { asset_infos })
         .unwrap()
         .contract_addr;
 
-    // increase allowances
-    router
-        .execute_contract(
-            owner.clone(),
-            token1.clone(),
-            &Cw20ExecuteMsg::IncreaseAllowance {
-                spender: pair.to_string(),
-                expires: None,
-                amount: 100_000_000_000u128.into(),
-            },
-            &[],
-        )
-        .unwrap();
     router
         .execute_contract(
             owner.clone(),
-            token2.clone(),
+            token.clone(),
             &Cw20ExecuteMsg::IncreaseAllowance {
                 spender: pair.to_string(),
                 expires: None,
-                amount: 100_000_100_000u128.into(),
+                amount: Uint128::new(1_000_000),
             },
             &[],
         )
         .unwrap();
 
-    // first provide liquidity with two assets
+    // the pair only receives 90_000 of the provided tokens
     let msg = ExecuteMsg::ProvideLiquidity {
         assets: vec![
-            AssetInfo::Token(token1.to_string()).with_balance(100_000_000_000u128),
-            AssetInfo::Token(token2.to_string()).with_balance(100_000_000_000u128),
+            AssetInfo::Native("uusd".to_string()).with_balance(100_000u128),
+            AssetInfo::Token(token.to_string()).with_balance(100_000u128),
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
-        .execute_contract(owner.clone(), pair.clone(), &msg, &[])
+        .execute_contract(owner.clone(), pair.clone(), &msg, &[coin(100_000, "uusd")])
         .unwrap();
 
-    // then with only one asset
-    let msg = ExecuteMsg::ProvideLiquidity {
-        assets: vec![AssetInfo::Token(token2.to_string()).with_balance(100_000u128)],
-        slippage_tolerance: None,
-        receiver: None,
+    let res: PoolResponse = router
+        .wrap()
+        .query_wasm_smart(&pair, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(
+        res.assets,
+        vec![
+            AssetInfoValidated::Native("uusd".to_string()).with_balance(100_000u128),
+            AssetInfoValidated::Token(token.clone()).with_balance(90_000u128),
+        ]
+    );
+    // the shares are minted for the received amounts
+    assert_eq!(res.total_share.u128(), 94_868);
+
+    // swapping 10_000 tokens only swaps the 9_000 the pair receives
+    let simulation: SimulationResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &pair,
+            &QueryMsg::Simulation {
+                offer_asset: AssetInfo::Token(token.to_string()).with_balance(9_000u128),
+                ask_asset_info: None,
+                referral: false,
+                referral_commission: None,
+            },
+        )
+        .unwrap();
+    let msg = ExecuteMsg::Swap {
+        offer_asset: AssetInfo::Token(token.to_string()).with_balance(10_000u128),
+        ask_asset_info: None,
+        belief_price: None,
+        max_spread: Some(Decimal::percent(50)),
+        to: None,
+        referral_address: None,
+        referral_commission: None,
     };
     router
         .execute_contract(owner.clone(), pair.clone(), &msg, &[])
         .unwrap();
 
-    // should have no more balance of token2
     assert_eq!(
-        query_token_balance(&router.wrap(), token2, owner)
-            .unwrap()
-            .u128(),
-        0
+        router.wrap().query_balance(&owner, "uusd").unwrap().amount,
+        Uint128::new(900_000) + simulation.return_amount
     );
     let res: PoolResponse = router
         .wrap()
-        .query_wasm_smart(pair, &QueryMsg::Pool {})
+        .query_wasm_smart(&pair, &QueryMsg::Pool {})
         .unwrap();
-    // should have 100_000_000_000 + 49999 shares (losing some because of spread)
-    assert_eq!(100_000_000_000 + 49_999, res.total_share.u128());
+    assert_eq!(
+        res.assets,
+        vec![
+            AssetInfoValidated::Native("uusd".to_string())
+                .with_balance(Uint128::new(100_000) - simulation.return_amount),
+            AssetInfoValidated::Token(token.clone()).with_balance(99_000u128),
+        ]
+    );
+
+    // swapping through the receive hook also swaps what the pair received instead of the reported amount
+    let simulation: SimulationResponse = router
+        .wrap()
+        .query_wasm_smart(
+            &pair,
+            &QueryMsg::Simulation {
+                offer_asset: AssetInfo::Token(token.to_string()).with_balance(9_000u128),
+                ask_asset_info: None,
+                referral: false,
+                referral_commission: None,
+            },
+        )
+        .unwrap();
+    let uusd_before = router.wrap().query_balance(&owner, "uusd").unwrap().amount;
+    let msg = Cw20ExecuteMsg::Send {
+        contract: pair.to_string(),
+        amount: Uint128::new(10_000),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_asset_info: None,
+            belief_price: None,
+            max_spread: Some(Decimal::percent(50)),
+            to: None,
+            referral_address: None,
+            referral_commission: None,
+        })
+        .unwrap(),
+    };
+    router
+        .execute_contract(owner.clone(), token.clone(), &msg, &[])
+        .unwrap();
+
+    assert_eq!(
+        router.wrap().query_balance(&owner, "uusd").unwrap().amount,
+        uusd_before + simulation.return_amount
+    );
+    let res: PoolResponse = router
+        .wrap()
+        .query_wasm_smart(&pair, &QueryMsg::Pool {})
+        .unwrap();
+    assert_eq!(
+        res.assets[1],
+        AssetInfoValidated::Token(token).with_balance(108_000u128)
+    );
 }
 
 #[test]
@@ -1207,6 +3282,10 @@ fn wrong_number_of_assets() {
         init_params: None,
         staking_config: default_stake_config(staking_contract_code_id).to_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -1240,6 +3319,10 @@ fn wrong_number_of_assets() {
         init_params: None,
         staking_config: default_stake_config(staking_contract_code_id).to_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -1262,3 +3345,266 @@ fn wrong_number_of_assets() {
         "Invalid number of assets. This pair supports at least 2 and at most 2 assets within a pool"
     );
 }
+
+#[test]
+fn estimate_swap_fees() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let estimate = |app: &App, pair: &Addr| -> EstimateSwapFeesResponse {
+        app.wrap()
+            .query_wasm_smart(
+                pair,
+                &QueryMsg::EstimateSwapFees {
+                    offer_asset: native_asset_info("uusd").with_balance(1_000_000_000u128),
+                    ask_asset_info: None,
+                },
+            )
+            .unwrap()
+    };
+    // constant product output of the swap before fees
+    let gross_return = Uint128::new(999_000_999);
+
+    // the pair has no fees yet
+    assert_eq!(
+        estimate(&app, &pair_instance),
+        EstimateSwapFeesResponse {
+            lp_fee: Uint128::zero(),
+            protocol_fee: Uint128::zero(),
+            spread: Uint128::new(999_001),
+            return_amount: gross_return,
+        }
+    );
+
+    let factory = Addr::unchecked(
+        app.wrap()
+            .query_wasm_contract_info(&pair_instance)
+            .unwrap()
+            .creator,
+    );
+    let update_fees = FactoryExecuteMsg::UpdatePairFees {
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Native("uluna".to_string()),
+        ],
+        fee_config: FeeConfig {
+            total_fee_bps: 100,
+            protocol_fee_bps: 5000,
+        },
+    };
+    app.execute_contract(owner.clone(), factory, &update_fees, &[])
+        .unwrap();
+
+    // 1% fee, half of which goes to the protocol
+    let fees = estimate(&app, &pair_instance);
+    assert_eq!(fees.protocol_fee, Uint128::new(4_995_004));
+    assert_eq!(fees.lp_fee, Uint128::new(4_995_005));
+    assert_eq!(fees.spread, Uint128::new(999_001));
+    assert_eq!(
+        fees.lp_fee + fees.protocol_fee,
+        gross_return - fees.return_amount
+    );
+
+    // matches the simulation
+    let simulation: SimulationResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &pair_instance,
+            &QueryMsg::Simulation {
+                offer_asset: native_asset_info("uusd").with_balance(1_000_000_000u128),
+                ask_asset_info: None,
+                referral: false,
+                referral_commission: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(fees.return_amount, simulation.return_amount);
+    assert_eq!(
+        fees.lp_fee + fees.protocol_fee,
+        simulation.commission_amount
+    );
+
+    // without protocol fee recipients, the swap does not take a protocol fee
+    let factory = instantiate_factory_with_fee_address(&mut app, &owner, None);
+    let pair = create_pair(&mut app, &owner, &factory);
+    app.execute_contract(owner.clone(), pair.clone(), &msg, &coins)
+        .unwrap();
+    app.execute_contract(owner.clone(), factory, &update_fees, &[])
+        .unwrap();
+    assert_eq!(
+        estimate(&app, &pair),
+        EstimateSwapFeesResponse {
+            lp_fee: fees.lp_fee + fees.protocol_fee,
+            protocol_fee: Uint128::zero(),
+            spread: fees.spread,
+            return_amount: fees.return_amount,
+        }
+    );
+}
+
+#[test]
+fn simulation_reports_exceeding_default_spread() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    let simulate = |amount: u128| -> SimulationResponse {
+        app.wrap()
+            .query_wasm_smart(
+                &pair_instance,
+                &QueryMsg::Simulation {
+                    offer_asset: native_asset_info("uusd").with_balance(amount),
+                    ask_asset_info: None,
+                    referral: false,
+                    referral_commission: None,
+                },
+            )
+            .unwrap()
+    };
+
+    // swapping 0.1% of the pool stays below the default max spread of 0.5%
+    assert!(!simulate(1_000_000_000).spread_exceeds_default);
+    // swapping 1% of the pool has a spread of almost 1%
+    assert!(simulate(10_000_000_000).spread_exceeds_default);
+
+    // executing the flagged swap without a max spread indeed fails
+    let err = app
+        .execute_contract(
+            owner.clone(),
+            pair_instance.clone(),
+            &ExecuteMsg::Swap {
+                offer_asset: native_asset_info("uusd").with_balance(10_000_000_000u128),
+                ask_asset_info: None,
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+            &[Coin::new(10_000_000_000, "uusd")],
+        )
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Operation exceeds max spread limit"
+    );
+}
+
+#[test]
+fn provide_liquidity_with_min_lp_amount() {
+    let owner = Addr::unchecked("owner");
+    let mut app = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000_000_u128),
+            },
+        ],
+    );
+
+    let pair_instance = instantiate_pair(&mut app, &owner);
+    let (msg, coins) = provide_liquidity_msg(
+        Uint128::new(1_000_000_000_000),
+        Uint128::new(1_000_000_000_000),
+        None,
+        None,
+    );
+    app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+
+    // providing 0.1% of the pool mints 0.1% of the LP supply
+    let provide = |min_lp_amount: u128| {
+        let (msg, coins) = provide_liquidity_msg(
+            Uint128::new(1_000_000_000),
+            Uint128::new(1_000_000_000),
+            None,
+            None,
+        );
+        let msg = match msg {
+            ExecuteMsg::ProvideLiquidity {
+                assets,
+                slippage_tolerance,
+                receiver,
+                ..
+            } => ExecuteMsg::ProvideLiquidity {
+                assets,
+                slippage_tolerance,
+                receiver,
+                min_lp_amount: Some(Uint128::new(min_lp_amount)),
+            },
+            _ => unreachable!(),
+        };
+        (msg, coins)
+    };
+
+    let (msg, coins) = provide(1_000_000_001);
+    let err = app
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap_err();
+    assert_eq!(
+        err.root_cause().to_string(),
+        "Providing liquidity mints 1000000000 LP tokens, which is less than the minimum of 1000000001"
+    );
+
+    let (msg, coins) = provide(1_000_000_000);
+    let res = app
+        .execute_contract(owner.clone(), pair_instance.clone(), &msg, &coins)
+        .unwrap();
+    assert_eq!(
+        res.events
+            .iter()
+            .flat_map(|event| &event.attributes)
+            .find(|attr| attr.key == "share")
+            .unwrap()
+            .value,
+        "1000000000"
+    );
+}
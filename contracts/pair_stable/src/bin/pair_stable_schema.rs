@@ -1,10 +1,11 @@
 use cosmwasm_schema::write_api;
-use sg_swap::pair::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use sg_swap::pair::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 
 fn main() {
     write_api! {
         instantiate: InstantiateMsg,
         query: QueryMsg,
         execute: ExecuteMsg,
+        migrate: MigrateMsg,
     }
 }
@@ -10,30 +10,37 @@ use cosmwasm_std::{
 };
 use cw2::set_contract_version;
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cw_utils::ensure_from_older_version;
 use itertools::Itertools;
 
 use sg_swap::asset::{
-    addr_opt_validate, check_swap_parameters, Asset, AssetInfo, AssetInfoValidated, AssetValidated,
-    Decimal256Ext, DecimalAsset, MINIMUM_LIQUIDITY_AMOUNT,
+    addr_opt_validate, check_swap_parameters, Asset, AssetInfo, AssetInfoExt, AssetInfoValidated,
+    AssetValidated, Decimal256Ext, DecimalAsset, MINIMUM_LIQUIDITY_AMOUNT,
 };
 use sg_swap::common::{claim_ownership, drop_ownership_proposal, propose_new_owner};
 use sg_swap::factory::PairType;
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
-    add_referral, assert_max_spread, check_asset_infos, check_assets, check_cw20_in_pool,
-    create_lp_token, get_share_in_assets, handle_referral, handle_reply, migration_check,
-    mint_token_message, save_tmp_staking_config, take_referral, ConfigResponse, Cw20HookMsg,
-    InstantiateMsg, StablePoolParams, StablePoolUpdateParams,
+    accrue_fee, accumulate_volume, add_referral, assert_max_spread, assert_min_assets,
+    assert_min_lp_amount, calculate_twap, check_asset_infos, check_assets, check_cw20_in_pool,
+    create_lp_token, cw20_balances_before, cw20_tokens, get_share_in_assets, handle_referral,
+    handle_reply, migration_check, mint_token_message, protocol_fee_msgs, pull_swap_offer,
+    received_amount, received_hook_amount, replace_received_amounts, save_cw20_reserves,
+    save_tmp_staking_config, simulate_withdraw, spread_exceeds, swap_attributes, take_referral,
+    ConfigResponse, Cw20HookMsg, InstantiateMsg, MigrateMsg, StablePoolParams,
+    StablePoolUpdateParams,
 };
 use sg_swap::pair::{
-    CumulativePricesResponse, ExecuteMsg, PairInfo, PoolResponse, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse, StablePoolConfig,
+    CumulativePricesResponse, CumulativeVolumeResponse, EstimateSwapFeesResponse, ExecuteMsg,
+    LpTokenType, PairInfo, PoolResponse, PoolStateResponse, QueryMsg, ReverseSimulationResponse,
+    SimulateProvideResponse, SimulationResponse, StablePoolConfig, TwapResponse,
 };
 use sg_swap::querier::{query_factory_config, query_fee_info, query_supply};
-use sg_swap::DecimalCheckedOps;
+use sg_swap::{decimal2decimal256, DecimalCheckedOps};
 
 use crate::math::{
-    calc_y, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE, MIN_AMP_CHANGING_TIME,
+    calc_spot_price, calc_y, calc_y_d, compute_d, AMP_PRECISION, MAX_AMP, MAX_AMP_CHANGE,
+    MIN_AMP_CHANGING_TIME,
 };
 use crate::state::{get_precision, store_precisions, Config, CONFIG, OWNERSHIP_PROPOSAL};
 use crate::utils::{
@@ -66,6 +73,12 @@ pub fn instantiate(
     }
 
     msg.validate_fees()?;
+    msg.validate_lp_token_info()?;
+    // stable pairs only issue cw20 LP shares
+    if msg.lp_token_type != LpTokenType::Cw20 {
+        return Err(ContractError::NonSupported {});
+    }
+    let default_max_spread = msg.default_max_spread()?;
 
     let params: StablePoolParams = from_binary(&msg.init_params.unwrap())?;
 
@@ -95,6 +108,8 @@ pub fn instantiate(
         msg.token_code_id,
         &asset_infos,
         &factory_addr,
+        msg.lp_token_name.clone(),
+        msg.lp_token_symbol.clone(),
     )?;
 
     let config = Config {
@@ -116,6 +131,11 @@ pub fn instantiate(
         greatest_precision,
         cumulative_prices,
         trading_starts: msg.trading_starts,
+        paused: false,
+        default_max_spread,
+        accrued_protocol_fees: vec![],
+        offered_volume: vec![],
+        returned_volume: vec![],
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -141,6 +161,10 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 /// * **ExecuteMsg::UpdateConfig { params: Binary }** Updates the contract configuration with the specified
 /// input parameters.
 ///
+/// * **ExecuteMsg::StartAmpRamp { next_amp, next_amp_time }** Starts gradually changing the pool amplification.
+///
+/// * **ExecuteMsg::StopAmpRamp {}** Stops changing the pool amplification.
+///
 /// * **ExecuteMsg::Receive(msg)** Receives a message of type [`Cw20ReceiveMsg`] and processes
 /// it depending on the received template.
 ///
@@ -148,17 +172,26 @@ pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractE
 ///             assets,
 ///             slippage_tolerance,
 ///             receiver,
+///             min_lp_amount,
 ///         }** Provides liquidity in the pair using the specified input parameters.
 ///
+/// * **ExecuteMsg::ProvideReceived { .. }** Continues providing cw20 tokens with the amounts the pair received.
+///
 /// * **ExecuteMsg::Swap {
 ///             offer_asset,
 ///             belief_price,
 ///             max_spread,
 ///             to,
 ///         }** Performs an swap using the specified parameters.
+///
+/// * **ExecuteMsg::SwapReceived { .. }** Continues swapping a cw20 token with the amount the pair received.
+///
+/// * **ExecuteMsg::SetPaused { paused }** Pauses or unpauses swaps and liquidity operations.
+///
+/// * **ExecuteMsg::CollectProtocolFees {}** Sends the accrued protocol fees to the fee address.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
-    deps: DepsMut,
+    mut deps: DepsMut,
     env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
@@ -169,15 +202,92 @@ pub fn execute(
         return Err(ContractError::PairIsNotMigrated {});
     }
 
+    if cfg.paused
+        && matches!(
+            msg,
+            ExecuteMsg::Receive(_) | ExecuteMsg::ProvideLiquidity { .. } | ExecuteMsg::Swap { .. }
+        )
+    {
+        return Err(ContractError::PairPaused {});
+    }
+
+    // the cw20 tokens that arrived before this message, as opposed to the ones the pair sends itself
+    let asset_infos = cfg.pair_info.asset_infos.clone();
+    let received = match &msg {
+        ExecuteMsg::Receive(_) => vec![info.sender.clone()],
+        ExecuteMsg::ProvideReceived { .. } | ExecuteMsg::SwapReceived { .. } => {
+            cw20_tokens(&asset_infos)
+        }
+        _ => vec![],
+    };
+
+    let res = execute_msg(deps.branch(), env.clone(), info, msg, cfg)?;
+    save_cw20_reserves(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &asset_infos,
+        &received,
+        &res,
+    )?;
+    Ok(res)
+}
+
+/// Executes the [`ExecuteMsg`] after [`execute`] checked that the pair can handle it.
+fn execute_msg(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+    cfg: Config,
+) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::UpdateConfig { params } => update_config(deps, env, info, params),
+        ExecuteMsg::StartAmpRamp {
+            next_amp,
+            next_amp_time,
+        } => {
+            assert_owner(deps.as_ref(), &info, &cfg)?;
+            start_changing_amp(cfg, deps, env, next_amp, next_amp_time)?;
+            Ok(Response::new().add_attributes(vec![
+                attr("action", "start_amp_ramp"),
+                attr("next_amp", next_amp.to_string()),
+                attr("next_amp_time", next_amp_time.to_string()),
+            ]))
+        }
+        ExecuteMsg::StopAmpRamp {} => {
+            assert_owner(deps.as_ref(), &info, &cfg)?;
+            stop_changing_amp(cfg, deps, env)?;
+            Ok(Response::new().add_attribute("action", "stop_amp_ramp"))
+        }
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
         ExecuteMsg::ProvideLiquidity {
             assets,
             receiver,
             slippage_tolerance: _,
-        } => provide_liquidity(deps, env, info, assets, receiver),
+            min_lp_amount,
+        } => provide_liquidity(deps, env, info, assets, receiver, min_lp_amount),
+        ExecuteMsg::ProvideReceived {
+            sender,
+            assets,
+            prev_balances,
+            receiver,
+            min_lp_amount,
+            ..
+        } => provide_received(
+            deps,
+            env,
+            info,
+            sender,
+            assets,
+            prev_balances,
+            receiver,
+            min_lp_amount,
+        ),
+        ExecuteMsg::WithdrawLiquidity { .. } => Err(ContractError::NonSupported {}),
         ExecuteMsg::UpdateFees { fee_config } => update_fees(deps, info, fee_config),
+        ExecuteMsg::SetPaused { paused } => set_paused(deps, info, paused),
+        ExecuteMsg::CollectProtocolFees {} => collect_protocol_fees(deps),
         ExecuteMsg::Swap {
             offer_asset,
             ask_asset_info,
@@ -188,8 +298,24 @@ pub fn execute(
             referral_commission,
         } => {
             let offer_asset = offer_asset.validate(deps.api)?;
-            if !offer_asset.is_native_token() {
-                return Err(ContractError::Unauthorized {});
+            match &offer_asset.info {
+                AssetInfoValidated::Token(contract_addr) => {
+                    check_cw20_in_pool(&cfg.pair_info.asset_infos, contract_addr)?;
+                    return pull_swap_offer(
+                        &deps.querier,
+                        &env,
+                        info,
+                        offer_asset,
+                        ask_asset_info,
+                        belief_price,
+                        max_spread,
+                        to,
+                        referral_address,
+                        referral_commission,
+                    );
+                }
+                AssetInfoValidated::Native(_) => {}
+                AssetInfoValidated::Cw1155 { .. } => return Err(ContractError::Unauthorized {}),
             }
 
             let to_addr = addr_opt_validate(deps.api, &to)?;
@@ -209,6 +335,50 @@ pub fn execute(
                 referral_commission,
             )
         }
+        ExecuteMsg::SwapReceived {
+            sender,
+            offer_asset_info,
+            prev_balance,
+            ask_asset_info,
+            belief_price,
+            max_spread,
+            to,
+            referral_address,
+            referral_commission,
+        } => {
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized {});
+            }
+            let sender = deps.api.addr_validate(&sender)?;
+            let offer_asset_info = offer_asset_info.validate(deps.api)?;
+            let to_addr = addr_opt_validate(deps.api, &to)?;
+            let referral_address = addr_opt_validate(deps.api, &referral_address)?;
+
+            // swap what the pair actually received, which can be less than the pulled amount
+            let amount = received_amount(
+                &deps.querier,
+                &env.contract.address,
+                &offer_asset_info,
+                prev_balance,
+            )?;
+
+            swap(
+                deps,
+                env,
+                info,
+                sender,
+                AssetValidated {
+                    info: offer_asset_info,
+                    amount,
+                },
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to_addr,
+                referral_address,
+                referral_commission,
+            )
+        }
         ExecuteMsg::ProposeNewOwner { owner, expires_in } => {
             let config = CONFIG.load(deps.storage)?;
             let factory_config = query_factory_config(&deps.querier, config.factory_addr)?;
@@ -278,6 +448,14 @@ pub fn receive_cw20(
             let referral_address = addr_opt_validate(deps.api, &referral_address)?;
             let contract_addr = info.sender.clone();
             let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            // swap what the pair actually received, which can be less than the reported amount
+            let amount = received_hook_amount(
+                deps.storage,
+                &deps.querier,
+                &env.contract.address,
+                &contract_addr,
+                cw20_msg.amount,
+            )?;
             swap(
                 deps,
                 env,
@@ -285,7 +463,7 @@ pub fn receive_cw20(
                 sender,
                 AssetValidated {
                     info: AssetInfoValidated::Token(contract_addr),
-                    amount: cw20_msg.amount,
+                    amount,
                 },
                 ask_asset_info,
                 belief_price,
@@ -295,13 +473,21 @@ pub fn receive_cw20(
                 referral_commission,
             )
         }
-        Cw20HookMsg::WithdrawLiquidity { assets } => {
+        Cw20HookMsg::WithdrawLiquidity { assets, min_assets } => {
             let sender = deps.api.addr_validate(&cw20_msg.sender)?;
-            withdraw_liquidity(deps, env, info, sender, cw20_msg.amount, assets)
+            withdraw_liquidity(deps, env, info, sender, cw20_msg.amount, assets, min_assets)
+        }
+        Cw20HookMsg::WithdrawToAsset { asset, min_out } => {
+            let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+            withdraw_to_asset(deps, env, info, sender, cw20_msg.amount, asset, min_out)
         }
     }
 }
 
+/// Updates the fee configuration of the pair. Subsequent swaps use the new fees.
+///
+/// ## Executor
+/// Only the factory, the pair owner or, if no owner is set, the factory owner can execute this.
 pub fn update_fees(
     deps: DepsMut,
     info: MessageInfo,
@@ -310,8 +496,12 @@ pub fn update_fees(
     let mut config = CONFIG.load(deps.storage)?;
 
     // check permissions
-    if Some(info.sender) != config.owner {
-        return Err(ContractError::Unauthorized {});
+    if info.sender != config.factory_addr {
+        assert_owner(deps.as_ref(), &info, &config)?;
+    }
+
+    if !fee_config.valid_fee_bps() {
+        return Err(ContractError::InvalidFeeBps {});
     }
 
     // update config
@@ -321,6 +511,59 @@ pub fn update_fees(
     Ok(Response::default())
 }
 
+/// Pauses or unpauses swaps, liquidity provision and withdrawal.
+///
+/// ## Executor
+/// Only the factory can execute this.
+pub fn set_paused(
+    deps: DepsMut,
+    info: MessageInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+
+    // check permissions
+    if info.sender != config.factory_addr {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    config.paused = paused;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_paused"),
+        attr("paused", paused.to_string()),
+    ]))
+}
+
+/// Sends all accrued protocol fees to the fee address of the factory,
+/// or splits them between its fee recipients if it has any.
+///
+/// ## Executor
+/// Anyone can execute this.
+pub fn collect_protocol_fees(deps: DepsMut) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let recipients = factory_config.protocol_fee_recipients();
+    if recipients.is_empty() {
+        return Err(ContractError::NoFeeAddress {});
+    }
+
+    let fees = std::mem::take(&mut config.accrued_protocol_fees);
+    CONFIG.save(deps.storage, &config)?;
+
+    let messages = protocol_fee_msgs(&fees, &recipients)?;
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "collect_protocol_fees"),
+        attr(
+            "fee_address",
+            recipients.iter().map(|(addr, _)| addr).join(", "),
+        ),
+        attr("fees", fees.iter().join(", ")),
+    ]))
+}
+
 /// Provides liquidity with the specified input parameters.
 ///
 /// * **assets** vector with assets available in the pool.
@@ -328,44 +571,233 @@ pub fn update_fees(
 ///
 /// * **receiver** address that receives LP tokens. If this address isn't specified, the function will default to the caller.
 ///
+/// * **min_lp_amount** optional minimum amount of LP tokens that need to be minted.
+///
 /// NOTE - the address that wants to provide liquidity should approve the pair contract to pull its relevant tokens.
+/// When cw20 tokens are provided, the pair continues with [`ExecuteMsg::ProvideReceived`] after pulling them,
+/// so that the deposits are the amounts it actually received.
 pub fn provide_liquidity(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     assets: Vec<Asset>,
     receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
     let assets = check_assets(deps.api, &assets)?;
-    let mut config = CONFIG.load(deps.storage)?;
+    let config = CONFIG.load(deps.storage)?;
 
-    if assets.len() > config.pair_info.asset_infos.len() {
-        return Err(ContractError::TooManyAssets {
-            max: config.pair_info.asset_infos.len(),
-            provided: assets.len(),
-        });
+    let pools = query_pools_map(deps.as_ref(), &env, &config)?;
+
+    for asset in &assets {
+        asset.assert_sent_native_token_balance(&info)?;
     }
+    let mut assets_collection = collect_deposits(&config, &pools, &assets)?;
 
-    let pools: HashMap<_, _> = config
-        .pair_info
+    // the received cw20 amounts are only known once the transfers went through
+    let prev_balances = cw20_balances_before(&deps.querier, &env.contract.address, &assets)?;
+    if !prev_balances.is_empty() {
+        let mut messages = vec![];
+        for deposit in &assets {
+            // If the pool is a token contract, then we need to execute a TransferFrom msg to receive funds
+            if let AssetInfoValidated::Token(contract_addr) = &deposit.info {
+                if !deposit.amount.is_zero() {
+                    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                        contract_addr: contract_addr.to_string(),
+                        msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                            owner: info.sender.to_string(),
+                            recipient: env.contract.address.to_string(),
+                            amount: deposit.amount,
+                        })?,
+                        funds: vec![],
+                    }))
+                }
+            }
+        }
+        messages.push(
+            wasm_execute(
+                &env.contract.address,
+                &ExecuteMsg::ProvideReceived {
+                    sender: info.sender.to_string(),
+                    assets: assets.into_iter().map(Into::into).collect(),
+                    prev_balances: prev_balances.into_iter().map(Into::into).collect(),
+                    slippage_tolerance: None,
+                    receiver,
+                    min_lp_amount,
+                },
+                vec![],
+            )?
+            .into(),
+        );
+        return Ok(Response::new().add_messages(messages));
+    }
+
+    for (deposit, pool) in assets_collection.iter_mut() {
+        // Without cw20 deposits, the pool balance already increased by the native deposits
+        // To calculate the pool balance properly, we should subtract the user deposit from the recorded pool token amount
+        *pool = pool.checked_sub(deposit.amount)?;
+    }
+
+    provide_deposits(
+        deps,
+        env,
+        config,
+        info.sender,
+        assets,
+        pools,
+        assets_collection,
+        receiver,
+        min_lp_amount,
+    )
+}
+
+/// Continues a [`provide_liquidity`] with cw20 tokens after the pair pulled them,
+/// replacing the amounts of the pulled tokens by what the pair received.
+///
+/// ## Executor
+/// Only the pair itself can execute this.
+#[allow(clippy::too_many_arguments)]
+fn provide_received(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: String,
+    assets: Vec<Asset>,
+    prev_balances: Vec<Asset>,
+    receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let sender = deps.api.addr_validate(&sender)?;
+    let mut assets = check_assets(deps.api, &assets)?;
+    let prev_balances = check_assets(deps.api, &prev_balances)?;
+    replace_received_amounts(
+        &deps.querier,
+        &env.contract.address,
+        &mut assets,
+        &prev_balances,
+    )?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let pools = query_pools_map(deps.as_ref(), &env, &config)?;
+    let mut assets_collection = collect_deposits(&config, &pools, &assets)?;
+
+    // all deposits were transferred to the pair by now, so none of them are part of the pool
+    for (deposit, pool) in assets_collection.iter_mut() {
+        *pool = pool.checked_sub(deposit.amount)?;
+    }
+
+    provide_deposits(
+        deps,
+        env,
+        config,
+        sender,
+        assets,
+        pools,
+        assets_collection,
+        receiver,
+        min_lp_amount,
+    )
+}
+
+/// Returns the pool balances of the pair by asset.
+fn query_pools_map(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+) -> StdResult<HashMap<AssetInfoValidated, Uint128>> {
+    Ok(config
         .query_pools(&deps.querier, &env.contract.address)?
         .into_iter()
         .map(|pool| (pool.info, pool.amount))
-        .collect();
+        .collect())
+}
 
-    let mut non_zero_flag = false;
+/// Mints LP shares for the deposited `assets`.
+///
+/// Important: When providing the pool balances in `assets_collection`, make sure that those do *not* include the deposits.
+#[allow(clippy::too_many_arguments)]
+fn provide_deposits(
+    deps: DepsMut,
+    env: Env,
+    mut config: Config,
+    sender: Addr,
+    assets: Vec<AssetValidated>,
+    pools: HashMap<AssetInfoValidated, Uint128>,
+    assets_collection: Vec<(AssetValidated, Uint128)>,
+    receiver: Option<String>,
+    min_lp_amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let mut messages = vec![];
+    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
+    let (share, _) = compute_provide_share(
+        deps.as_ref(),
+        &env,
+        &config,
+        &assets_collection,
+        total_share,
+    )?;
+    assert_min_lp_amount(share, min_lp_amount)?;
+    if total_share.is_zero() {
+        messages.extend(mint_token_message(
+            &config.pair_info.liquidity_token,
+            &env.contract.address,
+            MINIMUM_LIQUIDITY_AMOUNT,
+        )?);
+    }
 
-    let mut assets_collection = assets
-        .clone()
+    // Mint LP token for the caller (or for the receiver if it was set)
+    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| sender.clone());
+    messages.extend(mint_token_message(
+        &config.pair_info.liquidity_token,
+        &receiver,
+        share,
+    )?);
+
+    let pools = pools
         .into_iter()
-        .map(|asset| {
-            asset.assert_sent_native_token_balance(&info)?;
+        .map(|(info, amount)| {
+            let precision = get_precision(deps.storage, &info)?;
+            Ok(DecimalAsset {
+                info,
+                amount: Decimal256::with_precision(amount, precision)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
 
-            // Check that at least one asset is non-zero
-            if !asset.amount.is_zero() {
-                non_zero_flag = true;
-            }
+    if accumulate_prices(deps.as_ref(), env, &mut config, &pools)? {
+        CONFIG.save(deps.storage, &config)?;
+    }
 
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "provide_liquidity"),
+        attr("sender", sender),
+        attr("receiver", receiver),
+        attr("assets", assets.iter().join(", ")),
+        attr("share", share),
+    ]))
+}
+
+/// Pairs each pool with the deposited amount of its asset, adding zero deposits for omitted assets.
+/// Fails if an asset is not in the pool, if nothing is deposited, or if an empty pool would receive nothing.
+fn collect_deposits(
+    config: &Config,
+    pools: &HashMap<AssetInfoValidated, Uint128>,
+    assets: &[AssetValidated],
+) -> Result<Vec<(AssetValidated, Uint128)>, ContractError> {
+    if assets.len() > config.pair_info.asset_infos.len() {
+        return Err(ContractError::TooManyAssets {
+            max: config.pair_info.asset_infos.len(),
+            provided: assets.len(),
+        });
+    }
+
+    let mut assets_collection = assets
+        .iter()
+        .cloned()
+        .map(|asset| {
             // Get appropriate pool
             let pool = pools
                 .get(&asset.info)
@@ -389,53 +821,52 @@ pub fn provide_liquidity(
         }
     });
 
-    if !non_zero_flag {
+    // Check that at least one asset is non-zero
+    if assets.iter().all(|asset| asset.amount.is_zero()) {
         return Err(ContractError::InvalidZeroAmount {});
     }
 
-    let mut messages = vec![];
-    for (deposit, pool) in assets_collection.iter_mut() {
-        // We cannot put a zero amount into an empty pool.
-        if deposit.amount.is_zero() && pool.is_zero() {
-            return Err(ContractError::InvalidProvideLPsWithSingleToken {});
-        }
-
-        // Transfer only non-zero amount
-        if !deposit.amount.is_zero() {
-            // If the pool is a token contract, then we need to execute a TransferFrom msg to receive funds
-            if let AssetInfoValidated::Token(contract_addr) = &deposit.info {
-                messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-                    contract_addr: contract_addr.to_string(),
-                    msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
-                        owner: info.sender.to_string(),
-                        recipient: env.contract.address.to_string(),
-                        amount: deposit.amount,
-                    })?,
-                    funds: vec![],
-                }))
-            } else {
-                // If the asset is a native token, the pool balance already increased
-                // To calculate the pool balance properly, we should subtract the user deposit from the recorded pool token amount
-                *pool = pool.checked_sub(deposit.amount)?;
-            }
-        }
+    // We cannot put a zero amount into an empty pool.
+    if assets_collection
+        .iter()
+        .any(|(deposit, pool)| deposit.amount.is_zero() && pool.is_zero())
+    {
+        return Err(ContractError::InvalidProvideLPsWithSingleToken {});
     }
 
+    Ok(assets_collection)
+}
+
+/// Calculates the amount of LP tokens minted for the given deposits, as well as the fee charged
+/// in each asset for an imbalanced provide.
+/// The first deposit mints the invariant D minus the [`MINIMUM_LIQUIDITY_AMOUNT`] that stays locked in the pair.
+///
+/// Important: When providing the pool balances in `assets_collection`, make sure that those do *not* include the deposits.
+fn compute_provide_share(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    assets_collection: &[(AssetValidated, Uint128)],
+    total_share: Uint128,
+) -> Result<(Uint128, Vec<AssetValidated>), ContractError> {
+    let precisions = assets_collection
+        .iter()
+        .map(|(asset, _)| get_precision(deps.storage, &asset.info))
+        .collect::<StdResult<Vec<_>>>()?;
     let assets_collection = assets_collection
         .iter()
-        .cloned()
-        .map(|(asset, pool)| {
-            let coin_precision = get_precision(deps.storage, &asset.info)?;
+        .zip(&precisions)
+        .map(|((asset, pool), &coin_precision)| {
             Ok((
                 asset.to_decimal_asset(coin_precision)?,
-                Decimal256::with_precision(pool, coin_precision)?,
+                Decimal256::with_precision(*pool, coin_precision)?,
             ))
         })
         .collect::<StdResult<Vec<(DecimalAsset, Decimal256)>>>()?;
 
     let n_coins = config.pair_info.asset_infos.len() as u8;
 
-    let amp = compute_current_amp(&config, &env)?;
+    let amp = compute_current_amp(config, env)?;
 
     // Initial invariant (D)
     let old_balances = assets_collection
@@ -451,94 +882,70 @@ pub fn provide_liquidity(
         .collect::<StdResult<Vec<_>>>()?;
     let deposit_d = compute_d(amp, &new_balances, config.greatest_precision)?;
 
-    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
-    let share = if total_share.is_zero() {
+    if total_share.is_zero() {
         let share = deposit_d
             .to_uint128_with_precision(config.greatest_precision)?
             .checked_sub(MINIMUM_LIQUIDITY_AMOUNT)
             .map_err(|_| ContractError::MinimumLiquidityAmountError {})?;
 
-        messages.extend(mint_token_message(
-            &config.pair_info.liquidity_token,
-            &env.contract.address,
-            MINIMUM_LIQUIDITY_AMOUNT,
-        )?);
-
         // share cannot become zero after minimum liquidity subtraction
         if share.is_zero() {
             return Err(ContractError::MinimumLiquidityAmountError {});
         }
 
-        share
-    } else {
-        // Get fee info from the factory
-        let fee_info = query_fee_info(
-            &deps.querier,
-            &config.factory_addr,
-            config.pair_info.pair_type.clone(),
-        )?;
-
-        // total_fee_rate * N_COINS / (4 * (N_COINS - 1))
-        let fee = fee_info
-            .total_fee_rate
-            .checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
-
-        let fee = Decimal256::new(fee.atomics().into());
-
-        for i in 0..n_coins as usize {
-            let ideal_balance = deposit_d.checked_multiply_ratio(old_balances[i], init_d)?;
-            let difference = if ideal_balance > new_balances[i] {
-                ideal_balance - new_balances[i]
-            } else {
-                new_balances[i] - ideal_balance
-            };
-            // Fee will be charged only during imbalanced provide i.e. if invariant D was changed
-            new_balances[i] -= fee.checked_mul(difference)?;
-        }
+        // no fee is charged for the first deposit
+        let fees = assets_collection
+            .iter()
+            .map(|(deposit, _)| AssetValidated {
+                info: deposit.info.clone(),
+                amount: Uint128::zero(),
+            })
+            .collect();
+        return Ok((share, fees));
+    }
 
-        let after_fee_d = compute_d(amp, &new_balances, config.greatest_precision)?;
+    // Get fee info from the factory
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+    )?;
 
-        let share = Decimal256::with_precision(total_share, config.greatest_precision)?
-            .checked_multiply_ratio(after_fee_d.saturating_sub(init_d), init_d)?
-            .to_uint128_with_precision(config.greatest_precision)?;
+    // total_fee_rate * N_COINS / (4 * (N_COINS - 1))
+    let fee = fee_info
+        .total_fee_rate
+        .checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
 
-        if share.is_zero() {
-            return Err(ContractError::LiquidityAmountTooSmall {});
-        }
+    let fee = Decimal256::new(fee.atomics().into());
 
-        share
-    };
+    let mut fees = Vec::with_capacity(n_coins as usize);
+    for i in 0..n_coins as usize {
+        let ideal_balance = deposit_d.checked_multiply_ratio(old_balances[i], init_d)?;
+        let difference = if ideal_balance > new_balances[i] {
+            ideal_balance - new_balances[i]
+        } else {
+            new_balances[i] - ideal_balance
+        };
+        // Fee will be charged only during imbalanced provide i.e. if invariant D was changed
+        let imbalance_fee = fee.checked_mul(difference)?;
+        new_balances[i] -= imbalance_fee;
+        fees.push(AssetValidated {
+            info: assets_collection[i].0.info.clone(),
+            amount: imbalance_fee.to_uint128_with_precision(precisions[i])?,
+        });
+    }
 
-    // Mint LP token for the caller (or for the receiver if it was set)
-    let receiver = addr_opt_validate(deps.api, &receiver)?.unwrap_or_else(|| info.sender.clone());
-    messages.extend(mint_token_message(
-        &config.pair_info.liquidity_token,
-        &receiver,
-        share,
-    )?);
+    let after_fee_d = compute_d(amp, &new_balances, config.greatest_precision)?;
 
-    let pools = pools
-        .into_iter()
-        .map(|(info, amount)| {
-            let precision = get_precision(deps.storage, &info)?;
-            Ok(DecimalAsset {
-                info,
-                amount: Decimal256::with_precision(amount, precision)?,
-            })
-        })
-        .collect::<StdResult<Vec<_>>>()?;
+    let share = Decimal256::with_precision(total_share, config.greatest_precision)?
+        .checked_multiply_ratio(after_fee_d.saturating_sub(init_d), init_d)?
+        .to_uint128_with_precision(config.greatest_precision)?;
 
-    if accumulate_prices(deps.as_ref(), env, &mut config, &pools)? {
-        CONFIG.save(deps.storage, &config)?;
+    if share.is_zero() {
+        return Err(ContractError::LiquidityAmountTooSmall {});
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "provide_liquidity"),
-        attr("sender", info.sender),
-        attr("receiver", receiver),
-        attr("assets", assets.iter().join(", ")),
-        attr("share", share),
-    ]))
+    Ok((share, fees))
 }
 
 /// Withdraw liquidity from the pool.
@@ -547,6 +954,8 @@ pub fn provide_liquidity(
 /// * **amount** is the amount of LP tokens to burn.
 ///
 /// * **assets** optional array which specifies the assets amount to withdraw.
+///
+/// * **min_assets** optional minimum amounts of the assets to receive.
 pub fn withdraw_liquidity(
     deps: DepsMut,
     env: Env,
@@ -554,6 +963,7 @@ pub fn withdraw_liquidity(
     sender: Addr,
     amount: Uint128,
     assets: Vec<Asset>,
+    min_assets: Option<Vec<Asset>>,
 ) -> Result<Response, ContractError> {
     let assets = check_assets(deps.api, &assets)?;
     let mut config = CONFIG.load(deps.storage)?;
@@ -589,6 +999,7 @@ pub fn withdraw_liquidity(
         }
         refund_assets = assets;
     }
+    assert_min_assets(deps.api, &refund_assets, min_assets)?;
 
     messages.extend(
         refund_assets
@@ -649,7 +1060,6 @@ fn imbalanced_withdraw(
     }
 
     let pools: HashMap<_, _> = config
-        .pair_info
         .query_pools(&deps.querier, &env.contract.address)?
         .into_iter()
         .map(|pool| (pool.info, pool.amount))
@@ -761,6 +1171,167 @@ fn imbalanced_withdraw(
     Ok(burn_amount)
 }
 
+/// Withdraw liquidity from the pool entirely into a single asset.
+/// * **sender** is the address that will receive the asset.
+///
+/// * **amount** is the amount of LP tokens to burn.
+///
+/// * **asset** is the pool asset to receive.
+///
+/// * **min_out** optional minimum amount of the asset to receive.
+pub fn withdraw_to_asset(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    sender: Addr,
+    amount: Uint128,
+    asset: AssetInfo,
+    min_out: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let asset = asset.validate(deps.api)?;
+    let mut config = CONFIG.load(deps.storage)?;
+
+    if info.sender != config.pair_info.liquidity_token {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let refund_asset = AssetValidated {
+        amount: compute_withdraw_to_asset(deps.as_ref(), &env, &config, amount, &asset)?,
+        info: asset,
+    };
+    if let Some(min_out) = min_out {
+        if refund_asset.amount < min_out {
+            return Err(ContractError::WithdrawSlippage {
+                returned: refund_asset.to_string(),
+                min: refund_asset.info.with_balance(min_out).to_string(),
+            });
+        }
+    }
+
+    let messages: Vec<CosmosMsg> = vec![
+        refund_asset.clone().into_msg(&sender)?,
+        wasm_execute(
+            &config.pair_info.liquidity_token,
+            &Cw20ExecuteMsg::Burn { amount },
+            vec![],
+        )?
+        .into(),
+    ];
+
+    let pools = config
+        .query_pools(&deps.querier, &env.contract.address)?
+        .iter()
+        .map(|pool| {
+            let precision = get_precision(deps.storage, &pool.info)?;
+            pool.to_decimal_asset(precision)
+        })
+        .collect::<StdResult<Vec<DecimalAsset>>>()?;
+
+    if accumulate_prices(deps.as_ref(), env, &mut config, &pools)? {
+        CONFIG.save(deps.storage, &config)?;
+    }
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "withdraw_to_asset"),
+        attr("sender", sender),
+        attr("withdrawn_share", amount),
+        attr("refund_assets", refund_asset.to_string()),
+    ]))
+}
+
+/// Computes how much of `asset` is returned for burning `amount` LP tokens when withdrawing
+/// into that single asset. The imbalance fee is charged just like in [`imbalanced_withdraw`].
+fn compute_withdraw_to_asset(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    amount: Uint128,
+    asset: &AssetInfoValidated,
+) -> Result<Uint128, ContractError> {
+    let (pools, total_share) = pool_info(deps.querier, config)?;
+    if !pools.iter().any(|pool| pool.info == *asset) {
+        return Err(ContractError::InvalidAsset(asset.to_string()));
+    }
+    if amount >= total_share {
+        return Err(
+            StdError::generic_err("Cannot withdraw all liquidity into a single asset").into(),
+        );
+    }
+
+    let pools = pools
+        .iter()
+        .map(|pool| {
+            let precision = get_precision(deps.storage, &pool.info)?;
+            pool.to_decimal_asset(precision)
+        })
+        .collect::<StdResult<Vec<DecimalAsset>>>()?;
+    let precision = get_precision(deps.storage, asset)?;
+    let amp = compute_current_amp(config, env)?;
+
+    // Initial invariant (D) and the invariant after burning the LP tokens
+    let balances = pools.iter().map(|pool| pool.amount).collect_vec();
+    let init_d = compute_d(amp, &balances, config.greatest_precision)?;
+    let withdraw_d = init_d.checked_multiply_ratio(
+        Decimal256::from_integer(total_share - amount),
+        Decimal256::from_integer(total_share),
+    )?;
+
+    // Balance of the asset if all the value was taken from it without fees
+    let new_balance = Decimal256::with_precision(
+        calc_y_d(asset, &pools, withdraw_d, amp, precision)?,
+        precision,
+    )?;
+
+    // Get fee info from the factory
+    let fee_info = query_fee_info(
+        &deps.querier,
+        &config.factory_addr,
+        config.pair_info.pair_type.clone(),
+    )?;
+
+    // total_fee_rate * N_COINS / (4 * (N_COINS - 1))
+    let n_coins = config.pair_info.asset_infos.len() as u8;
+    let fee = fee_info
+        .total_fee_rate
+        .checked_mul(Decimal::from_ratio(n_coins, 4 * (n_coins - 1)))?;
+    let fee = Decimal256::new(fee.atomics().into());
+
+    // Charge the fee on the difference to a balanced withdrawal
+    let reduced_pools = pools
+        .iter()
+        .map(|pool| {
+            let ideal_balance = pool.amount.checked_multiply_ratio(withdraw_d, init_d)?;
+            let new_balance = if pool.info == *asset {
+                new_balance
+            } else {
+                pool.amount
+            };
+            let difference = if ideal_balance > new_balance {
+                ideal_balance - new_balance
+            } else {
+                new_balance - ideal_balance
+            };
+            Ok(DecimalAsset {
+                info: pool.info.clone(),
+                amount: pool.amount - fee.checked_mul(difference)?,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let reduced_pool = reduced_pools
+        .iter()
+        .find(|pool| pool.info == *asset)
+        .ok_or_else(|| ContractError::InvalidAsset(asset.to_string()))?
+        .amount
+        .to_uint128_with_precision(precision)?;
+    let reduced_balance = calc_y_d(asset, &reduced_pools, withdraw_d, amp, precision)?;
+
+    // In case of rounding errors - make it unfavorable for the "attacker"
+    Ok(reduced_pool
+        .checked_sub(reduced_balance)?
+        .saturating_sub(Uint128::one()))
+}
+
 /// Performs an swap operation with the specified parameters.
 ///
 /// * **sender** is the sender of the swap operation.
@@ -788,6 +1359,9 @@ pub fn swap(
     referral_address: Option<Addr>,
     referral_commission: Option<Decimal>,
 ) -> Result<Response, ContractError> {
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
     offer_asset.assert_sent_native_token_balance(&info)?;
 
     let ask_asset_info = ask_asset_info.map(|a| a.validate(deps.api)).transpose()?;
@@ -799,7 +1373,6 @@ pub fn swap(
     // If the asset balance already increased
     // We should subtract the user deposit from the pool offer asset amount
     let pools = config
-        .pair_info
         .query_pools(&deps.querier, &env.contract.address)?
         .into_iter()
         .map(|mut pool| {
@@ -819,6 +1392,7 @@ pub fn swap(
     // Get config from the factory
     let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
 
+    let original_offer_amount = offer_asset.amount;
     handle_referral(
         &factory_config,
         referral_address,
@@ -833,6 +1407,9 @@ pub fn swap(
     let offer_precision = get_precision(deps.storage, &offer_pool.info)?;
 
     // Check if the liquidity is non-zero
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::PoolEmpty {});
+    }
     check_swap_parameters(
         pools
             .iter()
@@ -863,11 +1440,14 @@ pub fn swap(
         .total_fee_rate()
         .checked_mul_uint128(return_amount)?;
     let return_amount = return_amount.saturating_sub(commission_amount);
+    if return_amount.is_zero() {
+        return Err(ContractError::SwapTooSmall {});
+    }
 
-    // Check the max spread limit (if it was specified)
+    // Check the max spread limit, falling back to the default max spread
     assert_max_spread(
         belief_price,
-        max_spread,
+        max_spread.or(Some(config.default_max_spread)),
         offer_asset.amount,
         return_amount,
         spread_amount + commission_amount,
@@ -875,49 +1455,51 @@ pub fn swap(
 
     let receiver = to.unwrap_or_else(|| sender.clone());
 
-    messages.push(
-        AssetValidated {
-            info: ask_pool.info.clone(),
-            amount: return_amount,
-        }
-        .into_msg(&receiver)?,
-    );
+    let return_asset = AssetValidated {
+        info: ask_pool.info.clone(),
+        amount: return_amount,
+    };
+    messages.push(return_asset.into_msg(&receiver)?);
 
     // Compute the protocol fee
     let mut protocol_fee_amount = Uint128::zero();
-    if let Some(fee_address) = factory_config.fee_address {
+    if !factory_config.protocol_fee_recipients().is_empty() {
         if let Some(f) = calculate_protocol_fee(
             &ask_pool.info,
             commission_amount,
             config.pair_info.fee_config.protocol_fee_rate(),
         ) {
             protocol_fee_amount = f.amount;
-            messages.push(f.into_msg(fee_address)?);
+            accrue_fee(&mut config.accrued_protocol_fees, f)?;
         }
     }
 
-    if accumulate_prices(deps.as_ref(), env, &mut config, &pools)? {
-        CONFIG.save(deps.storage, &config)?;
-    }
+    accumulate_volume(&mut config.offered_volume, &offer_asset);
+    accumulate_volume(&mut config.returned_volume, &return_asset);
+
+    accumulate_prices(deps.as_ref(), env, &mut config, &pools)?;
+    CONFIG.save(deps.storage, &config)?;
 
     Ok(Response::new()
         .add_messages(
-            // 1. send collateral tokens from the contract to a user
-            // 2. send inactive commission fees to the protocol
+            // send collateral tokens from the contract to a user
             messages,
         )
         .add_attributes(vec![
             attr("action", "swap"),
             attr("sender", sender),
             attr("receiver", receiver),
-            attr("offer_asset", offer_asset.info.to_string()),
-            attr("ask_asset", ask_pool.info.to_string()),
-            attr("offer_amount", offer_asset.amount),
-            attr("return_amount", return_amount),
-            attr("spread_amount", spread_amount),
-            attr("commission_amount", commission_amount),
-            attr("protocol_fee_amount", protocol_fee_amount),
-        ]))
+        ])
+        .add_attributes(swap_attributes(
+            &offer_asset.info,
+            &ask_pool.info,
+            offer_asset.amount,
+            return_amount,
+            spread_amount,
+            commission_amount,
+            original_offer_amount - offer_asset.amount,
+        ))
+        .add_attribute("protocol_fee_amount", protocol_fee_amount))
 }
 
 /// Calculates the amount of fees the protocol gets according to specified pair parameters.
@@ -954,21 +1536,51 @@ pub fn calculate_protocol_fee(
 /// * **QueryMsg::Share { amount }** Returns the amount of assets that could be withdrawn from the pool
 /// using a specific amount of LP tokens. The result is returned in a vector that contains objects of type [`Asset`].
 ///
+/// * **QueryMsg::SimulateWithdraw { amount }** Returns the amount of assets that withdrawing a specific amount
+/// of LP tokens would return. Fails if there is no liquidity or the amount exceeds the LP token supply.
+///
+/// * **QueryMsg::SharesToAssets { shares }** Returns the assets that the given amount of LP-equivalent shares,
+/// such as the shares of an sg721-pair NFT, are redeemable for. Calculated like `SimulateWithdraw`.
+///
+/// * **QueryMsg::SimulateProvide { assets, .. }** Returns the amount of LP tokens that providing the given assets
+/// would mint, as well as the imbalance fee, using a [`SimulateProvideResponse`] object.
+///
 /// * **QueryMsg::Simulation { offer_asset }** Returns the result of a swap simulation using a [`SimulationResponse`] object.
 ///
+/// * **QueryMsg::EstimateSwapFees { offer_asset }** Returns the LP fee, protocol fee and spread of a swap
+/// using an [`EstimateSwapFeesResponse`] object.
+///
 /// * **QueryMsg::ReverseSimulation { ask_asset }** Returns the result of a reverse swap simulation using
 /// a [`ReverseSimulationResponse`] object.
 ///
 /// * **QueryMsg::CumulativePrices {}** Returns information about cumulative prices for the assets in the
 /// pool using a [`CumulativePricesResponse`] object.
 ///
+/// * **QueryMsg::Twap { start_cumulative, start_time }** Returns the time-weighted average prices
+/// since the given snapshot using a [`TwapResponse`] object.
+///
+/// * **QueryMsg::SpotPrice { offer, ask }** Returns the current marginal price of `offer` in terms of `ask`.
+///
 /// * **QueryMsg::Config {}** Returns the configuration for the pair contract using a [`ConfigResponse`] object.
+///
+/// * **QueryMsg::AccruedProtocolFees {}** Returns the protocol fees that were not collected yet.
+///
+/// * **QueryMsg::CumulativeVolume {}** Returns the total amounts offered to and returned by the pair in swaps
+/// using a [`CumulativeVolumeResponse`] object.
+///
+/// * **QueryMsg::PoolState {}** Returns the reserves, LP token supply, fees, pair type and trading start
+/// of the pool using a [`PoolStateResponse`] object.
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Pair {} => to_binary(&CONFIG.load(deps.storage)?.pair_info),
         QueryMsg::Pool {} => to_binary(&query_pool(deps)?),
         QueryMsg::Share { amount } => to_binary(&query_share(deps, amount)?),
+        QueryMsg::SimulateWithdraw { amount } => to_binary(&query_simulate_withdraw(deps, amount)?),
+        QueryMsg::SharesToAssets { shares } => to_binary(&query_simulate_withdraw(deps, shares)?),
+        QueryMsg::SimulateProvide { assets, .. } => {
+            to_binary(&query_simulate_provide(deps, env, assets)?)
+        }
         QueryMsg::Simulation {
             offer_asset,
             ask_asset_info,
@@ -982,6 +1594,15 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             referral,
             referral_commission,
         )?),
+        QueryMsg::EstimateSwapFees {
+            offer_asset,
+            ask_asset_info,
+        } => to_binary(&query_estimate_swap_fees(
+            deps,
+            env,
+            offer_asset,
+            ask_asset_info,
+        )?),
         QueryMsg::ReverseSimulation {
             offer_asset_info,
             ask_asset,
@@ -996,11 +1617,54 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             referral_commission,
         )?),
         QueryMsg::CumulativePrices {} => to_binary(&query_cumulative_prices(deps, env)?),
+        QueryMsg::Twap {
+            start_cumulative,
+            start_time,
+        } => to_binary(&query_twap(deps, env, start_cumulative, start_time)?),
         QueryMsg::Config {} => to_binary(&query_config(deps, env)?),
+        QueryMsg::AccruedProtocolFees {} => {
+            to_binary(&CONFIG.load(deps.storage)?.accrued_protocol_fees)
+        }
+        QueryMsg::PoolState {} => to_binary(&query_pool_state(deps)?),
+        QueryMsg::CumulativeVolume {} => {
+            let config = CONFIG.load(deps.storage)?;
+            to_binary(&CumulativeVolumeResponse {
+                offered: config.offered_volume,
+                returned: config.returned_volume,
+            })
+        }
+        QueryMsg::SpotPrice { offer, ask } => to_binary(&query_spot_price(deps, env, offer, ask)?),
         QueryMsg::QueryComputeD {} => to_binary(&query_compute_d(deps, env)?),
+        QueryMsg::FeeApr { .. } => Err(StdError::generic_err("Query is not supported")),
     }
 }
 
+/// Manages the contract migration.
+/// New fields of the stored config have defaults, so it is read as is.
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // Rejects downgrades and migrations from other contracts
+    let previous_version =
+        ensure_from_older_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // start measuring the cw20 tokens received with a hook
+    let asset_infos = CONFIG.load(deps.storage)?.pair_info.asset_infos;
+    save_cw20_reserves(
+        deps.storage,
+        &deps.querier,
+        &env.contract.address,
+        &asset_infos,
+        &cw20_tokens(&asset_infos),
+        &Response::new(),
+    )?;
+
+    Ok(Response::new().add_attributes([
+        attr("action", "migrate"),
+        attr("previous_contract_version", previous_version.to_string()),
+        attr("new_contract_version", CONTRACT_VERSION),
+    ]))
+}
+
 /// Returns the amounts of assets in the pair contract as well as the amount of LP
 /// tokens currently minted in an object of type [`PoolResponse`].
 pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
@@ -1015,6 +1679,20 @@ pub fn query_pool(deps: Deps) -> StdResult<PoolResponse> {
     Ok(resp)
 }
 
+/// Returns the combined state of the pool in a [`PoolStateResponse`] object.
+pub fn query_pool_state(deps: Deps) -> StdResult<PoolStateResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let (assets, total_share) = pool_info(deps.querier, &config)?;
+
+    Ok(PoolStateResponse {
+        assets,
+        total_share,
+        fee_config: config.pair_info.fee_config,
+        pair_type: config.pair_info.pair_type,
+        trading_starts: config.trading_starts,
+    })
+}
+
 /// Returns the amount of assets that could be withdrawn from the pool using a specific amount of LP tokens.
 /// The result is returned in a vector that contains objects of type [`Asset`].
 ///
@@ -1027,6 +1705,54 @@ pub fn query_share(deps: Deps, amount: Uint128) -> StdResult<Vec<AssetValidated>
     Ok(refund_assets)
 }
 
+/// Returns the amount of assets that withdrawing a specific amount of LP tokens would return.
+/// This uses the same calculation as a balanced withdrawal of liquidity.
+///
+/// * **amount** is the amount of LP tokens to withdraw.
+pub fn query_simulate_withdraw(deps: Deps, amount: Uint128) -> StdResult<Vec<AssetValidated>> {
+    let config = CONFIG.load(deps.storage)?;
+    let (pools, total_share) = pool_info(deps.querier, &config)?;
+
+    simulate_withdraw(&pools, amount, total_share)
+}
+
+/// Returns the amount of LP tokens that providing the given assets would mint, as well as the fee
+/// charged in each asset for an imbalanced provide, in a [`SimulateProvideResponse`] object.
+///
+/// * **assets** are the assets to provide.
+pub fn query_simulate_provide(
+    deps: Deps,
+    env: Env,
+    assets: Vec<Asset>,
+) -> StdResult<SimulateProvideResponse> {
+    let assets = check_assets(deps.api, &assets)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    let pools: HashMap<_, _> = config
+        .query_pools(&deps.querier, &env.contract.address)?
+        .into_iter()
+        .map(|pool| (pool.info, pool.amount))
+        .collect();
+    let assets_collection = collect_deposits(&config, &pools, &assets)?;
+
+    let total_share = query_supply(&deps.querier, &config.pair_info.liquidity_token)?;
+    let (share, mut imbalance_fee) =
+        compute_provide_share(deps, &env, &config, &assets_collection, total_share)?;
+    // return the fees in the order of the pair's assets
+    imbalance_fee.sort_by_key(|fee| {
+        config
+            .pair_info
+            .asset_infos
+            .iter()
+            .position(|info| info == &fee.info)
+    });
+
+    Ok(SimulateProvideResponse {
+        share,
+        imbalance_fee,
+    })
+}
+
 /// Returns information about a swap simulation in a [`SimulationResponse`] object.
 ///
 /// * **offer_asset** is the asset to swap as well as an amount of the said asset.
@@ -1039,11 +1765,13 @@ pub fn query_simulation(
     referral_commission: Option<Decimal>,
 ) -> StdResult<SimulationResponse> {
     let mut offer_asset = offer_asset.validate(deps.api)?;
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {}.into());
+    }
     let ask_asset_info = ask_asset_info.map(|a| a.validate(deps.api)).transpose()?;
     let config = CONFIG.load(deps.storage)?;
-    let pools = config
-        .pair_info
-        .query_pools_decimal(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools =
+        config.query_pools_decimal(deps.storage, &deps.querier, &config.pair_info.contract_addr)?;
 
     let referral_amount = if referral {
         let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
@@ -1058,6 +1786,9 @@ pub fn query_simulation(
 
     let offer_precision = get_precision(deps.storage, &offer_pool.info)?;
 
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::PoolEmpty {}.into());
+    }
     if check_swap_parameters(
         pools
             .iter()
@@ -1075,6 +1806,7 @@ pub fn query_simulation(
             spread_amount: Uint128::zero(),
             commission_amount: Uint128::zero(),
             referral_amount: Uint128::zero(),
+            spread_exceeds_default: false,
         });
     }
 
@@ -1098,12 +1830,50 @@ pub fn query_simulation(
         .total_fee_rate()
         .checked_mul_uint128(return_amount)?;
     let return_amount = return_amount.saturating_sub(commission_amount);
+    // same check as in `swap`
+    if return_amount.is_zero() {
+        return Err(ContractError::SwapTooSmall {}.into());
+    }
 
     Ok(SimulationResponse {
         return_amount,
         spread_amount,
         commission_amount,
         referral_amount,
+        // same values as the max spread check in `swap`
+        spread_exceeds_default: spread_exceeds(
+            config.default_max_spread,
+            return_amount,
+            spread_amount + commission_amount,
+        ),
+    })
+}
+
+/// Returns the fee breakdown of a swap in an [`EstimateSwapFeesResponse`] object.
+///
+/// * **offer_asset** is the asset to swap as well as an amount of the said asset.
+/// * **ask_asset_info** is the asset to swap to.
+pub fn query_estimate_swap_fees(
+    deps: Deps,
+    env: Env,
+    offer_asset: Asset,
+    ask_asset_info: Option<AssetInfo>,
+) -> StdResult<EstimateSwapFeesResponse> {
+    let simulation = query_simulation(deps, env, offer_asset, ask_asset_info, false, None)?;
+    let config = CONFIG.load(deps.storage)?;
+
+    // same condition as in `swap`, the protocol fee is only taken if there are recipients
+    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
+    let protocol_fee = if factory_config.protocol_fee_recipients().is_empty() {
+        Uint128::zero()
+    } else {
+        simulation.commission_amount * config.pair_info.fee_config.protocol_fee_rate()
+    };
+    Ok(EstimateSwapFeesResponse {
+        lp_fee: simulation.commission_amount - protocol_fee,
+        protocol_fee,
+        spread: simulation.spread_amount,
+        return_amount: simulation.return_amount,
     })
 }
 
@@ -1123,12 +1893,14 @@ pub fn query_reverse_simulation(
     referral_commission: Option<Decimal>,
 ) -> StdResult<ReverseSimulationResponse> {
     let ask_asset = ask_asset.validate(deps.api)?;
+    if ask_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {}.into());
+    }
     let offer_asset_info = offer_asset_info.map(|a| a.validate(deps.api)).transpose()?;
 
     let config = CONFIG.load(deps.storage)?;
-    let pools = config
-        .pair_info
-        .query_pools_decimal(&deps.querier, &config.pair_info.contract_addr)?;
+    let pools =
+        config.query_pools_decimal(deps.storage, &deps.querier, &config.pair_info.contract_addr)?;
     let (offer_pool, ask_pool) =
         select_pools(offer_asset_info.as_ref(), Some(&ask_asset.info), &pools)
             .map_err(|err| StdError::generic_err(format!("{err}")))?;
@@ -1137,6 +1909,9 @@ pub fn query_reverse_simulation(
     let ask_precision = get_precision(deps.storage, &ask_asset.info)?;
 
     // Check the swap parameters are valid
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::PoolEmpty {}.into());
+    }
     if check_swap_parameters(
         pools
             .iter()
@@ -1157,17 +1932,11 @@ pub fn query_reverse_simulation(
         });
     }
 
-    // Get fee info from factory
-    let fee_info = query_fee_info(
-        &deps.querier,
-        &config.factory_addr,
-        config.pair_info.pair_type.clone(),
-    )?;
-    let before_commission = (Decimal256::one()
-        - Decimal256::new(fee_info.total_fee_rate.atomics().into()))
-    .inv()
-    .unwrap_or_else(Decimal256::one)
-    .checked_mul(Decimal256::with_precision(ask_asset.amount, ask_precision)?)?;
+    let total_fee_rate = config.pair_info.fee_config.total_fee_rate();
+    let before_commission = (Decimal256::one() - decimal2decimal256(total_fee_rate)?)
+        .inv()
+        .unwrap_or_else(Decimal256::one)
+        .checked_mul(Decimal256::with_precision(ask_asset.amount, ask_precision)?)?;
 
     let new_offer_pool_amount = calc_y(
         &ask_pool,
@@ -1202,8 +1971,7 @@ pub fn query_reverse_simulation(
         offer_amount: offer_asset.amount,
         spread_amount: offer_amount
             .saturating_sub(before_commission.to_uint128_with_precision(offer_precision)?),
-        commission_amount: fee_info
-            .total_fee_rate
+        commission_amount: total_fee_rate
             .checked_mul_uint128(before_commission.to_uint128_with_precision(ask_precision)?)?,
         referral_amount,
     })
@@ -1229,9 +1997,76 @@ pub fn query_cumulative_prices(deps: Deps, env: Env) -> StdResult<CumulativePric
         assets,
         total_share,
         cumulative_prices: config.cumulative_prices,
+        block_time_last: config.block_time_last,
     })
 }
 
+/// Returns the time-weighted average prices since the given snapshot using a [`TwapResponse`] object.
+pub fn query_twap(
+    deps: Deps,
+    env: Env,
+    start_cumulative: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    start_time: u64,
+) -> StdResult<TwapResponse> {
+    let current = query_cumulative_prices(deps, env)?;
+    calculate_twap(deps.api, current, start_cumulative, start_time)
+}
+
+/// Returns the marginal price of one unit of `offer` in terms of `ask` at the current pool balances,
+/// after deducting the swap fee.
+pub fn query_spot_price(
+    deps: Deps,
+    env: Env,
+    offer: AssetInfo,
+    ask: AssetInfo,
+) -> StdResult<Decimal> {
+    let offer = offer.validate(deps.api)?;
+    let ask = ask.validate(deps.api)?;
+    if offer.equal(&ask) {
+        return Err(StdError::generic_err(
+            "The offer asset and ask asset cannot be the same.",
+        ));
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    let pools =
+        config.query_pools_decimal(deps.storage, &deps.querier, &config.pair_info.contract_addr)?;
+    let find_pool = |info: &AssetInfoValidated| {
+        pools
+            .iter()
+            .find(|pool| pool.info.equal(info))
+            .ok_or_else(|| {
+                StdError::generic_err(format!("Asset {info} does not belong in the pair"))
+            })
+    };
+    let offer_pool = find_pool(&offer)?;
+    let ask_pool = find_pool(&ask)?;
+    if pools.iter().any(|pool| pool.amount.is_zero()) {
+        return Err(ContractError::PoolEmpty {}.into());
+    }
+
+    let price = calc_spot_price(
+        compute_current_amp(&config, &env)?,
+        offer_pool.amount,
+        ask_pool.amount,
+        &pools.iter().map(|pool| pool.amount).collect_vec(),
+        config.greatest_precision,
+    )?;
+
+    // the pools are normalized, so the price has to be adjusted to the assets' precisions
+    let offer_precision = get_precision(deps.storage, &offer)?;
+    let ask_precision = get_precision(deps.storage, &ask)?;
+    let price = price
+        * Decimal256::from_ratio(
+            10u128.pow(ask_precision.into()),
+            10u128.pow(offer_precision.into()),
+        )
+        * (Decimal256::one() - decimal2decimal256(config.pair_info.fee_config.total_fee_rate())?);
+
+    Decimal::from_atomics(Uint128::try_from(price.atomics())?, price.decimal_places())
+        .map_err(|err| StdError::generic_err(err.to_string()))
+}
+
 /// Returns the pair contract configuration in a [`ConfigResponse`] object.
 pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
     let config = CONFIG.load(deps.storage)?;
@@ -1241,6 +2076,7 @@ pub fn query_config(deps: Deps, env: Env) -> StdResult<ConfigResponse> {
             amp: Decimal::from_ratio(compute_current_amp(&config, &env)?, AMP_PRECISION),
         })?),
         owner: config.owner,
+        default_max_spread: config.default_max_spread,
     })
 }
 
@@ -1249,9 +2085,7 @@ pub fn pool_info(
     querier: QuerierWrapper,
     config: &Config,
 ) -> StdResult<(Vec<AssetValidated>, Uint128)> {
-    let pools = config
-        .pair_info
-        .query_pools(&querier, &config.pair_info.contract_addr)?;
+    let pools = config.query_pools(&querier, &config.pair_info.contract_addr)?;
     let total_share = query_supply(&querier, &config.pair_info.liquidity_token)?;
 
     Ok((pools, total_share))
@@ -1267,17 +2101,7 @@ pub fn update_config(
     params: Binary,
 ) -> Result<Response, ContractError> {
     let config = CONFIG.load(deps.storage)?;
-    let factory_config = query_factory_config(&deps.querier, &config.factory_addr)?;
-
-    if info.sender
-        != if let Some(ref owner) = config.owner {
-            owner.to_owned()
-        } else {
-            factory_config.owner
-        }
-    {
-        return Err(ContractError::Unauthorized {});
-    }
+    assert_owner(deps.as_ref(), &info, &config)?;
 
     match from_binary::<StablePoolUpdateParams>(&params)? {
         StablePoolUpdateParams::StartChangingAmp {
@@ -1290,6 +2114,20 @@ pub fn update_config(
     Ok(Response::default())
 }
 
+/// Checks that the sender is the pair owner or, if no owner is set, the factory owner.
+fn assert_owner(deps: Deps, info: &MessageInfo, config: &Config) -> Result<(), ContractError> {
+    let owner = match config.owner {
+        Some(ref owner) => owner.to_owned(),
+        None => query_factory_config(&deps.querier, &config.factory_addr)?.owner,
+    };
+
+    if info.sender != owner {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    Ok(())
+}
+
 /// Start changing the AMP value.
 ///
 /// * **next_amp** new value for AMP.
@@ -1360,8 +2198,7 @@ fn query_compute_d(deps: Deps, env: Env) -> StdResult<Uint128> {
 
     let amp = compute_current_amp(&config, &env)?;
     let pools = config
-        .pair_info
-        .query_pools_decimal(&deps.querier, env.contract.address)?
+        .query_pools_decimal(deps.storage, &deps.querier, env.contract.address)?
         .into_iter()
         .map(|pool| pool.amount)
         .collect::<Vec<_>>();
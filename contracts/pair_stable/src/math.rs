@@ -1,11 +1,11 @@
 use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256, Uint64};
 use itertools::Itertools;
 use sg_swap::asset::{AssetInfoValidated, Decimal256Ext, DecimalAsset};
+pub use sg_swap::pair::MAX_AMP;
 
 /// The maximum number of calculation steps for Newton's method.
 const ITERATIONS: u8 = 32;
 
-pub const MAX_AMP: u64 = 1_000_000;
 pub const MAX_AMP_CHANGE: u64 = 10;
 pub const MIN_AMP_CHANGING_TIME: u64 = 86400;
 pub const AMP_PRECISION: u64 = 100;
@@ -60,6 +60,40 @@ pub(crate) fn compute_d(
     }
 }
 
+/// Computes the marginal price of the `offer` pool in terms of the `ask` pool at the given balances.
+///
+/// This is the partial derivative of the stableswap invariant, `-dy/dx`, which means that
+///
+/// price = (Ann * x_offer + D_P) * x_ask / ((Ann * x_ask + D_P) * x_offer)
+///
+/// where `D_P = D**(n+1) / (n**n * prod(x_i))`
+pub(crate) fn calc_spot_price(
+    amp: Uint64,
+    offer_pool: Decimal256,
+    ask_pool: Decimal256,
+    pools: &[Decimal256],
+    greatest_precision: u8,
+) -> StdResult<Decimal256> {
+    let d = compute_d(amp, pools, greatest_precision)?;
+    if d.is_zero() {
+        return Err(StdError::generic_err("The pool is empty"));
+    }
+
+    let n_coins = pools.len() as u8;
+    let ann = Decimal256::from_ratio(amp.checked_mul(n_coins.into())?.u64(), AMP_PRECISION);
+    let n_coins = Decimal256::from_integer(n_coins);
+    let d_p = pools
+        .iter()
+        .try_fold::<_, _, StdResult<_>>(d, |acc, pool| {
+            let denominator = pool.checked_mul(n_coins)?;
+            acc.checked_multiply_ratio(d, denominator)
+        })?;
+
+    let numerator = (ann * offer_pool + d_p).checked_mul(ask_pool)?;
+    let denominator = (ann * ask_pool + d_p).checked_mul(offer_pool)?;
+    Ok(numerator / denominator)
+}
+
 /// Computes the new balance of a `to` pool if one makes `from` pool = `new_amount`.
 ///
 /// Done by solving quadratic equation iteratively.
@@ -85,26 +119,47 @@ pub(crate) fn calc_y(
     if from_asset.amount.eq(&new_amount) {
         return Err(StdError::generic_err("The swap amount cannot be zero."));
     }
+    let pool_values = pools.iter().map(|asset| asset.amount).collect_vec();
+    let d = compute_d(amp, &pool_values, target_precision)?;
+    let new_pools = pools
+        .iter()
+        .map(|pool| DecimalAsset {
+            info: pool.info.clone(),
+            amount: if pool.info.eq(&from_asset.info) {
+                new_amount
+            } else {
+                pool.amount
+            },
+        })
+        .collect_vec();
+    calc_y_d(to, &new_pools, d, amp, target_precision)
+}
+
+/// Computes the balance of the `to` pool that keeps the invariant at `d`,
+/// given the balances of all the other `pools`.
+///
+/// Solves the same quadratic equation as [`calc_y`], but for a given invariant.
+pub(crate) fn calc_y_d(
+    to: &AssetInfoValidated,
+    pools: &[DecimalAsset],
+    d: Decimal256,
+    amp: Uint64,
+    target_precision: u8,
+) -> StdResult<Uint128> {
     let n_coins = Uint64::from(pools.len() as u8);
     let ann = Uint256::from(amp.checked_mul(n_coins)?.u64() / AMP_PRECISION);
     let mut sum = Decimal256::zero();
-    let pool_values = pools.iter().map(|asset| asset.amount).collect_vec();
-    let d = compute_d(amp, &pool_values, target_precision)?
-        .to_uint256_with_precision(target_precision)?;
+    let d = d.to_uint256_with_precision(target_precision)?;
     let mut c = d;
     for pool in pools {
-        let pool_amount: Decimal256 = if pool.info.eq(&from_asset.info) {
-            new_amount
-        } else if !pool.info.eq(to) {
-            pool.amount
-        } else {
+        if pool.info.eq(to) {
             continue;
-        };
-        sum += pool_amount;
+        }
+        sum += pool.amount;
         c = c
             .checked_multiply_ratio(
                 d,
-                pool_amount.to_uint256_with_precision(target_precision)? * Uint256::from(n_coins),
+                pool.amount.to_uint256_with_precision(target_precision)? * Uint256::from(n_coins),
             )
             .map_err(|_| StdError::generic_err("CheckedMultiplyRatioError"))?;
     }
@@ -1,9 +1,11 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::{Addr, DepsMut, StdResult, Storage, Uint128};
+use cosmwasm_std::{
+    Addr, Decimal, Decimal256, DepsMut, QuerierWrapper, StdError, StdResult, Storage, Uint128,
+};
 use cw_storage_plus::{Item, Map};
-use sg_swap::asset::AssetInfoValidated;
+use sg_swap::asset::{AssetInfoValidated, AssetValidated, DecimalAsset};
 use sg_swap::common::OwnershipProposal;
-use sg_swap::pair::PairInfo;
+use sg_swap::pair::{default_slippage, subtract_accrued_fees, ContractError, PairInfo};
 
 /// This structure stores the main stableswap pair parameters.
 #[cw_serde]
@@ -30,6 +32,62 @@ pub struct Config {
     pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint128)>,
     /// The block time until which trading is disabled
     pub trading_starts: u64,
+    /// Whether swaps and liquidity operations are currently disabled
+    #[serde(default)]
+    pub paused: bool,
+    /// The max spread applied to swaps that do not specify one
+    #[serde(default = "default_slippage")]
+    pub default_max_spread: Decimal,
+    /// The protocol fees held by the pair until they are collected
+    #[serde(default)]
+    pub accrued_protocol_fees: Vec<AssetValidated>,
+    /// The total amount of each asset offered to the pair in swaps
+    #[serde(default)]
+    pub offered_volume: Vec<AssetValidated>,
+    /// The total amount of each asset returned by the pair in swaps
+    #[serde(default)]
+    pub returned_volume: Vec<AssetValidated>,
+}
+
+impl Config {
+    /// Returns the balance for each asset in the pool, excluding the accrued protocol fees.
+    ///
+    /// * **contract_addr** is pair's pool address.
+    pub fn query_pools(
+        &self,
+        querier: &QuerierWrapper,
+        contract_addr: impl Into<String>,
+    ) -> StdResult<Vec<AssetValidated>> {
+        let mut pools = self.pair_info.query_pools(querier, contract_addr)?;
+        subtract_accrued_fees(&mut pools, &self.accrued_protocol_fees)?;
+        Ok(pools)
+    }
+
+    /// Returns the balance for each asset in the pool in decimal, excluding the accrued protocol fees.
+    /// The balances are scaled by the precisions stored at instantiation, so assets with different
+    /// decimals are comparable.
+    ///
+    /// * **contract_addr** is pair's pool address.
+    pub fn query_pools_decimal(
+        &self,
+        storage: &dyn Storage,
+        querier: &QuerierWrapper,
+        contract_addr: impl Into<String>,
+    ) -> StdResult<Vec<DecimalAsset>> {
+        self.query_pools(querier, contract_addr)?
+            .into_iter()
+            .map(|pool| {
+                Ok(DecimalAsset {
+                    amount: Decimal256::from_atomics(
+                        pool.amount,
+                        get_precision(storage, &pool.info)?.into(),
+                    )
+                    .map_err(|_| StdError::generic_err("Decimal256RangeExceeded"))?,
+                    info: pool.info,
+                })
+            })
+            .collect()
+    }
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
@@ -37,15 +95,26 @@ pub const CONFIG: Item<Config> = Item::new("config");
 /// Stores map of AssetInfo (as String) -> precision
 const PRECISIONS: Map<String, u8> = Map::new("precisions");
 
+/// The greatest supported precision, which is the number of decimal places of [`Decimal256`]
+const MAX_PRECISION: u8 = 18;
+
 /// Stores the latest contract ownership transfer proposal
 pub const OWNERSHIP_PROPOSAL: Item<OwnershipProposal> = Item::new("ownership_proposal");
 
 /// Store all token precisions and return the greatest one.
-pub(crate) fn store_precisions(deps: DepsMut, asset_infos: &[AssetInfoValidated]) -> StdResult<u8> {
+/// Fails if the decimals of a token cannot be queried or are too many to do stableswap math with.
+pub(crate) fn store_precisions(
+    deps: DepsMut,
+    asset_infos: &[AssetInfoValidated],
+) -> Result<u8, ContractError> {
     let mut max = 0u8;
 
     for asset_info in asset_infos {
-        let precision = asset_info.decimals(&deps.querier)?;
+        let precision = asset_info
+            .decimals(&deps.querier)
+            .ok()
+            .filter(|&precision| precision <= MAX_PRECISION)
+            .ok_or_else(|| ContractError::UnsupportedDecimals(asset_info.to_string()))?;
         max = max.max(precision);
         PRECISIONS.save(deps.storage, asset_info.to_string(), &precision)?;
     }
@@ -2,19 +2,20 @@ use crate::contract::{execute, instantiate};
 use crate::state::CONFIG;
 use sg_swap::fee_config::FeeConfig;
 // TODO: Copied here just as a temporary measure
-use crate::mock_querier::mock_dependencies;
+use crate::mock_querier::{mock_dependencies, WasmMockQuerier};
 
-use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::testing::{mock_env, mock_info, MockApi, MockStorage, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
-    attr, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env, ReplyOn,
-    Response, StdError, SubMsg, Timestamp, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, BankMsg, BlockInfo, Coin, CosmosMsg, Decimal, DepsMut, Env,
+    MessageInfo, OwnedDeps, ReplyOn, Response, StdError, SubMsg, Timestamp, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as TokenInstantiateMsg;
 use cw_utils::MsgInstantiateContractResponse;
 use sg_swap::asset::{Asset, AssetInfo, AssetInfoValidated};
 use sg_swap::pair::{
-    ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg, StablePoolParams, StakeConfig,
+    ContractError, Cw20HookMsg, ExecuteMsg, InstantiateMsg, LpTokenType, StablePoolParams,
+    StakeConfig,
 };
 
 fn mock_env_with_block_time(time: u64) -> Env {
@@ -44,6 +45,59 @@ fn store_liquidity_token(deps: DepsMut, contract_addr: String) {
     CONFIG.save(deps.storage, &config).unwrap();
 }
 
+/// Executes `msg` and, if the pair continues with a message to itself, executes that one as well
+/// after crediting the pulled cw20 tokens to the pair.
+/// The token balances are restored afterwards, so the tests keep setting up the pools themselves.
+/// Returns the messages of both steps, without the one the pair sent to itself.
+fn execute_with_callback(
+    deps: &mut OwnedDeps<MockStorage, MockApi, WasmMockQuerier>,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    let mut res = execute(deps.as_mut(), env.clone(), info, msg)?;
+    let callback = match res.messages.last().map(|sub_msg| &sub_msg.msg) {
+        Some(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        })) if contract_addr == MOCK_CONTRACT_ADDR => from_binary(msg).unwrap(),
+        _ => return Ok(res),
+    };
+    res.messages.pop();
+
+    let mut prev_balances = vec![];
+    for sub_msg in &res.messages {
+        if let CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr, msg, ..
+        }) = &sub_msg.msg
+        {
+            if let Ok(Cw20ExecuteMsg::TransferFrom { amount, .. }) = from_binary(msg) {
+                let balance = deps
+                    .querier
+                    .token_balance(contract_addr, MOCK_CONTRACT_ADDR);
+                deps.querier
+                    .set_token_balance(contract_addr, MOCK_CONTRACT_ADDR, balance + amount);
+                prev_balances.push((contract_addr.clone(), balance));
+            }
+        }
+    }
+
+    let callback_res = execute(
+        deps.as_mut(),
+        env,
+        mock_info(MOCK_CONTRACT_ADDR, &[]),
+        callback,
+    );
+    for (token, balance) in prev_balances {
+        deps.querier
+            .set_token_balance(&token, MOCK_CONTRACT_ADDR, balance);
+    }
+
+    let mut callback_res = callback_res?;
+    res.messages.append(&mut callback_res.messages);
+    callback_res.messages = res.messages;
+    Ok(callback_res)
+}
+
 fn default_stake_config() -> StakeConfig {
     StakeConfig {
         staking_code_id: 11,
@@ -79,6 +133,10 @@ fn proper_initialization() {
         ),
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -133,6 +191,44 @@ fn proper_initialization() {
     );
 }
 
+#[test]
+fn instantiate_requires_known_decimals() {
+    // "asset0000" does not answer token info queries, so its decimals are unknown
+    let mut deps = mock_dependencies(&[]);
+
+    let msg = InstantiateMsg {
+        factory_addr: String::from("factory"),
+        asset_infos: vec![
+            AssetInfo::Native("uusd".to_string()),
+            AssetInfo::Token("asset0000".to_string()),
+        ],
+        token_code_id: 10u64,
+        init_params: Some(
+            to_binary(&StablePoolParams {
+                amp: 100,
+                owner: None,
+            })
+            .unwrap(),
+        ),
+        staking_config: default_stake_config(),
+        trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        fee_config: FeeConfig {
+            total_fee_bps: 0,
+            protocol_fee_bps: 0,
+        },
+    };
+
+    let err = instantiate(deps.as_mut(), mock_env(), mock_info("addr0000", &[]), msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::UnsupportedDecimals("asset0000".to_string())
+    );
+}
+
 #[test]
 fn provide_liquidity() {
     let mut deps = mock_dependencies(&[Coin {
@@ -167,6 +263,10 @@ fn provide_liquidity() {
         ),
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -195,6 +295,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env();
@@ -205,7 +306,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
     let mint_min_liquidity_msg = res.messages.get(1).expect("no message");
     let mint_receiver_msg = res.messages.get(2).expect("no message");
@@ -307,6 +408,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -318,7 +420,7 @@ fn provide_liquidity() {
         }],
     );
 
-    let res: Response = execute(deps.as_mut(), env, info, msg).unwrap();
+    let res: Response = execute_with_callback(&mut deps, env, info, msg).unwrap();
     let transfer_from_msg = res.messages.get(0).expect("no message");
     let mint_msg = res.messages.get(1).expect("no message");
     assert_eq!(
@@ -373,6 +475,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env();
@@ -383,7 +486,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    let res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap_err();
     match res {
         ContractError::Std(StdError::GenericErr { msg, .. }) => assert_eq!(
             msg,
@@ -450,6 +553,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -460,7 +564,7 @@ fn provide_liquidity() {
             amount: Uint128::from(100_000000000000000000u128),
         }],
     );
-    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let _res = execute_with_callback(&mut deps, env.clone(), info, msg).unwrap();
 
     // Initialize token balances with a ratio of 1:1
     deps.querier.with_balance(&[(
@@ -485,6 +589,7 @@ fn provide_liquidity() {
         ],
         slippage_tolerance: Some(Decimal::percent(1)),
         receiver: None,
+        min_lp_amount: None,
     };
 
     let env = mock_env_with_block_time(env.block.time.seconds() + 1000);
@@ -495,7 +600,7 @@ fn provide_liquidity() {
             amount: Uint128::from(99_000000000000000000u128),
         }],
     );
-    let _res = execute(deps.as_mut(), env, info, msg).unwrap();
+    let _res = execute_with_callback(&mut deps, env, info, msg).unwrap();
 }
 
 #[test]
@@ -532,6 +637,10 @@ fn withdraw_liquidity() {
         ),
         staking_config: default_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -549,7 +658,11 @@ fn withdraw_liquidity() {
     // Withdraw liquidity
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: String::from("addr0000"),
-        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity { assets: vec![] }).unwrap(),
+        msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+            assets: vec![],
+            min_assets: None,
+        })
+        .unwrap(),
         amount: Uint128::new(100u128),
     });
 
@@ -766,6 +879,7 @@ mod disabled {
                 attr("return_amount", expected_return_amount.to_string()),
                 attr("spread_amount", expected_spread_amount.to_string()),
                 attr("commission_amount", expected_commission_amount.to_string()),
+                attr("referral_amount", "0"),
                 attr(
                     "protocol_fee_amount",
                     expected_protocol_fee_amount.to_string()
@@ -934,6 +1048,7 @@ mod disabled {
                 attr("return_amount", expected_return_amount.to_string()),
                 attr("spread_amount", expected_spread_amount.to_string()),
                 attr("commission_amount", expected_commission_amount.to_string()),
+                attr("referral_amount", "0"),
                 attr(
                     "protocol_fee_amount",
                     expected_protocol_fee_amount.to_string()
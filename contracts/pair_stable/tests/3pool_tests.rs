@@ -1,9 +1,10 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{assert_approx_eq, Addr, Decimal, Uint128};
+use cw_multi_test::Executor;
 use itertools::Itertools;
 
 use helper::AppExtension;
-use sg_swap::asset::AssetInfoExt;
-use sg_swap::pair::ContractError;
+use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
+use sg_swap::pair::{ContractError, ExecuteMsg};
 
 use crate::helper::{Helper, TestCoin};
 
@@ -131,6 +132,261 @@ fn provide_and_withdraw_no_fee() {
     assert_eq!(0, helper.coin_balance(&test_coins[2], &user3));
 }
 
+#[test]
+fn simulate_withdraw_matches_withdraw() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20("USDD"),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    // nothing to withdraw from an empty pool
+    let err = helper.simulate_withdraw(1_000).unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("The pool does not have any liquidity"));
+
+    let user1 = Addr::unchecked("user1");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[2]].with_balance(100_000_000u128),
+    ];
+    helper.give_me_money(&assets, &user1);
+    helper.provide_liquidity(&user1, &assets).unwrap();
+
+    // an imbalanced provide changes the composition of the pool
+    let user2 = Addr::unchecked("user2");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(50_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(10_000_000u128),
+    ];
+    helper.give_me_money(&assets, &user2);
+    helper.provide_liquidity(&user2, &assets).unwrap();
+
+    // 1000 LP tokens are locked in the pair forever
+    let total_share = helper.token_balance(&helper.lp_token, &user1)
+        + helper.token_balance(&helper.lp_token, &user2)
+        + 1_000;
+    let err = helper.simulate_withdraw(total_share + 1).unwrap_err();
+    assert!(err.to_string().contains(&format!(
+        "Cannot withdraw more than the total supply of {total_share} LP tokens"
+    )));
+
+    let lp_amount = helper.token_balance(&helper.lp_token, &user1);
+    let simulated = helper.simulate_withdraw(lp_amount).unwrap();
+    assert_eq!(simulated.len(), 3);
+
+    helper
+        .withdraw_liquidity(&user1, lp_amount, vec![])
+        .unwrap();
+    for (coin, asset) in test_coins.iter().zip(simulated) {
+        assert_eq!(helper.assets[coin], asset.info);
+        assert_eq!(helper.coin_balance(coin, &user1), asset.amount.u128());
+    }
+}
+
+#[test]
+fn withdraw_with_min_assets() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20("USDD"),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    let user1 = Addr::unchecked("user1");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[2]].with_balance(100_000_000u128),
+    ];
+    helper.give_me_money(&assets, &user1);
+    helper.provide_liquidity(&user1, &assets).unwrap();
+
+    // an imbalanced provide shifts the composition of the pool
+    let user2 = Addr::unchecked("user2");
+    let assets = vec![helper.assets[&test_coins[0]].with_balance(50_000_000u128)];
+    helper.give_me_money(&assets, &user2);
+    helper.provide_liquidity(&user2, &assets).unwrap();
+
+    let lp_amount = helper.token_balance(&helper.lp_token, &user1);
+    let simulated = helper.simulate_withdraw(lp_amount).unwrap();
+
+    // asking for more of the first asset than the withdrawal returns fails
+    let too_high = simulated[0]
+        .info
+        .with_balance(simulated[0].amount + Uint128::one());
+    let err = helper
+        .withdraw_liquidity_with_min(&user1, lp_amount, vec![too_high.clone().into()])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::WithdrawSlippage {
+            returned: simulated[0].to_string(),
+            min: too_high.to_string(),
+        },
+        err.downcast().unwrap()
+    );
+
+    // asking for exactly the returned amounts succeeds
+    helper
+        .withdraw_liquidity_with_min(
+            &user1,
+            lp_amount,
+            simulated.iter().cloned().map(Into::into).collect(),
+        )
+        .unwrap();
+    for (coin, asset) in test_coins.iter().zip(simulated) {
+        assert_eq!(helper.coin_balance(coin, &user1), asset.amount.u128());
+    }
+}
+
+#[test]
+fn withdraw_to_single_asset() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20("USDD"),
+    ];
+
+    let user1 = Addr::unchecked("user1");
+    let setup = |swap_fee| {
+        let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, Some(swap_fee)).unwrap();
+        let assets = vec![
+            helper.assets[&test_coins[0]].with_balance(100_000_000u128),
+            helper.assets[&test_coins[1]].with_balance(100_000_000u128),
+            helper.assets[&test_coins[2]].with_balance(100_000_000u128),
+        ];
+        helper.give_me_money(&assets, &user1);
+        helper.provide_liquidity(&user1, &assets).unwrap();
+        helper
+    };
+
+    // without fees, 10% of the LP tokens return up to 10% of the pool value in the first asset
+    let mut helper = setup(0);
+    let uluna = helper.assets[&test_coins[0]].clone();
+    helper
+        .withdraw_to_asset(&user1, 30_000_000, uluna.clone().into(), None)
+        .unwrap();
+    let without_fee = helper.coin_balance(&test_coins[0], &user1);
+    assert!(without_fee > 29_900_000 && without_fee <= 30_000_000);
+    assert_eq!(0, helper.coin_balance(&test_coins[1], &user1));
+    assert_eq!(0, helper.coin_balance(&test_coins[2], &user1));
+    assert_eq!(
+        299_999_000 - 30_000_000,
+        helper.token_balance(&helper.lp_token, &user1)
+    );
+
+    // with fees, the imbalance fee is charged
+    let mut helper = setup(100);
+    let err = helper
+        .withdraw_to_asset(&user1, 30_000_000, uluna.clone().into(), Some(without_fee))
+        .unwrap_err();
+    assert!(matches!(
+        err.downcast::<ContractError>().unwrap(),
+        ContractError::WithdrawSlippage { .. }
+    ));
+    let err = helper
+        .withdraw_to_asset(
+            &user1,
+            30_000_000,
+            AssetInfo::Native("random".to_string()),
+            None,
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::InvalidAsset("random".to_string()),
+        err.downcast().unwrap()
+    );
+
+    helper
+        .withdraw_to_asset(&user1, 30_000_000, uluna.into(), None)
+        .unwrap();
+    let with_fee = helper.coin_balance(&test_coins[0], &user1);
+    // 1% * 3 / (4 * 2) = 0.375% of the 40_000_000 imbalance
+    assert_approx_eq!(
+        Uint128::new(without_fee - with_fee),
+        Uint128::new(150_000),
+        "0.1"
+    );
+}
+
+#[test]
+fn simulate_provide_matches_provide() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20("USDD"),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    // the first deposit mints D minus the locked minimum liquidity, without any fee
+    let user1 = Addr::unchecked("user1");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000u128),
+        helper.assets[&test_coins[2]].with_balance(100_000_000u128),
+    ];
+    let simulated = helper.simulate_provide(&assets).unwrap();
+    assert_eq!(simulated.share.u128(), 299_999_000);
+    assert!(simulated
+        .imbalance_fee
+        .iter()
+        .all(|fee| fee.amount.is_zero()));
+    helper.give_me_money(&assets, &user1);
+    helper.provide_liquidity(&user1, &assets).unwrap();
+    assert_eq!(
+        helper.token_balance(&helper.lp_token, &user1),
+        simulated.share.u128()
+    );
+
+    // a balanced deposit does not pay any imbalance fee
+    let user2 = Addr::unchecked("user2");
+    let simulated = helper.simulate_provide(&assets).unwrap();
+    assert_eq!(simulated.imbalance_fee.len(), 3);
+    assert!(simulated
+        .imbalance_fee
+        .iter()
+        .all(|fee| fee.amount.is_zero()));
+    helper.give_me_money(&assets, &user2);
+    helper.provide_liquidity(&user2, &assets).unwrap();
+    assert_eq!(
+        helper.token_balance(&helper.lp_token, &user2),
+        simulated.share.u128()
+    );
+
+    // an imbalanced deposit pays a fee on the excess
+    let user3 = Addr::unchecked("user3");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(200_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(100_000_000u128),
+    ];
+    let simulated = helper.simulate_provide(&assets).unwrap();
+    assert_eq!(simulated.imbalance_fee.len(), 3);
+    for (coin, fee) in test_coins.iter().zip(&simulated.imbalance_fee) {
+        assert_eq!(helper.assets[coin], fee.info);
+    }
+    assert!(!simulated.imbalance_fee[0].amount.is_zero());
+    helper.give_me_money(&assets, &user3);
+    helper.provide_liquidity(&user3, &assets).unwrap();
+    assert_eq!(
+        helper.token_balance(&helper.lp_token, &user3),
+        simulated.share.u128()
+    );
+}
+
 #[test]
 fn provide_with_different_precision() {
     let owner = Addr::unchecked("owner");
@@ -237,6 +493,128 @@ fn swap_different_precisions() {
     assert_eq!(99_949011, helper.coin_balance(&test_coins[2], &user));
 }
 
+#[test]
+fn swap_between_6_and_18_decimals() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![TestCoin::native("uluna"), TestCoin::cw20precise("WETH", 18)];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    // 1_000_000 of each, scaled by their decimals
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(1_000_000_000000u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000000000000000000u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user = Addr::unchecked("user");
+    // 100 uluna tokens are worth about 100 WETH tokens in a balanced pool
+    let offer_asset = helper.assets[&test_coins[0]].with_balance(100_000000u128);
+    helper.give_me_money(&[offer_asset.clone()], &user);
+    helper
+        .swap(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[1]].clone()),
+        )
+        .unwrap();
+    let received = helper.coin_balance(&test_coins[1], &user);
+    // the swap is close to parity
+    assert_approx_eq!(
+        Uint128::new(received),
+        Uint128::new(100_000000000000000000),
+        "0.01"
+    );
+
+    // and back again
+    let offer_asset = helper.assets[&test_coins[1]].with_balance(received);
+    helper
+        .swap(
+            &user,
+            &offer_asset,
+            Some(helper.assets[&test_coins[0]].clone()),
+        )
+        .unwrap();
+    assert_approx_eq!(
+        Uint128::new(helper.coin_balance(&test_coins[0], &user)),
+        Uint128::new(100_000000),
+        "0.01"
+    );
+}
+
+#[test]
+fn swap_zero_and_dust_amounts() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20precise("FOO", 4),
+        TestCoin::cw20precise("ADN", 6),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000_000_u128),
+        helper.assets[&test_coins[1]].with_balance(1_000_000_000_u128),
+        helper.assets[&test_coins[2]].with_balance(100_000_000_000_u128),
+    ];
+    helper.provide_liquidity(&owner, &assets).unwrap();
+
+    let user = Addr::unchecked("user");
+    let pair = helper.pair_addr.clone();
+    let err = helper
+        .app
+        .execute_contract(
+            user.clone(),
+            pair,
+            &ExecuteMsg::Swap {
+                offer_asset: helper.assets[&test_coins[0]].with_balance(0u128).into(),
+                ask_asset_info: Some(helper.assets[&test_coins[2]].clone().into()),
+                belief_price: None,
+                max_spread: None,
+                to: None,
+                referral_address: None,
+                referral_commission: None,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidZeroAmount {}, err.downcast().unwrap());
+
+    let err = helper
+        .simulate_swap(
+            helper.assets[&test_coins[0]].with_balance(0u128),
+            Some(helper.assets[&test_coins[2]].clone()),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Event of zero transfer"));
+    let err = helper
+        .simulate_reverse_swap(
+            helper.assets[&test_coins[2]].with_balance(0u128),
+            Some(helper.assets[&test_coins[0]].clone()),
+        )
+        .unwrap_err();
+    assert!(err.to_string().contains("Event of zero transfer"));
+
+    // 0.000001 ADN is less than the smallest unit of FOO, so the balanced pool returns nothing
+    let dust = helper.assets[&test_coins[2]].with_balance(1u128);
+    let err = helper
+        .simulate_swap(dust.clone(), Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("The offered amount is too small to return anything"));
+
+    helper.give_me_money(&[dust.clone()], &user);
+    let err = helper
+        .swap(&user, &dust, Some(helper.assets[&test_coins[1]].clone()))
+        .unwrap_err();
+    assert_eq!(ContractError::SwapTooSmall {}, err.downcast().unwrap());
+    assert_eq!(1, helper.coin_balance(&test_coins[2], &user));
+}
+
 #[test]
 fn check_swaps() {
     let owner = Addr::unchecked("owner");
@@ -509,3 +887,110 @@ fn check_5pool_prices() {
     helper.app.next_block(86400);
     check_prices(&helper);
 }
+
+#[test]
+fn spot_price_matches_small_swap() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20precise("USDD", 8),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, None).unwrap();
+
+    // imbalanced pool, so the prices are not 1:1
+    let user = Addr::unchecked("user");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(50_000_000_000u128),
+        helper.assets[&test_coins[2]].with_balance(8_000_000_000_000u128),
+    ];
+    helper.give_me_money(&assets, &user);
+    helper.provide_liquidity(&user, &assets).unwrap();
+
+    // one whole token of each asset
+    let offer_amounts = [1_000_000u128, 1_000_000, 100_000_000];
+    for (offer, ask) in [(0, 1), (1, 0), (1, 2), (2, 0)] {
+        let offer_info = &helper.assets[&test_coins[offer]];
+        let ask_info = &helper.assets[&test_coins[ask]];
+
+        let spot_price = helper.query_spot_price(offer_info, ask_info).unwrap();
+        let sim_resp = helper
+            .simulate_swap(
+                offer_info.with_balance(offer_amounts[offer]),
+                Some(ask_info.clone()),
+            )
+            .unwrap();
+        assert_approx_eq!(
+            sim_resp.return_amount.u128(),
+            (Uint128::new(offer_amounts[offer]) * spot_price).u128(),
+            "0.0001"
+        );
+    }
+
+    // the stableswap price is way closer to 1:1 than the ratio of the pools
+    let spot_price = helper
+        .query_spot_price(
+            &helper.assets[&test_coins[1]],
+            &helper.assets[&test_coins[0]],
+        )
+        .unwrap();
+    assert!(spot_price > Decimal::one() && spot_price < Decimal::percent(110));
+
+    // assets have to be part of the pair
+    helper
+        .query_spot_price(
+            &AssetInfoValidated::Native("uatom".to_string()),
+            &helper.assets[&test_coins[0]],
+        )
+        .unwrap_err();
+}
+
+#[test]
+fn reverse_simulation_round_trips_forward_simulation() {
+    let owner = Addr::unchecked("owner");
+
+    let test_coins = vec![
+        TestCoin::native("uluna"),
+        TestCoin::cw20("USDC"),
+        TestCoin::cw20("USDD"),
+    ];
+
+    let mut helper = Helper::new(&owner, test_coins.clone(), 100u64, Some(30)).unwrap();
+
+    let user = Addr::unchecked("user");
+    let assets = vec![
+        helper.assets[&test_coins[0]].with_balance(100_000_000_000u128),
+        helper.assets[&test_coins[1]].with_balance(50_000_000_000u128),
+        helper.assets[&test_coins[2]].with_balance(80_000_000_000u128),
+    ];
+    helper.give_me_money(&assets, &user);
+    helper.provide_liquidity(&user, &assets).unwrap();
+
+    for (offer, ask) in [(0, 1), (1, 0), (1, 2), (2, 0)] {
+        let offer_info = &helper.assets[&test_coins[offer]];
+        let ask_info = &helper.assets[&test_coins[ask]];
+
+        for ask_amount in [1_000_000u128, 100_000_000, 10_000_000_000] {
+            let reverse = helper
+                .simulate_reverse_swap(ask_info.with_balance(ask_amount), Some(offer_info.clone()))
+                .unwrap();
+            let sim_resp = helper
+                .simulate_swap(
+                    offer_info.with_balance(reverse.offer_amount),
+                    Some(ask_info.clone()),
+                )
+                .unwrap();
+
+            // offering the reverse simulated amount returns the requested amount
+            assert_approx_eq!(sim_resp.return_amount.u128(), ask_amount, "0.002");
+            assert_approx_eq!(
+                sim_resp.commission_amount,
+                reverse.commission_amount,
+                "0.002"
+            );
+        }
+    }
+}
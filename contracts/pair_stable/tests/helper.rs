@@ -15,7 +15,7 @@ use sg_swap::factory::{DefaultStakeConfig, PairConfig, PairType, PartialStakeCon
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
     CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, PairInfo, QueryMsg,
-    ReverseSimulationResponse, SimulationResponse, StablePoolParams,
+    ReverseSimulationResponse, SimulateProvideResponse, SimulationResponse, StablePoolParams,
 };
 use sg_swap::querier::NATIVE_TOKEN_PRECISION;
 use sg_swap_pair_stable::contract::{execute, instantiate, query, reply};
@@ -158,6 +158,8 @@ impl Helper {
                 },
                 pair_type: PairType::Stable {},
                 is_disabled: false,
+                default_max_spread: None,
+                init_params: None,
             }],
             token_code_id,
             owner: owner.to_string(),
@@ -170,6 +172,7 @@ impl Helper {
                 max_distributions: 6,
             },
             trading_starts: None,
+            fee_recipients: None,
         };
 
         let factory = app.instantiate_contract(
@@ -192,6 +195,11 @@ impl Helper {
             init_params: Some(to_binary(&StablePoolParams { amp, owner: None }).unwrap()),
             staking_config: PartialStakeConfig::default(),
             total_fee_bps: None,
+            lp_token_name: None,
+            lp_token_symbol: None,
+            trading_starts: None,
+            trading_starts_delay: None,
+            lp_token_type: None,
         };
 
         app.execute_contract(owner.clone(), factory.clone(), &init_pair_msg, &[])?;
@@ -226,6 +234,7 @@ impl Helper {
             assets: assets.iter().cloned().map(Into::into).collect(),
             slippage_tolerance: None,
             receiver: None,
+            min_lp_amount: None,
         };
 
         self.app
@@ -241,7 +250,52 @@ impl Helper {
         let msg = Cw20ExecuteMsg::Send {
             contract: self.pair_addr.to_string(),
             amount: Uint128::from(amount),
-            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity { assets }).unwrap(),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                assets,
+                min_assets: None,
+            })
+            .unwrap(),
+        };
+
+        self.app
+            .execute_contract(sender.clone(), self.lp_token.clone(), &msg, &[])
+    }
+
+    pub fn withdraw_liquidity_with_min(
+        &mut self,
+        sender: &Addr,
+        amount: u128,
+        min_assets: Vec<Asset>,
+    ) -> AnyResult<AppResponse> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.pair_addr.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::WithdrawLiquidity {
+                assets: vec![],
+                min_assets: Some(min_assets),
+            })
+            .unwrap(),
+        };
+
+        self.app
+            .execute_contract(sender.clone(), self.lp_token.clone(), &msg, &[])
+    }
+
+    pub fn withdraw_to_asset(
+        &mut self,
+        sender: &Addr,
+        amount: u128,
+        asset: AssetInfo,
+        min_out: Option<u128>,
+    ) -> AnyResult<AppResponse> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.pair_addr.to_string(),
+            amount: Uint128::from(amount),
+            msg: to_binary(&Cw20HookMsg::WithdrawToAsset {
+                asset,
+                min_out: min_out.map(Uint128::new),
+            })
+            .unwrap(),
         };
 
         self.app
@@ -294,6 +348,9 @@ impl Helper {
                 self.app
                     .execute_contract(sender.clone(), self.pair_addr.clone(), &msg, &funds)
             }
+            AssetInfoValidated::Cw1155 { .. } => {
+                panic!("cannot swap {offer_asset}, pairs do not support cw1155 tokens")
+            }
         }
     }
 
@@ -333,6 +390,42 @@ impl Helper {
         )
     }
 
+    pub fn simulate_withdraw(&self, amount: u128) -> StdResult<Vec<AssetValidated>> {
+        self.app.wrap().query_wasm_smart(
+            &self.pair_addr,
+            &QueryMsg::SimulateWithdraw {
+                amount: amount.into(),
+            },
+        )
+    }
+
+    pub fn simulate_provide(
+        &self,
+        assets: &[AssetValidated],
+    ) -> StdResult<SimulateProvideResponse> {
+        self.app.wrap().query_wasm_smart(
+            &self.pair_addr,
+            &QueryMsg::SimulateProvide {
+                assets: assets.iter().cloned().map(Into::into).collect(),
+                slippage_tolerance: None,
+            },
+        )
+    }
+
+    pub fn query_spot_price(
+        &self,
+        offer: &AssetInfoValidated,
+        ask: &AssetInfoValidated,
+    ) -> StdResult<Decimal> {
+        self.app.wrap().query_wasm_smart(
+            &self.pair_addr,
+            &QueryMsg::SpotPrice {
+                offer: offer.clone().into(),
+                ask: ask.clone().into(),
+            },
+        )
+    }
+
     pub fn query_prices(&self) -> StdResult<CumulativePricesResponse> {
         self.app
             .wrap()
@@ -393,6 +486,11 @@ impl Helper {
                 .unwrap()
                 .amount
                 .u128(),
+            info @ AssetInfoValidated::Cw1155 { .. } => {
+                panic!(
+                    "cannot query the {info} balance of {user}, pairs do not support cw1155 tokens"
+                )
+            }
         }
     }
 
@@ -5,9 +5,9 @@ use sg_swap::factory::{
 };
 use sg_swap::fee_config::FeeConfig;
 use sg_swap::pair::{
-    ConfigResponse, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, PairInfo,
-    PoolResponse, QueryMsg, StablePoolConfig, StablePoolParams, StablePoolUpdateParams,
-    TWAP_PRECISION,
+    ConfigResponse, ContractError, CumulativePricesResponse, Cw20HookMsg, ExecuteMsg,
+    InstantiateMsg, LpTokenType, PairInfo, PoolResponse, QueryMsg, SimulationResponse,
+    StablePoolConfig, StablePoolParams, StablePoolUpdateParams, TWAP_PRECISION,
 };
 
 use cosmwasm_std::{
@@ -92,12 +92,15 @@ fn instantiate_factory(router: &mut App, owner: &Addr) -> Addr {
             fee_config,
             pair_type: PairType::Stable {},
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id: token_contract_code_id,
         owner: owner.to_string(),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(stake_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     router
@@ -119,13 +122,18 @@ fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
         init_params: None,
         total_fee_bps: None,
         staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     let resp = router
         .execute_contract(owner.clone(), factory.clone(), &msg, &[])
         .unwrap_err();
     assert_eq!(
-        "You need to provide init params",
+        "Stable pairs need an amp, either in the init params or in the pair config",
         resp.root_cause().to_string()
     );
 
@@ -141,6 +149,11 @@ fn instantiate_pair(router: &mut App, owner: &Addr) -> Addr {
         ),
         total_fee_bps: None,
         staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
     router
         .execute_contract(owner.clone(), factory.clone(), &msg, &[])
@@ -352,6 +365,7 @@ fn provide_liquidity_msg(
         ],
         slippage_tolerance: None,
         receiver,
+        min_lp_amount: None,
     };
 
     let coins = [
@@ -462,12 +476,15 @@ fn provide_lp_for_single_token() {
             },
             pair_type: PairType::Stable {},
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id,
         owner: String::from("owner0000"),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(stake_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let factory_instance = app
@@ -496,6 +513,11 @@ fn provide_lp_for_single_token() {
         ),
         staking_config: PartialStakeConfig::default(),
         total_fee_bps: None,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     app.execute_contract(
@@ -555,9 +577,39 @@ fn provide_lp_for_single_token() {
     let err = app
         .execute_contract(owner.clone(), token_x_instance.clone(), &swap_msg, &[])
         .unwrap_err();
+    assert_eq!("One of the pools is empty", err.root_cause().to_string());
+
+    // simulations and the spot price return the same error instead of zeros
+    let err = app
+        .wrap()
+        .query_wasm_smart::<SimulationResponse>(
+            &pair_instance,
+            &QueryMsg::Simulation {
+                offer_asset: AssetInfo::Token(token_x_instance.to_string())
+                    .with_balance(swap_amount),
+                ask_asset_info: None,
+                referral: false,
+                referral_commission: None,
+            },
+        )
+        .unwrap_err();
     assert_eq!(
-        "Generic error: One of the pools is empty",
-        err.root_cause().to_string()
+        "Generic error: Querier contract error: Generic error: One of the pools is empty",
+        err.to_string()
+    );
+    let err = app
+        .wrap()
+        .query_wasm_smart::<Decimal>(
+            &pair_instance,
+            &QueryMsg::SpotPrice {
+                offer: AssetInfo::Token(token_x_instance.to_string()),
+                ask: AssetInfo::Token(token_y_instance.to_string()),
+            },
+        )
+        .unwrap_err();
+    assert_eq!(
+        "Generic error: Querier contract error: Generic error: One of the pools is empty",
+        err.to_string()
     );
 
     let msg = ExecuteMsg::ProvideLiquidity {
@@ -573,6 +625,7 @@ fn provide_lp_for_single_token() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let err = app
@@ -596,6 +649,7 @@ fn provide_lp_for_single_token() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -615,6 +669,7 @@ fn provide_lp_for_single_token() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -642,6 +697,7 @@ fn provide_lp_for_single_token() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -783,12 +839,15 @@ fn test_compatibility_of_tokens_with_different_precision() {
             },
             pair_type: PairType::Stable {},
             is_disabled: false,
+            default_max_spread: None,
+            init_params: None,
         }],
         token_code_id,
         owner: String::from("owner0000"),
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(stake_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let factory_instance = app
@@ -817,6 +876,11 @@ fn test_compatibility_of_tokens_with_different_precision() {
         ),
         staking_config: PartialStakeConfig::default(),
         total_fee_bps: None,
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
 
     app.execute_contract(
@@ -872,6 +936,7 @@ fn test_compatibility_of_tokens_with_different_precision() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     app.execute_contract(owner.clone(), pair_instance.clone(), &msg, &[])
@@ -1011,6 +1076,35 @@ fn test_if_twap_is_calculated_correctly_when_pool_idles() {
     assert_eq!(twap1 / price_precision, Uint128::new(87121)); // 0.991712963 * ELAPSED_SECONDS
 }
 
+#[test]
+fn update_fees_permissions() {
+    let owner = Addr::unchecked(OWNER);
+    let mut router = mock_app(owner.clone(), vec![]);
+    let pair_instance = instantiate_pair(&mut router, &owner);
+    let fee_config = FeeConfig {
+        total_fee_bps: 30,
+        protocol_fee_bps: 1000,
+    };
+    let msg = ExecuteMsg::UpdateFees {
+        fee_config: fee_config.clone(),
+    };
+
+    let err = router
+        .execute_contract(Addr::unchecked("someone"), pair_instance.clone(), &msg, &[])
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // the pair has no owner, so the factory owner can update the fees
+    router
+        .execute_contract(owner, pair_instance.clone(), &msg, &[])
+        .unwrap();
+    let res: PairInfo = router
+        .wrap()
+        .query_wasm_smart(&pair_instance, &QueryMsg::Pair {})
+        .unwrap();
+    assert_eq!(res.fee_config, fee_config);
+}
+
 #[test]
 fn create_pair_with_same_assets() {
     let owner = Addr::unchecked(OWNER);
@@ -1042,6 +1136,10 @@ fn create_pair_with_same_assets() {
         init_params: None,
         staking_config: default_stake_config(stake_code_id).to_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             total_fee_bps: 0,
             protocol_fee_bps: 0,
@@ -1105,6 +1203,7 @@ fn provide_liquidity_with_one_asset() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
         .execute_contract(
@@ -1150,6 +1249,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     let err = router
         .execute_contract(
@@ -1182,6 +1282,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![AssetInfo::Native("uusd".to_string()).with_balance(0u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     let err = router
         .execute_contract(owner.clone(), pair.clone(), &msg, &[])
@@ -1194,6 +1295,7 @@ fn provide_liquidity_sad_path() {
         assets: vec![],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
 
     let err = router
@@ -1237,6 +1339,11 @@ fn provide_liquidity_with_one_cw20_asset() {
         ),
         total_fee_bps: None,
         staking_config: PartialStakeConfig::default(),
+        lp_token_name: None,
+        lp_token_symbol: None,
+        trading_starts: None,
+        trading_starts_delay: None,
+        lp_token_type: None,
     };
     router
         .execute_contract(owner.clone(), factory.clone(), &msg, &[])
@@ -1282,6 +1389,7 @@ fn provide_liquidity_with_one_cw20_asset() {
         ],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
         .execute_contract(owner.clone(), pair.clone(), &msg, &[])
@@ -1292,6 +1400,7 @@ fn provide_liquidity_with_one_cw20_asset() {
         assets: vec![AssetInfo::Token(token2.to_string()).with_balance(100_000u128)],
         slippage_tolerance: None,
         receiver: None,
+        min_lp_amount: None,
     };
     router
         .execute_contract(owner.clone(), pair.clone(), &msg, &[])
@@ -1404,6 +1513,7 @@ fn update_pair_config() {
         max_referral_commission: Decimal::one(),
         default_stake_config: default_stake_config(stake_code_id),
         trading_starts: None,
+        fee_recipients: None,
     };
 
     let factory_instance = router
@@ -1433,6 +1543,10 @@ fn update_pair_config() {
         ),
         staking_config: default_stake_config(stake_code_id).to_stake_config(),
         trading_starts: 0,
+        default_max_spread: None,
+        lp_token_type: LpTokenType::Cw20,
+        lp_token_name: None,
+        lp_token_symbol: None,
         fee_config: FeeConfig {
             protocol_fee_bps: 0,
             total_fee_bps: 0,
@@ -1617,3 +1731,195 @@ fn update_pair_config() {
 
     assert_eq!(params.amp, Decimal::from_ratio(150u32, 1u32));
 }
+
+#[test]
+fn amp_ramp_is_interpolated_and_used_by_swaps() {
+    let owner = Addr::unchecked(OWNER);
+    let mut router = mock_app(
+        owner.clone(),
+        vec![
+            Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+            Coin {
+                denom: "uluna".to_string(),
+                amount: Uint128::new(100_000_000_000u128),
+            },
+        ],
+    );
+
+    let token_contract_code_id = store_token_code(&mut router);
+    let pair_contract_code_id = store_pair_code(&mut router);
+    let factory_code_id = store_factory_code(&mut router);
+    let stake_code_id = store_stake_code(&mut router);
+
+    let factory_instance = router
+        .instantiate_contract(
+            factory_code_id,
+            owner.clone(),
+            &FactoryInstantiateMsg {
+                fee_address: None,
+                pair_configs: vec![],
+                token_code_id: token_contract_code_id,
+                owner: owner.to_string(),
+                max_referral_commission: Decimal::one(),
+                default_stake_config: default_stake_config(stake_code_id),
+                trading_starts: None,
+                fee_recipients: None,
+            },
+            &[],
+            "FACTORY",
+            None,
+        )
+        .unwrap();
+
+    // creates a pair with the given amp and some imbalanced liquidity
+    let create_pair = |router: &mut App, amp: u64| {
+        let msg = InstantiateMsg {
+            asset_infos: vec![
+                AssetInfo::Native("uusd".to_string()),
+                AssetInfo::Native("uluna".to_string()),
+            ],
+            token_code_id: token_contract_code_id,
+            factory_addr: factory_instance.to_string(),
+            init_params: Some(to_binary(&StablePoolParams { amp, owner: None }).unwrap()),
+            staking_config: default_stake_config(stake_code_id).to_stake_config(),
+            trading_starts: 0,
+            default_max_spread: None,
+            lp_token_type: LpTokenType::Cw20,
+            lp_token_name: None,
+            lp_token_symbol: None,
+            fee_config: FeeConfig {
+                protocol_fee_bps: 0,
+                total_fee_bps: 0,
+            },
+        };
+        let pair = router
+            .instantiate_contract(
+                pair_contract_code_id,
+                owner.clone(),
+                &msg,
+                &[],
+                String::from("PAIR"),
+                None,
+            )
+            .unwrap();
+
+        let (msg, coins) =
+            provide_liquidity_msg(Uint128::new(1_000_000_000), Uint128::new(400_000_000), None);
+        router
+            .execute_contract(owner.clone(), pair.clone(), &msg, &coins)
+            .unwrap();
+
+        pair
+    };
+    let query_amp = |router: &App, pair: &Addr| {
+        let res: ConfigResponse = router
+            .wrap()
+            .query_wasm_smart(pair, &QueryMsg::Config {})
+            .unwrap();
+        from_binary::<StablePoolConfig>(&res.params.unwrap())
+            .unwrap()
+            .amp
+    };
+    let simulate = |router: &App, pair: &Addr| {
+        router
+            .wrap()
+            .query_wasm_smart::<SimulationResponse>(
+                pair,
+                &QueryMsg::Simulation {
+                    offer_asset: AssetInfo::Native("uusd".to_string()).with_balance(10_000_000u128),
+                    ask_asset_info: None,
+                    referral: false,
+                    referral_commission: None,
+                },
+            )
+            .unwrap()
+            .return_amount
+    };
+
+    let pair = create_pair(&mut router, 100);
+
+    router.update_block(|b| {
+        b.time = b.time.plus_seconds(MIN_AMP_CHANGING_TIME);
+    });
+
+    // only the owner can start a ramp
+    let ramp_msg = ExecuteMsg::StartAmpRamp {
+        next_amp: 250,
+        next_amp_time: router.block_info().time.seconds() + MIN_AMP_CHANGING_TIME,
+    };
+    let err = router
+        .execute_contract(Addr::unchecked("someone"), pair.clone(), &ramp_msg, &[])
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Unauthorized {},
+        err.downcast::<ContractError>().unwrap()
+    );
+
+    // ramps that change the amp too much are rejected
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            pair.clone(),
+            &ExecuteMsg::StartAmpRamp {
+                next_amp: 100 * MAX_AMP_CHANGE + 1,
+                next_amp_time: router.block_info().time.seconds() + MIN_AMP_CHANGING_TIME,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MaxAmpChangeAssertion {
+            max_amp_change: MAX_AMP_CHANGE
+        },
+        err.downcast::<ContractError>().unwrap()
+    );
+
+    // as are ramps that are too fast
+    let err = router
+        .execute_contract(
+            owner.clone(),
+            pair.clone(),
+            &ExecuteMsg::StartAmpRamp {
+                next_amp: 250,
+                next_amp_time: router.block_info().time.seconds() + MIN_AMP_CHANGING_TIME - 1,
+            },
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MinAmpChangingTimeAssertion {
+            min_amp_changing_time: MIN_AMP_CHANGING_TIME
+        },
+        err.downcast::<ContractError>().unwrap()
+    );
+
+    router
+        .execute_contract(owner.clone(), pair.clone(), &ramp_msg, &[])
+        .unwrap();
+    let swap_before = simulate(&router, &pair);
+
+    // half way through the ramp, the amp is interpolated linearly
+    router.update_block(|b| {
+        b.time = b.time.plus_seconds(MIN_AMP_CHANGING_TIME / 2);
+    });
+    assert_eq!(query_amp(&router, &pair), Decimal::from_ratio(175u32, 1u32));
+
+    // and swaps behave like in a pool with that amp
+    let reference_pair = create_pair(&mut router, 175);
+    let swap_mid_ramp = simulate(&router, &pair);
+    assert_eq!(swap_mid_ramp, simulate(&router, &reference_pair));
+    assert_ne!(swap_mid_ramp, swap_before);
+
+    // stopping the ramp keeps the current amp
+    router
+        .execute_contract(owner, pair.clone(), &ExecuteMsg::StopAmpRamp {}, &[])
+        .unwrap();
+    router.update_block(|b| {
+        b.time = b.time.plus_seconds(MIN_AMP_CHANGING_TIME);
+    });
+    assert_eq!(query_amp(&router, &pair), Decimal::from_ratio(175u32, 1u32));
+    assert_eq!(simulate(&router, &pair), swap_mid_ramp);
+}
@@ -1,31 +1,44 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    from_slice, to_binary, Addr, Binary, Decimal, Deps, DepsMut, Env, MessageInfo, Order, Response,
-    StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
+    attr, coin, from_slice, to_binary, Addr, Attribute, Binary, Decimal, Deps, DepsMut, Env,
+    MessageInfo, Order, Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
-use sg_swap::asset::{AssetInfo, AssetInfoValidated};
+use sg_swap::asset::{addr_opt_validate, AssetInfo, AssetInfoExt, AssetInfoValidated};
+use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, PairInfo, QueryMsg as PairQueryMsg};
 use sg_swap::stake::{InstantiateMsg, UnbondingPeriod};
 
 use crate::distribution::{
     apply_points_correction, execute_delegate_withdrawal, execute_distribute_rewards,
-    execute_withdraw_rewards, query_delegated, query_distributed_rewards, query_distribution_data,
-    query_undistributed_rewards, query_withdraw_adjustment_data, query_withdrawable_rewards,
+    execute_set_reward_receiver, execute_withdraw_rewards, forfeit_rewards, query_delegated,
+    query_delegated_to, query_distributed_rewards, query_distribution_data,
+    query_projected_rewards, query_simulate_distribution, query_undistributed_rewards,
+    query_withdraw_adjustment_data, query_withdrawable_reward, query_withdrawable_rewards,
+    remove_distribution, settle_withdraw_adjustments, take_withdrawable_rewards,
+    undistributed_rewards,
 };
 use crate::utils::CurveExt;
 use cw2::set_contract_version;
-use cw_utils::{maybe_addr, Expiration};
+use cw_controllers::ClaimsResponse;
+use cw_utils::{maybe_addr, must_pay, Expiration};
 
 use crate::error::ContractError;
 use crate::msg::{
-    AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse, BondingInfoResponse,
-    BondingPeriodInfo, ExecuteMsg, QueryMsg, ReceiveDelegationMsg, RewardsPowerResponse,
-    StakedResponse, TotalStakedResponse, TotalUnbondingResponse,
+    AdminActionsResponse, AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse,
+    BondingInfoResponse, BondingPeriodInfo, ExecuteMsg, LockScheduleResponse, QueryMsg,
+    RebondRewardsPower, ReceiveDelegationMsg, RewardCurvesResponse, RewardsPowerResponse,
+    SimulateRebondResponse, StakedAtResponse, StakedResponse, StakingMetricsResponse,
+    TotalRewardsPowerPerPeriodResponse, TotalStakedAtResponse, TotalStakedResponse,
+    TotalUnbondingResponse,
 };
 use crate::state::{
-    load_total_of_period, Config, Distribution, TokenInfo, TotalStake, ADMIN, CLAIMS, CONFIG,
-    DISTRIBUTION, REWARD_CURVE, STAKE, TOTAL_PER_PERIOD, TOTAL_STAKED,
+    all_claims, claim_recipient_tokens, create_recipient_claim, load_total_of_period,
+    pending_claims, read_admin_actions, read_claims, record_admin_action, save_total_per_period,
+    update_total_staked, AdminAction, BondingInfo, Config, Distribution, PowerRescale, StakedToken,
+    TokenInfo, TotalStake, ADMIN, CLAIMS, CLOSED_DISTRIBUTION, CONFIG, DEFAULT_MAX_CLAIMS_PER_USER,
+    DISTRIBUTION, POWER_RESCALES, REWARD_CURVE, STAKE, STAKE_SNAPSHOTS, STAKING_METRICS,
+    TOTAL_PER_PERIOD, TOTAL_STAKED,
 };
 use wynd_curve_utils::Curve;
 
@@ -40,7 +53,7 @@ const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     mut deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     mut msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
@@ -52,7 +65,11 @@ pub fn instantiate(
     // min_bond is at least 1, so 0 stake -> non-membership
     let min_bond = std::cmp::max(msg.min_bond, Uint128::new(1));
 
-    TOTAL_STAKED.save(deps.storage, &TokenInfo::default())?;
+    if matches!(msg.instant_unbond_penalty, Some(penalty) if penalty > Decimal::one()) {
+        return Err(ContractError::InvalidInstantUnbondPenalty {});
+    }
+
+    TOTAL_STAKED.save(deps.storage, &TokenInfo::default(), env.block.height)?;
 
     // make sure they are sorted, this is important because the rest of the contract assumes the same
     // order everywhere and uses binary search in some places.
@@ -65,15 +82,24 @@ pub fn instantiate(
             .iter()
             .map(|unbonding_period| (*unbonding_period, TotalStake::default()))
             .collect(),
+        env.block.height,
     )?;
 
+    let staked_token = match msg.native_denom {
+        Some(denom) => StakedToken::Native(denom),
+        None => StakedToken::Cw20(deps.api.addr_validate(&msg.cw20_contract)?),
+    };
+
     let config = Config {
         instantiator: info.sender,
-        cw20_contract: deps.api.addr_validate(&msg.cw20_contract)?,
+        staked_token,
         tokens_per_power: msg.tokens_per_power,
         min_bond,
+        original_min_bond: None,
         unbonding_periods: msg.unbonding_periods,
         max_distributions: msg.max_distributions,
+        instant_unbond_penalty: msg.instant_unbond_penalty,
+        max_claims_per_user: DEFAULT_MAX_CLAIMS_PER_USER,
     };
     CONFIG.save(deps.storage, &config)?;
 
@@ -91,24 +117,70 @@ pub fn execute(
     let api = deps.api;
     match msg {
         ExecuteMsg::UpdateAdmin { admin } => {
-            Ok(ADMIN.execute_update_admin(deps, info, maybe_addr(api, admin)?)?)
+            execute_update_admin(deps, env, info, maybe_addr(api, admin)?)
+        }
+        ExecuteMsg::UpdateTokensPerPower { tokens_per_power } => {
+            execute_update_tokens_per_power(deps, env, info, tokens_per_power)
+        }
+        ExecuteMsg::UpdateMinBond { min_bond } => {
+            execute_update_min_bond(deps, env, info, min_bond)
+        }
+        ExecuteMsg::UpdateMaxClaimsPerUser {
+            max_claims_per_user,
+        } => execute_update_max_claims_per_user(deps, env, info, max_claims_per_user),
+        ExecuteMsg::SetMaxDistributions { max } => {
+            execute_set_max_distributions(deps, env, info, max)
+        }
+        ExecuteMsg::RemoveUnbondingPeriod { period, migrate_to } => {
+            execute_remove_unbonding_period(deps, env, info, period, migrate_to)
+        }
+        ExecuteMsg::AddUnbondingPeriod { period, rewards } => {
+            execute_add_unbonding_period(deps, env, info, period, rewards)
         }
         ExecuteMsg::CreateDistributionFlow {
             manager,
             asset,
             rewards,
-        } => execute_create_distribution_flow(deps, info, manager, asset, rewards),
+        } => execute_create_distribution_flow(deps, env, info, manager, asset, rewards),
+        ExecuteMsg::CloseDistributionFlow { asset } => {
+            execute_close_distribution_flow(deps, env, info, asset)
+        }
+        ExecuteMsg::UpdateRewardMultipliers { asset, rewards } => {
+            execute_update_reward_multipliers(deps, env, info, asset, rewards)
+        }
+        ExecuteMsg::SetDistributionPaused { asset, paused } => {
+            execute_set_distribution_paused(deps, env, info, asset, paused)
+        }
         ExecuteMsg::Rebond {
             tokens,
             bond_from,
             bond_to,
-        } => execute_rebond(deps, env, info, tokens, bond_from, bond_to),
+            include_locked,
+        } => execute_rebond(deps, env, info, tokens, bond_from, bond_to, include_locked),
+        ExecuteMsg::TransferStake {
+            recipient,
+            unbonding_period,
+            amount,
+        } => execute_transfer_stake(deps, env, info, recipient, unbonding_period, amount),
         ExecuteMsg::Unbond {
             tokens: amount,
             unbonding_period,
-        } => execute_unbond(deps, env, info, amount, unbonding_period),
-        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+            claim_recipient,
+        } => execute_unbond(deps, env, info, amount, unbonding_period, claim_recipient),
+        ExecuteMsg::UnbondAll {} => execute_unbond_all(deps, env, info),
+        ExecuteMsg::EmergencyWithdraw { unbonding_period } => {
+            execute_emergency_withdraw(deps, env, info, unbonding_period)
+        }
+        ExecuteMsg::InstantUnbond {
+            tokens: amount,
+            unbonding_period,
+        } => execute_instant_unbond(deps, env, info, amount, unbonding_period),
+        ExecuteMsg::Claim { up_to_amount } => execute_claim(deps, env, info, up_to_amount),
         ExecuteMsg::Receive(msg) => execute_receive_delegation(deps, env, info, msg),
+        ExecuteMsg::BondNative {
+            unbonding_period,
+            delegate_as,
+        } => execute_bond_native(deps, env, info, unbonding_period, delegate_as),
         ExecuteMsg::DistributeRewards { sender } => {
             execute_distribute_rewards(deps, env, info, sender)
         }
@@ -118,45 +190,90 @@ pub fn execute(
         ExecuteMsg::DelegateWithdrawal { delegated } => {
             execute_delegate_withdrawal(deps, info, delegated)
         }
+        ExecuteMsg::SetRewardReceiver { receiver } => {
+            execute_set_reward_receiver(deps, info, receiver)
+        }
         ExecuteMsg::FundDistribution { curve } => execute_fund_distribution(env, deps, info, curve),
+        ExecuteMsg::CompoundRewards {
+            unbonding_period,
+            pair,
+            max_spread,
+        } => execute_compound_rewards(deps, env, info, unbonding_period, pair, max_spread),
+        ExecuteMsg::BondCompounded {
+            owner,
+            unbonding_period,
+            prev_balance,
+        } => execute_bond_compounded(deps, env, info, owner, unbonding_period, prev_balance),
+        ExecuteMsg::WithdrawRewardsToStake { unbonding_period } => {
+            execute_withdraw_rewards_to_stake(deps, env, info, unbonding_period)
+        }
     }
 }
 
+/// Changes the admin of the contract. Only callable by the current admin.
+pub fn execute_update_admin(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    admin: Option<Addr>,
+) -> Result<Response, ContractError> {
+    let res = ADMIN.execute_update_admin(deps.branch(), info.clone(), admin.clone())?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::UpdateAdmin { admin },
+    )?;
+    Ok(res)
+}
+
 /// Fund a previously created distribution flow with the given amount of native tokens.
 /// Allows for providing multiple native tokens at once to update multiple distribution flows with the same optionally provided Curve.
 pub fn execute_fund_distribution(
     env: Env,
-    deps: DepsMut,
+    mut deps: DepsMut,
     info: MessageInfo,
     schedule: Curve,
 ) -> Result<Response, ContractError> {
-    let api = deps.api;
-    let storage = deps.storage;
-
+    let mut resp = Response::new().add_attribute("action", "fund_distribution");
     for fund in info.funds {
         let asset = AssetInfo::Native(fund.denom);
-        let validated_asset = asset.validate(api)?;
-        update_reward_config(
+        let validated_asset = asset.validate(deps.api)?;
+        let attributes = update_reward_config(
             &env,
-            storage,
+            deps.branch(),
             validated_asset,
             fund.amount,
             schedule.clone(),
         )?;
+        resp = resp.add_attributes(attributes);
     }
-    Ok(Response::default())
+    Ok(resp)
 }
 
-/// Update reward config for the given asset with an additional amount of funding
+/// Update reward config for the given asset with an additional amount of funding.
+/// Returns the attributes describing the funding, including the range of the resulting reward curve.
+/// The funds have to be transferred to the contract already, since the rewards that are still locked
+/// after the update are checked against the undistributed rewards of the asset.
 fn update_reward_config(
     env: &Env,
-    storage: &mut dyn Storage,
+    deps: DepsMut,
     validated_asset: AssetInfoValidated,
     amount: Uint128,
     schedule: Curve,
-) -> Result<(), ContractError> {
+) -> Result<Vec<Attribute>, ContractError> {
+    let distribution = DISTRIBUTION.load(deps.storage, &validated_asset)?;
+    // a closed distribution would never release the funding to the stakers
+    if distribution.closed_at.is_some() {
+        return Err(ContractError::DistributionClosed(validated_asset));
+    }
+    // the curve of a paused distribution is shifted when resuming, which would also delay the new funding
+    if distribution.paused_at.is_some() {
+        return Err(ContractError::DistributionPaused(validated_asset));
+    }
+
     // How can we validate the amount and curve? Monotonic decreasing check is below, given this is there still a need to test the amount?
-    let previous_reward_curve = REWARD_CURVE.load(storage, &validated_asset)?;
+    let previous_reward_curve = REWARD_CURVE.load(deps.storage, &validated_asset)?;
     let (min, max) = schedule.range();
     // Validate the the curve locks at most the amount provided and also fully unlocks all rewards sent
     if min != 0 || max > amount.u128() {
@@ -174,13 +291,36 @@ fn update_reward_config(
     let new_reward_curve = previous_reward_curve.combine(&schedule);
     new_reward_curve.validate_monotonic_decreasing()?;
 
-    REWARD_CURVE.save(storage, &validated_asset, &new_reward_curve)?;
-    Ok(())
+    // all rewards that are locked from now on have to be covered by the undistributed rewards,
+    // which exclude the withdrawable rewards and, for the staked token, the stake
+    let cfg = CONFIG.load(deps.storage)?;
+    let locked = Uint128::new(new_reward_curve.value(env.block.time.seconds()));
+    let balance =
+        undistributed_rewards(deps.as_ref(), &cfg, &validated_asset, &env.contract.address)?
+            .saturating_sub(distribution.withdrawable_total);
+    if locked > balance {
+        return Err(ContractError::InsufficientRewardFunding {
+            asset: validated_asset.to_string(),
+            locked,
+            balance,
+        });
+    }
+
+    REWARD_CURVE.save(deps.storage, &validated_asset, &new_reward_curve)?;
+
+    let (curve_min, curve_max) = new_reward_curve.range();
+    Ok(vec![
+        attr("asset", validated_asset.to_string()),
+        attr("amount", amount),
+        attr("curve_min", curve_min.to_string()),
+        attr("curve_max", curve_max.to_string()),
+    ])
 }
 
 /// Create a new rewards distribution flow for the given asset as a reward
 pub fn execute_create_distribution_flow(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     manager: String,
     asset: AssetInfo,
@@ -195,26 +335,14 @@ pub fn execute_create_distribution_flow(
 
     // make sure the asset is not the staked token, since we distribute this contract's balance
     // and we definitely do not want to distribute the staked tokens.
+    // The only exception is when instant unbonding is enabled, because then the penalties
+    // are distributed as rewards in the staked token (excluding the actual stake).
     let config = CONFIG.load(deps.storage)?;
-    if let AssetInfoValidated::Token(addr) = &asset {
-        if addr == &config.cw20_contract {
-            return Err(ContractError::InvalidAsset {});
-        }
+    if asset == config.staked_token.asset_info() && config.instant_unbond_penalty.is_none() {
+        return Err(ContractError::InvalidAsset {});
     }
 
-    // validate rewards unbonding periods
-    if rewards
-        .iter()
-        .map(|(period, _)| period)
-        .ne(config.unbonding_periods.iter())
-    {
-        return Err(ContractError::InvalidRewards {});
-    }
-    // make sure rewards are monotonically increasing (equality is allowed)
-    // this assumes that `config.unbonding_periods` (and therefore also `rewards`) is sorted (checked in instantiate)
-    if rewards.windows(2).any(|w| w[0].1 > w[1].1) {
-        return Err(ContractError::InvalidRewards {});
-    }
+    validate_reward_multipliers(&config, &rewards)?;
 
     // make sure to respect the distribution count limit to create an upper bound for all the staking operations
     let keys = DISTRIBUTION
@@ -230,6 +358,11 @@ pub fn execute_create_distribution_flow(
     if keys.contains(&asset) {
         return Err(ContractError::DistributionAlreadyExists(asset));
     }
+    // a removed flow of the same asset may have left withdraw adjustments behind,
+    // so the new flow is a new generation that ignores them
+    let generation = CLOSED_DISTRIBUTION
+        .may_load(deps.storage, &asset)?
+        .map_or(0, |closed| closed.generation + 1);
 
     REWARD_CURVE.save(deps.storage, &asset, &Curve::constant(0))?;
 
@@ -237,208 +370,1132 @@ pub fn execute_create_distribution_flow(
         deps.storage,
         &asset,
         &Distribution {
-            manager,
+            manager: manager.clone(),
             reward_multipliers: rewards,
             shares_per_point: Uint128::zero(),
             shares_leftover: 0,
             distributed_total: Uint128::zero(),
             withdrawable_total: Uint128::zero(),
+            paused_at: None,
+            closed_at: None,
+            generation,
+            rescales: 0,
         },
     )?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::CreateDistributionFlow { asset, manager },
+    )?;
 
     Ok(Response::default())
 }
 
-pub fn execute_rebond(
+/// Changes the `tokens_per_power` of the config. Only callable by the admin.
+///
+/// The rewards power of every staker changes with the new scaling, but recalculating the points
+/// correction of each of them would be too expensive. Instead, the change is recorded for each
+/// distribution together with its current shares per point, and applied to the withdraw adjustment
+/// of each staker the next time it is loaded (see [`Distribution::withdraw_adjustment`]).
+/// That corrects the rewards distributed so far back to the old rewards power of the staker, so they
+/// stay exactly the same, and the new scaling only applies to future distributions.
+pub fn execute_update_tokens_per_power(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    amount: Uint128,
-    bond_from: u64,
-    bond_to: u64,
+    tokens_per_power: Uint128,
 ) -> Result<Response, ContractError> {
-    // Raise if no amount was provided
-    if amount == Uint128::zero() {
-        return Err(ContractError::NoRebondAmount {});
-    }
-    // Short out with an error if trying to rebond to itself
-    if bond_from == bond_to {
-        return Err(ContractError::SameUnbondingRebond {});
-    }
-
-    let cfg = CONFIG.load(deps.storage)?;
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    if cfg.unbonding_periods.binary_search(&bond_from).is_err() {
-        return Err(ContractError::NoUnbondingPeriodFound(bond_from));
-    }
-    if cfg.unbonding_periods.binary_search(&bond_to).is_err() {
-        return Err(ContractError::NoUnbondingPeriodFound(bond_to));
+    if tokens_per_power.is_zero() {
+        return Err(ContractError::ZeroTokensPerPower {});
     }
 
+    let old_config = CONFIG.load(deps.storage)?;
+    let new_config = Config {
+        tokens_per_power,
+        ..old_config.clone()
+    };
+
     let distributions: Vec<_> = DISTRIBUTION
         .range(deps.storage, None, None, Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
-
-    // calculate rewards power before updating the stake
-    let old_rewards = calc_rewards_powers(deps.storage, &cfg, &info.sender, distributions.iter())?;
-
-    // Reduce the bond_from
-    let mut old_stake_from = Uint128::zero();
-    let new_stake_from = STAKE
-        .update(
-            deps.storage,
-            (&info.sender, bond_from),
-            |bonding_info| -> StdResult<_> {
-                let mut bonding_info = bonding_info.unwrap_or_default();
-                old_stake_from = bonding_info.total_stake();
-                // Release the stake, also accounting for locked tokens, raising if there is not enough tokens
-                bonding_info.release_stake(&env, amount)?;
-                Ok(bonding_info)
-            },
-        )?
-        .total_stake();
-
-    // Increase the bond_to
-    let mut old_stake_to = Uint128::zero();
-    let new_stake_to = STAKE
-        .update(
+        .collect::<StdResult<_>>()?;
+    for (asset_info, mut distribution) in distributions {
+        // without any distributed rewards, there are no rewards to keep
+        if distribution.shares_per_point.is_zero() {
+            continue;
+        }
+        POWER_RESCALES.save(
             deps.storage,
-            (&info.sender, bond_to),
-            |bonding_info| -> StdResult<_> {
-                let mut bonding_info = bonding_info.unwrap_or_default();
-                old_stake_to = bonding_info.total_stake();
-
-                if bond_from > bond_to {
-                    bonding_info.add_locked_tokens(
-                        env.block.time.plus_seconds(bond_from - bond_to),
-                        amount,
-                    );
-                } else {
-                    bonding_info.add_unlocked_tokens(amount);
-                };
-                Ok(bonding_info)
+            (&asset_info, distribution.generation, distribution.rescales),
+            &PowerRescale {
+                tokens_per_power_before: old_config.tokens_per_power,
+                tokens_per_power_after: tokens_per_power,
+                shares_per_point: distribution.shares_per_point,
             },
-        )?
-        .total_stake();
+        )?;
+        distribution.rescales += 1;
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+    }
 
-    update_total_stake(
+    CONFIG.save(deps.storage, &new_config)?;
+    record_admin_action(
         deps.storage,
-        &cfg,
-        bond_from,
-        old_stake_from,
-        new_stake_from,
+        &env.block,
+        &info.sender,
+        AdminAction::UpdateTokensPerPower { tokens_per_power },
     )?;
-    update_total_stake(deps.storage, &cfg, bond_to, old_stake_to, new_stake_to)?;
 
-    // update the adjustment data for all distributions
-    for ((asset_info, mut distribution), old_reward_power) in
-        distributions.into_iter().zip(old_rewards.into_iter())
-    {
-        let new_reward_power = distribution.calc_rewards_power(deps.storage, &cfg, &info.sender)?;
-        update_rewards(
-            deps.storage,
-            &asset_info,
-            &info.sender,
-            &mut distribution,
-            old_reward_power,
-            new_reward_power,
-        )?;
+    Ok(Response::new()
+        .add_attribute("action", "update_tokens_per_power")
+        .add_attribute("tokens_per_power", tokens_per_power))
+}
 
-        // save updated distribution
-        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+/// Changes the `min_bond` of the config. Only callable by the admin.
+///
+/// Whether a stake counts towards the rewards power is only decided when it changes, so the new
+/// `min_bond` applies to each stake the next time it is bonded, unbonded or rebonded. Until then,
+/// the stake keeps its rewards power (or lack thereof), which keeps the total powered stake and the
+/// points corrections consistent without going over all stakers.
+pub fn execute_update_min_bond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    min_bond: Uint128,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    // min_bond is at least 1, so 0 stake -> non-membership
+    let min_bond = std::cmp::max(min_bond, Uint128::new(1));
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    // stakes that did not change yet were compared against the original value
+    if cfg.original_min_bond.is_none() {
+        cfg.original_min_bond = Some(cfg.min_bond);
     }
+    cfg.min_bond = min_bond;
+    CONFIG.save(deps.storage, &cfg)?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::UpdateMinBond { min_bond },
+    )?;
 
     Ok(Response::new()
-        .add_attribute("action", "rebond")
-        .add_attribute("amount", amount)
-        .add_attribute("bond_from", bond_from.to_string())
-        .add_attribute("bond_to", bond_to.to_string()))
+        .add_attribute("action", "update_min_bond")
+        .add_attribute("min_bond", min_bond))
 }
 
-pub fn execute_bond(
+pub fn execute_update_max_claims_per_user(
     deps: DepsMut,
     env: Env,
-    sender_cw20_contract: Addr,
-    amount: Uint128,
-    unbonding_period: u64,
-    sender: Addr,
+    info: MessageInfo,
+    max_claims_per_user: u32,
 ) -> Result<Response, ContractError> {
-    let delegations = vec![(sender.to_string(), amount)];
-    let res = execute_mass_bond(
-        deps,
-        env,
-        sender_cw20_contract,
-        amount,
-        unbonding_period,
-        delegations,
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    CONFIG.update::<_, StdError>(deps.storage, |mut cfg| {
+        cfg.max_claims_per_user = max_claims_per_user;
+        Ok(cfg)
+    })?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::UpdateMaxClaimsPerUser {
+            max_claims_per_user,
+        },
     )?;
-    Ok(res.add_attribute("sender", sender))
+
+    Ok(Response::new()
+        .add_attribute("action", "update_max_claims_per_user")
+        .add_attribute("max_claims_per_user", max_claims_per_user.to_string()))
 }
 
-pub fn execute_mass_bond(
+/// Changes the `max_distributions` of the config. Only callable by the admin.
+///
+/// Lowering it below the number of existing distribution flows is rejected, so the limit always
+/// bounds the distributions every staking operation goes through.
+/// Flows that are no longer needed have to be closed first.
+pub fn execute_set_max_distributions(
     deps: DepsMut,
-    _env: Env,
-    sender_cw20_contract: Addr,
-    amount_sent: Uint128,
-    unbonding_period: u64,
-    delegate_to: Vec<(String, Uint128)>,
+    env: Env,
+    info: MessageInfo,
+    max: u32,
 ) -> Result<Response, ContractError> {
-    let cfg = CONFIG.load(deps.storage)?;
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    // ensure that cw20 token contract's addresses matches
-    if cfg.cw20_contract != sender_cw20_contract {
-        return Err(ContractError::Cw20AddressesNotMatch {
-            got: sender_cw20_contract.into(),
-            expected: cfg.cw20_contract.into(),
-        });
+    let distributions = DISTRIBUTION
+        .keys(deps.storage, None, None, Order::Ascending)
+        .count();
+    if distributions > max as usize {
+        return Err(ContractError::TooManyDistributions(max));
     }
 
-    if cfg
-        .unbonding_periods
-        .binary_search(&unbonding_period)
-        .is_err()
-    {
-        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
-    }
+    CONFIG.update::<_, StdError>(deps.storage, |mut cfg| {
+        cfg.max_distributions = max;
+        Ok(cfg)
+    })?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::SetMaxDistributions { max },
+    )?;
 
-    // ensure total is <= amount sent
-    let total = delegate_to.iter().map(|(_, x)| x).sum();
-    if total > amount_sent {
-        return Err(ContractError::MassDelegateTooMuch { total, amount_sent });
+    Ok(Response::new()
+        .add_attribute("action", "set_max_distributions")
+        .add_attribute("max_distributions", max.to_string()))
+}
+
+/// Returns an error if `staker` cannot get `new_claims` more claims without exceeding
+/// the `max_claims_per_user` of the config.
+/// This bounds the number of claims `execute_claim` has to go through.
+fn assert_claims_limit(
+    deps: Deps,
+    cfg: &Config,
+    staker: &Addr,
+    new_claims: usize,
+) -> Result<(), ContractError> {
+    if pending_claims(deps, staker)? + new_claims > cfg.max_claims_per_user as usize {
+        return Err(ContractError::TooManyClaims(cfg.max_claims_per_user));
     }
+    Ok(())
+}
 
-    // update this for every user
-    let mut distributions: Vec<_> = DISTRIBUTION
-        .range(deps.storage, None, None, Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
+/// Closes the distribution flow of the given asset. Only callable by the admin.
+///
+/// Closing happens in two phases. First, the rewards the reward curve already released are
+/// distributed to the stakers, the ones that are still locked are sent back to the manager and
+/// the flow stops accruing. If there is no rewards power, the released rewards have noone to go to,
+/// so the manager gets them back as well.
+/// The closed flow still counts against `max_distributions` until its stakers withdrew all rewards,
+/// then it is removed. Since rounding can leave a few units that noone can withdraw, the admin can
+/// also remove it by calling this again once the longest unbonding period has passed since closing.
+/// Rewards that were not withdrawn until then stay in the contract for the next flow of the asset.
+pub fn execute_close_distribution_flow(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
 
-    // loop over all delegates, adding to their stake
-    for (sender, amount) in delegate_to {
-        let sender = deps.api.addr_validate(&sender)?;
+    let asset = asset.validate(deps.api)?;
+    let mut distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let now = env.block.time.seconds();
 
-        // calculate rewards power before updating the stake
-        let old_rewards = calc_rewards_powers(deps.storage, &cfg, &sender, distributions.iter())?;
+    let mut resp = Response::new()
+        .add_attribute("action", "close_distribution_flow")
+        .add_attribute("asset", asset.to_string());
 
-        // add to the sender's stake
-        let mut old_stake = Uint128::zero();
-        let new_stake = STAKE
-            .update(
-                deps.storage,
-                (&sender, unbonding_period),
-                |bonding_info| -> StdResult<_> {
-                    let mut bonding_info = bonding_info.unwrap_or_default();
-                    old_stake = bonding_info.total_stake();
-                    bonding_info.add_unlocked_tokens(amount);
-                    Ok(bonding_info)
-                },
-            )?
-            .total_stake();
+    let remove = match distribution.closed_at {
+        // already closed, so this only removes the flow
+        Some(closed_at) => {
+            let claim_period = cfg
+                .unbonding_periods
+                .iter()
+                .max()
+                .copied()
+                .unwrap_or_default();
+            if !distribution.withdrawable_total.is_zero()
+                && now < closed_at.saturating_add(claim_period)
+            {
+                return Err(ContractError::PendingRewards(asset));
+            }
+            true
+        }
+        None => {
+            // a paused curve did not release anything since the pause
+            let curve = REWARD_CURVE.load(deps.storage, &asset)?;
+            let locked = Uint128::new(curve.value(distribution.paused_at.unwrap_or(now)));
+            let released =
+                undistributed_rewards(deps.as_ref(), &cfg, &asset, &env.contract.address)?
+                    .checked_sub(distribution.withdrawable_total)?
+                    .checked_sub(locked)?;
+
+            let total_rewards = distribution.total_rewards_power(deps.storage, &cfg);
+            let (distributed, reclaimed) = if total_rewards.is_zero() {
+                (Uint128::zero(), released + locked)
+            } else {
+                distribution.distribute(released.u128(), total_rewards);
+                (released, locked)
+            };
+            REWARD_CURVE.save(deps.storage, &asset, &Curve::constant(0))?;
+            distribution.closed_at = Some(now);
+
+            resp = resp
+                .add_attribute("manager", distribution.manager.as_str())
+                .add_attribute("distributed", distributed)
+                .add_attribute("reclaimed", reclaimed);
+            if !reclaimed.is_zero() {
+                resp = resp.add_message(
+                    asset
+                        .with_balance(reclaimed)
+                        .into_msg(distribution.manager.clone())?,
+                );
+            }
+            distribution.withdrawable_total.is_zero()
+        }
+    };
 
-        update_total_stake(deps.storage, &cfg, unbonding_period, old_stake, new_stake)?;
+    if remove {
+        remove_distribution(deps.storage, &asset, &distribution)?;
+    } else {
+        DISTRIBUTION.save(deps.storage, &asset, &distribution)?;
+    }
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::CloseDistributionFlow {
+            asset: asset.clone(),
+        },
+    )?;
 
-        // update the adjustment data for all distributions
-        distributions = distributions
-            .into_iter()
+    Ok(resp.add_attribute("removed", remove.to_string()))
+}
+
+/// Validates that the given rewards multipliers contain exactly one entry for every unbonding period
+/// and do not decrease with longer unbonding periods.
+fn validate_reward_multipliers(
+    config: &Config,
+    rewards: &[(UnbondingPeriod, Decimal)],
+) -> Result<(), ContractError> {
+    // validate rewards unbonding periods
+    if rewards
+        .iter()
+        .map(|(period, _)| period)
+        .ne(config.unbonding_periods.iter())
+    {
+        return Err(ContractError::InvalidRewards {});
+    }
+    // make sure rewards are monotonically increasing (equality is allowed)
+    // this assumes that `config.unbonding_periods` (and therefore also `rewards`) is sorted (checked in instantiate)
+    if rewards.windows(2).any(|w| w[0].1 > w[1].1) {
+        return Err(ContractError::InvalidRewards {});
+    }
+    Ok(())
+}
+
+/// Replaces the rewards multipliers of an existing distribution flow.
+/// Only callable by the manager of that distribution.
+///
+/// The total rewards power is always derived from the multipliers and the total stake per period,
+/// so it reflects the new multipliers right away. The points corrections of existing stakers are
+/// preserved and not recalculated, which means that rewards distributed but not yet withdrawn
+/// are also weighted by the new multipliers. The manager should therefore update the multipliers
+/// right after a distribution, when most rewards have been withdrawn.
+pub fn execute_update_reward_multipliers(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+    rewards: Vec<(UnbondingPeriod, Decimal)>,
+) -> Result<Response, ContractError> {
+    let asset = asset.validate(deps.api)?;
+    let mut distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+    if info.sender != distribution.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let config = CONFIG.load(deps.storage)?;
+    validate_reward_multipliers(&config, &rewards)?;
+
+    distribution.reward_multipliers = rewards.clone();
+    DISTRIBUTION.save(deps.storage, &asset, &distribution)?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::UpdateRewardMultipliers {
+            asset: asset.clone(),
+            rewards,
+        },
+    )?;
+
+    let total_power = distribution.total_rewards_power(deps.storage, &config);
+    Ok(Response::new()
+        .add_attribute("action", "update_reward_multipliers")
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("total_rewards_power", total_power))
+}
+
+/// Pauses or resumes the distribution flow of the given asset.
+/// Only callable by the manager of that distribution.
+///
+/// While paused, [`execute_distribute_rewards`] skips the asset, so the rewards of all stakers stay
+/// where they were at the pause. When resuming, the reward curve is shifted to the right by the
+/// paused time, so the rewards it locked at the pause are released as if the pause never happened.
+pub fn execute_set_distribution_paused(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+    paused: bool,
+) -> Result<Response, ContractError> {
+    let asset = asset.validate(deps.api)?;
+    let mut distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+    if info.sender != distribution.manager {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let now = env.block.time.seconds();
+    match (paused, distribution.paused_at) {
+        (true, None) => distribution.paused_at = Some(now),
+        (false, Some(paused_at)) => {
+            let curve = REWARD_CURVE.load(deps.storage, &asset)?;
+            REWARD_CURVE.save(deps.storage, &asset, &curve.shift(now - paused_at))?;
+            distribution.paused_at = None;
+        }
+        // already in the requested state
+        _ => {}
+    }
+    DISTRIBUTION.save(deps.storage, &asset, &distribution)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "set_distribution_paused")
+        .add_attribute("asset", asset.to_string())
+        .add_attribute("paused", paused.to_string()))
+}
+
+pub fn execute_rebond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    bond_from: u64,
+    bond_to: u64,
+    include_locked: bool,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    validate_rebond(&cfg, amount, bond_from, bond_to)?;
+
+    let distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // calculate rewards power before updating the stake
+    settle_withdraw_adjustments(deps.storage, &cfg, &info.sender, &distributions)?;
+    let old_rewards = calc_rewards_powers(deps.storage, &cfg, &info.sender, distributions.iter())?;
+
+    let mut from = STAKE
+        .may_load(deps.storage, (&info.sender, bond_from))?
+        .unwrap_or_default();
+    let mut to = STAKE
+        .may_load(deps.storage, (&info.sender, bond_to))?
+        .unwrap_or_default();
+    let (old_stake_from, old_stake_to) = (from.total_stake(), to.total_stake());
+    rebond_stake(
+        &env,
+        &mut from,
+        &mut to,
+        amount,
+        bond_from,
+        bond_to,
+        include_locked,
+    )?;
+    let (new_stake_from, new_stake_to) = (from.total_stake(), to.total_stake());
+    STAKE.save(deps.storage, (&info.sender, bond_from), &from)?;
+    STAKE.save(deps.storage, (&info.sender, bond_to), &to)?;
+
+    update_total_stake(
+        deps.storage,
+        env.block.height,
+        &cfg,
+        &info.sender,
+        bond_from,
+        old_stake_from,
+        new_stake_from,
+    )?;
+    update_total_stake(
+        deps.storage,
+        env.block.height,
+        &cfg,
+        &info.sender,
+        bond_to,
+        old_stake_to,
+        new_stake_to,
+    )?;
+
+    // update the adjustment data for all distributions
+    for ((asset_info, mut distribution), old_reward_power) in
+        distributions.into_iter().zip(old_rewards.into_iter())
+    {
+        let new_reward_power = distribution.calc_rewards_power(deps.storage, &cfg, &info.sender)?;
+        update_rewards(
+            deps.storage,
+            &asset_info,
+            &info.sender,
+            &mut distribution,
+            old_reward_power,
+            new_reward_power,
+        )?;
+
+        // save updated distribution
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "rebond")
+        .add_attribute("amount", amount)
+        .add_attribute("bond_from", bond_from.to_string())
+        .add_attribute("bond_to", bond_to.to_string()))
+}
+
+/// Returns an error if `amount` of stake cannot be rebonded from `bond_from` to `bond_to`,
+/// without looking at the stake itself.
+fn validate_rebond(
+    cfg: &Config,
+    amount: Uint128,
+    bond_from: UnbondingPeriod,
+    bond_to: UnbondingPeriod,
+) -> Result<(), ContractError> {
+    // Raise if no amount was provided
+    if amount == Uint128::zero() {
+        return Err(ContractError::NoRebondAmount {});
+    }
+    // Short out with an error if trying to rebond to itself
+    if bond_from == bond_to {
+        return Err(ContractError::SameUnbondingRebond {});
+    }
+
+    if cfg.unbonding_periods.binary_search(&bond_from).is_err() {
+        return Err(ContractError::NoUnbondingPeriodFound(bond_from));
+    }
+    if cfg.unbonding_periods.binary_search(&bond_to).is_err() {
+        return Err(ContractError::NoUnbondingPeriodFound(bond_to));
+    }
+    Ok(())
+}
+
+/// Moves `amount` of the stake in `from` (of unbonding period `bond_from`) to `to` (of `bond_to`).
+/// This does not touch the storage, so it is used both for rebonding and for simulating it.
+fn rebond_stake(
+    env: &Env,
+    from: &mut BondingInfo,
+    to: &mut BondingInfo,
+    amount: Uint128,
+    bond_from: UnbondingPeriod,
+    bond_to: UnbondingPeriod,
+    include_locked: bool,
+) -> StdResult<()> {
+    // Release the stake, also accounting for locked tokens, raising if there is not enough tokens
+    let locked_tokens = if include_locked {
+        from.release_stake_including_locked(env, amount)?
+    } else {
+        from.release_stake(env, amount)?;
+        vec![]
+    };
+    let locked = locked_tokens
+        .iter()
+        .map(|(_, locked)| *locked)
+        .sum::<Uint128>();
+
+    to.add_rebonded_tokens(env, amount - locked, locked_tokens, bond_from, bond_to);
+    Ok(())
+}
+
+/// Moves `amount` of the sender's stake in the given unbonding period to `recipient`,
+/// updating the rewards power of both in all distributions.
+pub fn execute_transfer_stake(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    unbonding_period: UnbondingPeriod,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::NoTransferAmount {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    if recipient == info.sender {
+        return Err(ContractError::TransferStakeToSelf {});
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
+    }
+
+    let distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // calculate rewards power of both parties before updating the stake
+    settle_withdraw_adjustments(deps.storage, &cfg, &info.sender, &distributions)?;
+    settle_withdraw_adjustments(deps.storage, &cfg, &recipient, &distributions)?;
+    let old_sender_rewards =
+        calc_rewards_powers(deps.storage, &cfg, &info.sender, distributions.iter())?;
+    let old_recipient_rewards =
+        calc_rewards_powers(deps.storage, &cfg, &recipient, distributions.iter())?;
+
+    let mut bonding_from = STAKE
+        .may_load(deps.storage, (&info.sender, unbonding_period))?
+        .unwrap_or_default();
+    let old_stake_from = bonding_from.total_stake();
+    let mut bonding_to = STAKE
+        .may_load(deps.storage, (&recipient, unbonding_period))?
+        .unwrap_or_default();
+    let old_stake_to = bonding_to.total_stake();
+
+    bonding_from.transfer_into(&env, &mut bonding_to, amount)?;
+    let new_stake_from = bonding_from.total_stake();
+    let new_stake_to = bonding_to.total_stake();
+
+    STAKE.save(
+        deps.storage,
+        (&info.sender, unbonding_period),
+        &bonding_from,
+    )?;
+    STAKE.save(deps.storage, (&recipient, unbonding_period), &bonding_to)?;
+
+    update_total_stake(
+        deps.storage,
+        env.block.height,
+        &cfg,
+        &info.sender,
+        unbonding_period,
+        old_stake_from,
+        new_stake_from,
+    )?;
+    update_total_stake(
+        deps.storage,
+        env.block.height,
+        &cfg,
+        &recipient,
+        unbonding_period,
+        old_stake_to,
+        new_stake_to,
+    )?;
+
+    // update the adjustment data for all distributions
+    for ((asset_info, mut distribution), (old_sender_power, old_recipient_power)) in distributions
+        .into_iter()
+        .zip(old_sender_rewards.into_iter().zip(old_recipient_rewards))
+    {
+        for (staker, old_reward_power) in [
+            (&info.sender, old_sender_power),
+            (&recipient, old_recipient_power),
+        ] {
+            let new_reward_power = distribution.calc_rewards_power(deps.storage, &cfg, staker)?;
+            update_rewards(
+                deps.storage,
+                &asset_info,
+                staker,
+                &mut distribution,
+                old_reward_power,
+                new_reward_power,
+            )?;
+        }
+
+        // save updated distribution
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "transfer_stake")
+        .add_attribute("sender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("unbonding_period", unbonding_period.to_string()))
+}
+
+/// Removes the given unbonding period, rebonding all stake in it into `migrate_to`.
+pub fn execute_remove_unbonding_period(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    period: UnbondingPeriod,
+    migrate_to: UnbondingPeriod,
+) -> Result<Response, ContractError> {
+    // only admin can remove unbonding periods
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    if period == migrate_to {
+        return Err(ContractError::SameUnbondingRebond {});
+    }
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    let period_idx = cfg
+        .unbonding_periods
+        .binary_search(&period)
+        .map_err(|_| ContractError::NoUnbondingPeriodFound(period))?;
+    if cfg.unbonding_periods.binary_search(&migrate_to).is_err() {
+        return Err(ContractError::NoUnbondingPeriodFound(migrate_to));
+    }
+
+    // collect all stakers of the removed period before modifying the stake map
+    let stakers = STAKE
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter_map(|item| match item {
+            Ok(((addr, p), _)) if p == period => Some(Ok(addr)),
+            Ok(_) => None,
+            Err(e) => Some(Err(e)),
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    for staker in &stakers {
+        // calculate rewards power before updating the stake
+        settle_withdraw_adjustments(deps.storage, &cfg, staker, &distributions)?;
+        let old_rewards = calc_rewards_powers(deps.storage, &cfg, staker, distributions.iter())?;
+
+        let mut bonding_from = STAKE.load(deps.storage, (staker, period))?;
+        let old_stake_from = bonding_from.total_stake();
+        let mut bonding_to = STAKE
+            .may_load(deps.storage, (staker, migrate_to))?
+            .unwrap_or_default();
+        let old_stake_to = bonding_to.total_stake();
+
+        bonding_from.move_into(&env, &mut bonding_to, period, migrate_to);
+        let new_stake_to = bonding_to.total_stake();
+
+        STAKE.save(deps.storage, (staker, migrate_to), &bonding_to)?;
+
+        update_total_stake(
+            deps.storage,
+            env.block.height,
+            &cfg,
+            staker,
+            period,
+            old_stake_from,
+            Uint128::zero(),
+        )?;
+        update_total_stake(
+            deps.storage,
+            env.block.height,
+            &cfg,
+            staker,
+            migrate_to,
+            old_stake_to,
+            new_stake_to,
+        )?;
+        // only remove the old stake after updating the total, which needs to know if it was powered
+        STAKE.remove(deps.storage, (staker, period));
+
+        // update the adjustment data for all distributions
+        distributions = distributions
+            .into_iter()
+            .zip(old_rewards.into_iter())
+            .map(|((asset_info, mut distribution), old_reward_power)| {
+                let new_reward_power =
+                    distribution.calc_rewards_power(deps.storage, &cfg, staker)?;
+                update_rewards(
+                    deps.storage,
+                    &asset_info,
+                    staker,
+                    &mut distribution,
+                    old_reward_power,
+                    new_reward_power,
+                )?;
+                Ok((asset_info, distribution))
+            })
+            .collect::<StdResult<Vec<_>>>()?;
+    }
+
+    // remove the period from all distributions and save them
+    for (asset_info, mut distribution) in distributions {
+        distribution
+            .reward_multipliers
+            .retain(|(unbonding_period, _)| *unbonding_period != period);
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+    }
+
+    // remove the period from the totals and the config
+    let mut totals = TOTAL_PER_PERIOD.load(deps.storage)?;
+    totals.retain(|(unbonding_period, _)| *unbonding_period != period);
+    save_total_per_period(deps.storage, env.block.height, totals)?;
+    cfg.unbonding_periods.remove(period_idx);
+    CONFIG.save(deps.storage, &cfg)?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::RemoveUnbondingPeriod { period, migrate_to },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "remove_unbonding_period")
+        .add_attribute("period", period.to_string())
+        .add_attribute("migrate_to", migrate_to.to_string())
+        .add_attribute("migrated_stakers", stakers.len().to_string()))
+}
+
+/// Adds a new unbonding period and extends all distributions with the given multipliers.
+pub fn execute_add_unbonding_period(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    period: UnbondingPeriod,
+    rewards: Vec<(AssetInfo, Decimal)>,
+) -> Result<Response, ContractError> {
+    // only admin can add unbonding periods
+    ADMIN.assert_admin(deps.as_ref(), &info.sender)?;
+
+    let mut cfg = CONFIG.load(deps.storage)?;
+    // find the position to insert the new period at, keeping everything sorted
+    let idx = match cfg.unbonding_periods.binary_search(&period) {
+        Ok(_) => return Err(ContractError::UnbondingPeriodAlreadyExists(period)),
+        Err(idx) => idx,
+    };
+
+    let rewards = rewards
+        .into_iter()
+        .map(|(asset, multiplier)| Ok((asset.validate(deps.api)?, multiplier)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    if rewards.len() != distributions.len() {
+        return Err(ContractError::InvalidRewards {});
+    }
+
+    for (asset_info, mut distribution) in distributions {
+        let multiplier = rewards
+            .iter()
+            .find(|(asset, _)| asset == &asset_info)
+            .map(|(_, multiplier)| *multiplier)
+            .ok_or(ContractError::InvalidRewards {})?;
+        // `reward_multipliers` and `unbonding_periods` are in the same order, so we can use the same index
+        distribution
+            .reward_multipliers
+            .insert(idx, (period, multiplier));
+        // make sure rewards are still monotonically increasing (equality is allowed)
+        if distribution
+            .reward_multipliers
+            .windows(2)
+            .any(|w| w[0].1 > w[1].1)
+        {
+            return Err(ContractError::InvalidRewards {});
+        }
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+    }
+
+    let mut totals = TOTAL_PER_PERIOD.load(deps.storage)?;
+    totals.insert(idx, (period, TotalStake::default()));
+    save_total_per_period(deps.storage, env.block.height, totals)?;
+    cfg.unbonding_periods.insert(idx, period);
+    CONFIG.save(deps.storage, &cfg)?;
+    record_admin_action(
+        deps.storage,
+        &env.block,
+        &info.sender,
+        AdminAction::AddUnbondingPeriod { period },
+    )?;
+
+    Ok(Response::new()
+        .add_attribute("action", "add_unbonding_period")
+        .add_attribute("period", period.to_string()))
+}
+
+pub fn execute_bond(
+    deps: DepsMut,
+    env: Env,
+    sent_token: StakedToken,
+    amount: Uint128,
+    unbonding_period: u64,
+    sender: Addr,
+) -> Result<Response, ContractError> {
+    let delegations = vec![(sender.to_string(), amount)];
+    let res = execute_mass_bond(deps, env, sent_token, amount, unbonding_period, delegations)?;
+    Ok(res.add_attribute("sender", sender))
+}
+
+/// Bonds the native tokens sent with the message for `delegate_as` or the sender.
+pub fn execute_bond_native(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    unbonding_period: u64,
+    delegate_as: Option<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let denom = match cfg.staked_token {
+        StakedToken::Native(denom) => denom,
+        StakedToken::Cw20(_) => return Err(ContractError::NativeStakingDisabled {}),
+    };
+    let amount = must_pay(&info, &denom)?;
+    let sender = match delegate_as {
+        Some(delegate_as) => deps.api.addr_validate(&delegate_as)?,
+        None => info.sender,
+    };
+
+    execute_bond(
+        deps,
+        env,
+        StakedToken::Native(denom),
+        amount,
+        unbonding_period,
+        sender,
+    )
+}
+
+/// Withdraws the sender's rewards in the assets of the given pair, provides each of them
+/// as single-sided liquidity to the pair and bonds the received liquidity tokens.
+pub fn execute_compound_rewards(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    unbonding_period: u64,
+    pair: String,
+    max_spread: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
+    }
+
+    let pair = deps.api.addr_validate(&pair)?;
+    let pair_info: PairInfo = deps
+        .querier
+        .query_wasm_smart(&pair, &PairQueryMsg::Pair {})?;
+    if StakedToken::Cw20(pair_info.liquidity_token.clone()) != cfg.staked_token {
+        return Err(ContractError::InvalidCompoundPair {});
+    }
+
+    let mut resp = Response::new()
+        .add_attribute("action", "compound_rewards")
+        .add_attribute("owner", info.sender.as_str())
+        .add_attribute("pair", pair.as_str());
+
+    // there is no route for rewards that are not part of the pair, so they stay withdrawable
+    for reward in query_withdrawable_rewards(deps.as_ref(), info.sender.to_string())?.rewards {
+        if !reward.amount.is_zero() && !pair_info.asset_infos.contains(&reward.info) {
+            resp = resp.add_attribute(format!("skipped_{}", reward.info), reward.amount);
+        }
+    }
+
+    let rewards = take_withdrawable_rewards(deps.branch(), &info.sender, |asset_info| {
+        pair_info.asset_infos.contains(asset_info)
+    })?;
+    if rewards.is_empty() {
+        return Err(ContractError::NothingToCompound {});
+    }
+
+    let prev_balance = cfg
+        .staked_token
+        .asset_info()
+        .query_balance(&deps.querier, &env.contract.address)?;
+
+    for reward in rewards {
+        resp = resp.add_attribute(format!("reward_{}", reward.info), reward.amount);
+
+        let provide_msg = to_binary(&PairExecuteMsg::ProvideLiquidity {
+            assets: vec![reward.clone().into()],
+            slippage_tolerance: max_spread,
+            receiver: None,
+            min_lp_amount: None,
+        })?;
+        resp = match &reward.info {
+            AssetInfoValidated::Native(denom) => resp.add_message(WasmMsg::Execute {
+                contract_addr: pair.to_string(),
+                msg: provide_msg,
+                funds: vec![coin(reward.amount.u128(), denom)],
+            }),
+            AssetInfoValidated::Token(token) => resp
+                .add_message(WasmMsg::Execute {
+                    contract_addr: token.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                        spender: pair.to_string(),
+                        amount: reward.amount,
+                        expires: None,
+                    })?,
+                    funds: vec![],
+                })
+                .add_message(WasmMsg::Execute {
+                    contract_addr: pair.to_string(),
+                    msg: provide_msg,
+                    funds: vec![],
+                }),
+            AssetInfoValidated::Cw1155 { .. } => {
+                return Err(StdError::generic_err("Cw1155 rewards cannot be compounded").into())
+            }
+        };
+    }
+
+    // bond the liquidity tokens after all of them were minted to this contract
+    Ok(resp.add_message(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::BondCompounded {
+            owner: info.sender.to_string(),
+            unbonding_period,
+            prev_balance,
+        })?,
+        funds: vec![],
+    }))
+}
+
+/// Bonds all staked tokens this contract received since `prev_balance` for the given owner.
+pub fn execute_bond_compounded(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    unbonding_period: u64,
+    prev_balance: Uint128,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let balance = cfg
+        .staked_token
+        .asset_info()
+        .query_balance(&deps.querier, &env.contract.address)?;
+    let amount = balance.checked_sub(prev_balance)?;
+    let owner = deps.api.addr_validate(&owner)?;
+
+    execute_bond(deps, env, cfg.staked_token, amount, unbonding_period, owner)
+}
+
+/// Withdraws the sender's rewards in the staked token and bonds them into `unbonding_period`.
+/// The rewards are already held by this contract, so they are bonded without being sent anywhere.
+pub fn execute_withdraw_rewards_to_stake(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    unbonding_period: u64,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
+    }
+
+    let staked_asset = cfg.staked_token.asset_info();
+    let amount: Uint128 = take_withdrawable_rewards(deps.branch(), &info.sender, |asset_info| {
+        asset_info == &staked_asset
+    })?
+    .into_iter()
+    .map(|reward| reward.amount)
+    .sum();
+
+    let resp = if amount.is_zero() {
+        Response::new().add_attribute("result", "no rewards in the staked token")
+    } else {
+        execute_bond(
+            deps,
+            env,
+            cfg.staked_token,
+            amount,
+            unbonding_period,
+            info.sender.clone(),
+        )?
+    };
+
+    Ok(resp
+        .add_attribute("action", "withdraw_rewards_to_stake")
+        .add_attribute("owner", info.sender))
+}
+
+pub fn execute_mass_bond(
+    deps: DepsMut,
+    env: Env,
+    sent_token: StakedToken,
+    amount_sent: Uint128,
+    unbonding_period: u64,
+    delegate_to: Vec<(String, Uint128)>,
+) -> Result<Response, ContractError> {
+    let delegations = delegate_to
+        .into_iter()
+        .map(|(addr, amount)| (addr, amount, unbonding_period))
+        .collect();
+    execute_mass_bond_multi(deps, env, sent_token, amount_sent, delegations)
+}
+
+/// Bonds the given amounts for each of the delegates, each with its own unbonding period.
+pub fn execute_mass_bond_multi(
+    deps: DepsMut,
+    env: Env,
+    sent_token: StakedToken,
+    amount_sent: Uint128,
+    delegations: Vec<(String, Uint128, UnbondingPeriod)>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    // ensure that the sent token is the staked token
+    if cfg.staked_token != sent_token {
+        return Err(ContractError::Cw20AddressesNotMatch {
+            got: sent_token.to_string(),
+            expected: cfg.staked_token.to_string(),
+        });
+    }
+
+    if let Some(&(_, _, unbonding_period)) = delegations
+        .iter()
+        .find(|(_, _, period)| cfg.unbonding_periods.binary_search(period).is_err())
+    {
+        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
+    }
+
+    // ensure total is <= amount sent
+    let total = delegations.iter().map(|(_, x, _)| x).sum();
+    if total > amount_sent {
+        return Err(ContractError::MassDelegateTooMuch { total, amount_sent });
+    }
+
+    // update this for every user
+    let mut distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    // loop over all delegates, adding to their stake
+    for (sender, amount, unbonding_period) in delegations {
+        let sender = deps.api.addr_validate(&sender)?;
+
+        // calculate rewards power before updating the stake
+        settle_withdraw_adjustments(deps.storage, &cfg, &sender, &distributions)?;
+        let old_rewards = calc_rewards_powers(deps.storage, &cfg, &sender, distributions.iter())?;
+
+        // add to the sender's stake
+        let mut old_stake = Uint128::zero();
+        let new_stake = STAKE
+            .update(
+                deps.storage,
+                (&sender, unbonding_period),
+                |bonding_info| -> StdResult<_> {
+                    let mut bonding_info = bonding_info.unwrap_or_default();
+                    old_stake = bonding_info.total_stake();
+                    bonding_info.add_unlocked_tokens(amount);
+                    Ok(bonding_info)
+                },
+            )?
+            .total_stake();
+
+        update_total_stake(
+            deps.storage,
+            env.block.height,
+            &cfg,
+            &sender,
+            unbonding_period,
+            old_stake,
+            new_stake,
+        )?;
+
+        // update the adjustment data for all distributions
+        distributions = distributions
+            .into_iter()
             .zip(old_rewards.into_iter())
             .map(|((asset_info, mut distribution), old_reward_power)| {
                 let new_reward_power =
@@ -462,11 +1519,9 @@ pub fn execute_mass_bond(
     }
 
     // update total after all individuals are handled
-    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
-        Ok(TokenInfo {
-            staked: token_info.staked + amount_sent,
-            unbonding: token_info.unbonding,
-        })
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked + amount_sent,
+        unbonding: token_info.unbonding,
     })?;
 
     Ok(Response::new()
@@ -474,11 +1529,14 @@ pub fn execute_mass_bond(
         .add_attribute("amount", amount_sent))
 }
 
-/// Updates the total stake for the given unbonding period
-/// Make sure to always pass in the full old and new stake of one staker for the given unbonding period
+/// Updates the total stake for the given unbonding period and the staker's snapshot at `height`.
+/// Make sure to always pass in the full old and new stake of one staker for the given unbonding period,
+/// after the new stake was saved.
 fn update_total_stake(
     storage: &mut dyn Storage,
+    height: u64,
     cfg: &Config,
+    staker: &Addr,
     unbonding_period: UnbondingPeriod,
     old_stake: Uint128,
     new_stake: Uint128,
@@ -498,9 +1556,14 @@ fn update_total_stake(
     };
 
     // Update the total of all stakes above min_bond.
-    // Some variables and consts for readability
-    let previously_above_min_bond = old_stake >= cfg.min_bond;
-    let now_above_min_bond = new_stake >= cfg.min_bond;
+    // Whether the old stake was counted is remembered by the staker's bonding info, because
+    // `min_bond` might have changed since then.
+    let mut bonding_info = STAKE
+        .may_load(storage, (staker, unbonding_period))?
+        .unwrap_or_default();
+    let (previously_above_min_bond, now_above_min_bond) =
+        bonding_info.update_powered(cfg, old_stake, new_stake);
+    STAKE.save(storage, (staker, unbonding_period), &bonding_info)?;
     // Case distinction:
     match (previously_above_min_bond, now_above_min_bond) {
         (false, false) => {} // rewards power does not change, so do nothing
@@ -523,56 +1586,315 @@ fn update_total_stake(
     }
 
     // save updated total
-    TOTAL_PER_PERIOD.save(storage, &totals)?;
+    save_total_per_period(storage, height, totals)?;
+
+    // update the staker's total over all unbonding periods for historical queries
+    let mut old_staker_total = Uint128::zero();
+    let new_staker_total =
+        STAKE_SNAPSHOTS.update(storage, staker, height, |stake| -> StdResult<_> {
+            let stake = stake.unwrap_or_default();
+            old_staker_total = stake;
+            Ok(if old_stake <= new_stake {
+                stake.checked_add(new_stake - old_stake)?
+            } else {
+                stake.checked_sub(old_stake - new_stake)?
+            })
+        })?;
+
+    // a position opens or closes when the stake in this period becomes non-zero or zero,
+    // and the same goes for stakers and their total over all periods
+    let mut metrics = STAKING_METRICS.may_load(storage)?.unwrap_or_default();
+    match (old_stake.is_zero(), new_stake.is_zero()) {
+        (true, false) => metrics.positions += 1,
+        (false, true) => metrics.positions -= 1,
+        _ => {}
+    }
+    match (old_staker_total.is_zero(), new_staker_total.is_zero()) {
+        (true, false) => metrics.stakers += 1,
+        (false, true) => metrics.stakers -= 1,
+        _ => {}
+    }
+    STAKING_METRICS.save(storage, &metrics)?;
 
     Ok(())
 }
 
-pub fn execute_receive_delegation(
+pub fn execute_receive_delegation(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the address of the cw20 contract (that re-sent this message).
+    // wrapper.sender is the address of the user that requested the cw20 contract to send this.
+    // This cannot be fully trusted (the cw20 contract can fake it), so only use it for actions
+    // in the address's favor (like paying/bonding tokens, not withdrawls)
+
+    let msg: ReceiveDelegationMsg = from_slice(&wrapper.msg)?;
+    let api = deps.api;
+    match msg {
+        ReceiveDelegationMsg::Delegate {
+            unbonding_period,
+            delegate_as,
+        } => execute_bond(
+            deps,
+            env,
+            StakedToken::Cw20(info.sender),
+            wrapper.amount,
+            unbonding_period,
+            api.addr_validate(&delegate_as.unwrap_or(wrapper.sender))?,
+        ),
+        ReceiveDelegationMsg::MassDelegate {
+            unbonding_period,
+            delegate_to,
+        } => execute_mass_bond(
+            deps,
+            env,
+            StakedToken::Cw20(info.sender),
+            wrapper.amount,
+            unbonding_period,
+            delegate_to,
+        ),
+        ReceiveDelegationMsg::MassDelegateMulti { delegations } => execute_mass_bond_multi(
+            deps,
+            env,
+            StakedToken::Cw20(info.sender),
+            wrapper.amount,
+            delegations,
+        ),
+        ReceiveDelegationMsg::Fund { curve } => {
+            let validated_asset = AssetInfo::Token(info.sender.to_string()).validate(deps.api)?;
+            let attributes =
+                update_reward_config(&env, deps, validated_asset, wrapper.amount, curve)?;
+            Ok(Response::new()
+                .add_attribute("action", "fund_distribution")
+                .add_attributes(attributes))
+        }
+    }
+}
+
+pub fn execute_unbond(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+    unbonding_period: u64,
+    claim_recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+    let claim_recipient = addr_opt_validate(deps.api, &claim_recipient)?
+        .filter(|recipient| *recipient != info.sender);
+    assert_claims_limit(deps.as_ref(), &cfg, &info.sender, 1)?;
+
+    reduce_stake(
+        deps.storage,
+        &env,
+        &cfg,
+        &info.sender,
+        amount,
+        unbonding_period,
+    )?;
+
+    // update total
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked.saturating_sub(amount),
+        unbonding: token_info.unbonding + amount,
+    })?;
+
+    // provide them a claim, paid out to the claim recipient if there is one
+    let release_at = Expiration::AtTime(env.block.time.plus_seconds(unbonding_period));
+    let mut response = Response::new()
+        .add_attribute("action", "unbond")
+        .add_attribute("amount", amount);
+    match claim_recipient {
+        Some(recipient) => {
+            create_recipient_claim(deps.storage, &info.sender, &recipient, amount, release_at)?;
+            response = response.add_attribute("claim_recipient", recipient);
+        }
+        None => CLAIMS.create_claim(deps.storage, &info.sender, amount, release_at)?,
+    }
+
+    Ok(response.add_attribute("sender", info.sender))
+}
+
+/// Unbonds all unlocked stake of the sender in every unbonding period, creating a claim for each of them.
+/// Locked tokens cannot be unbonded yet, so they are skipped and reported in the `locked` attribute.
+pub fn execute_unbond_all(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let mut total = Uint128::zero();
+    let mut locked = Uint128::zero();
+    let mut attributes = vec![];
+    for &unbonding_period in &cfg.unbonding_periods {
+        let bonding_info = STAKE
+            .may_load(deps.storage, (&info.sender, unbonding_period))?
+            .unwrap_or_default();
+        locked += bonding_info.total_locked(&env);
+        let amount = bonding_info.total_unlocked(&env);
+        if amount.is_zero() {
+            continue;
+        }
+
+        reduce_stake(
+            deps.storage,
+            &env,
+            &cfg,
+            &info.sender,
+            amount,
+            unbonding_period,
+        )?;
+        assert_claims_limit(deps.as_ref(), &cfg, &info.sender, 1)?;
+        CLAIMS.create_claim(
+            deps.storage,
+            &info.sender,
+            amount,
+            Expiration::AtTime(env.block.time.plus_seconds(unbonding_period)),
+        )?;
+
+        total += amount;
+        attributes.push(attr(format!("unbonded_{unbonding_period}"), amount));
+    }
+
+    if total.is_zero() {
+        return Err(ContractError::NothingToUnbond {});
+    }
+
+    // update total
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked.saturating_sub(total),
+        unbonding: token_info.unbonding + total,
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "unbond_all")
+        .add_attribute("amount", total)
+        .add_attribute("locked", locked)
+        .add_attributes(attributes)
+        .add_attribute("sender", info.sender))
+}
+
+/// Unbonds all unlocked stake of the sender in the given unbonding period, creating a claim for it.
+/// Unlike [`execute_unbond`], this does not fail if the reward calculations of a distribution fail.
+/// The rewards of the sender in such a distribution are forfeited and distributed again where they
+/// can still be calculated, see [`forfeit_rewards`]. All other distributions are updated as usual.
+/// Stake that is still locked after a rebond is skipped and can be withdrawn once it unlocks.
+pub fn execute_emergency_withdraw(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    wrapper: Cw20ReceiveMsg,
+    unbonding_period: UnbondingPeriod,
 ) -> Result<Response, ContractError> {
-    // info.sender is the address of the cw20 contract (that re-sent this message).
-    // wrapper.sender is the address of the user that requested the cw20 contract to send this.
-    // This cannot be fully trusted (the cw20 contract can fake it), so only use it for actions
-    // in the address's favor (like paying/bonding tokens, not withdrawls)
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(ContractError::NoUnbondingPeriodFound(unbonding_period));
+    }
+    assert_claims_limit(deps.as_ref(), &cfg, &info.sender, 1)?;
 
-    let msg: ReceiveDelegationMsg = from_slice(&wrapper.msg)?;
-    let api = deps.api;
-    match msg {
-        ReceiveDelegationMsg::Delegate {
-            unbonding_period,
-            delegate_as,
-        } => execute_bond(
-            deps,
-            env,
-            info.sender,
-            wrapper.amount,
-            unbonding_period,
-            api.addr_validate(&delegate_as.unwrap_or(wrapper.sender))?,
-        ),
-        ReceiveDelegationMsg::MassDelegate {
-            unbonding_period,
-            delegate_to,
-        } => execute_mass_bond(
-            deps,
-            env,
-            info.sender,
-            wrapper.amount,
-            unbonding_period,
-            delegate_to,
-        ),
-        ReceiveDelegationMsg::Fund { curve } => {
-            let validated_asset = AssetInfo::Token(info.sender.to_string()).validate(deps.api)?;
-            update_reward_config(&env, deps.storage, validated_asset, wrapper.amount, curve)?;
-            Ok(Response::default())
+    let distributions: Vec<_> = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    // settle each distribution on its own before updating the stake, so one failing does not stop the others
+    let settled: Vec<_> = distributions
+        .iter()
+        .map(|distribution| {
+            settle_withdraw_adjustments(
+                deps.storage,
+                &cfg,
+                &info.sender,
+                std::slice::from_ref(distribution),
+            )
+            .is_ok()
+        })
+        .collect();
+    let old_rewards = calc_rewards_powers(deps.storage, &cfg, &info.sender, distributions.iter())?;
+
+    let mut bonding_info = STAKE
+        .may_load(deps.storage, (&info.sender, unbonding_period))?
+        .unwrap_or_default();
+    let old_stake = bonding_info.total_stake();
+    let locked = bonding_info.total_locked(&env);
+    let amount = bonding_info.total_unlocked(&env);
+    if amount.is_zero() {
+        return Err(ContractError::NothingToUnbond {});
+    }
+    bonding_info.release_stake(&env, amount)?;
+    let new_stake = bonding_info.total_stake();
+    STAKE.save(
+        deps.storage,
+        (&info.sender, unbonding_period),
+        &bonding_info,
+    )?;
+
+    update_total_stake(
+        deps.storage,
+        env.block.height,
+        &cfg,
+        &info.sender,
+        unbonding_period,
+        old_stake,
+        new_stake,
+    )?;
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked.saturating_sub(amount),
+        unbonding: token_info.unbonding + amount,
+    })?;
+
+    let mut response = Response::new()
+        .add_attribute("action", "emergency_withdraw")
+        .add_attribute("amount", amount)
+        .add_attribute("locked", locked);
+    for (((asset_info, mut distribution), old_reward_power), settled) in distributions
+        .into_iter()
+        .zip(old_rewards.into_iter())
+        .zip(settled)
+    {
+        let new_reward_power = distribution.calc_rewards_power(deps.storage, &cfg, &info.sender)?;
+        let updated = settled
+            && update_rewards(
+                deps.storage,
+                &asset_info,
+                &info.sender,
+                &mut distribution,
+                old_reward_power,
+                new_reward_power,
+            )
+            .is_ok();
+        if !updated {
+            let forfeited = forfeit_rewards(
+                deps.storage,
+                &info.sender,
+                &asset_info,
+                &mut distribution,
+                old_reward_power,
+                new_reward_power,
+            )?;
+            response = response.add_attribute(format!("forfeited_{asset_info}"), forfeited);
         }
+        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
     }
+
+    CLAIMS.create_claim(
+        deps.storage,
+        &info.sender,
+        amount,
+        Expiration::AtTime(env.block.time.plus_seconds(unbonding_period)),
+    )?;
+
+    Ok(response.add_attribute("sender", info.sender))
 }
 
-pub fn execute_unbond(
+/// Unbonds the given amount of tokens immediately, deducting the configured penalty.
+/// The penalty stays in the contract to be distributed as rewards by the distribution flow
+/// of the staked token, so a penalty is rejected if there is no open one.
+pub fn execute_instant_unbond(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
@@ -580,7 +1902,64 @@ pub fn execute_unbond(
     unbonding_period: u64,
 ) -> Result<Response, ContractError> {
     let cfg = CONFIG.load(deps.storage)?;
+    let penalty = cfg
+        .instant_unbond_penalty
+        .ok_or(ContractError::InstantUnbondDisabled {})?;
+
+    reduce_stake(
+        deps.storage,
+        &env,
+        &cfg,
+        &info.sender,
+        amount,
+        unbonding_period,
+    )?;
+
+    // the tokens leave the stake immediately, without going through unbonding
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked.saturating_sub(amount),
+        unbonding: token_info.unbonding,
+    })?;
+
+    // Since the penalty is no longer part of `TOTAL_STAKED`, it is picked up as undistributed rewards
+    // by the distribution flow of the staked token.
+    let staked_asset = cfg.staked_token.asset_info();
+    let penalty_amount = amount * penalty;
+    if !penalty_amount.is_zero()
+        && !matches!(
+            DISTRIBUTION.may_load(deps.storage, &staked_asset)?,
+            Some(distribution) if distribution.closed_at.is_none()
+        )
+    {
+        return Err(ContractError::NoPenaltyDistribution {});
+    }
+    let release = amount - penalty_amount;
+
+    let mut resp = Response::new()
+        .add_attribute("action", "instant_unbond")
+        .add_attribute("amount", amount)
+        .add_attribute("penalty", penalty_amount)
+        .add_attribute("released", release)
+        .add_attribute("sender", info.sender.as_str());
 
+    if !release.is_zero() {
+        resp = resp.add_message(staked_asset.with_balance(release).into_msg(&info.sender)?);
+    }
+
+    Ok(resp)
+}
+
+/// Reduces the stake of `staker` in the given unbonding period by `amount`, updating the
+/// rewards power in all distributions accordingly.
+/// This does not touch [`TOTAL_STAKED`], since that depends on where the tokens go afterwards.
+fn reduce_stake(
+    storage: &mut dyn Storage,
+    env: &Env,
+    cfg: &Config,
+    staker: &Addr,
+    amount: Uint128,
+    unbonding_period: UnbondingPeriod,
+) -> Result<(), ContractError> {
     if cfg
         .unbonding_periods
         .binary_search(&unbonding_period)
@@ -590,65 +1969,56 @@ pub fn execute_unbond(
     }
 
     let distributions: Vec<_> = DISTRIBUTION
-        .range(deps.storage, None, None, Order::Ascending)
+        .range(storage, None, None, Order::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
     // calculate rewards power before updating the stake
-    let old_rewards = calc_rewards_powers(deps.storage, &cfg, &info.sender, distributions.iter())?;
+    settle_withdraw_adjustments(storage, cfg, staker, &distributions)?;
+    let old_rewards = calc_rewards_powers(storage, cfg, staker, distributions.iter())?;
 
-    // reduce the sender's stake - aborting if insufficient
+    // reduce the staker's stake - aborting if insufficient
     let mut old_stake = Uint128::zero();
     let new_stake = STAKE
         .update(
-            deps.storage,
-            (&info.sender, unbonding_period),
+            storage,
+            (staker, unbonding_period),
             |bonding_info| -> StdResult<_> {
                 let mut bonding_info = bonding_info.unwrap_or_default();
                 old_stake = bonding_info.total_stake();
-                bonding_info.release_stake(&env, amount)?;
+                bonding_info.release_stake(env, amount)?;
                 Ok(bonding_info)
             },
         )?
         .total_stake();
 
-    update_total_stake(deps.storage, &cfg, unbonding_period, old_stake, new_stake)?;
+    update_total_stake(
+        storage,
+        env.block.height,
+        cfg,
+        staker,
+        unbonding_period,
+        old_stake,
+        new_stake,
+    )?;
 
     // update the adjustment data for all distributions
     for ((asset_info, mut distribution), old_reward_power) in
         distributions.into_iter().zip(old_rewards.into_iter())
     {
-        let new_reward_power = distribution.calc_rewards_power(deps.storage, &cfg, &info.sender)?;
+        let new_reward_power = distribution.calc_rewards_power(storage, cfg, staker)?;
         update_rewards(
-            deps.storage,
+            storage,
             &asset_info,
-            &info.sender,
+            staker,
             &mut distribution,
             old_reward_power,
             new_reward_power,
         )?;
 
         // save updated distribution
-        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+        DISTRIBUTION.save(storage, &asset_info, &distribution)?;
     }
-    // update total
-    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
-        Ok(TokenInfo {
-            staked: token_info.staked.saturating_sub(amount),
-            unbonding: token_info.unbonding + amount,
-        })
-    })?;
-
-    // provide them a claim
-    CLAIMS.create_claim(
-        deps.storage,
-        &info.sender,
-        amount,
-        Expiration::AtTime(env.block.time.plus_seconds(unbonding_period)),
-    )?;
 
-    Ok(Response::new()
-        .add_attribute("action", "unbond")
-        .add_attribute("amount", amount)
-        .add_attribute("sender", info.sender))
+    Ok(())
 }
 
 /// Calculates rewards power of the user for all given distributions (for all unbonding periods).
@@ -677,16 +2047,19 @@ fn update_rewards(
     distribution: &mut Distribution,
     old_reward_power: Uint128,
     new_reward_power: Uint128,
-) -> StdResult<()> {
+) -> Result<(), ContractError> {
     // short-circuit if no change
     if old_reward_power == new_reward_power {
         return Ok(());
     }
 
     // update their share of the distribution
-    let ppw = distribution.shares_per_point.u128();
-    let diff = new_reward_power.u128() as i128 - old_reward_power.u128() as i128;
-    apply_points_correction(storage, sender, asset_info, ppw, diff)?;
+    let to_i128 = |power: Uint128| {
+        i128::try_from(power.u128()).map_err(|_| ContractError::PointsCorrectionOverflow {})
+    };
+    // both powers are non-negative, so the subtraction cannot overflow
+    let diff = to_i128(new_reward_power)? - to_i128(old_reward_power)?;
+    apply_points_correction(storage, sender, asset_info, distribution, diff)?;
 
     Ok(())
 }
@@ -695,36 +2068,67 @@ pub fn execute_claim(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
+    up_to_amount: Option<Uint128>,
 ) -> Result<Response, ContractError> {
-    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, None)?;
+    // claim_tokens only returns the total, so count the released claims by comparing before and after
+    let claims_before = CLAIMS
+        .query_claims(deps.as_ref(), &info.sender)?
+        .claims
+        .len();
+    let release = CLAIMS.claim_tokens(deps.storage, &info.sender, &env.block, up_to_amount)?;
+    let own_claims_released = claims_before
+        - CLAIMS
+            .query_claims(deps.as_ref(), &info.sender)?
+            .claims
+            .len();
+
+    // claims that were unbonded with a different claim recipient are paid out to that recipient
+    let mut payouts = vec![];
+    if !release.is_zero() {
+        payouts.push((info.sender.clone(), release));
+    }
+    let (recipient_releases, recipient_claims_released) = claim_recipient_tokens(
+        deps.storage,
+        &info.sender,
+        &env.block,
+        up_to_amount.map(|cap| cap - release),
+    )?;
+    payouts.extend(recipient_releases);
+    let claims_released = own_claims_released + recipient_claims_released;
+
+    let release: Uint128 = payouts.iter().map(|(_, amount)| amount).sum();
     if release.is_zero() {
         return Err(ContractError::NothingToClaim {});
     }
 
     let config = CONFIG.load(deps.storage)?;
-    let amount_str = coin_to_string(release, config.cw20_contract.as_str());
-    let undelegate = Cw20ExecuteMsg::Transfer {
-        recipient: info.sender.to_string(),
-        amount: release,
-    };
-    let undelegate_msg = SubMsg::new(WasmMsg::Execute {
-        contract_addr: config.cw20_contract.to_string(),
-        msg: to_binary(&undelegate)?,
-        funds: vec![],
-    });
+    let amount_str = coin_to_string(release, &config.staked_token.to_string());
 
-    TOTAL_STAKED.update::<_, StdError>(deps.storage, |token_info| {
-        Ok(TokenInfo {
-            staked: token_info.staked,
-            unbonding: token_info.unbonding.saturating_sub(release),
-        })
+    update_total_staked(deps.storage, env.block.height, |token_info| TokenInfo {
+        staked: token_info.staked,
+        unbonding: token_info.unbonding.saturating_sub(release),
     })?;
 
-    Ok(Response::new()
-        .add_submessage(undelegate_msg)
+    let mut response = Response::new()
         .add_attribute("action", "claim")
         .add_attribute("tokens", amount_str)
-        .add_attribute("sender", info.sender))
+        .add_attribute("released", release)
+        .add_attribute("claims_released", claims_released.to_string());
+    for (recipient, amount) in payouts {
+        // this is a cw20 transfer or a bank send, depending on the staked token
+        let undelegate_msg = SubMsg::new(
+            config
+                .staked_token
+                .asset_info()
+                .with_balance(amount)
+                .into_msg(&recipient)?,
+        );
+        response = response
+            .add_submessage(undelegate_msg)
+            .add_attribute("recipient", recipient.as_str());
+    }
+
+    Ok(response.add_attribute("sender", info.sender))
 }
 
 #[inline]
@@ -735,31 +2139,88 @@ fn coin_to_string(amount: Uint128, address: &str) -> String {
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
-        QueryMsg::Claims { address } => {
-            to_binary(&CLAIMS.query_claims(deps, &deps.api.addr_validate(&address)?)?)
-        }
+        QueryMsg::Claims { address } => to_binary(&ClaimsResponse {
+            claims: all_claims(deps, &deps.api.addr_validate(&address)?)?,
+        }),
+        QueryMsg::ClaimsPaginated {
+            address,
+            start_after,
+            limit,
+        } => to_binary(&ClaimsResponse {
+            claims: read_claims(deps, &deps.api.addr_validate(&address)?, start_after, limit)?,
+        }),
         QueryMsg::Staked {
             address,
             unbonding_period,
         } => to_binary(&query_staked(deps, &env, address, unbonding_period)?),
+        QueryMsg::LockSchedule {
+            address,
+            unbonding_period,
+        } => to_binary(&query_lock_schedule(deps, &env, address, unbonding_period)?),
         QueryMsg::AnnualizedRewards {} => to_binary(&query_annualized_rewards(deps, env)?),
+        QueryMsg::AnnualizedRewardsForPeriod { unbonding_period } => to_binary(
+            &query_annualized_rewards_for_period(deps, env, unbonding_period)?,
+        ),
         QueryMsg::BondingInfo {} => to_binary(&query_bonding_info(deps)?),
+        QueryMsg::StakedAt { address, height } => {
+            to_binary(&query_staked_at(deps, address, height)?)
+        }
         QueryMsg::AllStaked { address } => to_binary(&query_all_staked(deps, env, address)?),
         QueryMsg::TotalStaked {} => to_binary(&query_total_staked(deps)?),
+        QueryMsg::TotalStakedAt { height } => to_binary(&query_total_staked_at(deps, height)?),
         QueryMsg::TotalUnbonding {} => to_binary(&query_total_unbonding(deps)?),
+        QueryMsg::StakingMetrics {} => to_binary(&query_staking_metrics(deps)?),
         QueryMsg::Admin {} => to_binary(&ADMIN.query_admin(deps)?),
         QueryMsg::TotalRewardsPower {} => to_binary(&query_total_rewards(deps)?),
+        QueryMsg::TotalRewardsPowerPerPeriod {} => {
+            to_binary(&query_total_rewards_per_period(deps)?)
+        }
         QueryMsg::RewardsPower { address } => to_binary(&query_rewards(deps, address)?),
+        QueryMsg::SimulateRebond {
+            address,
+            amount,
+            bond_from,
+            bond_to,
+            include_locked,
+        } => to_binary(&query_simulate_rebond(
+            deps,
+            env,
+            address,
+            amount,
+            bond_from,
+            bond_to,
+            include_locked,
+        )?),
         QueryMsg::WithdrawableRewards { owner } => {
             to_binary(&query_withdrawable_rewards(deps, owner)?)
         }
+        QueryMsg::WithdrawableReward { owner, asset } => {
+            to_binary(&query_withdrawable_reward(deps, owner, asset)?)
+        }
+        QueryMsg::ProjectedRewards {
+            owner,
+            horizon_seconds,
+        } => to_binary(&query_projected_rewards(deps, env, owner, horizon_seconds)?),
         QueryMsg::DistributedRewards {} => to_binary(&query_distributed_rewards(deps)?),
         QueryMsg::UndistributedRewards {} => to_binary(&query_undistributed_rewards(deps, env)?),
+        QueryMsg::SimulateDistribution { asset, amount } => {
+            to_binary(&query_simulate_distribution(deps, asset, amount)?)
+        }
         QueryMsg::Delegated { owner } => to_binary(&query_delegated(deps, owner)?),
+        QueryMsg::DelegatedTo {
+            delegate,
+            start_after,
+            limit,
+        } => to_binary(&query_delegated_to(deps, delegate, start_after, limit)?),
         QueryMsg::DistributionData {} => to_binary(&query_distribution_data(deps)?),
         QueryMsg::WithdrawAdjustmentData { addr, asset } => {
             to_binary(&query_withdraw_adjustment_data(deps, addr, asset)?)
         }
+        QueryMsg::RewardCurve { asset } => to_binary(&query_reward_curve(deps, asset)?),
+        QueryMsg::RewardCurves {} => to_binary(&query_reward_curves(deps)?),
+        QueryMsg::AdminActions { start_after, limit } => {
+            to_binary(&query_admin_actions(deps, start_after, limit)?)
+        }
     }
 }
 
@@ -769,46 +2230,117 @@ fn query_annualized_rewards(deps: Deps, env: Env) -> StdResult<AnnualizedRewards
         .collect::<StdResult<Vec<_>>>()?;
     let config = CONFIG.load(deps.storage)?;
 
-    let mut aprs = Vec::with_capacity(config.unbonding_periods.len());
-
-    for &unbonding_period in &config.unbonding_periods {
-        let mut rewards = Vec::with_capacity(distributions.len());
-        for (asset_info, dist) in &distributions {
+    let aprs = config
+        .unbonding_periods
+        .iter()
+        .map(|&unbonding_period| {
             let total_stake = load_total_of_period(deps.storage, unbonding_period)
-                .unwrap()
+                .map_err(|err| StdError::generic_err(err.to_string()))?
                 .powered_stake;
-            let total_power = dist.total_rewards_power(deps.storage, &config);
-
-            if total_stake.is_zero() || total_power.is_zero() {
-                rewards.push(AnnualizedReward {
-                    info: asset_info.clone(),
-                    amount: None,
-                });
-                continue;
-            }
-
-            let power_of_period = dist
-                .total_rewards_power_of_period(deps.storage, &config, unbonding_period)
-                .unwrap();
-
-            let reward_curve = REWARD_CURVE.load(deps.storage, asset_info)?;
+            let rewards = annualized_rewards_of_period(
+                deps,
+                &env,
+                &config,
+                &distributions,
+                unbonding_period,
+                total_stake,
+            )?;
+            Ok((unbonding_period, rewards))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(AnnualizedRewardsResponse { rewards: aprs })
+}
 
-            let rewards_per_year = (reward_curve.value(env.block.time.seconds())
-                + reward_curve.value(env.block.time.seconds() + SECONDS_PER_YEAR))
-                * Uint128::from(SECONDS_PER_YEAR).checked_div(Uint128::from(100u128))?;
+fn query_annualized_rewards_for_period(
+    deps: Deps,
+    env: Env,
+    unbonding_period: UnbondingPeriod,
+) -> StdResult<Vec<AnnualizedReward>> {
+    let total_stake = load_total_of_period(deps.storage, unbonding_period)
+        .map_err(|err| StdError::generic_err(err.to_string()))?
+        .powered_stake;
+    let distributions = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let config = CONFIG.load(deps.storage)?;
 
-            let period_rewards =
-                Decimal::from_ratio(rewards_per_year * power_of_period, total_power);
-            let rewards_per_token = period_rewards / total_stake;
+    annualized_rewards_of_period(
+        deps,
+        &env,
+        &config,
+        &distributions,
+        unbonding_period,
+        total_stake,
+    )
+}
 
-            rewards.push(AnnualizedReward {
+/// Calculates how many rewards of each distribution one token staked in `unbonding_period`
+/// receives in one year.
+fn annualized_rewards_of_period(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    distributions: &[(AssetInfoValidated, Distribution)],
+    unbonding_period: UnbondingPeriod,
+    total_stake: Uint128,
+) -> StdResult<Vec<AnnualizedReward>> {
+    distributions
+        .iter()
+        .map(|(asset_info, dist)| {
+            Ok(AnnualizedReward {
                 info: asset_info.clone(),
-                amount: Some(rewards_per_token),
-            });
-        }
-        aprs.push((unbonding_period, rewards));
+                amount: annualized_reward_of_distribution(
+                    deps,
+                    env,
+                    config,
+                    asset_info,
+                    dist,
+                    unbonding_period,
+                    total_stake,
+                )?,
+            })
+        })
+        .collect()
+}
+
+/// Calculates the annualized rewards per token of a single distribution.
+/// Returns `None` if there is nothing to calculate them from, i.e. nothing is staked,
+/// the distribution has no rewards power or the result does not fit into a [`Decimal`].
+fn annualized_reward_of_distribution(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    asset_info: &AssetInfoValidated,
+    dist: &Distribution,
+    unbonding_period: UnbondingPeriod,
+    total_stake: Uint128,
+) -> StdResult<Option<Decimal>> {
+    // without tokens per power there is no rewards power, so check before calculating it
+    if total_stake.is_zero() || config.tokens_per_power.is_zero() {
+        return Ok(None);
     }
-    Ok(AnnualizedRewardsResponse { rewards: aprs })
+    let total_power = dist.total_rewards_power(deps.storage, config);
+    if total_power.is_zero() {
+        return Ok(None);
+    }
+
+    let power_of_period = dist
+        .total_rewards_power_of_period(deps.storage, config, unbonding_period)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let reward_curve = REWARD_CURVE.load(deps.storage, asset_info)?;
+
+    let now = env.block.time.seconds();
+    let rewards_per_year = reward_curve
+        .value(now)
+        .checked_add(reward_curve.value(now + SECONDS_PER_YEAR))
+        .and_then(|sum| sum.checked_mul(Uint128::from(SECONDS_PER_YEAR / 100)))
+        .and_then(|rewards| rewards.checked_mul(power_of_period));
+    let period_rewards = rewards_per_year
+        .ok()
+        .and_then(|rewards| Decimal::checked_from_ratio(rewards, total_power).ok());
+    // `total_stake` was checked to be non-zero above
+    Ok(period_rewards.map(|period_rewards| period_rewards / total_stake))
 }
 
 fn query_rewards(deps: Deps, addr: String) -> StdResult<RewardsPowerResponse> {
@@ -846,19 +2378,88 @@ fn query_total_rewards(deps: Deps) -> StdResult<RewardsPowerResponse> {
     })
 }
 
-fn query_bonding_info(deps: Deps) -> StdResult<BondingInfoResponse> {
-    let total_stakes = TOTAL_PER_PERIOD.load(deps.storage)?;
+fn query_total_rewards_per_period(deps: Deps) -> StdResult<TotalRewardsPowerPerPeriodResponse> {
+    let distributions = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let cfg = CONFIG.load(deps.storage)?;
+
+    let rewards = cfg
+        .unbonding_periods
+        .iter()
+        .map(|&unbonding_period| {
+            let powers = distributions
+                .iter()
+                .map(|(asset_info, dist)| {
+                    dist.total_rewards_power_of_period(deps.storage, &cfg, unbonding_period)
+                        .map(|power| (asset_info.clone(), power))
+                        .map_err(|err| StdError::generic_err(err.to_string()))
+                })
+                .collect::<StdResult<Vec<_>>>()?;
+            Ok((unbonding_period, powers))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TotalRewardsPowerPerPeriodResponse { rewards })
+}
+
+fn query_bonding_info(deps: Deps) -> StdResult<BondingInfoResponse> {
+    let total_stakes = TOTAL_PER_PERIOD.load(deps.storage)?;
+
+    let bonding = total_stakes
+        .into_iter()
+        .map(|(unbonding_period, total_staked)| -> StdResult<_> {
+            Ok(BondingPeriodInfo {
+                unbonding_period,
+                total_staked: total_staked.staked,
+            })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(BondingInfoResponse { bonding })
+}
+
+fn query_lock_schedule(
+    deps: Deps,
+    env: &Env,
+    addr: String,
+    unbonding_period: u64,
+) -> StdResult<LockScheduleResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    // sanity check if such unbonding period exists
+    let cfg = CONFIG.load(deps.storage)?;
+    if cfg
+        .unbonding_periods
+        .binary_search(&unbonding_period)
+        .is_err()
+    {
+        return Err(StdError::generic_err(format!(
+            "No unbonding period found: {}",
+            unbonding_period
+        )));
+    }
+
+    let stake = STAKE
+        .may_load(deps.storage, (&addr, unbonding_period))?
+        .unwrap_or_default();
+    Ok(LockScheduleResponse {
+        unlocked: stake.total_unlocked(env),
+        locked_tokens: stake.locked_tokens(env),
+    })
+}
+
+fn query_reward_curve(deps: Deps, asset: AssetInfo) -> StdResult<Curve> {
+    let asset = asset.validate(deps.api)?;
+    REWARD_CURVE.may_load(deps.storage, &asset)?.ok_or_else(|| {
+        StdError::generic_err(format!("No distribution flow for this token: {asset}"))
+    })
+}
 
-    let bonding = total_stakes
-        .into_iter()
-        .map(|(unbonding_period, total_staked)| -> StdResult<_> {
-            Ok(BondingPeriodInfo {
-                unbonding_period,
-                total_staked: total_staked.staked,
-            })
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-    Ok(BondingInfoResponse { bonding })
+fn query_reward_curves(deps: Deps) -> StdResult<RewardCurvesResponse> {
+    Ok(RewardCurvesResponse {
+        curves: REWARD_CURVE
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?,
+    })
 }
 
 pub fn query_staked(
@@ -879,7 +2480,7 @@ pub fn query_staked(
     let stake = STAKE
         .may_load(deps.storage, (&addr, unbonding_period))?
         .unwrap_or_default();
-    let cw20_contract = CONFIG.load(deps.storage)?.cw20_contract.to_string();
+    let cw20_contract = CONFIG.load(deps.storage)?.staked_token.to_string();
     Ok(StakedResponse {
         stake: stake.total_stake(),
         total_locked: stake.total_locked(env),
@@ -888,10 +2489,18 @@ pub fn query_staked(
     })
 }
 
+pub fn query_staked_at(deps: Deps, addr: String, height: u64) -> StdResult<StakedAtResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let stake = STAKE_SNAPSHOTS
+        .may_load_at_height(deps.storage, &addr, height)?
+        .unwrap_or_default();
+    Ok(StakedAtResponse { stake, height })
+}
+
 pub fn query_all_staked(deps: Deps, env: Env, addr: String) -> StdResult<AllStakedResponse> {
     let addr = deps.api.addr_validate(&addr)?;
     let config = CONFIG.load(deps.storage)?;
-    let cw20_contract = config.cw20_contract.to_string();
+    let cw20_contract = config.staked_token.to_string();
 
     let stakes = config
         .unbonding_periods
@@ -917,6 +2526,27 @@ pub fn query_total_staked(deps: Deps) -> StdResult<TotalStakedResponse> {
     })
 }
 
+pub fn query_total_staked_at(deps: Deps, height: u64) -> StdResult<TotalStakedAtResponse> {
+    let totals = TOTAL_STAKED
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default();
+    let bonding = TOTAL_PER_PERIOD
+        .may_load_at_height(deps.storage, height)?
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(unbonding_period, total)| BondingPeriodInfo {
+            unbonding_period,
+            total_staked: total.staked,
+        })
+        .collect();
+    Ok(TotalStakedAtResponse {
+        total_staked: totals.staked,
+        total_unbonding: totals.unbonding,
+        bonding,
+        height,
+    })
+}
+
 pub fn query_total_unbonding(deps: Deps) -> StdResult<TotalUnbondingResponse> {
     Ok(TotalUnbondingResponse {
         total_unbonding: TOTAL_STAKED
@@ -926,17 +2556,99 @@ pub fn query_total_unbonding(deps: Deps) -> StdResult<TotalUnbondingResponse> {
     })
 }
 
+pub fn query_staking_metrics(deps: Deps) -> StdResult<StakingMetricsResponse> {
+    let metrics = STAKING_METRICS.may_load(deps.storage)?.unwrap_or_default();
+    let totals = TOTAL_STAKED.load(deps.storage).unwrap_or_default();
+    Ok(StakingMetricsResponse {
+        stakers: metrics.stakers,
+        positions: metrics.positions,
+        total_staked: totals.staked,
+        total_unbonding: totals.unbonding,
+    })
+}
+
+/// Simulates [`execute_rebond`] on copies of the stakes of `address` and calculates its rewards
+/// power in every distribution before and after.
+pub fn query_simulate_rebond(
+    deps: Deps,
+    env: Env,
+    address: String,
+    amount: Uint128,
+    bond_from: UnbondingPeriod,
+    bond_to: UnbondingPeriod,
+    include_locked: bool,
+) -> StdResult<SimulateRebondResponse> {
+    let address = deps.api.addr_validate(&address)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    validate_rebond(&cfg, amount, bond_from, bond_to)
+        .map_err(|err| StdError::generic_err(err.to_string()))?;
+
+    let load_stake = |unbonding_period| -> StdResult<BondingInfo> {
+        Ok(STAKE
+            .may_load(deps.storage, (&address, unbonding_period))?
+            .unwrap_or_default())
+    };
+    let mut from = load_stake(bond_from)?;
+    let mut to = load_stake(bond_to)?;
+    let (old_stake_from, old_stake_to) = (from.total_stake(), to.total_stake());
+    rebond_stake(
+        &env,
+        &mut from,
+        &mut to,
+        amount,
+        bond_from,
+        bond_to,
+        include_locked,
+    )?;
+    // like `update_total_stake` does for the actual stakes
+    let (new_stake_from, new_stake_to) = (from.total_stake(), to.total_stake());
+    from.update_powered(&cfg, old_stake_from, new_stake_from);
+    to.update_powered(&cfg, old_stake_to, new_stake_to);
+
+    let rewards_power = DISTRIBUTION
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|distribution| {
+            let (asset, distribution) = distribution?;
+            let before = distribution.calc_rewards_power_with(&cfg, load_stake)?;
+            let after = distribution.calc_rewards_power_with(&cfg, |unbonding_period| {
+                match unbonding_period {
+                    period if period == bond_from => Ok(from.clone()),
+                    period if period == bond_to => Ok(to.clone()),
+                    period => load_stake(period),
+                }
+            })?;
+            Ok(RebondRewardsPower {
+                asset,
+                before,
+                after,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+
+    Ok(SimulateRebondResponse { rewards_power })
+}
+
+pub fn query_admin_actions(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<AdminActionsResponse> {
+    Ok(AdminActionsResponse {
+        actions: read_admin_actions(deps.storage, start_after, limit)?,
+    })
+}
+
 #[cfg(test)]
 mod tests {
-    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
-    use cosmwasm_std::{from_slice, Coin, CosmosMsg, Decimal, WasmMsg};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{coins, from_slice, Coin, CosmosMsg, Decimal, WasmMsg};
     use cw_controllers::Claim;
     use cw_utils::Duration;
     use sg_swap::asset::{native_asset_info, token_asset_info};
 
     use crate::error::ContractError;
     use crate::msg::{DistributionDataResponse, WithdrawAdjustmentDataResponse};
-    use crate::state::{Distribution, WithdrawAdjustment};
+    use crate::state::{Distribution, WithdrawAdjustment, SHARES_SHIFT, WITHDRAW_ADJUSTMENT};
 
     use super::*;
 
@@ -976,11 +2688,13 @@ mod tests {
     ) {
         let msg = InstantiateMsg {
             cw20_contract: CW20_ADDRESS.to_owned(),
+            native_denom: None,
             tokens_per_power,
             min_bond,
             unbonding_periods: stake_config,
             admin: Some(INIT_ADMIN.into()),
             max_distributions: 6,
+            instant_unbond_penalty: None,
         };
         let info = mock_info("creator", &[]);
         instantiate(deps, env, info, msg).unwrap();
@@ -1036,6 +2750,7 @@ mod tests {
                     bond_from,
                     bond_to,
                     tokens: Uint128::new(*stake),
+                    include_locked: false,
                 };
                 let info = mock_info(addr, &[]);
                 execute(deps.branch(), env.clone(), info, msg).unwrap();
@@ -1059,6 +2774,7 @@ mod tests {
                 let msg = ExecuteMsg::Unbond {
                     tokens: Uint128::new(*stake),
                     unbonding_period,
+                    claim_recipient: None,
                 };
                 let info = mock_info(addr, &[]);
                 execute(deps.branch(), env.clone(), info, msg).unwrap();
@@ -1087,6 +2803,7 @@ mod tests {
         // setup distribution flow
         execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1108,6 +2825,10 @@ mod tests {
                     withdrawable_total: Uint128::zero(),
                     manager: Addr::unchecked(INIT_ADMIN),
                     reward_multipliers: vec![(UNBONDING_PERIOD, Decimal::percent(1))],
+                    paused_at: None,
+                    closed_at: None,
+                    generation: 0,
+                    rescales: 0,
                 }
             )]
         );
@@ -1127,6 +2848,8 @@ mod tests {
             WithdrawAdjustment {
                 shares_correction: 0,
                 withdrawn_rewards: Uint128::zero(),
+                generation: 0,
+                rescales: 0,
             }
         );
     }
@@ -1262,7 +2985,7 @@ mod tests {
             deps.as_mut(),
             env.clone(),
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap();
         assert_eq!(res.messages.len(), 1);
@@ -1323,7 +3046,7 @@ mod tests {
             deps.as_mut(),
             env2,
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap_err();
         assert_eq!(err, ContractError::NothingToClaim {});
@@ -1336,7 +3059,7 @@ mod tests {
             deps.as_mut(),
             env3.clone(),
             mock_info(USER1, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap();
         assert_cw20_undelegate(res, USER1, 4_500);
@@ -1346,7 +3069,7 @@ mod tests {
             deps.as_mut(),
             env3.clone(),
             mock_info(USER2, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap();
         assert_cw20_undelegate(res, USER2, 2_600);
@@ -1356,7 +3079,7 @@ mod tests {
             deps.as_mut(),
             env3,
             mock_info(USER3, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap_err();
         assert_eq!(err, ContractError::NothingToClaim {});
@@ -1383,13 +3106,74 @@ mod tests {
             deps.as_mut(),
             env4,
             mock_info(USER2, &[]),
-            ExecuteMsg::Claim {},
+            ExecuteMsg::Claim { up_to_amount: None },
         )
         .unwrap();
         assert_cw20_undelegate(res, USER2, 2_950); // 1_345 + 600 + 1_005
         assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER2)), vec![]);
     }
 
+    #[test]
+    fn claim_with_cap() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut(), mock_env());
+
+        bond_cw20(deps.as_mut(), 12_000, 0, 0, 5);
+        unbond(deps.as_mut(), 4_000, 0, 0, 10);
+        unbond(deps.as_mut(), 3_000, 0, 0, 12);
+
+        let mut env = mock_env();
+        env.block.time = env.block.time.plus_seconds(12);
+        let expires = Duration::Time(UNBONDING_PERIOD).after(&env.block);
+
+        env.block.time = env.block.time.plus_seconds(UNBONDING_PERIOD);
+
+        // cap smaller than any single claim releases nothing
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {
+                up_to_amount: Some(Uint128::new(2_000)),
+            },
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::NothingToClaim {});
+
+        // cap smaller than the total mature amount only releases the first claim
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {
+                up_to_amount: Some(Uint128::new(5_000)),
+            },
+        )
+        .unwrap();
+        assert_cw20_undelegate(res.clone(), USER1, 4_000);
+        assert!(res
+            .attributes
+            .iter()
+            .any(|attr| attr.key == "released" && attr.value == "4000"));
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![Claim::new(3_000, expires)]
+        );
+
+        // cap larger than the mature amount releases everything
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(USER1, &[]),
+            ExecuteMsg::Claim {
+                up_to_amount: Some(Uint128::new(10_000)),
+            },
+        )
+        .unwrap();
+        assert_cw20_undelegate(res, USER1, 3_000);
+        assert_eq!(get_claims(deps.as_ref(), &Addr::unchecked(USER1)), vec![]);
+    }
+
     fn rewards(deps: Deps, user: &str) -> Vec<(AssetInfoValidated, Uint128)> {
         query_rewards(deps, user.to_string()).unwrap().rewards
     }
@@ -1409,6 +3193,7 @@ mod tests {
         // create distribution flow to be able to receive rewards
         execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1477,6 +3262,7 @@ mod tests {
         // create distribution flow to be able to receive rewards
         execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1625,6 +3411,7 @@ mod tests {
         for denom in &DENOMS {
             execute_create_distribution_flow(
                 deps.as_mut(),
+                mock_env(),
                 mock_info(INIT_ADMIN, &[]),
                 INIT_ADMIN.to_string(),
                 native_asset_info(denom),
@@ -1635,6 +3422,7 @@ mod tests {
         // next one should fail
         let err = execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1652,6 +3440,7 @@ mod tests {
         // create distribution flow
         execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1662,6 +3451,7 @@ mod tests {
         // next one should fail
         let err = execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1685,6 +3475,7 @@ mod tests {
         // create distribution flow
         execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1716,6 +3507,7 @@ mod tests {
         // try to create distribution flow for staking token
         let err = execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             token_asset_info(CW20_ADDRESS),
@@ -1734,6 +3526,7 @@ mod tests {
         // try to create distribution flow for staking token
         let _res = execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1757,6 +3550,122 @@ mod tests {
         assert_eq!(err, ContractError::InvalidRewards {});
     }
 
+    #[test]
+    fn reward_curves_need_to_be_covered_by_balance() {
+        let mut deps = mock_dependencies();
+        default_instantiate(deps.as_mut(), mock_env());
+
+        execute_create_distribution_flow(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            INIT_ADMIN.to_string(),
+            native_asset_info(DENOM),
+            vec![(UNBONDING_PERIOD, Decimal::one())],
+        )
+        .unwrap();
+        let fund = |deps: DepsMut, curve| {
+            execute_fund_distribution(
+                mock_env(),
+                deps,
+                mock_info(INIT_ADMIN, &coins(100, DENOM)),
+                curve,
+            )
+        };
+
+        // the curve cannot lock more than was sent
+        let err = fund(deps.as_mut(), Curve::saturating_linear((0, 150), (100, 0))).unwrap_err();
+        assert_eq!(err, ContractError::InvalidRewards {});
+
+        // the funds did not actually arrive in the contract
+        let err = fund(deps.as_mut(), Curve::saturating_linear((0, 100), (100, 0))).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientRewardFunding {
+                asset: DENOM.to_string(),
+                locked: Uint128::new(100),
+                balance: Uint128::zero(),
+            }
+        );
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, DENOM));
+        fund(deps.as_mut(), Curve::saturating_linear((0, 100), (100, 0))).unwrap();
+
+        // both curves together lock more than the contract holds
+        let err = fund(deps.as_mut(), Curve::saturating_linear((0, 100), (100, 0))).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientRewardFunding {
+                asset: DENOM.to_string(),
+                locked: Uint128::new(200),
+                balance: Uint128::new(100),
+            }
+        );
+    }
+
+    #[test]
+    fn reward_curves_are_not_covered_by_stake() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            cw20_contract: CW20_ADDRESS.to_owned(),
+            native_denom: Some(DENOM.to_owned()),
+            tokens_per_power: TOKENS_PER_POWER,
+            min_bond: MIN_BOND,
+            unbonding_periods: vec![UNBONDING_PERIOD],
+            admin: Some(INIT_ADMIN.into()),
+            max_distributions: 6,
+            instant_unbond_penalty: Some(Decimal::percent(10)),
+        };
+        instantiate(deps.as_mut(), mock_env(), mock_info("creator", &[]), msg).unwrap();
+
+        // distribute the staked token
+        execute_create_distribution_flow(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            INIT_ADMIN.to_string(),
+            native_asset_info(DENOM),
+            vec![(UNBONDING_PERIOD, Decimal::one())],
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(USER1, &coins(10_000, DENOM)),
+            ExecuteMsg::Delegate {
+                unbonding_period: UNBONDING_PERIOD,
+                delegate_as: None,
+            },
+        )
+        .unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(10_000, DENOM));
+
+        // the contract only holds the stake, which cannot back the curve
+        let fund = |deps: DepsMut| {
+            execute_fund_distribution(
+                mock_env(),
+                deps,
+                mock_info(INIT_ADMIN, &coins(100, DENOM)),
+                Curve::saturating_linear((0, 100), (100, 0)),
+            )
+        };
+        let err = fund(deps.as_mut()).unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::InsufficientRewardFunding {
+                asset: DENOM.to_string(),
+                locked: Uint128::new(100),
+                balance: Uint128::zero(),
+            }
+        );
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(10_100, DENOM));
+        fund(deps.as_mut()).unwrap();
+    }
+
     #[test]
     fn distribution_flow_wrong_unbonding_period_fails() {
         let mut deps = mock_dependencies();
@@ -1765,6 +3674,7 @@ mod tests {
         // try to create distribution flow with wrong unbonding period
         let err = execute_create_distribution_flow(
             deps.as_mut(),
+            mock_env(),
             mock_info(INIT_ADMIN, &[]),
             INIT_ADMIN.to_string(),
             native_asset_info(DENOM),
@@ -1807,4 +3717,207 @@ mod tests {
         .u128();
         assert_eq!(stake, 100u128);
     }
+
+    #[test]
+    fn points_correction_overflow_is_an_error() {
+        let mut deps = mock_dependencies();
+        let staker = Addr::unchecked(USER1);
+        let asset_info = AssetInfoValidated::Native(DENOM.to_string());
+        let mut distribution = Distribution {
+            shares_per_point: Uint128::new(2),
+            shares_leftover: 0,
+            distributed_total: Uint128::zero(),
+            withdrawable_total: Uint128::zero(),
+            manager: Addr::unchecked(INIT_ADMIN),
+            reward_multipliers: vec![(UNBONDING_PERIOD, Decimal::one())],
+            paused_at: None,
+            closed_at: None,
+            generation: 0,
+            rescales: 0,
+        };
+
+        // a power that does not fit into an i128
+        let err = update_rewards(
+            &mut deps.storage,
+            &asset_info,
+            &staker,
+            &mut distribution,
+            Uint128::zero(),
+            Uint128::new(i128::MAX as u128 + 1),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::PointsCorrectionOverflow {});
+
+        // a power that fits, but overflows when multiplied with the shares per point
+        let err = update_rewards(
+            &mut deps.storage,
+            &asset_info,
+            &staker,
+            &mut distribution,
+            Uint128::zero(),
+            Uint128::new(i128::MAX as u128),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::PointsCorrectionOverflow {});
+
+        // no corrupted correction was stored
+        assert!(WITHDRAW_ADJUSTMENT
+            .may_load(&deps.storage, (&staker, &asset_info))
+            .unwrap()
+            .is_none());
+
+        // regular powers still work
+        update_rewards(
+            &mut deps.storage,
+            &asset_info,
+            &staker,
+            &mut distribution,
+            Uint128::zero(),
+            Uint128::new(1_000),
+        )
+        .unwrap();
+        let adjustment = WITHDRAW_ADJUSTMENT
+            .load(&deps.storage, (&staker, &asset_info))
+            .unwrap();
+        assert_eq!(adjustment.shares_correction, -2_000);
+    }
+
+    #[test]
+    fn emergency_withdraw_skips_distributions() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        default_instantiate(deps.as_mut(), env.clone());
+        execute_create_distribution_flow(
+            deps.as_mut(),
+            mock_env(),
+            mock_info(INIT_ADMIN, &[]),
+            INIT_ADMIN.to_string(),
+            native_asset_info(DENOM),
+            vec![(UNBONDING_PERIOD, Decimal::one())],
+        )
+        .unwrap();
+        bond_cw20(deps.as_mut(), 12_000, 7_500, 0, 0);
+
+        // break the reward accounting of the distribution
+        let asset_info = native(DENOM);
+        let mut distribution = DISTRIBUTION.load(&deps.storage, &asset_info).unwrap();
+        distribution.shares_per_point = Uint128::MAX;
+        DISTRIBUTION
+            .save(&mut deps.storage, &asset_info, &distribution)
+            .unwrap();
+
+        // the staker can still get out, even though its rewards cannot be calculated
+        let msg = ExecuteMsg::EmergencyWithdraw {
+            unbonding_period: UNBONDING_PERIOD,
+        };
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(USER1, &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr(format!("forfeited_{asset_info}"), "0")));
+
+        assert_stake_in_period(deps.as_ref(), &env, 0, 7_500, 0, UNBONDING_PERIOD);
+        let expires = Duration::Time(UNBONDING_PERIOD).after(&env.block);
+        assert_eq!(
+            get_claims(deps.as_ref(), &Addr::unchecked(USER1)),
+            vec![Claim::new(12_000, expires)]
+        );
+        let total = TOTAL_STAKED.load(&deps.storage).unwrap();
+        assert_eq!(total.staked.u128(), 7_500);
+        assert_eq!(total.unbonding.u128(), 12_000);
+        let total = load_total_of_period(&deps.storage, UNBONDING_PERIOD).unwrap();
+        assert_eq!(total.staked.u128(), 7_500);
+        assert_eq!(total.powered_stake.u128(), 7_500);
+        // the distribution was not touched
+        assert_eq!(
+            DISTRIBUTION.load(&deps.storage, &asset_info).unwrap(),
+            distribution
+        );
+
+        // there is nothing left to withdraw
+        let err = execute(deps.as_mut(), env.clone(), mock_info(USER1, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::NothingToUnbond {});
+
+        // while a regular unbond fails on the distribution
+        let msg = ExecuteMsg::Unbond {
+            tokens: Uint128::new(7_500),
+            unbonding_period: UNBONDING_PERIOD,
+            claim_recipient: None,
+        };
+        let err = execute(deps.as_mut(), env, mock_info(USER2, &[]), msg).unwrap_err();
+        assert_eq!(err, ContractError::PointsCorrectionOverflow {});
+    }
+
+    #[test]
+    fn forfeited_rewards_are_distributed_again() {
+        let mut deps = mock_dependencies();
+        let staker = Addr::unchecked(USER1);
+        let asset_info = AssetInfoValidated::Native(DENOM.to_string());
+        let mut distribution = Distribution {
+            shares_per_point: Uint128::new(3 << SHARES_SHIFT),
+            shares_leftover: 0,
+            distributed_total: Uint128::new(10_000),
+            withdrawable_total: Uint128::new(10_000),
+            manager: Addr::unchecked(INIT_ADMIN),
+            reward_multipliers: vec![(UNBONDING_PERIOD, Decimal::one())],
+            paused_at: None,
+            closed_at: None,
+            generation: 0,
+            rescales: 0,
+        };
+        WITHDRAW_ADJUSTMENT
+            .save(
+                &mut deps.storage,
+                (&staker, &asset_info),
+                &WithdrawAdjustment {
+                    withdrawn_rewards: Uint128::new(500),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+
+        // 1_000 power earned 3_000, of which 500 were withdrawn already
+        let forfeited = forfeit_rewards(
+            &mut deps.storage,
+            &staker,
+            &asset_info,
+            &mut distribution,
+            Uint128::new(1_000),
+            Uint128::new(400),
+        )
+        .unwrap();
+        assert_eq!(forfeited.u128(), 2_500);
+        assert_eq!(distribution.withdrawable_total.u128(), 7_500);
+        // the remaining power starts without rewards
+        assert_eq!(
+            WITHDRAW_ADJUSTMENT
+                .load(&deps.storage, (&staker, &asset_info))
+                .unwrap(),
+            WithdrawAdjustment {
+                shares_correction: -(400 * (3 << SHARES_SHIFT)),
+                withdrawn_rewards: Uint128::zero(),
+                generation: 0,
+                rescales: 0,
+            }
+        );
+
+        // nothing changes if the rewards cannot be calculated
+        distribution.shares_per_point = Uint128::MAX;
+        let forfeited = forfeit_rewards(
+            &mut deps.storage,
+            &staker,
+            &asset_info,
+            &mut distribution,
+            Uint128::new(400),
+            Uint128::zero(),
+        )
+        .unwrap();
+        assert_eq!(forfeited.u128(), 0);
+        assert_eq!(distribution.withdrawable_total.u128(), 7_500);
+    }
 }
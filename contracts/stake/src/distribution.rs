@@ -1,16 +1,22 @@
 use std::collections::HashSet;
 
-use cosmwasm_std::{Addr, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128};
-use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
+use cosmwasm_std::{
+    Addr, Deps, DepsMut, Empty, Env, MessageInfo, OverflowError, OverflowOperation, Response,
+    StdError, StdResult, Storage, Uint128,
+};
+use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated};
 
 use crate::error::ContractError;
 use crate::msg::{
-    DelegatedResponse, DistributedRewardsResponse, DistributionDataResponse,
-    UndistributedRewardsResponse, WithdrawAdjustmentDataResponse, WithdrawableRewardsResponse,
+    DelegatedResponse, DelegatedToResponse, DistributedRewardsResponse, DistributionDataResponse,
+    ProjectedReward, ProjectedRewardsResponse, SimulateDistributionResponse,
+    UndistributedRewardsResponse, WithdrawAdjustmentDataResponse, WithdrawableRewardResponse,
+    WithdrawableRewardsResponse,
 };
 use crate::state::{
-    Config, Distribution, WithdrawAdjustment, CONFIG, DELEGATED, DISTRIBUTION, REWARD_CURVE,
-    SHARES_SHIFT, WITHDRAW_ADJUSTMENT,
+    read_delegated_to, Config, Distribution, WithdrawAdjustment, CLOSED_DISTRIBUTION, CONFIG,
+    DELEGATED, DELEGATED_TO, DISTRIBUTION, REWARD_CURVE, REWARD_RECEIVER, SHARES_SHIFT,
+    TOTAL_STAKED, WITHDRAW_ADJUSTMENT,
 };
 
 pub fn execute_distribute_rewards(
@@ -32,6 +38,7 @@ pub fn execute_distribute_rewards(
     // we can only check the ones that were sent with the message (so only native assets)
     let supported_assets = distributions
         .iter()
+        .filter(|(_, distribution)| distribution.closed_at.is_none())
         .filter_map(|(a, _)| a.native_denom())
         .collect::<HashSet<_>>();
     if let Some(unsupported_coin) = info
@@ -48,6 +55,12 @@ pub fn execute_distribute_rewards(
 
     let cfg = CONFIG.load(deps.storage)?;
     for (asset_info, mut distribution) in distributions {
+        // Paused distributions keep their rewards until they are resumed,
+        // closed ones distributed everything when closing
+        if distribution.paused_at.is_some() || distribution.closed_at.is_some() {
+            continue;
+        }
+
         let total_rewards = distribution.total_rewards_power(deps.storage, &cfg);
         // There are no shares in play - noone to distribute to
         if total_rewards.is_zero() {
@@ -57,8 +70,13 @@ pub fn execute_distribute_rewards(
         let withdrawable: u128 = distribution.withdrawable_total.into();
 
         // Query current reward balance
-        let balance =
-            undistributed_rewards(deps.as_ref(), &asset_info, env.contract.address.clone())?.u128();
+        let balance = undistributed_rewards(
+            deps.as_ref(),
+            &cfg,
+            &asset_info,
+            env.contract.address.clone(),
+        )?
+        .u128();
 
         let curve = REWARD_CURVE.load(deps.storage, &asset_info)?;
 
@@ -71,18 +89,7 @@ pub fn execute_distribute_rewards(
             continue;
         }
 
-        let leftover: u128 = distribution.shares_leftover.into();
-        let points = (amount << SHARES_SHIFT) + leftover;
-        let points_per_share = points / total_rewards.u128();
-        distribution.shares_leftover = (points % total_rewards.u128()) as u64;
-
-        // Everything goes back to 128-bits/16-bytes
-        // Full amount is added here to total withdrawable, as it should not be considered on its own
-        // on future distributions - even if because of calculation offsets it is not fully
-        // distributed, the error is handled by leftover.
-        distribution.shares_per_point += Uint128::new(points_per_share);
-        distribution.distributed_total += Uint128::new(amount);
-        distribution.withdrawable_total += Uint128::new(amount);
+        distribution.distribute(amount, total_rewards);
 
         DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
 
@@ -93,13 +100,21 @@ pub fn execute_distribute_rewards(
 }
 
 /// Query current reward balance of the given asset.
-/// Make sure not to call this for the staking token
-fn undistributed_rewards(
+/// For the staking token, the staked and unbonding tokens are excluded.
+pub fn undistributed_rewards(
     deps: Deps,
+    cfg: &Config,
     asset_info: &AssetInfoValidated,
     contract_address: impl Into<String>,
 ) -> StdResult<Uint128> {
-    asset_info.query_balance(&deps.querier, contract_address)
+    let balance = asset_info.query_balance(&deps.querier, contract_address)?;
+    match asset_info {
+        _ if asset_info == &cfg.staked_token.asset_info() => {
+            let total_staked = TOTAL_STAKED.load(deps.storage)?.total();
+            Ok(balance.saturating_sub(total_staked))
+        }
+        _ => Ok(balance),
+    }
 }
 
 pub fn execute_withdraw_rewards(
@@ -112,10 +127,12 @@ pub fn execute_withdraw_rewards(
         || Ok(info.sender.clone()),
         |owner| deps.api.addr_validate(&owner),
     )?;
-    let receiver = receiver
-        .map(|receiver| deps.api.addr_validate(&receiver))
-        .transpose()?
-        .unwrap_or_else(|| info.sender.clone());
+    let receiver = match receiver {
+        Some(receiver) => deps.api.addr_validate(&receiver)?,
+        None => REWARD_RECEIVER
+            .may_load(deps.storage, &owner)?
+            .unwrap_or_else(|| info.sender.clone()),
+    };
 
     let mut resp = Response::new()
         .add_attribute("action", "withdraw_rewards")
@@ -123,10 +140,6 @@ pub fn execute_withdraw_rewards(
         .add_attribute("owner", owner.as_str())
         .add_attribute("receiver", receiver.as_str());
 
-    let distributions = DISTRIBUTION
-        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
-        .collect::<StdResult<Vec<_>>>()?;
-
     let delegated = DELEGATED
         .may_load(deps.storage, &owner)?
         .unwrap_or_else(|| owner.clone());
@@ -134,32 +147,71 @@ pub fn execute_withdraw_rewards(
         return Err(ContractError::Unauthorized {});
     }
 
+    for reward in take_withdrawable_rewards(deps, &owner, |_| true)? {
+        // send rewards to receiver
+        resp = resp
+            .add_attribute(format!("reward_{}", reward.info), reward.amount)
+            .add_message(reward.into_msg(receiver.clone())?);
+    }
+
+    Ok(resp)
+}
+
+/// Marks all rewards of `owner` in the assets selected by `include` as withdrawn
+/// and returns them. Sending the rewards out is up to the caller.
+pub fn take_withdrawable_rewards(
+    deps: DepsMut,
+    owner: &Addr,
+    include: impl Fn(&AssetInfoValidated) -> bool,
+) -> StdResult<Vec<AssetValidated>> {
+    let distributions = DISTRIBUTION
+        .range(deps.storage, None, None, cosmwasm_std::Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
     let cfg = CONFIG.load(deps.storage)?;
+    let mut rewards = vec![];
     for (asset_info, mut distribution) in distributions {
+        if !include(&asset_info) {
+            continue;
+        }
+
         // get adjustment data
-        let mut adjustment = WITHDRAW_ADJUSTMENT
-            .may_load(deps.storage, (&owner, &asset_info))?
-            .unwrap_or_default();
+        let mut adjustment =
+            distribution.withdraw_adjustment(deps.storage, &cfg, owner, &asset_info)?;
 
-        let reward = withdrawable_rewards(deps.as_ref(), &cfg, &owner, &distribution, &adjustment)?;
+        let reward = withdrawable_rewards(deps.as_ref(), &cfg, owner, &distribution, &adjustment)?;
 
         if reward.is_zero() {
             // Just do nothing
             continue;
         }
         adjustment.withdrawn_rewards += reward;
-        WITHDRAW_ADJUSTMENT.save(deps.storage, (&owner, &asset_info), &adjustment)?;
-        distribution.withdrawable_total -= reward;
-        DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
-        // send rewards to receiver
-        let msg = asset_info.with_balance(reward).into_msg(receiver.clone())?;
+        WITHDRAW_ADJUSTMENT.save(deps.storage, (owner, &asset_info), &adjustment)?;
+        distribution.withdrawable_total = distribution.withdrawable_total.checked_sub(reward)?;
+        if distribution.closed_at.is_some() && distribution.withdrawable_total.is_zero() {
+            // the last rewards of a closed distribution were withdrawn, so it is done
+            remove_distribution(deps.storage, &asset_info, &distribution)?;
+        } else {
+            DISTRIBUTION.save(deps.storage, &asset_info, &distribution)?;
+        }
 
-        resp = resp
-            .add_message(msg)
-            .add_attribute(format!("reward_{}", asset_info), reward);
+        rewards.push(asset_info.with_balance(reward));
     }
 
-    Ok(resp)
+    Ok(rewards)
+}
+
+/// Removes the distribution flow of the given asset, once it is closed and settled.
+/// The final state is kept in [`CLOSED_DISTRIBUTION`], so a new flow of the asset starts with the next
+/// generation and ignores the withdraw adjustments of this one.
+pub fn remove_distribution(
+    storage: &mut dyn Storage,
+    asset_info: &AssetInfoValidated,
+    distribution: &Distribution,
+) -> StdResult<()> {
+    DISTRIBUTION.remove(storage, asset_info);
+    REWARD_CURVE.remove(storage, asset_info);
+    CLOSED_DISTRIBUTION.save(storage, asset_info, distribution)
 }
 
 pub fn execute_delegate_withdrawal(
@@ -169,7 +221,11 @@ pub fn execute_delegate_withdrawal(
 ) -> Result<Response, ContractError> {
     let delegated = deps.api.addr_validate(&delegated)?;
 
+    if let Some(previous) = DELEGATED.may_load(deps.storage, &info.sender)? {
+        DELEGATED_TO.remove(deps.storage, (&previous, &info.sender));
+    }
     DELEGATED.save(deps.storage, &info.sender, &delegated)?;
+    DELEGATED_TO.save(deps.storage, (&delegated, &info.sender), &Empty {})?;
     let resp = Response::new()
         .add_attribute("action", "delegate_withdrawal")
         .add_attribute("sender", info.sender.as_str())
@@ -178,6 +234,27 @@ pub fn execute_delegate_withdrawal(
     Ok(resp)
 }
 
+pub fn execute_set_reward_receiver(
+    deps: DepsMut,
+    info: MessageInfo,
+    receiver: Option<String>,
+) -> Result<Response, ContractError> {
+    let mut resp = Response::new()
+        .add_attribute("action", "set_reward_receiver")
+        .add_attribute("sender", info.sender.as_str());
+
+    match receiver {
+        Some(receiver) => {
+            let receiver = deps.api.addr_validate(&receiver)?;
+            REWARD_RECEIVER.save(deps.storage, &info.sender, &receiver)?;
+            resp = resp.add_attribute("receiver", &receiver);
+        }
+        None => REWARD_RECEIVER.remove(deps.storage, &info.sender),
+    }
+
+    Ok(resp)
+}
+
 pub fn query_withdrawable_rewards(
     deps: Deps,
     owner: String,
@@ -193,9 +270,8 @@ pub fn query_withdrawable_rewards(
     let rewards = distributions
         .map(|distr| -> StdResult<_> {
             let (asset_info, distribution) = distr?;
-            let adjustment = WITHDRAW_ADJUSTMENT
-                .may_load(deps.storage, (&owner, &asset_info))?
-                .unwrap_or_default();
+            let adjustment =
+                distribution.withdraw_adjustment(deps.storage, &cfg, &owner, &asset_info)?;
             let rewards = withdrawable_rewards(deps, &cfg, &owner, &distribution, &adjustment)?;
 
             Ok(asset_info.with_balance(rewards))
@@ -204,17 +280,88 @@ pub fn query_withdrawable_rewards(
     Ok(WithdrawableRewardsResponse { rewards })
 }
 
+pub fn query_projected_rewards(
+    deps: Deps,
+    env: Env,
+    owner: String,
+    horizon_seconds: u64,
+) -> StdResult<ProjectedRewardsResponse> {
+    // Not checking address, same as in `query_withdrawable_rewards`
+    let owner = Addr::unchecked(&owner);
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let now = env.block.time.seconds();
+    let distributions =
+        DISTRIBUTION.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
+
+    let rewards = distributions
+        .map(|distr| -> StdResult<_> {
+            let (asset_info, distribution) = distr?;
+            let adjustment =
+                distribution.withdraw_adjustment(deps.storage, &cfg, &owner, &asset_info)?;
+            let withdrawable =
+                withdrawable_rewards(deps, &cfg, &owner, &distribution, &adjustment)?;
+
+            // like in `execute_distribute_rewards`, everything the curve does not hold back anymore
+            // at the end of the horizon is distributed, including rewards that are already released
+            let curve = REWARD_CURVE.load(deps.storage, &asset_info)?;
+            let released =
+                undistributed_rewards(deps, &cfg, &asset_info, env.contract.address.clone())?
+                    .saturating_sub(distribution.withdrawable_total)
+                    .saturating_sub(curve.value(now.saturating_add(horizon_seconds)));
+            let total_power = distribution.total_rewards_power(deps.storage, &cfg);
+            let projected = if total_power.is_zero()
+                || distribution.paused_at.is_some()
+                || distribution.closed_at.is_some()
+            {
+                Uint128::zero()
+            } else {
+                let power = distribution.calc_rewards_power(deps.storage, &cfg, &owner)?;
+                released.multiply_ratio(power, total_power)
+            };
+
+            Ok(ProjectedReward {
+                asset: asset_info,
+                withdrawable,
+                projected,
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    Ok(ProjectedRewardsResponse { rewards })
+}
+
+pub fn query_withdrawable_reward(
+    deps: Deps,
+    owner: String,
+    asset: AssetInfo,
+) -> StdResult<WithdrawableRewardResponse> {
+    // Not checking address, as if it is invalid it is guaranteed not to appear in maps
+    let owner = Addr::unchecked(&owner);
+    let asset = asset.validate(deps.api)?;
+
+    let cfg = CONFIG.load(deps.storage)?;
+    let distribution = DISTRIBUTION.load(deps.storage, &asset)?;
+    let adjustment = distribution.withdraw_adjustment(deps.storage, &cfg, &owner, &asset)?;
+    let reward = withdrawable_rewards(deps, &cfg, &owner, &distribution, &adjustment)?;
+
+    Ok(WithdrawableRewardResponse {
+        reward: asset.with_balance(reward),
+    })
+}
+
 pub fn query_undistributed_rewards(
     deps: Deps,
     env: Env,
 ) -> StdResult<UndistributedRewardsResponse> {
+    let cfg = CONFIG.load(deps.storage)?;
     let distributions =
         DISTRIBUTION.range(deps.storage, None, None, cosmwasm_std::Order::Ascending);
 
     let rewards = distributions
         .map(|distribution| -> StdResult<_> {
             let (asset_info, distribution) = distribution?;
-            let balance = undistributed_rewards(deps, &asset_info, env.contract.address.clone())?;
+            let balance =
+                undistributed_rewards(deps, &cfg, &asset_info, env.contract.address.clone())?;
 
             Ok(asset_info.with_balance(balance - distribution.withdrawable_total))
         })
@@ -240,6 +387,39 @@ pub fn query_distributed_rewards(deps: Deps) -> StdResult<DistributedRewardsResp
     })
 }
 
+pub fn query_simulate_distribution(
+    deps: Deps,
+    asset: AssetInfo,
+    amount: Uint128,
+) -> StdResult<SimulateDistributionResponse> {
+    let asset = asset.validate(deps.api)?;
+    let cfg = CONFIG.load(deps.storage)?;
+    let mut distribution = DISTRIBUTION
+        .may_load(deps.storage, &asset)?
+        .ok_or_else(|| {
+            StdError::generic_err(format!("No distribution flow for this token: {asset}"))
+        })?;
+
+    // like in `execute_distribute_rewards`, paused distributions and distributions without
+    // any rewards power keep the amount until later, closed ones do not distribute anymore
+    let total_rewards = distribution.total_rewards_power(deps.storage, &cfg);
+    let shares_per_point_increment = if distribution.paused_at.is_some()
+        || distribution.closed_at.is_some()
+        || total_rewards.is_zero()
+        || amount.is_zero()
+    {
+        Uint128::zero()
+    } else {
+        distribution.distribute(amount.u128(), total_rewards)
+    };
+
+    Ok(SimulateDistributionResponse {
+        shares_per_point_increment,
+        shares_per_point: distribution.shares_per_point,
+        withdrawable_total: distribution.withdrawable_total,
+    })
+}
+
 pub fn query_delegated(deps: Deps, owner: String) -> StdResult<DelegatedResponse> {
     let owner = deps.api.addr_validate(&owner)?;
 
@@ -248,6 +428,22 @@ pub fn query_delegated(deps: Deps, owner: String) -> StdResult<DelegatedResponse
     Ok(DelegatedResponse { delegated })
 }
 
+pub fn query_delegated_to(
+    deps: Deps,
+    delegate: String,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<DelegatedToResponse> {
+    let delegate = deps.api.addr_validate(&delegate)?;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    let owners = read_delegated_to(deps.storage, &delegate, start_after.as_ref(), limit)?;
+
+    Ok(DelegatedToResponse { owners })
+}
+
 pub fn query_distribution_data(deps: Deps) -> StdResult<DistributionDataResponse> {
     Ok(DistributionDataResponse {
         distributions: DISTRIBUTION
@@ -263,32 +459,106 @@ pub fn query_withdraw_adjustment_data(
 ) -> StdResult<WithdrawAdjustmentDataResponse> {
     let addr = deps.api.addr_validate(&owner)?;
     let asset = asset.validate(deps.api)?;
-    let adjust = WITHDRAW_ADJUSTMENT
-        .may_load(deps.storage, (&addr, &asset))?
-        .unwrap_or(WithdrawAdjustmentDataResponse {
-            shares_correction: 0,
-            withdrawn_rewards: Uint128::zero(),
-        });
+    let cfg = CONFIG.load(deps.storage)?;
+    let adjust = match DISTRIBUTION.may_load(deps.storage, &asset)? {
+        Some(distribution) => {
+            distribution.withdraw_adjustment(deps.storage, &cfg, &addr, &asset)?
+        }
+        None => WITHDRAW_ADJUSTMENT
+            .may_load(deps.storage, (&addr, &asset))?
+            .unwrap_or_default(),
+    };
     Ok(adjust)
 }
 
 /// Applies points correction for given address.
-/// `shares_per_point` is current value from `SHARES_PER_POINT` - not loaded in function, to
+/// `distribution` is the current state of the distribution - not loaded in function, to
 /// avoid multiple queries on bulk updates.
+/// The withdraw adjustment has to be settled with [`settle_withdraw_adjustments`] before the stake changed.
 /// `diff` is the points change
+///
+/// Returns [`ContractError::PointsCorrectionOverflow`] instead of wrapping
+/// if the correction does not fit into an `i128`.
 pub fn apply_points_correction(
     storage: &mut dyn Storage,
     addr: &Addr,
     asset_info: &AssetInfoValidated,
-    shares_per_point: u128,
+    distribution: &Distribution,
     diff: i128,
+) -> Result<(), ContractError> {
+    let shares_per_point = i128::try_from(distribution.shares_per_point.u128())
+        .map_err(|_| ContractError::PointsCorrectionOverflow {})?;
+    let mut adjustment = distribution.stored_withdraw_adjustment(storage, addr, asset_info)?;
+    adjustment.shares_correction = shares_per_point
+        .checked_mul(diff)
+        .and_then(|correction| adjustment.shares_correction.checked_sub(correction))
+        .ok_or(ContractError::PointsCorrectionOverflow {})?;
+    WITHDRAW_ADJUSTMENT.save(storage, (addr, asset_info), &adjustment)?;
+    Ok(())
+}
+
+/// Drops the rewards of `staker` in `distribution` after its rewards power changed from `old_reward_power`
+/// to `new_reward_power` without a points correction, e.g. because the correction overflowed.
+/// The withdraw adjustment starts over with the new power, so only later rewards can be withdrawn,
+/// and the dropped rewards are no longer withdrawable, so they are distributed again.
+/// Returns the dropped rewards. If they cannot be calculated either, nothing is changed.
+pub fn forfeit_rewards(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    asset_info: &AssetInfoValidated,
+    distribution: &mut Distribution,
+    old_reward_power: Uint128,
+    new_reward_power: Uint128,
+) -> StdResult<Uint128> {
+    let adjustment = distribution.stored_withdraw_adjustment(storage, staker, asset_info)?;
+    let shares = |power: Uint128| {
+        distribution
+            .shares_per_point
+            .u128()
+            .checked_mul(power.u128())
+            .and_then(|shares| i128::try_from(shares).ok())
+    };
+    let rewards = shares(old_reward_power)
+        .and_then(|shares| shares.checked_add(adjustment.shares_correction))
+        .and_then(|shares| u128::try_from(shares).ok())
+        .map(|shares| {
+            Uint128::new(shares >> SHARES_SHIFT).saturating_sub(adjustment.withdrawn_rewards)
+        });
+    let (rewards, new_shares) = match (rewards, shares(new_reward_power)) {
+        (Some(rewards), Some(new_shares)) => (rewards, new_shares),
+        _ => return Ok(Uint128::zero()),
+    };
+
+    WITHDRAW_ADJUSTMENT.save(
+        storage,
+        (staker, asset_info),
+        &WithdrawAdjustment {
+            shares_correction: -new_shares,
+            withdrawn_rewards: Uint128::zero(),
+            generation: distribution.generation,
+            rescales: distribution.rescales,
+        },
+    )?;
+    distribution.withdrawable_total = distribution.withdrawable_total.saturating_sub(rewards);
+    Ok(rewards)
+}
+
+/// Stores the withdraw adjustments of `staker` with all `tokens_per_power` changes applied.
+/// They are applied with the current stake, so this has to be called before changing it.
+pub fn settle_withdraw_adjustments(
+    storage: &mut dyn Storage,
+    cfg: &Config,
+    staker: &Addr,
+    distributions: &[(AssetInfoValidated, Distribution)],
 ) -> StdResult<()> {
-    WITHDRAW_ADJUSTMENT.update(storage, (addr, asset_info), |old| -> StdResult<_> {
-        let mut old = old.unwrap_or_default();
-        let shares_correction: i128 = old.shares_correction;
-        old.shares_correction = shares_correction - shares_per_point as i128 * diff;
-        Ok(old)
-    })?;
+    for (asset_info, distribution) in distributions {
+        let stored = distribution.stored_withdraw_adjustment(storage, staker, asset_info)?;
+        if stored.rescales == distribution.rescales {
+            continue;
+        }
+        let adjustment = distribution.withdraw_adjustment(storage, cfg, staker, asset_info)?;
+        WITHDRAW_ADJUSTMENT.save(storage, (staker, asset_info), &adjustment)?;
+    }
     Ok(())
 }
 
@@ -308,9 +578,16 @@ pub fn withdrawable_rewards(
 
     let correction = adjustment.shares_correction;
     let points = (ppw * points) as i128;
-    let points = points + correction;
-    let amount = points as u128 >> SHARES_SHIFT;
-    let amount = amount - adjustment.withdrawn_rewards.u128();
+    // the correction never takes away more than was added, see `apply_points_correction`
+    let points = u128::try_from(points + correction).map_err(|_| {
+        StdError::overflow(OverflowError::new(
+            OverflowOperation::Add,
+            points,
+            correction,
+        ))
+    })?;
+    let amount = Uint128::new(points >> SHARES_SHIFT);
+    let amount = amount.checked_sub(adjustment.withdrawn_rewards)?;
 
-    Ok(amount.into())
+    Ok(amount)
 }
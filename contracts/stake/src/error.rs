@@ -2,6 +2,7 @@ use cosmwasm_std::{Coin, OverflowError, StdError, Uint128};
 use thiserror::Error;
 
 use cw_controllers::{AdminError, HookError};
+use cw_utils::PaymentError;
 use sg_swap::asset::AssetInfoValidated;
 use wynd_curve_utils::CurveError;
 
@@ -19,6 +20,9 @@ pub enum ContractError {
     #[error("{0}")]
     Curve(#[from] CurveError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
@@ -28,14 +32,29 @@ pub enum ContractError {
     #[error("Rebond amount is invalid")]
     NoRebondAmount {},
 
+    #[error("Transfer amount is invalid")]
+    NoTransferAmount {},
+
+    #[error("Cannot transfer stake to yourself")]
+    TransferStakeToSelf {},
+
     #[error("No claims that can be released currently")]
     NothingToClaim {},
 
+    #[error("No unlocked stake to unbond")]
+    NothingToUnbond {},
+
+    #[error("Cannot have more than {0} pending claims, claim the mature ones first")]
+    TooManyClaims(u32),
+
     #[error(
         "Sender's CW20 token contract address {got} does not match one from config {expected}"
     )]
     Cw20AddressesNotMatch { got: String, expected: String },
 
+    #[error("The staked token is a cw20 token, bond it by sending it to this contract")]
+    NativeStakingDisabled {},
+
     #[error("Trying to mass delegate {total} tokens, but only sent {amount_sent}.")]
     MassDelegateTooMuch {
         total: Uint128,
@@ -51,6 +70,9 @@ pub enum ContractError {
     #[error("No unbonding period found: {0}")]
     NoUnbondingPeriodFound(u64),
 
+    #[error("Unbonding period already exists: {0}")]
+    UnbondingPeriodAlreadyExists(u64),
+
     #[error("No members to distribute tokens to")]
     NoMembersToDistributeTo {},
 
@@ -63,6 +85,15 @@ pub enum ContractError {
     #[error("No distribution flow for this token: {0}")]
     NoDistributionFlow(Coin),
 
+    #[error("The distribution for {0} is closed")]
+    DistributionClosed(AssetInfoValidated),
+
+    #[error("Cannot remove the closed distribution for {0} while there are rewards to withdraw")]
+    PendingRewards(AssetInfoValidated),
+
+    #[error("The distribution for {0} is paused")]
+    DistributionPaused(AssetInfoValidated),
+
     #[error("Cannot add more than {0} distributions")]
     TooManyDistributions(u32),
 
@@ -72,8 +103,38 @@ pub enum ContractError {
     #[error("Invalid distribution rewards")]
     InvalidRewards {},
 
+    #[error("The reward curves lock {locked} {asset}, but the contract only holds {balance} of undistributed rewards")]
+    InsufficientRewardFunding {
+        asset: String,
+        locked: Uint128,
+        balance: Uint128,
+    },
+
+    #[error("Tokens per power must not be zero")]
+    ZeroTokensPerPower {},
+
+    #[error("Instant unbonding is disabled")]
+    InstantUnbondDisabled {},
+
+    #[error("Instant unbond penalty must be between 0 and 1")]
+    InvalidInstantUnbondPenalty {},
+
+    #[error(
+        "Instant unbonding needs an open distribution flow of the staked token for the penalty"
+    )]
+    NoPenaltyDistribution {},
+
     #[error("No reward duration provided for rewards distribution")]
     ZeroRewardDuration {},
+
+    #[error("The liquidity token of the pair is not the staked token")]
+    InvalidCompoundPair {},
+
+    #[error("No rewards to compound")]
+    NothingToCompound {},
+
+    #[error("Rewards power too large for the points correction")]
+    PointsCorrectionOverflow {},
 }
 
 impl From<OverflowError> for ContractError {
@@ -1,7 +1,7 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 use cw20::Cw20ReceiveMsg;
 
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{Addr, Decimal, Timestamp, Uint128};
 use sg_swap::asset::{AssetInfo, AssetInfoValidated, AssetValidated};
 use wynd_curve_utils::Curve;
 
@@ -15,6 +15,19 @@ pub enum ExecuteMsg {
         // these must be valid time periods
         bond_from: u64,
         bond_to: u64,
+        /// If set, tokens that are still locked in `bond_from` are rebonded as well, once the unlocked
+        /// ones are used up. They stay locked, for the difference between both periods longer when moving
+        /// to a shorter period and shorter when moving to a longer one. Otherwise, only unlocked tokens
+        /// can be rebonded.
+        #[serde(default)]
+        include_locked: bool,
+    },
+    /// Moves the given amount of staked tokens to `recipient`, keeping the unbonding period.
+    /// Unlocked tokens are moved first, then locked tokens, which keep their unlock time.
+    TransferStake {
+        recipient: String,
+        unbonding_period: u64,
+        amount: Uint128,
     },
     /// Unbond will start the unbonding process for the given number of tokens.
     /// The sender immediately loses power from these tokens, and can claim them
@@ -24,13 +37,71 @@ pub enum ExecuteMsg {
         /// As each unbonding period in delegation corresponds to particular voting
         /// multiplier, unbonding_period needs to be passed in unbond as well
         unbonding_period: u64,
+        /// If set, the tokens are paid out to this address instead of the sender
+        /// when the sender claims them after the unbonding period.
+        #[serde(default)]
+        claim_recipient: Option<String>,
+    },
+    /// UnbondAll starts the unbonding process for all of the sender's stake in every unbonding period,
+    /// creating one claim per period. Tokens that are still locked after a rebond are skipped.
+    UnbondAll {},
+    /// EmergencyWithdraw unbonds all unlocked stake of the sender in `unbonding_period`,
+    /// creating a claim for it just like `Unbond`. Stake that is still locked after a rebond
+    /// is skipped until it unlocks.
+    /// This is a safety hatch for when reward accounting fails: the distributions are updated as
+    /// usual, but the sender forfeits its rewards in every distribution whose reward calculations fail.
+    /// Forfeited rewards are distributed again to all stakers.
+    EmergencyWithdraw { unbonding_period: u64 },
+    /// InstantUnbond releases the given number of tokens immediately, without waiting for
+    /// the unbonding period. The configured penalty is deducted from the released tokens and
+    /// distributed to the stakers as rewards, so this fails if there is no open distribution
+    /// for the staked token.
+    InstantUnbond {
+        tokens: Uint128,
+        unbonding_period: u64,
     },
     /// Claim is used to claim your native tokens that you previously "unbonded"
     /// after the contract-defined waiting period (eg. 1 week)
-    Claim {},
+    Claim {
+        /// If set, at most this amount of mature tokens is released.
+        /// Claims are only ever released as a whole, so this might release less than the cap.
+        /// Otherwise all mature tokens are claimed.
+        up_to_amount: Option<Uint128>,
+    },
 
     /// Change the admin
     UpdateAdmin { admin: Option<String> },
+    /// Changes how many staked tokens give one unit of rewards power.
+    /// Only callable by the admin.
+    /// Rewards distributed before the update keep the old scaling for each staker, so the new scaling
+    /// only applies to rewards distributed after the update.
+    UpdateTokensPerPower { tokens_per_power: Uint128 },
+    /// Changes the minimum stake needed in an unbonding period to get rewards power.
+    /// Only callable by the admin. Values below 1 are raised to 1.
+    /// The new value is applied to each stake the next time it changes.
+    UpdateMinBond { min_bond: Uint128 },
+    /// Changes the maximum number of pending claims a staker can have before unbonding is rejected.
+    /// Only callable by the admin.
+    UpdateMaxClaimsPerUser { max_claims_per_user: u32 },
+    /// Changes the maximum number of distribution flows. It cannot be lowered below the number
+    /// of existing distribution flows. Only callable by the admin.
+    SetMaxDistributions { max: u32 },
+    /// Removes an unbonding period. All stake in that period is rebonded into `migrate_to`.
+    /// Only callable by the admin.
+    RemoveUnbondingPeriod {
+        /// The unbonding period to remove
+        period: UnbondingPeriod,
+        /// The unbonding period that all stake of the removed period is moved to
+        migrate_to: UnbondingPeriod,
+    },
+    /// Adds a new unbonding period. Only callable by the admin.
+    AddUnbondingPeriod {
+        /// The unbonding period to add
+        period: UnbondingPeriod,
+        /// Rewards multiplier of the new period for each existing distribution.
+        /// There has to be exactly one entry for every distribution flow.
+        rewards: Vec<(AssetInfo, Decimal)>,
+    },
     /// Create a new distribution flow
     CreateDistributionFlow {
         /// The address of the manager that can change this distribution
@@ -43,9 +114,41 @@ pub enum ExecuteMsg {
         /// Only periods that are defined in the contract can be used here
         rewards: Vec<(UnbondingPeriod, Decimal)>,
     },
+    /// Replaces the rewards multipliers of an existing distribution flow.
+    /// Only callable by the manager of that distribution.
+    /// Existing points corrections are preserved, so rewards that were distributed but not withdrawn yet
+    /// are also weighted by the new multipliers.
+    UpdateRewardMultipliers {
+        /// The asset of the distribution flow to update
+        asset: AssetInfo,
+        /// Rewards multiplier by unbonding period for this distribution.
+        /// There has to be exactly one entry for every unbonding period and they must not decrease.
+        rewards: Vec<(UnbondingPeriod, Decimal)>,
+    },
+    /// Pauses or resumes the distribution flow of the given asset.
+    /// Only callable by the manager of that distribution.
+    /// While paused, no rewards are distributed and the reward curve does not release anything.
+    /// Resuming shifts the curve by the paused time, so it continues where it stopped.
+    SetDistributionPaused { asset: AssetInfo, paused: bool },
+    /// Closes the distribution flow of the given asset. Only callable by the admin.
+    /// The rewards already released by the reward curve are distributed to the stakers, the locked ones
+    /// are sent back to the manager and the flow stops accruing.
+    /// The flow frees its slot once all rewards are withdrawn. Calling this again removes it anyway,
+    /// as soon as the longest unbonding period has passed since closing.
+    CloseDistributionFlow {
+        /// The asset of the distribution flow to close
+        asset: AssetInfo,
+    },
 
     /// This accepts a properly-encoded ReceiveMsg from a cw20 contract
     Receive(Cw20ReceiveMsg),
+    /// Bonds the native tokens sent with this message.
+    /// Only available if the staked token is a native token.
+    BondNative {
+        unbonding_period: u64,
+        /// The address the tokens are bonded for. Defaults to the sender.
+        delegate_as: Option<String>,
+    },
 
     /// Distributes rewards sent with this message, and all rewards transferred since last call of this
     /// to members, proportionally to their points. Rewards are not immediately send to members, but
@@ -61,7 +164,8 @@ pub enum ExecuteMsg {
         /// to be eligible for withdrawal from `owner` address to perform this call (`owner` has to
         /// call `DelegateWithdrawal { delegated: sender }` before)
         owner: Option<String>,
-        /// Address where to transfer funds. If not present, funds would be sent to `sender`.
+        /// Address where to transfer funds. If not present, funds would be sent to the reward
+        /// receiver of `owner` (see `SetRewardReceiver`) or to `sender` if there is none.
         receiver: Option<String>,
     },
     /// Sets given address as allowed for senders funds withdrawal. Funds still can be withdrawn by
@@ -72,11 +176,36 @@ pub enum ExecuteMsg {
         /// to own address.
         delegated: String,
     },
+    /// Sets the address the sender's rewards are sent to when they are withdrawn without an explicit
+    /// receiver, regardless of who withdraws them. `None` clears it, so rewards go to the withdrawing
+    /// account again.
+    SetRewardReceiver { receiver: Option<String> },
     /// Fund a distribution flow with 1 or more native tokens, updating each provided native token's reward config appropriately.
     /// The x-values of the given curve are interpreted as seconds from now (so you probably want to start with `0`) and
     /// the y-values as locked rewards that should not be distributed at that point in time.
     /// Funds to be provided are included in `info.funds`
     FundDistribution { curve: Curve },
+    /// Withdraws the sender's rewards, provides them as liquidity to the given pair and bonds the
+    /// received liquidity tokens into `unbonding_period`. The pair's liquidity token must be the staked token.
+    /// Rewards in assets that are not part of the pair are skipped and stay withdrawable.
+    CompoundRewards {
+        unbonding_period: u64,
+        /// The pair to provide the rewards to
+        pair: String,
+        /// Slippage tolerance used when providing the rewards as liquidity
+        max_spread: Option<Decimal>,
+    },
+    /// Withdraws the sender's rewards in the staked token and bonds them into `unbonding_period`
+    /// in the same transaction. Rewards in other assets stay withdrawable.
+    WithdrawRewardsToStake { unbonding_period: u64 },
+    /// Internal use
+    /// Bonds the liquidity tokens received by `CompoundRewards`
+    BondCompounded {
+        owner: String,
+        unbonding_period: u64,
+        /// The staked token balance of this contract before the rewards were provided
+        prev_balance: Uint128,
+    },
 }
 
 #[cw_serde]
@@ -95,6 +224,11 @@ pub enum ReceiveDelegationMsg {
         unbonding_period: u64,
         delegate_to: Vec<(String, Uint128)>,
     },
+    /// Same as `MassDelegate`, but with a separate unbonding period (in seconds) for each delegation.
+    /// The total amount in delegations must be <= the amount of tokens sent.
+    MassDelegateMulti {
+        delegations: Vec<(String, Uint128, UnbondingPeriod)>,
+    },
     /// Fund a distribution flow with cw20 tokens and update the Reward Config for that cw20 asset.
     /// The x-values of the given curve are interpreted as seconds from now (so you probably want to start with `0`) and
     /// the y-values as locked rewards that should not be distributed at that point in time.
@@ -104,9 +238,19 @@ pub enum ReceiveDelegationMsg {
 #[cw_serde]
 #[derive(QueryResponses)]
 pub enum QueryMsg {
-    /// Claims shows the tokens in process of unbonding for this address
+    /// Claims shows the tokens in process of unbonding for this address,
+    /// including the ones that are paid out to a claim recipient
     #[returns(cw_controllers::ClaimsResponse)]
     Claims { address: String },
+    /// Same as `Claims`, but returns at most `limit` claims, ordered by their release time.
+    /// Claims released at the same time are always returned on the same page, which can exceed `limit` for them.
+    #[returns(cw_controllers::ClaimsResponse)]
+    ClaimsPaginated {
+        address: String,
+        /// The release timestamp (in seconds) of the last claim of the previous page
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
     /// Show the number of tokens currently staked by this address.
     #[returns(StakedResponse)]
     Staked {
@@ -114,21 +258,57 @@ pub enum QueryMsg {
         /// Unbonding period in seconds
         unbonding_period: u64,
     },
+    /// Show when the locked tokens staked by this address in the given unbonding period unlock.
+    /// Tokens are locked when rebonding to a shorter unbonding period.
+    #[returns(LockScheduleResponse)]
+    LockSchedule {
+        address: String,
+        /// Unbonding period in seconds
+        unbonding_period: u64,
+    },
+    /// Show the number of tokens staked by this address over all unbonding periods
+    /// at the start of the given block height
+    #[returns(StakedAtResponse)]
+    StakedAt { address: String, height: u64 },
     /// Show the number of tokens currently staked by this address for all unbonding periods
     #[returns(AllStakedResponse)]
     AllStaked { address: String },
     /// Show the number of all, not unbonded tokens delegated by all users for all unbonding periods
     #[returns(TotalStakedResponse)]
     TotalStaked {},
+    /// Show the number of all, not unbonded tokens delegated by all users at the start of the given
+    /// block height, both in total and per unbonding period
+    #[returns(TotalStakedAtResponse)]
+    TotalStakedAt { height: u64 },
     /// Show the number of all tokens being unbonded for all unbonding periods
     #[returns(TotalUnbondingResponse)]
     TotalUnbonding {},
+    /// Show the number of unique stakers and their positions (stakes per unbonding period),
+    /// together with the totals of `TotalStaked` and `TotalUnbonding`
+    #[returns(StakingMetricsResponse)]
+    StakingMetrics {},
     /// Show the total number of outstanding rewards
     #[returns(RewardsPowerResponse)]
     TotalRewardsPower {},
+    /// Show the total rewards power of each unbonding period
+    #[returns(TotalRewardsPowerPerPeriodResponse)]
+    TotalRewardsPowerPerPeriod {},
     /// Show the outstanding rewards for this address
     #[returns(RewardsPowerResponse)]
     RewardsPower { address: String },
+    /// Show how the rewards power of this address in each distribution would change
+    /// if it executed `ExecuteMsg::Rebond` with the same parameters now.
+    /// Fails like the rebond itself would.
+    #[returns(SimulateRebondResponse)]
+    SimulateRebond {
+        address: String,
+        amount: Uint128,
+        bond_from: UnbondingPeriod,
+        bond_to: UnbondingPeriod,
+        /// See `ExecuteMsg::Rebond`
+        #[serde(default)]
+        include_locked: bool,
+    },
     /// Return AdminResponse
     #[returns(cw_controllers::AdminResponse)]
     Admin {},
@@ -138,10 +318,23 @@ pub enum QueryMsg {
     /// Return how many rewards will be received per token in each unbonding period in one year
     #[returns(AnnualizedRewardsResponse)]
     AnnualizedRewards {},
+    /// Same as `AnnualizedRewards`, but only for the given unbonding period
+    #[returns(Vec<AnnualizedReward>)]
+    AnnualizedRewardsForPeriod { unbonding_period: UnbondingPeriod },
     /// Return how many rewards are assigned for withdrawal from the given address. Returns
     /// `RewardsResponse`.
     #[returns(WithdrawableRewardsResponse)]
     WithdrawableRewards { owner: String },
+    /// Return how many rewards of the given asset are assigned for withdrawal from the given address.
+    /// Fails if there is no distribution for that asset.
+    #[returns(WithdrawableRewardResponse)]
+    WithdrawableReward { owner: String, asset: AssetInfo },
+    /// Return the rewards assigned for withdrawal from the given address, together with an estimate
+    /// of what it will receive over the next `horizon_seconds`, from the reward curves as well as
+    /// from the undistributed rewards.
+    /// This is only an estimate, which assumes that the total rewards power stays constant.
+    #[returns(ProjectedRewardsResponse)]
+    ProjectedRewards { owner: String, horizon_seconds: u64 },
     /// Return how many rewards were distributed in total by this contract. Returns
     /// `RewardsResponse`.
     #[returns(DistributedRewardsResponse)]
@@ -150,15 +343,46 @@ pub enum QueryMsg {
     /// and await for distribution. Returns `RewardsResponse`.
     #[returns(UndistributedRewardsResponse)]
     UndistributedRewards {},
+    /// Show how distributing `amount` of the given asset with `ExecuteMsg::DistributeRewards` now
+    /// would change its distribution, without the rewards that are already waiting for distribution.
+    /// Fails if there is no distribution for that asset.
+    #[returns(SimulateDistributionResponse)]
+    SimulateDistribution { asset: AssetInfo, amount: Uint128 },
     /// Return address allowed for withdrawal of the funds assigned to owner. Returns `DelegatedResponse`
     #[returns(DelegatedResponse)]
     Delegated { owner: String },
+    /// Return the owners that delegated their withdrawals to `delegate`, ordered by address.
+    /// Returns `DelegatedToResponse`
+    #[returns(DelegatedToResponse)]
+    DelegatedTo {
+        delegate: String,
+        /// The last owner of the previous page
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     /// Returns rewards distribution data
     #[returns(DistributionDataResponse)]
     DistributionData {},
     /// Returns withdraw adjustment data
     #[returns(WithdrawAdjustmentDataResponse)]
     WithdrawAdjustmentData { addr: String, asset: AssetInfo },
+    /// Returns the reward curve of the given asset. Its x-values are timestamps in seconds
+    /// and its y-values the rewards that are still locked at that point in time.
+    /// Fails if there is no distribution for that asset.
+    #[returns(Curve)]
+    RewardCurve { asset: AssetInfo },
+    /// Returns the reward curves of all distributions
+    #[returns(RewardCurvesResponse)]
+    RewardCurves {},
+    /// Returns the log of admin actions, like admin changes, distribution creations and
+    /// reward multiplier updates, oldest first.
+    /// Only the most recent `MAX_ADMIN_ACTIONS` entries are kept.
+    #[returns(AdminActionsResponse)]
+    AdminActions {
+        /// The id of the last entry of the previous page
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
 }
 
 #[cw_serde]
@@ -166,24 +390,73 @@ pub struct StakedResponse {
     pub stake: Uint128,
     pub total_locked: Uint128,
     pub unbonding_period: u64,
+    /// The address of the staked cw20 token, or the denom of the staked native token
     pub cw20_contract: String,
 }
 
+#[cw_serde]
+pub struct LockScheduleResponse {
+    /// The staked tokens that can be unbonded right now
+    pub unlocked: Uint128,
+    /// The amounts of staked tokens that are still locked, together with the time they unlock,
+    /// sorted by time
+    pub locked_tokens: Vec<(Timestamp, Uint128)>,
+}
+
 #[cw_serde]
 pub struct AllStakedResponse {
     pub stakes: Vec<StakedResponse>,
 }
 
+#[cw_serde]
+pub struct StakedAtResponse {
+    pub stake: Uint128,
+    pub height: u64,
+}
+
 #[cw_serde]
 pub struct TotalStakedResponse {
     pub total_staked: Uint128,
 }
 
+#[cw_serde]
+pub struct TotalStakedAtResponse {
+    pub total_staked: Uint128,
+    pub total_unbonding: Uint128,
+    /// The total stake of each unbonding period that existed at that height
+    pub bonding: Vec<BondingPeriodInfo>,
+    pub height: u64,
+}
+
 #[cw_serde]
 pub struct TotalUnbondingResponse {
     pub total_unbonding: Uint128,
 }
 
+#[cw_serde]
+pub struct SimulateRebondResponse {
+    pub rewards_power: Vec<RebondRewardsPower>,
+}
+
+#[cw_serde]
+pub struct RebondRewardsPower {
+    pub asset: AssetInfoValidated,
+    /// The current rewards power in this distribution
+    pub before: Uint128,
+    /// The rewards power in this distribution after the rebond
+    pub after: Uint128,
+}
+
+#[cw_serde]
+pub struct StakingMetricsResponse {
+    /// The number of addresses with a non-zero stake in at least one unbonding period
+    pub stakers: u64,
+    /// The number of non-zero stakes, counting every unbonding period of a staker separately
+    pub positions: u64,
+    pub total_staked: Uint128,
+    pub total_unbonding: Uint128,
+}
+
 #[cw_serde]
 pub struct RewardsPowerResponse {
     /// The rewards power of the address per asset
@@ -192,6 +465,13 @@ pub struct RewardsPowerResponse {
     pub rewards: Vec<(AssetInfoValidated, Uint128)>,
 }
 
+#[cw_serde]
+pub struct TotalRewardsPowerPerPeriodResponse {
+    /// The total rewards power per asset for each unbonding period.
+    /// As in [`RewardsPowerResponse`], the `Uint128` is rewards power, not an asset amount.
+    pub rewards: Vec<(UnbondingPeriod, Vec<(AssetInfoValidated, Uint128)>)>,
+}
+
 #[cw_serde]
 pub struct BondingPeriodInfo {
     pub unbonding_period: u64,
@@ -228,11 +508,42 @@ pub struct WithdrawableRewardsResponse {
     pub rewards: Vec<AssetValidated>,
 }
 
+#[cw_serde]
+pub struct WithdrawableRewardResponse {
+    /// Amount of rewards of a single asset assigned for withdrawal from the given address.
+    pub reward: AssetValidated,
+}
+
+#[cw_serde]
+pub struct ProjectedRewardsResponse {
+    pub rewards: Vec<ProjectedReward>,
+}
+
+#[cw_serde]
+pub struct ProjectedReward {
+    pub asset: AssetInfoValidated,
+    /// Amount of rewards currently assigned for withdrawal
+    pub withdrawable: Uint128,
+    /// Estimated amount of additional rewards over the horizon,
+    /// based on the owner's share of the total rewards power
+    pub projected: Uint128,
+}
+
 #[cw_serde]
 pub struct DelegatedResponse {
     pub delegated: Addr,
 }
 
+#[cw_serde]
+pub struct DelegatedToResponse {
+    pub owners: Vec<Addr>,
+}
+
+#[cw_serde]
+pub struct AdminActionsResponse {
+    pub actions: Vec<crate::state::AdminActionEntry>,
+}
+
 #[cw_serde]
 pub struct DistributedRewardsResponse {
     /// Total number of tokens sent to the contract over all time.
@@ -241,9 +552,25 @@ pub struct DistributedRewardsResponse {
     pub withdrawable: Vec<AssetValidated>,
 }
 
+#[cw_serde]
+pub struct SimulateDistributionResponse {
+    /// How much the distribution's `shares_per_point` increases.
+    /// Each staker receives its rewards power times this, shifted right by 32 bits.
+    pub shares_per_point_increment: Uint128,
+    /// The distribution's `shares_per_point` after distributing
+    pub shares_per_point: Uint128,
+    /// Total number of tokens available to be withdrawn after distributing
+    pub withdrawable_total: Uint128,
+}
+
 pub type UndistributedRewardsResponse = WithdrawableRewardsResponse;
 #[cw_serde]
 pub struct DistributionDataResponse {
     pub distributions: Vec<(AssetInfoValidated, crate::state::Distribution)>,
 }
 pub type WithdrawAdjustmentDataResponse = crate::state::WithdrawAdjustment;
+
+#[cw_serde]
+pub struct RewardCurvesResponse {
+    pub curves: Vec<(AssetInfoValidated, Curve)>,
+}
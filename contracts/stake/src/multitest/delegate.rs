@@ -1,7 +1,11 @@
-use cosmwasm_std::Uint128;
-use cw_controllers::Claim;
+use cosmwasm_std::{coin, Decimal, Event, OverflowError, OverflowOperation, StdError, Uint128};
+use cw_controllers::{AdminError, Claim};
+use cw_utils::{Expiration, PaymentError};
 
-use super::suite::{SuiteBuilder, SEVEN_DAYS};
+use sg_swap::asset::AssetInfo;
+
+use super::suite::{juno_power, Suite, SuiteBuilder, SEVEN_DAYS};
+use crate::ContractError;
 
 #[test]
 fn delegate_and_unbond_tokens_still_vested() {
@@ -162,3 +166,661 @@ fn mass_delegation_simple_case() {
         50_000u128
     );
 }
+
+#[test]
+fn paginated_claims() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    suite.delegate(user, 50_000u128, unbonding_period1).unwrap();
+    suite.delegate(user, 50_000u128, unbonding_period2).unwrap();
+
+    // creates claims releasing at start + 1000, 1500, ..., 3500 and start + 4000, 4500, ..., 6500
+    for _ in 0..6 {
+        suite.unbond(user, 1_000u128, unbonding_period1).unwrap();
+        suite.unbond(user, 2_000u128, unbonding_period2).unwrap();
+        suite.update_time(500);
+    }
+    // these release at the same time as the first claim of the second period
+    suite.unbond(user, 500u128, unbonding_period1).unwrap();
+    suite.unbond(user, 500u128, unbonding_period1).unwrap();
+    assert_eq!(suite.query_claims(user).unwrap().len(), 14);
+
+    let claim = |amount: u128, offset: u64| {
+        Claim::new(amount, Expiration::AtTime(start.plus_seconds(offset)))
+    };
+    // claims are returned in the order they were created, without merging the ones released together
+    let created: Vec<_> = (0..6)
+        .flat_map(|i| [claim(1_000, 1000 + 500 * i), claim(2_000, 4000 + 500 * i)])
+        .chain([claim(500, 4000), claim(500, 4000)])
+        .collect();
+    assert_eq!(suite.query_claims(user).unwrap(), created);
+
+    // pages are ordered by release time, claims released together stay in the order they were created
+    let expected: Vec<_> = (0..6)
+        .map(|i| claim(1_000, 1000 + 500 * i))
+        .chain([claim(2_000, 4000), claim(500, 4000), claim(500, 4000)])
+        .chain((1..6).map(|i| claim(2_000, 4000 + 500 * i)))
+        .collect();
+
+    // page through all claims
+    let release_time = |claim: &Claim| match claim.release_at {
+        Expiration::AtTime(time) => time.seconds(),
+        _ => unreachable!(),
+    };
+    let mut claims: Vec<Claim> = vec![];
+    loop {
+        let page = suite
+            .query_claims_paginated(user, claims.last().map(release_time), Some(5))
+            .unwrap();
+        if page.is_empty() {
+            break;
+        }
+        assert!(page.len() <= 5);
+        claims.extend(page);
+    }
+    assert_eq!(claims, expected);
+
+    // the default limit is 10
+    assert_eq!(
+        suite.query_claims_paginated(user, None, None).unwrap(),
+        expected[..10]
+    );
+    // claims released at the same time are not split across pages
+    assert_eq!(
+        suite
+            .query_claims_paginated(user, Some(release_time(&expected[4])), Some(2))
+            .unwrap(),
+        expected[5..9]
+    );
+
+    // releasing claims between two pages does not skip any of the remaining ones
+    let first_page = suite.query_claims_paginated(user, None, Some(5)).unwrap();
+    assert_eq!(first_page, expected[..5]);
+    // the claims up to start + 3000 are mature by now
+    suite.claim(user).unwrap();
+    assert_eq!(suite.query_claims(user).unwrap().len(), 9);
+    assert_eq!(
+        suite
+            .query_claims_paginated(user, first_page.last().map(release_time), Some(5))
+            .unwrap(),
+        expected[5..10]
+    );
+}
+
+#[test]
+fn unbond_all_periods() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    // nothing staked yet
+    let err = suite.unbond_all(user).unwrap_err();
+    assert_eq!(ContractError::NothingToUnbond {}, err.downcast().unwrap());
+
+    suite.delegate(user, 30_000u128, unbonding_period1).unwrap();
+    suite.delegate(user, 50_000u128, unbonding_period2).unwrap();
+    // rebonding to the shorter period locks the tokens for the difference of 3000 seconds
+    suite
+        .rebond(user, 10_000u128, unbonding_period2, unbonding_period1)
+        .unwrap();
+
+    let res = suite.unbond_all(user).unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("action", "unbond_all")
+            .add_attribute("amount", "70000")
+            .add_attribute("locked", "10000")
+            .add_attribute("unbonded_1000", "30000")
+            .add_attribute("unbonded_4000", "40000"),
+    );
+
+    // only the locked tokens remain staked
+    assert_eq!(suite.query_staked(user, unbonding_period1).unwrap(), 10_000);
+    assert_eq!(suite.query_staked(user, unbonding_period2).unwrap(), 0);
+    assert_eq!(
+        suite.query_claims(user).unwrap(),
+        vec![
+            Claim::new(30_000, Expiration::AtTime(start.plus_seconds(1000))),
+            Claim::new(40_000, Expiration::AtTime(start.plus_seconds(4000))),
+        ]
+    );
+
+    let err = suite.unbond_all(user).unwrap_err();
+    assert_eq!(ContractError::NothingToUnbond {}, err.downcast().unwrap());
+
+    // once unlocked, the rest can be unbonded as well
+    suite.update_time(3000);
+    suite.unbond_all(user).unwrap();
+    assert_eq!(suite.query_staked(user, unbonding_period1).unwrap(), 0);
+    assert_eq!(suite.query_claims(user).unwrap().len(), 3);
+
+    suite.update_time(1000);
+    suite.claim(user).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(user).unwrap(),
+        100_000u128
+    );
+}
+
+#[test]
+fn staking_metrics_count_stakers_and_positions() {
+    let user1 = "user1";
+    let user2 = "user2";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_initial_balances(vec![(user1, 100_000), (user2, 100_000)])
+        .build();
+
+    let metrics = |suite: &Suite| {
+        let metrics = suite.query_staking_metrics().unwrap();
+        (
+            metrics.stakers,
+            metrics.positions,
+            metrics.total_staked.u128(),
+            metrics.total_unbonding.u128(),
+        )
+    };
+    assert_eq!(metrics(&suite), (0, 0, 0, 0));
+
+    // the first position creates a staker, further bonds in the same period do not count
+    suite
+        .delegate(user1, 30_000u128, unbonding_period1)
+        .unwrap();
+    suite
+        .delegate(user1, 10_000u128, unbonding_period1)
+        .unwrap();
+    assert_eq!(metrics(&suite), (1, 1, 40_000, 0));
+
+    // a second period is another position of the same staker
+    suite
+        .delegate(user1, 20_000u128, unbonding_period2)
+        .unwrap();
+    suite.delegate(user2, 5_000u128, unbonding_period2).unwrap();
+    assert_eq!(metrics(&suite), (2, 3, 65_000, 0));
+
+    // rebonding everything closes the old position
+    suite
+        .rebond(user1, 40_000u128, unbonding_period1, unbonding_period2)
+        .unwrap();
+    assert_eq!(metrics(&suite), (2, 2, 65_000, 0));
+
+    // partially unbonding keeps the position
+    suite.unbond(user2, 2_000u128, unbonding_period2).unwrap();
+    assert_eq!(metrics(&suite), (2, 2, 63_000, 2_000));
+
+    // unbonding the last position removes the staker
+    suite.unbond(user2, 3_000u128, unbonding_period2).unwrap();
+    assert_eq!(metrics(&suite), (1, 1, 60_000, 5_000));
+
+    // bonding again counts the staker again
+    suite.delegate(user2, 1_000u128, unbonding_period1).unwrap();
+    assert_eq!(metrics(&suite), (2, 2, 61_000, 5_000));
+}
+
+#[test]
+fn claim_reports_released_claims() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![1000])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+
+    suite.delegate(user, 60_000u128, 1000).unwrap();
+    // three claims, releasing after 1000, 1100 and 1200 seconds
+    for _ in 0..3 {
+        suite.unbond(user, 10_000u128, 1000).unwrap();
+        suite.update_time(100);
+    }
+
+    // the first two claims are mature
+    suite.update_time(800);
+    let res = suite.claim(user).unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("action", "claim")
+            .add_attribute("released", "20000")
+            .add_attribute("claims_released", "2")
+            .add_attribute("recipient", user),
+    );
+    assert_eq!(suite.query_claims(user).unwrap().len(), 1);
+
+    suite.update_time(100);
+    let res = suite.claim(user).unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("released", "10000")
+            .add_attribute("claims_released", "1")
+            .add_attribute("recipient", user),
+    );
+    assert_eq!(suite.query_claims(user).unwrap().len(), 0);
+}
+
+#[test]
+fn claim_pays_claim_recipient() {
+    let user = "user";
+    let custodian = "custodian";
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![1000])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+
+    suite.delegate(user, 60_000u128, 1000).unwrap();
+    suite
+        .unbond_with_claim_recipient(user, 10_000u128, 1000, custodian)
+        .unwrap();
+    suite.unbond(user, 20_000u128, 1000).unwrap();
+
+    // nothing to claim before the unbonding period is over
+    suite.update_time(500);
+    let err = suite.claim(user).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+
+    suite.update_time(500);
+    let res = suite.claim(user).unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("action", "claim")
+            .add_attribute("released", "30000")
+            .add_attribute("claims_released", "2"),
+    );
+
+    // the claim with a recipient went to the custodian, the other one to the staker
+    assert_eq!(
+        suite.query_balance_vesting_contract(custodian).unwrap(),
+        10_000
+    );
+    assert_eq!(suite.query_balance_vesting_contract(user).unwrap(), 60_000);
+
+    // everything was claimed
+    let err = suite.claim(user).unwrap_err();
+    assert_eq!(ContractError::NothingToClaim {}, err.downcast().unwrap());
+}
+
+#[test]
+fn claims_include_claim_recipient() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![1000])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    suite.delegate(user, 60_000u128, 1000).unwrap();
+    suite.unbond(user, 20_000u128, 1000).unwrap();
+    suite.update_time(100);
+    suite
+        .unbond_with_claim_recipient(user, 10_000u128, 1000, "custodian")
+        .unwrap();
+
+    let expected = vec![
+        Claim::new(20_000, Expiration::AtTime(start.plus_seconds(1000))),
+        Claim::new(10_000, Expiration::AtTime(start.plus_seconds(1100))),
+    ];
+    assert_eq!(suite.query_claims(user).unwrap(), expected);
+    assert_eq!(
+        suite.query_claims_paginated(user, None, None).unwrap(),
+        expected
+    );
+
+    // the claim recipient does not own the claim
+    assert_eq!(suite.query_claims("custodian").unwrap(), vec![]);
+}
+
+#[test]
+fn pending_claims_are_limited() {
+    let user = "user";
+    let admin = "admin";
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![1000])
+        .with_initial_balances(vec![(user, 100_000)])
+        .with_admin(admin)
+        .build();
+
+    // only the admin can change the limit
+    let err = suite.update_max_claims_per_user(user, 3).unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+    suite.update_max_claims_per_user(admin, 3).unwrap();
+
+    suite.delegate(user, 60_000u128, 1000).unwrap();
+    // claims with a claim recipient count as well
+    suite
+        .unbond_with_claim_recipient(user, 10_000u128, 1000, "custodian")
+        .unwrap();
+    suite.unbond(user, 10_000u128, 1000).unwrap();
+    suite.update_time(100);
+    suite.unbond(user, 10_000u128, 1000).unwrap();
+
+    let err = suite.unbond(user, 10_000u128, 1000).unwrap_err();
+    assert_eq!(ContractError::TooManyClaims(3), err.downcast().unwrap());
+
+    // claiming frees the slots of the mature claims
+    suite.update_time(900);
+    suite.claim(user).unwrap();
+    assert_eq!(suite.query_claims(user).unwrap().len(), 1);
+    suite.unbond(user, 10_000u128, 1000).unwrap();
+    suite.unbond(user, 10_000u128, 1000).unwrap();
+    let err = suite.unbond(user, 10_000u128, 1000).unwrap_err();
+    assert_eq!(ContractError::TooManyClaims(3), err.downcast().unwrap());
+}
+
+#[test]
+fn lock_schedule_after_rebond() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    suite.delegate(user, 20_000u128, unbonding_period1).unwrap();
+    suite.delegate(user, 50_000u128, unbonding_period2).unwrap();
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 20_000);
+    assert_eq!(schedule.locked_tokens, vec![]);
+
+    // rebonding to the shorter period locks the tokens for the difference of 3000 seconds
+    suite
+        .rebond(user, 10_000u128, unbonding_period2, unbonding_period1)
+        .unwrap();
+    suite.update_time(100);
+    suite
+        .rebond(user, 5_000u128, unbonding_period2, unbonding_period1)
+        .unwrap();
+
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 20_000);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![
+            (start.plus_seconds(3000), Uint128::new(10_000)),
+            (start.plus_seconds(3100), Uint128::new(5_000)),
+        ]
+    );
+    // nothing is locked in the longer period
+    let schedule = suite.query_lock_schedule(user, unbonding_period2).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 35_000);
+    assert_eq!(schedule.locked_tokens, vec![]);
+
+    // the first entry unlocks
+    suite.update_time(2900);
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 30_000);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![(start.plus_seconds(3100), Uint128::new(5_000))]
+    );
+
+    suite.update_time(100);
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 35_000);
+    assert_eq!(schedule.locked_tokens, vec![]);
+
+    // unknown unbonding periods are rejected
+    suite.query_lock_schedule(user, 2000).unwrap_err();
+}
+
+#[test]
+fn rebond_including_locked_tokens() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 2000u64;
+    let unbonding_period3 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![
+            unbonding_period1,
+            unbonding_period2,
+            unbonding_period3,
+        ])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    suite.delegate(user, 5_000u128, unbonding_period1).unwrap();
+    suite.delegate(user, 50_000u128, unbonding_period3).unwrap();
+    // locks 10_000 tokens in the shortest period until `start + 3000`
+    suite
+        .rebond(user, 10_000u128, unbonding_period3, unbonding_period1)
+        .unwrap();
+    suite.update_time(100);
+
+    // only the unlocked tokens can be rebonded by default
+    let err = suite
+        .rebond(user, 12_000u128, unbonding_period1, unbonding_period2)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Std(StdError::overflow(OverflowError::new(
+            OverflowOperation::Sub,
+            5_000u128,
+            12_000u128
+        ))),
+        err.downcast().unwrap()
+    );
+
+    // moving to a longer period shortens the lock by the difference of 1000 seconds
+    suite
+        .rebond_including_locked(user, 12_000u128, unbonding_period1, unbonding_period2)
+        .unwrap();
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 0);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![(start.plus_seconds(3000), Uint128::new(3_000))]
+    );
+    let schedule = suite.query_lock_schedule(user, unbonding_period2).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 5_000);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![(start.plus_seconds(2000), Uint128::new(7_000))]
+    );
+
+    // the lock is gone once the difference covers it
+    suite
+        .rebond_including_locked(user, 3_000u128, unbonding_period1, unbonding_period3)
+        .unwrap();
+    let schedule = suite.query_lock_schedule(user, unbonding_period3).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 43_000);
+    assert_eq!(schedule.locked_tokens, vec![]);
+
+    // moving to a shorter period extends the lock by the difference of 1000 seconds
+    // and locks the unlocked tokens for the difference as well
+    suite
+        .rebond_including_locked(user, 12_000u128, unbonding_period2, unbonding_period1)
+        .unwrap();
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 0);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![
+            (start.plus_seconds(1100), Uint128::new(5_000)),
+            (start.plus_seconds(3000), Uint128::new(7_000)),
+        ]
+    );
+    assert_eq!(suite.query_staked(user, unbonding_period2).unwrap(), 0);
+}
+
+#[test]
+fn transfer_partially_locked_stake() {
+    let user = "user";
+    let recipient = "recipient";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+    let start = suite.app.block_info().time;
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user,
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::one()),
+                (unbonding_period2, Decimal::percent(200)),
+            ],
+        )
+        .unwrap();
+
+    // 20_000 unlocked and 10_000 locked in the first period
+    suite.delegate(user, 20_000u128, unbonding_period1).unwrap();
+    suite.delegate(user, 50_000u128, unbonding_period2).unwrap();
+    suite
+        .rebond(user, 10_000u128, unbonding_period2, unbonding_period1)
+        .unwrap();
+    assert_eq!(suite.query_rewards_power(user).unwrap(), juno_power(110));
+
+    let err = suite
+        .transfer_stake(user, user, 1_000u128, unbonding_period1)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TransferStakeToSelf {},
+        err.downcast().unwrap()
+    );
+    let err = suite
+        .transfer_stake(user, recipient, 1_000u128, 2000)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::NoUnbondingPeriodFound(2000),
+        err.downcast().unwrap()
+    );
+
+    // the unlocked tokens move first, then the locked ones keep their unlock time
+    suite
+        .transfer_stake(user, recipient, 25_000u128, unbonding_period1)
+        .unwrap();
+
+    let schedule = suite.query_lock_schedule(user, unbonding_period1).unwrap();
+    assert_eq!(schedule.unlocked.u128(), 0);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![(start.plus_seconds(3000), Uint128::new(5_000))]
+    );
+    let schedule = suite
+        .query_lock_schedule(recipient, unbonding_period1)
+        .unwrap();
+    assert_eq!(schedule.unlocked.u128(), 20_000);
+    assert_eq!(
+        schedule.locked_tokens,
+        vec![(start.plus_seconds(3000), Uint128::new(5_000))]
+    );
+    // the stake in the other period is untouched
+    assert_eq!(
+        suite.query_staked(user, unbonding_period2).unwrap(),
+        40_000u128
+    );
+    assert_eq!(
+        suite.query_staked(recipient, unbonding_period2).unwrap(),
+        0u128
+    );
+
+    // 5 + 40 * 2 for the user, 25 for the recipient, so the total does not change
+    assert_eq!(suite.query_rewards_power(user).unwrap(), juno_power(85));
+    assert_eq!(
+        suite.query_rewards_power(recipient).unwrap(),
+        juno_power(25)
+    );
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(110));
+
+    // the recipient can unbond the unlocked part right away
+    suite
+        .unbond(recipient, 20_000u128, unbonding_period1)
+        .unwrap();
+    assert_eq!(suite.query_rewards_power(recipient).unwrap(), juno_power(5));
+}
+
+#[test]
+fn native_bond_and_claim() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_native_staked_token("ustake")
+        .with_native_balances("ustake", vec![(user, 100_000)])
+        .with_native_balances("juno", vec![(user, 10_000)])
+        .with_initial_balances(vec![(user, 10_000)])
+        .build();
+
+    // only the staked denom can be bonded
+    let err = suite
+        .bond_native(user, &[coin(10_000, "juno")], None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Payment(PaymentError::MissingDenom("ustake".to_owned())),
+        err.downcast().unwrap()
+    );
+    let err = suite
+        .bond_native(user, &[coin(10_000, "ustake"), coin(10_000, "juno")], None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Payment(PaymentError::MultipleDenoms {}),
+        err.downcast().unwrap()
+    );
+    // and cw20 tokens are not accepted
+    let err = suite.delegate(user, 10_000, None).unwrap_err();
+    assert_eq!(
+        ContractError::Cw20AddressesNotMatch {
+            got: suite.token_contract(),
+            expected: "ustake".to_owned(),
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .bond_native(user, &[coin(60_000, "ustake")], None)
+        .unwrap();
+    assert_eq!(suite.query_staked(user, None).unwrap(), 60_000);
+    assert_eq!(suite.query_balance(user, "ustake").unwrap(), 40_000);
+    assert_eq!(
+        suite
+            .query_balance(&suite.stake_contract(), "ustake")
+            .unwrap(),
+        60_000
+    );
+
+    suite.unbond(user, 60_000, None).unwrap();
+    suite.update_time(SEVEN_DAYS);
+    suite.claim(user).unwrap();
+    assert_eq!(suite.query_staked(user, None).unwrap(), 0);
+    assert_eq!(suite.query_balance(user, "ustake").unwrap(), 100_000);
+    assert_eq!(
+        suite
+            .query_balance(&suite.stake_contract(), "ustake")
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn native_bond_requires_native_staked_token() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_native_balances("ustake", vec![(user, 10_000)])
+        .build();
+
+    let err = suite
+        .bond_native(user, &[coin(10_000, "ustake")], None)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::NativeStakingDisabled {},
+        err.downcast().unwrap()
+    );
+}
@@ -1,12 +1,15 @@
-use cosmwasm_std::{Addr, Decimal, Uint128};
+use cosmwasm_std::{assert_approx_eq, coin, Addr, Decimal, Event, Uint128};
 use cw20::{Cw20Coin, MinterResponse};
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
+use cw_controllers::AdminError;
 use cw_multi_test::Executor;
 use sg_swap::asset::{AssetInfo, AssetInfoExt, AssetInfoValidated};
+use wynd_curve_utils::Curve;
 
-use super::suite::{contract_token, SuiteBuilder};
+use super::suite::{contract_token, Suite, SuiteBuilder};
 use crate::{
     multitest::suite::{juno, juno_power, native_token},
+    state::{AdminAction, AdminActionEntry, MAX_ADMIN_ACTIONS},
     ContractError,
 };
 
@@ -375,6 +378,313 @@ fn mass_bond_with_multiple_distribution_flows() {
     );
 }
 
+#[test]
+fn mass_bond_with_different_unbonding_periods() {
+    let members = vec![
+        "member1".to_owned(),
+        "member2".to_owned(),
+        "member3".to_owned(),
+        "member4".to_owned(),
+    ];
+    let richie = "richie rich";
+    let short = 1000u64;
+    let long = 2000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![short, long])
+        .with_initial_balances(vec![(richie, 30_000u128)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(&members[3], 350)])
+        .build();
+
+    // the longer period gives twice the rewards power
+    suite
+        .create_distribution_flow(
+            "admin",
+            &members[0],
+            AssetInfo::Native("juno".to_string()),
+            vec![(short, Decimal::one()), (long, Decimal::percent(200))],
+        )
+        .unwrap();
+
+    // every delegation needs a valid unbonding period
+    let err = suite
+        .mass_delegate_multi(
+            richie,
+            20_000,
+            &[(&members[0], 5_000, short), (&members[1], 10_000, 1500)],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::NoUnbondingPeriodFound(1500),
+        err.downcast().unwrap()
+    );
+
+    // the delegations cannot add up to more than was sent
+    let delegations: &[(&str, u128, u64)] = &[
+        (&members[0], 5_000, short),
+        (&members[1], 10_000, long),
+        (&members[2], 5_000, long),
+    ];
+    let err = suite
+        .mass_delegate_multi(richie, 19_999, delegations)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::MassDelegateTooMuch {
+            total: Uint128::new(20_000),
+            amount_sent: Uint128::new(19_999),
+        },
+        err.downcast().unwrap()
+    );
+
+    suite
+        .mass_delegate_multi(richie, 20_000, delegations)
+        .unwrap();
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 20_000);
+    assert_eq!(suite.query_staked(&members[0], short).unwrap(), 5_000);
+    assert_eq!(suite.query_staked(&members[0], long).unwrap(), 0);
+    assert_eq!(suite.query_staked(&members[1], short).unwrap(), 0);
+    assert_eq!(suite.query_staked(&members[1], long).unwrap(), 10_000);
+    assert_eq!(suite.query_staked(&members[2], long).unwrap(), 5_000);
+
+    // rewards powers are 5, 20 and 10
+    suite
+        .execute_fund_distribution(&members[3], None, juno(350))
+        .unwrap();
+    suite.update_time(100);
+    suite.distribute_funds(&members[3], None, None).unwrap();
+
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(50)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(&members[1]).unwrap(),
+        vec![juno(200)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(&members[2]).unwrap(),
+        vec![juno(100)]
+    );
+}
+
+#[test]
+fn query_reward_curves() {
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 1000)])
+        .build();
+
+    // no distribution for juno yet
+    suite
+        .query_reward_curve(AssetInfo::Native("juno".to_string()))
+        .unwrap_err();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    let start = suite.app.block_info().time.seconds();
+    suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap();
+
+    // the curve is stored relative to the time it was funded
+    let curve = suite
+        .query_reward_curve(AssetInfo::Native("juno".to_string()))
+        .unwrap();
+    assert_eq!(curve.value(start), 1000);
+    assert_eq!(curve.value(start + 50), 750);
+    assert_eq!(curve.value(start + 100), 500);
+    assert_eq!(curve.value(start + 200), 0);
+    assert_eq!(curve.value(start + 1000), 0);
+
+    assert_eq!(
+        suite.query_reward_curves().unwrap(),
+        vec![(AssetInfoValidated::Native("juno".to_string()), curve)]
+    );
+}
+
+#[test]
+fn projected_rewards_follow_the_curve() {
+    let members = vec!["member1".to_owned(), "member2".to_owned()];
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(&members[0], 5_000), (&members[1], 15_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 1000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .delegate(&members[0], 1_000, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(&members[1], 3_000, unbonding_period)
+        .unwrap();
+
+    suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap();
+
+    let projected = |suite: &Suite, member: &str, horizon_seconds: u64| {
+        let rewards = suite.projected_rewards(member, horizon_seconds).unwrap();
+        assert_eq!(rewards.len(), 1);
+        assert_eq!(
+            rewards[0].asset,
+            AssetInfoValidated::Native("juno".to_string())
+        );
+        (rewards[0].withdrawable.u128(), rewards[0].projected.u128())
+    };
+
+    // half of the curve is released over the next 100 seconds, split by rewards power
+    assert_eq!(projected(&suite, &members[0], 100), (0, 125));
+    assert_eq!(projected(&suite, &members[1], 100), (0, 375));
+
+    // released rewards that were not distributed yet are included
+    suite.update_time(100);
+    assert_eq!(projected(&suite, &members[0], 50), (0, 187));
+    assert_eq!(projected(&suite, &members[1], 50), (0, 562));
+
+    // once distributed, the released part becomes withdrawable
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(projected(&suite, &members[0], 50), (125, 62));
+    assert_eq!(projected(&suite, &members[1], 50), (375, 187));
+
+    // the projection stops at the end of the curve
+    assert_eq!(projected(&suite, &members[0], 1000), (125, 125));
+    assert_eq!(projected(&suite, &members[1], 1000), (375, 375));
+}
+
+#[test]
+fn paused_distribution_does_not_accrue_rewards() {
+    let members = vec!["member1".to_owned(), "member2".to_owned()];
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(&members[0], 5_000), (&members[1], 15_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 2000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .delegate(&members[0], 1_000, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(&members[1], 3_000, unbonding_period)
+        .unwrap();
+
+    suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap();
+
+    // half of the curve is released after 100 seconds
+    suite.update_time(100);
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(125)]
+    );
+
+    // only the manager can pause the distribution
+    let err = suite
+        .set_distribution_paused(&members[0], juno_info.clone(), true)
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+    suite
+        .set_distribution_paused(funder, juno_info.clone(), true)
+        .unwrap();
+
+    // nothing is distributed or projected while paused
+    suite.update_time(100);
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(125)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(&members[1]).unwrap(),
+        vec![juno(375)]
+    );
+    assert_eq!(
+        suite.projected_rewards(&members[0], 100).unwrap()[0].projected,
+        Uint128::zero()
+    );
+
+    // it cannot be funded while paused
+    let err = suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionPaused(AssetInfoValidated::Native("juno".to_string())),
+        err.downcast().unwrap()
+    );
+
+    // after resuming, the curve continues where it was paused
+    suite
+        .set_distribution_paused(funder, juno_info.clone(), false)
+        .unwrap();
+    let curve = suite.query_reward_curve(juno_info).unwrap();
+    assert_eq!(curve.value(suite.app.block_info().time.seconds()), 500);
+
+    suite.update_time(50);
+    suite.distribute_funds(funder, None, None).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(&members[0]).unwrap(),
+        vec![juno(187)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(&members[1]).unwrap(),
+        vec![juno(562)]
+    );
+}
+
 #[test]
 fn can_fund_an_inprogress_reward_period_with_more_funds_and_a_curve() {
     let members = vec![
@@ -866,16 +1176,891 @@ fn calculate_apr() {
     // 3. 126144000 * 30 / 55 / 10_000 = 6880.5818181818181818
     let annual_rewards = suite.query_annualized_rewards().unwrap();
     assert_eq!(
-        annual_rewards[0].1[0].amount,
-        Some(Decimal::from_atomics(1146763636363636363636u128, 18).unwrap())
+        annual_rewards[0].1[0].amount,
+        Some(Decimal::from_atomics(1146763636363636363636u128, 18).unwrap())
+    );
+    assert_eq!(
+        annual_rewards[1].1[0].amount,
+        Some(Decimal::from_atomics(2293527272727272727272u128, 18).unwrap())
+    );
+    assert_eq!(
+        annual_rewards[2].1[0].amount,
+        Some(Decimal::from_atomics(6880581818181818181818u128, 18).unwrap())
+    );
+}
+
+#[test]
+fn apr_of_degenerate_distributions() {
+    let distributor = "distributor";
+    let member = "member";
+    let unbonding_periods = vec![100u64, 1000u64];
+
+    // without tokens per power no rewards power can be calculated
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_tokens_per_power(0)
+        .build();
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_periods[0], Decimal::one()),
+                (unbonding_periods[1], Decimal::percent(200)),
+            ],
+        )
+        .unwrap();
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+    assert_eq!(annual_rewards.len(), 2);
+    for (_, rewards) in &annual_rewards {
+        assert_eq!(rewards[0].amount, None);
+    }
+    assert_eq!(
+        suite
+            .query_annualized_rewards_for_period(unbonding_periods[1])
+            .unwrap()[0]
+            .amount,
+        None
+    );
+
+    // stake that is too small to get any rewards power leaves the distribution empty
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_initial_balances(vec![(member, 1_000_000)])
+        .with_native_balances("juno", vec![(distributor, 100_000_000)])
+        .build();
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_periods[0], Decimal::percent(10)),
+                (unbonding_periods[1], Decimal::percent(10)),
+            ],
+        )
+        .unwrap();
+    // 5_000 * 0.1 / 1000 = 0
+    suite.delegate(member, 5_000, unbonding_periods[0]).unwrap();
+    suite
+        .execute_fund_distribution(distributor, None, juno(400))
+        .unwrap();
+    assert_eq!(suite.query_total_rewards_power().unwrap(), vec![]);
+
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+    // the first period has stake, the second one has none, but neither has rewards power
+    assert_eq!(annual_rewards[0].1[0].amount, None);
+    assert_eq!(annual_rewards[1].1[0].amount, None);
+}
+
+#[test]
+fn update_reward_multipliers() {
+    let distributor = "distributor";
+    let member = "member";
+    let unbonding_periods = vec![100u64, 1000u64];
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_initial_balances(vec![(member, 1_000_000)])
+        .with_native_balances("juno", vec![(distributor, 100_000_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info.clone(),
+            vec![
+                (unbonding_periods[0], Decimal::percent(50)),
+                (unbonding_periods[1], Decimal::one()),
+            ],
+        )
+        .unwrap();
+
+    suite
+        .delegate(member, 10_000, unbonding_periods[0])
+        .unwrap();
+    suite
+        .delegate(member, 10_000, unbonding_periods[1])
+        .unwrap();
+    suite
+        .execute_fund_distribution(distributor, None, juno(400))
+        .unwrap();
+
+    // total apr is 400 / 100 * SECONDS_PER_YEAR = 126144000
+    // 1. 126144000 * 5 / 15 / 10_000 = 4204.8
+    // 2. 126144000 * 10 / 15 / 10_000 = 8409.6
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+    assert_eq!(
+        annual_rewards[0].1[0].amount,
+        Some(Decimal::from_atomics(42048u128, 1).unwrap())
+    );
+    assert_eq!(
+        annual_rewards[1].1[0].amount,
+        Some(Decimal::from_atomics(84096u128, 1).unwrap())
+    );
+    // a single period matches its entry of the full query
+    for (period, rewards) in &annual_rewards {
+        assert_eq!(
+            &suite.query_annualized_rewards_for_period(*period).unwrap(),
+            rewards
+        );
+    }
+    let err = suite.query_annualized_rewards_for_period(500).unwrap_err();
+    assert!(err.to_string().contains("No unbonding period found: 500"));
+
+    let new_rewards = vec![
+        (unbonding_periods[0], Decimal::one()),
+        (unbonding_periods[1], Decimal::percent(300)),
+    ];
+
+    // only the manager can update the multipliers
+    let err = suite
+        .update_reward_multipliers("admin", juno_info.clone(), new_rewards.clone())
+        .unwrap_err();
+    assert_eq!(ContractError::Unauthorized {}, err.downcast().unwrap());
+
+    // multipliers have to be given for exactly the existing unbonding periods
+    let err = suite
+        .update_reward_multipliers(
+            distributor,
+            juno_info.clone(),
+            vec![(unbonding_periods[0], Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidRewards {}, err.downcast().unwrap());
+
+    // multipliers must not decrease
+    let err = suite
+        .update_reward_multipliers(
+            distributor,
+            juno_info.clone(),
+            vec![
+                (unbonding_periods[0], Decimal::percent(300)),
+                (unbonding_periods[1], Decimal::one()),
+            ],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidRewards {}, err.downcast().unwrap());
+
+    suite
+        .update_reward_multipliers(distributor, juno_info, new_rewards)
+        .unwrap();
+
+    // 10_000 * 1 / 1000 = 10 and 10_000 * 3 / 1000 = 30
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(40));
+    // 1. 126144000 * 10 / 40 / 10_000 = 3153.6
+    // 2. 126144000 * 30 / 40 / 10_000 = 9460.8
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+    assert_eq!(
+        annual_rewards[0].1[0].amount,
+        Some(Decimal::from_atomics(31536u128, 1).unwrap())
+    );
+    assert_eq!(
+        annual_rewards[1].1[0].amount,
+        Some(Decimal::from_atomics(94608u128, 1).unwrap())
+    );
+}
+
+#[test]
+fn admin_actions_are_recorded() {
+    let distributor = "distributor";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_admin("admin")
+        .build();
+    assert_eq!(suite.query_admin_actions(None, None).unwrap(), vec![]);
+
+    let rewards = vec![(unbonding_period, Decimal::one())];
+    suite
+        .create_distribution_flow("admin", distributor, juno_info.clone(), rewards)
+        .unwrap();
+    let creation_height = suite.app.block_info().height;
+    suite.next_block();
+    let rewards = vec![(unbonding_period, Decimal::percent(200))];
+    suite
+        .update_reward_multipliers(distributor, juno_info.clone(), rewards.clone())
+        .unwrap();
+
+    // failed actions are not recorded
+    suite.update_min_bond(distributor, 2_000).unwrap_err();
+
+    let juno_info = AssetInfoValidated::Native("juno".to_string());
+    let expected = vec![
+        AdminActionEntry {
+            id: 0,
+            height: creation_height,
+            sender: Addr::unchecked("admin"),
+            action: AdminAction::CreateDistributionFlow {
+                asset: juno_info.clone(),
+                manager: Addr::unchecked(distributor),
+            },
+        },
+        AdminActionEntry {
+            id: 1,
+            height: creation_height + 1,
+            sender: Addr::unchecked(distributor),
+            action: AdminAction::UpdateRewardMultipliers {
+                asset: juno_info,
+                rewards,
+            },
+        },
+    ];
+    assert_eq!(suite.query_admin_actions(None, None).unwrap(), expected);
+    assert_eq!(
+        suite.query_admin_actions(Some(0), None).unwrap(),
+        expected[1..]
+    );
+    assert_eq!(
+        suite.query_admin_actions(None, Some(1)).unwrap(),
+        expected[..1]
+    );
+
+    // only the most recent actions are kept
+    for min_bond in 0..MAX_ADMIN_ACTIONS {
+        suite
+            .update_min_bond("admin", min_bond as u128 + 1)
+            .unwrap();
+    }
+    let actions = suite.query_admin_actions(None, Some(1)).unwrap();
+    assert_eq!(actions[0].id, 2);
+    assert_eq!(
+        actions[0].action,
+        AdminAction::UpdateMinBond {
+            min_bond: Uint128::new(1)
+        }
+    );
+}
+
+#[test]
+fn simulate_rebond_matches_rebond() {
+    let member = "member";
+    let unbonding_periods = vec![1000u64, 2000u64];
+    let juno_info = AssetInfo::Native("juno".to_string());
+    let luna_info = AssetInfo::Native("luna".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_min_bond(5_000)
+        .with_initial_balances(vec![(member, 10_000)])
+        .build();
+
+    for (asset, multipliers) in [
+        (juno_info, [Decimal::percent(50), Decimal::one()]),
+        (luna_info, [Decimal::one(), Decimal::percent(200)]),
+    ] {
+        suite
+            .create_distribution_flow(
+                "admin",
+                "distributor",
+                asset,
+                unbonding_periods.iter().copied().zip(multipliers).collect(),
+            )
+            .unwrap();
+    }
+    suite
+        .delegate(member, 10_000, unbonding_periods[0])
+        .unwrap();
+
+    // the remaining 4_000 tokens fall below min_bond, so they lose their rewards power
+    let simulation = suite
+        .simulate_rebond(member, 6_000, unbonding_periods[0], unbonding_periods[1])
+        .unwrap();
+    let before: Vec<_> = simulation
+        .iter()
+        .map(|power| (power.asset.clone(), power.before.u128()))
+        .collect();
+    let after: Vec<_> = simulation
+        .iter()
+        .map(|power| (power.asset.clone(), power.after.u128()))
+        .collect();
+    assert_eq!(before, suite.query_rewards_power(member).unwrap());
+    assert_eq!(
+        after,
+        vec![
+            (AssetInfoValidated::Native("juno".to_string()), 6),
+            (AssetInfoValidated::Native("luna".to_string()), 12)
+        ]
+    );
+
+    suite
+        .rebond(member, 6_000, unbonding_periods[0], unbonding_periods[1])
+        .unwrap();
+    assert_eq!(after, suite.query_rewards_power(member).unwrap());
+
+    // the simulation fails like the rebond would
+    let err = suite
+        .simulate_rebond(member, 5_000, unbonding_periods[0], unbonding_periods[1])
+        .unwrap_err();
+    assert!(err.to_string().contains("Cannot Sub with 4000 and 5000"));
+    let err = suite
+        .simulate_rebond(member, 1_000, unbonding_periods[0], unbonding_periods[0])
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains(&ContractError::SameUnbondingRebond {}.to_string()));
+}
+
+#[test]
+fn simulate_distribution_matches_distribution() {
+    let members = ["member1", "member2"];
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfoValidated::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_admin("admin")
+        .with_initial_balances(vec![(members[0], 5_000), (members[1], 10_000)])
+        .with_native_balances("juno", vec![("funder", 2_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            "funder",
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(members[0], 5_000, unbonding_period).unwrap();
+    suite
+        .delegate(members[1], 10_000, unbonding_period)
+        .unwrap();
+
+    for _ in 0..2 {
+        let before = suite.query_distribution(&juno_info).unwrap();
+        let simulation = suite
+            .simulate_distribution(AssetInfo::Native("juno".to_string()), 1_000)
+            .unwrap();
+        assert_eq!(
+            simulation.shares_per_point,
+            before.shares_per_point + simulation.shares_per_point_increment
+        );
+        assert_eq!(
+            simulation.withdrawable_total,
+            before.withdrawable_total + Uint128::new(1_000)
+        );
+
+        // the simulation does not change anything
+        assert_eq!(suite.query_distribution(&juno_info).unwrap(), before);
+
+        suite
+            .distribute_funds("funder", None, Some(juno(1_000)))
+            .unwrap();
+        let after = suite.query_distribution(&juno_info).unwrap();
+        assert_eq!(after.shares_per_point, simulation.shares_per_point);
+        assert_eq!(after.withdrawable_total, simulation.withdrawable_total);
+    }
+
+    // the stakers split the rewards by their rewards power
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![juno(666)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(members[1]).unwrap(),
+        vec![juno(1_333)]
+    );
+
+    let err = suite
+        .simulate_distribution(AssetInfo::Native("luna".to_string()), 1_000)
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("No distribution flow for this token"));
+}
+
+#[test]
+fn update_tokens_per_power() {
+    let distributor = "distributor";
+    let member = "member";
+    let unbonding_periods = vec![100u64, 1000u64];
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_initial_balances(vec![(member, 1_000_000)])
+        .with_native_balances("juno", vec![(distributor, 100_000_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info,
+            vec![
+                (unbonding_periods[0], Decimal::percent(50)),
+                (unbonding_periods[1], Decimal::one()),
+            ],
+        )
+        .unwrap();
+
+    suite
+        .delegate(member, 15_000, unbonding_periods[0])
+        .unwrap();
+    suite
+        .delegate(member, 15_000, unbonding_periods[1])
+        .unwrap();
+    suite
+        .execute_fund_distribution(distributor, None, juno(400))
+        .unwrap();
+
+    // half of the rewards are distributed with the old scaling
+    suite.update_time(50);
+    suite.distribute_funds(member, None, None).unwrap();
+    let withdrawable = suite.withdrawable_rewards(member).unwrap();
+
+    // 15_000 * 0.5 / 1000 = 7.5 is rounded down to 7, 15_000 * 1 / 1000 = 15
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(22));
+    let annual_rewards = suite.query_annualized_rewards().unwrap();
+
+    // only the admin can update the scaling
+    let err = suite.update_tokens_per_power(member, 500).unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+    let err = suite.update_tokens_per_power("admin", 0).unwrap_err();
+    assert_eq!(
+        ContractError::ZeroTokensPerPower {},
+        err.downcast().unwrap()
+    );
+
+    suite.update_tokens_per_power("admin", 500).unwrap();
+
+    // 15_000 * 0.5 / 500 = 15, 15_000 * 1 / 500 = 30
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(45));
+    // the remaining apr is 200 / 100 * SECONDS_PER_YEAR = 63072000
+    // 1. 63072000 * 15 / 45 / 15_000 = 1401.6 (was 63072000 * 7 / 22 / 15_000)
+    // 2. 63072000 * 30 / 45 / 15_000 = 2803.2 (was 63072000 * 15 / 22 / 15_000)
+    let new_annual_rewards = suite.query_annualized_rewards().unwrap();
+    assert_ne!(new_annual_rewards, annual_rewards);
+    assert_eq!(
+        new_annual_rewards[0].1[0].amount,
+        Some(Decimal::from_atomics(14016u128, 1).unwrap())
+    );
+    assert_eq!(
+        new_annual_rewards[1].1[0].amount,
+        Some(Decimal::from_atomics(28032u128, 1).unwrap())
+    );
+
+    // rewards that were already distributed are not affected
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), withdrawable);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(
+        suite.query_balance(member, "juno").unwrap(),
+        withdrawable[0].amount.u128()
+    );
+
+    // and the rest is distributed with the new scaling
+    suite.update_time(50);
+    suite.distribute_funds(member, None, None).unwrap();
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_approx_eq!(
+        Uint128::new(suite.query_balance(member, "juno").unwrap()),
+        Uint128::new(400),
+        "0.01"
+    );
+}
+
+#[test]
+fn update_tokens_per_power_keeps_rewards_of_each_staker() {
+    let distributor = "distributor";
+    let member1 = "member1";
+    let member2 = "member2";
+    let unbonding_periods = vec![100u64, 1000u64];
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(unbonding_periods.clone())
+        .with_admin("admin")
+        .with_initial_balances(vec![(member1, 30_000), (member2, 15_000)])
+        .with_native_balances("juno", vec![(distributor, 100_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            distributor,
+            juno_info,
+            vec![
+                (unbonding_periods[0], Decimal::percent(50)),
+                (unbonding_periods[1], Decimal::one()),
+            ],
+        )
+        .unwrap();
+    suite
+        .delegate(member1, 15_000, unbonding_periods[0])
+        .unwrap();
+    suite
+        .delegate(member2, 15_000, unbonding_periods[1])
+        .unwrap();
+
+    // 7 and 15 power
+    suite
+        .distribute_funds(distributor, None, Some(juno(220)))
+        .unwrap();
+    suite.withdraw_funds(member1, None, None).unwrap();
+    suite.withdraw_funds(member2, None, None).unwrap();
+    assert_eq!(suite.query_balance(member1, "juno").unwrap(), 70);
+    assert_eq!(suite.query_balance(member2, "juno").unwrap(), 150);
+
+    // 15 and 30 power, so member1 gained relative to member2,
+    // but that must not shift any of the rewards that were already distributed
+    suite.update_tokens_per_power("admin", 500).unwrap();
+    assert_eq!(suite.withdrawable_rewards(member1).unwrap(), vec![juno(0)]);
+    assert_eq!(suite.withdrawable_rewards(member2).unwrap(), vec![juno(0)]);
+
+    // changing the stake applies the new scaling before the stake is updated
+    suite
+        .delegate(member1, 15_000, unbonding_periods[0])
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member1).unwrap(), vec![juno(0)]);
+
+    // 30 and 30 power
+    suite
+        .distribute_funds(distributor, None, Some(juno(600)))
+        .unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(member1).unwrap(),
+        vec![juno(300)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(member2).unwrap(),
+        vec![juno(300)]
+    );
+    suite.withdraw_funds(member1, None, None).unwrap();
+    suite.withdraw_funds(member2, None, None).unwrap();
+    assert_eq!(suite.query_balance(member1, "juno").unwrap(), 370);
+    assert_eq!(suite.query_balance(member2, "juno").unwrap(), 450);
+    assert_eq!(
+        suite
+            .query_balance(suite.stake_contract().as_str(), "juno")
+            .unwrap(),
+        0
+    );
+}
+
+#[test]
+fn set_max_distributions() {
+    let unbonding_period = 1000u64;
+    let rewards = vec![(unbonding_period, Decimal::one())];
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_admin("admin")
+        .build();
+
+    for denom in ["juno", "luna"] {
+        suite
+            .create_distribution_flow(
+                "admin",
+                "distributor",
+                AssetInfo::Native(denom.to_string()),
+                rewards.clone(),
+            )
+            .unwrap();
+    }
+
+    // only the admin can change the limit
+    let err = suite.set_max_distributions("distributor", 3).unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    // the limit cannot be lowered below the existing distributions
+    let err = suite.set_max_distributions("admin", 1).unwrap_err();
+    assert_eq!(
+        ContractError::TooManyDistributions(1),
+        err.downcast().unwrap()
+    );
+
+    // but it can be lowered to exactly their number
+    suite.set_max_distributions("admin", 2).unwrap();
+    let err = suite
+        .create_distribution_flow(
+            "admin",
+            "distributor",
+            AssetInfo::Native("atom".to_string()),
+            rewards.clone(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::TooManyDistributions(2),
+        err.downcast().unwrap()
+    );
+
+    // raising it allows new distributions again
+    suite.set_max_distributions("admin", 3).unwrap();
+    suite
+        .create_distribution_flow(
+            "admin",
+            "distributor",
+            AssetInfo::Native("atom".to_string()),
+            rewards,
+        )
+        .unwrap();
+}
+
+#[test]
+fn close_distribution_flow_after_full_distribution() {
+    let member = "member";
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(member, 1_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 1000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 1_000, unbonding_period).unwrap();
+
+    suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap();
+
+    // half of the rewards are unlocked and distributed
+    suite.update_time(100);
+    suite.distribute_funds(member, None, None).unwrap();
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
+
+    // only the admin can close the flow
+    let err = suite
+        .close_distribution_flow(funder, juno_info.clone())
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    // the rewards that are still locked go back to the manager and nothing is left to withdraw,
+    // so the flow is removed right away
+    let res = suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("action", "close_distribution_flow")
+            .add_attribute("asset", "juno")
+            .add_attribute("manager", funder)
+            .add_attribute("distributed", "0")
+            .add_attribute("reclaimed", "500")
+            .add_attribute("removed", "true"),
+    );
+    assert_eq!(suite.query_balance(funder, "juno").unwrap(), 500);
+    assert_eq!(
+        suite
+            .query_balance(suite.stake_contract().as_str(), "juno")
+            .unwrap(),
+        0
+    );
+
+    // the flow is gone and nothing accrues anymore
+    assert_eq!(suite.query_reward_curves().unwrap(), vec![]);
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![]);
+    suite.update_time(100);
+    suite.distribute_funds(member, None, None).unwrap();
+    assert_eq!(suite.distributed_funds().unwrap(), vec![]);
+
+    // the slot is free again and the asset can get a new flow,
+    // which does not inherit what the member withdrew from the old one
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .distribute_funds(funder, None, Some(juno(100)))
+        .unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(100)]);
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 600);
+}
+
+#[test]
+fn close_distribution_flow_with_pending_rewards() {
+    let member = "member";
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(member, 1_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 1000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 1_000, unbonding_period).unwrap();
+    suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(1000, "juno")],
+            Curve::saturating_linear((0, 1000), (200, 0)),
+        )
+        .unwrap();
+
+    // half of the rewards are released, but noone distributed them yet
+    suite.update_time(100);
+    let res = suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap();
+    res.assert_event(
+        &Event::new("wasm")
+            .add_attribute("distributed", "500")
+            .add_attribute("reclaimed", "500")
+            .add_attribute("removed", "false"),
+    );
+    assert_eq!(suite.query_balance(funder, "juno").unwrap(), 500);
+
+    // the released rewards belong to the member, and nothing accrues anymore
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(500)]);
+    suite.update_time(100);
+    suite.distribute_funds(member, None, None).unwrap();
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![juno(500)]);
+    let err = suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(100, "juno")],
+            Curve::saturating_linear((0, 100), (200, 0)),
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionClosed(AssetInfoValidated::Native("juno".to_string())),
+        err.downcast().unwrap()
+    );
+
+    // the closed flow stays until the rewards are withdrawn
+    let err = suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap_err();
+    assert_eq!(
+        ContractError::PendingRewards(AssetInfoValidated::Native("juno".to_string())),
+        err.downcast().unwrap()
+    );
+    let err = suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::DistributionAlreadyExists(AssetInfoValidated::Native("juno".to_string())),
+        err.downcast().unwrap()
+    );
+
+    // withdrawing the last rewards removes it
+    suite.withdraw_funds(member, None, None).unwrap();
+    assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
+    assert_eq!(suite.query_reward_curves().unwrap(), vec![]);
+    assert_eq!(suite.distributed_funds().unwrap(), vec![]);
+}
+
+#[test]
+fn remove_closed_distribution_flow_after_unbonding_period() {
+    let member = "member";
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+    let juno_info = AssetInfo::Native("juno".to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(member, 1_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 1000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info.clone(),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.delegate(member, 1_000, unbonding_period).unwrap();
+    suite
+        .distribute_funds(funder, None, Some(juno(1000)))
+        .unwrap();
+    suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap();
+
+    // the member does not withdraw, so the admin has to wait for the longest unbonding period
+    suite.update_time(unbonding_period - 1);
+    let err = suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap_err();
+    assert_eq!(
+        ContractError::PendingRewards(AssetInfoValidated::Native("juno".to_string())),
+        err.downcast().unwrap()
     );
+    suite.update_time(1);
+    let res = suite
+        .close_distribution_flow("admin", juno_info.clone())
+        .unwrap();
+    res.assert_event(&Event::new("wasm").add_attribute("removed", "true"));
+    assert_eq!(suite.withdrawable_rewards(member).unwrap(), vec![]);
+
+    // the rewards stayed in the contract and go to the next flow of the asset
     assert_eq!(
-        annual_rewards[1].1[0].amount,
-        Some(Decimal::from_atomics(2293527272727272727272u128, 18).unwrap())
+        suite
+            .query_balance(suite.stake_contract().as_str(), "juno")
+            .unwrap(),
+        1000
     );
+    suite
+        .create_distribution_flow(
+            "admin",
+            funder,
+            juno_info,
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite.distribute_funds(funder, None, None).unwrap();
     assert_eq!(
-        annual_rewards[2].1[0].amount,
-        Some(Decimal::from_atomics(6880581818181818181818u128, 18).unwrap())
+        suite.withdrawable_rewards(member).unwrap(),
+        vec![juno(1000)]
     );
 }
 
@@ -1638,6 +2823,126 @@ fn funds_withdrawal_delegation() {
     assert_eq!(suite.query_balance(&members[2], "juno").unwrap(), 0);
 }
 
+#[test]
+fn query_owners_delegated_to() {
+    let mut suite = SuiteBuilder::new().build();
+
+    suite.delegate_withdrawal("member1", "delegate").unwrap();
+    suite.delegate_withdrawal("member2", "delegate").unwrap();
+    suite.delegate_withdrawal("member3", "other").unwrap();
+
+    assert_eq!(
+        suite.delegated_to("delegate", None, None).unwrap(),
+        vec![Addr::unchecked("member1"), Addr::unchecked("member2")]
+    );
+    assert_eq!(
+        suite.delegated_to("delegate", None, Some(1)).unwrap(),
+        vec![Addr::unchecked("member1")]
+    );
+    assert_eq!(
+        suite
+            .delegated_to("delegate", Some("member1"), Some(1))
+            .unwrap(),
+        vec![Addr::unchecked("member2")]
+    );
+
+    // delegating to someone else removes the owner from the old delegate
+    suite.delegate_withdrawal("member1", "other").unwrap();
+    assert_eq!(
+        suite.delegated_to("delegate", None, None).unwrap(),
+        vec![Addr::unchecked("member2")]
+    );
+    assert_eq!(
+        suite.delegated_to("other", None, None).unwrap(),
+        vec![Addr::unchecked("member1"), Addr::unchecked("member3")]
+    );
+    assert_eq!(
+        suite.delegated_to("nobody", None, None).unwrap(),
+        Vec::<Addr>::new()
+    );
+}
+
+#[test]
+fn reward_receiver_override() {
+    let members = vec![
+        "member1".to_owned(),
+        "member2".to_owned(),
+        "member3".to_owned(),
+    ];
+    let treasury = "treasury";
+
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(1000)
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(&members[2], 400)])
+        .with_initial_balances(vec![(&members[0], 4_000u128), (&members[1], 6_000u128)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            &members[0],
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite
+        .delegate(&members[0], 4_000u128, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(&members[1], 6_000u128, unbonding_period)
+        .unwrap();
+
+    suite
+        .set_reward_receiver(&members[0], Some(treasury))
+        .unwrap();
+    suite.delegate_withdrawal(&members[0], &members[1]).unwrap();
+
+    // rewards go to the receiver, no matter who withdraws them
+    suite
+        .distribute_funds(&members[2], None, Some(juno(100)))
+        .unwrap();
+    suite.withdraw_funds(&members[0], None, None).unwrap();
+    assert_eq!(suite.query_balance(treasury, "juno").unwrap(), 40);
+    assert_eq!(suite.query_balance(&members[0], "juno").unwrap(), 0);
+
+    suite
+        .distribute_funds(&members[2], None, Some(juno(100)))
+        .unwrap();
+    suite
+        .withdraw_funds(&members[1], members[0].as_str(), None)
+        .unwrap();
+    assert_eq!(suite.query_balance(treasury, "juno").unwrap(), 80);
+    assert_eq!(suite.query_balance(&members[1], "juno").unwrap(), 0);
+
+    // an explicit receiver still takes precedence
+    suite
+        .distribute_funds(&members[2], None, Some(juno(100)))
+        .unwrap();
+    suite
+        .withdraw_funds(&members[0], None, members[2].as_str())
+        .unwrap();
+    assert_eq!(suite.query_balance(&members[2], "juno").unwrap(), 140);
+    assert_eq!(suite.query_balance(treasury, "juno").unwrap(), 80);
+
+    // other stakers are not affected
+    suite.withdraw_funds(&members[1], None, None).unwrap();
+    assert_eq!(suite.query_balance(&members[1], "juno").unwrap(), 180);
+
+    // clearing the receiver restores the default
+    suite.set_reward_receiver(&members[0], None).unwrap();
+    suite
+        .distribute_funds(&members[2], None, Some(juno(100)))
+        .unwrap();
+    suite.withdraw_funds(&members[0], None, None).unwrap();
+    assert_eq!(suite.query_balance(&members[0], "juno").unwrap(), 40);
+    assert_eq!(suite.query_balance(treasury, "juno").unwrap(), 80);
+}
+
 #[test]
 fn querying_unknown_address() {
     let suite = SuiteBuilder::new().build();
@@ -2196,3 +3501,276 @@ fn withdraw_adjustment_handled_lazily() {
     // member should get rewards
     assert_eq!(suite.query_balance(member, "juno").unwrap(), 500);
 }
+
+#[test]
+fn instant_unbond_penalty_is_distributed() {
+    let members = ["member0", "member1"];
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(1000)
+        .with_admin("admin")
+        .with_instant_unbond_penalty(Decimal::percent(10))
+        .with_initial_balances(vec![(members[0], 4_000), (members[1], 2_000)])
+        .build();
+
+    // penalty is paid in the staked token, so it can be distributed
+    suite
+        .create_distribution_flow(
+            "admin",
+            "admin",
+            AssetInfo::Token(suite.token_contract()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite
+        .delegate(members[0], 4_000u128, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(members[1], 2_000u128, unbonding_period)
+        .unwrap();
+
+    // member0 pays a penalty of 300 for instantly unbonding 3_000
+    suite
+        .instant_unbond(members[0], 3_000u128, unbonding_period)
+        .unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(members[0]).unwrap(),
+        2_700
+    );
+
+    // the penalty stays in the contract, but is not counted as stake
+    let staked_token = AssetInfoValidated::Token(Addr::unchecked(suite.token_contract()));
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 3_300);
+    assert_eq!(
+        suite.undistributed_funds().unwrap(),
+        vec![staked_token.with_balance(300u128)]
+    );
+
+    // member0 has 1_000 staked and member1 2_000, so they get 1/3 and 2/3 of the penalty
+    suite.distribute_funds("admin", None, None).unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![staked_token.with_balance(100u128)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(members[1]).unwrap(),
+        vec![staked_token.with_balance(200u128)]
+    );
+
+    // withdrawing rewards does not touch the stake
+    suite.withdraw_funds(members[1], None, None).unwrap();
+    assert_eq!(
+        suite.query_balance_vesting_contract(members[1]).unwrap(),
+        200
+    );
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 3_100);
+    assert_eq!(
+        suite.query_staked(members[1], unbonding_period).unwrap(),
+        2_000
+    );
+    assert_eq!(
+        suite.undistributed_funds().unwrap(),
+        vec![staked_token.with_balance(0u128)]
+    );
+}
+
+#[test]
+fn withdraw_rewards_to_stake() {
+    let members = ["member0", "member1"];
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![1000, 2000])
+        .with_min_bond(1000)
+        .with_admin("admin")
+        .with_instant_unbond_penalty(Decimal::percent(10))
+        .with_initial_balances(vec![
+            (members[0], 4_000),
+            (members[1], 2_000),
+            ("admin", 4_000),
+        ])
+        .build();
+
+    let staked_token = AssetInfoValidated::Token(Addr::unchecked(suite.token_contract()));
+    suite
+        .create_distribution_flow(
+            "admin",
+            "admin",
+            AssetInfo::Token(suite.token_contract()),
+            vec![(1000, Decimal::one()), (2000, Decimal::percent(200))],
+        )
+        .unwrap();
+
+    suite.delegate(members[0], 4_000u128, 1000).unwrap();
+    suite.delegate(members[1], 2_000u128, 1000).unwrap();
+
+    suite
+        .distribute_funds("admin", None, Some(staked_token.with_balance(3_000u128)))
+        .unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![staked_token.with_balance(2_000u128)]
+    );
+
+    // the rewards are bonded without leaving the contract
+    suite.withdraw_rewards_to_stake(members[0], 2000).unwrap();
+    assert_eq!(suite.query_staked(members[0], 2000).unwrap(), 2_000);
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![staked_token.with_balance(0u128)]
+    );
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 9_000);
+    assert_eq!(
+        suite.undistributed_funds().unwrap(),
+        vec![staked_token.with_balance(0u128)]
+    );
+    // 4_000 * 1 / 1000 + 2_000 * 2 / 1000 = 8
+    assert_eq!(
+        suite.query_rewards_power(members[0]).unwrap(),
+        vec![(staked_token.clone(), 8)]
+    );
+
+    // the next distribution takes the new power into account
+    suite
+        .distribute_funds("admin", None, Some(staked_token.with_balance(1_000u128)))
+        .unwrap();
+    assert_eq!(
+        suite.withdrawable_rewards(members[0]).unwrap(),
+        vec![staked_token.with_balance(800u128)]
+    );
+    assert_eq!(
+        suite.withdrawable_rewards(members[1]).unwrap(),
+        vec![staked_token.with_balance(1_200u128)]
+    );
+
+    // without rewards in the staked token, nothing is bonded
+    let res = suite.withdraw_rewards_to_stake("admin", 1000).unwrap();
+    res.assert_event(&Event::new("wasm").add_attribute("result", "no rewards in the staked token"));
+    assert_eq!(suite.query_staked("admin", 1000).unwrap(), 0);
+}
+
+#[test]
+fn query_withdrawable_reward_of_single_asset() {
+    let members = ["member0", "member1", "member2"];
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_initial_balances(vec![(members[0], 5_000), (members[1], 10_000)])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(members[2], 600)])
+        .with_native_balances("luna", vec![(members[2], 300)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            members[0],
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+    suite
+        .create_distribution_flow(
+            "admin",
+            members[0],
+            AssetInfo::Native("luna".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite
+        .delegate(members[0], 5_000u128, unbonding_period)
+        .unwrap();
+    suite
+        .delegate(members[1], 10_000u128, unbonding_period)
+        .unwrap();
+
+    suite
+        .distribute_funds(members[2], None, Some(juno(600)))
+        .unwrap();
+    suite
+        .distribute_funds(
+            members[2],
+            None,
+            Some(native_token("luna".to_string(), 300)),
+        )
+        .unwrap();
+
+    // only the requested asset is returned
+    assert_eq!(
+        suite
+            .withdrawable_reward(members[0], AssetInfo::Native("juno".to_string()))
+            .unwrap(),
+        juno(200)
+    );
+    assert_eq!(
+        suite
+            .withdrawable_reward(members[1], AssetInfo::Native("luna".to_string()))
+            .unwrap(),
+        native_token("luna".to_string(), 200)
+    );
+    // it matches the corresponding entry of all withdrawable rewards
+    assert_eq!(
+        suite.withdrawable_rewards(members[1]).unwrap(),
+        vec![juno(400), native_token("luna".to_string(), 200)]
+    );
+
+    // no distribution for this asset
+    suite
+        .withdrawable_reward(members[0], AssetInfo::Native("atom".to_string()))
+        .unwrap_err();
+}
+
+#[test]
+fn fund_distribution_emits_attributes() {
+    let funder = "funder";
+    let unbonding_period = 1000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_admin("admin")
+        .with_native_balances("juno", vec![(funder, 400)])
+        .with_native_balances("luna", vec![(funder, 600)])
+        .build();
+
+    for denom in ["juno", "luna"] {
+        suite
+            .create_distribution_flow(
+                "admin",
+                funder,
+                AssetInfo::Native(denom.to_string()),
+                vec![(unbonding_period, Decimal::one())],
+            )
+            .unwrap();
+    }
+
+    let res = suite
+        .execute_fund_distribution_with_curve(
+            funder,
+            &[coin(400, "juno"), coin(600, "luna")],
+            Curve::saturating_linear((0, 400), (100, 0)),
+        )
+        .unwrap();
+
+    // one group of attributes per denom
+    let expected = Event::new("wasm")
+        .add_attribute("action", "fund_distribution")
+        .add_attribute("asset", "juno")
+        .add_attribute("amount", "400")
+        .add_attribute("curve_min", "0")
+        .add_attribute("curve_max", "400")
+        .add_attribute("asset", "luna")
+        .add_attribute("amount", "600")
+        .add_attribute("curve_min", "0")
+        .add_attribute("curve_max", "400");
+    let event = res
+        .events
+        .iter()
+        .find(|e| e.ty == "wasm")
+        .expect("wasm event");
+    // skip the contract address
+    assert_eq!(event.attributes[1..], expected.attributes[..]);
+}
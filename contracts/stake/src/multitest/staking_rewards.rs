@@ -3,8 +3,9 @@ use sg_swap::asset::{AssetInfo, AssetInfoValidated};
 use sg_swap::stake::UnbondingPeriod;
 
 use crate::error::ContractError;
-use crate::msg::{AllStakedResponse, StakedResponse};
+use crate::msg::{AllStakedResponse, BondingPeriodInfo, StakedResponse};
 use crate::multitest::suite::{juno_power, SEVEN_DAYS};
+use cw_controllers::AdminError;
 
 use super::suite::SuiteBuilder;
 use test_case::test_case;
@@ -430,6 +431,69 @@ fn multiple_users_multiple_unbonding_periods() {
     assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(27)); // same as before
 }
 
+#[test]
+fn total_rewards_power_per_period() {
+    let user1 = "user1";
+    let user2 = "user2";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period2])
+        .with_admin("admin")
+        .with_initial_balances(vec![(user1, 100_000), (user2, 100_000)])
+        .build();
+
+    let juno = AssetInfoValidated::Native("juno".to_string());
+    let luna = AssetInfoValidated::Native("luna".to_string());
+    suite
+        .create_distribution_flow(
+            "admin",
+            user1,
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(50)),
+                (unbonding_period2, Decimal::one()),
+            ],
+        )
+        .unwrap();
+    suite
+        .create_distribution_flow(
+            "admin",
+            user1,
+            AssetInfo::Native("luna".to_string()),
+            vec![
+                (unbonding_period1, Decimal::one()),
+                (unbonding_period2, Decimal::percent(300)),
+            ],
+        )
+        .unwrap();
+
+    suite.delegate(user1, 20_000, unbonding_period1).unwrap();
+    suite.delegate(user2, 30_000, unbonding_period2).unwrap();
+    suite.delegate(user1, 10_000, unbonding_period2).unwrap();
+
+    // power = stake / tokens_per_power * multiplier
+    assert_eq!(
+        suite.query_total_rewards_power_per_period().unwrap(),
+        vec![
+            (
+                unbonding_period1,
+                vec![(juno.clone(), 10), (luna.clone(), 20)] // 0.5 * 20, 1 * 20
+            ),
+            (
+                unbonding_period2,
+                vec![(juno.clone(), 40), (luna.clone(), 120)] // 1 * 40, 3 * 40
+            ),
+        ]
+    );
+    // the breakdown adds up to the total rewards power
+    assert_eq!(
+        suite.query_total_rewards_power().unwrap(),
+        vec![(juno, 50), (luna, 140)]
+    );
+}
+
 #[test]
 fn one_user_rebond_decrease() {
     let user = "user";
@@ -780,3 +844,438 @@ fn delegate_unbond_under_min_bond() {
         1_500u128
     );
 }
+
+#[test]
+fn update_min_bond_applies_on_next_stake_change() {
+    let user1 = "user1";
+    let user2 = "user2";
+    let unbonding_period = 1000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period])
+        .with_min_bond(5_000)
+        .with_admin("admin")
+        .with_initial_balances(vec![(user1, 100_000), (user2, 100_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user1,
+            AssetInfo::Native("juno".to_string()),
+            vec![(unbonding_period, Decimal::one())],
+        )
+        .unwrap();
+
+    suite.delegate(user1, 10_000u128, unbonding_period).unwrap();
+    suite.delegate(user2, 10_000u128, unbonding_period).unwrap();
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(10));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(20));
+
+    // only the admin can update min_bond
+    let err = suite.update_min_bond(user1, 20_000).unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    // raising min_bond above the stakes does not change anything right away
+    suite.update_min_bond("admin", 20_000).unwrap();
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(10));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(20));
+
+    // but on the next stake operation, user1 loses the rewards power
+    suite.delegate(user1, 1_000u128, unbonding_period).unwrap();
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), vec![]);
+    assert_eq!(suite.query_rewards_power(user2).unwrap(), juno_power(10));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(10));
+
+    // lowering it again applies the same way, 0 is raised to 1
+    suite.update_min_bond("admin", 0).unwrap();
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), vec![]);
+    suite.unbond(user1, 1_000u128, unbonding_period).unwrap();
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(10));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(20));
+
+    // unbonding everything still removes the stake from the total
+    suite.unbond(user2, 10_000u128, unbonding_period).unwrap();
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(10));
+}
+
+#[test]
+fn remove_unbonding_period() {
+    let user1 = "user1";
+    let user2 = "user2";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let unbonding_period3 = 8000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![
+            unbonding_period1,
+            unbonding_period2,
+            unbonding_period3,
+        ])
+        .with_admin("admin")
+        .with_initial_balances(vec![(user1, 30_000), (user2, 40_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            "admin",
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(25)),
+                (unbonding_period2, Decimal::percent(60)),
+                (unbonding_period3, Decimal::percent(80)),
+            ],
+        )
+        .unwrap();
+
+    suite
+        .delegate(user1, 20_000u128, unbonding_period1)
+        .unwrap();
+    suite
+        .delegate(user2, 30_000u128, unbonding_period1)
+        .unwrap();
+    suite
+        .delegate(user2, 10_000u128, unbonding_period2)
+        .unwrap();
+
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(5));
+    assert_eq!(suite.query_rewards_power(user2).unwrap(), juno_power(13));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(18));
+
+    // only admin can remove periods
+    let err = suite
+        .remove_unbonding_period(user1, unbonding_period1, unbonding_period2)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    // target period has to exist
+    let err = suite
+        .remove_unbonding_period("admin", unbonding_period1, 2000)
+        .unwrap_err();
+    assert_eq!(
+        ContractError::NoUnbondingPeriodFound(2000),
+        err.downcast().unwrap()
+    );
+
+    // remove the shortest period, moving everything to the second one
+    suite
+        .remove_unbonding_period("admin", unbonding_period1, unbonding_period2)
+        .unwrap();
+
+    // the period is gone
+    suite.query_staked(user1, unbonding_period1).unwrap_err();
+    let periods = suite.query_staked_periods().unwrap();
+    assert_eq!(periods.len(), 2);
+    assert_eq!(periods[0].unbonding_period, unbonding_period2);
+    assert_eq!(periods[0].total_staked.u128(), 60_000);
+
+    assert_eq!(
+        suite.query_staked(user1, unbonding_period2).unwrap(),
+        20_000u128
+    );
+    assert_eq!(
+        suite.query_staked(user2, unbonding_period2).unwrap(),
+        40_000u128
+    );
+    // 20_000 * 0.6 / 1000 = 12
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(12));
+    // 40_000 * 0.6 / 1000 = 24
+    assert_eq!(suite.query_rewards_power(user2).unwrap(), juno_power(24));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(36));
+
+    // migrating down to a shorter period locks the tokens
+    suite
+        .delegate(user1, 10_000u128, unbonding_period3)
+        .unwrap();
+    suite
+        .remove_unbonding_period("admin", unbonding_period3, unbonding_period2)
+        .unwrap();
+    assert_eq!(
+        suite.query_all_staked(user1).unwrap(),
+        AllStakedResponse {
+            stakes: vec![StakedResponse {
+                stake: Uint128::new(30_000),
+                total_locked: Uint128::new(10_000),
+                unbonding_period: unbonding_period2,
+                cw20_contract: suite.token_contract(),
+            }]
+        }
+    );
+    // 30_000 * 0.6 / 1000 = 18
+    assert_eq!(suite.query_rewards_power(user1).unwrap(), juno_power(18));
+    assert_eq!(suite.query_total_rewards_power().unwrap(), juno_power(42));
+
+    // locked tokens cannot be unbonded yet
+    suite
+        .unbond(user1, 30_000u128, unbonding_period2)
+        .unwrap_err();
+    suite.update_time(unbonding_period3 - unbonding_period2);
+    suite.unbond(user1, 30_000u128, unbonding_period2).unwrap();
+}
+
+#[test]
+fn add_unbonding_period() {
+    let user = "user";
+    let unbonding_period1 = 1000u64;
+    let unbonding_period2 = 4000u64;
+    let unbonding_period3 = 8000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period1, unbonding_period3])
+        .with_admin("admin")
+        .with_initial_balances(vec![(user, 100_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            "admin",
+            AssetInfo::Native("juno".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(25)),
+                (unbonding_period3, Decimal::percent(80)),
+            ],
+        )
+        .unwrap();
+
+    // only admin can add periods
+    let err = suite
+        .add_unbonding_period(
+            user,
+            unbonding_period2,
+            vec![(AssetInfo::Native("juno".to_string()), Decimal::percent(60))],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::Admin(AdminError::NotAdmin {}),
+        err.downcast().unwrap()
+    );
+
+    // cannot add an existing period
+    let err = suite
+        .add_unbonding_period(
+            "admin",
+            unbonding_period3,
+            vec![(AssetInfo::Native("juno".to_string()), Decimal::percent(80))],
+        )
+        .unwrap_err();
+    assert_eq!(
+        ContractError::UnbondingPeriodAlreadyExists(unbonding_period3),
+        err.downcast().unwrap()
+    );
+
+    // every distribution needs a multiplier
+    let err = suite
+        .add_unbonding_period("admin", unbonding_period2, vec![])
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidRewards {}, err.downcast().unwrap());
+
+    // multipliers have to stay monotonically increasing
+    let err = suite
+        .add_unbonding_period(
+            "admin",
+            unbonding_period2,
+            vec![(AssetInfo::Native("juno".to_string()), Decimal::percent(90))],
+        )
+        .unwrap_err();
+    assert_eq!(ContractError::InvalidRewards {}, err.downcast().unwrap());
+
+    suite
+        .add_unbonding_period(
+            "admin",
+            unbonding_period2,
+            vec![(AssetInfo::Native("juno".to_string()), Decimal::percent(60))],
+        )
+        .unwrap();
+
+    let periods = suite.query_staked_periods().unwrap();
+    assert_eq!(
+        periods
+            .iter()
+            .map(|p| p.unbonding_period)
+            .collect::<Vec<_>>(),
+        vec![unbonding_period1, unbonding_period2, unbonding_period3]
+    );
+
+    // new distribution flows can use the new period
+    suite
+        .create_distribution_flow(
+            "admin",
+            "admin",
+            AssetInfo::Native("atom".to_string()),
+            vec![
+                (unbonding_period1, Decimal::percent(50)),
+                (unbonding_period2, Decimal::one()),
+                (unbonding_period3, Decimal::one()),
+            ],
+        )
+        .unwrap();
+
+    // bond into the new period
+    suite.delegate(user, 10_000u128, unbonding_period2).unwrap();
+    assert_eq!(
+        suite.query_staked(user, unbonding_period2).unwrap(),
+        10_000u128
+    );
+    assert_eq!(
+        suite.query_rewards_power(user).unwrap(),
+        vec![
+            // 10_000 * 1 / 1000 = 10
+            (AssetInfoValidated::Native("atom".to_string()), 10u128),
+            // 10_000 * 0.6 / 1000 = 6
+            (AssetInfoValidated::Native("juno".to_string()), 6u128),
+        ]
+    );
+}
+
+#[test]
+fn instant_unbond_disabled_by_default() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_initial_balances(vec![(user, 10_000)])
+        .build();
+
+    suite.delegate(user, 10_000u128, None).unwrap();
+
+    let err = suite.instant_unbond(user, 5_000u128, None).unwrap_err();
+    assert_eq!(
+        ContractError::InstantUnbondDisabled {},
+        err.downcast().unwrap()
+    );
+    assert_eq!(suite.query_staked(user, None).unwrap(), 10_000u128);
+}
+
+#[test]
+fn instant_unbond_penalty_needs_distribution() {
+    let user = "user";
+    let mut suite = SuiteBuilder::new()
+        .with_admin("admin")
+        .with_instant_unbond_penalty(Decimal::percent(10))
+        .with_initial_balances(vec![(user, 20_000)])
+        .build();
+
+    suite
+        .create_distribution_flow(
+            "admin",
+            user,
+            AssetInfo::Native("juno".to_string()),
+            vec![(SEVEN_DAYS, Decimal::one())],
+        )
+        .unwrap();
+
+    suite.delegate(user, 20_000u128, None).unwrap();
+    assert_eq!(suite.query_rewards_power(user).unwrap(), juno_power(20));
+
+    // there is no distribution for the staked token, so the penalty would not be distributed
+    let err = suite.instant_unbond(user, 10_000u128, None).unwrap_err();
+    assert_eq!(
+        ContractError::NoPenaltyDistribution {},
+        err.downcast().unwrap()
+    );
+
+    assert_eq!(suite.query_staked(user, None).unwrap(), 20_000u128);
+    assert_eq!(suite.query_rewards_power(user).unwrap(), juno_power(20));
+    assert_eq!(suite.query_claims(user).unwrap(), vec![]);
+    assert_eq!(suite.query_balance_vesting_contract(user).unwrap(), 0u128);
+    assert_eq!(suite.query_balance_staking_contract().unwrap(), 20_000u128);
+}
+
+#[test]
+fn query_stake_at_past_height() {
+    let user = "user";
+    let unbonding_period = 1000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period, SEVEN_DAYS])
+        .with_initial_balances(vec![(user, 20_000)])
+        .build();
+
+    let bonded_at = suite.app.block_info().height;
+    suite.delegate(user, 12_000u128, unbonding_period).unwrap();
+    suite.delegate(user, 8_000u128, SEVEN_DAYS).unwrap();
+
+    suite.next_block();
+    let unbonded_at = suite.app.block_info().height;
+    suite.unbond(user, 5_000u128, unbonding_period).unwrap();
+    // rebonding does not change the total stake
+    suite
+        .rebond(user, 3_000u128, SEVEN_DAYS, unbonding_period)
+        .unwrap();
+
+    suite.next_block();
+
+    // snapshots show the stake at the start of the block
+    assert_eq!(suite.query_staked_at(user, bonded_at).unwrap(), 0);
+    assert_eq!(suite.query_staked_at(user, unbonded_at).unwrap(), 20_000);
+    assert_eq!(
+        suite.query_staked_at(user, unbonded_at + 1).unwrap(),
+        15_000
+    );
+    assert_eq!(suite.query_staked_at("other", unbonded_at).unwrap(), 0);
+}
+
+#[test]
+fn query_total_staked_at_past_height() {
+    let user = "user";
+    let unbonding_period = 1000u64;
+    let mut suite = SuiteBuilder::new()
+        .with_unbonding_periods(vec![unbonding_period, SEVEN_DAYS])
+        .with_initial_balances(vec![(user, 20_000)])
+        .build();
+
+    let bonded_at = suite.app.block_info().height;
+    suite.delegate(user, 12_000u128, unbonding_period).unwrap();
+    suite.delegate(user, 8_000u128, SEVEN_DAYS).unwrap();
+
+    suite.next_block();
+    let unbonded_at = suite.app.block_info().height;
+    suite.unbond(user, 5_000u128, unbonding_period).unwrap();
+
+    suite.next_block();
+
+    // nothing was staked at the start of the bonding block
+    let totals = suite.query_total_staked_at(bonded_at).unwrap();
+    assert_eq!(totals.total_staked.u128(), 0);
+    assert_eq!(totals.total_unbonding.u128(), 0);
+    assert_eq!(
+        totals.bonding,
+        vec![
+            BondingPeriodInfo {
+                unbonding_period,
+                total_staked: Uint128::zero(),
+            },
+            BondingPeriodInfo {
+                unbonding_period: SEVEN_DAYS,
+                total_staked: Uint128::zero(),
+            },
+        ]
+    );
+
+    // the earlier total is kept after unbonding
+    let totals = suite.query_total_staked_at(unbonded_at).unwrap();
+    assert_eq!(totals.total_staked.u128(), 20_000);
+    assert_eq!(totals.total_unbonding.u128(), 0);
+    assert_eq!(
+        totals.bonding,
+        vec![
+            BondingPeriodInfo {
+                unbonding_period,
+                total_staked: Uint128::new(12_000),
+            },
+            BondingPeriodInfo {
+                unbonding_period: SEVEN_DAYS,
+                total_staked: Uint128::new(8_000),
+            },
+        ]
+    );
+
+    let totals = suite.query_total_staked_at(unbonded_at + 1).unwrap();
+    assert_eq!(totals.total_staked.u128(), 15_000);
+    assert_eq!(totals.total_unbonding.u128(), 5_000);
+    assert_eq!(totals.bonding[0].total_staked.u128(), 7_000);
+    assert_eq!(totals.bonding[1].total_staked.u128(), 8_000);
+}
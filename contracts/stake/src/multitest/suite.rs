@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use anyhow::Result as AnyResult;
 
-use cosmwasm_std::{to_binary, Addr, Coin, Decimal, Empty, StdResult, Uint128};
+use cosmwasm_std::{to_binary, Addr, Coin, Decimal, Empty, StdError, StdResult, Uint128};
 use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as Cw20InstantiateMsg;
 use cw_controllers::{Claim, ClaimsResponse};
@@ -14,11 +14,16 @@ use sg_swap::{
 use wynd_curve_utils::Curve;
 
 use crate::msg::{
-    AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse, BondingInfoResponse,
-    BondingPeriodInfo, DelegatedResponse, DistributedRewardsResponse, ExecuteMsg, QueryMsg,
-    ReceiveDelegationMsg, RewardsPowerResponse, StakedResponse, UndistributedRewardsResponse,
-    WithdrawableRewardsResponse,
+    AdminActionsResponse, AllStakedResponse, AnnualizedReward, AnnualizedRewardsResponse,
+    BondingInfoResponse, BondingPeriodInfo, DelegatedResponse, DelegatedToResponse,
+    DistributedRewardsResponse, DistributionDataResponse, ExecuteMsg, LockScheduleResponse,
+    ProjectedReward, ProjectedRewardsResponse, QueryMsg, RebondRewardsPower, ReceiveDelegationMsg,
+    RewardCurvesResponse, RewardsPowerResponse, SimulateDistributionResponse,
+    SimulateRebondResponse, StakedAtResponse, StakedResponse, StakingMetricsResponse,
+    TotalRewardsPowerPerPeriodResponse, TotalStakedAtResponse, UndistributedRewardsResponse,
+    WithdrawableRewardResponse, WithdrawableRewardsResponse,
 };
+use crate::state::{AdminActionEntry, Distribution};
 
 pub const SEVEN_DAYS: u64 = 604800;
 
@@ -57,24 +62,28 @@ pub(super) fn native_token(denom: String, amount: u128) -> AssetValidated {
 #[derive(Debug)]
 pub struct SuiteBuilder {
     pub cw20_contract: String,
+    pub native_denom: Option<String>,
     pub tokens_per_power: Uint128,
     pub min_bond: Uint128,
     pub unbonding_periods: Vec<UnbondingPeriod>,
     pub admin: Option<String>,
     pub initial_balances: Vec<Cw20Coin>,
     pub native_balances: Vec<(Addr, Coin)>,
+    pub instant_unbond_penalty: Option<Decimal>,
 }
 
 impl SuiteBuilder {
     pub fn new() -> Self {
         Self {
             cw20_contract: "".to_owned(),
+            native_denom: None,
             tokens_per_power: Uint128::new(1000),
             min_bond: Uint128::new(5000),
             unbonding_periods: vec![SEVEN_DAYS],
             admin: None,
             initial_balances: vec![],
             native_balances: vec![],
+            instant_unbond_penalty: None,
         }
     }
 
@@ -104,6 +113,11 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_tokens_per_power(mut self, tokens_per_power: u128) -> Self {
+        self.tokens_per_power = tokens_per_power.into();
+        self
+    }
+
     pub fn with_min_bond(mut self, min_bond: u128) -> Self {
         self.min_bond = min_bond.into();
         self
@@ -114,6 +128,16 @@ impl SuiteBuilder {
         self
     }
 
+    pub fn with_instant_unbond_penalty(mut self, penalty: Decimal) -> Self {
+        self.instant_unbond_penalty = Some(penalty);
+        self
+    }
+
+    pub fn with_native_staked_token(mut self, denom: &str) -> Self {
+        self.native_denom = Some(denom.to_owned());
+        self
+    }
+
     pub fn with_unbonding_periods(mut self, unbonding_periods: Vec<UnbondingPeriod>) -> Self {
         self.unbonding_periods = unbonding_periods;
         self
@@ -170,11 +194,13 @@ impl SuiteBuilder {
                 admin,
                 &InstantiateMsg {
                     cw20_contract: token_contract.to_string(),
+                    native_denom: self.native_denom,
                     tokens_per_power: self.tokens_per_power,
                     min_bond: self.min_bond,
                     unbonding_periods: self.unbonding_periods,
                     admin: self.admin,
                     max_distributions: 6,
+                    instant_unbond_penalty: self.instant_unbond_penalty,
                 },
                 &[],
                 "stake",
@@ -212,6 +238,12 @@ impl Suite {
         self.app.set_block(block);
     }
 
+    pub fn next_block(&mut self) {
+        let mut block = self.app.block_info();
+        block.height += 1;
+        self.app.set_block(block);
+    }
+
     fn unbonding_period_or_default(&self, unbonding_period: impl Into<Option<u64>>) -> u64 {
         // Use default SEVEN_DAYS unbonding period if none provided
         if let Some(up) = unbonding_period.into() {
@@ -241,6 +273,97 @@ impl Suite {
         )
     }
 
+    pub fn update_reward_multipliers(
+        &mut self,
+        sender: &str,
+        asset: AssetInfo,
+        rewards: Vec<(UnbondingPeriod, Decimal)>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::UpdateRewardMultipliers { asset, rewards },
+            &[],
+        )
+    }
+
+    pub fn set_distribution_paused(
+        &mut self,
+        sender: &str,
+        asset: AssetInfo,
+        paused: bool,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::SetDistributionPaused { asset, paused },
+            &[],
+        )
+    }
+
+    pub fn update_tokens_per_power(
+        &mut self,
+        sender: &str,
+        tokens_per_power: u128,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::UpdateTokensPerPower {
+                tokens_per_power: tokens_per_power.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn update_min_bond(&mut self, sender: &str, min_bond: u128) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::UpdateMinBond {
+                min_bond: min_bond.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn update_max_claims_per_user(
+        &mut self,
+        sender: &str,
+        max_claims_per_user: u32,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::UpdateMaxClaimsPerUser {
+                max_claims_per_user,
+            },
+            &[],
+        )
+    }
+
+    pub fn set_max_distributions(&mut self, sender: &str, max: u32) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::SetMaxDistributions { max },
+            &[],
+        )
+    }
+
+    pub fn close_distribution_flow(
+        &mut self,
+        sender: &str,
+        asset: AssetInfo,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::CloseDistributionFlow { asset },
+            &[],
+        )
+    }
+
     // call to staking contract by sender
     pub fn delegate(
         &mut self,
@@ -274,6 +397,23 @@ impl Suite {
         )
     }
 
+    pub fn bond_native(
+        &mut self,
+        sender: &str,
+        funds: &[Coin],
+        unbonding_period: impl Into<Option<u64>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::BondNative {
+                unbonding_period: self.unbonding_period_or_default(unbonding_period),
+                delegate_as: None,
+            },
+            funds,
+        )
+    }
+
     // call to staking contract by sender
     pub fn mass_delegate(
         &mut self,
@@ -302,6 +442,30 @@ impl Suite {
         )
     }
 
+    // call to staking contract by sender
+    pub fn mass_delegate_multi(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        delegations: &[(&str, u128, u64)],
+    ) -> AnyResult<AppResponse> {
+        let delegations = delegations
+            .iter()
+            .map(|(a, b, c)| (a.to_string(), Uint128::new(*b), *c))
+            .collect();
+
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.token_contract.clone(),
+            &Cw20ExecuteMsg::Send {
+                contract: self.stake_contract.to_string(),
+                amount: amount.into(),
+                msg: to_binary(&ReceiveDelegationMsg::MassDelegateMulti { delegations })?,
+            },
+            &[],
+        )
+    }
+
     // call to stake contract by sender
     pub fn rebond(
         &mut self,
@@ -317,11 +481,79 @@ impl Suite {
                 tokens: amount.into(),
                 bond_from: self.unbonding_period_or_default(bond_from),
                 bond_to: self.unbonding_period_or_default(bond_to),
+                include_locked: false,
+            },
+            &[],
+        )
+    }
+
+    pub fn rebond_including_locked(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        bond_from: u64,
+        bond_to: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::Rebond {
+                tokens: amount.into(),
+                bond_from,
+                bond_to,
+                include_locked: true,
             },
             &[],
         )
     }
 
+    pub fn transfer_stake(
+        &mut self,
+        sender: &str,
+        recipient: &str,
+        amount: u128,
+        unbonding_period: impl Into<Option<u64>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::TransferStake {
+                recipient: recipient.to_owned(),
+                unbonding_period: self.unbonding_period_or_default(unbonding_period),
+                amount: amount.into(),
+            },
+            &[],
+        )
+    }
+
+    pub fn remove_unbonding_period(
+        &mut self,
+        sender: &str,
+        period: u64,
+        migrate_to: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::RemoveUnbondingPeriod { period, migrate_to },
+            &[],
+        )
+    }
+
+    pub fn add_unbonding_period(
+        &mut self,
+        sender: &str,
+        period: u64,
+        rewards: Vec<(AssetInfo, Decimal)>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::AddUnbondingPeriod { period, rewards },
+            &[],
+        )
+    }
+
     pub fn unbond(
         &mut self,
         sender: &str,
@@ -334,6 +566,52 @@ impl Suite {
             &ExecuteMsg::Unbond {
                 tokens: amount.into(),
                 unbonding_period: self.unbonding_period_or_default(unbonding_period),
+                claim_recipient: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn unbond_with_claim_recipient(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        unbonding_period: impl Into<Option<u64>>,
+        claim_recipient: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::Unbond {
+                tokens: amount.into(),
+                unbonding_period: self.unbonding_period_or_default(unbonding_period),
+                claim_recipient: Some(claim_recipient.to_string()),
+            },
+            &[],
+        )
+    }
+
+    pub fn unbond_all(&mut self, sender: &str) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::UnbondAll {},
+            &[],
+        )
+    }
+
+    pub fn instant_unbond(
+        &mut self,
+        sender: &str,
+        amount: u128,
+        unbonding_period: impl Into<Option<u64>>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.stake_contract.clone(),
+            &ExecuteMsg::InstantUnbond {
+                tokens: amount.into(),
+                unbonding_period: self.unbonding_period_or_default(unbonding_period),
             },
             &[],
         )
@@ -343,7 +621,7 @@ impl Suite {
         self.app.execute_contract(
             Addr::unchecked(sender),
             self.stake_contract.clone(),
-            &ExecuteMsg::Claim {},
+            &ExecuteMsg::Claim { up_to_amount: None },
             &[],
         )
     }
@@ -411,6 +689,20 @@ impl Suite {
         )
     }
 
+    pub fn execute_fund_distribution_with_curve(
+        &mut self,
+        executor: &str,
+        funds: &[Coin],
+        curve: Curve,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(executor),
+            self.stake_contract.clone(),
+            &ExecuteMsg::FundDistribution { curve },
+            funds,
+        )
+    }
+
     // call to staking contract by sender
     pub fn execute_fund_distribution_with_cw20(
         &mut self,
@@ -449,6 +741,19 @@ impl Suite {
         )
     }
 
+    pub fn withdraw_rewards_to_stake(
+        &mut self,
+        executor: &str,
+        unbonding_period: u64,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(executor),
+            self.stake_contract.clone(),
+            &ExecuteMsg::WithdrawRewardsToStake { unbonding_period },
+            &[],
+        )
+    }
+
     #[allow(dead_code)]
     pub fn delegate_withdrawal(
         &mut self,
@@ -465,6 +770,21 @@ impl Suite {
         )
     }
 
+    pub fn set_reward_receiver(
+        &mut self,
+        executor: &str,
+        receiver: Option<&str>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(executor),
+            self.stake_contract.clone(),
+            &ExecuteMsg::SetRewardReceiver {
+                receiver: receiver.map(str::to_owned),
+            },
+            &[],
+        )
+    }
+
     pub fn withdrawable_rewards(&self, owner: &str) -> StdResult<Vec<AssetValidated>> {
         let resp: WithdrawableRewardsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -475,6 +795,47 @@ impl Suite {
         Ok(resp.rewards)
     }
 
+    pub fn projected_rewards(
+        &self,
+        owner: &str,
+        horizon_seconds: u64,
+    ) -> StdResult<Vec<ProjectedReward>> {
+        let resp: ProjectedRewardsResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::ProjectedRewards {
+                owner: owner.to_owned(),
+                horizon_seconds,
+            },
+        )?;
+        Ok(resp.rewards)
+    }
+
+    pub fn query_reward_curve(&self, asset: AssetInfo) -> StdResult<Curve> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::RewardCurve { asset },
+        )
+    }
+
+    pub fn query_reward_curves(&self) -> StdResult<Vec<(AssetInfoValidated, Curve)>> {
+        let resp: RewardCurvesResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::RewardCurves {})?;
+        Ok(resp.curves)
+    }
+
+    pub fn withdrawable_reward(&self, owner: &str, asset: AssetInfo) -> StdResult<AssetValidated> {
+        let resp: WithdrawableRewardResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::WithdrawableReward {
+                owner: owner.to_owned(),
+                asset,
+            },
+        )?;
+        Ok(resp.reward)
+    }
+
     pub fn distributed_funds(&self) -> StdResult<Vec<AssetValidated>> {
         let resp: DistributedRewardsResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -510,6 +871,23 @@ impl Suite {
         Ok(resp.delegated)
     }
 
+    pub fn delegated_to(
+        &self,
+        delegate: &str,
+        start_after: Option<&str>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Addr>> {
+        let resp: DelegatedToResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::DelegatedTo {
+                delegate: delegate.to_owned(),
+                start_after: start_after.map(str::to_owned),
+                limit,
+            },
+        )?;
+        Ok(resp.owners)
+    }
+
     /// returns address' balance of native token
     pub fn query_balance(&self, address: &str, denom: &str) -> StdResult<u128> {
         let resp = self.app.wrap().query_balance(address, denom)?;
@@ -563,6 +941,38 @@ impl Suite {
         Ok(staked.stake.u128())
     }
 
+    pub fn query_staked_at(&self, address: &str, height: u64) -> StdResult<u128> {
+        let staked: StakedAtResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::StakedAt {
+                address: address.to_owned(),
+                height,
+            },
+        )?;
+        Ok(staked.stake.u128())
+    }
+
+    pub fn query_total_staked_at(&self, height: u64) -> StdResult<TotalStakedAtResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::TotalStakedAt { height },
+        )
+    }
+
+    pub fn query_lock_schedule(
+        &self,
+        address: &str,
+        unbonding_period: impl Into<Option<u64>>,
+    ) -> StdResult<LockScheduleResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::LockSchedule {
+                address: address.to_owned(),
+                unbonding_period: self.unbonding_period_or_default(unbonding_period),
+            },
+        )
+    }
+
     pub fn query_staked_periods(&self) -> StdResult<Vec<BondingPeriodInfo>> {
         let info: BondingInfoResponse = self
             .app
@@ -591,6 +1001,23 @@ impl Suite {
         Ok(claims.claims)
     }
 
+    pub fn query_claims_paginated(
+        &self,
+        address: &str,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<Claim>> {
+        let claims: ClaimsResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::ClaimsPaginated {
+                address: address.to_owned(),
+                start_after,
+                limit,
+            },
+        )?;
+        Ok(claims.claims)
+    }
+
     pub fn query_annualized_rewards(
         &self,
     ) -> StdResult<Vec<(UnbondingPeriod, Vec<AnnualizedReward>)>> {
@@ -601,6 +1028,16 @@ impl Suite {
         Ok(apr.rewards)
     }
 
+    pub fn query_annualized_rewards_for_period(
+        &self,
+        unbonding_period: UnbondingPeriod,
+    ) -> StdResult<Vec<AnnualizedReward>> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::AnnualizedRewardsForPeriod { unbonding_period },
+        )
+    }
+
     pub fn query_rewards_power(&self, address: &str) -> StdResult<Vec<(AssetInfoValidated, u128)>> {
         let rewards: RewardsPowerResponse = self.app.wrap().query_wasm_smart(
             self.stake_contract.clone(),
@@ -617,6 +1054,70 @@ impl Suite {
             .collect())
     }
 
+    pub fn simulate_distribution(
+        &self,
+        asset: AssetInfo,
+        amount: u128,
+    ) -> StdResult<SimulateDistributionResponse> {
+        self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::SimulateDistribution {
+                asset,
+                amount: amount.into(),
+            },
+        )
+    }
+
+    pub fn query_distribution(&self, asset: &AssetInfoValidated) -> StdResult<Distribution> {
+        let resp: DistributionDataResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::DistributionData {})?;
+        resp.distributions
+            .into_iter()
+            .find(|(info, _)| info == asset)
+            .map(|(_, distribution)| distribution)
+            .ok_or_else(|| StdError::generic_err("No distribution flow for this token"))
+    }
+
+    pub fn simulate_rebond(
+        &self,
+        address: &str,
+        amount: u128,
+        bond_from: u64,
+        bond_to: u64,
+    ) -> StdResult<Vec<RebondRewardsPower>> {
+        let resp: SimulateRebondResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::SimulateRebond {
+                address: address.to_owned(),
+                amount: amount.into(),
+                bond_from,
+                bond_to,
+                include_locked: false,
+            },
+        )?;
+        Ok(resp.rewards_power)
+    }
+
+    pub fn query_admin_actions(
+        &self,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    ) -> StdResult<Vec<AdminActionEntry>> {
+        let resp: AdminActionsResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::AdminActions { start_after, limit },
+        )?;
+        Ok(resp.actions)
+    }
+
+    pub fn query_staking_metrics(&self) -> StdResult<StakingMetricsResponse> {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.stake_contract.clone(), &QueryMsg::StakingMetrics {})
+    }
+
     pub fn query_total_rewards_power(&self) -> StdResult<Vec<(AssetInfoValidated, u128)>> {
         let rewards: RewardsPowerResponse = self
             .app
@@ -630,4 +1131,24 @@ impl Suite {
             .filter(|(_, p)| *p > 0)
             .collect())
     }
+
+    pub fn query_total_rewards_power_per_period(
+        &self,
+    ) -> StdResult<Vec<(UnbondingPeriod, Vec<(AssetInfoValidated, u128)>)>> {
+        let rewards: TotalRewardsPowerPerPeriodResponse = self.app.wrap().query_wasm_smart(
+            self.stake_contract.clone(),
+            &QueryMsg::TotalRewardsPowerPerPeriod {},
+        )?;
+
+        Ok(rewards
+            .rewards
+            .into_iter()
+            .map(|(period, powers)| {
+                (
+                    period,
+                    powers.into_iter().map(|(a, p)| (a, p.u128())).collect(),
+                )
+            })
+            .collect())
+    }
 }
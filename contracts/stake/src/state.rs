@@ -3,26 +3,221 @@ use serde::{Deserialize, Serialize};
 use wynd_curve_utils::Curve;
 
 use crate::{utils::calc_power, ContractError};
-use cosmwasm_std::{Addr, Decimal, Env, OverflowError, StdResult, Storage, Timestamp, Uint128};
-use cw_controllers::{Admin, Claims};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{
+    Addr, BlockInfo, Decimal, Deps, Empty, Env, Order, OverflowError, StdError, StdResult, Storage,
+    Timestamp, Uint128,
+};
+use cw_controllers::{Admin, Claim, Claims};
+use cw_storage_plus::{Bound, Item, Map, SnapshotItem, SnapshotMap, Strategy};
+use cw_utils::Expiration;
 use sg_swap::asset::AssetInfoValidated;
 use sg_swap::stake::UnbondingPeriod;
+use std::fmt;
 
 pub const CLAIMS: Claims = Claims::new("claims");
 
+/// Claims that are paid out to a different address than the staker who unbonded,
+/// keyed by staker and claim recipient
+pub const RECIPIENT_CLAIMS: Map<(&Addr, &Addr), Vec<Claim>> = Map::new("recipient_claims");
+
+/// Adds a claim of `staker` to [`RECIPIENT_CLAIMS`] that is paid out to `recipient`.
+pub fn create_recipient_claim(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    recipient: &Addr,
+    amount: Uint128,
+    release_at: Expiration,
+) -> StdResult<()> {
+    RECIPIENT_CLAIMS.update(storage, (staker, recipient), |claims| -> StdResult<_> {
+        let mut claims = claims.unwrap_or_default();
+        claims.push(Claim { amount, release_at });
+        Ok(claims)
+    })?;
+    Ok(())
+}
+
+/// Returns the number of claims of `staker` in [`CLAIMS`] and [`RECIPIENT_CLAIMS`] that were not claimed yet.
+pub fn pending_claims(deps: Deps, staker: &Addr) -> StdResult<usize> {
+    Ok(all_claims(deps, staker)?.len())
+}
+
+/// Iterates over all claims of `staker` that were not claimed yet: the ones in [`CLAIMS`] in the order
+/// they were created, followed by the ones in [`RECIPIENT_CLAIMS`], grouped by recipient.
+fn claims_of<'a>(
+    deps: Deps<'a>,
+    staker: &'a Addr,
+) -> StdResult<impl Iterator<Item = StdResult<Claim>> + 'a> {
+    let claims = CLAIMS.query_claims(deps, staker)?.claims;
+    let recipient_claims = RECIPIENT_CLAIMS
+        .prefix(staker)
+        .range(deps.storage, None, None, Order::Ascending)
+        .flat_map(|entry| match entry {
+            Ok((_, claims)) => claims.into_iter().map(Ok).collect(),
+            Err(err) => vec![Err(err)],
+        });
+    Ok(claims.into_iter().map(Ok).chain(recipient_claims))
+}
+
+/// Returns all claims of `staker` that were not claimed yet: the claims from [`CLAIMS`] in the order
+/// they were created, followed by the ones from [`RECIPIENT_CLAIMS`] ordered by recipient.
+pub fn all_claims(deps: Deps, staker: &Addr) -> StdResult<Vec<Claim>> {
+    claims_of(deps, staker)?.collect()
+}
+
+/// Releases the mature claims of `staker` from [`RECIPIENT_CLAIMS`].
+/// Like [`Claims::claim_tokens`], only whole claims are released and, if `cap` is set,
+/// claims that would exceed it are skipped.
+///
+/// Returns the released amount of each recipient and the number of released claims.
+pub fn claim_recipient_tokens(
+    storage: &mut dyn Storage,
+    staker: &Addr,
+    block: &BlockInfo,
+    cap: Option<Uint128>,
+) -> StdResult<(Vec<(Addr, Uint128)>, usize)> {
+    let all_claims = RECIPIENT_CLAIMS
+        .prefix(staker)
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let mut total = Uint128::zero();
+    let mut released_claims = 0;
+    let mut releases = vec![];
+    for (recipient, claims) in all_claims {
+        let mut released = Uint128::zero();
+        let remaining: Vec<_> = claims
+            .into_iter()
+            .filter(|claim| {
+                let fits = cap.map_or(true, |cap| total + claim.amount <= cap);
+                if claim.release_at.is_expired(block) && fits {
+                    total += claim.amount;
+                    released += claim.amount;
+                    released_claims += 1;
+                    false
+                } else {
+                    true
+                }
+            })
+            .collect();
+
+        if remaining.is_empty() {
+            RECIPIENT_CLAIMS.remove(storage, (staker, &recipient));
+        } else {
+            RECIPIENT_CLAIMS.save(storage, (staker, &recipient), &remaining)?;
+        }
+        if !released.is_zero() {
+            releases.push((recipient, released));
+        }
+    }
+
+    Ok((releases, released_claims))
+}
+
+/// ## Pagination settings
+/// The default limit for reading claims with [`read_claims`]
+const DEFAULT_CLAIMS_LIMIT: u32 = 10;
+/// The maximum limit for reading claims with [`read_claims`]
+pub const MAX_CLAIMS_LIMIT: u32 = 30;
+
+/// Reads the claims of `addr` from [`CLAIMS`] and [`RECIPIENT_CLAIMS`], ordered by their release time.
+/// Claims released at the same time are kept in the order of [`all_claims`].
+///
+/// `start_after` is the release timestamp (in seconds) of the last claim seen.
+///
+/// `limit` is the number of items to retrieve. Claims released at the same time are never split
+/// across pages, since `start_after` cannot tell them apart, so a page can contain more claims
+/// if several of them are released at the time of its last claim.
+pub fn read_claims(
+    deps: Deps,
+    addr: &Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Claim>> {
+    let limit = limit.unwrap_or(DEFAULT_CLAIMS_LIMIT).min(MAX_CLAIMS_LIMIT) as usize;
+
+    // all claims of this contract are released at a point in time
+    let mut claims = vec![];
+    for claim in claims_of(deps, addr)? {
+        let claim = claim?;
+        if let Expiration::AtTime(time) = claim.release_at {
+            if start_after.map_or(true, |start| time.seconds() > start) {
+                claims.push((time.seconds(), claim));
+            }
+        }
+    }
+    // the sort is stable, so it keeps the order of claims released at the same time
+    claims.sort_by_key(|(time, _)| *time);
+
+    let end = match limit.checked_sub(1).and_then(|last| claims.get(last)) {
+        Some(&(last_time, _)) => claims
+            .iter()
+            .position(|(time, _)| *time > last_time)
+            .unwrap_or(claims.len()),
+        None if limit == 0 => 0,
+        None => claims.len(),
+    };
+    Ok(claims
+        .into_iter()
+        .take(end)
+        .map(|(_, claim)| claim)
+        .collect())
+}
+
+/// The token that is staked in this contract
+#[cw_serde]
+pub enum StakedToken {
+    /// A cw20 token, bonded by sending it to this contract
+    Cw20(Addr),
+    /// A native token, bonded with `ExecuteMsg::BondNative`
+    Native(String),
+}
+
+impl StakedToken {
+    pub fn asset_info(&self) -> AssetInfoValidated {
+        match self {
+            StakedToken::Cw20(addr) => AssetInfoValidated::Token(addr.clone()),
+            StakedToken::Native(denom) => AssetInfoValidated::Native(denom.clone()),
+        }
+    }
+}
+
+impl fmt::Display for StakedToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            StakedToken::Cw20(addr) => write!(f, "{}", addr),
+            StakedToken::Native(denom) => write!(f, "{}", denom),
+        }
+    }
+}
+
 #[cw_serde]
 pub struct Config {
-    /// address of cw20 contract token to stake
-    pub cw20_contract: Addr,
+    /// the token to stake
+    pub staked_token: StakedToken,
     /// address that instantiated the contract
     pub instantiator: Addr,
     pub tokens_per_power: Uint128,
     pub min_bond: Uint128,
+    /// The `min_bond` before it was first changed by the admin.
+    /// Stakes that did not change since then are still compared against it, see [`BondingInfo::powered_stake`].
+    #[serde(default)]
+    pub original_min_bond: Option<Uint128>,
     /// configured unbonding periods in seconds
     pub unbonding_periods: Vec<UnbondingPeriod>,
     /// the maximum number of distributions that can be created
     pub max_distributions: u32,
+    /// the penalty for unbonding instantly. `None` means instant unbonding is disabled
+    pub instant_unbond_penalty: Option<Decimal>,
+    /// the maximum number of pending claims a staker can have, see [`pending_claims`]
+    #[serde(default = "default_max_claims_per_user")]
+    pub max_claims_per_user: u32,
+}
+
+/// The maximum number of pending claims per staker the contract is instantiated with
+pub const DEFAULT_MAX_CLAIMS_PER_USER: u32 = 100;
+
+fn default_max_claims_per_user() -> u32 {
+    DEFAULT_MAX_CLAIMS_PER_USER
 }
 
 #[cw_serde]
@@ -32,6 +227,11 @@ pub struct BondingInfo {
     stake: Uint128,
     /// Vec of locked_tokens sorted by expiry timestamp
     locked_tokens: Vec<(Timestamp, Uint128)>,
+    /// Whether the stake counts towards the rewards power. This is decided by comparing the stake
+    /// to `min_bond` every time it changes, so a new `min_bond` only applies once the stake changes.
+    /// `None` if the stake did not change since before `min_bond` was first updated.
+    #[serde(default)]
+    powered: Option<bool>,
 }
 
 impl BondingInfo {
@@ -82,6 +282,111 @@ impl BondingInfo {
         Ok(self.stake)
     }
 
+    /// Like [`BondingInfo::release_stake`], but also releases locked tokens if there are not
+    /// enough unlocked ones. Unlocked tokens are released first, then locked tokens in the order they unlock.
+    /// On success, returns the released locked tokens together with their unlock time.
+    pub fn release_stake_including_locked(
+        &mut self,
+        env: &Env,
+        amount: Uint128,
+    ) -> Result<Vec<(Timestamp, Uint128)>, OverflowError> {
+        self.free_unlocked_tokens(env);
+        // fail if there are not enough tokens
+        self.total_stake().checked_sub(amount)?;
+
+        let unlocked = self.stake.min(amount);
+        self.stake -= unlocked;
+
+        let mut remaining = amount - unlocked;
+        let mut released = vec![];
+        for (expires, locked) in self.locked_tokens.iter_mut() {
+            if remaining.is_zero() {
+                break;
+            }
+            let moved = (*locked).min(remaining);
+            released.push((*expires, moved));
+            *locked -= moved;
+            remaining -= moved;
+        }
+        self.locked_tokens.retain(|(_, locked)| !locked.is_zero());
+
+        Ok(released)
+    }
+
+    /// Adds tokens that are rebonded into this BondingInfo from another unbonding period.
+    /// When moving to a shorter unbonding period, the tokens are locked for the difference between
+    /// both periods (on top of any existing lock, so locked tokens unlock that much later).
+    /// When moving to a longer period, existing locks are shortened by the difference,
+    /// since the longer unbonding period already covers it.
+    pub fn add_rebonded_tokens(
+        &mut self,
+        env: &Env,
+        unlocked: Uint128,
+        locked_tokens: Vec<(Timestamp, Uint128)>,
+        bond_from: UnbondingPeriod,
+        bond_to: UnbondingPeriod,
+    ) {
+        if bond_from > bond_to {
+            let diff = bond_from - bond_to;
+            if !unlocked.is_zero() {
+                self.add_locked_tokens(env.block.time.plus_seconds(diff), unlocked);
+            }
+            for (expires, amount) in locked_tokens {
+                self.add_locked_tokens(expires.plus_seconds(diff), amount);
+            }
+        } else {
+            let diff = bond_to - bond_from;
+            self.add_unlocked_tokens(unlocked);
+            for (expires, amount) in locked_tokens {
+                let expires = expires.minus_seconds(diff);
+                if expires <= env.block.time {
+                    self.add_unlocked_tokens(amount);
+                } else {
+                    self.add_locked_tokens(expires, amount);
+                }
+            }
+        }
+    }
+
+    /// Moves all tokens (locked and unlocked) of this BondingInfo into `target`, leaving this one empty.
+    /// Follows the same locking rules as rebonding, see [`BondingInfo::add_rebonded_tokens`].
+    pub fn move_into(
+        &mut self,
+        env: &Env,
+        target: &mut BondingInfo,
+        bond_from: UnbondingPeriod,
+        bond_to: UnbondingPeriod,
+    ) {
+        self.free_unlocked_tokens(env);
+        let stake = std::mem::take(&mut self.stake);
+        let locked_tokens = std::mem::take(&mut self.locked_tokens);
+
+        target.add_rebonded_tokens(env, stake, locked_tokens, bond_from, bond_to);
+    }
+
+    /// Moves `amount` of tokens of this BondingInfo into `target`, which has the same unbonding period.
+    /// Unlocked tokens are moved first, then locked tokens in the order they unlock.
+    /// Locked tokens keep their unlock time.
+    pub fn transfer_into(
+        &mut self,
+        env: &Env,
+        target: &mut BondingInfo,
+        amount: Uint128,
+    ) -> Result<(), OverflowError> {
+        let locked_tokens = self.release_stake_including_locked(env, amount)?;
+
+        let locked = locked_tokens
+            .iter()
+            .map(|(_, locked)| *locked)
+            .sum::<Uint128>();
+        target.add_unlocked_tokens(amount - locked);
+        for (expires, locked) in locked_tokens {
+            target.add_locked_tokens(expires, locked);
+        }
+
+        Ok(())
+    }
+
     /// Return all locked tokens at a given block time that is all
     /// locked_tokens with a Timestamp > the block time passed in env as a param
     pub fn total_locked(&self, env: &Env) -> Uint128 {
@@ -106,6 +411,46 @@ impl BondingInfo {
         unlocked_stake
     }
 
+    /// Return the locked_tokens entries that are still locked at the given block time,
+    /// sorted by the time they unlock
+    pub fn locked_tokens(&self, env: &Env) -> Vec<(Timestamp, Uint128)> {
+        self.locked_tokens
+            .iter()
+            .filter(|(t, _)| t > &env.block.time)
+            .copied()
+            .collect()
+    }
+
+    /// Return the stake that counts towards the rewards power, which is zero if the stake
+    /// was below `min_bond` the last time it changed
+    pub fn powered_stake(&self, cfg: &Config) -> Uint128 {
+        let stake = self.total_stake();
+        if self.is_powered(cfg, stake) {
+            stake
+        } else {
+            Uint128::zero()
+        }
+    }
+
+    /// Compares the new stake to `min_bond` after it changed from `old_stake` to `new_stake`.
+    /// Returns whether the stake counted towards the rewards power before and whether it does now.
+    pub fn update_powered(
+        &mut self,
+        cfg: &Config,
+        old_stake: Uint128,
+        new_stake: Uint128,
+    ) -> (bool, bool) {
+        let previously_powered = self.is_powered(cfg, old_stake);
+        let now_powered = new_stake >= cfg.min_bond;
+        self.powered = Some(now_powered);
+        (previously_powered, now_powered)
+    }
+
+    fn is_powered(&self, cfg: &Config, stake: Uint128) -> bool {
+        self.powered
+            .unwrap_or_else(|| stake >= cfg.original_min_bond.unwrap_or(cfg.min_bond))
+    }
+
     /// Return all stake for this BondingInfo, including locked_tokens
     pub fn total_stake(&self) -> Uint128 {
         let total_stake: Uint128 = self
@@ -121,7 +466,119 @@ pub const REWARD_CURVE: Map<&AssetInfoValidated, Curve> = Map::new("reward_curve
 pub const ADMIN: Admin = Admin::new("admin");
 pub const CONFIG: Item<Config> = Item::new("config");
 
-#[derive(Default, Serialize, Deserialize)]
+/// An action of the admin or a distribution manager that changes how the contract works
+#[cw_serde]
+pub enum AdminAction {
+    UpdateAdmin {
+        admin: Option<Addr>,
+    },
+    UpdateTokensPerPower {
+        tokens_per_power: Uint128,
+    },
+    UpdateMinBond {
+        min_bond: Uint128,
+    },
+    UpdateMaxClaimsPerUser {
+        max_claims_per_user: u32,
+    },
+    SetMaxDistributions {
+        max: u32,
+    },
+    AddUnbondingPeriod {
+        period: UnbondingPeriod,
+    },
+    RemoveUnbondingPeriod {
+        period: UnbondingPeriod,
+        migrate_to: UnbondingPeriod,
+    },
+    CreateDistributionFlow {
+        asset: AssetInfoValidated,
+        manager: Addr,
+    },
+    CloseDistributionFlow {
+        asset: AssetInfoValidated,
+    },
+    UpdateRewardMultipliers {
+        asset: AssetInfoValidated,
+        rewards: Vec<(UnbondingPeriod, Decimal)>,
+    },
+}
+
+#[cw_serde]
+pub struct AdminActionEntry {
+    /// Increases by one with every recorded action
+    pub id: u64,
+    /// The block height the action was executed at
+    pub height: u64,
+    pub sender: Addr,
+    pub action: AdminAction,
+}
+
+/// The maximum number of entries kept in [`ADMIN_ACTIONS`]. Older entries are pruned.
+pub const MAX_ADMIN_ACTIONS: u64 = 100;
+/// The log of admin actions, keyed by their id
+pub const ADMIN_ACTIONS: Map<u64, AdminActionEntry> = Map::new("admin_actions");
+/// The id of the next entry of [`ADMIN_ACTIONS`]
+pub const NEXT_ADMIN_ACTION_ID: Item<u64> = Item::new("next_admin_action_id");
+
+/// Appends `action` to the log of admin actions, pruning the oldest entry once there are
+/// more than [`MAX_ADMIN_ACTIONS`].
+pub fn record_admin_action(
+    storage: &mut dyn Storage,
+    block: &BlockInfo,
+    sender: &Addr,
+    action: AdminAction,
+) -> StdResult<()> {
+    let id = NEXT_ADMIN_ACTION_ID.may_load(storage)?.unwrap_or_default();
+    ADMIN_ACTIONS.save(
+        storage,
+        id,
+        &AdminActionEntry {
+            id,
+            height: block.height,
+            sender: sender.clone(),
+            action,
+        },
+    )?;
+    NEXT_ADMIN_ACTION_ID.save(storage, &(id + 1))?;
+    if id >= MAX_ADMIN_ACTIONS {
+        ADMIN_ACTIONS.remove(storage, id - MAX_ADMIN_ACTIONS);
+    }
+    Ok(())
+}
+
+/// The default limit for reading entries with [`read_admin_actions`]
+const DEFAULT_ADMIN_ACTIONS_LIMIT: u32 = 10;
+/// The maximum limit for reading entries with [`read_admin_actions`]
+pub const MAX_ADMIN_ACTIONS_LIMIT: u32 = 30;
+
+/// Reads the recorded admin actions, oldest first.
+///
+/// `start_after` is the id of the last entry of the previous page.
+///
+/// `limit` is the number of items to retrieve.
+pub fn read_admin_actions(
+    storage: &dyn Storage,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<AdminActionEntry>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_ADMIN_ACTIONS_LIMIT)
+        .min(MAX_ADMIN_ACTIONS_LIMIT) as usize;
+
+    ADMIN_ACTIONS
+        .range(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .map(|item| item.map(|(_, entry)| entry))
+        .take(limit)
+        .collect()
+}
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TokenInfo {
     // how many tokens are fully bonded
     pub staked: Uint128,
@@ -135,11 +592,52 @@ impl TokenInfo {
     }
 }
 
-pub const TOTAL_STAKED: Item<TokenInfo> = Item::new("total_staked");
+/// The totals of all stakers, snapshotted at every height they change.
+/// This allows querying past totals, e.g. to verify past reward distributions.
+pub const TOTAL_STAKED: SnapshotItem<TokenInfo> = SnapshotItem::new(
+    "total_staked",
+    "total_staked__checkpoints",
+    "total_staked__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Updates [`TOTAL_STAKED`] with `action`, writing a snapshot at `height` only if the totals changed.
+pub fn update_total_staked(
+    storage: &mut dyn Storage,
+    height: u64,
+    action: impl FnOnce(TokenInfo) -> TokenInfo,
+) -> StdResult<()> {
+    let old_totals = TOTAL_STAKED.load(storage)?;
+    let new_totals = action(old_totals.clone());
+    if new_totals != old_totals {
+        TOTAL_STAKED.save(storage, &new_totals, height)?;
+    }
+    Ok(())
+}
 
 pub const STAKE: Map<(&Addr, UnbondingPeriod), BondingInfo> = Map::new("stake");
 
 #[derive(Default, Serialize, Deserialize)]
+pub struct StakingMetrics {
+    /// The number of addresses with a non-zero stake in at least one unbonding period
+    pub stakers: u64,
+    /// The number of non-zero stakes, counting every unbonding period of a staker separately
+    pub positions: u64,
+}
+
+/// Counts the entries of [`STAKE`] to avoid scanning it. Kept up to date by `update_total_stake`.
+pub const STAKING_METRICS: Item<StakingMetrics> = Item::new("staking_metrics");
+
+/// The total stake of each staker over all unbonding periods, snapshotted at every height it changes.
+/// This allows querying past stakes, e.g. for voting.
+pub const STAKE_SNAPSHOTS: SnapshotMap<&Addr, Uint128> = SnapshotMap::new(
+    "stake_snapshots",
+    "stake_snapshots__checkpoints",
+    "stake_snapshots__changelog",
+    Strategy::EveryBlock,
+);
+
+#[derive(Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct TotalStake {
     /// Total stake
     pub staked: Uint128,
@@ -153,8 +651,25 @@ pub struct TotalStake {
 /// want to count stakes below min_bond into the total.
 ///
 /// Using an item here to save some gas.
-pub const TOTAL_PER_PERIOD: Item<Vec<(UnbondingPeriod, TotalStake)>> =
-    Item::new("total_per_period");
+/// Like [`TOTAL_STAKED`], it is snapshotted at every height it changes.
+pub const TOTAL_PER_PERIOD: SnapshotItem<Vec<(UnbondingPeriod, TotalStake)>> = SnapshotItem::new(
+    "total_per_period",
+    "total_per_period__checkpoints",
+    "total_per_period__changelog",
+    Strategy::EveryBlock,
+);
+
+/// Saves `totals` to [`TOTAL_PER_PERIOD`], writing a snapshot at `height` only if they changed.
+pub fn save_total_per_period(
+    storage: &mut dyn Storage,
+    height: u64,
+    totals: Vec<(UnbondingPeriod, TotalStake)>,
+) -> StdResult<()> {
+    if TOTAL_PER_PERIOD.may_load(storage)? != Some(totals.clone()) {
+        TOTAL_PER_PERIOD.save(storage, &totals, height)?;
+    }
+    Ok(())
+}
 
 /// Loads the total powered stake of the given period.
 /// See [`TOTAL_PER_PERIOD`] for more details.
@@ -195,6 +710,20 @@ pub struct Distribution {
     pub manager: Addr,
     /// Rewards multiplier by unbonding period for this distribution
     pub reward_multipliers: Vec<(UnbondingPeriod, Decimal)>,
+    /// When the manager paused this distribution, in seconds. `None` if it is not paused.
+    #[serde(default)]
+    pub paused_at: Option<u64>,
+    /// When the admin closed this distribution, in seconds. `None` if it is still open.
+    /// A closed distribution does not accrue anymore and is removed once all rewards are withdrawn.
+    #[serde(default)]
+    pub closed_at: Option<u64>,
+    /// How many distributions of the same asset were removed before this one.
+    /// Withdraw adjustments of an older generation are ignored.
+    #[serde(default)]
+    pub generation: u64,
+    /// How many changes of `tokens_per_power` were recorded in [`POWER_RESCALES`] for this distribution.
+    #[serde(default)]
+    pub rescales: u64,
 }
 
 impl Distribution {
@@ -209,20 +738,71 @@ impl Distribution {
             .map_err(|_| ContractError::NoUnbondingPeriodFound(unbonding_period))
     }
 
+    /// Returns the total rewards power of the given unbonding period within this distribution.
     pub fn total_rewards_power_of_period(
         &self,
         storage: &dyn Storage,
         cfg: &Config,
         period: UnbondingPeriod,
     ) -> Result<Uint128, ContractError> {
-        let totals = TOTAL_PER_PERIOD.load(storage).unwrap_or_default();
-        let total = totals
-            .binary_search_by_key(&period, |(period, _)| *period)
-            .map(|idx| totals[idx].1.powered_stake) // map to powered stake
-            .map_err(|_| ContractError::NoUnbondingPeriodFound(period))?;
+        let total = load_total_of_period(storage, period)?.powered_stake;
         Ok(calc_power(cfg, total, self.rewards_multiplier(period)?))
     }
 
+    /// Returns the withdraw adjustment of `staker` in this distribution as it is stored.
+    /// An adjustment left behind by a removed distribution of the same asset counts as empty.
+    pub fn stored_withdraw_adjustment(
+        &self,
+        storage: &dyn Storage,
+        staker: &Addr,
+        asset_info: &AssetInfoValidated,
+    ) -> StdResult<WithdrawAdjustment> {
+        Ok(WITHDRAW_ADJUSTMENT
+            .may_load(storage, (staker, asset_info))?
+            .filter(|adjustment| adjustment.generation == self.generation)
+            .unwrap_or(WithdrawAdjustment {
+                generation: self.generation,
+                ..Default::default()
+            }))
+    }
+
+    /// Returns the withdraw adjustment of `staker` in this distribution, including the
+    /// `tokens_per_power` changes that were not applied to it yet.
+    ///
+    /// Each change is applied with the current stake of `staker`, so the stake must not have changed
+    /// since the last time the adjustment was stored. All stake updates store it first.
+    pub fn withdraw_adjustment(
+        &self,
+        storage: &dyn Storage,
+        cfg: &Config,
+        staker: &Addr,
+        asset_info: &AssetInfoValidated,
+    ) -> StdResult<WithdrawAdjustment> {
+        let mut adjustment = self.stored_withdraw_adjustment(storage, staker, asset_info)?;
+        let overflow =
+            || StdError::generic_err(ContractError::PointsCorrectionOverflow {}.to_string());
+        let to_i128 = |value: Uint128| i128::try_from(value.u128()).map_err(|_| overflow());
+        for idx in adjustment.rescales..self.rescales {
+            let rescale = POWER_RESCALES.load(storage, (asset_info, self.generation, idx))?;
+            let power_for = |tokens_per_power| {
+                let cfg = Config {
+                    tokens_per_power,
+                    ..cfg.clone()
+                };
+                self.calc_rewards_power(storage, &cfg, staker)
+            };
+            let power_before = to_i128(power_for(rescale.tokens_per_power_before)?)?;
+            let power_after = to_i128(power_for(rescale.tokens_per_power_after)?)?;
+            // the rewards up to the change stay the same, only the later ones use the new power
+            adjustment.shares_correction = to_i128(rescale.shares_per_point)?
+                .checked_mul(power_before - power_after)
+                .and_then(|correction| adjustment.shares_correction.checked_add(correction))
+                .ok_or_else(overflow)?;
+        }
+        adjustment.rescales = self.rescales;
+        Ok(adjustment)
+    }
+
     /// Returns the total rewards power within this distribution.
     pub fn total_rewards_power(&self, storage: &dyn Storage, cfg: &Config) -> Uint128 {
         let totals = TOTAL_PER_PERIOD.load(storage).unwrap_or_default();
@@ -242,19 +822,50 @@ impl Distribution {
             .sum::<Uint128>()
     }
 
+    /// Distributes `amount` over `total_rewards` power by increasing `shares_per_point`.
+    /// Returns the increment of `shares_per_point`, so each staker receives its rewards power times
+    /// the increment, shifted right by [`SHARES_SHIFT`].
+    pub fn distribute(&mut self, amount: u128, total_rewards: Uint128) -> Uint128 {
+        let leftover: u128 = self.shares_leftover.into();
+        let points = (amount << SHARES_SHIFT) + leftover;
+        let points_per_share = Uint128::new(points / total_rewards.u128());
+        self.shares_leftover = (points % total_rewards.u128()) as u64;
+
+        // Everything goes back to 128-bits/16-bytes
+        // Full amount is added here to total withdrawable, as it should not be considered on its own
+        // on future distributions - even if because of calculation offsets it is not fully
+        // distributed, the error is handled by leftover.
+        self.shares_per_point += points_per_share;
+        self.distributed_total += Uint128::new(amount);
+        self.withdrawable_total += Uint128::new(amount);
+        points_per_share
+    }
+
     pub fn calc_rewards_power(
         &self,
         storage: &dyn Storage,
         cfg: &Config,
         staker: &Addr,
+    ) -> StdResult<Uint128> {
+        self.calc_rewards_power_with(cfg, |unbonding_period| {
+            Ok(STAKE
+                .may_load(storage, (staker, unbonding_period))?
+                .unwrap_or_default())
+        })
+    }
+
+    /// Same as [`Self::calc_rewards_power`], but with the bonding info of each unbonding period
+    /// provided by `bonding_info`, e.g. to calculate the rewards power after a simulated change.
+    pub fn calc_rewards_power_with(
+        &self,
+        cfg: &Config,
+        mut bonding_info: impl FnMut(UnbondingPeriod) -> StdResult<BondingInfo>,
     ) -> StdResult<Uint128> {
         // get rewards for all unbonding periods
         let mut power = Uint128::zero();
         for &(unbonding_period, multiplier) in self.reward_multipliers.iter() {
-            let bonding_info = STAKE
-                .may_load(storage, (staker, unbonding_period))?
-                .unwrap_or_default();
-            power += calc_power(cfg, bonding_info.total_stake(), multiplier);
+            let bonding_info = bonding_info(unbonding_period)?;
+            power += calc_power(cfg, bonding_info.powered_stake(cfg), multiplier);
         }
         Ok(power)
     }
@@ -267,17 +878,78 @@ pub struct WithdrawAdjustment {
     pub shares_correction: i128,
     /// How much funds addresses already withdrawn.
     pub withdrawn_rewards: Uint128,
+    /// The [`Distribution::generation`] this adjustment belongs to.
+    #[serde(default)]
+    pub generation: u64,
+    /// How many of the [`Distribution::rescales`] are already included in `shares_correction`.
+    #[serde(default)]
+    pub rescales: u64,
+}
+
+/// A change of `tokens_per_power` while a distribution was active.
+#[cw_serde]
+pub struct PowerRescale {
+    pub tokens_per_power_before: Uint128,
+    pub tokens_per_power_after: Uint128,
+    /// The shares per point of the distribution at the time of the change
+    pub shares_per_point: Uint128,
 }
 
 /// Rewards distribution data
 pub const DISTRIBUTION: Map<&AssetInfoValidated, Distribution> = Map::new("distribution");
+/// The final state of distribution flows that were closed and removed.
+/// A new flow of the same asset continues with the next [`Distribution::generation`].
+pub const CLOSED_DISTRIBUTION: Map<&AssetInfoValidated, Distribution> =
+    Map::new("closed_distribution");
 /// Information how to exactly adjust rewards while withdrawal.
 /// This is per user, so it applies to all distributions.
 pub const WITHDRAW_ADJUSTMENT: Map<(&Addr, &AssetInfoValidated), WithdrawAdjustment> =
     Map::new("withdraw_adjustment");
+/// The `tokens_per_power` changes of each distribution, keyed by `(asset, generation, index)`.
+/// They are applied to the withdraw adjustment of each staker the next time it is loaded.
+pub const POWER_RESCALES: Map<(&AssetInfoValidated, u64, u64), PowerRescale> =
+    Map::new("power_rescales");
 
 /// User delegated for funds withdrawal
 pub const DELEGATED: Map<&Addr, Addr> = Map::new("delegated");
+/// Index of [`DELEGATED`] by the delegate, keyed by `(delegate, owner)`.
+/// It is kept in sync with [`DELEGATED`] in `execute_delegate_withdrawal`.
+pub const DELEGATED_TO: Map<(&Addr, &Addr), Empty> = Map::new("delegated_to");
+
+/// The default limit for reading owners with [`read_delegated_to`]
+const DEFAULT_DELEGATED_TO_LIMIT: u32 = 10;
+/// The maximum limit for reading owners with [`read_delegated_to`]
+pub const MAX_DELEGATED_TO_LIMIT: u32 = 30;
+
+/// Reads the owners that delegated their withdrawals to `delegate`, ordered by address.
+///
+/// `start_after` is the last owner of the previous page.
+///
+/// `limit` is the number of items to retrieve.
+pub fn read_delegated_to(
+    storage: &dyn Storage,
+    delegate: &Addr,
+    start_after: Option<&Addr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<Addr>> {
+    let limit = limit
+        .unwrap_or(DEFAULT_DELEGATED_TO_LIMIT)
+        .min(MAX_DELEGATED_TO_LIMIT) as usize;
+
+    DELEGATED_TO
+        .prefix(delegate)
+        .keys(
+            storage,
+            start_after.map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect()
+}
+
+/// Address the rewards of a staker are sent to if no receiver is given on withdrawal
+pub const REWARD_RECEIVER: Map<&Addr, Addr> = Map::new("reward_receiver");
 
 #[cfg(test)]
 mod tests {
@@ -369,4 +1041,36 @@ mod tests {
         assert_eq!(info.total_stake(), Uint128::new(1500));
         assert_eq!(info.total_locked(&env), Uint128::new(1000u128));
     }
+
+    #[test]
+    fn test_transfer_into() {
+        let mut info = BondingInfo::default();
+        let mut target = BondingInfo::default();
+        let env = mock_env();
+
+        info.add_unlocked_tokens(Uint128::new(1000u128));
+        info.add_locked_tokens(env.block.time.plus_seconds(10), Uint128::new(500u128));
+        info.add_locked_tokens(env.block.time.plus_seconds(20), Uint128::new(500u128));
+
+        // cannot transfer more than the total stake
+        info.transfer_into(&env, &mut target, Uint128::new(2001u128))
+            .unwrap_err();
+
+        // unlocked tokens go first, then the earliest locked ones
+        info.transfer_into(&env, &mut target, Uint128::new(1200u128))
+            .unwrap();
+        assert_eq!(info.total_unlocked(&env), Uint128::zero());
+        assert_eq!(
+            info.locked_tokens(&env),
+            [
+                (env.block.time.plus_seconds(10), Uint128::new(300u128)),
+                (env.block.time.plus_seconds(20), Uint128::new(500u128))
+            ]
+        );
+        assert_eq!(target.total_unlocked(&env), Uint128::new(1000u128));
+        assert_eq!(
+            target.locked_tokens(&env),
+            [(env.block.time.plus_seconds(10), Uint128::new(200u128))]
+        );
+    }
 }
@@ -3,12 +3,10 @@ use wynd_curve_utils::{Curve, PiecewiseLinear, SaturatingLinear};
 
 use crate::state::Config;
 
+/// Calculates the rewards power of the given stake.
+/// Stake below `min_bond` has to be excluded by the caller already, see [`crate::state::BondingInfo::powered_stake`].
 pub fn calc_power(cfg: &Config, stake: Uint128, multiplier: Decimal) -> Uint128 {
-    if stake < cfg.min_bond {
-        Uint128::zero()
-    } else {
-        stake * multiplier / cfg.tokens_per_power
-    }
+    stake * multiplier / cfg.tokens_per_power
 }
 
 pub trait CurveExt {
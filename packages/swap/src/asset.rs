@@ -3,10 +3,12 @@ use cosmwasm_std::Env;
 use cw_storage_plus::{Key, KeyDeserialize, Prefixer, PrimaryKey};
 use std::fmt;
 
+use crate::cw1155::Cw1155ExecuteMsg;
 use crate::pair::PairInfo;
 use crate::pair::QueryMsg as PairQueryMsg;
 use crate::querier::{
-    query_balance, query_token_balance, query_token_symbol, NATIVE_TOKEN_PRECISION,
+    query_balance, query_cw1155_balance, query_token_balance, query_token_symbol,
+    NATIVE_TOKEN_PRECISION,
 };
 use cosmwasm_std::{
     to_binary, Addr, Api, BankMsg, Coin, ConversionOverflowError, CosmosMsg, Decimal256, Fraction,
@@ -15,7 +17,11 @@ use cosmwasm_std::{
 use cw20::{Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse, TokenInfoResponse};
 use itertools::Itertools;
 
-/// Minimum initial LP share
+/// Minimum initial LP share.
+/// The pairs mint this amount of LP tokens to themselves on the first deposit and never burn them,
+/// so the LP supply can never shrink back to a few tokens. Without it, the first depositor could
+/// mint a tiny supply and inflate the value of a single LP token by donating assets to the pair,
+/// making the deposits of later providers round down to (almost) nothing.
 pub const MINIMUM_LIQUIDITY_AMOUNT: Uint128 = Uint128::new(1_000);
 
 /// This enum describes a Terra asset (native or CW20).
@@ -91,6 +97,9 @@ impl AssetValidated {
     /// Before the token is sent, we need to deduct a tax.
     ///
     /// For a token of type [`AssetInfo`] we use the default method [`Cw20ExecuteMsg::Transfer`] and so there's no need to deduct any other tax.
+    ///
+    /// cw1155 transfers need to know the current owner of the tokens, so they are rejected here.
+    /// Use [`AssetValidated::into_send_from_msg`] for them instead.
     pub fn into_msg(&self, recipient: impl Into<String>) -> StdResult<CosmosMsg> {
         let recipient = recipient.into();
         match &self.info {
@@ -109,11 +118,45 @@ impl AssetValidated {
                     amount: self.amount,
                 }],
             })),
+            AssetInfoValidated::Cw1155 { .. } => Err(StdError::generic_err(
+                "Cw1155 transfers need an owner, use into_send_from_msg",
+            )),
+        }
+    }
+
+    /// Returns a [`Cw1155ExecuteMsg::SendFrom`] message that moves the tokens from `from` to `recipient`.
+    /// The message sender needs to be `from` or an approved operator of it.
+    ///
+    /// Only cw1155 tokens are supported, use [`AssetValidated::into_msg`] for the others.
+    pub fn into_send_from_msg(
+        &self,
+        from: impl Into<String>,
+        recipient: impl Into<String>,
+    ) -> StdResult<CosmosMsg> {
+        match &self.info {
+            AssetInfoValidated::Cw1155 { contract, token_id } => {
+                Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: contract.to_string(),
+                    msg: to_binary(&Cw1155ExecuteMsg::SendFrom {
+                        from: from.into(),
+                        to: recipient.into(),
+                        token_id: token_id.clone(),
+                        value: self.amount,
+                        msg: None,
+                    })?,
+                    funds: vec![],
+                }))
+            }
+            _ => Err(StdError::generic_err(format!(
+                "{} is not a cw1155 token",
+                self.info
+            ))),
         }
     }
 
     /// For native coins, this asserts that they were received with this message already.
     /// For cw20 tokens, this adds a transfer message to the given `Vec` to receive them.
+    /// For cw1155 tokens, this adds a send message, which requires the contract to be an approved operator of the sender.
     pub fn receive(
         &self,
         env: &Env,
@@ -138,6 +181,10 @@ impl AssetValidated {
                 }));
                 Ok(())
             }
+            AssetInfoValidated::Cw1155 { .. } => {
+                messages.push(self.into_send_from_msg(&info.sender, &env.contract.address)?);
+                Ok(())
+            }
         }
     }
 
@@ -180,6 +227,8 @@ pub enum AssetInfo {
     Token(String),
     /// Native token
     Native(String),
+    /// A single token id of a cw1155 contract
+    Cw1155 { contract: String, token_id: String },
 }
 
 impl AssetInfo {
@@ -203,6 +252,15 @@ impl AssetInfo {
                 }
                 AssetInfoValidated::Native(denom.to_string())
             }
+            AssetInfo::Cw1155 { contract, token_id } => {
+                if token_id.is_empty() {
+                    return Err(StdError::generic_err("Cw1155 token id must not be empty"));
+                }
+                AssetInfoValidated::Cw1155 {
+                    contract: api.addr_validate(contract)?,
+                    token_id: token_id.to_string(),
+                }
+            }
         })
     }
     pub fn query_pool(
@@ -215,16 +273,22 @@ impl AssetInfo {
                 query_token_balance(querier, contract_addr, pool_addr)
             }
             AssetInfo::Native(denom) => query_balance(querier, pool_addr, denom),
+            AssetInfo::Cw1155 { contract, token_id } => {
+                query_cw1155_balance(querier, contract, token_id, pool_addr)
+            }
         }
     }
 
     /// If the caller object is a native token of type [`AssetInfo`] then his `denom` field converts to a byte string.
     ///
     /// If the caller object is a token of type [`AssetInfo`] then its `contract_addr` field converts to a byte string.
+    ///
+    /// For cw1155 tokens only the `contract` is used, so the result is not unique per token id.
     pub fn as_bytes(&self) -> &[u8] {
         match self {
             AssetInfo::Native(denom) => denom.as_bytes(),
             AssetInfo::Token(contract_addr) => contract_addr.as_bytes(),
+            AssetInfo::Cw1155 { contract, .. } => contract.as_bytes(),
         }
     }
 }
@@ -234,6 +298,7 @@ impl fmt::Display for AssetInfo {
         match self {
             AssetInfo::Native(denom) => write!(f, "{}", denom),
             AssetInfo::Token(contract_addr) => write!(f, "{}", contract_addr),
+            AssetInfo::Cw1155 { contract, token_id } => write!(f, "{}/{}", contract, token_id),
         }
     }
 }
@@ -253,6 +318,8 @@ pub enum AssetInfoValidated {
     Token(Addr),
     /// Native token
     Native(String),
+    /// A single token id of a cw1155 contract
+    Cw1155 { contract: Addr, token_id: String },
 }
 
 impl From<AssetInfoValidated> for AssetInfo {
@@ -260,6 +327,10 @@ impl From<AssetInfoValidated> for AssetInfo {
         match a {
             AssetInfoValidated::Token(addr) => AssetInfo::Token(addr.to_string()),
             AssetInfoValidated::Native(denom) => AssetInfo::Native(denom),
+            AssetInfoValidated::Cw1155 { contract, token_id } => AssetInfo::Cw1155 {
+                contract: contract.to_string(),
+                token_id,
+            },
         }
     }
 }
@@ -269,6 +340,9 @@ impl fmt::Display for AssetInfoValidated {
         match self {
             AssetInfoValidated::Native(denom) => write!(f, "{}", denom),
             AssetInfoValidated::Token(contract_addr) => write!(f, "{}", contract_addr),
+            AssetInfoValidated::Cw1155 { contract, token_id } => {
+                write!(f, "{}/{}", contract, token_id)
+            }
         }
     }
 }
@@ -300,6 +374,9 @@ impl AssetInfoValidated {
                 query_token_balance(querier, contract_addr, account_addr)
             }
             AssetInfoValidated::Native(denom) => query_balance(querier, account_addr, denom),
+            AssetInfoValidated::Cw1155 { contract, token_id } => {
+                query_cw1155_balance(querier, contract, token_id, account_addr)
+            }
         }
     }
 
@@ -313,6 +390,7 @@ impl AssetInfoValidated {
 
                 res.decimals
             }
+            AssetInfoValidated::Cw1155 { .. } => 0,
         };
 
         Ok(decimals)
@@ -329,6 +407,13 @@ impl AssetInfoValidated {
                 AssetInfoValidated::Token(contract_addr),
                 AssetInfoValidated::Token(other_contract_addr),
             ) => contract_addr == other_contract_addr,
+            (
+                AssetInfoValidated::Cw1155 { contract, token_id },
+                AssetInfoValidated::Cw1155 {
+                    contract: other_contract,
+                    token_id: other_token_id,
+                },
+            ) => contract == other_contract && token_id == other_token_id,
             _ => false,
         }
     }
@@ -336,10 +421,13 @@ impl AssetInfoValidated {
     /// If the caller object is a native token of type [`AssetInfo`] then his `denom` field converts to a byte string.
     ///
     /// If the caller object is a token of type [`AssetInfo`] then its `contract_addr` field converts to a byte string.
+    ///
+    /// For cw1155 tokens only the `contract` is used, so the result is not unique per token id.
     pub fn as_bytes(&self) -> &[u8] {
         match self {
             AssetInfoValidated::Native(denom) => denom.as_bytes(),
             AssetInfoValidated::Token(contract_addr) => contract_addr.as_bytes(),
+            AssetInfoValidated::Cw1155 { contract, .. } => contract.as_bytes(),
         }
     }
 }
@@ -348,6 +436,12 @@ impl KeyDeserialize for &AssetInfoValidated {
     type Output = AssetInfoValidated;
 
     fn from_vec(value: Vec<u8>) -> StdResult<Self::Output> {
+        // the first element is the length-prefixed asset type
+        if value.get(2) == Some(&2) {
+            let (_, contract, token_id) = <(u8, Addr, String)>::from_vec(value)?;
+            return Ok(AssetInfoValidated::Cw1155 { contract, token_id });
+        }
+
         let (asset_type, denom) = <(u8, &str)>::from_vec(value)?;
 
         match asset_type {
@@ -379,6 +473,11 @@ impl<'a> PrimaryKey<'a> for &AssetInfoValidated {
                 vec![Key::Val8([0]), Key::Ref(denom.as_bytes())]
             }
             AssetInfoValidated::Token(addr) => vec![Key::Val8([1]), Key::Ref(addr.as_bytes())],
+            AssetInfoValidated::Cw1155 { contract, token_id } => vec![
+                Key::Val8([2]),
+                Key::Ref(contract.as_bytes()),
+                Key::Ref(token_id.as_bytes()),
+            ],
         }
     }
 }
@@ -407,6 +506,9 @@ pub fn format_lp_token_name(
                 let token_symbol = query_token_symbol(querier, contract_addr)?;
                 token_symbol.chars().take(TOKEN_SYMBOL_MAX_LENGTH).collect()
             }
+            AssetInfoValidated::Cw1155 { token_id, .. } => {
+                token_id.chars().take(TOKEN_SYMBOL_MAX_LENGTH).collect()
+            }
         };
         short_symbols.push(short_symbol);
     }
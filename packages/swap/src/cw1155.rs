@@ -0,0 +1,28 @@
+//! The subset of the cw1155 multi-token interface that is needed to hold and move cw1155 assets.
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{Binary, Uint128};
+
+#[cw_serde]
+pub enum Cw1155ExecuteMsg {
+    /// Transfers `value` of `token_id` from `from` to `to`.
+    /// The sender needs to be `from` or an approved operator of it.
+    SendFrom {
+        from: String,
+        to: String,
+        token_id: String,
+        value: Uint128,
+        /// If set, `to` is called with a receive hook containing this message
+        msg: Option<Binary>,
+    },
+}
+
+#[cw_serde]
+pub enum Cw1155QueryMsg {
+    /// Returns the balance of `owner` for `token_id`
+    Balance { owner: String, token_id: String },
+}
+
+#[cw_serde]
+pub struct BalanceResponse {
+    pub balance: Uint128,
+}
@@ -1,7 +1,8 @@
 use crate::{
     asset::AssetInfo,
+    common::OwnershipProposal,
     fee_config::FeeConfig,
-    pair::{PairInfo, StakeConfig},
+    pair::{LpTokenType, PairInfo, StakeConfig},
     stake::UnbondingPeriod,
 };
 
@@ -13,9 +14,10 @@ use std::fmt::{Display, Formatter, Result};
 /// This enum describes available pair types.
 /// ## Available pool types
 /// ```
-/// # use sg_swap::factory::PairType::{Custom, Stable, Xyk};
+/// # use sg_swap::factory::PairType::{Custom, Stable, Weighted, Xyk};
 /// Xyk {};
 /// Stable {};
+/// Weighted { weights: [8_000, 2_000] };
 /// Custom(String::from("Custom"));
 /// ```
 #[cw_serde]
@@ -24,6 +26,9 @@ pub enum PairType {
     Xyk {},
     /// Stable pair type
     Stable {},
+    /// Weighted constant product pair type.
+    /// The weights are given in the same order as the pair's assets and have to sum up to [`WEIGHTS_BASIS`].
+    Weighted { weights: [u16; 2] },
     /// Custom pair type
     Custom(String),
 }
@@ -34,11 +39,44 @@ impl Display for PairType {
         match self {
             PairType::Xyk {} => fmt.write_str("xyk"),
             PairType::Stable {} => fmt.write_str("stable"),
+            PairType::Weighted { .. } => fmt.write_str("weighted"),
             PairType::Custom(pair_type) => fmt.write_str(format!("custom-{}", pair_type).as_str()),
         }
     }
 }
 
+/// The sum of the weights of a [`PairType::Weighted`] pair
+pub const WEIGHTS_BASIS: u16 = 10_000;
+
+/// The maximum number of pairs that can be created with one [`ExecuteMsg::CreatePairs`] message
+pub const MAX_PAIRS_PER_BATCH: usize = 10;
+
+/// The maximum number of pairs that rewards can be withdrawn from with one
+/// [`ExecuteMsg::WithdrawAllRewards`] message
+pub const MAX_REWARD_WITHDRAWALS: usize = 10;
+
+/// The maximum number of pairs that can be migrated with one [`ExecuteMsg::MigratePairs`] message
+pub const MAX_PAIR_MIGRATIONS: usize = 10;
+
+impl PairType {
+    /// Returns the weights of the pair's assets if this is a weighted pair type
+    pub fn weights(&self) -> Option<[u16; 2]> {
+        match self {
+            PairType::Weighted { weights } => Some(*weights),
+            _ => None,
+        }
+    }
+
+    /// Checks that the weights of a weighted pair type are both non-zero and sum up to [`WEIGHTS_BASIS`].
+    /// Always returns `true` for all other pair types.
+    pub fn valid_weights(&self) -> bool {
+        match self.weights() {
+            Some([w0, w1]) => w0 != 0 && w1 != 0 && w0.checked_add(w1) == Some(WEIGHTS_BASIS),
+            None => true,
+        }
+    }
+}
+
 /// This structure stores a pair type's configuration.
 #[cw_serde]
 pub struct PairConfig {
@@ -51,6 +89,13 @@ pub struct PairConfig {
     /// Whether a pair type is disabled or not. If it is disabled, new pairs cannot be
     /// created, but existing ones can still read the pair configuration
     pub is_disabled: bool,
+    /// The max spread applied to swaps on pairs of this type that do not specify one.
+    /// If not provided, the pair's default is used.
+    pub default_max_spread: Option<Decimal>,
+    /// The default init params for pairs of this type, e.g. the amp of stable pairs.
+    /// Used if the init params are not provided when creating a pair.
+    #[serde(default)]
+    pub init_params: Option<Binary>,
 }
 
 /// This structure stores the basic settings for creating a new factory contract.
@@ -71,6 +116,10 @@ pub struct InstantiateMsg {
     pub default_stake_config: DefaultStakeConfig,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// Addresses to split the protocol fees between, with weights summing up to one.
+    /// If this is not specified, all protocol fees are sent to `fee_address`
+    #[serde(default)]
+    pub fee_recipients: Option<Vec<(String, Decimal)>>,
 }
 
 #[cw_serde]
@@ -126,6 +175,10 @@ pub enum ExecuteMsg {
         fee_address: Option<String>,
         /// Whether only the owner or anyone can create new pairs
         only_owner_can_create_pairs: Option<bool>,
+        /// Addresses to split the protocol fees between, with weights summing up to one.
+        /// An empty list sends all protocol fees to the fee address again
+        #[serde(default)]
+        fee_recipients: Option<Vec<(String, Decimal)>>,
     },
     /// UpdatePairConfig updates the config for a pair type.
     UpdatePairConfig {
@@ -138,7 +191,8 @@ pub enum ExecuteMsg {
         pair_type: PairType,
         /// The assets to create the pool for
         asset_infos: Vec<AssetInfo>,
-        /// Optional binary serialised parameters for custom pool types
+        /// Optional binary serialised parameters for custom pool types.
+        /// Replaces the default init params of the pair type if provided.
         init_params: Option<Binary>,
         /// The total fees (in bps) charged by a pair of this type.
         /// In relation to the returned amount of tokens.
@@ -147,7 +201,28 @@ pub enum ExecuteMsg {
         /// Config for the staking contract
         #[serde(default)]
         staking_config: PartialStakeConfig,
+        /// Overrides the auto-generated name of the pair's cw20 LP token
+        #[serde(default)]
+        lp_token_name: Option<String>,
+        /// Overrides the default symbol of the pair's cw20 LP token
+        #[serde(default)]
+        lp_token_symbol: Option<String>,
+        /// The kind of token used for the pair's LP shares. Defaults to [`LpTokenType::Cw20`].
+        #[serde(default)]
+        lp_token_type: Option<LpTokenType>,
+        /// The block time until which trading is disabled in this pair.
+        /// Trading never starts before the factory's `trading_starts`.
+        #[serde(default)]
+        trading_starts: Option<u64>,
+        /// Disables trading for this many seconds after the pair is created,
+        /// as an alternative to `trading_starts`. Only one of them can be set.
+        #[serde(default)]
+        trading_starts_delay: Option<u64>,
     },
+    /// CreatePairs instantiates multiple new pair contracts at once.
+    /// If any of them cannot be created, none of them are.
+    /// At most [`MAX_PAIRS_PER_BATCH`] pairs can be created with one message.
+    CreatePairs { pairs: Vec<CreatePairParams> },
     /// UpdatePairFees updates the fees for a pair.
     /// This just sends the corresponding message to the pair.
     UpdatePairFees {
@@ -156,11 +231,43 @@ pub enum ExecuteMsg {
         /// The new fee config
         fee_config: FeeConfig,
     },
+    /// PausePair pauses or unpauses swaps and liquidity operations on a pair.
+    /// This just sends the corresponding message to the pair.
+    PausePair {
+        /// The pair to pause
+        asset_infos: Vec<AssetInfo>,
+        /// Whether the pair should be paused
+        paused: bool,
+    },
     /// Deregister removes a previously created pair.
     Deregister {
         /// The assets for which we deregister a pool
         asset_infos: Vec<AssetInfo>,
     },
+    /// BlockAsset prevents new pairs from being created for the given asset.
+    /// Existing pairs are not affected.
+    BlockAsset {
+        /// The asset to block
+        asset: AssetInfo,
+        /// The block time (in seconds) until which the asset is blocked.
+        /// If not provided, the asset is blocked until it is unblocked again.
+        until: Option<u64>,
+    },
+    /// UnblockAsset allows creating pairs for a previously blocked asset again.
+    UnblockAsset {
+        /// The asset to unblock
+        asset: AssetInfo,
+    },
+    /// WithdrawAllRewards withdraws the sender's rewards from the staking contracts of all given pairs.
+    /// The sender has to call `DelegateWithdrawal { delegated: <factory> }` on each of the
+    /// staking contracts before.
+    /// At most [`MAX_REWARD_WITHDRAWALS`] pairs can be given with one message.
+    WithdrawAllRewards {
+        /// The assets of each pair to withdraw the rewards from
+        pairs: Vec<Vec<AssetInfo>>,
+        /// Address to send the rewards to. Defaults to the sender
+        receiver: Option<String>,
+    },
     /// ProposeNewOwner creates a proposal to change contract ownership.
     /// The validity period for the proposal is set in the `expires_in` variable.
     ProposeNewOwner {
@@ -175,6 +282,31 @@ pub enum ExecuteMsg {
     ClaimOwnership {},
     /// MarkAsMigrated marks pairs as migrated
     MarkAsMigrated { pairs: Vec<String> },
+    /// AllowMigrationCodeId allows or disallows migrating pairs of a pair type to a code id
+    /// with [`ExecuteMsg::MigratePairs`].
+    AllowMigrationCodeId {
+        /// The pair type of the pairs to migrate
+        pair_type: PairType,
+        /// The code id to migrate the pairs to
+        code_id: u64,
+        /// Whether the pairs can be migrated to the code id
+        allowed: bool,
+    },
+    /// MigratePairs migrates the given pairs to a new code id and makes it the code id of their pair type,
+    /// so new pairs are created with it as well.
+    /// The code id has to be allowed for their pair type with [`ExecuteMsg::AllowMigrationCodeId`] first.
+    /// All pairs need to have the same pair type and the factory has to be their admin.
+    /// The factory is the admin of all pairs it creates. The admin of pairs created by older versions
+    /// of the factory has to be handed over to the factory first.
+    /// At most [`MAX_PAIR_MIGRATIONS`] pairs can be migrated with one message.
+    MigratePairs {
+        /// The assets of each pair to migrate
+        pairs: Vec<Vec<AssetInfo>>,
+        /// The code id to migrate the pairs to
+        new_code_id: u64,
+        /// The migrate message sent to every pair
+        msg: Binary,
+    },
     /// Combines pair creation and creation of distribution flows for the pair staking contract
     /// into one message
     CreatePairAndDistributionFlows {
@@ -182,7 +314,8 @@ pub enum ExecuteMsg {
         pair_type: PairType,
         /// The assets to create the pool for
         asset_infos: Vec<AssetInfo>,
-        /// Optional binary serialised parameters for custom pool types
+        /// Optional binary serialised parameters for custom pool types.
+        /// Replaces the default init params of the pair type if provided.
         init_params: Option<Binary>,
         /// The total fees (in bps) charged by a pair of this type.
         /// In relation to the returned amount of tokens.
@@ -191,6 +324,23 @@ pub enum ExecuteMsg {
         /// Config for the staking contract
         #[serde(default)]
         staking_config: PartialStakeConfig,
+        /// Overrides the auto-generated name of the pair's cw20 LP token
+        #[serde(default)]
+        lp_token_name: Option<String>,
+        /// Overrides the default symbol of the pair's cw20 LP token
+        #[serde(default)]
+        lp_token_symbol: Option<String>,
+        /// The kind of token used for the pair's LP shares. Defaults to [`LpTokenType::Cw20`].
+        #[serde(default)]
+        lp_token_type: Option<LpTokenType>,
+        /// The block time until which trading is disabled in this pair.
+        /// Trading never starts before the factory's `trading_starts`.
+        #[serde(default)]
+        trading_starts: Option<u64>,
+        /// Disables trading for this many seconds after the pair is created,
+        /// as an alternative to `trading_starts`. Only one of them can be set.
+        #[serde(default)]
+        trading_starts_delay: Option<u64>,
         /// The distribution flows to create
         distribution_flows: Vec<DistributionFlow>,
     },
@@ -207,6 +357,43 @@ pub enum ExecuteMsg {
     },
 }
 
+/// The parameters of one pair created by [`ExecuteMsg::CreatePairs`].
+/// These are the same as the ones of [`ExecuteMsg::CreatePair`].
+#[cw_serde]
+pub struct CreatePairParams {
+    /// The pair type (exposed in [`PairType`])
+    pub pair_type: PairType,
+    /// The assets to create the pool for
+    pub asset_infos: Vec<AssetInfo>,
+    /// Optional binary serialised parameters for custom pool types.
+    /// Replaces the default init params of the pair type if provided.
+    pub init_params: Option<Binary>,
+    /// The total fees (in bps) charged by a pair of this type.
+    /// In relation to the returned amount of tokens.
+    /// If not provided, the default is used.
+    pub total_fee_bps: Option<u16>,
+    /// Config for the staking contract
+    #[serde(default)]
+    pub staking_config: PartialStakeConfig,
+    /// Overrides the auto-generated name of the pair's cw20 LP token
+    #[serde(default)]
+    pub lp_token_name: Option<String>,
+    /// Overrides the default symbol of the pair's cw20 LP token
+    #[serde(default)]
+    pub lp_token_symbol: Option<String>,
+    /// The kind of token used for the pair's LP shares. Defaults to [`LpTokenType::Cw20`].
+    #[serde(default)]
+    pub lp_token_type: Option<LpTokenType>,
+    /// The block time until which trading is disabled in this pair.
+    /// Trading never starts before the factory's `trading_starts`.
+    #[serde(default)]
+    pub trading_starts: Option<u64>,
+    /// Disables trading for this many seconds after the pair is created,
+    /// as an alternative to `trading_starts`. Only one of them can be set.
+    #[serde(default)]
+    pub trading_starts_delay: Option<u64>,
+}
+
 #[cw_serde]
 pub struct DistributionFlow {
     /// The asset that will be distributed
@@ -250,6 +437,17 @@ pub enum QueryMsg {
         /// The pair item to start reading from. It is an [`Option`] type that accepts [`AssetInfo`] elements.
         start_after: Option<Vec<AssetInfo>>,
         /// The number of pairs to read and return. It is an [`Option`] type.
+        /// Defaults to 10 and is capped at 30.
+        limit: Option<u32>,
+    },
+    /// PairsByAsset returns the pairs that trade the given asset, ordered by pair address.
+    #[returns(PairsResponse)]
+    PairsByAsset {
+        /// The asset to return the pairs for
+        asset: AssetInfo,
+        /// The address of the last pair of the previous page
+        start_after: Option<String>,
+        /// The number of pairs to read and return. Defaults to 10 and is capped at 30.
         limit: Option<u32>,
     },
     /// FeeInfo returns default fee parameters for a specific pair type.
@@ -270,6 +468,10 @@ pub enum QueryMsg {
     /// Used by the `gauge-adapter` contract
     #[returns(bool)]
     ValidateStakingAddress { address: String },
+    /// Returns the pending ownership transfer proposal, if there is one.
+    /// The proposed owner has to accept it using `ExecuteMsg::ClaimOwnership` before it expires.
+    #[returns(Option<OwnershipProposal>)]
+    OwnershipProposal {},
 }
 
 /// A custom struct for each query response that returns general contract settings/configs.
@@ -289,6 +491,24 @@ pub struct ConfigResponse {
     pub only_owner_can_create_pairs: bool,
     /// The block time until which trading is disabled
     pub trading_starts: Option<u64>,
+    /// Addresses the protocol fees are split between, with weights summing up to one.
+    /// If this is empty, all protocol fees are sent to `fee_address`
+    #[serde(default)]
+    pub fee_recipients: Vec<(Addr, Decimal)>,
+}
+
+impl ConfigResponse {
+    /// Returns the addresses the protocol fees are sent to, together with their share.
+    /// Falls back to the fee address if no fee recipients are configured.
+    pub fn protocol_fee_recipients(&self) -> Vec<(Addr, Decimal)> {
+        if !self.fee_recipients.is_empty() {
+            return self.fee_recipients.clone();
+        }
+        self.fee_address
+            .iter()
+            .map(|addr| (addr.clone(), Decimal::one()))
+            .collect()
+    }
 }
 
 /// A custom struct for each query response that returns an array of objects of type [`PairInfo`].
@@ -322,6 +542,11 @@ pub enum UpdateAddr {
 pub enum MigrateMsg {
     /// Used to instantiate from cw-placeholder
     Init(InstantiateMsg),
+    /// Used to upgrade from an older version of the factory
+    Update {
+        /// If set, replaces the default config of the staking contracts created for new pairs
+        default_stake_config: Option<DefaultStakeConfig>,
+    },
 }
 
 /// Map which contains a list of all pairs which are able to convert X <> Y assets.
@@ -1,5 +1,6 @@
 pub mod asset;
 pub mod common;
+pub mod cw1155;
 pub mod factory;
 pub mod fee_config;
 pub mod multi_hop;
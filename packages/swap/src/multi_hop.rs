@@ -1,17 +1,22 @@
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::{Binary, Decimal, Uint128};
 use cw20::Cw20ReceiveMsg;
 
 use crate::asset::{AssetInfo, AssetValidated};
 
 pub const MAX_SWAP_OPERATIONS: usize = 50;
+/// The default maximum number of swap operations in a single route
+pub const DEFAULT_MAX_HOPS: u32 = 5;
 
 /// This structure holds the parameters used for creating a contract.
 #[cw_serde]
 pub struct InstantiateMsg {
     /// The swap factory contract address
     pub sg_swap_factory: String,
+    /// The maximum number of swap operations in a single route.
+    /// Defaults to [`DEFAULT_MAX_HOPS`] and cannot exceed [`MAX_SWAP_OPERATIONS`].
+    pub max_hops: Option<u32>,
 }
 
 /// This enum describes a swap operation.
@@ -29,6 +34,14 @@ pub enum SwapOperation {
 }
 
 impl SwapOperation {
+    pub fn get_offer_asset_info(&self) -> AssetInfo {
+        match self {
+            SwapOperation::StargazeSwap {
+                offer_asset_info, ..
+            } => offer_asset_info.clone(),
+        }
+    }
+
     pub fn get_target_asset_info(&self) -> AssetInfo {
         match self {
             SwapOperation::StargazeSwap { ask_asset_info, .. } => ask_asset_info.clone(),
@@ -56,6 +69,11 @@ pub enum ExecuteMsg {
         /// The commission for the referral.
         /// This is capped by the configured max commission
         referral_commission: Option<Decimal>,
+        /// Unix timestamp in seconds after which the swap is rejected
+        deadline: Option<u64>,
+        /// If set, the final asset is sent to the receiver with a cw20 `Send` containing this message
+        /// instead of a plain transfer. Only allowed if the final asset is a cw20 token.
+        receive_msg: Option<Binary>,
     },
 
     /// Internal use
@@ -82,6 +100,16 @@ pub enum ExecuteMsg {
         minimum_receive: Uint128,
         receiver: String,
     },
+    /// Internal use
+    /// SendWithReceiveMsg sends the amount of the final cw20 token that the contract received since its balance
+    /// was `prev_balance` to the receiver with a hook message
+    SendWithReceiveMsg {
+        asset_info: AssetInfo,
+        prev_balance: Uint128,
+        receiver: String,
+        msg: Binary,
+        minimum_receive: Option<Uint128>,
+    },
 }
 
 #[cw_serde]
@@ -99,9 +127,14 @@ pub enum Cw20HookMsg {
         referral_address: Option<String>,
         /// The commission for the referral. Only used if `referral_address` is set.
         /// This is capped by and defaulting to the configured max commission.
-        /// The commission is only applied to the first of these swap operations,
-        /// so the referrer will get a portion of the asset the swap starts with.
+        /// The commission is applied to each of these swap operations,
+        /// so the referrer will get a portion of every asset that is swapped.
         referral_commission: Option<Decimal>,
+        /// Unix timestamp in seconds after which the swap is rejected
+        deadline: Option<u64>,
+        /// If set, the final asset is sent to the receiver with a cw20 `Send` containing this message
+        /// instead of a plain transfer. Only allowed if the final asset is a cw20 token.
+        receive_msg: Option<Binary>,
     },
 }
 
@@ -123,8 +156,8 @@ pub enum QueryMsg {
         referral: bool,
         /// The commission for the referral. Only used if `referral` is set to `true`.
         /// This is capped by and defaulting to the configured max commission.
-        /// The commission is only applied to the first of these swap operations,
-        /// so the referrer will get a portion of the asset the swap starts with.
+        /// The commission is applied to each of these swap operations,
+        /// so the referrer will get a portion of every asset that is swapped.
         referral_commission: Option<Decimal>,
     },
     #[returns(SimulateSwapOperationsResponse)]
@@ -138,10 +171,36 @@ pub enum QueryMsg {
         referral: bool,
         /// The commission for the referral. Only used if `referral` is set to `true`.
         /// This is capped by and defaulting to the configured max commission.
-        /// The commission is only applied to the first of these swap operations,
-        /// so the referrer will get a portion of the asset the swap starts with.
+        /// The commission is applied to each of these swap operations,
+        /// so the referrer will get a portion of every asset that is swapped.
         referral_commission: Option<Decimal>,
     },
+    /// FindBestRoute searches the pairs of the factory for the route that returns the most ask tokens
+    /// for the given offer amount.
+    /// Only pairs with two assets are considered.
+    #[returns(FindBestRouteResponse)]
+    FindBestRoute {
+        /// The asset to swap from
+        offer_asset: AssetInfo,
+        /// The asset to swap to
+        ask_asset: AssetInfo,
+        /// The amount of tokens to swap
+        offer_amount: Uint128,
+        /// The maximum number of swap operations in the route.
+        /// Defaults to and cannot exceed the configured maximum.
+        max_hops: Option<u32>,
+    },
+    /// SimulateDirectSwap simulates a swap between two native tokens through the factory's pair of them.
+    /// Fails if there is no such pair.
+    #[returns(SimulateSwapOperationsResponse)]
+    SimulateDirectSwap {
+        /// The denom of the native token to swap from
+        offer: String,
+        /// The denom of the native token to swap to
+        ask: String,
+        /// The amount of tokens to swap
+        offer_amount: Uint128,
+    },
 }
 
 /// This structure describes a custom struct to return a query response containing the base contract configuration.
@@ -149,6 +208,17 @@ pub enum QueryMsg {
 pub struct ConfigResponse {
     /// The Stargaze Swap factory contract address
     pub sg_swap_factory: String,
+    /// The maximum number of swap operations in a single route
+    pub max_hops: u32,
+}
+
+/// This structure describes a custom struct to return a query response containing the best route for a swap
+#[cw_serde]
+pub struct FindBestRouteResponse {
+    /// The swap operations of the best route, ready to be used with [`ExecuteMsg::ExecuteSwapOperations`]
+    pub operations: Vec<SwapOperation>,
+    /// The amount of ask tokens received when swapping along this route
+    pub amount: Uint128,
 }
 
 /// This structure describes a custom struct to return a query response containing the end amount of a swap simulation
@@ -171,6 +241,12 @@ pub struct SimulateSwapOperationsResponse {
     /// and each entry is denominated in the asset that is swapped to (`ask_asset_info`).
     pub commission_amounts: Vec<AssetValidated>,
 
-    /// The absolute amount of referral commission. This is always denominated in `offer_asset_info`.
+    /// The absolute amount of referral commission on the asset the swap starts with, i.e. of the first
+    /// swap operation. This is always denominated in `offer_asset_info`.
     pub referral_amount: AssetValidated,
+
+    /// The absolute amounts of referral commission for each swap operation.
+    /// This contains one entry per swap operation in the same order as `spread_amounts` and `commission_amounts`,
+    /// and each entry is denominated in the asset that is swapped from (`offer_asset_info`).
+    pub referral_amounts: Vec<AssetValidated>,
 }
@@ -11,18 +11,23 @@ use cosmwasm_std::{
     WasmMsg,
 };
 use cw20::Cw20ReceiveMsg;
+use std::str::FromStr;
 
 #[cfg(test)]
 pub mod mock_querier;
 
 mod error;
+mod events;
 mod instantiate;
+mod received;
 mod referral;
 mod utils;
 
 use crate::factory::PairType;
 pub use error::ContractError;
+pub use events::*;
 pub use instantiate::*;
+pub use received::*;
 pub use referral::*;
 pub use utils::*;
 
@@ -36,7 +41,7 @@ pub struct PairInfo {
     pub asset_infos: Vec<AssetInfoValidated>,
     /// Pair contract address
     pub contract_addr: Addr,
-    /// Pair LP token address
+    /// Pair LP token address, or the LP denom for pairs with [`LpTokenType::Native`] LP shares
     pub liquidity_token: Addr,
     /// Staking contract address
     pub staking_addr: Addr,
@@ -108,9 +113,40 @@ pub struct InstantiateMsg {
     pub staking_config: StakeConfig,
     /// The block time until which trading is disabled
     pub trading_starts: u64,
+    /// The max spread applied to swaps that do not specify one.
+    /// If not provided, [`DEFAULT_SLIPPAGE`] is used.
+    pub default_max_spread: Option<Decimal>,
+    /// The kind of token used for the LP shares
+    #[serde(default)]
+    pub lp_token_type: LpTokenType,
+    /// Overrides the auto-generated name of the cw20 LP token
+    #[serde(default)]
+    pub lp_token_name: Option<String>,
+    /// Overrides the default symbol of the cw20 LP token
+    #[serde(default)]
+    pub lp_token_symbol: Option<String>,
 }
 
 impl InstantiateMsg {
+    /// Returns an error if the LP token name or symbol overrides would be rejected by cw20-base
+    pub fn validate_lp_token_info(&self) -> Result<(), ContractError> {
+        if let Some(name) = &self.lp_token_name {
+            if !(3..=50).contains(&name.len()) {
+                return Err(ContractError::InvalidLpTokenName {});
+            }
+        }
+        if let Some(symbol) = &self.lp_token_symbol {
+            if !(3..=12).contains(&symbol.len())
+                || !symbol
+                    .bytes()
+                    .all(|byte| byte.is_ascii_alphabetic() || byte == b'-')
+            {
+                return Err(ContractError::InvalidLpTokenSymbol {});
+            }
+        }
+        Ok(())
+    }
+
     /// Returns an error if the fee config is invalid
     pub fn validate_fees(&self) -> Result<(), ContractError> {
         self.fee_config
@@ -118,6 +154,28 @@ impl InstantiateMsg {
             .then_some(())
             .ok_or(ContractError::InvalidFeeBps {})
     }
+
+    /// Returns the default max spread for swaps,
+    /// or an error if it exceeds [`MAX_ALLOWED_SLIPPAGE`]
+    pub fn default_max_spread(&self) -> Result<Decimal, ContractError> {
+        let default_max_spread = self.default_max_spread.unwrap_or_else(default_slippage);
+        if default_max_spread > Decimal::from_str(MAX_ALLOWED_SLIPPAGE)? {
+            return Err(ContractError::AllowedSpreadAssertion {});
+        }
+        Ok(default_max_spread)
+    }
+}
+
+/// The kind of token a pair uses for its LP shares.
+#[cw_serde]
+#[derive(Default)]
+pub enum LpTokenType {
+    /// A cw20 token contract instantiated by the pair
+    #[default]
+    Cw20,
+    /// A token factory denom created by the pair.
+    /// Only supported by pairs built with the `token-factory` feature.
+    Native,
 }
 
 #[cw_serde]
@@ -131,11 +189,13 @@ pub struct StakeConfig {
 }
 
 impl StakeConfig {
-    /// Call this after instantiating the lp token to get a message to instantiate the staking contract
+    /// Call this after instantiating the lp token to get a message to instantiate the staking contract.
+    /// `lp_token` is the address of a cw20 LP token or the denom of native LP shares.
     pub fn into_init_msg(
         self,
         querier: &QuerierWrapper,
-        lp_token_address: String,
+        lp_token: String,
+        lp_token_type: &LpTokenType,
         factory_addr: String,
     ) -> StdResult<WasmMsg> {
         // Add factory's owner as owner of staking contract (DAO) to allow migration
@@ -143,14 +203,20 @@ impl StakeConfig {
             .query_wasm_smart::<FactoryConfigResponse>(&factory_addr, &FactoryQueryMsg::Config {})?
             .owner
             .to_string();
+        let (cw20_contract, native_denom) = match lp_token_type {
+            LpTokenType::Cw20 => (lp_token, None),
+            LpTokenType::Native => (String::new(), Some(lp_token)),
+        };
         Ok(WasmMsg::Instantiate {
             code_id: self.staking_code_id,
             msg: to_binary(&crate::stake::InstantiateMsg {
-                cw20_contract: lp_token_address, // address of LP token
+                cw20_contract,
+                native_denom,
                 tokens_per_power: self.tokens_per_power,
                 min_bond: self.min_bond,
                 unbonding_periods: self.unbonding_periods,
                 max_distributions: self.max_distributions,
+                instant_unbond_penalty: None,
                 admin: Some(factory_addr),
             })?,
             funds: vec![],
@@ -173,8 +239,28 @@ pub enum ExecuteMsg {
         slippage_tolerance: Option<Decimal>,
         /// The receiver of LP tokens
         receiver: Option<String>,
+        /// The minimum amount of LP tokens to mint, otherwise providing liquidity fails.
+        /// This can be used together with or instead of `slippage_tolerance`.
+        #[serde(default)]
+        min_lp_amount: Option<Uint128>,
     },
-    /// Swap performs a swap in the pool
+    /// Continues a `ProvideLiquidity` with cw20 tokens once the pair pulled them,
+    /// using the amounts it actually received instead of the requested ones.
+    /// Only callable by the pair itself.
+    ProvideReceived {
+        /// The sender of the `ProvideLiquidity` message
+        sender: String,
+        assets: Vec<Asset>,
+        /// The balances of the pulled cw20 tokens before they were pulled
+        prev_balances: Vec<Asset>,
+        slippage_tolerance: Option<Decimal>,
+        receiver: Option<String>,
+        min_lp_amount: Option<Uint128>,
+    },
+    /// Swap performs a swap in the pool.
+    /// A cw20 offer asset is pulled from the sender, which needs to approve the pair to do so.
+    /// Unlike the [`Cw20HookMsg::Swap`], this swaps the amount the pair actually received,
+    /// which supports tokens that take a fee on transfer.
     Swap {
         offer_asset: Asset,
         ask_asset_info: Option<AssetInfo>,
@@ -187,10 +273,53 @@ pub enum ExecuteMsg {
         /// This is capped by the configured max commission
         referral_commission: Option<Decimal>,
     },
+    /// Continues a `Swap` of a cw20 token once the pair pulled it, swapping the amount it actually received.
+    /// Only callable by the pair itself.
+    SwapReceived {
+        /// The sender of the `Swap` message
+        sender: String,
+        offer_asset_info: AssetInfo,
+        /// The balance of the offer asset before it was pulled
+        prev_balance: Uint128,
+        ask_asset_info: Option<AssetInfo>,
+        belief_price: Option<Decimal>,
+        max_spread: Option<Decimal>,
+        to: Option<String>,
+        referral_address: Option<String>,
+        referral_commission: Option<Decimal>,
+    },
+    /// Withdraw liquidity by sending native LP shares along with this message.
+    /// Only supported by pairs with [`LpTokenType::Native`] LP shares.
+    WithdrawLiquidity {
+        assets: Vec<Asset>,
+        /// The minimum amounts of the pool assets to receive.
+        /// The withdrawal fails if less than any of them would be returned.
+        min_assets: Option<Vec<Asset>>,
+    },
     /// Update the pair configuration
     UpdateConfig { params: Binary },
-    /// Update the fees for this pair
+    /// Starts gradually changing the amplification of a stableswap pool.
+    /// The amplification is interpolated linearly until it reaches `next_amp` at `next_amp_time`.
+    /// Only callable by the pair owner or, if none is set, the factory owner.
+    StartAmpRamp {
+        /// The amplification to ramp to
+        next_amp: u64,
+        /// The time (in seconds) at which `next_amp` is reached
+        next_amp_time: u64,
+    },
+    /// Stops changing the amplification of a stableswap pool, keeping the current amplification.
+    /// Only callable by the pair owner or, if none is set, the factory owner.
+    StopAmpRamp {},
+    /// Update the fees for this pair.
+    /// Only callable by the factory or, for stable pairs, also by the pair owner or,
+    /// if none is set, the factory owner.
     UpdateFees { fee_config: FeeConfig },
+    /// Pauses or unpauses swaps and liquidity operations on this pair.
+    /// Only callable by the factory.
+    SetPaused { paused: bool },
+    /// Sends the protocol fees accrued by swaps to the fee address of the factory.
+    /// Anyone can call this.
+    CollectProtocolFees {},
     /// ProposeNewOwner creates a proposal to change contract ownership.
     /// The validity period for the proposal is set in the `expires_in` variable.
     ProposeNewOwner {
@@ -208,7 +337,9 @@ pub enum ExecuteMsg {
 /// This structure describes a CW20 hook message.
 #[cw_serde]
 pub enum Cw20HookMsg {
-    /// Swap a given amount of asset
+    /// Swap a given amount of asset.
+    /// At most the amount the pair's balance increased by since its previous execution is swapped,
+    /// so tokens that take a fee on transfer cannot report more than they transferred.
     Swap {
         ask_asset_info: Option<AssetInfo>,
         belief_price: Option<Decimal>,
@@ -221,7 +352,20 @@ pub enum Cw20HookMsg {
         referral_commission: Option<Decimal>,
     },
     /// Withdraw liquidity from the pool
-    WithdrawLiquidity { assets: Vec<Asset> },
+    WithdrawLiquidity {
+        assets: Vec<Asset>,
+        /// The minimum amounts of the pool assets to receive.
+        /// The withdrawal fails if less than any of them would be returned.
+        min_assets: Option<Vec<Asset>>,
+    },
+    /// Withdraw liquidity from the pool entirely into one of its assets.
+    /// Since this leaves the pool imbalanced, the imbalance fee is charged.
+    /// Only supported by stableswap pairs.
+    WithdrawToAsset {
+        asset: AssetInfo,
+        /// The minimum amount of `asset` to receive
+        min_out: Option<Uint128>,
+    },
 }
 
 /// This structure describes the query messages available in the contract.
@@ -240,6 +384,23 @@ pub enum QueryMsg {
     /// Returns information about the share of the pool in a vector that contains objects of type [`Asset`].
     #[returns(Vec<AssetValidated>)]
     Share { amount: Uint128 },
+    /// Returns the assets that burning `amount` LP tokens would withdraw at the current reserves.
+    /// Unlike [`QueryMsg::Share`], this fails if there is no liquidity or `amount` exceeds the LP token supply.
+    #[returns(Vec<AssetValidated>)]
+    SimulateWithdraw { amount: Uint128 },
+    /// Returns the assets that `shares` LP-equivalent shares are redeemable for at the current reserves,
+    /// e.g. to show what the `shares` of an sg721-pair NFT are worth.
+    /// This is the same proportional calculation as [`QueryMsg::SimulateWithdraw`].
+    #[returns(Vec<AssetValidated>)]
+    SharesToAssets { shares: Uint128 },
+    /// Returns the amount of LP tokens that providing the given assets would mint in a [`SimulateProvideResponse`] object.
+    #[returns(SimulateProvideResponse)]
+    SimulateProvide {
+        /// The assets to provide, same as in [`ExecuteMsg::ProvideLiquidity`]
+        assets: Vec<Asset>,
+        /// The slippage tolerance to check the deposit against. Not used by stable pairs.
+        slippage_tolerance: Option<Decimal>,
+    },
     /// Returns information about a swap simulation in a [`SimulationResponse`] object.
     #[returns(SimulationResponse)]
     Simulation {
@@ -251,6 +412,13 @@ pub enum QueryMsg {
         /// This is capped by and defaulting to the configured max commission
         referral_commission: Option<Decimal>,
     },
+    /// Returns the fees a swap of `offer_asset` would be charged, split into the liquidity provider
+    /// and protocol parts, in an [`EstimateSwapFeesResponse`] object. Referrals are not taken into account.
+    #[returns(EstimateSwapFeesResponse)]
+    EstimateSwapFees {
+        offer_asset: Asset,
+        ask_asset_info: Option<AssetInfo>,
+    },
     /// Returns information about cumulative prices in a [`ReverseSimulationResponse`] object.
     #[returns(ReverseSimulationResponse)]
     ReverseSimulation {
@@ -265,11 +433,46 @@ pub enum QueryMsg {
     /// Returns information about the cumulative prices in a [`CumulativePricesResponse`] object
     #[returns(CumulativePricesResponse)]
     CumulativePrices {},
+    /// Returns the time-weighted average prices since the given snapshot in a [`TwapResponse`] object.
+    /// The snapshot is usually taken from an earlier [`QueryMsg::CumulativePrices`] query.
+    #[returns(TwapResponse)]
+    Twap {
+        /// The cumulative prices at the start of the period
+        start_cumulative: Vec<(AssetInfo, AssetInfo, Uint128)>,
+        /// The timestamp (in seconds) of the start snapshot
+        start_time: u64,
+    },
+    /// Returns the current marginal price of one unit of `offer` in terms of `ask` as a [`Decimal`] value.
+    /// The swap fee is already deducted from the price.
+    #[returns(Decimal)]
+    SpotPrice { offer: AssetInfo, ask: AssetInfo },
     /// Returns current D invariant in as a [`u128`] value
     #[returns(Uint128)]
     QueryComputeD {},
+    /// Returns the protocol fees that were accrued by swaps and not collected yet.
+    /// These are not part of the pool's liquidity.
+    #[returns(Vec<AssetValidated>)]
+    AccruedProtocolFees {},
+    /// Returns the reserves, LP token supply, fees, type and trading start of the pool
+    /// in a single [`PoolStateResponse`] object.
+    #[returns(PoolStateResponse)]
+    PoolState {},
+    /// Returns the total amounts of each asset that were offered to and returned by the pair in swaps
+    /// in a [`CumulativeVolumeResponse`] object.
+    #[returns(CumulativeVolumeResponse)]
+    CumulativeVolume {},
+    /// Estimates the annualized yield of the swap fees for liquidity providers,
+    /// based on the swap volume of roughly the last `window_seconds` and the current reserves.
+    /// Returns zero if there were no swaps yet.
+    #[returns(Decimal)]
+    FeeApr { window_seconds: u64 },
 }
 
+/// This structure describes a migration message.
+/// The pair state is upgraded in place, so no parameters are needed.
+#[cw_serde]
+pub struct MigrateMsg {}
+
 /// This struct is used to return a query result with the total amount of LP tokens and assets in a specific pool.
 #[cw_serde]
 pub struct PoolResponse {
@@ -279,6 +482,30 @@ pub struct PoolResponse {
     pub total_share: Uint128,
 }
 
+/// This struct is used to return the combined state of a pool, see [`QueryMsg::PoolState`].
+#[cw_serde]
+pub struct PoolStateResponse {
+    /// The assets in the pool together with asset amounts
+    pub assets: Vec<AssetValidated>,
+    /// The total amount of LP tokens currently issued
+    pub total_share: Uint128,
+    /// The fee configuration of the pair
+    pub fee_config: FeeConfig,
+    /// The pool type (xyk, stableswap etc)
+    pub pair_type: PairType,
+    /// The block time until which trading is disabled
+    pub trading_starts: u64,
+}
+
+/// This struct is used to return the swap volume of a pair, see [`QueryMsg::CumulativeVolume`].
+#[cw_serde]
+pub struct CumulativeVolumeResponse {
+    /// The total amount of each asset that was offered to the pair in swaps
+    pub offered: Vec<AssetValidated>,
+    /// The total amount of each asset that was returned by the pair in swaps, after fees
+    pub returned: Vec<AssetValidated>,
+}
+
 /// This struct is used to return a query result with the general contract configuration.
 #[cw_serde]
 pub struct ConfigResponse {
@@ -288,6 +515,8 @@ pub struct ConfigResponse {
     pub params: Option<Binary>,
     /// The contract owner
     pub owner: Option<Addr>,
+    /// The max spread applied to swaps that do not specify one
+    pub default_max_spread: Decimal,
 }
 
 /// This structure holds the parameters that are returned from a swap simulation response
@@ -301,6 +530,34 @@ pub struct SimulationResponse {
     pub commission_amount: Uint128,
     /// The absolute amount of referral commission (denominated in `offer_asset_info`)
     pub referral_amount: Uint128,
+    /// Whether the spread is above the pair's default max spread,
+    /// i.e. whether executing this swap without a `max_spread` would fail
+    #[serde(default)]
+    pub spread_exceeds_default: bool,
+}
+
+/// This structure holds the fee breakdown of a swap, see [`QueryMsg::EstimateSwapFees`].
+/// All amounts are denominated in the ask asset.
+#[cw_serde]
+pub struct EstimateSwapFeesResponse {
+    /// The part of the swap fee that stays in the pool for the liquidity providers
+    pub lp_fee: Uint128,
+    /// The part of the swap fee that is accrued for the protocol
+    pub protocol_fee: Uint128,
+    /// The spread of the swap
+    pub spread: Uint128,
+    /// The amount returned by the swap after fees
+    pub return_amount: Uint128,
+}
+
+/// This structure holds the parameters that are returned from a provide liquidity simulation.
+#[cw_serde]
+pub struct SimulateProvideResponse {
+    /// The amount of LP tokens that would be minted for the provider
+    pub share: Uint128,
+    /// The fee charged in each asset for providing liquidity in an imbalanced way.
+    /// Only stable pairs charge this fee, so this is empty for other pairs.
+    pub imbalance_fee: Vec<AssetValidated>,
 }
 
 /// This structure holds the parameters that are returned from a reverse swap simulation response.
@@ -325,8 +582,24 @@ pub struct CumulativePricesResponse {
     pub total_share: Uint128,
     /// The vector contains cumulative prices for each pair of assets in the pool
     pub cumulative_prices: Vec<(AssetInfoValidated, AssetInfoValidated, Uint128)>,
+    /// The timestamp (in seconds) the cumulative prices were accumulated up to
+    pub block_time_last: u64,
+}
+
+/// This structure is used to return a TWAP query response.
+#[cw_serde]
+pub struct TwapResponse {
+    /// The start of the period the average was calculated for
+    pub start_time: u64,
+    /// The end of the period the average was calculated for
+    pub end_time: u64,
+    /// The average price of the first asset, denominated in the second asset, for each pair of assets in the pool
+    pub twap: Vec<(AssetInfoValidated, AssetInfoValidated, Decimal)>,
 }
 
+/// The maximum amplification of a stableswap pool
+pub const MAX_AMP: u64 = 1_000_000;
+
 /// This structure holds stableswap pool parameters.
 #[cw_serde]
 pub struct StablePoolParams {
@@ -343,6 +616,14 @@ pub struct StablePoolConfig {
     pub amp: Decimal,
 }
 
+/// This structure holds weighted pool parameters.
+/// The factory sets these from the weights of the [`PairType::Weighted`] pair type.
+#[cw_serde]
+pub struct WeightedPoolParams {
+    /// The weights of the pool's assets, in the same order as the pair's asset infos
+    pub weights: [u16; 2],
+}
+
 /// This enum stores the options available to start and stop changing a stableswap pool's amplification.
 #[cw_serde]
 pub enum StablePoolUpdateParams {
@@ -1,5 +1,8 @@
 use crate::asset::MINIMUM_LIQUIDITY_AMOUNT;
-use cosmwasm_std::{CheckedMultiplyRatioError, ConversionOverflowError, OverflowError, StdError};
+use cosmwasm_std::{
+    CheckedMultiplyRatioError, ConversionOverflowError, OverflowError, StdError, Uint128,
+};
+use cw_utils::PaymentError;
 use thiserror::Error;
 
 /// This enum describes pair contract errors
@@ -8,6 +11,9 @@ pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Payment(#[from] PaymentError),
+
     #[error("Unknown reply id '{0}'")]
     UnknownReply(u64),
 
@@ -17,6 +23,12 @@ pub enum ContractError {
     #[error("Trading has not started yet")]
     TradingNotStarted {},
 
+    #[error("The pair is paused")]
+    PairPaused {},
+
+    #[error("The factory has no fee address to send the protocol fees to")]
+    NoFeeAddress {},
+
     #[error("The {0} address was set already and cannot be changed")]
     AddrAlreadySet(&'static str),
 
@@ -26,6 +38,9 @@ pub enum ContractError {
     #[error("Event of zero transfer")]
     InvalidZeroAmount {},
 
+    #[error("The offered amount is too small to return anything")]
+    SwapTooSmall {},
+
     #[error("Operation exceeds max spread limit")]
     MaxSpreadAssertion {},
 
@@ -35,6 +50,14 @@ pub enum ContractError {
     #[error("Operation exceeds max splippage tolerance")]
     MaxSlippageAssertion {},
 
+    #[error("Withdrawing liquidity returns {returned}, which is less than the minimum of {min}")]
+    WithdrawSlippage { returned: String, min: String },
+
+    #[error(
+        "Providing liquidity mints {minted} LP tokens, which is less than the minimum of {min}"
+    )]
+    ProvideSlippage { minted: Uint128, min: Uint128 },
+
     #[error("Doubling assets in asset infos")]
     DoublingAssets {},
 
@@ -75,12 +98,29 @@ pub enum ContractError {
     #[error("It is not possible to provide liquidity with one token for an empty pool")]
     InvalidProvideLPsWithSingleToken {},
 
+    #[error("One of the pools is empty")]
+    PoolEmpty {},
+
     #[error("The asset {0} does not belong to the pair")]
     InvalidAsset(String),
 
     #[error("Fee bps in must be smaller than or equal to 10,000")]
     InvalidFeeBps {},
 
+    #[error("LP token name must be between 3 and 50 characters long")]
+    InvalidLpTokenName {},
+
+    #[error(
+        "LP token symbol must be between 3 and 12 characters long and only contain letters and '-'"
+    )]
+    InvalidLpTokenSymbol {},
+
+    #[error("The decimals of {0} cannot be determined or are more than 18")]
+    UnsupportedDecimals(String),
+
+    #[error("Weights must be non-zero and sum up to 10,000")]
+    InvalidWeights {},
+
     #[error("Ask or offer asset is missed")]
     VariableAssetMissed {},
 
@@ -0,0 +1,51 @@
+use cosmwasm_std::{attr, Attribute, Uint128};
+
+use crate::asset::AssetInfoValidated;
+
+/// The asset offered in a swap
+pub const OFFER_ASSET_KEY: &str = "offer_asset";
+/// The asset asked for in a swap
+pub const ASK_ASSET_KEY: &str = "ask_asset";
+/// The amount of the offer asset that is swapped, after the referral commission was deducted
+pub const OFFER_AMOUNT_KEY: &str = "offer_amount";
+/// The amount of the ask asset the swap returns
+pub const RETURN_AMOUNT_KEY: &str = "return_amount";
+/// The spread of the swap, denominated in the ask asset
+pub const SPREAD_AMOUNT_KEY: &str = "spread_amount";
+/// The swap fee, denominated in the ask asset
+pub const COMMISSION_AMOUNT_KEY: &str = "commission_amount";
+/// The referral commission, denominated in the offer asset
+pub const REFERRAL_AMOUNT_KEY: &str = "referral_amount";
+
+/// The keys of [`swap_attributes`], in the order they are emitted
+pub const SWAP_ATTRIBUTE_KEYS: [&str; 7] = [
+    OFFER_ASSET_KEY,
+    ASK_ASSET_KEY,
+    OFFER_AMOUNT_KEY,
+    RETURN_AMOUNT_KEY,
+    SPREAD_AMOUNT_KEY,
+    COMMISSION_AMOUNT_KEY,
+    REFERRAL_AMOUNT_KEY,
+];
+
+/// Returns the attributes every swap is reported with, both by the pair and by every hop of a multi-hop swap.
+/// Using the same keys everywhere allows indexers to rely on them.
+pub fn swap_attributes(
+    offer_asset: &AssetInfoValidated,
+    ask_asset: &AssetInfoValidated,
+    offer_amount: Uint128,
+    return_amount: Uint128,
+    spread_amount: Uint128,
+    commission_amount: Uint128,
+    referral_amount: Uint128,
+) -> Vec<Attribute> {
+    vec![
+        attr(OFFER_ASSET_KEY, offer_asset.to_string()),
+        attr(ASK_ASSET_KEY, ask_asset.to_string()),
+        attr(OFFER_AMOUNT_KEY, offer_amount),
+        attr(RETURN_AMOUNT_KEY, return_amount),
+        attr(SPREAD_AMOUNT_KEY, spread_amount),
+        attr(COMMISSION_AMOUNT_KEY, commission_amount),
+        attr(REFERRAL_AMOUNT_KEY, referral_amount),
+    ]
+}
@@ -10,7 +10,7 @@ use cw_utils::MsgInstantiateContractResponse;
 use crate::asset::{format_lp_token_name, AssetInfoValidated};
 use crate::factory::{ConfigResponse as FactoryConfigResponse, QueryMsg as FactoryQueryMsg};
 
-use super::{ContractError, PairInfo, StakeConfig};
+use super::{ContractError, LpTokenType, PairInfo, StakeConfig};
 
 /// Stores some config options for the staking contract in-between
 /// lp token instantiation and staking contract instantiation.
@@ -24,14 +24,21 @@ const INSTANTIATE_STAKE_REPLY_ID: u64 = 2;
 
 /// Returns a sub-message to instantiate a new LP token.
 /// It uses [`INSTANTIATE_TOKEN_REPLY_ID`] as id.
+///
+/// `name` and `symbol` override the auto-generated name and the default symbol if given.
 pub fn create_lp_token(
     querier: &QuerierWrapper,
     env: &Env,
     token_code_id: u64,
     asset_infos: &[AssetInfoValidated],
     factory_addr: &Addr,
+    name: Option<String>,
+    symbol: Option<String>,
 ) -> StdResult<SubMsg> {
-    let token_name = format_lp_token_name(asset_infos, querier)?;
+    let token_name = match name {
+        Some(name) => name,
+        None => format_lp_token_name(asset_infos, querier)?,
+    };
 
     let factory_config: FactoryConfigResponse =
         querier.query_wasm_smart(factory_addr, &FactoryQueryMsg::Config {})?;
@@ -42,7 +49,7 @@ pub fn create_lp_token(
             code_id: token_code_id,
             msg: to_binary(&TokenInstantiateMsg {
                 name: token_name,
-                symbol: "uLP".to_string(),
+                symbol: symbol.unwrap_or_else(|| "uLP".to_string()),
                 decimals: LP_TOKEN_PRECISION,
                 initial_balances: vec![],
                 mint: Some(MinterResponse {
@@ -58,6 +65,26 @@ pub fn create_lp_token(
     ))
 }
 
+/// Returns a sub-message to instantiate the staking contract of native LP shares with the given `denom`.
+/// Unlike for cw20 LP tokens, the denom is known right away, so this does not wait for a reply.
+/// It uses [`INSTANTIATE_STAKE_REPLY_ID`] as id.
+pub fn create_native_lp_staking(
+    querier: &QuerierWrapper,
+    stake_config: StakeConfig,
+    denom: String,
+    factory_addr: &Addr,
+) -> StdResult<SubMsg> {
+    Ok(SubMsg::reply_on_success(
+        stake_config.into_init_msg(
+            querier,
+            denom,
+            &LpTokenType::Native,
+            factory_addr.to_string(),
+        )?,
+        INSTANTIATE_STAKE_REPLY_ID,
+    ))
+}
+
 /// Saves this `stake_config` to the storage temporarily
 /// until the reply for creating the lp token arrives.
 pub fn save_tmp_staking_config(
@@ -106,7 +133,12 @@ pub fn instantiate_lp_token_reply(
 
     Ok(Response::new()
         .add_submessage(SubMsg::reply_on_success(
-            staking_cfg.into_init_msg(&deps.querier, res.contract_address, factory.to_string())?,
+            staking_cfg.into_init_msg(
+                &deps.querier,
+                res.contract_address,
+                &LpTokenType::Cw20,
+                factory.to_string(),
+            )?,
             INSTANTIATE_STAKE_REPLY_ID,
         ))
         .add_attribute("liquidity_token_addr", &pair_info.liquidity_token))
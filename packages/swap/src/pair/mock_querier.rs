@@ -100,6 +100,7 @@ impl WasmMockQuerier {
                                 max_referral_commission: Decimal::one(),
                                 only_owner_can_create_pairs: true,
                                 trading_starts: None,
+                                fee_recipients: vec![],
                             })
                             .into(),
                         ),
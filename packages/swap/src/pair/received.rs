@@ -0,0 +1,207 @@
+//! Helpers to measure the amounts of cw20 tokens the pair actually received.
+//! Some tokens take a fee on transfer, so the pair can receive less than it pulled with `TransferFrom`.
+//! The pair therefore records its balances before pulling the tokens and continues with a message to itself
+//! (see [`ExecuteMsg::ProvideReceived`](super::ExecuteMsg::ProvideReceived) and
+//! [`ExecuteMsg::SwapReceived`](super::ExecuteMsg::SwapReceived)) that compares them to the new balances.
+//! Tokens sent with a receive hook already arrived, so the pair compares its balance to the reserves it
+//! saved after its previous execution instead (see [`save_cw20_reserves`]).
+
+use cosmwasm_std::{
+    from_binary, wasm_execute, Addr, CosmosMsg, Decimal, Env, MessageInfo, QuerierWrapper,
+    Response, StdResult, Storage, Uint128, WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::Map;
+
+use crate::asset::{AssetInfo, AssetInfoValidated, AssetValidated};
+
+use super::{ContractError, ExecuteMsg};
+
+/// The balances of the pair in its cw20 tokens after its previous execution.
+const CW20_RESERVES: Map<&Addr, Uint128> = Map::new("cw20_reserves");
+
+/// Returns the balances of `contract_addr` in the cw20 tokens of `assets` that have a non-zero amount.
+pub fn cw20_balances_before(
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    assets: &[AssetValidated],
+) -> StdResult<Vec<AssetValidated>> {
+    assets
+        .iter()
+        .filter(|asset| {
+            matches!(asset.info, AssetInfoValidated::Token(_)) && !asset.amount.is_zero()
+        })
+        .map(|asset| {
+            Ok(AssetValidated {
+                info: asset.info.clone(),
+                amount: asset.info.query_balance(querier, contract_addr)?,
+            })
+        })
+        .collect()
+}
+
+/// Replaces the amounts of the assets in `prev_balances` by how much the balance of `contract_addr`
+/// increased since then.
+pub fn replace_received_amounts(
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    assets: &mut [AssetValidated],
+    prev_balances: &[AssetValidated],
+) -> StdResult<()> {
+    for prev_balance in prev_balances {
+        let received = received_amount(
+            querier,
+            contract_addr,
+            &prev_balance.info,
+            prev_balance.amount,
+        )?;
+        for asset in assets
+            .iter_mut()
+            .filter(|asset| asset.info == prev_balance.info)
+        {
+            asset.amount = received;
+        }
+    }
+    Ok(())
+}
+
+/// Pulls the cw20 `offer_asset` of a swap from the sender and continues the swap with
+/// [`ExecuteMsg::SwapReceived`], which swaps the amount the pair received.
+#[allow(clippy::too_many_arguments)]
+pub fn pull_swap_offer(
+    querier: &QuerierWrapper,
+    env: &Env,
+    info: MessageInfo,
+    offer_asset: AssetValidated,
+    ask_asset_info: Option<AssetInfo>,
+    belief_price: Option<Decimal>,
+    max_spread: Option<Decimal>,
+    to: Option<String>,
+    referral_address: Option<String>,
+    referral_commission: Option<Decimal>,
+) -> Result<Response, ContractError> {
+    if offer_asset.amount.is_zero() {
+        return Err(ContractError::InvalidZeroAmount {});
+    }
+    let prev_balance = offer_asset
+        .info
+        .query_balance(querier, &env.contract.address)?;
+
+    let mut messages = vec![];
+    offer_asset.receive(env, &info, &mut messages)?;
+    messages.push(
+        wasm_execute(
+            &env.contract.address,
+            &ExecuteMsg::SwapReceived {
+                sender: info.sender.to_string(),
+                offer_asset_info: offer_asset.info.into(),
+                prev_balance,
+                ask_asset_info,
+                belief_price,
+                max_spread,
+                to,
+                referral_address,
+                referral_commission,
+            },
+            vec![],
+        )?
+        .into(),
+    );
+
+    Ok(Response::new().add_messages(messages))
+}
+
+/// Returns the amount of `asset_info` that `contract_addr` received since its balance was `prev_balance`.
+pub fn received_amount(
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    asset_info: &AssetInfoValidated,
+    prev_balance: Uint128,
+) -> StdResult<Uint128> {
+    Ok(asset_info
+        .query_balance(querier, contract_addr)?
+        .checked_sub(prev_balance)?)
+}
+
+/// Returns the addresses of the cw20 tokens in `asset_infos`.
+pub fn cw20_tokens(asset_infos: &[AssetInfoValidated]) -> Vec<Addr> {
+    asset_infos
+        .iter()
+        .filter_map(|asset_info| match asset_info {
+            AssetInfoValidated::Token(token) => Some(token.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Saves the balances `contract_addr` will have in the cw20 tokens of `asset_infos` once the messages
+/// of `response` are executed.
+/// Only the balances of tokens in `received` and of tokens the response sends are updated,
+/// since the others did not change.
+pub fn save_cw20_reserves(
+    storage: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    asset_infos: &[AssetInfoValidated],
+    received: &[Addr],
+    response: &Response,
+) -> StdResult<()> {
+    for asset_info in asset_infos {
+        let token = match asset_info {
+            AssetInfoValidated::Token(token) => token,
+            _ => continue,
+        };
+
+        let mut touched = received.contains(token);
+        let mut sent = Uint128::zero();
+        for sub_msg in &response.messages {
+            if let CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: msg_contract,
+                msg,
+                ..
+            }) = &sub_msg.msg
+            {
+                if msg_contract != token.as_str() {
+                    continue;
+                }
+                // tokens pulled with `TransferFrom` are saved by the continuation that receives them
+                match from_binary(msg) {
+                    Ok(Cw20ExecuteMsg::Transfer { amount, .. })
+                    | Ok(Cw20ExecuteMsg::Send { amount, .. }) => {
+                        touched = true;
+                        sent += amount;
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        if touched {
+            let balance = asset_info.query_balance(querier, contract_addr)?;
+            CW20_RESERVES.save(storage, token, &balance.checked_sub(sent)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the amount of the cw20 `token` that `contract_addr` received with a receive hook that reported `amount`.
+/// A token that takes a fee on transfer can report more than it transferred, so this is capped by how much
+/// the balance increased since the reserves were saved.
+/// Tokens that were sent to the pair without a hook are therefore not credited to the sender.
+pub fn received_hook_amount(
+    storage: &dyn Storage,
+    querier: &QuerierWrapper,
+    contract_addr: &Addr,
+    token: &Addr,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    match CW20_RESERVES.may_load(storage, token)? {
+        Some(reserve) => {
+            let balance =
+                AssetInfoValidated::Token(token.clone()).query_balance(querier, contract_addr)?;
+            Ok(amount.min(balance.saturating_sub(reserve)))
+        }
+        // no reserves were saved before the first deposit
+        None => Ok(amount),
+    }
+}
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use super::error::ContractError;
+use super::{CumulativePricesResponse, TwapResponse, TWAP_PRECISION};
 
 use crate::asset::{Asset, AssetInfo, AssetInfoValidated, AssetValidated};
 
@@ -16,6 +17,62 @@ pub const DEFAULT_SLIPPAGE: &str = "0.005";
 /// The maximum allowed swap slippage
 pub const MAX_ALLOWED_SLIPPAGE: &str = "0.5";
 
+/// Returns [`DEFAULT_SLIPPAGE`] as a [`Decimal`].
+pub fn default_slippage() -> Decimal {
+    Decimal::from_str(DEFAULT_SLIPPAGE).unwrap()
+}
+
+/// Calculates the time-weighted average prices between the given start snapshot and `current`.
+///
+/// * **current** are the cumulative prices accumulated up to the current block.
+///
+/// * **start_cumulative** are the cumulative prices at **start_time**, usually taken from an earlier
+/// cumulative prices query.
+pub fn calculate_twap(
+    api: &dyn Api,
+    current: CumulativePricesResponse,
+    start_cumulative: Vec<(AssetInfo, AssetInfo, Uint128)>,
+    start_time: u64,
+) -> StdResult<TwapResponse> {
+    let end_time = current.block_time_last;
+    if start_time >= end_time {
+        return Err(StdError::generic_err(
+            "TWAP start time must be before the last price update",
+        ));
+    }
+
+    let start_cumulative = start_cumulative
+        .into_iter()
+        .map(|(from, to, value)| Ok((from.validate(api)?, to.validate(api)?, value)))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    let price_precision = Uint128::from(10u128.pow(TWAP_PRECISION.into()));
+    let elapsed = Uint128::from(end_time - start_time).checked_mul(price_precision)?;
+
+    let twap = current
+        .cumulative_prices
+        .into_iter()
+        .map(|(from, to, end_value)| {
+            let (_, _, start_value) = start_cumulative
+                .iter()
+                .find(|(start_from, start_to, _)| start_from == &from && start_to == &to)
+                .ok_or_else(|| {
+                    StdError::generic_err(format!("Missing start cumulative price for {from}-{to}"))
+                })?;
+            // the accumulators wrap around on overflow, so the difference has to wrap as well
+            let price = Decimal::checked_from_ratio(end_value.wrapping_sub(*start_value), elapsed)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            Ok((from, to, price))
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+
+    Ok(TwapResponse {
+        start_time,
+        end_time,
+        twap,
+    })
+}
+
 /// This function makes raw query to the factory contract and
 /// checks whether the pair needs to update an owner or not.
 pub fn migration_check(
@@ -39,6 +96,13 @@ pub fn check_asset_infos(
     if !asset_infos.iter().all_unique() {
         return Err(ContractError::DoublingAssets {});
     }
+    // pairs only trade native and cw20 tokens
+    if asset_infos
+        .iter()
+        .any(|asset_info| matches!(asset_info, AssetInfo::Cw1155 { .. }))
+    {
+        return Err(ContractError::NonSupported {});
+    }
 
     asset_infos
         .iter()
@@ -128,6 +192,13 @@ pub fn assert_max_spread(
     Ok(())
 }
 
+/// Returns whether the spread of a swap is above `max_spread`.
+/// This is the same check [`assert_max_spread`] does if no belief price is given.
+pub fn spread_exceeds(max_spread: Decimal, return_amount: Uint128, spread_amount: Uint128) -> bool {
+    let total = return_amount + spread_amount;
+    !total.is_zero() && Decimal::from_ratio(spread_amount, total) > max_spread
+}
+
 /// Mint LP tokens for a beneficiary
 ///
 /// * **recipient** LP token recipient.
@@ -175,3 +246,135 @@ pub fn get_share_in_assets(
         })
         .collect()
 }
+
+/// Return the amount of tokens that withdrawing a specific amount of LP tokens would return.
+/// Same as [`get_share_in_assets`], but fails if no LP tokens are issued or if `amount` exceeds the total supply.
+///
+/// * **pools** array with assets available in the pool.
+///
+/// * **amount** amount of LP tokens to withdraw.
+///
+/// * **total_share** total amount of LP tokens currently issued by the pool.
+pub fn simulate_withdraw(
+    pools: &[AssetValidated],
+    amount: Uint128,
+    total_share: Uint128,
+) -> StdResult<Vec<AssetValidated>> {
+    if total_share.is_zero() {
+        return Err(StdError::generic_err(
+            "The pool does not have any liquidity",
+        ));
+    }
+    if amount > total_share {
+        return Err(StdError::generic_err(format!(
+            "Cannot withdraw more than the total supply of {total_share} LP tokens"
+        )));
+    }
+
+    Ok(get_share_in_assets(pools, amount, total_share))
+}
+
+/// Checks that the `refund_assets` of a withdrawal are at least the given `min_assets`.
+/// Every minimum needs to refer to one of the refunded assets.
+pub fn assert_min_assets(
+    api: &dyn Api,
+    refund_assets: &[AssetValidated],
+    min_assets: Option<Vec<Asset>>,
+) -> Result<(), ContractError> {
+    let min_assets = match min_assets {
+        Some(min_assets) => check_assets(api, &min_assets)?,
+        None => return Ok(()),
+    };
+
+    for min in min_assets {
+        let refund = refund_assets
+            .iter()
+            .find(|refund| refund.info.equal(&min.info))
+            .ok_or_else(|| ContractError::InvalidAsset(min.info.to_string()))?;
+        if refund.amount < min.amount {
+            return Err(ContractError::WithdrawSlippage {
+                returned: refund.to_string(),
+                min: min.to_string(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the `share` minted when providing liquidity is at least `min_lp_amount`.
+pub fn assert_min_lp_amount(
+    share: Uint128,
+    min_lp_amount: Option<Uint128>,
+) -> Result<(), ContractError> {
+    match min_lp_amount {
+        Some(min) if share < min => Err(ContractError::ProvideSlippage { minted: share, min }),
+        _ => Ok(()),
+    }
+}
+
+/// Subtracts the accrued protocol `fees` from the contract balances in `pools`,
+/// so that only the liquidity of the pool remains.
+pub fn subtract_accrued_fees(
+    pools: &mut [AssetValidated],
+    fees: &[AssetValidated],
+) -> StdResult<()> {
+    for fee in fees {
+        if let Some(pool) = pools.iter_mut().find(|pool| pool.info.equal(&fee.info)) {
+            pool.amount = pool.amount.checked_sub(fee.amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Adds `asset` to the swap `volume` of its asset.
+/// The volume saturates instead of overflowing, since it grows over the whole lifetime of the pair.
+pub fn accumulate_volume(volume: &mut Vec<AssetValidated>, asset: &AssetValidated) {
+    match volume
+        .iter_mut()
+        .find(|accumulated| accumulated.info.equal(&asset.info))
+    {
+        Some(accumulated) => accumulated.amount = accumulated.amount.saturating_add(asset.amount),
+        None => volume.push(asset.clone()),
+    }
+}
+
+/// Returns the messages that split the accrued protocol `fees` between the `recipients` by their weights.
+/// The rounding remainder goes to the last recipient, so the full amount is always sent.
+pub fn protocol_fee_msgs(
+    fees: &[AssetValidated],
+    recipients: &[(Addr, Decimal)],
+) -> StdResult<Vec<CosmosMsg>> {
+    let mut messages = vec![];
+    for fee in fees.iter().filter(|fee| !fee.amount.is_zero()) {
+        let mut remaining = fee.amount;
+        for (i, (recipient, weight)) in recipients.iter().enumerate() {
+            let amount = if i + 1 == recipients.len() {
+                remaining
+            } else {
+                fee.amount * *weight
+            };
+            if amount.is_zero() {
+                continue;
+            }
+            remaining -= amount;
+            let share = AssetValidated {
+                info: fee.info.clone(),
+                amount,
+            };
+            messages.push(share.into_msg(recipient)?);
+        }
+    }
+    Ok(messages)
+}
+
+/// Adds `fee` to the accrued protocol `fees`.
+pub fn accrue_fee(fees: &mut Vec<AssetValidated>, fee: AssetValidated) -> StdResult<()> {
+    match fees
+        .iter_mut()
+        .find(|accrued| accrued.info.equal(&fee.info))
+    {
+        Some(accrued) => accrued.amount = accrued.amount.checked_add(fee.amount)?,
+        None => fees.push(fee),
+    }
+    Ok(())
+}
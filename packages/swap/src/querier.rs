@@ -1,4 +1,5 @@
 use crate::asset::{Asset, AssetInfo, AssetInfoValidated};
+use crate::cw1155::{BalanceResponse as Cw1155BalanceResponse, Cw1155QueryMsg};
 use crate::factory::{
     ConfigResponse as FactoryConfigResponse, FeeInfoResponse, PairType, PairsResponse,
     QueryMsg as FactoryQueryMsg,
@@ -66,6 +67,30 @@ pub fn query_token_balance(
     Ok(resp.balance)
 }
 
+/// Returns a cw1155 token's balance for a specific account.
+///
+/// * **contract_addr** cw1155 contract address.
+///
+/// * **token_id** id of the token inside the cw1155 contract.
+///
+/// * **account_addr** account address for which to return the balance.
+pub fn query_cw1155_balance(
+    querier: &QuerierWrapper,
+    contract_addr: impl Into<String>,
+    token_id: impl Into<String>,
+    account_addr: impl Into<String>,
+) -> StdResult<Uint128> {
+    let resp: Cw1155BalanceResponse = querier.query_wasm_smart(
+        contract_addr,
+        &Cw1155QueryMsg::Balance {
+            owner: account_addr.into(),
+            token_id: token_id.into(),
+        },
+    )?;
+
+    Ok(resp.balance)
+}
+
 /// Returns a token's symbol.
 ///
 /// * **contract_addr** token contract address.
@@ -107,6 +132,8 @@ pub fn query_token_precision(
 
             res.decimals
         }
+        // cw1155 tokens are not divisible
+        AssetInfoValidated::Cw1155 { .. } => 0,
     };
 
     Ok(decimals)
@@ -1,5 +1,5 @@
 use cosmwasm_schema::cw_serde;
-use cosmwasm_std::Uint128;
+use cosmwasm_std::{Decimal, Uint128};
 
 /// Unbonding period in seconds
 pub type UnbondingPeriod = u64;
@@ -8,11 +8,19 @@ pub type UnbondingPeriod = u64;
 pub struct InstantiateMsg {
     /// address of cw20 contract token
     pub cw20_contract: String,
+    /// If set, native tokens of this denom are staked instead of the cw20 token
+    /// and `cw20_contract` is ignored.
+    #[serde(default)]
+    pub native_denom: Option<String>,
     pub tokens_per_power: Uint128,
     pub min_bond: Uint128,
     pub unbonding_periods: Vec<UnbondingPeriod>,
     /// the maximum number of distributions that can be created
     pub max_distributions: u32,
+    /// The penalty for unbonding instantly, without waiting for the unbonding period.
+    /// If this is `None`, instant unbonding is disabled.
+    #[serde(default)]
+    pub instant_unbond_penalty: Option<Decimal>,
 
     // admin can only add/remove hooks and add distributions, not change other parameters
     pub admin: Option<String>,
@@ -1,4 +1,7 @@
-use crate::asset::{format_lp_token_name, AssetInfo, AssetInfoValidated, AssetValidated};
+use crate::asset::{
+    format_lp_token_name, AssetInfo, AssetInfoExt, AssetInfoValidated, AssetValidated,
+};
+use crate::cw1155::Cw1155ExecuteMsg;
 use crate::fee_config::FeeConfig;
 use crate::mock_querier::mock_dependencies;
 use crate::pair::PairInfo;
@@ -8,9 +11,10 @@ use crate::querier::{
 
 use crate::factory::PairType;
 use crate::DecimalCheckedOps;
-use cosmwasm_std::testing::MOCK_CONTRACT_ADDR;
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{to_binary, Addr, BankMsg, Coin, CosmosMsg, Decimal, Uint128, WasmMsg};
 use cw20::Cw20ExecuteMsg;
+use cw_storage_plus::{KeyDeserialize, PrimaryKey};
 
 #[test]
 fn token_balance_querier() {
@@ -199,6 +203,92 @@ fn test_asset() {
     );
 }
 
+#[test]
+fn test_cw1155_asset_info() {
+    let deps = mock_dependencies(&[]);
+
+    let err = AssetInfo::Cw1155 {
+        contract: "multitoken".to_string(),
+        token_id: String::new(),
+    }
+    .validate(&deps.api)
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Cw1155 token id must not be empty"
+    );
+
+    let info = AssetInfo::Cw1155 {
+        contract: "multitoken".to_string(),
+        token_id: "gold".to_string(),
+    }
+    .validate(&deps.api)
+    .unwrap();
+    assert_eq!(
+        info,
+        AssetInfoValidated::Cw1155 {
+            contract: Addr::unchecked("multitoken"),
+            token_id: "gold".to_string(),
+        }
+    );
+    assert_eq!(info.to_string(), "multitoken/gold");
+    assert!(!info.is_native_token());
+    assert!(!info.equal(&AssetInfoValidated::Cw1155 {
+        contract: Addr::unchecked("multitoken"),
+        token_id: "silver".to_string(),
+    }));
+    assert!(!info.equal(&AssetInfoValidated::Token(Addr::unchecked("multitoken"))));
+
+    // the storage key contains the token id and can be read back
+    let key = (&info).joined_key();
+    assert_eq!(<&AssetInfoValidated>::from_vec(key).unwrap(), info);
+}
+
+#[test]
+fn test_cw1155_asset() {
+    let asset = AssetInfoValidated::Cw1155 {
+        contract: Addr::unchecked("multitoken"),
+        token_id: "gold".to_string(),
+    }
+    .with_balance(5u128);
+
+    let expected = CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: String::from("multitoken"),
+        msg: to_binary(&Cw1155ExecuteMsg::SendFrom {
+            from: String::from("addr0000"),
+            to: String::from("addr0001"),
+            token_id: String::from("gold"),
+            value: Uint128::new(5),
+            msg: None,
+        })
+        .unwrap(),
+        funds: vec![],
+    });
+    assert_eq!(
+        asset.into_send_from_msg("addr0000", "addr0001").unwrap(),
+        expected
+    );
+    // the owner of the tokens is not known here
+    asset.into_msg("addr0001").unwrap_err();
+    // only cw1155 tokens are sent this way
+    AssetInfoValidated::Native("uusd".to_string())
+        .with_balance(5u128)
+        .into_send_from_msg("addr0000", "addr0001")
+        .unwrap_err();
+
+    // receiving pulls the tokens from the sender to the contract
+    let mut messages = vec![];
+    asset
+        .receive(&mock_env(), &mock_info("addr0000", &[]), &mut messages)
+        .unwrap();
+    assert_eq!(
+        messages,
+        vec![asset
+            .into_send_from_msg("addr0000", MOCK_CONTRACT_ADDR)
+            .unwrap()]
+    );
+}
+
 #[test]
 fn query_sg_swap_pair_contract() {
     let mut deps = mock_dependencies(&[]);
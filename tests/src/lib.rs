@@ -1,3 +1,4 @@
 mod suite;
+mod token_factory;
 
 pub use suite::{Suite, SuiteBuilder};
@@ -1,10 +1,10 @@
 use anyhow::Result as AnyResult;
 
 use cosmwasm_schema::serde::Serialize;
-use cosmwasm_std::{coin, to_binary, Addr, Coin, Decimal, Uint128};
+use cosmwasm_std::{coin, to_binary, Addr, Coin, Decimal, Empty, Uint128};
 use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20QueryMsg, MinterResponse};
 use cw20_base::msg::InstantiateMsg as Cw20BaseInstantiateMsg;
-use cw_multi_test::{App, AppResponse, BankSudo, ContractWrapper, Executor, SudoMsg};
+use cw_multi_test::{AppResponse, BankSudo, BasicAppBuilder, ContractWrapper, Executor, SudoMsg};
 
 use sg_swap::asset::{Asset, AssetInfo};
 use sg_swap::factory::{
@@ -16,11 +16,15 @@ use sg_swap::fee_config::FeeConfig;
 use sg_swap::multi_hop::{
     ExecuteMsg, InstantiateMsg, QueryMsg, SimulateSwapOperationsResponse, SwapOperation,
 };
-use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, PairInfo};
+use sg_swap::pair::{ExecuteMsg as PairExecuteMsg, LpTokenType, PairInfo};
 use sg_swap::stake::UnbondingPeriod;
 use sg_swap_stake::msg::ExecuteMsg as StakeExecuteMsg;
 
-fn store_multi_hop(app: &mut App) -> u64 {
+use crate::token_factory::{
+    pair_execute, pair_instantiate, pair_reply, TokenFactory, TokenFactoryApp, TokenFactoryMsg,
+};
+
+fn store_multi_hop(app: &mut TokenFactoryApp) -> u64 {
     let contract = Box::new(ContractWrapper::new_with_empty(
         sg_swap_multi_hop::contract::execute,
         sg_swap_multi_hop::contract::instantiate,
@@ -30,7 +34,7 @@ fn store_multi_hop(app: &mut App) -> u64 {
     app.store_code(contract)
 }
 
-fn store_factory(app: &mut App) -> u64 {
+fn store_factory(app: &mut TokenFactoryApp) -> u64 {
     let contract = Box::new(
         ContractWrapper::new_with_empty(
             sg_swap_factory::contract::execute,
@@ -43,21 +47,22 @@ fn store_factory(app: &mut App) -> u64 {
     app.store_code(contract)
 }
 
-fn store_pair(app: &mut App) -> u64 {
+fn store_pair(app: &mut TokenFactoryApp) -> u64 {
+    // the pair forwards its token factory messages to the mocked token factory module
     let contract = Box::new(
-        ContractWrapper::new_with_empty(
-            sg_swap_pair::contract::execute,
-            sg_swap_pair::contract::instantiate,
+        ContractWrapper::new(
+            pair_execute,
+            pair_instantiate,
             sg_swap_pair::contract::query,
         )
-        .with_reply_empty(sg_swap_pair::contract::reply),
+        .with_reply(pair_reply),
     );
 
     app.store_code(contract)
 }
 
-fn store_staking(app: &mut App) -> u64 {
-    let contract = Box::new(ContractWrapper::new(
+fn store_staking(app: &mut TokenFactoryApp) -> u64 {
+    let contract = Box::new(ContractWrapper::new_with_empty(
         sg_swap_stake::contract::execute,
         sg_swap_stake::contract::instantiate,
         sg_swap_stake::contract::query,
@@ -66,8 +71,8 @@ fn store_staking(app: &mut App) -> u64 {
     app.store_code(contract)
 }
 
-fn store_cw20(app: &mut App) -> u64 {
-    let contract = Box::new(ContractWrapper::new(
+fn store_cw20(app: &mut TokenFactoryApp) -> u64 {
+    let contract = Box::new(ContractWrapper::new_with_empty(
         cw20_base::contract::execute,
         cw20_base::contract::instantiate,
         cw20_base::contract::query,
@@ -128,7 +133,9 @@ impl SuiteBuilder {
 
     #[track_caller]
     pub fn build(self) -> Suite {
-        let mut app = App::default();
+        let mut app = BasicAppBuilder::<TokenFactoryMsg, Empty>::new_custom()
+            .with_custom(TokenFactory)
+            .build(|_, _, _| {});
         let owner = Addr::unchecked("owner");
 
         let cw20_code_id = store_cw20(&mut app);
@@ -149,6 +156,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: 0,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                         PairConfig {
                             code_id: pair_code_id,
@@ -158,6 +167,8 @@ impl SuiteBuilder {
                                 protocol_fee_bps: 0,
                             },
                             is_disabled: false,
+                            default_max_spread: None,
+                            init_params: None,
                         },
                     ],
                     token_code_id: cw20_code_id,
@@ -169,6 +180,7 @@ impl SuiteBuilder {
                         ..self.stake_config
                     },
                     trading_starts: self.trading_starts,
+                    fee_recipients: None,
                 },
                 &[],
                 "Stargaze Swap Factory",
@@ -183,6 +195,7 @@ impl SuiteBuilder {
                 owner.clone(),
                 &InstantiateMsg {
                     sg_swap_factory: factory.to_string(),
+                    max_hops: None,
                 },
                 &[],
                 "Stargaze Swap Multi Hop",
@@ -211,7 +224,7 @@ impl SuiteBuilder {
 
 pub struct Suite {
     pub owner: String,
-    pub app: App,
+    pub app: TokenFactoryApp,
     pub factory: Addr,
     multi_hop: Addr,
     cw20_code_id: u64,
@@ -240,6 +253,11 @@ impl Suite {
                 init_params: None,
                 staking_config: staking_config.unwrap_or_default(),
                 total_fee_bps,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
             },
             &[],
         )?;
@@ -254,6 +272,93 @@ impl Suite {
         Ok(res.contract_addr)
     }
 
+    /// Creates a pair that only starts trading `trading_starts_delay` seconds from now
+    pub fn create_pair_with_trading_starts_delay(
+        &mut self,
+        sender: &str,
+        pair_type: PairType,
+        tokens: [AssetInfo; 2],
+        trading_starts_delay: u64,
+    ) -> AnyResult<Addr> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::CreatePair {
+                pair_type,
+                asset_infos: tokens.to_vec(),
+                init_params: None,
+                staking_config: PartialStakeConfig::default(),
+                total_fee_bps: None,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: Some(trading_starts_delay),
+                lp_token_type: None,
+            },
+            &[],
+        )?;
+
+        let res: PairInfo = self.app.wrap().query_wasm_smart(
+            self.factory.clone(),
+            &FactoryQueryMsg::Pair {
+                asset_infos: tokens.to_vec(),
+            },
+        )?;
+        Ok(res.contract_addr)
+    }
+
+    /// Pause or unpause a pair through the factory contract
+    /// Creates a pair that issues native LP shares instead of a cw20 LP token
+    pub fn create_pair_with_native_lp(
+        &mut self,
+        sender: &str,
+        pair_type: PairType,
+        tokens: [AssetInfo; 2],
+    ) -> AnyResult<Addr> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::CreatePair {
+                pair_type,
+                asset_infos: tokens.to_vec(),
+                init_params: None,
+                staking_config: PartialStakeConfig::default(),
+                total_fee_bps: None,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: Some(LpTokenType::Native),
+            },
+            &[],
+        )?;
+
+        let res: PairInfo = self.app.wrap().query_wasm_smart(
+            self.factory.clone(),
+            &FactoryQueryMsg::Pair {
+                asset_infos: tokens.to_vec(),
+            },
+        )?;
+        Ok(res.contract_addr)
+    }
+
+    pub fn pause_pair(
+        &mut self,
+        sender: &str,
+        asset_infos: Vec<AssetInfo>,
+        paused: bool,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::PausePair {
+                asset_infos,
+                paused,
+            },
+            &[],
+        )
+    }
+
     pub fn create_pair_and_distributions(
         &mut self,
         sender: &str,
@@ -272,6 +377,11 @@ impl Suite {
                 staking_config: staking_config.unwrap_or_default(),
                 distribution_flows,
                 total_fee_bps: None,
+                lp_token_name: None,
+                lp_token_symbol: None,
+                trading_starts: None,
+                trading_starts_delay: None,
+                lp_token_type: None,
             },
             &[],
         )
@@ -291,11 +401,30 @@ impl Suite {
                 assets: assets.to_vec(),
                 slippage_tolerance: None,
                 receiver: None,
+                min_lp_amount: None,
             },
             send_funds,
         )
     }
 
+    /// Withdraws liquidity from a pair with native LP shares by sending them along
+    pub fn withdraw_liquidity_native(
+        &mut self,
+        owner: &str,
+        pair: &Addr,
+        lp_shares: Coin,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(owner),
+            pair.clone(),
+            &PairExecuteMsg::WithdrawLiquidity {
+                assets: vec![],
+                min_assets: None,
+            },
+            &[lp_shares],
+        )
+    }
+
     fn increase_allowance(
         &mut self,
         owner: &str,
@@ -357,6 +486,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
         match second_asset.0.clone() {
             AssetInfo::Token(addr) => {
@@ -380,6 +512,9 @@ impl Suite {
                     }))
                     .unwrap();
             }
+            info @ AssetInfo::Cw1155 { .. } => {
+                panic!("cannot provide {info} as liquidity, pairs do not support cw1155 tokens")
+            }
         };
 
         self.provide_liquidity(
@@ -422,6 +557,24 @@ impl Suite {
         )
     }
 
+    pub fn bond_native(
+        &mut self,
+        staking_contract: Addr,
+        sender: &str,
+        unbonding_period: UnbondingPeriod,
+        funds: &[Coin],
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            staking_contract,
+            &StakeExecuteMsg::BondNative {
+                unbonding_period,
+                delegate_as: None,
+            },
+            funds,
+        )
+    }
+
     pub fn distribute_funds(
         &mut self,
         staking_contract: Addr,
@@ -436,6 +589,59 @@ impl Suite {
         )
     }
 
+    pub fn compound_rewards(
+        &mut self,
+        staking_contract: Addr,
+        sender: &str,
+        unbonding_period: UnbondingPeriod,
+        pair: &Addr,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            staking_contract,
+            &StakeExecuteMsg::CompoundRewards {
+                unbonding_period,
+                pair: pair.to_string(),
+                max_spread: None,
+            },
+            &[],
+        )
+    }
+
+    pub fn delegate_withdrawal(
+        &mut self,
+        staking_contract: Addr,
+        sender: &str,
+        delegated: &str,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            staking_contract,
+            &StakeExecuteMsg::DelegateWithdrawal {
+                delegated: delegated.to_string(),
+            },
+            &[],
+        )
+    }
+
+    /// Withdraw rewards from the staking contracts of multiple pairs through the factory contract
+    pub fn withdraw_all_rewards(
+        &mut self,
+        sender: &str,
+        pairs: Vec<Vec<AssetInfo>>,
+        receiver: Option<&str>,
+    ) -> AnyResult<AppResponse> {
+        self.app.execute_contract(
+            Addr::unchecked(sender),
+            self.factory.clone(),
+            &FactoryExecuteMsg::WithdrawAllRewards {
+                pairs,
+                receiver: receiver.map(str::to_string),
+            },
+            &[],
+        )
+    }
+
     pub fn instantiate_token(&mut self, owner: &str, token: &str) -> Addr {
         self.app
             .instantiate_contract(
@@ -524,6 +730,8 @@ impl Suite {
                 max_spread: None,
                 referral_address: referral_address.into(),
                 referral_commission: referral_commission.into(),
+                deadline: None,
+                receive_msg: None,
             },
             &[amount],
         )
@@ -561,6 +769,8 @@ impl Suite {
                     max_spread: None,
                     referral_address: referral_address.into(),
                     referral_commission: referral_commission.into(),
+                    deadline: None,
+                    receive_msg: None,
                 })
                 .unwrap(),
             },
@@ -0,0 +1,217 @@
+//! A mock of the token factory module, so pairs can issue native LP shares in the multitest app.
+//! The pair sends token factory messages as stargate messages, which the app cannot execute,
+//! so the pair's entry points forward them as [`TokenFactoryMsg`]s instead.
+
+use std::fmt::Debug;
+
+use anyhow::{bail, ensure, Context, Result as AnyResult};
+use cosmwasm_schema::cw_serde;
+use cosmwasm_schema::schemars::JsonSchema;
+use cosmwasm_schema::serde::de::DeserializeOwned;
+use cosmwasm_std::{
+    Addr, Api, BankMsg, Binary, BlockInfo, Coin, CosmosMsg, CustomQuery, DepsMut, Empty, Env,
+    MessageInfo, Querier, Reply, Response, Storage, SubMsg,
+};
+use cw_multi_test::{
+    App, AppResponse, BankKeeper, BankSudo, CosmosRouter, Module, SudoMsg, WasmKeeper,
+};
+
+use cosmwasm_std::testing::{MockApi, MockStorage};
+use sg_swap::pair::{ContractError, ExecuteMsg, InstantiateMsg};
+
+const MSG_CREATE_DENOM: &str = "/osmosis.tokenfactory.v1beta1.MsgCreateDenom";
+const MSG_MINT: &str = "/osmosis.tokenfactory.v1beta1.MsgMint";
+const MSG_BURN: &str = "/osmosis.tokenfactory.v1beta1.MsgBurn";
+
+/// The app used by the [`Suite`](crate::Suite), which supports native LP shares.
+pub type TokenFactoryApp =
+    App<BankKeeper, MockApi, MockStorage, TokenFactory, WasmKeeper<TokenFactoryMsg, Empty>>;
+
+/// A token factory message sent by a pair.
+#[cw_serde]
+pub struct TokenFactoryMsg {
+    pub type_url: String,
+    pub value: Binary,
+}
+
+/// Creates, mints and burns token factory denoms with the bank module of the app.
+pub struct TokenFactory;
+
+impl Module for TokenFactory {
+    type ExecT = TokenFactoryMsg;
+    type QueryT = Empty;
+    type SudoT = Empty;
+
+    fn execute<ExecC, QueryC>(
+        &self,
+        api: &dyn Api,
+        storage: &mut dyn Storage,
+        router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        block: &BlockInfo,
+        sender: Addr,
+        msg: TokenFactoryMsg,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        let fields = decode_fields(&msg.value)?;
+        ensure!(
+            field(&fields, 1)? == sender.as_bytes(),
+            "token factory messages have to be sent by their sender"
+        );
+        match msg.type_url.as_str() {
+            MSG_CREATE_DENOM => Ok(AppResponse::default()),
+            MSG_MINT => {
+                let amount = admin_coin(&sender, field(&fields, 2)?)?;
+                router.sudo(
+                    api,
+                    storage,
+                    block,
+                    SudoMsg::Bank(BankSudo::Mint {
+                        to_address: sender.to_string(),
+                        amount: vec![amount],
+                    }),
+                )
+            }
+            MSG_BURN => {
+                let amount = admin_coin(&sender, field(&fields, 2)?)?;
+                router.execute(
+                    api,
+                    storage,
+                    block,
+                    sender,
+                    BankMsg::Burn {
+                        amount: vec![amount],
+                    }
+                    .into(),
+                )
+            }
+            type_url => bail!("unsupported token factory message {type_url}"),
+        }
+    }
+
+    fn sudo<ExecC, QueryC>(
+        &self,
+        _api: &dyn Api,
+        _storage: &mut dyn Storage,
+        _router: &dyn CosmosRouter<ExecC = ExecC, QueryC = QueryC>,
+        _block: &BlockInfo,
+        msg: Empty,
+    ) -> AnyResult<AppResponse>
+    where
+        ExecC: Debug + Clone + PartialEq + JsonSchema + DeserializeOwned + 'static,
+        QueryC: CustomQuery + DeserializeOwned + 'static,
+    {
+        bail!("unexpected token factory sudo {msg:?}")
+    }
+
+    fn query(
+        &self,
+        _api: &dyn Api,
+        _storage: &dyn Storage,
+        _querier: &dyn Querier,
+        _block: &BlockInfo,
+        request: Empty,
+    ) -> AnyResult<Binary> {
+        bail!("unexpected token factory query {request:?}")
+    }
+}
+
+/// Decodes an encoded `cosmos.base.v1beta1.Coin` of a denom created by `admin`.
+fn admin_coin(admin: &Addr, bytes: &[u8]) -> AnyResult<Coin> {
+    let fields = decode_fields(bytes)?;
+    let denom = String::from_utf8(field(&fields, 1)?.to_vec())?;
+    ensure!(
+        denom.starts_with(&format!("factory/{admin}/")),
+        "{admin} is not the admin of {denom}"
+    );
+    let amount: u128 = std::str::from_utf8(field(&fields, 2)?)?.parse()?;
+    Ok(Coin::new(amount, denom))
+}
+
+/// Splits a protobuf message into its fields.
+/// The token factory messages only contain length-delimited fields (strings and embedded messages).
+fn decode_fields(mut bytes: &[u8]) -> AnyResult<Vec<(u8, &[u8])>> {
+    let mut fields = vec![];
+    while !bytes.is_empty() {
+        let key = bytes[0];
+        ensure!(key & 7 == 2, "unsupported wire type in key {key}");
+        let mut len = 0usize;
+        let mut pos = 1;
+        loop {
+            let byte = *bytes.get(pos).context("truncated field length")?;
+            len |= usize::from(byte & 0x7f) << (7 * (pos - 1));
+            pos += 1;
+            if byte < 0x80 {
+                break;
+            }
+        }
+        let value = bytes.get(pos..pos + len).context("truncated field")?;
+        fields.push((key >> 3, value));
+        bytes = &bytes[pos + len..];
+    }
+    Ok(fields)
+}
+
+fn field<'a>(fields: &[(u8, &'a [u8])], number: u8) -> AnyResult<&'a [u8]> {
+    fields
+        .iter()
+        .find(|(n, _)| *n == number)
+        .map(|(_, value)| *value)
+        .with_context(|| format!("missing field {number}"))
+}
+
+/// Forwards the stargate messages of a pair response as [`TokenFactoryMsg`]s.
+fn forward_token_factory_msgs(
+    res: Result<Response, ContractError>,
+) -> Result<Response<TokenFactoryMsg>, ContractError> {
+    let res = res?;
+    let messages = res.messages.into_iter().map(|sub_msg| SubMsg {
+        id: sub_msg.id,
+        msg: match sub_msg.msg {
+            CosmosMsg::Bank(msg) => CosmosMsg::Bank(msg),
+            CosmosMsg::Wasm(msg) => CosmosMsg::Wasm(msg),
+            CosmosMsg::Stargate { type_url, value } => {
+                CosmosMsg::Custom(TokenFactoryMsg { type_url, value })
+            }
+            msg => panic!("pairs do not send {msg:?}"),
+        },
+        gas_limit: sub_msg.gas_limit,
+        reply_on: sub_msg.reply_on,
+    });
+    let mut response = Response::new()
+        .add_submessages(messages)
+        .add_attributes(res.attributes)
+        .add_events(res.events);
+    if let Some(data) = res.data {
+        response = response.set_data(data);
+    }
+    Ok(response)
+}
+
+pub fn pair_instantiate(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response<TokenFactoryMsg>, ContractError> {
+    forward_token_factory_msgs(sg_swap_pair::contract::instantiate(deps, env, info, msg))
+}
+
+pub fn pair_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<TokenFactoryMsg>, ContractError> {
+    forward_token_factory_msgs(sg_swap_pair::contract::execute(deps, env, info, msg))
+}
+
+pub fn pair_reply(
+    deps: DepsMut,
+    env: Env,
+    msg: Reply,
+) -> Result<Response<TokenFactoryMsg>, ContractError> {
+    forward_token_factory_msgs(sg_swap_pair::contract::reply(deps, env, msg))
+}
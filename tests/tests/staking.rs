@@ -1,12 +1,16 @@
 use tests::SuiteBuilder;
 
-use cosmwasm_std::{coin, from_slice, Addr, Decimal, Uint128};
+use cosmwasm_std::testing::MockApi;
+use cosmwasm_std::{coin, from_slice, Addr, Decimal, Event, Uint128};
 
 use sg_swap::{
     asset::{AssetInfo, AssetInfoExt},
     factory::PartialStakeConfig,
+    pair::{PoolResponse, QueryMsg as PairQueryMsg},
+};
+use sg_swap_stake::msg::{
+    QueryMsg as StakeQueryMsg, ReceiveDelegationMsg, StakedResponse, WithdrawableRewardsResponse,
 };
-use sg_swap_stake::msg::{QueryMsg as StakeQueryMsg, ReceiveDelegationMsg, StakedResponse};
 use sg_swap_stake::state::Config as StargazeStakeConfig;
 
 mod staking {
@@ -109,6 +113,156 @@ mod staking {
         assert_eq!(1000, resp.stake.u128());
     }
 
+    #[test]
+    fn compound_rewards() {
+        let ujuno = "ujuno";
+        let uluna = "uluna";
+        let test = "test";
+
+        let liquidity_provider = "liquidity_provider";
+        let distributor = "distributor";
+
+        let ujuno_info = AssetInfo::Native(ujuno.to_string());
+        let uluna_info = AssetInfo::Native(uluna.to_string());
+        let test_info = AssetInfo::Native(test.to_string());
+
+        let mut suite = SuiteBuilder::new()
+            .with_funds(
+                liquidity_provider,
+                &[coin(10_000, ujuno), coin(10_000, uluna)],
+            )
+            .with_funds(distributor, &[coin(1_000, ujuno), coin(1_000, test)])
+            .with_stake_config(DefaultStakeConfig {
+                staking_code_id: 0,
+                tokens_per_power: Uint128::new(1),
+                min_bond: Uint128::new(1),
+                unbonding_periods: vec![1, 2],
+                max_distributions: 2,
+            })
+            .build();
+
+        let pair = suite
+            .create_pair(
+                "owner",
+                sg_swap::factory::PairType::Xyk {},
+                [ujuno_info.clone(), uluna_info.clone()],
+                None,
+                None,
+            )
+            .unwrap();
+        suite
+            .provide_liquidity(
+                liquidity_provider,
+                &pair,
+                [
+                    ujuno_info.with_balance(10_000u128),
+                    uluna_info.with_balance(10_000u128),
+                ],
+                &[coin(10_000, ujuno), coin(10_000, uluna)],
+            )
+            .unwrap();
+        let pair_info = suite
+            .query_pair(vec![ujuno_info.clone(), uluna_info.clone()])
+            .unwrap();
+
+        // one reward asset that is part of the pair and one that is not
+        for asset in [ujuno_info.clone(), test_info] {
+            suite
+                .create_distribution_flow(
+                    "owner",
+                    vec![ujuno_info.clone(), uluna_info.clone()],
+                    asset,
+                    vec![(1, Decimal::one()), (2, Decimal::one())],
+                )
+                .unwrap();
+        }
+
+        suite
+            .send_cw20(
+                liquidity_provider,
+                &pair_info.liquidity_token,
+                1_000,
+                pair_info.staking_addr.as_str(),
+                ReceiveDelegationMsg::Delegate {
+                    unbonding_period: 1,
+                    delegate_as: None,
+                },
+            )
+            .unwrap();
+
+        suite
+            .distribute_funds(
+                pair_info.staking_addr.clone(),
+                distributor,
+                &[coin(1_000, ujuno), coin(1_000, test)],
+            )
+            .unwrap();
+        suite.advance_time(100);
+
+        // compounding into an unknown unbonding period fails
+        suite
+            .compound_rewards(pair_info.staking_addr.clone(), liquidity_provider, 3, &pair)
+            .unwrap_err();
+
+        let res = suite
+            .compound_rewards(pair_info.staking_addr.clone(), liquidity_provider, 2, &pair)
+            .unwrap();
+        assert!(res.has_event(
+            &Event::new("wasm")
+                .add_attribute("action", "compound_rewards")
+                .add_attribute("reward_ujuno", "1000")
+                .add_attribute("skipped_test", "1000")
+        ));
+
+        // the ujuno rewards were provided as liquidity: half of them is swapped for
+        // 10_000 - 10_000 * 10_000 / 10_500 = 476 uluna, which together mint 476 LP tokens
+        let staked = |suite: &tests::Suite, unbonding_period| {
+            suite
+                .app
+                .wrap()
+                .query_wasm_smart::<StakedResponse>(
+                    &pair_info.staking_addr,
+                    &StakeQueryMsg::Staked {
+                        address: liquidity_provider.to_string(),
+                        unbonding_period,
+                    },
+                )
+                .unwrap()
+                .stake
+                .u128()
+        };
+        assert_eq!(staked(&suite, 1), 1_000);
+        assert_eq!(staked(&suite, 2), 476);
+
+        // the rewards without a route are still withdrawable
+        let rewards: WithdrawableRewardsResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &pair_info.staking_addr,
+                &StakeQueryMsg::WithdrawableRewards {
+                    owner: liquidity_provider.to_string(),
+                },
+            )
+            .unwrap();
+        let api = MockApi::default();
+        assert_eq!(
+            rewards.rewards,
+            vec![
+                AssetInfo::Native(test.to_string())
+                    .validate(&api)
+                    .unwrap()
+                    .with_balance(1_000u128),
+                ujuno_info.validate(&api).unwrap().with_balance(0u128),
+            ]
+        );
+
+        // nothing left to compound
+        suite
+            .compound_rewards(pair_info.staking_addr, liquidity_provider, 2, &pair)
+            .unwrap_err();
+    }
+
     #[test]
     fn stake_has_correct_instantiator() {
         let ujuno = "ujuno";
@@ -227,4 +381,225 @@ mod staking {
             .distribute_funds(pair_info.staking_addr, user, &[coin(100, no_dist)])
             .unwrap_err();
     }
+
+    #[test]
+    fn withdraw_rewards_from_multiple_pairs() {
+        let ujuno = "ujuno";
+        let uluna = "uluna";
+        let uatom = "uatom";
+        let reward = "reward";
+
+        let liquidity_provider = "liquidity_provider";
+        let distributor = "distributor";
+
+        let ujuno_info = AssetInfo::Native(ujuno.to_string());
+        let uluna_info = AssetInfo::Native(uluna.to_string());
+        let uatom_info = AssetInfo::Native(uatom.to_string());
+        let reward_info = AssetInfo::Native(reward.to_string());
+
+        let mut suite = SuiteBuilder::new()
+            .with_funds(
+                liquidity_provider,
+                &[
+                    coin(20_000, ujuno),
+                    coin(10_000, uluna),
+                    coin(10_000, uatom),
+                ],
+            )
+            .with_funds(distributor, &[coin(3_000, reward)])
+            .with_stake_config(DefaultStakeConfig {
+                staking_code_id: 0,
+                tokens_per_power: Uint128::new(1),
+                min_bond: Uint128::new(1),
+                unbonding_periods: vec![1],
+                max_distributions: 1,
+            })
+            .build();
+
+        // two pools, each distributing rewards to its stakers
+        let pairs = vec![
+            vec![ujuno_info.clone(), uluna_info],
+            vec![ujuno_info.clone(), uatom_info],
+        ];
+        let mut staking_addrs = vec![];
+        for (asset_infos, amount) in pairs.iter().zip([1_000, 2_000]) {
+            let pair = suite
+                .create_pair(
+                    "owner",
+                    sg_swap::factory::PairType::Xyk {},
+                    [asset_infos[0].clone(), asset_infos[1].clone()],
+                    None,
+                    None,
+                )
+                .unwrap();
+            let other = match &asset_infos[1] {
+                AssetInfo::Native(denom) => denom.clone(),
+                _ => unreachable!(),
+            };
+            suite
+                .provide_liquidity(
+                    liquidity_provider,
+                    &pair,
+                    [
+                        asset_infos[0].with_balance(10_000u128),
+                        asset_infos[1].with_balance(10_000u128),
+                    ],
+                    &[coin(10_000, ujuno), coin(10_000, other)],
+                )
+                .unwrap();
+            suite
+                .create_distribution_flow(
+                    "owner",
+                    asset_infos.clone(),
+                    reward_info.clone(),
+                    vec![(1, Decimal::one())],
+                )
+                .unwrap();
+
+            let pair_info = suite.query_pair(asset_infos.clone()).unwrap();
+            suite
+                .send_cw20(
+                    liquidity_provider,
+                    &pair_info.liquidity_token,
+                    1_000,
+                    pair_info.staking_addr.as_str(),
+                    ReceiveDelegationMsg::Delegate {
+                        unbonding_period: 1,
+                        delegate_as: None,
+                    },
+                )
+                .unwrap();
+            suite
+                .distribute_funds(
+                    pair_info.staking_addr.clone(),
+                    distributor,
+                    &[coin(amount, reward)],
+                )
+                .unwrap();
+            staking_addrs.push(pair_info.staking_addr);
+        }
+        suite.advance_time(100);
+
+        // the factory is not allowed to withdraw yet
+        suite
+            .withdraw_all_rewards(liquidity_provider, pairs.clone(), None)
+            .unwrap_err();
+
+        let factory = suite.factory.to_string();
+        for staking_addr in staking_addrs {
+            suite
+                .delegate_withdrawal(staking_addr, liquidity_provider, &factory)
+                .unwrap();
+        }
+
+        // one factory call withdraws the rewards of both pools
+        suite
+            .withdraw_all_rewards(liquidity_provider, pairs, None)
+            .unwrap();
+        assert_eq!(
+            suite.query_balance(liquidity_provider, reward).unwrap(),
+            3_000
+        );
+    }
+
+    #[test]
+    fn native_lp_shares() {
+        let ujuno = "ujuno";
+        let uluna = "uluna";
+
+        let liquidity_provider = "liquidity_provider";
+
+        let ujuno_info = AssetInfo::Native(ujuno.to_string());
+        let uluna_info = AssetInfo::Native(uluna.to_string());
+
+        let mut suite = SuiteBuilder::new()
+            .with_funds(
+                liquidity_provider,
+                &[coin(10_000, ujuno), coin(10_000, uluna)],
+            )
+            .with_stake_config(DefaultStakeConfig {
+                staking_code_id: 0,
+                tokens_per_power: Uint128::new(1),
+                min_bond: Uint128::new(1),
+                unbonding_periods: vec![1, 2],
+                max_distributions: 1,
+            })
+            .build();
+
+        let pair = suite
+            .create_pair_with_native_lp(
+                "owner",
+                sg_swap::factory::PairType::Xyk {},
+                [ujuno_info.clone(), uluna_info.clone()],
+            )
+            .unwrap();
+        let pair_info = suite
+            .query_pair(vec![ujuno_info.clone(), uluna_info.clone()])
+            .unwrap();
+        let lp_denom = pair_info.liquidity_token.to_string();
+        assert_eq!(lp_denom, format!("factory/{pair}/ulp"));
+
+        suite
+            .provide_liquidity(
+                liquidity_provider,
+                &pair,
+                [
+                    ujuno_info.with_balance(10_000u128),
+                    uluna_info.with_balance(10_000u128),
+                ],
+                &[coin(10_000, ujuno), coin(10_000, uluna)],
+            )
+            .unwrap();
+        // the pair keeps the minimum liquidity
+        assert_eq!(
+            suite.query_balance(liquidity_provider, &lp_denom).unwrap(),
+            9_000
+        );
+
+        // the staking contract of the pair stakes the native LP shares
+        suite
+            .bond_native(
+                pair_info.staking_addr.clone(),
+                liquidity_provider,
+                1,
+                &[coin(5_000, &lp_denom)],
+            )
+            .unwrap();
+        let resp: StakedResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(
+                pair_info.staking_addr,
+                &StakeQueryMsg::Staked {
+                    address: liquidity_provider.to_string(),
+                    unbonding_period: 1,
+                },
+            )
+            .unwrap();
+        assert_eq!(resp.stake.u128(), 5_000);
+        assert_eq!(resp.cw20_contract, lp_denom);
+
+        // the unstaked shares can still be withdrawn from the pair, which burns them
+        suite
+            .withdraw_liquidity_native(liquidity_provider, &pair, coin(4_000, &lp_denom))
+            .unwrap();
+        assert_eq!(
+            suite.query_balance(liquidity_provider, &lp_denom).unwrap(),
+            0
+        );
+        assert_eq!(
+            suite.query_balance(liquidity_provider, ujuno).unwrap(),
+            4_000
+        );
+        assert_eq!(
+            suite.query_balance(liquidity_provider, uluna).unwrap(),
+            4_000
+        );
+        let pool: PoolResponse = suite
+            .app
+            .wrap()
+            .query_wasm_smart(&pair, &PairQueryMsg::Pool {})
+            .unwrap();
+        assert_eq!(pool.total_share.u128(), 6_000);
+    }
 }
@@ -56,6 +56,144 @@ fn trading_frozen() {
         .unwrap();
 }
 
+#[test]
+fn trading_frozen_by_pair_delay() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+    let whale = "whale";
+
+    let ujuno_info = AssetInfo::Native(ujuno.to_string());
+    let uluna_info = AssetInfo::Native(uluna.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno)])
+        .with_funds(whale, &[coin(1_000_000, ujuno), coin(1_000_000, uluna)])
+        .build();
+
+    let owner = suite.owner.clone();
+    let pair = suite
+        .create_pair_with_trading_starts_delay(
+            &owner,
+            sg_swap::factory::PairType::Xyk {},
+            [ujuno_info.clone(), uluna_info.clone()],
+            1000,
+        )
+        .unwrap();
+    suite
+        .provide_liquidity(
+            whale,
+            &pair,
+            [
+                ujuno_info.with_balance(1_000_000u128),
+                uluna_info.with_balance(1_000_000u128),
+            ],
+            &[coin(1_000_000, ujuno), coin(1_000_000, uluna)],
+        )
+        .unwrap();
+
+    // trading is still frozen just before the delay elapses
+    suite.advance_time(999);
+    let err = suite
+        .swap_operations(
+            user,
+            coin(1000, ujuno),
+            vec![SwapOperation::StargazeSwap {
+                ask_asset_info: uluna_info.clone(),
+                offer_asset_info: ujuno_info.clone(),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Trading has not started yet");
+
+    suite.advance_time(1);
+    suite
+        .swap_operations(
+            user,
+            coin(1000, ujuno),
+            vec![SwapOperation::StargazeSwap {
+                ask_asset_info: uluna_info,
+                offer_asset_info: ujuno_info,
+            }],
+        )
+        .unwrap();
+}
+
+#[test]
+fn paused_pair_rejects_swaps() {
+    let ujuno = "ujuno";
+    let uluna = "uluna";
+    let user = "user";
+
+    let ujuno_info = AssetInfo::Native(ujuno.to_string());
+    let uluna_info = AssetInfo::Native(uluna.to_string());
+
+    let mut suite = SuiteBuilder::new()
+        .with_funds(user, &[coin(100_000, ujuno), coin(1_000, uluna)])
+        .build();
+
+    let pair = suite
+        .create_pair_and_provide_liquidity(
+            sg_swap::factory::PairType::Xyk {},
+            (ujuno_info.clone(), 1_000_000),
+            (uluna_info.clone(), 1_000_000),
+            vec![coin(1_000_000, ujuno), coin(1_000_000, uluna)],
+        )
+        .unwrap();
+
+    // only the factory owner can pause the pair
+    let err = suite
+        .pause_pair(user, vec![ujuno_info.clone(), uluna_info.clone()], true)
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "Unauthorized");
+
+    let owner = suite.owner.clone();
+    suite
+        .pause_pair(&owner, vec![ujuno_info.clone(), uluna_info.clone()], true)
+        .unwrap();
+
+    let err = suite
+        .swap_operations(
+            user,
+            coin(1000, ujuno),
+            vec![SwapOperation::StargazeSwap {
+                ask_asset_info: uluna_info.clone(),
+                offer_asset_info: ujuno_info.clone(),
+            }],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "The pair is paused");
+
+    let err = suite
+        .provide_liquidity(
+            user,
+            &pair,
+            [
+                ujuno_info.with_balance(1_000u128),
+                uluna_info.with_balance(1_000u128),
+            ],
+            &[coin(1_000, ujuno), coin(1_000, uluna)],
+        )
+        .unwrap_err();
+    assert_eq!(err.root_cause().to_string(), "The pair is paused");
+
+    // unpausing enables swaps again
+    suite
+        .pause_pair(&owner, vec![ujuno_info.clone(), uluna_info.clone()], false)
+        .unwrap();
+
+    suite
+        .swap_operations(
+            user,
+            coin(1000, ujuno),
+            vec![SwapOperation::StargazeSwap {
+                ask_asset_info: uluna_info,
+                offer_asset_info: ujuno_info,
+            }],
+        )
+        .unwrap();
+}
+
 #[test]
 fn custom_fee_works() {
     let ujuno = "ujuno";